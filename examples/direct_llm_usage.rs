@@ -58,6 +58,7 @@ async fn simple_call() -> helios_engine::Result<()> {
             .unwrap_or_else(|_| "your-api-key-here".to_string()),
         temperature: 0.7,
         max_tokens: 2048,
+        ..Default::default()
     };
 
     // Create a new LLM client.
@@ -94,6 +95,7 @@ async fn conversation_with_context() -> helios_engine::Result<()> {
             .unwrap_or_else(|_| "your-api-key-here".to_string()),
         temperature: 0.7,
         max_tokens: 2048,
+        ..Default::default()
     };
 
     // Create a new LLM client.
@@ -156,6 +158,7 @@ fn different_providers_info() {
     println!("       api_key: env::var(\"OPENAI_API_KEY\").unwrap(),");
     println!("       temperature: 0.7,");
     println!("       max_tokens: 2048,");
+    println!("       ..Default::default()");
     println!("   }}\n");
 
     println!("🟢 Local LM Studio:");
@@ -165,6 +168,7 @@ fn different_providers_info() {
     println!("       api_key: \"not-needed\".to_string(),");
     println!("       temperature: 0.7,");
     println!("       max_tokens: 2048,");
+    println!("       ..Default::default()");
     println!("   }}\n");
 
     println!("🦙 Ollama:");
@@ -174,6 +178,7 @@ fn different_providers_info() {
     println!("       api_key: \"not-needed\".to_string(),");
     println!("       temperature: 0.7,");
     println!("       max_tokens: 2048,");
+    println!("       ..Default::default()");
     println!("   }}\n");
 
     println!("🔷 Azure OpenAI:");
@@ -183,6 +188,7 @@ fn different_providers_info() {
     println!("       api_key: env::var(\"AZURE_OPENAI_KEY\").unwrap(),");
     println!("       temperature: 0.7,");
     println!("       max_tokens: 2048,");
+    println!("       ..Default::default()");
     println!("   }}\n");
 }
 
@@ -196,6 +202,7 @@ async fn interactive_chat() -> helios_engine::Result<()> {
             .unwrap_or_else(|_| "your-api-key-here".to_string()),
         temperature: 0.7,
         max_tokens: 2048,
+        ..Default::default()
     };
 
     // Create a new LLM client.
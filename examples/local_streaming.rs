@@ -34,6 +34,7 @@ async fn main() -> helios_engine::Result<()> {
         context_size: 2048,
         temperature: 0.7,
         max_tokens: 512,
+        ..LocalConfig::default()
     };
 
     println!("📥 Loading local model...");
@@ -43,7 +43,7 @@ async fn main() -> helios_engine::Result<()> {
     println!("Example 1: Documents with Metadata");
     println!("===================================\n");
 
-    let documents = vec![
+    let documents = [
         (
             "Rust is a systems programming language focused on safety and performance.",
             hashmap! {
@@ -4,7 +4,7 @@
 //! When enabled, the agent will reason about the task and create a plan
 //! before taking actions, leading to more thoughtful and systematic problem-solving.
 
-use helios_engine::{Agent, CalculatorTool, Config, EchoTool, FileReadTool};
+use helios_engine::{Agent, CalculatorTool, Config, ConsoleObserver, EchoTool, FileReadTool};
 
 #[tokio::main]
 async fn main() -> helios_engine::Result<()> {
@@ -28,6 +28,7 @@ async fn main() -> helios_engine::Result<()> {
             Box::new(FileReadTool),
         ])
         .react() // Enable ReAct mode - that's all it takes!
+        .observer(ConsoleObserver) // Print the reasoning as it happens
         .max_iterations(5)
         .build()
         .await?;
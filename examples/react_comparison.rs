@@ -3,7 +3,7 @@
 //! This example demonstrates the difference between standard agents and ReAct agents
 //! by running the same queries through both and comparing their approaches.
 
-use helios_engine::{Agent, CalculatorTool, Config, EchoTool};
+use helios_engine::{Agent, CalculatorTool, Config, ConsoleObserver, EchoTool};
 
 #[tokio::main]
 async fn main() -> helios_engine::Result<()> {
@@ -26,6 +26,7 @@ async fn main() -> helios_engine::Result<()> {
         .system_prompt("You are a helpful assistant with access to tools.")
         .tools(vec![Box::new(CalculatorTool), Box::new(EchoTool)])
         .react() // The only difference!
+        .observer(ConsoleObserver)
         .build()
         .await?;
 
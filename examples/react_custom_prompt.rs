@@ -3,7 +3,7 @@
 //! This example demonstrates how to use ReAct mode with custom reasoning prompts
 //! tailored to specific domains or tasks.
 
-use helios_engine::{Agent, CalculatorTool, Config, FileReadTool};
+use helios_engine::{Agent, CalculatorTool, Config, ConsoleObserver, FileReadTool};
 
 #[tokio::main]
 async fn main() -> helios_engine::Result<()> {
@@ -32,6 +32,7 @@ Provide your mathematical reasoning clearly."#;
         .system_prompt("You are a mathematical expert who thinks carefully about calculations.")
         .tool(Box::new(CalculatorTool))
         .react_with_prompt(math_prompt)
+        .observer(ConsoleObserver)
         .build()
         .await?;
 
@@ -61,6 +62,7 @@ Think through the data pipeline step by step."#;
         .system_prompt("You are a data analyst who carefully plans data processing tasks.")
         .tools(vec![Box::new(FileReadTool), Box::new(CalculatorTool)])
         .react_with_prompt(data_prompt)
+        .observer(ConsoleObserver)
         .build()
         .await?;
 
@@ -91,6 +93,7 @@ Plan the execution strategy carefully."#;
         .system_prompt("You are a strategic planner who breaks down complex tasks.")
         .tool(Box::new(CalculatorTool))
         .react_with_prompt(planning_prompt)
+        .observer(ConsoleObserver)
         .build()
         .await?;
 
@@ -121,6 +124,7 @@ Use rigorous scientific thinking."#;
         .system_prompt("You are a scientist who applies rigorous methodology.")
         .tool(Box::new(CalculatorTool))
         .react_with_prompt(scientific_prompt)
+        .observer(ConsoleObserver)
         .build()
         .await?;
 
@@ -3,7 +3,7 @@
 //! This example shows how ReAct mode helps with debugging and understanding
 //! how agents approach problems.
 
-use helios_engine::{Agent, CalculatorTool, Config, FileReadTool, JsonParserTool};
+use helios_engine::{Agent, CalculatorTool, Config, ConsoleObserver, FileReadTool, JsonParserTool};
 
 #[tokio::main]
 async fn main() -> helios_engine::Result<()> {
@@ -32,6 +32,7 @@ Be extremely detailed in your thinking."#;
             Box::new(FileReadTool),
         ])
         .react_with_prompt(debug_prompt)
+        .observer(ConsoleObserver)
         .max_iterations(15) // Allow more iterations for complex debugging
         .build()
         .await?;
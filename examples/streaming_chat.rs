@@ -23,6 +23,7 @@ async fn main() -> helios_engine::Result<()> {
             .unwrap_or_else(|_| "your-api-key-here".to_string()),
         temperature: 0.7,
         max_tokens: 2048,
+        ..Default::default()
     };
 
     // Create a new LLM client.
@@ -0,0 +1,208 @@
+//! # Abort Module
+//!
+//! This module provides the emergency stop used to halt an autonomous run. An
+//! `AbortHandle` is a shared flag: once it is tripped, guarded LLM calls and tool
+//! executions fail with `HeliosError::Aborted`, and any that are in flight are
+//! cancelled at their next await point.
+//!
+//! Handles can be linked to a parent, so aborting a forest stops every agent in it
+//! while aborting a single agent leaves the rest of the forest running.
+
+use crate::error::{HeliosError, Result};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// The state shared by clones of an `AbortHandle`.
+#[derive(Debug, Default)]
+struct AbortSignal {
+    aborted: AtomicBool,
+    notify: Notify,
+    parents: Mutex<Vec<AbortHandle>>,
+}
+
+/// A cloneable handle that stops further work when aborted.
+///
+/// Clones share the same flag, so a handle can be taken from an agent or forest
+/// before a run starts and tripped from another task, a signal handler or an HTTP
+/// endpoint.
+///
+/// ```rust
+/// use helios_engine::AbortHandle;
+///
+/// let handle = AbortHandle::new();
+/// let remote = handle.clone();
+/// remote.abort();
+/// assert!(handle.is_aborted());
+///
+/// handle.reset();
+/// assert!(!remote.is_aborted());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AbortHandle {
+    signal: Arc<AbortSignal>,
+}
+
+impl AbortHandle {
+    /// Creates a handle that has not been aborted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trips the handle, cancelling guarded operations that are in flight.
+    pub fn abort(&self) {
+        self.signal.aborted.store(true, Ordering::SeqCst);
+        self.signal.notify.notify_waiters();
+    }
+
+    /// Returns `true` if this handle, or any handle it is linked to, has been aborted.
+    pub fn is_aborted(&self) -> bool {
+        self.signal.aborted.load(Ordering::SeqCst)
+            || self.parents().iter().any(|parent| parent.is_aborted())
+    }
+
+    /// Clears this handle's own flag so work can resume.
+    ///
+    /// Handles it is linked to are left untouched.
+    pub fn reset(&self) {
+        self.signal.aborted.store(false, Ordering::SeqCst);
+    }
+
+    /// Links this handle to `parent`, so aborting the parent also aborts this handle.
+    pub fn link(&self, parent: &AbortHandle) {
+        if Arc::ptr_eq(&self.signal, &parent.signal) {
+            return;
+        }
+        self.signal
+            .parents
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(parent.clone());
+    }
+
+    /// Returns `HeliosError::Aborted` if the handle has been aborted.
+    pub fn check(&self) -> Result<()> {
+        if self.is_aborted() {
+            Err(aborted())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Completes once the handle, or any handle it is linked to, is aborted.
+    pub async fn cancelled(&self) {
+        let signals = self.signals();
+        let mut notified: Vec<_> = signals
+            .iter()
+            .map(|signal| Box::pin(signal.notify.notified()))
+            .collect();
+        // Register interest before checking the flags so an abort in between is not missed.
+        for future in notified.iter_mut() {
+            future.as_mut().enable();
+        }
+        if self.is_aborted() {
+            return;
+        }
+        futures::future::select_all(notified).await;
+    }
+
+    /// Runs `future` unless the handle is aborted first.
+    ///
+    /// Returns `HeliosError::Aborted` without polling the future if the handle is
+    /// already aborted, and drops the future if an abort arrives while it runs.
+    pub async fn run<F: Future>(&self, future: F) -> Result<F::Output> {
+        self.check()?;
+        tokio::select! {
+            biased;
+            _ = self.cancelled() => Err(aborted()),
+            output = future => Ok(output),
+        }
+    }
+
+    fn parents(&self) -> Vec<AbortHandle> {
+        self.signal
+            .parents
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Collects this handle's signal and those of every handle it is linked to.
+    fn signals(&self) -> Vec<Arc<AbortSignal>> {
+        let mut signals = vec![Arc::clone(&self.signal)];
+        for parent in self.parents() {
+            for signal in parent.signals() {
+                if !signals.iter().any(|s| Arc::ptr_eq(s, &signal)) {
+                    signals.push(signal);
+                }
+            }
+        }
+        signals
+    }
+}
+
+/// The error returned for work refused or cancelled by an abort.
+fn aborted() -> HeliosError {
+    HeliosError::Aborted("the run was stopped by an abort request".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Tests that clones share the abort flag and that reset clears it.
+    #[test]
+    fn test_abort_and_reset() {
+        let handle = AbortHandle::new();
+        let clone = handle.clone();
+        assert!(handle.check().is_ok());
+
+        clone.abort();
+        assert!(handle.is_aborted());
+        assert!(matches!(handle.check(), Err(HeliosError::Aborted(_))));
+
+        handle.reset();
+        assert!(!clone.is_aborted());
+    }
+
+    /// Tests that aborting a parent aborts linked children but not the reverse.
+    #[test]
+    fn test_linked_handles() {
+        let parent = AbortHandle::new();
+        let child = AbortHandle::new();
+        child.link(&parent);
+
+        child.abort();
+        assert!(!parent.is_aborted());
+        child.reset();
+
+        parent.abort();
+        assert!(child.is_aborted());
+        parent.reset();
+        assert!(!child.is_aborted());
+    }
+
+    /// Tests that an in-flight future is cancelled when its parent handle is aborted.
+    #[tokio::test]
+    async fn test_run_cancels_in_flight_future() {
+        let parent = AbortHandle::new();
+        let child = AbortHandle::new();
+        child.link(&parent);
+
+        let trigger = parent.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            trigger.abort();
+        });
+
+        let result = child.run(tokio::time::sleep(Duration::from_secs(30))).await;
+        assert!(matches!(result, Err(HeliosError::Aborted(_))));
+
+        // An aborted handle refuses new work until it is reset.
+        assert!(child.run(async { 1 }).await.is_err());
+        parent.reset();
+        assert_eq!(child.run(async { 1 }).await.unwrap(), 1);
+    }
+}
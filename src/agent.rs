@@ -7,12 +7,29 @@
 
 #![allow(dead_code)]
 #![allow(unused_variables)]
-use crate::chat::{ChatMessage, ChatSession};
+use crate::abort::AbortHandle;
+use crate::audit::ToolAudit;
+use crate::branching::{ConversationTree, MAIN_BRANCH};
+use crate::bundle::{AgentBundle, Guardrails, ModelProfile, ToolManifestEntry};
+use crate::chat::{ChatMessage, ChatSession, ToolCall};
+use crate::circuit_breaker::CircuitBreakerConfig;
 use crate::config::Config;
 use crate::error::{HeliosError, Result};
-use crate::llm::{LLMClient, LLMProviderType};
-use crate::tools::{ToolRegistry, ToolResult};
+use crate::events::{notify, AgentEvent, AgentObserver, ChannelObserver};
+use crate::footprint::{HistoryLimit, MemoryStats, ToolResultPruning};
+use crate::llm::{LLMClient, LLMProvider, LLMProviderType, StreamEvent};
+use crate::middleware::AgentMiddleware;
+use crate::moderation::{self, ModerationTarget, Moderator};
+use crate::prompt::SystemPromptBuilder;
+use crate::semantic_cache::{CachedAnswer, SemanticCache, SemanticCacheMode};
+use crate::tool_protocol::{ReactStream, ToolProtocol};
+use crate::tools::{ToolApprovalHandler, ToolPermission, ToolRegistry, ToolResult};
+use crate::toolset::{self, Toolset};
+use crate::truncation::{OutputLimit, OutputSummarizer};
+use crate::usage::{Budget, PriceTable, TokenUsage};
 use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Prefix for agent-specific keys in the chat session metadata.
 const AGENT_MEMORY_PREFIX: &str = "agent:";
@@ -27,12 +44,51 @@ pub struct Agent {
     tool_registry: ToolRegistry,
     /// The chat session, which stores the conversation history.
     chat_session: ChatSession,
+    /// How the conversation's branches were forked from one another.
+    branch_tree: ConversationTree,
+    /// The sessions of every branch except the current one, which is `chat_session`.
+    branch_sessions: HashMap<String, ChatSession>,
+    /// The id of the branch held in `chat_session`.
+    current_branch: String,
     /// The maximum number of iterations for tool execution in a single turn.
     max_iterations: usize,
     /// Whether the agent uses ReAct mode (Reasoning and Acting).
     react_mode: bool,
     /// Custom reasoning prompt for ReAct mode.
     react_prompt: Option<String>,
+    /// Prices used to turn token usage into a dollar cost.
+    price_table: PriceTable,
+    /// Per-model usage at the start of the current session.
+    session_baseline: HashMap<String, TokenUsage>,
+    /// Limits on the current session's usage, checked before every LLM call.
+    budget: Budget,
+    /// Stops LLM calls and tool executions when tripped.
+    abort: AbortHandle,
+    /// Hooks run around every LLM call and tool call, in registration order.
+    middleware: Vec<Box<dyn AgentMiddleware>>,
+    /// Check the user's input and the final reply, in registration order.
+    moderators: Vec<Box<dyn Moderator>>,
+    /// Pruned into at the start of every turn, if set.
+    history_limit: Option<HistoryLimit>,
+    /// Elides answered tool results at the start of every turn, if set.
+    tool_result_pruning: Option<ToolResultPruning>,
+    /// Told about reasoning, streamed text and tool calls as they happen.
+    observers: Vec<Arc<dyn AgentObserver>>,
+    /// How tools are offered to the model.
+    tool_protocol: ToolProtocol,
+    /// Composes the system prompt, which is recomposed when tools are registered.
+    prompt_builder: Option<SystemPromptBuilder>,
+    /// Answers questions similar to ones already answered, if set.
+    semantic_cache: Option<SemanticCache>,
+    /// The cached answer shown to the model during the current turn, in prime mode.
+    cache_hint: Option<CachedAnswer>,
+    /// Records every tool call the agent makes.
+    tool_audit: ToolAudit,
+}
+
+/// The error for a branch id the agent does not know.
+fn unknown_branch(id: &str) -> HeliosError {
+    HeliosError::AgentError(format!("Unknown branch '{}'", id))
 }
 
 impl Agent {
@@ -47,6 +103,11 @@ impl Agent {
     ///
     /// A `Result` containing the new `Agent` instance.
     async fn new(name: impl Into<String>, config: Config) -> Result<Self> {
+        #[cfg(feature = "local")]
+        if config.local.is_some() {
+            crate::model_pool::LocalModelPool::configure(config.model_pool.clone()).await;
+        }
+
         // Priority: Candle > Local > Remote (API)
 
         #[cfg(feature = "candle")]
@@ -79,15 +140,37 @@ impl Agent {
 
         let llm_client = LLMClient::new(provider_type).await?;
 
-        Ok(Self {
+        Ok(Self::with_client(name, llm_client))
+    }
+
+    /// Creates a new agent that talks to the LLM through `llm_client`.
+    fn with_client(name: impl Into<String>, llm_client: LLMClient) -> Self {
+        Self {
             name: name.into(),
             llm_client,
             tool_registry: ToolRegistry::new(),
             chat_session: ChatSession::new(),
+            branch_tree: ConversationTree::new(),
+            branch_sessions: HashMap::new(),
+            current_branch: MAIN_BRANCH.to_string(),
             max_iterations: 10,
             react_mode: false,
             react_prompt: None,
-        })
+            price_table: PriceTable::default(),
+            session_baseline: HashMap::new(),
+            budget: Budget::default(),
+            abort: AbortHandle::new(),
+            middleware: Vec::new(),
+            moderators: Vec::new(),
+            history_limit: None,
+            tool_result_pruning: None,
+            observers: Vec::new(),
+            tool_protocol: ToolProtocol::Native,
+            prompt_builder: None,
+            semantic_cache: None,
+            cache_hint: None,
+            tool_audit: ToolAudit::new(),
+        }
     }
 
     /// Returns a new `AgentBuilder` for constructing an agent.
@@ -129,13 +212,122 @@ impl Agent {
         &self.name
     }
 
+    /// Creates an agent from a bundle file written by [`Agent::export_bundle`].
+    ///
+    /// The bundle's model profile is applied on top of `config.toml` (or the
+    /// defaults), which supplies the API key. Use [`AgentBundle::into_builder`] to
+    /// pass a different configuration or tools that are not built in.
+    pub async fn from_bundle(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let config = Config::load_or_default("config.toml");
+        AgentBundle::load(path)?
+            .into_builder(config, Vec::new())?
+            .build()
+            .await
+    }
+
+    /// Describes this agent as a bundle that can be shared and loaded elsewhere.
+    ///
+    /// Tool configuration such as a shell policy or sandbox root cannot be read
+    /// back from the tools, so set `guardrails.shell_policy` and
+    /// `guardrails.workspace` on the returned bundle if the agent relies on them.
+    pub fn to_bundle(&self) -> AgentBundle {
+        let mut bundle = AgentBundle::new(self.name.clone());
+        bundle.system_prompt = self.chat_session.system_prompt.clone();
+
+        let mut tool_permissions = std::collections::BTreeMap::new();
+        let mut tools: Vec<ToolManifestEntry> = self
+            .tool_registry
+            .get_definitions()
+            .into_iter()
+            .map(|definition| {
+                let name = definition.function.name;
+                let permission = self.tool_registry.permission(&name);
+                let own = self.tool_registry.get(&name).map(|tool| tool.permission());
+                if let Some(permission) = permission.filter(|p| Some(*p) != own) {
+                    tool_permissions.insert(name.clone(), permission);
+                }
+                ToolManifestEntry {
+                    name,
+                    description: Some(definition.function.description),
+                    parameters: serde_json::to_value(definition.function.parameters).ok(),
+                }
+            })
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        bundle.tools = tools;
+
+        let mut guardrails = Guardrails {
+            max_iterations: Some(self.max_iterations),
+            react: self.react_mode,
+            react_prompt: self.react_prompt.clone(),
+            tool_permissions,
+            ..Default::default()
+        };
+        #[allow(irrefutable_let_patterns)]
+        if let LLMProviderType::Remote(config) = self.llm_client.provider_type() {
+            bundle.model = ModelProfile {
+                model_name: Some(config.model_name.clone()),
+                base_url: Some(config.base_url.clone()),
+                temperature: Some(config.temperature),
+                max_tokens: Some(config.max_tokens),
+            };
+            guardrails.stop_sequences = config.stop_sequences.clone();
+            guardrails.stop_patterns = config.stop_patterns.clone();
+        }
+        bundle.guardrails = guardrails;
+        bundle
+    }
+
+    /// Writes this agent's bundle to `path` as TOML, or JSON for `.json` files.
+    pub fn export_bundle(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        self.to_bundle().save(path)
+    }
+
     /// Sets the system prompt for the agent.
     ///
     /// # Arguments
     ///
     /// * `prompt` - The system prompt to set.
+    ///
+    /// This replaces a prompt composed with [`Agent::set_system_prompt_builder`].
     pub fn set_system_prompt(&mut self, prompt: impl Into<String>) {
-        self.chat_session = self.chat_session.clone().with_system_prompt(prompt);
+        self.prompt_builder = None;
+        self.chat_session.system_prompt = Some(prompt.into());
+    }
+
+    /// Composes the system prompt from the sections of `builder`.
+    ///
+    /// Unless `builder` has a [`SystemPromptBuilder::TOOL_GUIDANCE`] section, one
+    /// listing the usage notes of the agent's tools is placed after the persona.
+    /// It is updated as tools are registered.
+    pub fn set_system_prompt_builder(&mut self, builder: SystemPromptBuilder) {
+        self.prompt_builder = Some(builder);
+        self.compose_system_prompt();
+    }
+
+    /// Returns the builder the system prompt is composed with, if any.
+    pub fn system_prompt_builder(&self) -> Option<&SystemPromptBuilder> {
+        self.prompt_builder.as_ref()
+    }
+
+    /// Recomposes the system prompt from the prompt builder and the tools' usage notes.
+    fn compose_system_prompt(&mut self) {
+        let Some(mut builder) = self.prompt_builder.clone() else {
+            return;
+        };
+        let notes = self.tool_registry.usage_notes();
+        if builder.get(SystemPromptBuilder::TOOL_GUIDANCE).is_none() && !notes.is_empty() {
+            let mut guidance = String::from("Notes on using your tools:");
+            for (name, note) in notes {
+                guidance.push_str(&format!("\n- {}: {}", name, note.trim()));
+            }
+            builder = builder.section_after(
+                SystemPromptBuilder::PERSONA,
+                SystemPromptBuilder::TOOL_GUIDANCE,
+                guidance,
+            );
+        }
+        self.chat_session.system_prompt = Some(builder.build());
     }
 
     /// Registers a tool with the agent.
@@ -145,6 +337,7 @@ impl Agent {
     /// * `tool` - The tool to register.
     pub fn register_tool(&mut self, tool: Box<dyn crate::tools::Tool>) {
         self.tool_registry.register(tool);
+        self.compose_system_prompt();
     }
 
     /// Returns a reference to the agent's tool registry.
@@ -168,8 +361,381 @@ impl Agent {
     }
 
     /// Clears the agent's chat history.
+    ///
+    /// This also starts a new usage session, resetting [`Agent::session_usage`].
     pub fn clear_history(&mut self) {
         self.chat_session.clear();
+        self.session_baseline = self.llm_client.usage_tracker().by_model();
+    }
+
+    /// Forks the current branch at its latest message and returns the new branch's id.
+    ///
+    /// The agent stays on the current branch. Continue the fork with
+    /// [`Agent::chat_on_branch`], or move to it with [`Agent::switch_branch`].
+    ///
+    /// ```rust,no_run
+    /// # async fn example(mut agent: helios_engine::Agent) -> helios_engine::Result<()> {
+    /// agent.chat("Suggest a name for a bakery").await?;
+    /// let other = agent.fork()?;
+    /// agent.chat("Something more playful").await?;
+    /// agent.chat_on_branch(&other, "Something more classic").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fork(&mut self) -> Result<String> {
+        let current = self.current_branch.clone();
+        self.fork_at(&current, self.chat_session.messages.len())
+    }
+
+    /// Forks `branch` after its first `messages` messages and returns the new branch's id.
+    ///
+    /// See [`ChatSession::fork_at`] for cuts inside a tool exchange.
+    pub fn fork_at(&mut self, branch: &str, messages: usize) -> Result<String> {
+        let session = self
+            .branch(branch)
+            .ok_or_else(|| unknown_branch(branch))?
+            .fork_at(messages);
+        let id = self.branch_tree.fork(branch, session.messages.len())?;
+        self.branch_sessions.insert(id.clone(), session);
+        Ok(id)
+    }
+
+    /// Returns the id of the branch that `chat` and the other methods continue.
+    pub fn current_branch(&self) -> &str {
+        &self.current_branch
+    }
+
+    /// Returns how the conversation's branches were forked from one another.
+    pub fn branch_tree(&self) -> &ConversationTree {
+        &self.branch_tree
+    }
+
+    /// Returns the messages of a branch.
+    pub fn branch(&self, id: &str) -> Option<&ChatSession> {
+        if id == self.current_branch {
+            Some(&self.chat_session)
+        } else {
+            self.branch_sessions.get(id)
+        }
+    }
+
+    /// Makes `id` the current branch, which `chat` and the other methods then continue.
+    pub fn switch_branch(&mut self, id: &str) -> Result<()> {
+        if id == self.current_branch {
+            return Ok(());
+        }
+        let session = self
+            .branch_sessions
+            .remove(id)
+            .ok_or_else(|| unknown_branch(id))?;
+        let previous = std::mem::replace(&mut self.chat_session, session);
+        let previous_id = std::mem::replace(&mut self.current_branch, id.to_string());
+        self.branch_sessions.insert(previous_id, previous);
+        Ok(())
+    }
+
+    /// Sends a message on the given branch, leaving the current branch as it is.
+    pub async fn chat_on_branch(
+        &mut self,
+        branch: &str,
+        message: impl Into<String>,
+    ) -> Result<String> {
+        let previous = self.current_branch.clone();
+        self.switch_branch(branch)?;
+        let result = self.send_message(message).await;
+        self.switch_branch(&previous)?;
+        result
+    }
+
+    /// Deletes a branch and every branch forked from it.
+    ///
+    /// The main branch, and branches leading to the current one, cannot be deleted.
+    pub fn delete_branch(&mut self, id: &str) -> Result<()> {
+        let on_current_path = self
+            .branch_tree
+            .path(&self.current_branch)?
+            .iter()
+            .any(|branch| branch.id == id);
+        if on_current_path && id != MAIN_BRANCH {
+            return Err(HeliosError::AgentError(format!(
+                "Branch '{}' leads to the current branch; switch to another branch first",
+                id
+            )));
+        }
+        for removed in self.branch_tree.remove(id)? {
+            self.branch_sessions.remove(&removed);
+        }
+        Ok(())
+    }
+
+    /// Registers an observer that receives the agent's [`AgentEvent`]s.
+    pub fn add_observer(&mut self, observer: Arc<dyn AgentObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Returns a stream of the agent's events from now on.
+    ///
+    /// The stream ends when the agent is dropped.
+    pub fn subscribe(&mut self) -> impl futures::Stream<Item = AgentEvent> {
+        let (observer, receiver) = ChannelObserver::new();
+        self.add_observer(Arc::new(observer));
+        futures::StreamExt::map(
+            tokio_stream::wrappers::UnboundedReceiverStream::new(receiver),
+            |(_, event)| event,
+        )
+    }
+
+    /// Returns the number of messages and the approximate memory held by the history.
+    pub fn memory_usage(&self) -> MemoryStats {
+        self.chat_session.memory_usage()
+    }
+
+    /// Sets how much history the agent keeps; older messages are pruned each turn.
+    pub fn set_history_limit(&mut self, limit: Option<HistoryLimit>) {
+        self.history_limit = limit;
+    }
+
+    /// Sets which answered tool results are replaced with placeholders each turn.
+    pub fn set_tool_result_pruning(&mut self, pruning: Option<ToolResultPruning>) {
+        self.tool_result_pruning = pruning;
+    }
+
+    /// Applies the history limit and tool result pruning to the chat session.
+    fn prune_history(&mut self) {
+        if let Some(limit) = self.history_limit {
+            limit.apply(&mut self.chat_session);
+        }
+        if let Some(pruning) = self.tool_result_pruning {
+            pruning.apply(&mut self.chat_session);
+        }
+    }
+
+    /// Returns the log of the agent's tool calls, with per-tool statistics.
+    ///
+    /// ```rust,no_run
+    /// # fn example(agent: &helios_engine::Agent) {
+    /// for (tool, stats) in agent.tool_audit().stats() {
+    ///     println!("{}: {} calls, {} failed", tool, stats.calls, stats.failures);
+    /// }
+    /// # }
+    /// ```
+    pub fn tool_audit(&self) -> &ToolAudit {
+        &self.tool_audit
+    }
+
+    /// Replaces the log the agent records its tool calls in.
+    pub fn set_tool_audit(&mut self, audit: ToolAudit) {
+        self.tool_audit = audit;
+    }
+
+    /// Sets the cache that answers questions similar to ones already answered.
+    pub fn set_semantic_cache(&mut self, cache: Option<SemanticCache>) {
+        self.semantic_cache = cache;
+    }
+
+    /// Returns the agent's semantic cache, if it has one.
+    pub fn semantic_cache(&self) -> Option<&SemanticCache> {
+        self.semantic_cache.as_ref()
+    }
+
+    /// Looks `query` up in the semantic cache.
+    ///
+    /// Returns the answer to reply with in answer mode. In prime mode a hit is kept
+    /// for the turn's LLM calls instead. Cache failures only log a warning, so the
+    /// turn goes on without the cache.
+    async fn cached_answer(&mut self, query: &str) -> Option<String> {
+        let cache = self.semantic_cache.as_ref()?;
+        let hit = match cache.lookup(query).await {
+            Ok(hit) => hit?,
+            Err(e) => {
+                tracing::warn!(agent = %self.name, "semantic cache lookup failed: {}", e);
+                return None;
+            }
+        };
+        tracing::debug!(agent = %self.name, similarity = hit.similarity, "semantic cache hit");
+        match cache.mode() {
+            SemanticCacheMode::Answer => Some(hit.answer),
+            SemanticCacheMode::Prime => {
+                self.cache_hint = Some(hit);
+                None
+            }
+        }
+    }
+
+    /// Ends a turn run past the semantic cache, caching the reply unless the turn
+    /// was primed with an answer that is already cached.
+    async fn cache_reply(&mut self, query: &str, reply: &Result<String>) {
+        let primed = self.cache_hint.take().is_some();
+        let (Some(cache), Ok(reply)) = (&self.semantic_cache, reply) else {
+            return;
+        };
+        if primed || reply.trim().is_empty() {
+            return;
+        }
+        if let Err(e) = cache.store(query, reply).await {
+            tracing::warn!(agent = %self.name, "semantic cache store failed: {}", e);
+        }
+    }
+
+    /// Returns the model name of the provider that answered the agent's last LLM call.
+    ///
+    /// This differs from the configured model when a fallback provider was used.
+    pub fn last_provider(&self) -> Option<String> {
+        self.llm_client.last_provider()
+    }
+
+    /// Returns the token usage accumulated by the agent over its lifetime.
+    pub fn usage(&self) -> TokenUsage {
+        self.llm_client.usage()
+    }
+
+    /// Checks that the agent's LLM provider is reachable; see [`LLMClient::check_ready`].
+    pub async fn check_ready(&self) -> Result<()> {
+        self.llm_client.check_ready().await
+    }
+
+    /// Replaces the agent's LLM client, for example after the configuration changed.
+    ///
+    /// The conversation, tools and token usage so far are kept.
+    pub fn set_llm_client(&mut self, llm_client: LLMClient) {
+        let usage = self.llm_client.usage_tracker().clone();
+        self.llm_client = llm_client.with_usage_tracker(usage);
+    }
+
+    /// Switches the agent to another local model, keeping the conversation.
+    ///
+    /// A model already in the [`crate::LocalModelPool`] is used as is, so
+    /// switching back and forth between loaded models is cheap.
+    #[cfg(feature = "local")]
+    pub async fn switch_local_model(&mut self, model: crate::config::LocalConfig) -> Result<()> {
+        let llm_client = LLMClient::new(LLMProviderType::Local(model)).await?;
+        self.set_llm_client(llm_client);
+        Ok(())
+    }
+
+    /// Returns the token usage accumulated since the agent's history was last cleared.
+    pub fn session_usage(&self) -> TokenUsage {
+        let mut total = TokenUsage::default();
+        for usage in self.session_usage_by_model().values() {
+            total += *usage;
+        }
+        total
+    }
+
+    /// Returns the dollar cost of the agent's lifetime usage according to its price table.
+    pub fn cost(&self) -> f64 {
+        self.llm_client.usage_tracker().cost(&self.price_table)
+    }
+
+    /// Returns the dollar cost of the current session's usage according to its price table.
+    pub fn session_cost(&self) -> f64 {
+        self.price_table.total_cost(&self.session_usage_by_model())
+    }
+
+    /// Returns the price table used to compute costs.
+    pub fn price_table(&self) -> &PriceTable {
+        &self.price_table
+    }
+
+    /// Sets the price table used to compute costs.
+    pub fn set_price_table(&mut self, prices: PriceTable) {
+        self.price_table = prices;
+    }
+
+    /// Returns the limits on the current session's usage.
+    pub fn budget(&self) -> Budget {
+        self.budget
+    }
+
+    /// Limits the tokens and cost of each session.
+    ///
+    /// Once the session reaches a limit, every further LLM call fails with
+    /// `HeliosError::BudgetExceeded` until [`Agent::clear_history`] starts a new one.
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+    }
+
+    /// Fails if the current session has used up its budget.
+    fn check_budget(&self) -> Result<()> {
+        if !self.budget.is_limited() {
+            return Ok(());
+        }
+        let by_model = self.session_usage_by_model();
+        let mut usage = TokenUsage::default();
+        for model_usage in by_model.values() {
+            usage += *model_usage;
+        }
+        self.budget
+            .check(&usage, self.price_table.total_cost(&by_model))
+    }
+
+    /// Stops the agent immediately.
+    ///
+    /// The LLM call or tool execution in flight is cancelled and every further one
+    /// fails with `HeliosError::Aborted` until `reset_abort` is called. Tool calls
+    /// of the interrupted turn are removed from the history. Because
+    /// `chat` borrows the agent mutably, take an `abort_handle` first to abort a
+    /// run from another task.
+    pub fn abort(&self) {
+        self.abort.abort();
+    }
+
+    /// Returns a handle that aborts this agent, usable while it is running.
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.abort.clone()
+    }
+
+    /// Returns `true` if the agent, or the forest it belongs to, has been aborted.
+    pub fn is_aborted(&self) -> bool {
+        self.abort.is_aborted()
+    }
+
+    /// Clears an abort so the agent accepts work again.
+    pub fn reset_abort(&self) {
+        self.abort.reset();
+    }
+
+    /// Registers a middleware that runs around every LLM call and tool call.
+    ///
+    /// Middleware run in the order they were added.
+    pub fn add_middleware(&mut self, middleware: impl AgentMiddleware + 'static) {
+        self.middleware.push(Box::new(middleware));
+    }
+
+    /// Registers a moderator for the user's input and the agent's final reply.
+    ///
+    /// Moderators run in the order they were added.
+    pub fn add_moderator(&mut self, moderator: impl Moderator + 'static) {
+        self.moderators.push(Box::new(moderator));
+    }
+
+    /// Passes `text` through the agent's moderators.
+    async fn moderate(&self, text: String, target: ModerationTarget) -> Result<String> {
+        if self.moderators.is_empty() {
+            return Ok(text);
+        }
+        moderation::moderate(&self.moderators, &text, target).await
+    }
+
+    /// Moderates the last user message of a conversation passed in by the caller.
+    async fn moderate_last_input(&self, messages: &mut [ChatMessage]) -> Result<()> {
+        moderation::moderate_last_input(&self.moderators, messages).await
+    }
+
+    /// Returns the per-model usage since the session baseline was taken.
+    fn session_usage_by_model(&self) -> HashMap<String, TokenUsage> {
+        self.llm_client
+            .usage_tracker()
+            .by_model()
+            .into_iter()
+            .map(|(model, usage)| {
+                let delta = match self.session_baseline.get(&model) {
+                    Some(baseline) => usage.since(baseline),
+                    None => usage,
+                };
+                (model, delta)
+            })
+            .collect()
     }
 
     /// Sends a message to the agent and gets a response.
@@ -181,14 +747,28 @@ impl Agent {
     /// # Returns
     ///
     /// A `Result` containing the agent's response.
+    ///
+    /// If a moderator blocks the message or the reply, [`HeliosError::Blocked`] is
+    /// returned and the blocked text is not added to the chat history.
+    ///
+    /// With a semantic cache in answer mode, a question similar enough to one
+    /// already answered gets that answer without calling the model.
     pub async fn send_message(&mut self, message: impl Into<String>) -> Result<String> {
-        let user_message = message.into();
+        let user_message = self
+            .moderate(message.into(), ModerationTarget::Input)
+            .await?;
         self.chat_session.add_user_message(user_message.clone());
+        self.prune_history();
+        if let Some(answer) = self.cached_answer(&user_message).await {
+            self.chat_session.add_assistant_message(answer.clone());
+            return Ok(answer);
+        }
 
         // Execute agent loop with tool calling
-        let response = self.execute_with_tools().await?;
+        let response = self.execute_with_tools().await;
+        self.cache_reply(&user_message, &response).await;
 
-        Ok(response)
+        response
     }
 
     /// Default reasoning prompt for ReAct mode.
@@ -214,12 +794,17 @@ Provide your reasoning in a clear, structured way."#;
         // Create a temporary reasoning message
         let mut reasoning_messages = self.chat_session.get_messages();
         reasoning_messages.push(ChatMessage::user(reasoning_prompt));
+        let reasoning_messages = self.prepare_messages(reasoning_messages).await?;
 
         // Get reasoning from LLM without tools
         let response = self
-            .llm_client
-            .chat(reasoning_messages, None, None, None, None)
-            .await?;
+            .abort
+            .run(
+                self.llm_client
+                    .chat(reasoning_messages, None, None, None, None),
+            )
+            .await??;
+        let response = self.finish_response(response).await?;
 
         Ok(response.content)
     }
@@ -233,9 +818,11 @@ Provide your reasoning in a clear, structured way."#;
         // If ReAct mode is enabled, generate reasoning first
         if self.react_mode && !self.tool_registry.get_definitions().is_empty() {
             let reasoning = self.generate_reasoning().await?;
-
-            // Display reasoning to user
-            println!("\n💭 ReAct Reasoning:\n{}\n", reasoning);
+            notify(
+                &self.observers,
+                &self.name,
+                AgentEvent::Reasoning(reasoning.clone()),
+            );
 
             // Add reasoning to chat history as an assistant message (not user)
             // This represents the agent's internal thought process
@@ -248,6 +835,96 @@ Provide your reasoning in a clear, structured way."#;
         Ok(())
     }
 
+    /// Passes the messages about to be sent to the LLM through each middleware.
+    ///
+    /// Changes apply to the outgoing request only; the chat history is untouched.
+    /// Every LLM call goes through here, so the session budget is checked first.
+    async fn prepare_messages(&self, mut messages: Vec<ChatMessage>) -> Result<Vec<ChatMessage>> {
+        self.check_budget()?;
+        if let Some(hint) = &self.cache_hint {
+            let at = messages
+                .iter()
+                .rposition(|message| message.role == crate::chat::Role::User)
+                .unwrap_or(messages.len());
+            messages.insert(
+                at,
+                ChatMessage::system(format!(
+                    "A similar question was answered before.\nQuestion: {}\nAnswer: {}\n\
+                     Reuse this answer if it also answers the next question; otherwise answer it afresh.",
+                    hint.query, hint.answer
+                )),
+            );
+        }
+        for middleware in &self.middleware {
+            middleware.before_llm_call(&mut messages).await?;
+        }
+        Ok(messages)
+    }
+
+    /// Passes an LLM response through each middleware before the agent acts on it.
+    async fn finish_response(&self, mut response: ChatMessage) -> Result<ChatMessage> {
+        for middleware in &self.middleware {
+            middleware.after_llm_call(&mut response).await?;
+        }
+        Ok(response)
+    }
+
+    /// Executes a tool call requested by the LLM, running the middleware hooks around it.
+    ///
+    /// A middleware that rejects the call in `before_tool_call` blocks the tool, and
+    /// the rejection is reported back to the LLM as a failed tool result. An abort
+    /// returned by a middleware stops the run instead. Blocked and executed calls
+    /// are both recorded in the tool audit log.
+    async fn execute_tool_call(&self, tool_call: &ToolCall) -> Result<ToolResult> {
+        let tool_name = &tool_call.function.name;
+        let mut tool_args: Value = serde_json::from_str(&tool_call.function.arguments)
+            .unwrap_or(Value::Object(serde_json::Map::new()));
+        let started_at = crate::clock::now();
+        let started = std::time::Instant::now();
+        let audit = |arguments: Value, result: &ToolResult| {
+            self.tool_audit.record(
+                &self.name,
+                &tool_call.id,
+                tool_name,
+                arguments,
+                started_at,
+                started.elapsed(),
+                result.success,
+                &result.output,
+            );
+        };
+
+        for middleware in &self.middleware {
+            match middleware.before_tool_call(tool_name, &mut tool_args).await {
+                Ok(()) => {}
+                Err(e @ HeliosError::Aborted(_)) => return Err(e),
+                Err(e) => {
+                    let result = ToolResult::error(format!(
+                        "Tool call blocked by {}: {}",
+                        middleware.name(),
+                        e
+                    ));
+                    audit(tool_args, &result);
+                    return Ok(result);
+                }
+            }
+        }
+
+        let mut tool_result = self
+            .abort
+            .run(self.tool_registry.execute(tool_name, tool_args.clone()))
+            .await?
+            .unwrap_or_else(|e| ToolResult::error(format!("Tool execution failed: {}", e)));
+
+        for middleware in &self.middleware {
+            middleware
+                .after_tool_call(tool_name, &mut tool_result)
+                .await?;
+        }
+        audit(tool_args, &tool_result);
+        Ok(tool_result)
+    }
+
     /// Executes the agent's main loop, including tool calls.
     async fn execute_with_tools(&mut self) -> Result<String> {
         self.execute_with_tools_streaming().await
@@ -259,8 +936,8 @@ Provide your reasoning in a clear, structured way."#;
             .await
     }
 
-    /// Executes the agent's main loop with parameters, including tool calls.
-    async fn execute_with_tools_with_params(
+    /// Executes the agent's main loop with parameters and streaming, including tool calls.
+    async fn execute_with_tools_streaming_with_params(
         &mut self,
         temperature: Option<f32>,
         max_tokens: Option<u32>,
@@ -269,155 +946,301 @@ Provide your reasoning in a clear, structured way."#;
         // Handle ReAct reasoning if enabled
         self.handle_react_reasoning().await?;
 
-        let mut iterations = 0;
-        let tool_definitions = self.tool_registry.get_definitions();
-
-        loop {
-            if iterations >= self.max_iterations {
-                return Err(HeliosError::AgentError(
-                    "Maximum iterations reached".to_string(),
-                ));
-            }
-
-            let messages = self.chat_session.get_messages();
-            let tools_option = if tool_definitions.is_empty() {
-                None
-            } else {
-                Some(tool_definitions.clone())
-            };
-
-            let response = self
-                .llm_client
-                .chat(
-                    messages,
-                    tools_option,
-                    temperature,
-                    max_tokens,
-                    stop.clone(),
-                )
-                .await?;
-
-            // Check if the response includes tool calls
-            if let Some(ref tool_calls) = response.tool_calls {
-                // Add assistant message with tool calls
-                self.chat_session.add_message(response.clone());
-
-                // Execute each tool call
-                for tool_call in tool_calls {
-                    let tool_name = &tool_call.function.name;
-                    let tool_args: Value = serde_json::from_str(&tool_call.function.arguments)
-                        .unwrap_or(Value::Object(serde_json::Map::new()));
-
-                    let tool_result = self
-                        .tool_registry
-                        .execute(tool_name, tool_args)
-                        .await
-                        .unwrap_or_else(|e| {
-                            ToolResult::error(format!("Tool execution failed: {}", e))
-                        });
-
-                    // Add tool result message
-                    let tool_message = ChatMessage::tool(tool_result.output, tool_call.id.clone());
-                    self.chat_session.add_message(tool_message);
-                }
-
-                iterations += 1;
-                continue;
-            }
-
-            // No tool calls, we have the final response
-            self.chat_session.add_message(response.clone());
-            return Ok(response.content);
-        }
+        // Observers are told about the streamed text by the loop itself.
+        self.stream_session(temperature, max_tokens, stop, |_| {})
+            .await
     }
 
-    /// Executes the agent's main loop with parameters and streaming, including tool calls.
-    async fn execute_with_tools_streaming_with_params(
+    /// Streams the agent's loop over its own chat session and records the answer.
+    async fn stream_session<F>(
         &mut self,
         temperature: Option<f32>,
         max_tokens: Option<u32>,
         stop: Option<Vec<String>>,
-    ) -> Result<String> {
-        // Handle ReAct reasoning if enabled
-        self.handle_react_reasoning().await?;
+        on_event: F,
+    ) -> Result<String>
+    where
+        F: FnMut(StreamEvent) + Send,
+    {
+        let mut session = std::mem::take(&mut self.chat_session);
+        let snapshot = session.clone();
+        let result = self
+            .stream_loop(&mut session, temperature, max_tokens, stop, on_event)
+            .await;
+        // On failure, drop any tool calls whose results were never added
+        self.chat_session = if result.is_ok() { session } else { snapshot };
+
+        let mut response = result?;
+        response.content = self
+            .moderate(response.content, ModerationTarget::Output)
+            .await?;
+        self.chat_session.add_message(response.clone());
+        Ok(response.content)
+    }
 
+    /// Runs the tool loop on `session`, streaming every LLM response through `on_event`.
+    ///
+    /// Assistant messages with tool calls and the tool results are added to
+    /// `session`. The final response is returned without being added.
+    async fn stream_loop<F>(
+        &self,
+        session: &mut ChatSession,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        stop: Option<Vec<String>>,
+        mut on_event: F,
+    ) -> Result<ChatMessage>
+    where
+        F: FnMut(StreamEvent) + Send,
+    {
         let mut iterations = 0;
         let tool_definitions = self.tool_registry.get_definitions();
 
         loop {
+            self.abort.check()?;
             if iterations >= self.max_iterations {
                 return Err(HeliosError::AgentError(
                     "Maximum iterations reached".to_string(),
                 ));
             }
 
-            let messages = self.chat_session.get_messages();
-            let tools_option = if tool_definitions.is_empty() {
-                None
-            } else {
-                Some(tool_definitions.clone())
-            };
+            let request =
+                self.tool_protocol
+                    .request(session.get_messages(), &tool_definitions, stop.clone());
+            let messages = self.prepare_messages(request.messages).await?;
 
+            notify(&self.observers, &self.name, AgentEvent::ThinkingStarted);
+            let observers = &self.observers;
             let mut streamed_content = String::new();
-
-            let stream_result = self
-                .llm_client
-                .chat_stream(
+            // Under the ReAct protocol only the final answer is shown while streaming.
+            let mut react_stream = (self.tool_protocol == ToolProtocol::React
+                && !tool_definitions.is_empty())
+            .then(ReactStream::default);
+            let mut response = self
+                .abort
+                .run(self.llm_client.stream(
                     messages,
-                    tools_option, // Enable tools for streaming
+                    request.tools,
                     temperature,
                     max_tokens,
-                    stop.clone(),
-                    |chunk| {
-                        // Print chunk to stdout for visible streaming
-                        print!("{}", chunk);
-                        let _ = std::io::Write::flush(&mut std::io::stdout());
-                        streamed_content.push_str(chunk);
+                    request.stop,
+                    request.grammar,
+                    |event| {
+                        let StreamEvent::Content(text) = event else {
+                            on_event(event);
+                            return;
+                        };
+                        streamed_content.push_str(&text);
+                        let shown = match react_stream.as_mut() {
+                            Some(stream) => stream.push(&text),
+                            None => Some(text),
+                        };
+                        if let Some(text) = shown {
+                            notify(observers, &self.name, AgentEvent::TokenDelta(text.clone()));
+                            on_event(StreamEvent::Content(text));
+                        }
                     },
-                )
-                .await;
+                ))
+                .await??
+                .0;
+            response.content = streamed_content;
+            let response = self.tool_protocol.parse(response, &tool_definitions);
+            let response = self.finish_response(response).await?;
+
+            if let Some(text) = react_stream.and_then(|stream| stream.finish(&response)) {
+                notify(observers, &self.name, AgentEvent::TokenDelta(text.clone()));
+                on_event(StreamEvent::Content(text));
+            }
+            let Some(tool_calls) = response.tool_calls.clone() else {
+                notify(
+                    &self.observers,
+                    &self.name,
+                    AgentEvent::FinalAnswer(response.content.clone()),
+                );
+                return Ok(response);
+            };
+            session.add_message(response);
+
+            for tool_call in &tool_calls {
+                on_event(StreamEvent::ToolStarted {
+                    id: tool_call.id.clone(),
+                    name: tool_call.function.name.clone(),
+                    arguments: tool_call.function.arguments.clone(),
+                });
+                notify(
+                    &self.observers,
+                    &self.name,
+                    AgentEvent::ToolCallStarted {
+                        name: tool_call.function.name.clone(),
+                        args: serde_json::from_str(&tool_call.function.arguments)
+                            .unwrap_or(Value::Null),
+                    },
+                );
+                let tool_result = self.execute_tool_call(tool_call).await?;
+                notify(
+                    &self.observers,
+                    &self.name,
+                    AgentEvent::ToolCallFinished {
+                        name: tool_call.function.name.clone(),
+                        success: tool_result.success,
+                        output: tool_result.output.clone(),
+                    },
+                );
+                on_event(StreamEvent::ToolFinished {
+                    id: tool_call.id.clone(),
+                    name: tool_call.function.name.clone(),
+                    success: tool_result.success,
+                    output: tool_result.output.clone(),
+                });
+                session.add_message(ChatMessage::tool(tool_result.output, tool_call.id.clone()));
+            }
 
-            let response = stream_result?;
+            iterations += 1;
+        }
+    }
 
-            // Print newline after streaming completes
-            println!();
+    /// Sends a message and streams the reply, reporting tool calls as they run.
+    ///
+    /// `on_event` receives the response text as it is generated, the fragments of
+    /// any tool calls the model writes, and a [`StreamEvent::ToolStarted`] and
+    /// [`StreamEvent::ToolFinished`] around each tool the agent runs. The exchange
+    /// is recorded in the chat session, as with [`Agent::chat`].
+    ///
+    /// Moderators see the reply only once it is complete, after its text has been
+    /// streamed; the moderated reply is what gets returned and recorded.
+    pub async fn chat_stream<F>(
+        &mut self,
+        message: impl Into<String>,
+        mut on_event: F,
+    ) -> Result<String>
+    where
+        F: FnMut(StreamEvent) + Send,
+    {
+        let message = self
+            .moderate(message.into(), ModerationTarget::Input)
+            .await?;
+        self.chat_session.add_user_message(message.clone());
+        self.prune_history();
+        if let Some(answer) = self.cached_answer(&message).await {
+            on_event(StreamEvent::Content(answer.clone()));
+            self.chat_session.add_assistant_message(answer.clone());
+            return Ok(answer);
+        }
+        let response = match self.handle_react_reasoning().await {
+            Ok(()) => self.stream_session(None, None, None, on_event).await,
+            Err(e) => Err(e),
+        };
+        self.cache_reply(&message, &response).await;
+        response
+    }
 
-            // Check if the response includes tool calls
-            if let Some(ref tool_calls) = response.tool_calls {
-                // Add assistant message with tool calls
-                let mut msg_with_content = response.clone();
-                msg_with_content.content = streamed_content.clone();
-                self.chat_session.add_message(msg_with_content);
+    /// Sends a message, giving up if the whole turn takes longer than `deadline`.
+    ///
+    /// The limit covers every LLM call and tool execution of the turn, so a tool
+    /// loop that keeps going is stopped long before `max_iterations`. When time runs
+    /// out, the request or tool in flight is dropped, which cancels it. The chat
+    /// history is restored to what it was before the message was sent, and
+    /// `HeliosError::DeadlineExceeded` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, HeliosError};
+    /// # use std::time::Duration;
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let mut agent = Agent::builder("researcher")
+    ///     .config(Config::new_default())
+    ///     .build()
+    ///     .await?;
+    /// match agent
+    ///     .chat_with_deadline("Summarize the repository", Duration::from_secs(30))
+    ///     .await
+    /// {
+    ///     Err(HeliosError::DeadlineExceeded(_)) => println!("Took too long, try a narrower question."),
+    ///     other => println!("{}", other?),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn chat_with_deadline(
+        &mut self,
+        message: impl Into<String>,
+        deadline: std::time::Duration,
+    ) -> Result<String> {
+        let snapshot = self.chat_session.clone();
+        match tokio::time::timeout(deadline, self.send_message(message)).await {
+            Ok(result) => result,
+            Err(_) => {
+                self.chat_session = snapshot;
+                tracing::warn!(agent = %self.name, "turn cancelled after {:?}", deadline);
+                Err(HeliosError::DeadlineExceeded(format!(
+                    "agent turn took longer than {:?}",
+                    deadline
+                )))
+            }
+        }
+    }
 
-                // Execute each tool call
-                for tool_call in tool_calls {
-                    let tool_name = &tool_call.function.name;
-                    let tool_args: Value = serde_json::from_str(&tool_call.function.arguments)
-                        .unwrap_or(Value::Object(serde_json::Map::new()));
-
-                    let tool_result = self
-                        .tool_registry
-                        .execute(tool_name, tool_args)
-                        .await
-                        .unwrap_or_else(|e| {
-                            ToolResult::error(format!("Tool execution failed: {}", e))
-                        });
-
-                    // Add tool result message
-                    let tool_message = ChatMessage::tool(tool_result.output, tool_call.id.clone());
-                    self.chat_session.add_message(tool_message);
-                }
+    /// Answers the last user message again, replacing the previous answer.
+    ///
+    /// Everything the agent added after that message, including tool calls and
+    /// their results, is discarded before the turn is run again. A `temperature`
+    /// overrides the configured one for this turn only. If the new run fails, the
+    /// previous answer is kept.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(mut agent: helios_engine::Agent) -> helios_engine::Result<()> {
+    /// agent.chat("Write a haiku about autumn").await?;
+    /// let another = agent.regenerate(Some(1.2)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn regenerate(&mut self, temperature: Option<f32>) -> Result<String> {
+        let snapshot = self.chat_session.clone();
+        let message = self
+            .chat_session
+            .rewind_last_turn()
+            .ok_or_else(|| HeliosError::AgentError("There is no turn to regenerate".to_string()))?;
+        self.chat_session.add_message(message);
+        self.rerun_turn(snapshot, temperature).await
+    }
 
-                iterations += 1;
-                continue;
+    /// Replaces the last user message with `message` and answers it.
+    ///
+    /// The previous turn is taken back whole, so no tool calls or results from it
+    /// remain in the history. If the new message is blocked or the run fails, the
+    /// previous turn is restored.
+    pub async fn edit_last_user_message(&mut self, message: impl Into<String>) -> Result<String> {
+        let snapshot = self.chat_session.clone();
+        if self.chat_session.rewind_last_turn().is_none() {
+            return Err(HeliosError::AgentError(
+                "There is no user message to edit".to_string(),
+            ));
+        }
+        let message = match self.moderate(message.into(), ModerationTarget::Input).await {
+            Ok(message) => message,
+            Err(e) => {
+                self.chat_session = snapshot;
+                return Err(e);
             }
+        };
+        self.chat_session.add_user_message(message);
+        self.rerun_turn(snapshot, None).await
+    }
 
-            // No tool calls, we have the final response with streamed content
-            let mut final_msg = response;
-            final_msg.content = streamed_content.clone();
-            self.chat_session.add_message(final_msg);
-            return Ok(streamed_content);
+    /// Runs the turn ending in the session's last user message, restoring `snapshot` on failure.
+    async fn rerun_turn(
+        &mut self,
+        snapshot: ChatSession,
+        temperature: Option<f32>,
+    ) -> Result<String> {
+        let result = self
+            .execute_with_tools_streaming_with_params(temperature, None, None)
+            .await;
+        if result.is_err() {
+            self.chat_session = snapshot;
         }
+        result
     }
 
     /// A convenience method for sending a message to the agent.
@@ -459,6 +1282,17 @@ Provide your reasoning in a clear, structured way."#;
         self.max_iterations = max;
     }
 
+    /// Returns how tools are offered to the model.
+    pub fn tool_protocol(&self) -> ToolProtocol {
+        self.tool_protocol
+    }
+
+    /// Sets how tools are offered to the model, for example after switching to a
+    /// local model without function calling.
+    pub fn set_tool_protocol(&mut self, protocol: ToolProtocol) {
+        self.tool_protocol = protocol;
+    }
+
     /// Returns a summary of the current chat session.
     pub fn get_session_summary(&self) -> String {
         self.chat_session.get_summary()
@@ -532,11 +1366,13 @@ Provide your reasoning in a clear, structured way."#;
     /// A `Result` containing the assistant's response content.
     pub async fn chat_with_history(
         &mut self,
-        messages: Vec<ChatMessage>,
+        mut messages: Vec<ChatMessage>,
         temperature: Option<f32>,
         max_tokens: Option<u32>,
         stop: Option<Vec<String>>,
     ) -> Result<String> {
+        self.moderate_last_input(&mut messages).await?;
+
         // Create a temporary session with the provided messages
         let mut temp_session = ChatSession::new();
 
@@ -562,29 +1398,34 @@ Provide your reasoning in a clear, structured way."#;
         let tool_definitions = self.tool_registry.get_definitions();
 
         loop {
+            self.abort.check()?;
             if iterations >= self.max_iterations {
                 return Err(HeliosError::AgentError(
                     "Maximum iterations reached".to_string(),
                 ));
             }
 
-            let messages = temp_session.get_messages();
-            let tools_option = if tool_definitions.is_empty() {
-                None
-            } else {
-                Some(tool_definitions.clone())
-            };
+            let request = self.tool_protocol.request(
+                temp_session.get_messages(),
+                &tool_definitions,
+                stop.clone(),
+            );
+            let messages = self.prepare_messages(request.messages).await?;
 
             let response = self
-                .llm_client
-                .chat(
+                .abort
+                .run(self.llm_client.complete(
                     messages,
-                    tools_option,
+                    request.tools,
                     temperature,
                     max_tokens,
-                    stop.clone(),
-                )
-                .await?;
+                    request.stop,
+                    request.grammar,
+                ))
+                .await??
+                .0;
+            let response = self.tool_protocol.parse(response, &tool_definitions);
+            let response = self.finish_response(response).await?;
 
             // Check if the response includes tool calls
             if let Some(ref tool_calls) = response.tool_calls {
@@ -593,17 +1434,7 @@ Provide your reasoning in a clear, structured way."#;
 
                 // Execute each tool call
                 for tool_call in tool_calls {
-                    let tool_name = &tool_call.function.name;
-                    let tool_args: Value = serde_json::from_str(&tool_call.function.arguments)
-                        .unwrap_or(Value::Object(serde_json::Map::new()));
-
-                    let tool_result = self
-                        .tool_registry
-                        .execute(tool_name, tool_args)
-                        .await
-                        .unwrap_or_else(|e| {
-                            ToolResult::error(format!("Tool execution failed: {}", e))
-                        });
+                    let tool_result = self.execute_tool_call(tool_call).await?;
 
                     // Add tool result message to temp session
                     let tool_message = ChatMessage::tool(tool_result.output, tool_call.id.clone());
@@ -615,7 +1446,9 @@ Provide your reasoning in a clear, structured way."#;
             }
 
             // No tool calls, we have the final response
-            return Ok(response.content);
+            return self
+                .moderate(response.content, ModerationTarget::Output)
+                .await;
         }
     }
 
@@ -623,8 +1456,8 @@ Provide your reasoning in a clear, structured way."#;
     ///
     /// This method creates a temporary chat session with the provided messages
     /// and streams the agent's response in real-time as tokens are generated.
-    /// Note: Tool calls are not supported in streaming mode yet - they will be
-    /// executed after the initial response is complete.
+    /// Tool calls are executed between streamed responses; use
+    /// [`Agent::chat_stream_with_history_events`] to be told about them.
     ///
     /// # Arguments
     ///
@@ -643,120 +1476,49 @@ Provide your reasoning in a clear, structured way."#;
         temperature: Option<f32>,
         max_tokens: Option<u32>,
         stop: Option<Vec<String>>,
-        on_chunk: F,
+        mut on_chunk: F,
     ) -> Result<ChatMessage>
     where
         F: FnMut(&str) + Send,
     {
-        // Create a temporary session with the provided messages
-        let mut temp_session = ChatSession::new();
-
-        // Add all messages to the temporary session
-        for message in messages {
-            temp_session.add_message(message);
-        }
-
-        // For now, use streaming for the initial response only
-        // Tool calls will be handled after the stream completes
-        self.execute_streaming_with_tools_temp_session(
-            temp_session,
-            temperature,
-            max_tokens,
-            stop,
-            on_chunk,
-        )
+        self.chat_stream_with_history_events(messages, temperature, max_tokens, stop, |event| {
+            if let StreamEvent::Content(text) = event {
+                on_chunk(&text);
+            }
+        })
         .await
     }
 
-    /// Executes the agent's main loop with streaming and a temporary session.
-    async fn execute_streaming_with_tools_temp_session<F>(
+    /// Like [`Agent::chat_stream_with_history`], reporting text, tool-call fragments
+    /// and tool execution as [`StreamEvent`]s.
+    ///
+    /// As with [`Agent::chat_stream`], only the returned message is moderated.
+    pub async fn chat_stream_with_history_events<F>(
         &mut self,
-        mut temp_session: ChatSession,
+        mut messages: Vec<ChatMessage>,
         temperature: Option<f32>,
         max_tokens: Option<u32>,
         stop: Option<Vec<String>>,
-        mut on_chunk: F,
+        on_event: F,
     ) -> Result<ChatMessage>
     where
-        F: FnMut(&str) + Send,
+        F: FnMut(StreamEvent) + Send,
     {
-        let mut iterations = 0;
-        let tool_definitions = self.tool_registry.get_definitions();
-
-        loop {
-            if iterations >= self.max_iterations {
-                return Err(HeliosError::AgentError(
-                    "Maximum iterations reached".to_string(),
-                ));
-            }
-
-            let messages = temp_session.get_messages();
-            let tools_option = if tool_definitions.is_empty() {
-                None
-            } else {
-                Some(tool_definitions.clone())
-            };
-
-            // Use streaming for all iterations
-            let mut streamed_content = String::new();
-
-            let stream_result = self
-                .llm_client
-                .chat_stream(
-                    messages,
-                    tools_option,
-                    temperature,
-                    max_tokens,
-                    stop.clone(),
-                    |chunk| {
-                        on_chunk(chunk);
-                        streamed_content.push_str(chunk);
-                    },
-                )
-                .await;
-
-            match stream_result {
-                Ok(response) => {
-                    // Check if the response includes tool calls
-                    if let Some(ref tool_calls) = response.tool_calls {
-                        // Add assistant message with tool calls to temp session
-                        let mut msg_with_content = response.clone();
-                        msg_with_content.content = streamed_content.clone();
-                        temp_session.add_message(msg_with_content);
-
-                        // Execute each tool call
-                        for tool_call in tool_calls {
-                            let tool_name = &tool_call.function.name;
-                            let tool_args: Value =
-                                serde_json::from_str(&tool_call.function.arguments)
-                                    .unwrap_or(Value::Object(serde_json::Map::new()));
-
-                            let tool_result = self
-                                .tool_registry
-                                .execute(tool_name, tool_args)
-                                .await
-                                .unwrap_or_else(|e| {
-                                    ToolResult::error(format!("Tool execution failed: {}", e))
-                                });
-
-                            // Add tool result message to temp session
-                            let tool_message =
-                                ChatMessage::tool(tool_result.output, tool_call.id.clone());
-                            temp_session.add_message(tool_message);
-                        }
+        self.moderate_last_input(&mut messages).await?;
 
-                        iterations += 1;
-                        continue; // Continue the loop for another iteration
-                    } else {
-                        // No tool calls, return the final response with streamed content
-                        let mut final_msg = response;
-                        final_msg.content = streamed_content;
-                        return Ok(final_msg);
-                    }
-                }
-                Err(e) => return Err(e),
-            }
+        // Create a temporary session with the provided messages
+        let mut temp_session = ChatSession::new();
+        for message in messages {
+            temp_session.add_message(message);
         }
+
+        let mut response = self
+            .stream_loop(&mut temp_session, temperature, max_tokens, stop, on_event)
+            .await?;
+        response.content = self
+            .moderate(response.content, ModerationTarget::Output)
+            .await?;
+        Ok(response)
     }
 }
 
@@ -768,6 +1530,29 @@ pub struct AgentBuilder {
     max_iterations: usize,
     react_mode: bool,
     react_prompt: Option<String>,
+    project_overview: Option<String>,
+    prompt_builder: Option<SystemPromptBuilder>,
+    price_table: Option<PriceTable>,
+    budget: Budget,
+    tool_circuit_breaker: Option<CircuitBreakerConfig>,
+    llm_provider: Option<Box<dyn LLMProvider>>,
+    middleware: Vec<Box<dyn AgentMiddleware>>,
+    moderators: Vec<Box<dyn Moderator>>,
+    tool_approval: Option<Box<dyn ToolApprovalHandler>>,
+    tool_permissions: HashMap<String, ToolPermission>,
+    tool_output_limits: HashMap<String, OutputLimit>,
+    default_tool_output_limit: Option<OutputLimit>,
+    tool_output_summarizer: Option<Box<dyn OutputSummarizer>>,
+    history_limit: Option<HistoryLimit>,
+    tool_result_pruning: Option<ToolResultPruning>,
+    semantic_cache: Option<SemanticCache>,
+    tool_audit: Option<ToolAudit>,
+    observers: Vec<Arc<dyn AgentObserver>>,
+    deferred_error: Option<HeliosError>,
+    llm_profile: Option<String>,
+    #[cfg(feature = "local")]
+    local_model: Option<String>,
+    tool_protocol: ToolProtocol,
 }
 
 impl AgentBuilder {
@@ -780,6 +1565,29 @@ impl AgentBuilder {
             max_iterations: 10,
             react_mode: false,
             react_prompt: None,
+            project_overview: None,
+            prompt_builder: None,
+            price_table: None,
+            budget: Budget::default(),
+            tool_circuit_breaker: None,
+            llm_provider: None,
+            middleware: Vec::new(),
+            moderators: Vec::new(),
+            tool_approval: None,
+            tool_permissions: HashMap::new(),
+            tool_output_limits: HashMap::new(),
+            default_tool_output_limit: None,
+            tool_output_summarizer: None,
+            history_limit: None,
+            tool_result_pruning: None,
+            semantic_cache: None,
+            tool_audit: None,
+            observers: Vec::new(),
+            deferred_error: None,
+            llm_profile: None,
+            #[cfg(feature = "local")]
+            local_model: None,
+            tool_protocol: ToolProtocol::Native,
         }
     }
 
@@ -794,6 +1602,55 @@ impl AgentBuilder {
         self
     }
 
+    /// Uses the named `[profiles.<name>]` of the config instead of plain `[llm]`.
+    ///
+    /// An unknown profile is reported by `build`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let config = Config::from_file("config.toml")?;
+    /// let worker = Agent::builder("worker")
+    ///     .config(config)
+    ///     .llm_profile("fast")
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn llm_profile(mut self, name: impl Into<String>) -> Self {
+        self.llm_profile = Some(name.into());
+        self
+    }
+
+    /// Runs the agent on the named `[local_models.<name>]` of the config.
+    ///
+    /// Agents on the same model share its weights through the
+    /// [`crate::LocalModelPool`], so building another one does not load it again.
+    /// An unknown model is reported by `build`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let config = Config::from_file("config.toml")?;
+    /// let router = Agent::builder("router")
+    ///     .config(config)
+    ///     .local_model("small")
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "local")]
+    pub fn local_model(mut self, name: impl Into<String>) -> Self {
+        self.local_model = Some(name.into());
+        self
+    }
+
     pub fn system_prompt(mut self, prompt: impl Into<String>) -> Self {
         self.system_prompt = Some(prompt.into());
         self
@@ -804,6 +1661,77 @@ impl AgentBuilder {
         self.system_prompt(prompt)
     }
 
+    /// Sets the system prompt by rendering a template file with `vars`.
+    ///
+    /// See the [`prompt`](crate::prompt) module for the template syntax. A missing
+    /// file or a rendering error is reported by `build`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let agent = Agent::builder("Support")
+    ///     .config(Config::new_default())
+    ///     .system_prompt_template(
+    ///         "prompts/support.md",
+    ///         serde_json::json!({"product": "Helios", "tone": "friendly"}),
+    ///     )
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn system_prompt_template(
+        mut self,
+        path: impl AsRef<std::path::Path>,
+        vars: impl serde::Serialize,
+    ) -> Self {
+        match crate::prompt::PromptTemplate::from_file(path).and_then(|t| t.render(&vars)) {
+            Ok(prompt) => self.system_prompt = Some(prompt),
+            Err(e) => self.deferred_error = Some(e),
+        }
+        self
+    }
+
+    /// Composes the system prompt from named sections.
+    ///
+    /// Tools contribute their usage notes as the tool guidance section, see
+    /// [`Agent::set_system_prompt_builder`]. A prompt set with `system_prompt`
+    /// becomes the persona section if the builder has none, and a project
+    /// context is added as a `project` section.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config};
+    /// # use helios_engine::prompt::SystemPromptBuilder;
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let agent = Agent::builder("Support")
+    ///     .config(Config::new_default())
+    ///     .system_prompt_builder(
+    ///         SystemPromptBuilder::new()
+    ///             .section(SystemPromptBuilder::PERSONA, "You are a support assistant.")
+    ///             .section(SystemPromptBuilder::SAFETY, "Never ask for passwords."),
+    ///     )
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn system_prompt_builder(mut self, builder: SystemPromptBuilder) -> Self {
+        self.prompt_builder = Some(builder);
+        self
+    }
+
+    /// Adds every tool in a toolset to the agent.
+    ///
+    /// Toolsets named in the config file's `tools` list are added as well.
+    pub fn toolset(mut self, toolset: Toolset) -> Self {
+        self.tools.extend(toolset.into_tools());
+        self
+    }
+
     /// Adds a single tool to the agent.
     pub fn tool(mut self, tool: Box<dyn crate::tools::Tool>) -> Self {
         self.tools.push(tool);
@@ -846,6 +1774,24 @@ impl AgentBuilder {
         self
     }
 
+    /// Adds the WebAssembly tool plugins found in `dir`.
+    ///
+    /// Every `.wasm` and `.wat` file in the directory is loaded with the default
+    /// [`PluginLimits`](crate::plugins::PluginLimits); see the [`plugins`](crate::plugins)
+    /// module for the guest ABI. Errors are reported by `build`.
+    #[cfg(feature = "wasm-plugins")]
+    pub fn plugin_dir(mut self, dir: impl AsRef<std::path::Path>) -> Self {
+        match crate::plugins::load_plugins(dir) {
+            Ok(plugins) => self.tools.extend(
+                plugins
+                    .into_iter()
+                    .map(|tool| Box::new(tool) as Box<dyn crate::tools::Tool>),
+            ),
+            Err(e) => self.deferred_error = Some(e),
+        }
+        self
+    }
+
     pub fn max_iterations(mut self, max: usize) -> Self {
         self.max_iterations = max;
         self
@@ -913,54 +1859,633 @@ impl AgentBuilder {
         self
     }
 
-    pub async fn build(self) -> Result<Agent> {
-        let config = self
-            .config
-            .ok_or_else(|| HeliosError::AgentError("Config is required".to_string()))?;
-
-        let mut agent = Agent::new(self.name, config).await?;
-
-        if let Some(prompt) = self.system_prompt {
-            agent.set_system_prompt(prompt);
-        }
-
-        for tool in self.tools {
-            agent.register_tool(tool);
-        }
-
-        agent.set_max_iterations(self.max_iterations);
-        agent.react_mode = self.react_mode;
-        agent.react_prompt = self.react_prompt;
-
-        Ok(agent)
+    /// Sets how tools are offered to the model.
+    ///
+    /// Use [`ToolProtocol::React`] for models that do not support function calling,
+    /// such as many local GGUF models. The tools are then described in the system
+    /// prompt and the model calls them with `Action:` / `Action Input:` lines.
+    /// Unlike [`react`](Self::react), this changes how tools are called, not whether
+    /// the agent plans first; the two can be combined.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, CalculatorTool, Config, ToolProtocol};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// # let config = Config::new_default();
+    /// let agent = Agent::builder("LocalAgent")
+    ///     .config(config)
+    ///     .tool(Box::new(CalculatorTool))
+    ///     .tool_protocol(ToolProtocol::React)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tool_protocol(mut self, protocol: ToolProtocol) -> Self {
+        self.tool_protocol = protocol;
+        self
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::Config;
-    use crate::tools::{CalculatorTool, Tool, ToolParameter, ToolResult};
-    use serde_json::Value;
-    use std::collections::HashMap;
 
-    /// Tests that an agent can be created using the builder.
-    #[tokio::test]
-    async fn test_agent_creation_via_builder() {
-        let config = Config::new_default();
-        let agent = Agent::builder("test_agent").config(config).build().await;
-        assert!(agent.is_ok());
+    /// Injects a scanned project overview into the agent's system prompt.
+    ///
+    /// The overview is appended after any system prompt set on the builder, giving
+    /// coding agents a map of the repository before they start using file tools.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, ProjectContext};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// # let config = Config::new_default();
+    /// let project = ProjectContext::builder(".").build().await?;
+    /// let agent = Agent::builder("CodeAssistant")
+    ///     .config(config)
+    ///     .system_prompt("You are a coding assistant.")
+    ///     .project_context(&project)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn project_context(mut self, context: &crate::project_context::ProjectContext) -> Self {
+        self.project_overview = Some(context.overview());
+        self
     }
 
-    /// Tests the namespacing of agent memory.
-    #[tokio::test]
-    async fn test_agent_memory_namespacing_set_get_remove() {
-        let config = Config::new_default();
-        let mut agent = Agent::builder("test_agent")
-            .config(config)
-            .build()
-            .await
-            .unwrap();
+    /// Sets the price table used to compute the agent's dollar cost.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, PriceTable};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// # let config = Config::new_default();
+    /// let mut agent = Agent::builder("MyAgent")
+    ///     .config(config)
+    ///     .price_table(PriceTable::new().with_price("gpt-3.5-turbo", 0.5, 1.5))
+    ///     .build()
+    ///     .await?;
+    /// agent.chat("Hello!").await?;
+    /// println!("{:?} (${:.6})", agent.usage(), agent.cost());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn price_table(mut self, prices: PriceTable) -> Self {
+        self.price_table = Some(prices);
+        self
+    }
+
+    /// Caps the dollar cost of a session.
+    ///
+    /// Costs come from the price table, so set one with [`AgentBuilder::price_table`]
+    /// for this to take effect. Once a session has cost `usd`, the agent stops
+    /// calling the LLM and fails with `HeliosError::BudgetExceeded`, even in the
+    /// middle of a tool loop. [`Agent::clear_history`] starts a new session.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, HeliosError, PriceTable};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let mut agent = Agent::builder("MyAgent")
+    ///     .config(Config::new_default())
+    ///     .price_table(PriceTable::new().with_price("gpt-4o", 2.5, 10.0))
+    ///     .max_cost(0.50)
+    ///     .max_tokens_total(200_000)
+    ///     .build()
+    ///     .await?;
+    /// match agent.chat("Research this topic in depth").await {
+    ///     Err(HeliosError::BudgetExceeded(reason)) => println!("Stopped: {}", reason),
+    ///     other => println!("{}", other?),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn max_cost(mut self, usd: f64) -> Self {
+        self.budget.max_cost = Some(usd);
+        self
+    }
+
+    /// Caps the tokens a session may use, prompt and completion combined.
+    ///
+    /// Works like [`AgentBuilder::max_cost`], without needing prices.
+    pub fn max_tokens_total(mut self, tokens: u64) -> Self {
+        self.budget.max_tokens = Some(tokens);
+        self
+    }
+
+    /// Guards each of the agent's tools with a circuit breaker.
+    ///
+    /// A tool that keeps failing is short-circuited for the cooldown period, and the
+    /// model is told so instead of waiting on the tool again every iteration.
+    pub fn tool_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.tool_circuit_breaker = Some(config);
+        self
+    }
+
+    /// Sends the agent's LLM calls to a custom provider instead of the configured one.
+    ///
+    /// This is mainly used with `MockLLMProvider` to test agents without an API key.
+    /// A config is optional; when one is set, its `[llm]` section still supplies the
+    /// model name and default generation parameters.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, MockLLMProvider};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let mut agent = Agent::builder("tester")
+    ///     .llm_provider(MockLLMProvider::new().with_response("Hello!"))
+    ///     .build()
+    ///     .await?;
+    /// assert_eq!(agent.chat("Hi").await?, "Hello!");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn llm_provider(mut self, provider: impl LLMProvider + 'static) -> Self {
+        self.llm_provider = Some(Box::new(provider));
+        self
+    }
+
+    /// Adds a middleware that runs around every LLM call and tool call.
+    ///
+    /// Middleware run in the order they are added, so a redaction step added first
+    /// sees the messages before a logging step added after it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, AgentMiddleware, ChatMessage, Config};
+    /// # use async_trait::async_trait;
+    /// struct RedactEmails;
+    ///
+    /// #[async_trait]
+    /// impl AgentMiddleware for RedactEmails {
+    ///     async fn before_llm_call(&self, messages: &mut Vec<ChatMessage>) -> helios_engine::Result<()> {
+    ///         for message in messages.iter_mut() {
+    ///             message.content = message.content.replace("alice@example.com", "[email]");
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let agent = Agent::builder("assistant")
+    ///     .config(Config::new_default())
+    ///     .middleware(RedactEmails)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn middleware(mut self, middleware: impl AgentMiddleware + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Adds a moderator for the user's input and the agent's final reply.
+    ///
+    /// A moderator can let text pass, redact it, append a note, or block it with
+    /// [`HeliosError::Blocked`](crate::HeliosError::Blocked). Moderators run in the
+    /// order they are added.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, KeywordModerator, ModerationAction, OpenAIModerator};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let agent = Agent::builder("assistant")
+    ///     .config(Config::new_default())
+    ///     .moderator(KeywordModerator::new().words(["password"], ModerationAction::Block)?)
+    ///     .moderator(OpenAIModerator::new("https://api.openai.com/v1", "sk-..."))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn moderator(mut self, moderator: impl Moderator + 'static) -> Self {
+        self.moderators.push(Box::new(moderator));
+        self
+    }
+
+    /// Sets the handler asked to approve calls to destructive tools such as
+    /// `shell_command` and `file_write`.
+    ///
+    /// A refused call is reported to the LLM as a failed tool result.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, ConsoleApprovalHandler, FileWriteTool};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let agent = Agent::builder("assistant")
+    ///     .config(Config::new_default())
+    ///     .tool(Box::new(FileWriteTool))
+    ///     .tool_approval(ConsoleApprovalHandler)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tool_approval(mut self, handler: impl ToolApprovalHandler + 'static) -> Self {
+        self.tool_approval = Some(Box::new(handler));
+        self
+    }
+
+    /// Overrides the permission level of the tool called `name`.
+    ///
+    /// Marking a tool [`ToolPermission::Destructive`] makes it go through the
+    /// approval handler set with [`AgentBuilder::tool_approval`].
+    pub fn tool_permission(mut self, name: impl Into<String>, permission: ToolPermission) -> Self {
+        self.tool_permissions.insert(name.into(), permission);
+        self
+    }
+
+    /// Limits how much output the tool called `name` returns to the model.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, FileReadTool, HttpRequestTool, OutputLimit};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let agent = Agent::builder("assistant")
+    ///     .config(Config::new_default())
+    ///     .tool(Box::new(FileReadTool))
+    ///     .tool(Box::new(HttpRequestTool))
+    ///     .default_tool_output_limit(OutputLimit::head_tail(8_000))
+    ///     .tool_output_limit("file_read", OutputLimit::head(20_000))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tool_output_limit(mut self, name: impl Into<String>, limit: OutputLimit) -> Self {
+        self.tool_output_limits.insert(name.into(), limit);
+        self
+    }
+
+    /// Limits the output of every tool without a limit of its own.
+    pub fn default_tool_output_limit(mut self, limit: OutputLimit) -> Self {
+        self.default_tool_output_limit = Some(limit);
+        self
+    }
+
+    /// Sets the summarizer for tools whose limit uses [`TruncationStrategy::Summarize`](crate::TruncationStrategy::Summarize).
+    ///
+    /// An [`LLMClient`](crate::LLMClient) for a small, cheap model works well.
+    pub fn tool_output_summarizer(mut self, summarizer: impl OutputSummarizer + 'static) -> Self {
+        self.tool_output_summarizer = Some(Box::new(summarizer));
+        self
+    }
+
+    /// Bounds the conversation history so long-running agents don't grow without limit.
+    ///
+    /// The oldest messages are dropped at the start of each turn; the system prompt
+    /// is always kept.
+    pub fn history_limit(mut self, limit: HistoryLimit) -> Self {
+        self.history_limit = Some(limit);
+        self
+    }
+
+    /// Replaces bulky tool results the model has already answered with placeholders
+    /// such as `[tool output elided, 14.2 KiB]` at the start of each turn.
+    ///
+    /// The tool exchange stays in the history, so the conversation remains valid.
+    /// The original outputs are kept in the [tool audit log](Agent::tool_audit),
+    /// up to its [`max_output_chars`](ToolAudit::max_output_chars).
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, FileReadTool, ToolAudit, ToolResultPruning};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let mut agent = Agent::builder("reader")
+    ///     .config(Config::new_default())
+    ///     .tool(Box::new(FileReadTool))
+    ///     .prune_tool_results(ToolResultPruning::default())
+    ///     .tool_audit(ToolAudit::new().max_output_chars(100_000))
+    ///     .build()
+    ///     .await?;
+    /// agent.chat("Summarize Cargo.lock").await?;
+    /// // From the next turn on, the file contents are elided from the history,
+    /// // but the audit log still has them.
+    /// let call = &agent.tool_audit().entries()[0];
+    /// let original = agent.tool_audit().find(&call.call_id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prune_tool_results(mut self, pruning: ToolResultPruning) -> Self {
+        self.tool_result_pruning = Some(pruning);
+        self
+    }
+
+    /// Answers questions similar to ones the agent already answered from `cache`.
+    ///
+    /// Replies the model gives are cached as the answers to the messages that
+    /// prompted them. Cached answers were moderated when first given and are not
+    /// moderated again, and no middleware, tools or observers run for them.
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, InMemoryVectorStore, OpenAIEmbeddings, SemanticCache};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let cache = SemanticCache::new(
+    ///     Box::new(OpenAIEmbeddings::new("https://api.openai.com/v1/embeddings", "sk-...")),
+    ///     Box::new(InMemoryVectorStore::new()),
+    /// )
+    /// .with_threshold(0.92);
+    /// let mut agent = Agent::builder("support")
+    ///     .config(Config::new_default())
+    ///     .semantic_cache(cache)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn semantic_cache(mut self, cache: SemanticCache) -> Self {
+        self.semantic_cache = Some(cache);
+        self
+    }
+
+    /// Records the agent's tool calls in `audit` instead of a new in-memory log.
+    ///
+    /// Use [`ToolAudit::with_jsonl`] to also keep every call in a file, or pass a
+    /// clone of another agent's log to share one log between agents.
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, ToolAudit};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let agent = Agent::builder("ops")
+    ///     .config(Config::new_default())
+    ///     .tool_audit(ToolAudit::with_jsonl("audit/tools.jsonl")?)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tool_audit(mut self, audit: ToolAudit) -> Self {
+        self.tool_audit = Some(audit);
+        self
+    }
+
+    /// Adds an observer that receives the agent's events.
+    ///
+    /// Agents print nothing themselves; add [`crate::events::ConsoleObserver`] to
+    /// see streamed text and tool activity in the terminal.
+    pub fn observer(mut self, observer: impl AgentObserver + 'static) -> Self {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    pub async fn build(mut self) -> Result<Agent> {
+        if let Some(e) = self.deferred_error {
+            return Err(e);
+        }
+        let mut config = self.config;
+        if let Some(name) = &self.llm_profile {
+            let base = config.get_or_insert_with(Config::new_default);
+            base.llm = base.profile(name)?;
+        }
+        #[cfg(feature = "local")]
+        if let Some(name) = &self.local_model {
+            let base = config.get_or_insert_with(Config::new_default);
+            base.local = Some(base.local_model(name)?);
+        }
+        let config_tools = match &config {
+            Some(config) => toolset::resolve_tool_names(&config.tools)?,
+            None => Vec::new(),
+        };
+        let mut agent = match self.llm_provider {
+            Some(provider) => {
+                let config = config.unwrap_or_else(Config::new_default);
+                Agent::with_client(
+                    self.name,
+                    LLMClient::from_boxed_provider(provider, config.llm),
+                )
+            }
+            None => {
+                let config = config
+                    .ok_or_else(|| HeliosError::AgentError("Config is required".to_string()))?;
+                Agent::new(self.name, config).await?
+            }
+        };
+
+        let prompt_builder = self.prompt_builder.map(|mut builder| {
+            if let Some(prompt) = self.system_prompt.take() {
+                if builder.get(SystemPromptBuilder::PERSONA).is_none() {
+                    let first = builder.names().first().map(|name| name.to_string());
+                    builder = builder.section_before(
+                        first.as_deref().unwrap_or_default(),
+                        SystemPromptBuilder::PERSONA,
+                        prompt,
+                    );
+                }
+            }
+            if let Some(overview) = self.project_overview.take() {
+                if builder.get("project").is_none() {
+                    builder = builder.section("project", overview);
+                }
+            }
+            builder
+        });
+        let system_prompt = match (self.system_prompt, self.project_overview) {
+            (Some(prompt), Some(overview)) => Some(format!("{}\n\n{}", prompt, overview)),
+            (prompt, overview) => prompt.or(overview),
+        };
+        if let Some(prompt) = system_prompt {
+            agent.set_system_prompt(prompt);
+        }
+
+        if let Some(config) = self.tool_circuit_breaker {
+            agent.tool_registry.set_circuit_breaker(config);
+        }
+        if let Some(handler) = self.tool_approval {
+            agent.tool_registry.set_boxed_approval_handler(handler);
+        }
+        for tool in config_tools
+            .iter()
+            .filter_map(|name| crate::tools::builtin_tool(name, None))
+            .chain(self.tools)
+        {
+            agent.register_tool(tool);
+        }
+        for (name, permission) in self.tool_permissions {
+            agent.tool_registry.set_permission(name, permission);
+        }
+        for (name, limit) in self.tool_output_limits {
+            agent.tool_registry.set_output_limit(name, limit);
+        }
+        if let Some(limit) = self.default_tool_output_limit {
+            agent.tool_registry.set_default_output_limit(limit);
+        }
+        if let Some(summarizer) = self.tool_output_summarizer {
+            agent.tool_registry.set_boxed_output_summarizer(summarizer);
+        }
+        if let Some(builder) = prompt_builder {
+            agent.set_system_prompt_builder(builder);
+        }
+
+        agent.set_max_iterations(self.max_iterations);
+        agent.react_mode = self.react_mode;
+        agent.react_prompt = self.react_prompt;
+        agent.budget = self.budget;
+        if let Some(prices) = self.price_table {
+            agent.price_table = prices;
+        }
+        agent.middleware = self.middleware;
+        agent.moderators = self.moderators;
+        agent.history_limit = self.history_limit;
+        agent.tool_result_pruning = self.tool_result_pruning;
+        agent.semantic_cache = self.semantic_cache;
+        if let Some(audit) = self.tool_audit {
+            agent.tool_audit = audit;
+        }
+        agent.observers = self.observers;
+        agent.tool_protocol = self.tool_protocol;
+
+        Ok(agent)
+    }
+}
+
+/// Exposes an agent as a tool that other agents can delegate tasks to.
+///
+/// The tool takes a `task` string, runs it through the wrapped agent, and returns
+/// the agent's answer. This is the lightweight alternative to a
+/// [`ForestOfAgents`](crate::forest::ForestOfAgents) when one agent just needs to
+/// hand a sub-task to a specialist.
+///
+/// By default the sub-agent starts every task with an empty history, so
+/// delegated tasks don't leak into each other; see [`AgentAsTool::keep_history`].
+/// Concurrent calls are run one at a time.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use helios_engine::{Agent, AgentAsTool, Config};
+/// # async fn example() -> helios_engine::Result<()> {
+/// # let config = Config::new_default();
+/// let researcher = Agent::builder("Researcher")
+///     .config(config.clone())
+///     .system_prompt("You research topics and report facts with sources.")
+///     .build()
+///     .await?;
+///
+/// let mut lead = Agent::builder("Lead")
+///     .config(config)
+///     .tool(Box::new(AgentAsTool::new(
+///         researcher,
+///         "research",
+///         "Ask the research specialist to look something up",
+///     )))
+///     .build()
+///     .await?;
+/// let answer = lead.chat("Summarize the history of the Rust language").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AgentAsTool {
+    agent: Arc<tokio::sync::Mutex<Agent>>,
+    name: String,
+    description: String,
+    keep_history: bool,
+}
+
+impl AgentAsTool {
+    /// Wraps `agent` as a tool called `name`.
+    ///
+    /// The description tells the calling agent when to delegate to this one.
+    pub fn new(agent: Agent, name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            agent: Arc::new(tokio::sync::Mutex::new(agent)),
+            name: name.into(),
+            description: description.into(),
+            keep_history: false,
+        }
+    }
+
+    /// Keeps the sub-agent's conversation between tasks, so later tasks can refer
+    /// to earlier ones.
+    pub fn keep_history(mut self, keep: bool) -> Self {
+        self.keep_history = keep;
+        self
+    }
+
+    /// Returns the wrapped agent, e.g. to inspect its usage or history.
+    pub fn agent(&self) -> Arc<tokio::sync::Mutex<Agent>> {
+        Arc::clone(&self.agent)
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::tools::Tool for AgentAsTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> HashMap<String, crate::tools::ToolParameter> {
+        let mut params = HashMap::new();
+        params.insert(
+            "task".to_string(),
+            crate::tools::ToolParameter {
+                param_type: "string".to_string(),
+                description: "The task to hand over, with all the context needed to do it"
+                    .to_string(),
+                required: Some(true),
+            },
+        );
+        params
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let task = args
+            .get("task")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| HeliosError::ToolError("Missing 'task' parameter".to_string()))?;
+
+        let mut agent = self.agent.lock().await;
+        if !self.keep_history {
+            agent.chat_session.clear();
+        }
+        match agent.chat(task).await {
+            Ok(answer) => Ok(ToolResult::success(answer)),
+            // The calling agent sees the failure and can try another way.
+            Err(e) => Ok(ToolResult::error(format!(
+                "Agent '{}' failed: {}",
+                agent.name(),
+                e
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::Role;
+    use crate::config::Config;
+    use crate::tools::{CalculatorTool, Tool, ToolParameter, ToolResult};
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    /// Tests that an agent can be created using the builder.
+    #[tokio::test]
+    async fn test_agent_creation_via_builder() {
+        let config = Config::new_default();
+        let agent = Agent::builder("test_agent").config(config).build().await;
+        assert!(agent.is_ok());
+    }
+
+    /// Tests the namespacing of agent memory.
+    #[tokio::test]
+    async fn test_agent_memory_namespacing_set_get_remove() {
+        let config = Config::new_default();
+        let mut agent = Agent::builder("test_agent")
+            .config(config)
+            .build()
+            .await
+            .unwrap();
 
         // Set and get namespaced memory
         agent.set_memory("working_directory", "/tmp");
@@ -1102,9 +2627,44 @@ mod tests {
         );
     }
 
-    /// Tests clearing the chat history of an agent.
+    /// Tests adding toolsets from the builder and the config's `tools` list.
     #[tokio::test]
-    async fn test_agent_clear_history() {
+    async fn test_agent_toolsets() {
+        let mut config = Config::new_default();
+        config.tools = vec!["web".to_string(), "calculator".to_string()];
+        let agent = Agent::builder("test_agent")
+            .config(config.clone())
+            .toolset(Toolset::data())
+            .build()
+            .await
+            .unwrap();
+        let mut tools = agent.tool_registry().list_tools();
+        tools.sort();
+        assert_eq!(
+            tools,
+            [
+                "calculator",
+                "http_request",
+                "json_parser",
+                "text_processor",
+                "timestamp",
+                "web_scraper"
+            ]
+        );
+
+        config.tools = vec!["kitchen".to_string()];
+        let error = Agent::builder("test_agent")
+            .config(config)
+            .build()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error, HeliosError::ConfigError(_)));
+    }
+
+    /// Tests clearing the chat history of an agent.
+    #[tokio::test]
+    async fn test_agent_clear_history() {
         let config = Config::new_default();
         let mut agent = Agent::builder("test_agent")
             .config(config)
@@ -1121,6 +2681,867 @@ mod tests {
         assert!(agent.chat_session().messages.is_empty());
     }
 
+    /// Tests that session usage and cost are measured from the last history clear.
+    #[tokio::test]
+    async fn test_agent_session_usage_and_cost() {
+        let config = Config::new_default();
+        let model = config.llm.model_name.clone();
+        let mut agent = Agent::builder("test_agent")
+            .config(config)
+            .price_table(crate::usage::PriceTable::new().with_price(model.clone(), 1.0, 2.0))
+            .build()
+            .await
+            .unwrap();
+
+        let tracker = agent.llm_client.usage_tracker().clone();
+        tracker.record(&model, &crate::llm::Usage::new(1_000_000, 1_000_000));
+        assert_eq!(agent.usage().total_tokens, 2_000_000);
+        assert!((agent.session_cost() - 3.0).abs() < 1e-9);
+
+        agent.clear_history();
+        assert_eq!(agent.session_usage(), TokenUsage::default());
+
+        tracker.record(&model, &crate::llm::Usage::new(500_000, 0));
+        assert_eq!(agent.session_usage().prompt_tokens, 500_000);
+        assert!((agent.session_cost() - 0.5).abs() < 1e-9);
+        assert!((agent.cost() - 3.5).abs() < 1e-9);
+    }
+
+    /// Tests that a deadline cancels a slow turn and restores the history.
+    #[tokio::test]
+    async fn test_agent_chat_with_deadline() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_tool_call("slow", serde_json::json!({}))
+            .with_response("quick");
+        let mut agent = Agent::builder("timed")
+            .llm_provider(mock.clone())
+            .tool(
+                crate::ToolBuilder::new("slow")
+                    .description("Takes a long time")
+                    .function(|_| async {
+                        tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+                        Ok(ToolResult::success("done"))
+                    })
+                    .build(),
+            )
+            .build()
+            .await
+            .unwrap();
+        agent.chat_session_mut().add_user_message("earlier");
+
+        let started = std::time::Instant::now();
+        let err = agent
+            .chat_with_deadline("Run the slow tool", std::time::Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HeliosError::DeadlineExceeded(_)));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert_eq!(mock.requests().len(), 1);
+        let history = &agent.chat_session().messages;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].content, "earlier");
+
+        let answer = agent
+            .chat_with_deadline("Hi", std::time::Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(answer, "quick");
+    }
+
+    /// Tests that aborting a tool call leaves no unanswered tool calls in the history.
+    #[tokio::test]
+    async fn test_agent_abort_during_tool_call_restores_history() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_tool_call("slow", serde_json::json!({}))
+            .with_response("recovered");
+        let mut agent = Agent::builder("aborted")
+            .llm_provider(mock.clone())
+            .tool(
+                crate::ToolBuilder::new("slow")
+                    .description("Takes a long time")
+                    .function(|_| async {
+                        tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+                        Ok(ToolResult::success("done"))
+                    })
+                    .build(),
+            )
+            .build()
+            .await
+            .unwrap();
+
+        let handle = agent.abort_handle();
+        let (result, _) = tokio::join!(agent.chat("Run the slow tool"), async {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            handle.abort();
+        });
+        assert!(matches!(result, Err(HeliosError::Aborted(_))));
+        assert!(agent
+            .chat_session()
+            .messages
+            .iter()
+            .all(|message| message.tool_calls.is_none()));
+
+        agent.reset_abort();
+        assert_eq!(agent.chat("Try again").await.unwrap(), "recovered");
+        let last_request = mock.requests().pop().unwrap();
+        assert!(last_request
+            .messages
+            .iter()
+            .all(|message| message.tool_calls.is_none()));
+    }
+
+    /// Tests that a spent budget stops the agent until a new session starts.
+    #[tokio::test]
+    async fn test_agent_budget() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_tool_call("spend", serde_json::json!({}))
+            .with_response("unreachable");
+        let mut agent = Agent::builder("frugal")
+            .llm_provider(mock.clone())
+            .max_tokens_total(1_000)
+            .build()
+            .await
+            .unwrap();
+        let tracker = agent.llm_client.usage_tracker().clone();
+        tracker.record("mock", &crate::llm::Usage::new(600, 400));
+
+        let err = agent.chat("Hello").await.unwrap_err();
+        assert!(matches!(err, HeliosError::BudgetExceeded(_)));
+        assert!(mock.requests().is_empty());
+
+        // A new session gets a fresh budget, which the tool loop then runs out of.
+        agent.clear_history();
+        let spender = tracker.clone();
+        agent.register_tool(
+            crate::ToolBuilder::new("spend")
+                .description("Spends tokens")
+                .sync_function(move |_| {
+                    spender.record("mock", &crate::llm::Usage::new(1_000, 0));
+                    Ok(ToolResult::success("spent"))
+                })
+                .build(),
+        );
+        let err = agent.chat("Spend").await.unwrap_err();
+        assert!(matches!(err, HeliosError::BudgetExceeded(_)));
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    /// A middleware that redacts, blocks and annotates, for testing the hooks.
+    struct GuardMiddleware;
+
+    #[async_trait::async_trait]
+    impl AgentMiddleware for GuardMiddleware {
+        fn name(&self) -> &str {
+            "guard"
+        }
+
+        async fn before_llm_call(&self, messages: &mut Vec<ChatMessage>) -> crate::Result<()> {
+            for message in messages.iter_mut() {
+                message.content = message.content.replace("4111-1111", "[card]");
+            }
+            Ok(())
+        }
+
+        async fn after_llm_call(&self, response: &mut ChatMessage) -> crate::Result<()> {
+            response.content = response.content.to_uppercase();
+            Ok(())
+        }
+
+        async fn before_tool_call(&self, tool_name: &str, _: &mut Value) -> crate::Result<()> {
+            if tool_name == "calculator" {
+                return Err(HeliosError::ToolError("calculator is not allowed".into()));
+            }
+            Ok(())
+        }
+
+        async fn after_tool_call(&self, _: &str, result: &mut ToolResult) -> crate::Result<()> {
+            result.output.push_str(" (checked)");
+            Ok(())
+        }
+    }
+
+    /// Tests that a history limit prunes the oldest messages at the start of a turn.
+    #[tokio::test]
+    async fn test_agent_history_limit() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_response("one")
+            .with_response("two")
+            .with_response("three");
+        let mut agent = Agent::builder("bounded")
+            .system_prompt("Be brief.")
+            .llm_provider(mock.clone())
+            .history_limit(HistoryLimit::Messages(3))
+            .build()
+            .await
+            .unwrap();
+
+        for message in ["a", "b", "c"] {
+            agent.chat(message).await.unwrap();
+        }
+        assert_eq!(agent.memory_usage().entries, 4);
+        assert_eq!(agent.chat_session().messages[0].content, "b");
+        // The last request carried the system prompt and the three newest messages.
+        assert_eq!(mock.requests()[2].messages.len(), 4);
+    }
+
+    /// Tests that answered tool results are elided on the next turn and stay in the audit log.
+    #[tokio::test]
+    async fn test_agent_prune_tool_results() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_tool_call("echo", serde_json::json!({"message": "z".repeat(1500)}))
+            .with_response("It said a lot of z.")
+            .with_response("You're welcome.");
+        let mut agent = Agent::builder("pruner")
+            .llm_provider(mock.clone())
+            .tool(Box::new(crate::tools::EchoTool))
+            .prune_tool_results(ToolResultPruning::default())
+            .build()
+            .await
+            .unwrap();
+
+        agent.chat("Echo some z.").await.unwrap();
+        assert_eq!(agent.chat_session().messages[2].content.len(), 1506);
+        agent.chat("Thanks").await.unwrap();
+
+        let requests = mock.requests();
+        let tool_message = requests[2]
+            .messages
+            .iter()
+            .find(|message| message.role == crate::chat::Role::Tool)
+            .unwrap();
+        assert_eq!(tool_message.content, "[tool output elided, 1.5 KiB]");
+        let call_id = tool_message.tool_call_id.as_deref().unwrap();
+        let original = agent.tool_audit().find(call_id).unwrap();
+        assert_eq!(original.output.len(), 1506);
+        assert!(!original.output_truncated);
+    }
+
+    /// Tests that the system prompt can be rendered from a template file.
+    #[tokio::test]
+    async fn test_agent_system_prompt_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("system.md");
+        std::fs::write(
+            &path,
+            "You support {{product}}.{{#if strict}} Never guess.{{/if}}",
+        )
+        .unwrap();
+
+        let mock = crate::mock::MockLLMProvider::new().with_response("ok");
+        let mut agent = Agent::builder("support")
+            .system_prompt_template(
+                &path,
+                serde_json::json!({"product": "Helios", "strict": true}),
+            )
+            .llm_provider(mock.clone())
+            .build()
+            .await
+            .unwrap();
+        agent.chat("hi").await.unwrap();
+        assert_eq!(
+            mock.requests()[0].messages[0].content,
+            "You support Helios. Never guess."
+        );
+
+        let result = Agent::builder("support")
+            .system_prompt_template(&path, serde_json::json!({}))
+            .llm_provider(crate::mock::MockLLMProvider::new())
+            .build()
+            .await;
+        assert!(result.is_err());
+    }
+
+    /// Tests composing the system prompt from sections and the tools' usage notes.
+    #[tokio::test]
+    async fn test_agent_system_prompt_builder() {
+        let note_tool = |name: &str, notes: &str| {
+            crate::ToolBuilder::new(name)
+                .description("Looks things up")
+                .usage_notes(notes)
+                .sync_function(|_| Ok(ToolResult::success("found")))
+                .build()
+        };
+        let mut agent = Agent::builder("support")
+            .llm_provider(crate::mock::MockLLMProvider::new())
+            .system_prompt("You help customers.")
+            .system_prompt_builder(
+                SystemPromptBuilder::new().section(SystemPromptBuilder::SAFETY, "Be honest."),
+            )
+            .tool(note_tool(
+                "orders",
+                "Look up an order before answering about it.",
+            ))
+            .tool(Box::new(CalculatorTool))
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(
+            agent.chat_session().system_prompt.as_deref(),
+            Some(
+                "You help customers.\n\nNotes on using your tools:\n\
+                 - orders: Look up an order before answering about it.\n\nBe honest."
+            )
+        );
+
+        agent.register_tool(note_tool("faq", "Prefer the FAQ for policy questions."));
+        let prompt = agent.chat_session().system_prompt.clone().unwrap();
+        assert!(prompt.contains("- faq: Prefer the FAQ for policy questions.\n- orders:"));
+
+        // An explicit tool guidance section overrides the notes.
+        agent.set_system_prompt_builder(
+            agent
+                .system_prompt_builder()
+                .unwrap()
+                .clone()
+                .section(SystemPromptBuilder::TOOL_GUIDANCE, "Use tools sparingly."),
+        );
+        assert!(agent
+            .chat_session()
+            .system_prompt
+            .as_deref()
+            .unwrap()
+            .ends_with("Be honest.\n\nUse tools sparingly."));
+
+        agent.set_system_prompt("Plain.");
+        assert!(agent.system_prompt_builder().is_none());
+        agent.register_tool(note_tool("docs", "Search the docs."));
+        assert_eq!(
+            agent.chat_session().system_prompt.as_deref(),
+            Some("Plain.")
+        );
+    }
+
+    /// Tests that an agent can use a named LLM profile from its config.
+    #[tokio::test]
+    async fn test_agent_llm_profile() {
+        let config = Config::new_default().with_profile(
+            "fast",
+            crate::config::LLMProfile {
+                model_name: Some("gpt-4o-mini".to_string()),
+                ..Default::default()
+            },
+        );
+        let agent = Agent::builder("worker")
+            .config(config.clone())
+            .llm_profile("fast")
+            .llm_provider(crate::mock::MockLLMProvider::new())
+            .build()
+            .await
+            .unwrap();
+        #[allow(irrefutable_let_patterns)]
+        let LLMProviderType::Remote(llm) = agent.llm_client.provider_type() else {
+            panic!("expected a remote provider");
+        };
+        assert_eq!(llm.model_name, "gpt-4o-mini");
+
+        let result = Agent::builder("worker")
+            .config(config)
+            .llm_profile("smart")
+            .llm_provider(crate::mock::MockLLMProvider::new())
+            .build()
+            .await;
+        assert!(result.is_err());
+    }
+
+    /// Tests that an agent can delegate a task to another agent through a tool.
+    #[tokio::test]
+    async fn test_agent_as_tool() {
+        let worker_mock = crate::mock::MockLLMProvider::new()
+            .with_response("Paris")
+            .with_response("Rome");
+        let worker = Agent::builder("worker")
+            .system_prompt("Answer geography questions.")
+            .llm_provider(worker_mock.clone())
+            .build()
+            .await
+            .unwrap();
+        let delegate = AgentAsTool::new(worker, "geography", "Answers geography questions");
+        let handle = delegate.agent();
+
+        let lead_mock = crate::mock::MockLLMProvider::new()
+            .with_tool_call(
+                "geography",
+                serde_json::json!({"task": "Capital of France?"}),
+            )
+            .with_response("It is Paris.")
+            .with_tool_call(
+                "geography",
+                serde_json::json!({"task": "Capital of Italy?"}),
+            )
+            .with_response("It is Rome.");
+        let mut lead = Agent::builder("lead")
+            .llm_provider(lead_mock.clone())
+            .tool(Box::new(delegate))
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(lead.chat("France?").await.unwrap(), "It is Paris.");
+        assert_eq!(lead.chat("Italy?").await.unwrap(), "It is Rome.");
+        let lead_requests = lead_mock.requests();
+        assert_eq!(lead_requests[1].messages.last().unwrap().content, "Paris");
+        // Each delegated task started from a fresh history.
+        let worker_requests = worker_mock.requests();
+        let second = &worker_requests[1].messages;
+        assert_eq!(second.len(), 2);
+        assert_eq!(second[1].content, "Capital of Italy?");
+        assert_eq!(handle.lock().await.chat_session().messages.len(), 2);
+    }
+
+    /// Tests that tool calls are recorded in the agent's audit log, including
+    /// calls to unknown tools.
+    #[tokio::test]
+    async fn test_agent_tool_audit() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_tool_call("calculator", serde_json::json!({"expression": "2 + 2"}))
+            .with_tool_call("teleport", serde_json::json!({"to": "Mars"}))
+            .with_response("Done.");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tools.jsonl");
+        let mut agent = Agent::builder("auditor")
+            .llm_provider(mock)
+            .tool(Box::new(CalculatorTool))
+            .tool_audit(ToolAudit::with_jsonl(&path).unwrap())
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(agent.chat("Go.").await.unwrap(), "Done.");
+
+        let entries = agent.tool_audit().entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            (entries[0].agent.as_str(), entries[0].call_id.as_str()),
+            ("auditor", "call_1")
+        );
+        assert_eq!(entries[0].arguments["expression"], "2 + 2");
+        assert!(entries[0].success && entries[0].output.contains('4'));
+        assert_eq!(entries[1].tool, "teleport");
+        assert!(!entries[1].success);
+
+        let stats = agent.tool_audit().stats();
+        assert_eq!(
+            (stats["calculator"].calls, stats["calculator"].failures),
+            (1, 0)
+        );
+        assert_eq!(stats["teleport"].failures, 1);
+        assert_eq!(ToolAudit::load(&path).unwrap(), entries);
+    }
+
+    /// Tests that streaming reports tool execution and records the exchange.
+    #[tokio::test]
+    async fn test_agent_chat_stream_reports_tool_calls() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_tool_call("calculator", serde_json::json!({"expression": "2 + 2"}))
+            .with_response("It is 4.");
+        let mut agent = Agent::builder("streamer")
+            .llm_provider(mock)
+            .tool(Box::new(CalculatorTool))
+            .build()
+            .await
+            .unwrap();
+
+        let mut events = Vec::new();
+        let answer = agent
+            .chat_stream("What is 2 + 2?", |event| events.push(event))
+            .await
+            .unwrap();
+        assert_eq!(answer, "It is 4.");
+
+        assert!(matches!(
+            &events[0],
+            StreamEvent::ToolCallDelta { name: Some(name), .. } if name == "calculator"
+        ));
+        assert!(
+            matches!(&events[1], StreamEvent::ToolStarted { name, .. } if name == "calculator")
+        );
+        assert!(matches!(
+            &events[2],
+            StreamEvent::ToolFinished { success: true, output, .. } if output.contains('4')
+        ));
+        assert_eq!(events[3], StreamEvent::Content("It is 4.".to_string()));
+        assert_eq!(events.len(), 4);
+
+        let roles: Vec<_> = agent
+            .chat_session()
+            .messages
+            .iter()
+            .map(|m| m.role.clone())
+            .collect();
+        assert_eq!(
+            roles,
+            [Role::User, Role::Assistant, Role::Tool, Role::Assistant]
+        );
+    }
+
+    /// Tests calling tools through the textual ReAct protocol.
+    #[tokio::test]
+    async fn test_agent_react_tool_protocol() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_response(
+                "Thought: I should add.\nAction: calculator\nAction Input: {\"expression\": \"2 + 2\"}",
+            )
+            .with_response("Thought: I can answer now.\nFinal Answer: It is 4.");
+        let mut agent = Agent::builder("local")
+            .llm_provider(mock.clone())
+            .system_prompt("Be brief.")
+            .tool(Box::new(CalculatorTool))
+            .tool_protocol(ToolProtocol::React)
+            .build()
+            .await
+            .unwrap();
+
+        let answer = agent.chat("What is 2 + 2?").await.unwrap();
+        assert_eq!(answer, "It is 4.");
+
+        let requests = mock.requests();
+        assert!(requests[0].tools.is_none());
+        assert!(requests[0]
+            .grammar
+            .as_ref()
+            .is_some_and(|grammar| grammar.as_gbnf().contains("calculator-arguments")));
+        assert!(requests[0]
+            .stop
+            .as_ref()
+            .is_some_and(|stop| stop.iter().any(|s| s.contains("Observation:"))));
+        assert!(requests[0].messages[0]
+            .content
+            .contains("Action: the tool to use"));
+        let observation = requests[1].messages.last().unwrap();
+        assert_eq!(observation.role, Role::User);
+        assert_eq!(observation.content, "Observation: 4");
+
+        // The history keeps the native form.
+        let history = &agent.chat_session().messages;
+        let call = &history[1];
+        assert_eq!(call.content, "I should add.");
+        let calls = call.tool_calls.as_ref().unwrap();
+        assert_eq!(calls[0].function.name, "calculator");
+        assert_eq!(history[2].role, Role::Tool);
+        assert_eq!(history[3].content, "It is 4.");
+    }
+
+    /// Tests that streaming under the ReAct protocol shows only the final answer.
+    #[tokio::test]
+    async fn test_agent_react_tool_protocol_stream() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_response("Thought: add\nAction: calculator\nAction Input: 2 + 2")
+            .with_response("Final Answer: It is 4.");
+        let mut agent = Agent::builder("local")
+            .llm_provider(mock)
+            .tool(Box::new(CalculatorTool))
+            .tool_protocol(ToolProtocol::React)
+            .build()
+            .await
+            .unwrap();
+
+        let mut events = Vec::new();
+        let answer = agent
+            .chat_stream("What is 2 + 2?", |event| events.push(event))
+            .await
+            .unwrap();
+        assert_eq!(answer, "It is 4.");
+        assert!(matches!(
+            &events[0],
+            StreamEvent::ToolStarted { arguments, .. } if arguments == "{\"expression\":\"2 + 2\"}"
+        ));
+        assert!(matches!(
+            &events[1],
+            StreamEvent::ToolFinished { success: true, .. }
+        ));
+        assert_eq!(events[2], StreamEvent::Content("It is 4.".to_string()));
+        assert_eq!(events.len(), 3);
+    }
+
+    /// Tests that the agent reports typed events instead of printing.
+    #[tokio::test]
+    async fn test_agent_event_stream() {
+        use crate::events::AgentEvent;
+        use futures::StreamExt;
+
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&seen);
+        let mut agent = Agent::builder("observed")
+            .llm_provider(
+                crate::mock::MockLLMProvider::new()
+                    .with_tool_call("calculator", serde_json::json!({"expression": "2 + 3"}))
+                    .with_response("5"),
+            )
+            .tool(Box::new(CalculatorTool))
+            .observer(move |agent: &str, _: &AgentEvent| {
+                recorder.lock().unwrap().push(agent.to_string())
+            })
+            .build()
+            .await
+            .unwrap();
+        let events = agent.subscribe();
+
+        agent.chat("2 + 3?").await.unwrap();
+        drop(agent);
+        let events: Vec<AgentEvent> = events.collect().await;
+
+        assert_eq!(
+            events,
+            [
+                AgentEvent::ThinkingStarted,
+                AgentEvent::ToolCallStarted {
+                    name: "calculator".to_string(),
+                    args: serde_json::json!({"expression": "2 + 3"}),
+                },
+                AgentEvent::ToolCallFinished {
+                    name: "calculator".to_string(),
+                    success: true,
+                    output: "5".to_string(),
+                },
+                AgentEvent::ThinkingStarted,
+                AgentEvent::TokenDelta("5".to_string()),
+                AgentEvent::FinalAnswer("5".to_string()),
+            ]
+        );
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), events.len());
+        assert!(seen.iter().all(|name| name == "observed"));
+    }
+
+    /// Tests that middleware hooks run around LLM and tool calls.
+    #[tokio::test]
+    async fn test_agent_middleware_hooks() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_tool_call("calculator", serde_json::json!({"expression": "1 + 1"}))
+            .with_tool_call("mock_tool", serde_json::json!({"input": "x"}))
+            .with_response("done");
+        let mut agent = Agent::builder("guarded")
+            .llm_provider(mock.clone())
+            .tools(vec![Box::new(CalculatorTool), Box::new(MockTool)])
+            .middleware(GuardMiddleware)
+            .build()
+            .await
+            .unwrap();
+
+        let answer = agent.chat("My card is 4111-1111").await.unwrap();
+        assert_eq!(answer, "DONE");
+
+        let requests = mock.requests();
+        assert_eq!(requests[0].messages[0].content, "My card is [card]");
+        assert_eq!(
+            agent.chat_session().messages[0].content,
+            "My card is 4111-1111"
+        );
+
+        let blocked = requests[1].messages.last().unwrap();
+        assert!(blocked
+            .content
+            .starts_with("Tool call blocked by guard: Tool error: calculator is not allowed"));
+        let executed = requests[2].messages.last().unwrap();
+        assert!(executed.content.ends_with("(checked)"));
+    }
+
+    /// Tests that moderators redact and block input and output.
+    #[tokio::test]
+    async fn test_agent_moderation() {
+        use crate::moderation::{KeywordModerator, ModerationAction};
+
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_response("Call 555-1234")
+            .with_response("How to build a bomb");
+        let moderator = KeywordModerator::new()
+            .words(["bomb"], ModerationAction::Block)
+            .unwrap()
+            .pattern(r"\d{3}-\d{4}", ModerationAction::Redact("[phone]".into()))
+            .unwrap();
+        let mut agent = Agent::builder("moderated")
+            .llm_provider(mock.clone())
+            .moderator(moderator)
+            .build()
+            .await
+            .unwrap();
+
+        let err = agent.chat("bomb recipe").await.unwrap_err();
+        assert!(matches!(err, HeliosError::Blocked(_)));
+        assert!(agent.chat_session().messages.is_empty());
+        assert!(mock.requests().is_empty());
+
+        let answer = agent.chat("my number is 555-9999").await.unwrap();
+        assert_eq!(answer, "Call [phone]");
+        assert_eq!(
+            mock.requests()[0].messages[0].content,
+            "my number is [phone]"
+        );
+
+        let err = agent.chat("and then?").await.unwrap_err();
+        assert!(err.to_string().contains("rejected the response"));
+        let history = &agent.chat_session().messages;
+        assert_eq!(history.last().unwrap().content, "and then?");
+        assert!(history.iter().all(|m| !m.content.contains("bomb")));
+    }
+
+    /// Tests forking, chatting on and switching between branches.
+    #[tokio::test]
+    async fn test_agent_branching() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_response("Crumb & Co.")
+            .with_response("The Giggling Loaf")
+            .with_response("Maison Levain")
+            .with_response("Rise");
+        let mut agent = Agent::builder("namer")
+            .llm_provider(mock.clone())
+            .build()
+            .await
+            .unwrap();
+
+        agent.chat("Name a bakery").await.unwrap();
+        let classic = agent.fork().unwrap();
+        agent.chat("More playful").await.unwrap();
+        let answer = agent
+            .chat_on_branch(&classic, "More classic")
+            .await
+            .unwrap();
+        assert_eq!(answer, "Maison Levain");
+
+        // The branch saw only the shared start, and main was left alone.
+        let requests = mock.requests();
+        let contents: Vec<&str> = requests[2]
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(
+            contents,
+            vec!["Name a bakery", "Crumb & Co.", "More classic"]
+        );
+        assert_eq!(agent.current_branch(), MAIN_BRANCH);
+        assert_eq!(
+            agent.chat_session().messages[3].content,
+            "The Giggling Loaf"
+        );
+        assert_eq!(agent.branch(&classic).unwrap().messages.len(), 4);
+
+        // Fork main before its first answer and continue there.
+        let short = agent.fork_at(MAIN_BRANCH, 1).unwrap();
+        agent.switch_branch(&short).unwrap();
+        assert_eq!(agent.chat("One word").await.unwrap(), "Rise");
+        assert_eq!(agent.chat_session().messages.len(), 3);
+        assert_eq!(agent.branch_tree().children(MAIN_BRANCH).len(), 2);
+
+        assert!(agent.delete_branch(MAIN_BRANCH).is_err());
+        assert!(agent.delete_branch(&short).is_err());
+        agent.delete_branch(&classic).unwrap();
+        assert!(agent.branch(&classic).is_none());
+        assert!(agent.chat_on_branch("missing", "Hi").await.is_err());
+        assert_eq!(agent.current_branch(), short);
+    }
+
+    /// Tests answering from and priming with the semantic cache.
+    #[tokio::test]
+    async fn test_agent_semantic_cache() {
+        use crate::rag::InMemoryVectorStore;
+        use crate::semantic_cache::tests::WordEmbeddings;
+
+        let mock = crate::mock::MockLLMProvider::new().with_response("Paris");
+        let mut agent = Agent::builder("geo")
+            .llm_provider(mock.clone())
+            .semantic_cache(SemanticCache::new(
+                Box::new(WordEmbeddings),
+                Box::new(InMemoryVectorStore::new()),
+            ))
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            agent.chat("What is the capital of France?").await.unwrap(),
+            "Paris"
+        );
+        // Answered from the cache; the mock has no second response.
+        let mut streamed = String::new();
+        let answer = agent
+            .chat_stream("what is the capital of france", |event| {
+                if let StreamEvent::Content(text) = event {
+                    streamed.push_str(&text);
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!((answer.as_str(), streamed.as_str()), ("Paris", "Paris"));
+        assert_eq!(mock.requests().len(), 1);
+        assert_eq!(agent.chat_session().messages.len(), 4);
+
+        // In prime mode the model sees the cached answer and still replies.
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_response("Paris")
+            .with_response("Paris, as before.");
+        let mut agent = Agent::builder("geo")
+            .llm_provider(mock.clone())
+            .semantic_cache(
+                SemanticCache::new(
+                    Box::new(WordEmbeddings),
+                    Box::new(InMemoryVectorStore::new()),
+                )
+                .with_mode(SemanticCacheMode::Prime),
+            )
+            .build()
+            .await
+            .unwrap();
+        agent.chat("What is the capital of France?").await.unwrap();
+        agent.clear_history();
+        assert_eq!(
+            agent.chat("What is the capital of France").await.unwrap(),
+            "Paris, as before."
+        );
+        let requests = mock.requests();
+        let hint = &requests[1].messages[requests[1].messages.len() - 2];
+        assert_eq!(hint.role, Role::System);
+        assert!(hint.content.contains("Answer: Paris"));
+        assert!(agent
+            .chat_session()
+            .messages
+            .iter()
+            .all(|message| message.role != Role::System));
+    }
+
+    /// Tests regenerating and editing the last turn, including its tool exchange.
+    #[tokio::test]
+    async fn test_agent_regenerate_and_edit() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_tool_call("calculator", serde_json::json!({"expression": "2 + 2"}))
+            .with_response("It is 4.")
+            .with_response("Four.")
+            .with_response("Six.");
+        let mut agent = Agent::builder("math")
+            .llm_provider(mock.clone())
+            .tool(Box::new(CalculatorTool))
+            .build()
+            .await
+            .unwrap();
+
+        assert!(agent.regenerate(None).await.is_err());
+        agent.chat("What is 2 + 2?").await.unwrap();
+        assert_eq!(agent.chat_session().messages.len(), 4);
+
+        assert_eq!(agent.regenerate(Some(1.5)).await.unwrap(), "Four.");
+        let messages = &agent.chat_session().messages;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content, "What is 2 + 2?");
+        assert_eq!(messages[1].content, "Four.");
+        let requests = mock.requests();
+        assert_eq!(requests[2].temperature, Some(1.5));
+        assert_eq!(requests[2].messages.len(), 1);
+
+        assert_eq!(
+            agent
+                .edit_last_user_message("What is 3 + 3?")
+                .await
+                .unwrap(),
+            "Six."
+        );
+        let messages = &agent.chat_session().messages;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content, "What is 3 + 3?");
+
+        // A failed run keeps the previous turn.
+        assert!(agent.regenerate(None).await.is_err());
+        assert_eq!(agent.chat_session().messages[1].content, "Six.");
+    }
+
     // Mock tool for testing
     struct MockTool;
 
@@ -0,0 +1,833 @@
+//! # Archive Module
+//!
+//! `ArchiveTool` creates, lists and extracts `.zip`, `.tar.gz` (`.tgz`) and `.tar`
+//! archives, so an agent can package build artifacts or look inside a download.
+//!
+//! Extraction is defensive: an entry whose path is absolute or climbs out with `..`
+//! is refused, links and device entries are skipped, existing files are kept unless
+//! `overwrite` is set, and the tool stops once the archive exceeds the entry-count
+//! or size limits. Sizes are counted as data is written rather than taken from the
+//! headers, which a hostile archive can fake. Wrap the tool in a `SandboxedTool` to
+//! confine `path`, `src_path` and `dst_path` to a workspace.
+//!
+//! Requires the `archive` feature.
+
+use crate::error::{HeliosError, Result};
+use crate::footprint::format_bytes;
+use crate::tools::{required_str, Tool, ToolParameter, ToolPermission, ToolResult};
+use crate::truncation::truncate_chars;
+use async_trait::async_trait;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// An archive format, chosen from the file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Zip,
+    TarGz,
+    Tar,
+}
+
+impl Format {
+    fn of(path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.ends_with(".zip") {
+            Ok(Format::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(Format::TarGz)
+        } else if name.ends_with(".tar") {
+            Ok(Format::Tar)
+        } else {
+            Err(HeliosError::ToolError(format!(
+                "Cannot tell the format of '{}'; use a .zip, .tar.gz, .tgz or .tar name",
+                path.display()
+            )))
+        }
+    }
+}
+
+/// A tool for creating, listing and extracting zip and tar archives.
+///
+/// ```rust
+/// use helios_engine::{ArchiveTool, PathJail, SandboxedTool};
+///
+/// let workspace = tempfile::tempdir()?;
+/// let archives = ArchiveTool::new()
+///     .max_total_bytes(100 * 1024 * 1024)
+///     .max_entries(5_000);
+/// let tool = SandboxedTool::new(Box::new(archives), PathJail::new(workspace.path())?);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArchiveTool {
+    max_total_bytes: u64,
+    max_entries: usize,
+    max_output_chars: usize,
+}
+
+impl ArchiveTool {
+    /// Creates the tool with the default limits.
+    pub fn new() -> Self {
+        Self {
+            max_total_bytes: 512 * 1024 * 1024,
+            max_entries: 10_000,
+            max_output_chars: 20_000,
+        }
+    }
+
+    /// Limits the uncompressed bytes an archive may pack or unpack (512 MiB by default).
+    pub fn max_total_bytes(mut self, max: u64) -> Self {
+        self.max_total_bytes = max;
+        self
+    }
+
+    /// Limits the number of entries an archive may hold (10,000 by default).
+    pub fn max_entries(mut self, max: usize) -> Self {
+        self.max_entries = max;
+        self
+    }
+
+    /// Limits the characters of a listing returned to the agent (20,000 by default).
+    pub fn max_output_chars(mut self, max: usize) -> Self {
+        self.max_output_chars = max;
+        self
+    }
+
+    fn list(&self, archive: &Path) -> Result<String> {
+        let format = Format::of(archive)?;
+        let file = File::open(archive)?;
+        // (name, uncompressed size, note)
+        let mut entries: Vec<(String, u64, &str)> = Vec::new();
+        let more = match format {
+            Format::Zip => {
+                let mut zip = zip::ZipArchive::new(file).map_err(zip_error)?;
+                for i in 0..zip.len().min(self.max_entries) {
+                    let entry = zip.by_index_raw(i).map_err(zip_error)?;
+                    let note = if entry.is_symlink() { "link" } else { "" };
+                    entries.push((entry.name().to_string(), entry.size(), note));
+                }
+                zip.len() > self.max_entries
+            }
+            Format::TarGz => self.list_tar(GzDecoder::new(file), &mut entries)?,
+            Format::Tar => self.list_tar(file, &mut entries)?,
+        };
+
+        let total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        let mut out = format!(
+            "{}: {} entries, {} uncompressed\n",
+            archive.display(),
+            entries.len(),
+            format_bytes(total as usize)
+        );
+        for (name, size, note) in &entries {
+            let note = if note.is_empty() {
+                String::new()
+            } else {
+                format!("  ({}, not extracted)", note)
+            };
+            out.push_str(&format!("{:>10}  {}{}\n", size, name, note));
+        }
+        if more {
+            out.push_str(&format!(
+                "... more than {} entries; the rest are not listed\n",
+                self.max_entries
+            ));
+        }
+        Ok(self.limit(out.trim_end().to_string()))
+    }
+
+    /// Lists a tar stream, returning whether it had more entries than are listed.
+    fn list_tar(&self, reader: impl Read, entries: &mut Vec<(String, u64, &str)>) -> Result<bool> {
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries()? {
+            let entry = entry?;
+            let kind = entry.header().entry_type();
+            if kind.is_pax_global_extensions() {
+                continue;
+            }
+            if entries.len() == self.max_entries {
+                return Ok(true);
+            }
+            let note = if kind.is_file() || kind.is_dir() {
+                ""
+            } else if kind.is_symlink() || kind.is_hard_link() {
+                "link"
+            } else {
+                "special file"
+            };
+            let name = entry.path()?.to_string_lossy().into_owned();
+            entries.push((name, entry.size(), note));
+        }
+        Ok(false)
+    }
+
+    fn create(&self, src: &Path, archive: &Path, overwrite: bool) -> Result<String> {
+        let format = Format::of(archive)?;
+        if !src.exists() {
+            return Err(HeliosError::ToolError(format!(
+                "'{}' does not exist",
+                src.display()
+            )));
+        }
+        if archive.exists() && !overwrite {
+            return Err(HeliosError::ToolError(format!(
+                "'{}' already exists; pass overwrite to replace it",
+                archive.display()
+            )));
+        }
+
+        // Entry names start with the source's own name, so `dist` packs as `dist/...`.
+        let base = src.parent().unwrap_or(src);
+        let mut entries: Vec<(PathBuf, PathBuf, bool)> = Vec::new();
+        let mut total = 0u64;
+        let mut links = 0;
+        for entry in walkdir::WalkDir::new(src).sort_by_file_name() {
+            let entry = entry.map_err(|e| HeliosError::ToolError(e.to_string()))?;
+            let name = entry.path().strip_prefix(base).unwrap_or(entry.path());
+            if name.as_os_str().is_empty() || entry.path() == archive {
+                continue;
+            }
+            let file_type = entry.file_type();
+            if file_type.is_symlink() {
+                links += 1;
+                continue;
+            }
+            if file_type.is_file() {
+                total += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+            if total > self.max_total_bytes {
+                return Err(HeliosError::ToolError(format!(
+                    "'{}' holds more than {}",
+                    src.display(),
+                    format_bytes(self.max_total_bytes as usize)
+                )));
+            }
+            entries.push((
+                entry.path().to_path_buf(),
+                name.to_path_buf(),
+                file_type.is_dir(),
+            ));
+            if entries.len() > self.max_entries {
+                return Err(HeliosError::ToolError(format!(
+                    "'{}' holds more than {} files and directories",
+                    src.display(),
+                    self.max_entries
+                )));
+            }
+        }
+
+        if let Some(parent) = archive.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let result = write_archive(format, archive, &entries);
+        if result.is_err() {
+            let _ = fs::remove_file(archive);
+        }
+        result?;
+
+        let files = entries.iter().filter(|(_, _, is_dir)| !is_dir).count();
+        let mut out = format!(
+            "Created {} with {} files ({})",
+            archive.display(),
+            files,
+            format_bytes(total as usize)
+        );
+        if links > 0 {
+            out.push_str(&format!("; skipped {} symbolic links", links));
+        }
+        Ok(out)
+    }
+
+    fn extract(&self, archive: &Path, dst: &Path, overwrite: bool) -> Result<String> {
+        let format = Format::of(archive)?;
+        let file = File::open(archive)?;
+        let created = !dst.exists();
+        fs::create_dir_all(dst)?;
+        let mut extractor = Extractor {
+            root: dst.canonicalize()?,
+            overwrite,
+            max_total_bytes: self.max_total_bytes,
+            max_entries: self.max_entries,
+            written: 0,
+            entries: 0,
+            files: 0,
+            skipped: Vec::new(),
+        };
+        let result = match format {
+            Format::Zip => extractor.zip(file),
+            Format::TarGz => extractor.tar(GzDecoder::new(file)),
+            Format::Tar => extractor.tar(file),
+        };
+        if let Err(e) = result {
+            if created {
+                let _ = fs::remove_dir_all(&extractor.root);
+            }
+            return Err(e);
+        }
+
+        let mut out = format!(
+            "Extracted {} files ({}) from {} to {}",
+            extractor.files,
+            format_bytes(extractor.written as usize),
+            archive.display(),
+            extractor.root.display()
+        );
+        if !extractor.skipped.is_empty() {
+            out.push_str(&format!(
+                "\nSkipped links and special files: {}",
+                extractor.skipped.join(", ")
+            ));
+        }
+        Ok(self.limit(out))
+    }
+
+    fn limit(&self, text: String) -> String {
+        let max = self.max_output_chars;
+        truncate_chars(
+            &text,
+            max,
+            &format!("\n... (truncated to {} characters)", max),
+        )
+    }
+}
+
+impl Default for ArchiveTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes `entries` (source path, name in the archive, is a directory) to a new archive.
+fn write_archive(
+    format: Format,
+    archive: &Path,
+    entries: &[(PathBuf, PathBuf, bool)],
+) -> Result<()> {
+    let file = File::create(archive)?;
+    match format {
+        Format::Zip => {
+            let mut zip = zip::ZipWriter::new(file);
+            for (path, name, is_dir) in entries {
+                let mut options = zip::write::SimpleFileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated);
+                if let Some(mode) = unix_mode(path) {
+                    options = options.unix_permissions(mode);
+                }
+                let name = name
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                if *is_dir {
+                    zip.add_directory(name, options).map_err(zip_error)?;
+                } else {
+                    zip.start_file(name, options).map_err(zip_error)?;
+                    io::copy(&mut File::open(path)?, &mut zip)?;
+                }
+            }
+            zip.finish().map_err(zip_error)?;
+        }
+        Format::TarGz => {
+            let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+            append_tar(&mut builder, entries)?;
+            builder.into_inner()?.finish()?;
+        }
+        Format::Tar => {
+            let mut builder = tar::Builder::new(file);
+            append_tar(&mut builder, entries)?;
+            builder.into_inner()?.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn append_tar<W: Write>(
+    builder: &mut tar::Builder<W>,
+    entries: &[(PathBuf, PathBuf, bool)],
+) -> Result<()> {
+    builder.follow_symlinks(false);
+    for (path, name, is_dir) in entries {
+        if *is_dir {
+            builder.append_dir(name, path)?;
+        } else {
+            builder.append_path_with_name(path, name)?;
+        }
+    }
+    Ok(())
+}
+
+/// Unpacks entries under a root directory, enforcing the tool's limits.
+struct Extractor {
+    root: PathBuf,
+    overwrite: bool,
+    max_total_bytes: u64,
+    max_entries: usize,
+    written: u64,
+    entries: usize,
+    files: usize,
+    skipped: Vec<String>,
+}
+
+impl Extractor {
+    fn zip(&mut self, file: File) -> Result<()> {
+        let mut zip = zip::ZipArchive::new(file).map_err(zip_error)?;
+        for i in 0..zip.len() {
+            let mut entry = zip.by_index(i).map_err(zip_error)?;
+            let name = PathBuf::from(entry.name());
+            if entry.is_dir() {
+                self.dir(&name)?;
+            } else if entry.is_symlink() {
+                self.skip(&name)?;
+            } else {
+                let mode = entry.unix_mode();
+                self.file(&name, mode, &mut entry)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn tar(&mut self, reader: impl Read) -> Result<()> {
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.into_owned();
+            let kind = entry.header().entry_type();
+            if kind.is_pax_global_extensions() {
+                continue;
+            } else if kind.is_dir() {
+                self.dir(&name)?;
+            } else if kind.is_file() {
+                let mode = entry.header().mode().ok();
+                self.file(&name, mode, &mut entry)?;
+            } else {
+                self.skip(&name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts an entry against the limit and returns where it goes.
+    fn target(&mut self, name: &Path) -> Result<PathBuf> {
+        self.entries += 1;
+        if self.entries > self.max_entries {
+            return Err(HeliosError::ToolError(format!(
+                "The archive has more than {} entries",
+                self.max_entries
+            )));
+        }
+        let relative = safe_entry_path(name).ok_or_else(|| {
+            HeliosError::ToolError(format!(
+                "Refusing entry '{}': it would be written outside the destination",
+                name.display()
+            ))
+        })?;
+        Ok(self.root.join(relative))
+    }
+
+    /// Creates a directory, checking that no existing symlink leads it out of the root.
+    fn create_dir(&self, dir: &Path, name: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        if dir.canonicalize()?.starts_with(&self.root) {
+            Ok(())
+        } else {
+            Err(HeliosError::ToolError(format!(
+                "Refusing entry '{}': a symbolic link in the destination leads outside it",
+                name.display()
+            )))
+        }
+    }
+
+    fn dir(&mut self, name: &Path) -> Result<()> {
+        let target = self.target(name)?;
+        self.create_dir(&target, name)
+    }
+
+    fn skip(&mut self, name: &Path) -> Result<()> {
+        self.entries += 1;
+        if self.entries > self.max_entries {
+            return Err(HeliosError::ToolError(format!(
+                "The archive has more than {} entries",
+                self.max_entries
+            )));
+        }
+        self.skipped.push(name.display().to_string());
+        Ok(())
+    }
+
+    fn file(&mut self, name: &Path, mode: Option<u32>, reader: &mut dyn Read) -> Result<()> {
+        let target = self.target(name)?;
+        if let Some(parent) = target.parent() {
+            self.create_dir(parent, name)?;
+        }
+        if let Ok(existing) = fs::symlink_metadata(&target) {
+            if !existing.is_file() {
+                return Err(HeliosError::ToolError(format!(
+                    "Refusing entry '{}': the destination holds a link or directory there",
+                    name.display()
+                )));
+            }
+            if !self.overwrite {
+                return Err(HeliosError::ToolError(format!(
+                    "'{}' already exists; pass overwrite to replace it",
+                    target.display()
+                )));
+            }
+        }
+
+        let remaining = self.max_total_bytes - self.written;
+        let mut out = File::create(&target)?;
+        let copied = io::copy(&mut reader.take(remaining + 1), &mut out)?;
+        if copied > remaining {
+            drop(out);
+            let _ = fs::remove_file(&target);
+            return Err(HeliosError::ToolError(format!(
+                "The archive unpacks to more than {}",
+                format_bytes(self.max_total_bytes as usize)
+            )));
+        }
+        self.written += copied;
+        self.files += 1;
+        set_mode(&target, mode);
+        Ok(())
+    }
+}
+
+/// Returns the path of an entry relative to the destination, or `None` if the
+/// name is absolute or climbs out with `..`.
+fn safe_entry_path(name: &Path) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+#[cfg(unix)]
+fn unix_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .ok()
+        .map(|m| m.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn unix_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Keeps an extracted file's permission bits, without setuid, setgid or sticky bits.
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: Option<u32>) {
+    use std::os::unix::fs::PermissionsExt;
+    if let Some(mode) = mode {
+        let _ = fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777));
+    }
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: Option<u32>) {}
+
+fn zip_error(e: zip::result::ZipError) -> HeliosError {
+    HeliosError::ToolError(format!("Zip error: {}", e))
+}
+
+#[async_trait]
+impl Tool for ArchiveTool {
+    fn name(&self) -> &str {
+        "archive"
+    }
+
+    fn description(&self) -> &str {
+        "Create, list and extract .zip, .tar.gz, .tgz and .tar archives. \
+         Operations: 'list' (path), 'create' (src_path into path), 'extract' (path into dst_path)."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        params.insert(
+            "operation".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "'list', 'create' or 'extract'".to_string(),
+                required: Some(true),
+            },
+        );
+        params.insert(
+            "path".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "The archive file; its extension picks the format".to_string(),
+                required: Some(true),
+            },
+        );
+        params.insert(
+            "src_path".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "For create: the file or directory to pack".to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "dst_path".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "For extract: the directory to unpack into (default: next to \
+                              the archive, named after it)"
+                    .to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "overwrite".to_string(),
+            ToolParameter {
+                param_type: "boolean".to_string(),
+                description: "Replace files that already exist (default false)".to_string(),
+                required: Some(false),
+            },
+        );
+        params
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Destructive
+    }
+
+    fn call_permission(&self, args: &Value) -> ToolPermission {
+        match args.get("operation").and_then(Value::as_str) {
+            Some("list") => ToolPermission::ReadOnly,
+            _ => ToolPermission::Destructive,
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let operation = required_str(&args, "operation")?.to_string();
+        let path = PathBuf::from(required_str(&args, "path")?);
+        let overwrite = args
+            .get("overwrite")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let src = match operation.as_str() {
+            "create" => Some(PathBuf::from(required_str(&args, "src_path")?)),
+            _ => None,
+        };
+        let dst = args
+            .get("dst_path")
+            .and_then(Value::as_str)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| default_destination(&path));
+
+        let tool = self.clone();
+        let result = tokio::task::spawn_blocking(move || match operation.as_str() {
+            "list" => tool.list(&path),
+            "create" => tool.create(src.as_deref().unwrap_or(&path), &path, overwrite),
+            "extract" => tool.extract(&path, &dst, overwrite),
+            other => Err(HeliosError::ToolError(format!(
+                "Unknown operation '{}'. Valid: list, create, extract",
+                other
+            ))),
+        })
+        .await
+        .map_err(|e| HeliosError::ToolError(format!("Archive task failed: {}", e)))?;
+
+        Ok(match result {
+            Ok(output) => ToolResult::success(output),
+            Err(e) => ToolResult::error(e.to_string()),
+        })
+    }
+}
+
+/// Returns the directory next to `archive` named after it, e.g. `build` for `build.tar.gz`.
+fn default_destination(archive: &Path) -> PathBuf {
+    let name = archive
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let lower = name.to_lowercase();
+    let stem_len = [".tar.gz", ".tgz", ".tar", ".zip"]
+        .iter()
+        .find(|ext| lower.ends_with(*ext))
+        .map(|ext| name.len() - ext.len())
+        .unwrap_or(name.len());
+    let stem = match &name[..stem_len] {
+        "" => "extracted",
+        stem => stem,
+    };
+    archive.with_file_name(stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn run(tool: &ArchiveTool, args: Value) -> ToolResult {
+        tool.execute(args).await.unwrap()
+    }
+
+    /// Tests packing a directory and unpacking it again in each format.
+    #[tokio::test]
+    async fn test_archive_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let dist = dir.path().join("dist");
+        fs::create_dir_all(dist.join("bin")).unwrap();
+        fs::write(dist.join("README.txt"), "hello").unwrap();
+        fs::write(dist.join("bin").join("app"), vec![7u8; 3000]).unwrap();
+        let tool = ArchiveTool::new();
+
+        for name in ["dist.zip", "dist.tar.gz", "dist.tar"] {
+            let archive = dir.path().join("out").join(name);
+            let result = run(
+                &tool,
+                json!({"operation": "create", "src_path": dist, "path": archive}),
+            )
+            .await;
+            assert!(result.success, "{}", result.output);
+            assert!(result.output.contains("with 2 files"), "{}", result.output);
+
+            let result = run(&tool, json!({"operation": "list", "path": archive})).await;
+            assert!(
+                result.output.contains("dist/README.txt"),
+                "{}",
+                result.output
+            );
+            assert!(result.output.contains("3000  dist/bin/app"));
+
+            let result = run(&tool, json!({"operation": "extract", "path": archive})).await;
+            assert!(result.success, "{}", result.output);
+            let unpacked = dir.path().join("out").join("dist");
+            assert_eq!(
+                fs::read_to_string(unpacked.join("dist").join("README.txt")).unwrap(),
+                "hello"
+            );
+            assert_eq!(fs::read(unpacked.join("dist/bin/app")).unwrap().len(), 3000);
+
+            // A second extraction keeps the files unless told to overwrite them.
+            let result = run(&tool, json!({"operation": "extract", "path": archive})).await;
+            assert!(!result.success);
+            assert!(result.output.contains("already exists"));
+            let result = run(
+                &tool,
+                json!({"operation": "extract", "path": archive, "overwrite": true}),
+            )
+            .await;
+            assert!(result.success, "{}", result.output);
+            fs::remove_dir_all(unpacked).unwrap();
+        }
+
+        let small = ArchiveTool::new().max_total_bytes(1000);
+        let result = run(
+            &small,
+            json!({"operation": "create", "src_path": dist, "path": dir.path().join("big.zip")}),
+        )
+        .await;
+        assert!(!result.success);
+        assert!(!dir.path().join("big.zip").exists());
+
+        assert_eq!(
+            tool.call_permission(&json!({"operation": "list"})),
+            ToolPermission::ReadOnly
+        );
+        assert_eq!(
+            tool.call_permission(&json!({"operation": "extract"})),
+            ToolPermission::Destructive
+        );
+    }
+
+    /// Tests that hostile archives cannot escape the destination or exceed the limits.
+    #[tokio::test]
+    async fn test_archive_extract_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = zip::write::SimpleFileOptions::default();
+
+        let evil = dir.path().join("evil.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&evil).unwrap());
+        zip.start_file("ok.txt", options).unwrap();
+        zip.write_all(b"fine").unwrap();
+        zip.start_file("../../escaped.txt", options).unwrap();
+        zip.write_all(b"gotcha").unwrap();
+        zip.finish().unwrap();
+
+        let tool = ArchiveTool::new();
+        let dst = dir.path().join("a").join("b");
+        let result = run(
+            &tool,
+            json!({"operation": "extract", "path": evil, "dst_path": dst}),
+        )
+        .await;
+        assert!(!result.success);
+        assert!(result.output.contains("outside the destination"));
+        assert!(!dir.path().join("escaped.txt").exists());
+        // The destination was created for this extraction, so it is removed again.
+        assert!(!dst.exists());
+
+        // A tar with an absolute path and a symlink.
+        let tarball = dir.path().join("links.tar");
+        let mut builder = tar::Builder::new(File::create(&tarball).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "link", "/etc/passwd")
+            .unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        builder
+            .append_data(&mut header, "data.txt", &b"data"[..])
+            .unwrap();
+        builder.into_inner().unwrap();
+        let result = run(&tool, json!({"operation": "extract", "path": tarball})).await;
+        assert!(result.success, "{}", result.output);
+        assert!(result
+            .output
+            .contains("Skipped links and special files: link"));
+        let out = dir.path().join("links");
+        assert!(out.join("data.txt").exists());
+        assert!(fs::symlink_metadata(out.join("link")).is_err());
+
+        // A small archive that unpacks to more than the limit.
+        let bomb = dir.path().join("bomb.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&bomb).unwrap());
+        zip.start_file(
+            "zeros",
+            options.compression_method(zip::CompressionMethod::Deflated),
+        )
+        .unwrap();
+        zip.write_all(&vec![0u8; 100_000]).unwrap();
+        zip.finish().unwrap();
+        assert!(fs::metadata(&bomb).unwrap().len() < 1000);
+        let result = run(
+            &ArchiveTool::new().max_total_bytes(10_000),
+            json!({"operation": "extract", "path": bomb}),
+        )
+        .await;
+        assert!(!result.success);
+        assert!(result.output.contains("unpacks to more than"));
+        assert!(!dir.path().join("bomb").exists());
+
+        let result = run(
+            &ArchiveTool::new().max_entries(1),
+            json!({"operation": "extract", "path": tarball, "dst_path": dir.path().join("few")}),
+        )
+        .await;
+        assert!(result.output.contains("more than 1 entries"));
+
+        assert_eq!(safe_entry_path(Path::new("/etc/passwd")), None);
+        assert_eq!(safe_entry_path(Path::new("a/../../b")), None);
+        assert_eq!(
+            safe_entry_path(Path::new("./a/b.txt")),
+            Some(PathBuf::from("a/b.txt"))
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! # Ask User Module
+//!
+//! `AskUserTool` lets an agent stop and ask the person it works for instead of
+//! guessing. The tool call waits until the host application supplies an answer,
+//! the answer becomes the tool result, and the turn carries on from there.
+//!
+//! The host answers through a [`QuestionHandler`]. That can be a closure,
+//! [`ConsoleQuestionHandler`] for terminal programs, or the channel returned by
+//! [`AskUserTool::channel`] for applications whose interface runs elsewhere, such
+//! as a web UI that shows the question and posts the reply back.
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{required_str, Tool, ToolParameter, ToolPermission, ToolResult};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// A question the agent wants the user to answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserQuestion {
+    /// The question, as the agent wrote it.
+    pub question: String,
+    /// Suggested answers, if the agent offered any. The user may still answer freely.
+    pub choices: Vec<String>,
+}
+
+/// Answers questions asked with [`AskUserTool`].
+///
+/// Any `Fn(&UserQuestion) -> Option<String>` closure is also a handler.
+#[async_trait]
+pub trait QuestionHandler: Send + Sync {
+    /// Returns the user's answer, or `None` if they gave none.
+    async fn ask(&self, question: &UserQuestion) -> Option<String>;
+}
+
+#[async_trait]
+impl<F> QuestionHandler for F
+where
+    F: Fn(&UserQuestion) -> Option<String> + Send + Sync,
+{
+    async fn ask(&self, question: &UserQuestion) -> Option<String> {
+        self(question)
+    }
+}
+
+/// A handler that prints the question on the terminal and reads the answer from stdin.
+///
+/// When the question has choices they are numbered, and typing a number picks that
+/// choice. An empty line counts as no answer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsoleQuestionHandler;
+
+#[async_trait]
+impl QuestionHandler for ConsoleQuestionHandler {
+    async fn ask(&self, question: &UserQuestion) -> Option<String> {
+        let question = question.clone();
+        tokio::task::spawn_blocking(move || {
+            println!("\n❓ {}", question.question);
+            for (i, choice) in question.choices.iter().enumerate() {
+                println!("   {}) {}", i + 1, choice);
+            }
+            print!("Your answer: ");
+            let _ = std::io::stdout().flush();
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line).ok()?;
+            console_answer(&line, &question.choices)
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+}
+
+/// Turns a line typed on the console into an answer, mapping a choice number to its text.
+fn console_answer(line: &str, choices: &[String]) -> Option<String> {
+    let answer = line.trim();
+    if answer.is_empty() {
+        return None;
+    }
+    let picked = answer
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| choices.get(i));
+    Some(picked.cloned().unwrap_or_else(|| answer.to_string()))
+}
+
+/// A question waiting for an answer, received from the channel of [`AskUserTool::channel`].
+///
+/// Dropping it without answering tells the agent the user gave no answer.
+#[derive(Debug)]
+pub struct PendingQuestion {
+    /// The question to show the user.
+    pub question: UserQuestion,
+    reply: oneshot::Sender<String>,
+}
+
+impl PendingQuestion {
+    /// Sends the user's answer back to the waiting agent.
+    pub fn answer(self, answer: impl Into<String>) {
+        let _ = self.reply.send(answer.into());
+    }
+}
+
+/// Forwards questions to a channel and waits for the reply.
+struct ChannelHandler {
+    sender: mpsc::Sender<PendingQuestion>,
+}
+
+#[async_trait]
+impl QuestionHandler for ChannelHandler {
+    async fn ask(&self, question: &UserQuestion) -> Option<String> {
+        let (reply, answer) = oneshot::channel();
+        let pending = PendingQuestion {
+            question: question.clone(),
+            reply,
+        };
+        self.sender.send(pending).await.ok()?;
+        answer.await.ok()
+    }
+}
+
+/// A tool that asks the user a question and returns their answer.
+///
+/// ```rust,no_run
+/// use helios_engine::{Agent, AskUserTool, Config};
+///
+/// # async fn example(config: Config) -> helios_engine::Result<()> {
+/// let (ask_user, mut questions) = AskUserTool::channel(8);
+/// tokio::spawn(async move {
+///     while let Some(pending) = questions.recv().await {
+///         // Show `pending.question` in the application and wait for the reply.
+///         pending.answer("Use the staging database");
+///     }
+/// });
+///
+/// let mut agent = Agent::builder("Assistant")
+///     .config(config)
+///     .tool(Box::new(ask_user))
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AskUserTool {
+    handler: Arc<dyn QuestionHandler>,
+    timeout: Option<Duration>,
+}
+
+impl AskUserTool {
+    /// Creates a tool that asks `handler` for answers.
+    pub fn new(handler: impl QuestionHandler + 'static) -> Self {
+        Self {
+            handler: Arc::new(handler),
+            timeout: None,
+        }
+    }
+
+    /// Creates a tool that asks on the terminal.
+    pub fn console() -> Self {
+        Self::new(ConsoleQuestionHandler)
+    }
+
+    /// Creates a tool that sends each question to the returned receiver.
+    ///
+    /// The agent waits until the [`PendingQuestion`] is answered or dropped. At most
+    /// `buffer` questions can wait in the channel.
+    pub fn channel(buffer: usize) -> (Self, mpsc::Receiver<PendingQuestion>) {
+        let (sender, receiver) = mpsc::channel(buffer.max(1));
+        (Self::new(ChannelHandler { sender }), receiver)
+    }
+
+    /// Gives up waiting after `timeout`. Without one the agent waits indefinitely.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Reads the `choices` argument: a JSON array, or a string of `|`-separated options.
+fn parse_choices(value: Option<&Value>) -> Vec<String> {
+    let choices: Vec<String> = match value {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| item.as_str().map(str::to_string))
+            .collect(),
+        Some(Value::String(text)) => text.split('|').map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
+    choices
+        .into_iter()
+        .map(|choice| choice.trim().to_string())
+        .filter(|choice| !choice.is_empty())
+        .collect()
+}
+
+#[async_trait]
+impl Tool for AskUserTool {
+    fn name(&self) -> &str {
+        "ask_user"
+    }
+
+    fn description(&self) -> &str {
+        "Ask the user a question and wait for the answer. Use this instead of guessing when \
+         the request is ambiguous, information is missing, or a decision is the user's to make."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        params.insert(
+            "question".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "The question to ask, with enough context to answer it".to_string(),
+                required: Some(true),
+            },
+        );
+        params.insert(
+            "choices".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "Suggested answers separated by '|', if there are obvious options"
+                    .to_string(),
+                required: Some(false),
+            },
+        );
+        params
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::ReadOnly
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let question = required_str(&args, "question")?.trim();
+        if question.is_empty() {
+            return Err(HeliosError::ToolError(
+                "'question' parameter cannot be empty".to_string(),
+            ));
+        }
+        let question = UserQuestion {
+            question: question.to_string(),
+            choices: parse_choices(args.get("choices")),
+        };
+
+        let answer = match self.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, self.handler.ask(&question)).await
+            {
+                Ok(answer) => answer,
+                Err(_) => {
+                    return Ok(ToolResult::error(format!(
+                        "The user did not answer within {} seconds",
+                        timeout.as_secs()
+                    )))
+                }
+            },
+            None => self.handler.ask(&question).await,
+        };
+        Ok(match answer {
+            Some(answer) => ToolResult::success(answer),
+            None => ToolResult::error("The user did not answer the question"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Tests answering with a closure and reading choices.
+    #[tokio::test]
+    async fn test_ask_user_closure() {
+        let tool = AskUserTool::new(|question: &UserQuestion| {
+            assert_eq!(question.choices, vec!["staging", "production"]);
+            Some(format!("{}, please", question.choices[0]))
+        });
+        let result = tool
+            .execute(json!({"question": "Which database?", "choices": "staging | production"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, "staging, please");
+
+        assert!(tool.execute(json!({"question": "  "})).await.is_err());
+        assert_eq!(
+            parse_choices(Some(&json!(["a", "", "b"]))),
+            vec!["a".to_string(), "b".to_string()]
+        );
+    }
+
+    /// Tests answering through the channel, giving no answer, and timing out.
+    #[tokio::test]
+    async fn test_ask_user_channel() {
+        let (tool, mut questions) = AskUserTool::channel(1);
+        let tool = tool.timeout(Duration::from_secs(5));
+        let host = tokio::spawn(async move {
+            let first = questions.recv().await.unwrap();
+            assert_eq!(first.question.question, "Deploy now?");
+            first.answer("yes");
+            // Dropping the second question means no answer.
+            drop(questions.recv().await.unwrap());
+            // Keep the channel open but never answer the third.
+            let third = questions.recv().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            drop(third);
+        });
+
+        let result = tool
+            .execute(json!({"question": "Deploy now?"}))
+            .await
+            .unwrap();
+        assert_eq!(result.output, "yes");
+        let result = tool.execute(json!({"question": "Why?"})).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("did not answer the question"));
+
+        let tool = tool.timeout(Duration::from_millis(50));
+        let result = tool
+            .execute(json!({"question": "Still there?"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("did not answer within"));
+        host.abort();
+    }
+
+    /// Tests reading console answers, including picking a choice by number.
+    #[test]
+    fn test_console_answer() {
+        let choices = vec!["staging".to_string(), "production".to_string()];
+        assert_eq!(
+            console_answer("2\n", &choices).as_deref(),
+            Some("production")
+        );
+        assert_eq!(console_answer("3\n", &choices).as_deref(), Some("3"));
+        assert_eq!(
+            console_answer(" other db \n", &choices).as_deref(),
+            Some("other db")
+        );
+        assert_eq!(console_answer("\n", &choices), None);
+        assert_eq!(console_answer("0", &[]).as_deref(), Some("0"));
+    }
+}
@@ -0,0 +1,324 @@
+//! # Audit Module
+//!
+//! This module records every tool call an agent makes: the tool, its arguments,
+//! how long it ran, whether it succeeded and what it returned. The log is kept in
+//! memory for inspection and per-tool statistics, and can also be appended to a
+//! JSONL file that outlives the process, one line per call, for compliance
+//! review.
+
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// One tool call, as recorded in the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolInvocation {
+    /// The name of the agent that made the call.
+    pub agent: String,
+    /// The ID the model gave the call.
+    pub call_id: String,
+    /// The name of the tool.
+    pub tool: String,
+    /// The arguments the tool was called with.
+    pub arguments: Value,
+    /// When the call started.
+    pub started_at: DateTime<Utc>,
+    /// How long the call took, in milliseconds.
+    pub duration_ms: u64,
+    /// Whether the tool succeeded.
+    pub success: bool,
+    /// The tool's output, cut to the log's output limit.
+    pub output: String,
+    /// Whether `output` was cut.
+    pub output_truncated: bool,
+}
+
+/// Totals over the recorded calls to one tool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolStats {
+    /// The number of calls.
+    pub calls: u64,
+    /// The number of calls that failed.
+    pub failures: u64,
+    /// The time spent in the tool over all calls, in milliseconds.
+    pub total_duration_ms: u64,
+    /// The longest call, in milliseconds.
+    pub max_duration_ms: u64,
+}
+
+impl ToolStats {
+    /// Returns the mean duration of a call, or zero without calls.
+    pub fn mean_duration(&self) -> Duration {
+        match self.total_duration_ms.checked_div(self.calls) {
+            Some(mean) => Duration::from_millis(mean),
+            None => Duration::ZERO,
+        }
+    }
+}
+
+/// The shared state of a [`ToolAudit`].
+#[derive(Debug, Default)]
+struct AuditState {
+    entries: VecDeque<ToolInvocation>,
+    stats: BTreeMap<String, ToolStats>,
+    file: Option<File>,
+}
+
+/// The log of an agent's tool calls.
+///
+/// Clones share the same log. The most recent `max_entries` calls are kept in
+/// memory; statistics and the JSONL file cover every call.
+#[derive(Debug, Clone)]
+pub struct ToolAudit {
+    state: Arc<Mutex<AuditState>>,
+    path: Option<PathBuf>,
+    max_entries: usize,
+    max_output_chars: usize,
+}
+
+impl Default for ToolAudit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ToolAudit {
+    /// Creates an in-memory log keeping the last 1000 calls, with outputs cut
+    /// to 2000 characters.
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(AuditState::default())),
+            path: None,
+            max_entries: 1000,
+            max_output_chars: 2000,
+        }
+    }
+
+    /// Creates a log that also appends every call to the JSONL file at `path`.
+    ///
+    /// The file and its directory are created if needed; existing lines are kept.
+    pub fn with_jsonl(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let audit = Self::new();
+        audit.lock().file = Some(file);
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            ..audit
+        })
+    }
+
+    /// Sets how many calls are kept in memory.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Sets how many characters of each output are recorded.
+    pub fn max_output_chars(mut self, max_output_chars: usize) -> Self {
+        self.max_output_chars = max_output_chars;
+        self
+    }
+
+    /// Returns the JSONL file calls are appended to, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Locks the shared state, recovering it if a writer panicked.
+    fn lock(&self) -> std::sync::MutexGuard<'_, AuditState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Records a finished tool call.
+    ///
+    /// A failure to write the JSONL file is logged and does not fail the call.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn record(
+        &self,
+        agent: &str,
+        call_id: &str,
+        tool: &str,
+        arguments: Value,
+        started_at: DateTime<Utc>,
+        duration: Duration,
+        success: bool,
+        output: &str,
+    ) {
+        let (output, output_truncated) = match output.char_indices().nth(self.max_output_chars) {
+            Some((end, _)) => (output[..end].to_string(), true),
+            None => (output.to_string(), false),
+        };
+        let invocation = ToolInvocation {
+            agent: agent.to_string(),
+            call_id: call_id.to_string(),
+            tool: tool.to_string(),
+            arguments,
+            started_at,
+            duration_ms: duration.as_millis() as u64,
+            success,
+            output,
+            output_truncated,
+        };
+
+        let mut state = self.lock();
+        let stats = state.stats.entry(invocation.tool.clone()).or_default();
+        stats.calls += 1;
+        stats.failures += u64::from(!invocation.success);
+        stats.total_duration_ms += invocation.duration_ms;
+        stats.max_duration_ms = stats.max_duration_ms.max(invocation.duration_ms);
+
+        if let Some(file) = state.file.as_mut() {
+            let written = serde_json::to_string(&invocation)
+                .map_err(std::io::Error::from)
+                .and_then(|line| writeln!(file, "{}", line));
+            if let Err(e) = written {
+                tracing::warn!("Failed to write the tool audit log: {}", e);
+            }
+        }
+
+        if self.max_entries > 0 {
+            if state.entries.len() == self.max_entries {
+                state.entries.pop_front();
+            }
+            state.entries.push_back(invocation);
+        }
+    }
+
+    /// Returns the calls kept in memory, oldest first.
+    pub fn entries(&self) -> Vec<ToolInvocation> {
+        self.lock().entries.iter().cloned().collect()
+    }
+
+    /// Returns the calls to `tool` kept in memory, oldest first.
+    pub fn entries_for(&self, tool: &str) -> Vec<ToolInvocation> {
+        self.lock()
+            .entries
+            .iter()
+            .filter(|entry| entry.tool == tool)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the call with the ID `call_id`, if it is still kept in memory.
+    ///
+    /// IDs are only unique within a conversation, so the latest matching call is
+    /// returned.
+    pub fn find(&self, call_id: &str) -> Option<ToolInvocation> {
+        self.lock()
+            .entries
+            .iter()
+            .rev()
+            .find(|entry| entry.call_id == call_id)
+            .cloned()
+    }
+
+    /// Returns the statistics of every tool that was called, by name.
+    pub fn stats(&self) -> BTreeMap<String, ToolStats> {
+        self.lock().stats.clone()
+    }
+
+    /// Forgets the calls kept in memory and the statistics. The JSONL file is kept.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.stats.clear();
+    }
+
+    /// Reads the calls recorded in a JSONL audit file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Vec<ToolInvocation>> {
+        std::fs::read_to_string(path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Ok(serde_json::from_str(line)?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests recording, truncation, statistics, the memory bound and the JSONL file.
+    #[test]
+    fn test_tool_audit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/tools.jsonl");
+        let audit = ToolAudit::with_jsonl(&path)
+            .unwrap()
+            .max_entries(2)
+            .max_output_chars(5);
+        let started = Utc::now();
+        let args = serde_json::json!({"expression": "2+2"});
+        audit.record(
+            "a",
+            "call_1",
+            "calculator",
+            args.clone(),
+            started,
+            Duration::from_millis(4),
+            true,
+            "4",
+        );
+        audit.record(
+            "a",
+            "call_2",
+            "file_read",
+            Value::Null,
+            started,
+            Duration::from_millis(10),
+            false,
+            "no such file",
+        );
+        audit.record(
+            "a",
+            "call_3",
+            "calculator",
+            args,
+            started,
+            Duration::from_millis(2),
+            true,
+            "héllo wörld",
+        );
+
+        let entries = audit.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].call_id, "call_2");
+        assert_eq!(
+            (entries[1].output.as_str(), entries[1].output_truncated),
+            ("héllo", true)
+        );
+        assert_eq!(audit.entries_for("calculator").len(), 1);
+
+        let stats = audit.stats();
+        assert_eq!(stats["calculator"].calls, 2);
+        assert_eq!(stats["calculator"].max_duration_ms, 4);
+        assert_eq!(
+            stats["calculator"].mean_duration(),
+            Duration::from_millis(3)
+        );
+        assert_eq!(stats["file_read"].failures, 1);
+
+        let logged = ToolAudit::load(&path).unwrap();
+        assert_eq!(logged.len(), 3);
+        assert_eq!(logged[0].arguments["expression"], "2+2");
+        assert_eq!(logged[0].started_at, started);
+
+        audit.clear();
+        assert!(audit.entries().is_empty() && audit.stats().is_empty());
+        assert_eq!(ToolAudit::load(&path).unwrap().len(), 3);
+    }
+}
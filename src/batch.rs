@@ -0,0 +1,283 @@
+//! # Batch Module
+//!
+//! This module runs many independent chat requests as one job, for offline work
+//! such as labeling a dataset overnight. Providers with the OpenAI Batch API get a
+//! single JSONL upload that is processed within the completion window, usually at
+//! a lower price. Other providers are called directly with a bounded number of
+//! requests in flight.
+//!
+//! Either way every request gets its own result, so one bad prompt does not fail
+//! the rest of the batch.
+
+use crate::error::{HeliosError, Result};
+use crate::llm::{LLMRequest, LLMResponse, RemoteLLMClient};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The endpoint the batched requests are sent to.
+const BATCH_ENDPOINT: &str = "/v1/chat/completions";
+
+/// How a batch is run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchMode {
+    /// Use the provider's Batch API when it has one, and concurrent requests otherwise.
+    #[default]
+    Auto,
+    /// Always use the provider's Batch API, failing when the provider is not remote.
+    Provider,
+    /// Always send the requests directly.
+    Concurrent,
+}
+
+/// Options for [`LLMClient::chat_batch_with`](crate::LLMClient::chat_batch_with).
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// How the batch is run.
+    pub mode: BatchMode,
+    /// The most requests in flight at once when sending them directly.
+    pub max_concurrency: usize,
+    /// How often the status of a provider batch is checked.
+    pub poll_interval: Duration,
+    /// The time the provider has to finish a batch, such as `"24h"`.
+    pub completion_window: String,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            mode: BatchMode::Auto,
+            max_concurrency: 8,
+            poll_interval: Duration::from_secs(30),
+            completion_window: "24h".to_string(),
+        }
+    }
+}
+
+impl BatchOptions {
+    /// Sets how the batch is run.
+    pub fn mode(mut self, mode: BatchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the most requests in flight at once when sending them directly.
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency;
+        self
+    }
+
+    /// Sets how often the status of a provider batch is checked.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+}
+
+/// An uploaded file.
+#[derive(Debug, Deserialize)]
+struct FileObject {
+    id: String,
+}
+
+/// A batch and its progress.
+#[derive(Debug, Deserialize)]
+struct BatchObject {
+    id: String,
+    status: String,
+    #[serde(default)]
+    output_file_id: Option<String>,
+    #[serde(default)]
+    error_file_id: Option<String>,
+    #[serde(default)]
+    errors: Option<serde_json::Value>,
+}
+
+/// One line of a batch's output or error file.
+#[derive(Debug, Deserialize)]
+struct OutputLine {
+    custom_id: String,
+    #[serde(default)]
+    response: Option<OutputResponse>,
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+}
+
+/// The response to one batched request.
+#[derive(Debug, Deserialize)]
+struct OutputResponse {
+    status_code: u16,
+    body: serde_json::Value,
+}
+
+/// Returns the `custom_id` of the request at `index`.
+fn custom_id(index: usize) -> String {
+    format!("request-{}", index)
+}
+
+/// Runs `requests` through the provider's Batch API and waits for the results.
+///
+/// The results are in the order of `requests`. An error for the whole call means
+/// the batch could not be submitted or failed validation.
+pub(crate) async fn run(
+    client: &RemoteLLMClient,
+    requests: Vec<LLMRequest>,
+    options: &BatchOptions,
+) -> Result<Vec<Result<LLMResponse>>> {
+    let mut results: Vec<Option<Result<LLMResponse>>> = Vec::with_capacity(requests.len());
+    let mut input = String::new();
+    for (index, request) in requests.iter().enumerate() {
+        match client.prepare(request) {
+            Ok(request) => {
+                let line = serde_json::json!({
+                    "custom_id": custom_id(index),
+                    "method": "POST",
+                    "url": BATCH_ENDPOINT,
+                    "body": request,
+                });
+                input.push_str(&line.to_string());
+                input.push('\n');
+                results.push(None);
+            }
+            Err(e) => results.push(Some(Err(e))),
+        }
+    }
+    if input.is_empty() {
+        return Ok(results.into_iter().flatten().collect());
+    }
+
+    let base_url = &client.config().base_url;
+    let form = reqwest::multipart::Form::new()
+        .text("purpose", "batch")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(input.into_bytes())
+                .file_name("batch.jsonl")
+                .mime_str("application/jsonl")?,
+        );
+    let file: FileObject = parse(
+        client
+            .authorize(client.http().post(format!("{}/files", base_url)))
+            .multipart(form)
+            .send()
+            .await?,
+    )
+    .await?;
+
+    let mut batch: BatchObject = parse(
+        client
+            .authorize(client.http().post(format!("{}/batches", base_url)))
+            .json(&serde_json::json!({
+                "input_file_id": file.id,
+                "endpoint": BATCH_ENDPOINT,
+                "completion_window": options.completion_window,
+            }))
+            .send()
+            .await?,
+    )
+    .await?;
+    tracing::info!(
+        "Submitted batch {} with {} requests",
+        batch.id,
+        results.iter().filter(|result| result.is_none()).count()
+    );
+
+    while !matches!(
+        batch.status.as_str(),
+        "completed" | "failed" | "expired" | "cancelled"
+    ) {
+        tokio::time::sleep(options.poll_interval).await;
+        batch = parse(
+            client
+                .authorize(
+                    client
+                        .http()
+                        .get(format!("{}/batches/{}", base_url, batch.id)),
+                )
+                .send()
+                .await?,
+        )
+        .await?;
+        tracing::debug!("Batch {} is {}", batch.id, batch.status);
+    }
+    if batch.status == "failed" {
+        return Err(HeliosError::LLMError(format!(
+            "Batch {} failed: {}",
+            batch.id,
+            batch.errors.unwrap_or_default()
+        )));
+    }
+
+    let mut lines = HashMap::new();
+    for file_id in [&batch.output_file_id, &batch.error_file_id]
+        .into_iter()
+        .flatten()
+    {
+        let content = client
+            .authorize(
+                client
+                    .http()
+                    .get(format!("{}/files/{}/content", base_url, file_id)),
+            )
+            .send()
+            .await?;
+        let content = check(content).await?.text().await?;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let line: OutputLine = serde_json::from_str(line)?;
+            lines.insert(line.custom_id.clone(), line);
+        }
+    }
+
+    Ok(results
+        .into_iter()
+        .enumerate()
+        .map(|(index, result)| {
+            result.unwrap_or_else(|| match lines.remove(&custom_id(index)) {
+                Some(line) => read_line(line),
+                None => Err(HeliosError::LLMError(format!(
+                    "Batch {} ended as {} without a result for request {}",
+                    batch.id, batch.status, index
+                ))),
+            })
+        })
+        .collect())
+}
+
+/// Turns one line of the output or error file into the request's result.
+fn read_line(line: OutputLine) -> Result<LLMResponse> {
+    match line.response {
+        Some(response) if (200..300).contains(&response.status_code) => {
+            Ok(serde_json::from_value(response.body)?)
+        }
+        Some(response) => Err(HeliosError::LLMError(format!(
+            "Batched request failed with status {}: {}",
+            response.status_code, response.body
+        ))),
+        None => Err(HeliosError::LLMError(format!(
+            "Batched request failed: {}",
+            line.error.unwrap_or_default()
+        ))),
+    }
+}
+
+/// Fails on an unsuccessful response, keeping the body in the error.
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let error_text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+    Err(HeliosError::LLMError(format!(
+        "Batch API request failed with status {}: {}",
+        status, error_text
+    )))
+}
+
+/// Parses a successful JSON response.
+async fn parse<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    Ok(check(response).await?.json().await?)
+}
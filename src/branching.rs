@@ -0,0 +1,173 @@
+//! # Branching Module
+//!
+//! A conversation can branch: from any point of it, a fork continues
+//! independently while the original stays as it was. [`ConversationTree`]
+//! records how the branches relate, so an interface can show alternative
+//! continuations side by side and let the user move between them.
+//!
+//! The tree holds only the shape of the conversation. The messages of each
+//! branch are a [`crate::ChatSession`], made with [`crate::ChatSession::fork_at`]
+//! and kept by the agent, see [`crate::Agent::fork`].
+
+use crate::error::{HeliosError, Result};
+use serde::{Deserialize, Serialize};
+
+/// The id of the branch every conversation starts on.
+pub const MAIN_BRANCH: &str = "main";
+
+/// A branch of a conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Branch {
+    /// The id of the branch.
+    pub id: String,
+    /// The branch it was forked from; `None` for the main branch.
+    pub parent: Option<String>,
+    /// How many of the parent's messages the branch starts with.
+    pub fork_point: usize,
+}
+
+/// How the branches of a conversation were forked from one another.
+///
+/// The tree always contains [`MAIN_BRANCH`]. Branches are listed in the order
+/// they were created.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationTree {
+    branches: Vec<Branch>,
+}
+
+impl Default for ConversationTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConversationTree {
+    /// Creates a tree holding only the main branch.
+    pub fn new() -> Self {
+        Self {
+            branches: vec![Branch {
+                id: MAIN_BRANCH.to_string(),
+                parent: None,
+                fork_point: 0,
+            }],
+        }
+    }
+
+    /// Records a new branch of `parent` starting with its first `fork_point`
+    /// messages, and returns the new branch's id.
+    pub fn fork(&mut self, parent: &str, fork_point: usize) -> Result<String> {
+        self.require(parent)?;
+        let id = crate::clock::new_id().to_string();
+        self.branches.push(Branch {
+            id: id.clone(),
+            parent: Some(parent.to_string()),
+            fork_point,
+        });
+        Ok(id)
+    }
+
+    /// Returns the branch with the given id.
+    pub fn get(&self, id: &str) -> Option<&Branch> {
+        self.branches.iter().find(|branch| branch.id == id)
+    }
+
+    /// Returns whether the tree has a branch with the given id.
+    pub fn contains(&self, id: &str) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Returns every branch, in the order they were created.
+    pub fn branches(&self) -> &[Branch] {
+        &self.branches
+    }
+
+    /// Returns the branches forked directly from `id`.
+    pub fn children(&self, id: &str) -> Vec<&Branch> {
+        self.branches
+            .iter()
+            .filter(|branch| branch.parent.as_deref() == Some(id))
+            .collect()
+    }
+
+    /// Returns the path from the main branch to `id`, both included.
+    pub fn path(&self, id: &str) -> Result<Vec<&Branch>> {
+        let mut path = vec![self.require(id)?];
+        while let Some(parent) = path[path.len() - 1].parent.as_deref() {
+            path.push(self.require(parent)?);
+        }
+        path.reverse();
+        Ok(path)
+    }
+
+    /// Removes a branch and every branch forked from it, returning their ids.
+    ///
+    /// The main branch cannot be removed.
+    pub fn remove(&mut self, id: &str) -> Result<Vec<String>> {
+        self.require(id)?;
+        if id == MAIN_BRANCH {
+            return Err(HeliosError::AgentError(
+                "The main branch cannot be removed".to_string(),
+            ));
+        }
+        let mut removed = vec![id.to_string()];
+        let mut i = 0;
+        while i < removed.len() {
+            let children: Vec<String> = self
+                .children(&removed[i])
+                .into_iter()
+                .map(|branch| branch.id.clone())
+                .collect();
+            removed.extend(children);
+            i += 1;
+        }
+        self.branches.retain(|branch| !removed.contains(&branch.id));
+        Ok(removed)
+    }
+
+    /// Returns the branch with the given id, or an error naming it.
+    fn require(&self, id: &str) -> Result<&Branch> {
+        self.get(id)
+            .ok_or_else(|| HeliosError::AgentError(format!("Unknown branch '{}'", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests forking, walking and removing branches.
+    #[test]
+    fn test_conversation_tree() {
+        let mut tree = ConversationTree::new();
+        let a = tree.fork(MAIN_BRANCH, 2).unwrap();
+        let b = tree.fork(MAIN_BRANCH, 4).unwrap();
+        let a1 = tree.fork(&a, 3).unwrap();
+        assert!(tree.fork("missing", 0).is_err());
+
+        let children: Vec<&str> = tree
+            .children(MAIN_BRANCH)
+            .iter()
+            .map(|branch| branch.id.as_str())
+            .collect();
+        assert_eq!(children, vec![a.as_str(), b.as_str()]);
+        let path: Vec<&str> = tree
+            .path(&a1)
+            .unwrap()
+            .iter()
+            .map(|branch| branch.id.as_str())
+            .collect();
+        assert_eq!(path, vec![MAIN_BRANCH, a.as_str(), a1.as_str()]);
+        assert_eq!(tree.get(&a1).unwrap().fork_point, 3);
+
+        assert_eq!(tree.remove(&a).unwrap(), vec![a.clone(), a1.clone()]);
+        assert!(!tree.contains(&a1));
+        assert_eq!(tree.branches().len(), 2);
+        assert!(tree.remove(MAIN_BRANCH).is_err());
+
+        let json = serde_json::to_string(&tree).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ConversationTree>(&json).unwrap(),
+            tree
+        );
+    }
+}
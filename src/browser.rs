@@ -0,0 +1,541 @@
+//! # Browser Module
+//!
+//! `BrowserTool` drives a headless Chrome or Chromium through the DevTools
+//! protocol, for pages that build their content with JavaScript. `WebScraperTool`
+//! only sees the HTML the server sends, which for single-page applications is an
+//! empty shell; the browser runs the scripts and reads the rendered page.
+//!
+//! The browser is started on the first call and reused, so an agent can open a
+//! page, click through it and take screenshots across several tool calls. It is
+//! closed with the `close` action or when the tool is dropped.
+//!
+//! Requires the `browser` feature and a Chrome or Chromium installation.
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{required_str, Tool, ToolParameter, ToolPermission, ToolResult};
+use crate::truncation::truncate_chars;
+use async_trait::async_trait;
+use chromiumoxide::browser::{Browser, BrowserConfig};
+use chromiumoxide::error::CdpError;
+use chromiumoxide::page::ScreenshotParams;
+use chromiumoxide::Page;
+use futures::StreamExt;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// How long to wait for a page to load or a selector to appear by default.
+const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How many characters of page text are returned by default.
+const DEFAULT_MAX_CONTENT_CHARS: usize = 20_000;
+
+/// The most links the `links` action returns.
+const MAX_LINKS: usize = 100;
+
+/// The actions the tool accepts.
+const ACTIONS: &[&str] = &[
+    "open",
+    "text",
+    "links",
+    "click",
+    "type",
+    "screenshot",
+    "close",
+];
+
+/// A running browser and the page the agent is working on.
+struct BrowserSession {
+    browser: Browser,
+    page: Page,
+    handler: JoinHandle<()>,
+}
+
+impl BrowserSession {
+    async fn close(mut self) {
+        let _ = self.browser.close().await;
+        let _ = self.browser.wait().await;
+        self.handler.abort();
+    }
+}
+
+/// A tool that loads pages in a headless browser, waits for them to render, and
+/// reads text and links, clicks, types and takes screenshots.
+///
+/// ```rust,no_run
+/// use helios_engine::{Agent, BrowserTool, Config};
+///
+/// # async fn example() -> helios_engine::Result<()> {
+/// let agent = Agent::builder("Researcher")
+///     .config(Config::from_file("config.toml")?)
+///     .tool(Box::new(
+///         BrowserTool::new()
+///             .no_sandbox() // needed when running as root in a container
+///             .screenshot_dir("./screenshots"),
+///     ))
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct BrowserTool {
+    chrome_executable: Option<PathBuf>,
+    no_sandbox: bool,
+    wait_timeout: Duration,
+    max_content_chars: usize,
+    screenshot_dir: PathBuf,
+    session: Mutex<Option<BrowserSession>>,
+}
+
+impl BrowserTool {
+    /// Creates a tool that finds Chrome or Chromium on the system, waits up to
+    /// 15 seconds for pages and returns up to 20,000 characters of text.
+    pub fn new() -> Self {
+        Self {
+            chrome_executable: None,
+            no_sandbox: false,
+            wait_timeout: DEFAULT_WAIT_TIMEOUT,
+            max_content_chars: DEFAULT_MAX_CONTENT_CHARS,
+            screenshot_dir: std::env::temp_dir(),
+            session: Mutex::new(None),
+        }
+    }
+
+    /// Uses the browser at `path` instead of searching for one.
+    pub fn chrome_executable(mut self, path: impl Into<PathBuf>) -> Self {
+        self.chrome_executable = Some(path.into());
+        self
+    }
+
+    /// Starts the browser without its sandbox, which Chrome requires when it
+    /// runs as root, as it usually does in containers.
+    pub fn no_sandbox(mut self) -> Self {
+        self.no_sandbox = true;
+        self
+    }
+
+    /// Sets how long to wait for navigation and for selectors to appear.
+    pub fn wait_timeout(mut self, timeout: Duration) -> Self {
+        self.wait_timeout = timeout;
+        self
+    }
+
+    /// Sets how many characters of page text are returned before truncating.
+    pub fn max_content_chars(mut self, max_chars: usize) -> Self {
+        self.max_content_chars = max_chars;
+        self
+    }
+
+    /// Sets the directory screenshots are saved in (the system temp directory by default).
+    pub fn screenshot_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.screenshot_dir = dir.into();
+        self
+    }
+
+    /// Starts the browser with a blank page.
+    async fn launch(&self) -> Result<BrowserSession> {
+        let mut config = BrowserConfig::builder().request_timeout(self.wait_timeout);
+        if self.no_sandbox {
+            config = config.no_sandbox();
+        }
+        if let Some(path) = &self.chrome_executable {
+            config = config.chrome_executable(path);
+        }
+        let config = config.build().map_err(|e| {
+            HeliosError::ToolError(format!("Failed to configure the browser: {}", e))
+        })?;
+
+        let (browser, mut handler) = Browser::launch(config)
+            .await
+            .map_err(cdp_error("Failed to start Chrome or Chromium"))?;
+        // The handler drives the DevTools connection and must be polled until the browser closes.
+        let handler = tokio::spawn(async move { while handler.next().await.is_some() {} });
+        let page = browser
+            .new_page("about:blank")
+            .await
+            .map_err(cdp_error("Failed to open a page"))?;
+        Ok(BrowserSession {
+            browser,
+            page,
+            handler,
+        })
+    }
+
+    /// Waits until an element matches `selector`, or fails after the wait timeout.
+    async fn wait_for_selector(&self, page: &Page, selector: &str) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + self.wait_timeout;
+        loop {
+            if page.find_element(selector).await.is_ok() {
+                return Ok(());
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(HeliosError::ToolError(format!(
+                    "No element matched '{}' within {} seconds",
+                    selector,
+                    self.wait_timeout.as_secs()
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+
+    /// Returns the visible text of the page, or of the first element matching `selector`.
+    async fn page_text(&self, page: &Page, selector: Option<&str>) -> Result<String> {
+        let text = match selector {
+            Some(selector) => page
+                .find_element(selector)
+                .await
+                .map_err(cdp_error(&format!("No element matched '{}'", selector)))?
+                .inner_text()
+                .await
+                .map_err(cdp_error("Failed to read the element's text"))?
+                .unwrap_or_default(),
+            None => page
+                .evaluate("document.body ? document.body.innerText : ''")
+                .await
+                .map_err(cdp_error("Failed to read the page text"))?
+                .into_value::<String>()
+                .unwrap_or_default(),
+        };
+        Ok(truncate_page_text(text.trim(), self.max_content_chars))
+    }
+
+    /// Describes the page: its URL and title.
+    async fn page_summary(page: &Page) -> String {
+        let url = page.url().await.ok().flatten().unwrap_or_default();
+        let title = page.get_title().await.ok().flatten().unwrap_or_default();
+        format!("URL: {}\nTitle: {}", url, title)
+    }
+
+    /// Runs one action on the current page.
+    async fn run_action(&self, page: &Page, action: &str, args: &Value) -> Result<String> {
+        let selector = args.get("selector").and_then(|v| v.as_str());
+        let require_selector = || {
+            selector.ok_or_else(|| {
+                HeliosError::ToolError(format!("The '{}' action needs a 'selector'", action))
+            })
+        };
+
+        match action {
+            "open" => {
+                let url = args.get("url").and_then(|v| v.as_str()).unwrap_or_default();
+                page.goto(url)
+                    .await
+                    .map_err(cdp_error(&format!("Failed to load {}", url)))?;
+                page.wait_for_navigation()
+                    .await
+                    .map_err(cdp_error(&format!("Failed to load {}", url)))?;
+                self.settle(page, args).await?;
+                Ok(format!(
+                    "{}\n\n{}",
+                    Self::page_summary(page).await,
+                    self.page_text(page, None).await?
+                ))
+            }
+            "text" => self.page_text(page, selector).await,
+            "links" => {
+                let links: Vec<(String, String)> = page
+                    .evaluate(format!(
+                        "Array.from(document.querySelectorAll('a[href]')).slice(0, {}).map(a => [a.innerText.trim(), a.href])",
+                        MAX_LINKS
+                    ))
+                    .await
+                    .map_err(cdp_error("Failed to read the links"))?
+                    .into_value()
+                    .unwrap_or_default();
+                if links.is_empty() {
+                    return Ok("The page has no links".to_string());
+                }
+                Ok(links
+                    .into_iter()
+                    .map(|(text, href)| format!("{} -> {}", text.replace('\n', " "), href))
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+            "click" => {
+                let selector = require_selector()?;
+                page.find_element(selector)
+                    .await
+                    .map_err(cdp_error(&format!("No element matched '{}'", selector)))?
+                    .click()
+                    .await
+                    .map_err(cdp_error(&format!("Failed to click '{}'", selector)))?;
+                self.settle(page, args).await?;
+                Ok(format!(
+                    "Clicked '{}'\n{}",
+                    selector,
+                    Self::page_summary(page).await
+                ))
+            }
+            "type" => {
+                let selector = require_selector()?;
+                let text = args.get("text").and_then(|v| v.as_str()).ok_or_else(|| {
+                    HeliosError::ToolError("The 'type' action needs 'text'".to_string())
+                })?;
+                let element = page
+                    .find_element(selector)
+                    .await
+                    .map_err(cdp_error(&format!("No element matched '{}'", selector)))?;
+                element
+                    .click()
+                    .await
+                    .map_err(cdp_error(&format!("Failed to focus '{}'", selector)))?;
+                element
+                    .type_str(text)
+                    .await
+                    .map_err(cdp_error(&format!("Failed to type into '{}'", selector)))?;
+                if args
+                    .get("submit")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false)
+                {
+                    element
+                        .press_key("Enter")
+                        .await
+                        .map_err(cdp_error("Failed to press Enter"))?;
+                    self.settle(page, args).await?;
+                }
+                Ok(format!("Typed into '{}'", selector))
+            }
+            "screenshot" => {
+                std::fs::create_dir_all(&self.screenshot_dir)?;
+                let path = self.screenshot_dir.join(format!(
+                    "helios-screenshot-{}.png",
+                    crate::clock::now().format("%Y%m%d-%H%M%S%.3f")
+                ));
+                let full_page = args
+                    .get("full_page")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let bytes = page
+                    .save_screenshot(
+                        ScreenshotParams::builder().full_page(full_page).build(),
+                        &path,
+                    )
+                    .await
+                    .map_err(cdp_error("Failed to take a screenshot"))?;
+                Ok(format!(
+                    "Saved a {} byte PNG screenshot to {}",
+                    bytes.len(),
+                    path.display()
+                ))
+            }
+            _ => unreachable!("actions are checked before the browser starts"),
+        }
+    }
+
+    /// Waits for the `wait_for` selector, if given, and then `wait_ms` more.
+    async fn settle(&self, page: &Page, args: &Value) -> Result<()> {
+        if let Some(selector) = args.get("wait_for").and_then(|v| v.as_str()) {
+            self.wait_for_selector(page, selector).await?;
+        }
+        if let Some(ms) = args.get("wait_ms").and_then(|v| v.as_u64()) {
+            tokio::time::sleep(Duration::from_millis(ms.min(30_000))).await;
+        }
+        Ok(())
+    }
+}
+
+impl Default for BrowserTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Wraps a DevTools error in a tool error with some context.
+fn cdp_error(context: &str) -> impl Fn(CdpError) -> HeliosError + '_ {
+    move |e| HeliosError::ToolError(format!("{}: {}", context, e))
+}
+
+/// Cuts `text` to `max` characters, noting how much was left out.
+fn truncate_page_text(text: &str, max: usize) -> String {
+    let total = text.chars().count();
+    truncate_chars(
+        text,
+        max,
+        &format!("\n\n[truncated, {} of {} characters shown]", max, total),
+    )
+}
+
+/// Checks the arguments before a browser is started for them.
+fn validate_args(args: &Value) -> Result<&str> {
+    let action = required_str(args, "action")?;
+    if !ACTIONS.contains(&action) {
+        return Err(HeliosError::ToolError(format!(
+            "Unknown action '{}'; use one of {}",
+            action,
+            ACTIONS.join(", ")
+        )));
+    }
+    if action == "open" {
+        let url = args
+            .get("url")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| HeliosError::ToolError("The 'open' action needs a 'url'".to_string()))?;
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| HeliosError::ToolError(format!("Invalid URL '{}': {}", url, e)))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(HeliosError::ToolError(format!(
+                "Unsupported URL scheme '{}'; only http and https are allowed",
+                parsed.scheme()
+            )));
+        }
+    }
+    Ok(action)
+}
+
+#[async_trait]
+impl Tool for BrowserTool {
+    fn name(&self) -> &str {
+        "browser"
+    }
+
+    fn description(&self) -> &str {
+        "Control a headless web browser that runs JavaScript, for pages that render their content client-side. \
+         Actions: 'open' a URL and return its text, read the 'text' of the page or an element, list its 'links', \
+         'click' or 'type' into an element by CSS selector, take a 'screenshot', or 'close' the browser. \
+         The page stays open between calls."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        let mut add = |name: &str, param_type: &str, description: &str, required: bool| {
+            params.insert(
+                name.to_string(),
+                ToolParameter {
+                    param_type: param_type.to_string(),
+                    description: description.to_string(),
+                    required: Some(required),
+                },
+            );
+        };
+        add(
+            "action",
+            "string",
+            "One of: open, text, links, click, type, screenshot, close",
+            true,
+        );
+        add(
+            "url",
+            "string",
+            "The http or https URL to open ('open')",
+            false,
+        );
+        add(
+            "selector",
+            "string",
+            "A CSS selector: the element to click or type into, or to read the text of",
+            false,
+        );
+        add("text", "string", "The text to type ('type')", false);
+        add(
+            "submit",
+            "boolean",
+            "Press Enter after typing (default: false)",
+            false,
+        );
+        add(
+            "wait_for",
+            "string",
+            "A CSS selector to wait for after opening, clicking or submitting",
+            false,
+        );
+        add(
+            "wait_ms",
+            "number",
+            "Extra milliseconds to wait for the page to render (at most 30000)",
+            false,
+        );
+        add(
+            "full_page",
+            "boolean",
+            "Capture the whole page rather than the viewport ('screenshot', default: false)",
+            false,
+        );
+        params
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Standard
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let action = validate_args(&args)?;
+        let mut session = self.session.lock().await;
+
+        if action == "close" {
+            return Ok(ToolResult::success(match session.take() {
+                Some(open) => {
+                    open.close().await;
+                    "Browser closed"
+                }
+                None => "The browser was not running",
+            }));
+        }
+        if session.is_none() {
+            *session = Some(self.launch().await?);
+        }
+        let Some(open) = session.as_ref() else {
+            unreachable!("the session was just started");
+        };
+        if action != "open"
+            && open.page.url().await.ok().flatten().as_deref() == Some("about:blank")
+        {
+            return Ok(ToolResult::error(
+                "No page is open yet; use the 'open' action first",
+            ));
+        }
+
+        match self.run_action(&open.page, action, &args).await {
+            Ok(output) => Ok(ToolResult::success(output)),
+            Err(HeliosError::ToolError(message)) => Ok(ToolResult::error(message)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Drop for BrowserTool {
+    fn drop(&mut self) {
+        // Chrome is started with kill-on-drop, so only the handler task needs stopping.
+        if let Some(session) = self.session.get_mut().take() {
+            session.handler.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Tests that bad arguments are rejected before a browser is started.
+    #[tokio::test]
+    async fn test_browser_tool_validates_arguments() {
+        let tool = BrowserTool::new();
+        for args in [
+            json!({}),
+            json!({"action": "scroll"}),
+            json!({"action": "open"}),
+            json!({"action": "open", "url": "file:///etc/passwd"}),
+            json!({"action": "open", "url": "not a url"}),
+        ] {
+            assert!(tool.execute(args).await.is_err());
+        }
+
+        let result = tool.execute(json!({"action": "close"})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, "The browser was not running");
+    }
+
+    /// Tests that long page text is cut to the configured length.
+    #[test]
+    fn test_truncate_page_text() {
+        assert_eq!(truncate_page_text("short", 10), "short");
+        let cut = truncate_page_text("abcdef", 3);
+        assert!(cut.starts_with("abc\n"));
+        assert!(cut.contains("3 of 6 characters"));
+    }
+}
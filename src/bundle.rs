@@ -0,0 +1,397 @@
+//! # Bundle Module
+//!
+//! This module defines agent bundles: a single versioned file holding everything
+//! that makes up an agent persona — its system prompt, the tools it uses, the model
+//! settings it was tuned for and its guardrails. Bundles let personas be shared
+//! between users and loaded with `Agent::from_bundle`.
+//!
+//! Bundles are TOML by default, or JSON when the file name ends in `.json`. They
+//! never contain API keys: the model profile is applied on top of the loader's own
+//! configuration.
+//!
+//! ```toml
+//! format_version = 1
+//! name = "Reviewer"
+//! description = "Reviews Rust code in the current project"
+//! system_prompt = "You are a meticulous Rust reviewer."
+//!
+//! [[tools]]
+//! name = "file_read"
+//!
+//! [[tools]]
+//! name = "shell_command"
+//!
+//! [model]
+//! model_name = "gpt-4o"
+//! temperature = 0.2
+//!
+//! [guardrails]
+//! max_iterations = 8
+//! workspace = "."
+//!
+//! [guardrails.shell_policy]
+//! allowed_programs = ["cargo", "git"]
+//! ```
+
+use crate::agent::{Agent, AgentBuilder};
+use crate::config::Config;
+use crate::error::{HeliosError, Result};
+use crate::path_utils::PathJail;
+use crate::shell_policy::ShellPolicy;
+use crate::tools::{builtin_tool, SandboxedTool, Tool, ToolPermission};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// The bundle format version written by this version of Helios.
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// The built-in tools that touch the file system and are confined to the workspace.
+const FILE_TOOLS: &[&str] = &[
+    "file_search",
+    "file_read",
+    "file_write",
+    "file_edit",
+    "file_io",
+    "file_list",
+];
+
+/// A shareable agent definition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentBundle {
+    /// The version of the bundle format, used to refuse bundles from newer releases.
+    pub format_version: u32,
+    /// The name of the agent.
+    pub name: String,
+    /// A short description of what the agent is for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The version of the persona itself, chosen by its author.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Who wrote the persona.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// The agent's system prompt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// The tools the agent uses.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<ToolManifestEntry>,
+    /// The model settings the persona was written for.
+    #[serde(default)]
+    pub model: ModelProfile,
+    /// Limits on what the agent may do.
+    #[serde(default)]
+    pub guardrails: Guardrails,
+}
+
+/// A tool listed in a bundle.
+///
+/// Built-in tools are resolved by name. Other tools must be supplied by the code
+/// loading the bundle; their description and parameters are recorded so readers
+/// of the bundle know what is expected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolManifestEntry {
+    /// The tool's name, e.g. `file_read`.
+    pub name: String,
+    /// The tool's description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The JSON schema of the tool's parameters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Value>,
+}
+
+/// Model settings applied on top of the loader's `[llm]` configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelProfile {
+    /// The model to use.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_name: Option<String>,
+    /// The API base URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// The sampling temperature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// The maximum number of tokens to generate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+/// Limits a bundle places on its agent.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Guardrails {
+    /// The most tool-calling rounds in one turn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_iterations: Option<usize>,
+    /// Whether the agent reasons before acting.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub react: bool,
+    /// A custom reasoning prompt for ReAct mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub react_prompt: Option<String>,
+    /// Stop sequences enforced on every response.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+    /// Regular expressions that end a response.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_patterns: Vec<String>,
+    /// A directory the built-in file tools are confined to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<PathBuf>,
+    /// The policy for the built-in `shell_command` tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell_policy: Option<ShellPolicy>,
+    /// Permission levels overriding the tools' own, keyed by tool name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tool_permissions: BTreeMap<String, ToolPermission>,
+}
+
+impl AgentBundle {
+    /// Creates an empty bundle for an agent called `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            format_version: BUNDLE_FORMAT_VERSION,
+            name: name.into(),
+            description: None,
+            version: None,
+            author: None,
+            system_prompt: None,
+            tools: Vec::new(),
+            model: ModelProfile::default(),
+            guardrails: Guardrails::default(),
+        }
+    }
+
+    /// Reads a bundle from a TOML file, or a JSON file if the name ends in `.json`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            HeliosError::ConfigError(format!(
+                "Failed to read agent bundle {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        if is_json(path) {
+            Self::from_json(&content)
+        } else {
+            Self::from_toml(&content)
+        }
+    }
+
+    /// Parses a bundle from TOML.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let bundle: Self = toml::from_str(content)?;
+        bundle.check_version()?;
+        Ok(bundle)
+    }
+
+    /// Parses a bundle from JSON.
+    pub fn from_json(content: &str) -> Result<Self> {
+        let bundle: Self = serde_json::from_str(content)?;
+        bundle.check_version()?;
+        Ok(bundle)
+    }
+
+    /// Writes the bundle as TOML, or as JSON if the name ends in `.json`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let content = if is_json(path) {
+            serde_json::to_string_pretty(self)?
+        } else {
+            toml::to_string_pretty(self).map_err(|e| {
+                HeliosError::ConfigError(format!("Failed to serialize agent bundle: {}", e))
+            })?
+        };
+        std::fs::write(path, content).map_err(|e| {
+            HeliosError::ConfigError(format!(
+                "Failed to write agent bundle {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Refuses bundles written in a newer format than this release understands.
+    fn check_version(&self) -> Result<()> {
+        if self.format_version == 0 || self.format_version > BUNDLE_FORMAT_VERSION {
+            return Err(HeliosError::ConfigError(format!(
+                "Agent bundle '{}' uses format version {}, but this version of Helios supports up to {}",
+                self.name, self.format_version, BUNDLE_FORMAT_VERSION
+            )));
+        }
+        Ok(())
+    }
+
+    /// Applies the bundle's model profile and stop conditions to `config`.
+    ///
+    /// `into_builder` does this itself; it is useful when a configuration is
+    /// loaded again later, for example when a server reloads its config file.
+    pub fn apply_to_config(&self, config: &mut Config) {
+        let profile = &self.model;
+        let llm = &mut config.llm;
+        if let Some(model_name) = &profile.model_name {
+            llm.model_name = model_name.clone();
+        }
+        if let Some(base_url) = &profile.base_url {
+            llm.base_url = base_url.clone();
+        }
+        if let Some(temperature) = profile.temperature {
+            llm.temperature = temperature;
+        }
+        if let Some(max_tokens) = profile.max_tokens {
+            llm.max_tokens = max_tokens;
+        }
+        llm.stop_sequences
+            .extend(self.guardrails.stop_sequences.iter().cloned());
+        llm.stop_patterns
+            .extend(self.guardrails.stop_patterns.iter().cloned());
+    }
+
+    /// Returns an agent builder set up from the bundle.
+    ///
+    /// The model profile and guardrails are applied to `config`. Built-in tools are
+    /// created by name; any other tool in the manifest must be in `custom_tools`.
+    /// Custom tools not listed in the manifest are ignored.
+    pub fn into_builder(
+        self,
+        mut config: Config,
+        custom_tools: Vec<Box<dyn Tool>>,
+    ) -> Result<AgentBuilder> {
+        self.apply_to_config(&mut config);
+        let guardrails = self.guardrails;
+
+        let jail = guardrails.workspace.map(PathJail::new).transpose()?;
+        let mut custom: HashMap<String, Box<dyn Tool>> = custom_tools
+            .into_iter()
+            .map(|tool| (tool.name().to_string(), tool))
+            .collect();
+        let mut tools = Vec::new();
+        let mut missing = Vec::new();
+        for entry in &self.tools {
+            let tool = custom
+                .remove(&entry.name)
+                .or_else(|| builtin_tool(&entry.name, guardrails.shell_policy.as_ref()));
+            match (tool, &jail) {
+                (Some(tool), Some(jail)) if FILE_TOOLS.contains(&entry.name.as_str()) => {
+                    tools.push(Box::new(SandboxedTool::new(tool, jail.clone())) as Box<dyn Tool>)
+                }
+                (Some(tool), _) => tools.push(tool),
+                (None, _) => missing.push(entry.name.as_str()),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(HeliosError::ConfigError(format!(
+                "Agent bundle '{}' needs tools that are not built in and were not provided: {}",
+                self.name,
+                missing.join(", ")
+            )));
+        }
+
+        let mut builder = Agent::builder(self.name).config(config).tools(tools);
+        if let Some(prompt) = self.system_prompt {
+            builder = builder.system_prompt(prompt);
+        }
+        if let Some(max) = guardrails.max_iterations {
+            builder = builder.max_iterations(max);
+        }
+        if guardrails.react {
+            builder = match guardrails.react_prompt {
+                Some(prompt) => builder.react_with_prompt(prompt),
+                None => builder.react(),
+            };
+        }
+        for (tool, permission) in guardrails.tool_permissions {
+            builder = builder.tool_permission(tool, permission);
+        }
+        Ok(builder)
+    }
+}
+
+/// Returns `true` if `path` names a JSON file.
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that an exported bundle loads back into an equivalent agent.
+    #[tokio::test]
+    async fn test_bundle_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut bundle = AgentBundle::new("Reviewer");
+        bundle.system_prompt = Some("Review code.".to_string());
+        bundle.tools = vec![
+            ToolManifestEntry {
+                name: "calculator".to_string(),
+                description: None,
+                parameters: None,
+            },
+            ToolManifestEntry {
+                name: "file_read".to_string(),
+                description: None,
+                parameters: None,
+            },
+        ];
+        bundle.model.model_name = Some("gpt-4o".to_string());
+        bundle.guardrails.max_iterations = Some(3);
+        bundle.guardrails.workspace = Some(dir.path().to_path_buf());
+        bundle
+            .guardrails
+            .tool_permissions
+            .insert("calculator".to_string(), ToolPermission::Destructive);
+
+        for file in ["reviewer.toml", "reviewer.json"] {
+            let path = dir.path().join(file);
+            bundle.save(&path).unwrap();
+            assert_eq!(AgentBundle::load(&path).unwrap(), bundle);
+        }
+
+        let agent = Agent::from_bundle(dir.path().join("reviewer.toml"))
+            .await
+            .unwrap();
+        assert_eq!(agent.name(), "Reviewer");
+        assert_eq!(
+            agent.tool_registry().permission("calculator"),
+            Some(ToolPermission::Destructive)
+        );
+
+        let exported = agent.to_bundle();
+        assert_eq!(exported.model.model_name.as_deref(), Some("gpt-4o"));
+        assert_eq!(exported.system_prompt.as_deref(), Some("Review code."));
+        assert_eq!(exported.guardrails.max_iterations, Some(3));
+        let names: Vec<_> = exported.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["calculator", "file_read"]);
+        assert_eq!(
+            exported.guardrails.tool_permissions.get("calculator"),
+            Some(&ToolPermission::Destructive)
+        );
+        assert!(exported.tools[0].parameters.is_some());
+    }
+
+    /// Tests that missing custom tools and newer format versions are reported.
+    #[test]
+    fn test_bundle_errors() {
+        let mut bundle = AgentBundle::new("Custom");
+        bundle.tools.push(ToolManifestEntry {
+            name: "deploy".to_string(),
+            description: Some("Deploys the app".to_string()),
+            parameters: None,
+        });
+        let error = bundle
+            .into_builder(Config::new_default(), Vec::new())
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("deploy"));
+
+        let error = AgentBundle::from_toml("format_version = 99\nname = \"Future\"\n").unwrap_err();
+        assert!(error.to_string().contains("format version 99"));
+    }
+}
@@ -9,10 +9,8 @@ use crate::config::CandleConfig;
 use crate::error::{HeliosError, Result};
 use crate::llm::{Choice, LLMProvider, LLMRequest, LLMResponse, Usage};
 use async_trait::async_trait;
-use chrono::Utc;
 use std::path::PathBuf;
 use std::sync::Arc;
-use uuid::Uuid;
 
 #[cfg(feature = "candle")]
 use {
@@ -582,14 +580,16 @@ impl LLMProvider for CandleLLMProvider {
 
         // Create response
         let response = LLMResponse {
-            id: format!("candle-{}", Uuid::new_v4()),
+            id: format!("candle-{}", crate::clock::new_id()),
             object: "text_completion".to_string(),
-            created: Utc::now().timestamp() as u64,
+            created: crate::clock::now().timestamp() as u64,
             model: self.config.huggingface_repo.clone(),
             choices: vec![Choice {
                 index: 0,
                 message: ChatMessage::assistant(content),
                 finish_reason: Some("stop".to_string()),
+                logprobs: None,
+                metadata: Default::default(),
             }],
             usage: Usage {
                 prompt_tokens: 0,
@@ -0,0 +1,423 @@
+//! # Capabilities Module
+//!
+//! This module describes which request parameters a provider accepts, so the
+//! client can shape requests before sending them. OpenAI-compatible APIs differ
+//! in small ways: reasoning models reject `temperature` and `stop`, some servers
+//! cap the number of stop sequences, and every model has a context window. Sending
+//! such a parameter anyway gets it silently dropped or the request rejected with a
+//! bare `400`.
+//!
+//! Capabilities are detected from the model name and base URL using a built-in
+//! table, and can be corrected per provider with `[llm.capabilities]`. Requests
+//! that use an unsupported parameter are either adapted (the parameter is dropped
+//! or clamped, with a warning) or rejected with an error naming the parameter,
+//! depending on `unsupported_parameters`.
+
+use crate::error::{HeliosError, Result};
+use crate::llm::LLMRequest;
+use serde::{Deserialize, Serialize};
+
+/// What a provider does with request parameters it does not support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnsupportedParameters {
+    /// Drop or clamp the parameter and log a warning.
+    #[default]
+    Adapt,
+    /// Fail the request with an error naming the parameter.
+    Reject,
+}
+
+/// The request parameters and limits a provider supports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderCapabilities {
+    /// Whether tool definitions and tool messages are accepted.
+    pub tools: bool,
+    /// Whether responses can be streamed.
+    pub streaming: bool,
+    /// Whether `temperature` may be set.
+    pub temperature: bool,
+    /// Whether `stop` sequences are accepted.
+    pub stop: bool,
+    /// The most stop sequences accepted in one request, if limited.
+    pub max_stop_sequences: Option<usize>,
+    /// Whether `response_format` JSON mode is supported.
+    pub json_mode: bool,
+    /// Whether token log probabilities can be requested.
+    pub logprobs: bool,
+    /// Whether the output limit is sent as `max_completion_tokens` instead of `max_tokens`.
+    pub max_completion_tokens: bool,
+    /// The context window in tokens, if known.
+    pub max_context_tokens: Option<u32>,
+    /// The most tokens the model generates in one response, if known.
+    pub max_output_tokens: Option<u32>,
+    /// Whether the provider runs OpenAI-style offline batches through `/files` and `/batches`.
+    #[serde(default)]
+    pub batch_api: bool,
+}
+
+impl Default for ProviderCapabilities {
+    /// Every request parameter supported and no known limits, which is how unknown
+    /// providers are treated. The Batch API is only assumed where it is known to exist.
+    fn default() -> Self {
+        Self {
+            tools: true,
+            streaming: true,
+            temperature: true,
+            stop: true,
+            max_stop_sequences: None,
+            json_mode: true,
+            logprobs: true,
+            max_completion_tokens: false,
+            max_context_tokens: None,
+            max_output_tokens: None,
+            batch_api: false,
+        }
+    }
+}
+
+/// Known context windows and output limits, matched by model name prefix.
+///
+/// More specific prefixes come first because the first match wins.
+const MODEL_LIMITS: &[(&str, u32, Option<u32>)] = &[
+    ("gpt-4o-mini", 128_000, Some(16_384)),
+    ("gpt-4o", 128_000, Some(16_384)),
+    ("gpt-4.1", 1_047_576, Some(32_768)),
+    ("gpt-4-turbo", 128_000, Some(4_096)),
+    ("gpt-4-32k", 32_768, Some(8_192)),
+    ("gpt-4", 8_192, Some(8_192)),
+    ("gpt-3.5-turbo-instruct", 4_096, Some(4_096)),
+    ("gpt-3.5-turbo", 16_385, Some(4_096)),
+    ("o1-mini", 128_000, Some(65_536)),
+    ("o1", 200_000, Some(100_000)),
+    ("o3-mini", 200_000, Some(100_000)),
+    ("o3", 200_000, Some(100_000)),
+    ("o4-mini", 200_000, Some(100_000)),
+    ("claude", 200_000, None),
+    ("deepseek", 65_536, Some(8_192)),
+    ("llama3.1", 131_072, None),
+    ("llama-3.1", 131_072, None),
+    ("llama3.2", 131_072, None),
+    ("llama-3.2", 131_072, None),
+    ("llama3.3", 131_072, None),
+    ("llama-3.3", 131_072, None),
+    ("mistral-large", 131_072, None),
+];
+
+impl ProviderCapabilities {
+    /// Detects the capabilities of `model` served from `base_url`.
+    ///
+    /// Unknown models and providers get [`ProviderCapabilities::default`], so
+    /// requests to them are sent unchanged.
+    pub fn detect(base_url: &str, model: &str) -> Self {
+        let mut caps = Self::default();
+        let base_url = base_url.to_ascii_lowercase();
+        // Routers such as OpenRouter prefix the model with its vendor.
+        let model = model.to_ascii_lowercase();
+        let model = model.rsplit('/').next().unwrap_or(&model);
+
+        if let Some((_, context, output)) = MODEL_LIMITS
+            .iter()
+            .find(|(prefix, _, _)| model.starts_with(prefix))
+        {
+            caps.max_context_tokens = Some(*context);
+            caps.max_output_tokens = *output;
+        }
+
+        // OpenAI reasoning models.
+        let reasoning = ["o1", "o3", "o4"]
+            .iter()
+            .any(|family| model == *family || model.starts_with(&format!("{}-", family)));
+        if reasoning {
+            caps.temperature = false;
+            caps.stop = false;
+            caps.logprobs = false;
+            caps.max_completion_tokens = true;
+            if model.starts_with("o1-mini") || model.starts_with("o1-preview") {
+                caps.tools = false;
+                caps.json_mode = false;
+            }
+        }
+        if model.starts_with("deepseek-reasoner") {
+            caps.tools = false;
+            caps.temperature = false;
+            caps.logprobs = false;
+        }
+
+        if base_url.contains("api.openai.com") || base_url.contains(".openai.azure.com") {
+            caps.max_stop_sequences = Some(4);
+        }
+        if base_url.contains("api.openai.com") {
+            caps.batch_api = true;
+        }
+        if base_url.contains("api.anthropic.com") {
+            caps.logprobs = false;
+            caps.json_mode = false;
+        }
+        if base_url.contains("api.groq.com") || base_url.contains("api.mistral.ai") {
+            caps.logprobs = false;
+        }
+
+        caps
+    }
+
+    /// Applies `[llm.capabilities]` overrides on top of these capabilities.
+    pub fn with_overrides(mut self, overrides: &CapabilityOverrides) -> Self {
+        let CapabilityOverrides {
+            tools,
+            streaming,
+            temperature,
+            stop,
+            max_stop_sequences,
+            json_mode,
+            logprobs,
+            max_completion_tokens,
+            max_context_tokens,
+            max_output_tokens,
+            batch_api,
+        } = overrides;
+
+        self.tools = tools.unwrap_or(self.tools);
+        self.streaming = streaming.unwrap_or(self.streaming);
+        self.temperature = temperature.unwrap_or(self.temperature);
+        self.stop = stop.unwrap_or(self.stop);
+        self.max_stop_sequences = max_stop_sequences.or(self.max_stop_sequences);
+        self.json_mode = json_mode.unwrap_or(self.json_mode);
+        self.logprobs = logprobs.unwrap_or(self.logprobs);
+        self.max_completion_tokens = max_completion_tokens.unwrap_or(self.max_completion_tokens);
+        self.max_context_tokens = max_context_tokens.or(self.max_context_tokens);
+        self.max_output_tokens = max_output_tokens.or(self.max_output_tokens);
+        self.batch_api = batch_api.unwrap_or(self.batch_api);
+        self
+    }
+
+    /// Rewrites `request` so that it only uses supported parameters.
+    ///
+    /// With [`UnsupportedParameters::Reject`] the first unsupported parameter is
+    /// reported as an error instead. Tool messages already in the history are not
+    /// checked here; they are rewritten as text when tools are unsupported.
+    pub fn shape(&self, request: &mut LLMRequest, policy: UnsupportedParameters) -> Result<()> {
+        let model = request.model.clone();
+        let unsupported = |parameter: &str| -> Result<()> {
+            match policy {
+                UnsupportedParameters::Reject => Err(HeliosError::LLMError(format!(
+                    "Model '{}' does not support {}",
+                    model, parameter
+                ))),
+                UnsupportedParameters::Adapt => {
+                    tracing::warn!(
+                        "Model '{}' does not support {}; dropping it",
+                        model,
+                        parameter
+                    );
+                    Ok(())
+                }
+            }
+        };
+
+        if !self.tools && request.tools.is_some() {
+            unsupported("tool calling")?;
+            request.tools = None;
+            request.tool_choice = None;
+        }
+        if !self.logprobs && request.logprobs.is_some() {
+            unsupported("log probabilities")?;
+            request.logprobs = None;
+            request.top_logprobs = None;
+        }
+        if !self.temperature && request.temperature.is_some() {
+            unsupported("the temperature parameter")?;
+            request.temperature = None;
+        }
+        if let Some(stop) = &mut request.stop {
+            // The client enforces every stop sequence itself, so dropping some from
+            // the payload only costs the tokens generated past the stop.
+            if !self.stop {
+                unsupported("stop sequences")?;
+                request.stop = None;
+            } else if let Some(max) = self.max_stop_sequences.filter(|max| stop.len() > *max) {
+                if policy == UnsupportedParameters::Reject {
+                    return Err(HeliosError::LLMError(format!(
+                        "Model '{}' accepts at most {} stop sequences, got {}",
+                        model,
+                        max,
+                        stop.len()
+                    )));
+                }
+                stop.truncate(max);
+            }
+        }
+
+        if let (Some(limit), Some(max_tokens)) = (self.max_output_tokens, request.max_tokens) {
+            if max_tokens > limit {
+                // Output limits are clamped under either policy: the configured
+                // default `max_tokens` applies to every model in a fallback chain.
+                tracing::debug!(
+                    "Clamping max_tokens from {} to {} for model '{}'",
+                    max_tokens,
+                    limit,
+                    model
+                );
+                request.max_tokens = Some(limit);
+            }
+        }
+
+        if let Some(context) = self.max_context_tokens {
+            let prompt = crate::llm::Usage::estimate(&request.messages, "").prompt_tokens;
+            if prompt >= context {
+                let message = format!(
+                    "Prompt of about {} tokens exceeds the {}-token context window of model '{}'",
+                    prompt, context, model
+                );
+                match policy {
+                    UnsupportedParameters::Reject => return Err(HeliosError::LLMError(message)),
+                    // The estimate is rough, so let the provider make the final call.
+                    UnsupportedParameters::Adapt => tracing::warn!("{}", message),
+                }
+            } else if let Some(max_tokens) = request.max_tokens {
+                request.max_tokens = Some(max_tokens.min(context - prompt));
+            }
+        }
+
+        if self.max_completion_tokens {
+            request.max_completion_tokens = request.max_tokens.take();
+        }
+
+        Ok(())
+    }
+}
+
+/// Per-provider corrections to the detected capabilities, set as `[llm.capabilities]`.
+///
+/// Only the keys that are set replace detected values:
+///
+/// ```toml
+/// [llm.capabilities]
+/// tools = false
+/// max_context_tokens = 32768
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityOverrides {
+    /// Whether tool definitions and tool messages are accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<bool>,
+    /// Whether responses can be streamed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub streaming: Option<bool>,
+    /// Whether `temperature` may be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<bool>,
+    /// Whether `stop` sequences are accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<bool>,
+    /// The most stop sequences accepted in one request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_stop_sequences: Option<usize>,
+    /// Whether `response_format` JSON mode is supported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_mode: Option<bool>,
+    /// Whether token log probabilities can be requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<bool>,
+    /// Whether the output limit is sent as `max_completion_tokens`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<bool>,
+    /// The context window in tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_context_tokens: Option<u32>,
+    /// The most tokens the model generates in one response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    /// Whether the OpenAI Batch API is available.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_api: Option<bool>,
+}
+
+impl CapabilityOverrides {
+    /// Returns `true` when no capability is overridden.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::ChatMessage;
+
+    fn request(model: &str) -> LLMRequest {
+        LLMRequest {
+            model: model.to_string(),
+            messages: vec![ChatMessage::user("hello")],
+            temperature: Some(0.7),
+            max_tokens: Some(50_000),
+            max_completion_tokens: None,
+            tools: Some(Vec::new()),
+            tool_choice: Some("auto".to_string()),
+            stream: None,
+            stop: Some(
+                vec!["a", "b", "c", "d", "e"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            ),
+            grammar: None,
+            logprobs: Some(true),
+            top_logprobs: Some(3),
+        }
+    }
+
+    /// Tests that reasoning models lose unsupported parameters and get `max_completion_tokens`.
+    #[test]
+    fn test_shape_adapts_reasoning_model() {
+        let caps = ProviderCapabilities::detect("https://api.openai.com/v1", "o1-mini");
+        let mut req = request("o1-mini");
+        caps.shape(&mut req, UnsupportedParameters::Adapt).unwrap();
+
+        assert_eq!(req.temperature, None);
+        assert_eq!(req.stop, None);
+        assert!(req.tools.is_none() && req.tool_choice.is_none());
+        assert_eq!(req.max_tokens, None);
+        assert_eq!(req.max_completion_tokens, Some(50_000));
+        assert_eq!((req.logprobs, req.top_logprobs), (None, None));
+    }
+
+    /// Tests stop-sequence and output clamping, rejection, and config overrides.
+    #[test]
+    fn test_shape_limits_and_reject() {
+        let caps = ProviderCapabilities::detect("https://api.openai.com/v1", "gpt-4o");
+        let mut req = request("gpt-4o");
+        caps.shape(&mut req, UnsupportedParameters::Adapt).unwrap();
+        assert_eq!(req.stop.as_ref().unwrap().len(), 4);
+        assert_eq!(req.max_tokens, Some(16_384));
+        assert!(req.tools.is_some());
+        assert!(caps.batch_api);
+
+        let error = caps
+            .shape(&mut request("gpt-4o"), UnsupportedParameters::Reject)
+            .unwrap_err();
+        assert!(error.to_string().contains("at most 4 stop sequences"));
+
+        let caps = ProviderCapabilities::detect("http://localhost:11434/v1", "llama3.1")
+            .with_overrides(&CapabilityOverrides {
+                tools: Some(false),
+                ..Default::default()
+            });
+        assert_eq!(caps.max_context_tokens, Some(131_072));
+        assert!(!caps.batch_api);
+        let error = caps
+            .shape(&mut request("llama3.1"), UnsupportedParameters::Reject)
+            .unwrap_err();
+        assert!(error.to_string().contains("does not support tool calling"));
+
+        // Unknown models are left alone.
+        let mut req = request("my-model");
+        ProviderCapabilities::detect("http://localhost:8000/v1", "my-model")
+            .shape(&mut req, UnsupportedParameters::Reject)
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&req).unwrap(),
+            serde_json::to_value(request("my-model")).unwrap()
+        );
+    }
+}
@@ -4,7 +4,10 @@
 //! It defines the roles in a conversation, the structure of a chat message,
 //! and the chat session that holds the conversation history.
 
+use crate::error::{HeliosError, Result};
+use crate::footprint::{format_bytes, MemoryFootprint, MemoryStats};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
 /// Represents the role of a participant in a chat conversation.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -35,11 +38,11 @@ impl From<&str> for Role {
 
 /// Represents a single message in a chat conversation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "WireMessage")]
 pub struct ChatMessage {
     /// The role of the message sender.
     pub role: Role,
     /// The content of the message.
-    #[serde(default, deserialize_with = "deserialize_null_as_empty_string")]
     pub content: String,
     /// The name of the message sender.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -50,15 +53,59 @@ pub struct ChatMessage {
     /// The ID of the tool call this message is a response to.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_call_id: Option<String>,
+    /// The reasoning the model did before answering, kept apart from `content`.
+    ///
+    /// It is recorded with the conversation but never sent back to a provider.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
 }
 
-/// Deserializes a null value as an empty string.
-fn deserialize_null_as_empty_string<'de, D>(deserializer: D) -> Result<String, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    use serde::Deserialize;
-    Option::<String>::deserialize(deserializer).map(|opt| opt.unwrap_or_default())
+/// The start of the placeholder that replaces an elided tool result.
+const ELIDED_TOOL_OUTPUT: &str = "[tool output elided";
+
+/// A chat message as providers and files spell it.
+///
+/// The content may be `null` or an array of content blocks, and the reasoning
+/// may come as `reasoning_content` or as `thinking` blocks in the content.
+#[derive(Deserialize)]
+struct WireMessage {
+    role: Role,
+    #[serde(default)]
+    content: serde_json::Value,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default)]
+    tool_call_id: Option<String>,
+    #[serde(default, alias = "reasoning_content")]
+    reasoning: Option<String>,
+}
+
+impl From<WireMessage> for ChatMessage {
+    fn from(wire: WireMessage) -> Self {
+        let thinking: Vec<&str> = wire
+            .content
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|block| block.get("type").and_then(|t| t.as_str()) == Some("thinking"))
+            .filter_map(|block| block.get("thinking").and_then(|t| t.as_str()))
+            .collect();
+        let reasoning = match (wire.reasoning, thinking.is_empty()) {
+            (reasoning, true) => reasoning,
+            (Some(reasoning), false) => Some(format!("{}\n{}", reasoning, thinking.join("\n"))),
+            (None, false) => Some(thinking.join("\n")),
+        };
+        Self {
+            role: wire.role,
+            content: content_text(&wire.content),
+            name: wire.name,
+            tool_calls: wire.tool_calls,
+            tool_call_id: wire.tool_call_id,
+            reasoning: reasoning.filter(|reasoning| !reasoning.is_empty()),
+        }
+    }
 }
 
 /// Represents a tool call requested by the assistant.
@@ -82,6 +129,34 @@ pub struct FunctionCall {
     pub arguments: String,
 }
 
+impl MemoryFootprint for ToolCall {
+    fn approx_bytes(&self) -> usize {
+        self.id.approx_bytes()
+            + self.call_type.approx_bytes()
+            + self.function.name.approx_bytes()
+            + self.function.arguments.approx_bytes()
+    }
+}
+
+impl MemoryFootprint for ChatMessage {
+    fn approx_bytes(&self) -> usize {
+        std::mem::size_of::<Role>()
+            + self.content.approx_bytes()
+            + self.name.approx_bytes()
+            + self.tool_calls.approx_bytes()
+            + self.tool_call_id.approx_bytes()
+            + self.reasoning.approx_bytes()
+    }
+}
+
+impl MemoryFootprint for ChatSession {
+    fn approx_bytes(&self) -> usize {
+        self.messages.approx_bytes()
+            + self.system_prompt.approx_bytes()
+            + self.metadata.approx_bytes()
+    }
+}
+
 impl ChatMessage {
     /// Creates a new system message.
     pub fn system(content: impl Into<String>) -> Self {
@@ -91,6 +166,7 @@ impl ChatMessage {
             name: None,
             tool_calls: None,
             tool_call_id: None,
+            reasoning: None,
         }
     }
 
@@ -107,6 +183,7 @@ impl ChatMessage {
             name: None,
             tool_calls: None,
             tool_call_id: None,
+            reasoning: None,
         }
     }
 
@@ -123,6 +200,7 @@ impl ChatMessage {
             name: None,
             tool_calls: None,
             tool_call_id: None,
+            reasoning: None,
         }
     }
 
@@ -139,18 +217,21 @@ impl ChatMessage {
             name: None,
             tool_calls: None,
             tool_call_id: Some(tool_call_id.into()),
+            reasoning: None,
         }
     }
 }
 
 /// Represents a chat session, including the conversation history and metadata.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatSession {
     /// The messages in the chat session.
     pub messages: Vec<ChatMessage>,
     /// The system prompt for the chat session.
+    #[serde(default)]
     pub system_prompt: Option<String>,
     /// Metadata associated with the chat session.
+    #[serde(default)]
     pub metadata: std::collections::HashMap<String, String>,
 }
 
@@ -217,6 +298,52 @@ impl ChatSession {
         self.messages.clear();
     }
 
+    /// Returns an independent copy of the session, to continue separately.
+    pub fn fork(&self) -> Self {
+        self.clone()
+    }
+
+    /// Returns an independent copy of the session holding only its first `messages` messages.
+    ///
+    /// A cut inside a tool exchange also drops the assistant message whose tool
+    /// calls would be left without all their results, so the fork always ends
+    /// where the conversation can go on.
+    pub fn fork_at(&self, messages: usize) -> Self {
+        let mut fork = self.clone();
+        fork.messages.truncate(messages);
+        let last_call = fork.messages.iter().rposition(|message| {
+            message.role == Role::Assistant
+                && message
+                    .tool_calls
+                    .as_ref()
+                    .is_some_and(|calls| !calls.is_empty())
+        });
+        if let Some(start) = last_call {
+            let calls = fork.messages[start].tool_calls.as_ref().map_or(0, Vec::len);
+            let results = fork.messages[start + 1..]
+                .iter()
+                .filter(|message| message.role == Role::Tool)
+                .count();
+            if results < calls {
+                fork.messages.truncate(start);
+            }
+        }
+        fork
+    }
+
+    /// Removes the last user message and everything after it, returning that message.
+    ///
+    /// This takes back the whole last turn, including any reasoning, tool calls and
+    /// tool results that answered it. Returns `None`, leaving the session as it
+    /// is, when there is no user message.
+    pub fn rewind_last_turn(&mut self) -> Option<ChatMessage> {
+        let start = self
+            .messages
+            .iter()
+            .rposition(|message| message.role == Role::User)?;
+        self.messages.drain(start..).next()
+    }
+
     /// Sets a metadata key-value pair for the session.
     pub fn set_metadata(&mut self, key: impl Into<String>, value: impl Into<String>) {
         self.metadata.insert(key.into(), value.into());
@@ -232,6 +359,100 @@ impl ChatSession {
         self.metadata.remove(key)
     }
 
+    /// Returns the number of messages and the approximate memory the session holds.
+    pub fn memory_usage(&self) -> MemoryStats {
+        MemoryStats {
+            entries: self.messages.len(),
+            bytes: self.approx_bytes(),
+        }
+    }
+
+    /// Drops the oldest messages so that at most `max_messages` remain.
+    ///
+    /// The system prompt is kept. Tool results whose call was dropped are dropped
+    /// too, so the history never starts in the middle of a tool exchange. Returns
+    /// the number of messages removed.
+    pub fn prune_oldest(&mut self, max_messages: usize) -> usize {
+        let excess = self.messages.len().saturating_sub(max_messages);
+        self.drain_front(excess)
+    }
+
+    /// Drops the oldest messages until the session holds at most about `max_bytes`.
+    ///
+    /// Returns the number of messages removed.
+    pub fn prune_to_bytes(&mut self, max_bytes: usize) -> usize {
+        let mut total = self.approx_bytes();
+        let mut cut = 0;
+        while total > max_bytes && cut < self.messages.len() {
+            total -= self.messages[cut].approx_bytes();
+            cut += 1;
+        }
+        self.drain_front(cut)
+    }
+
+    /// Replaces bulky tool results the model has already answered with a short
+    /// placeholder such as `[tool output elided, 14.2 KiB]`.
+    ///
+    /// Only results of at least `min_bytes`, followed by an assistant message and
+    /// older than the last `keep_turns` user messages, are elided. The messages
+    /// and their tool call IDs stay in place, so the original output can still be
+    /// found by call ID, for example in the agent's tool audit log. Returns the
+    /// number of results elided.
+    pub fn elide_tool_results(&mut self, min_bytes: usize, keep_turns: usize) -> usize {
+        let end = match keep_turns.checked_sub(1) {
+            None => self.messages.len(),
+            Some(skip) => match self
+                .messages
+                .iter()
+                .rposition(|message| message.role == Role::User)
+                .and_then(|last| {
+                    self.messages[..=last]
+                        .iter()
+                        .enumerate()
+                        .rev()
+                        .filter(|(_, message)| message.role == Role::User)
+                        .nth(skip)
+                }) {
+                Some((index, _)) => index,
+                None => return 0,
+            },
+        };
+
+        let mut elided = 0;
+        let mut answered = false;
+        for message in self.messages[..end].iter_mut().rev() {
+            match message.role {
+                Role::Assistant => answered = true,
+                Role::Tool
+                    if answered
+                        && message.content.len() >= min_bytes
+                        && !message.content.starts_with(ELIDED_TOOL_OUTPUT) =>
+                {
+                    message.content = format!(
+                        "{}, {}]",
+                        ELIDED_TOOL_OUTPUT,
+                        format_bytes(message.content.len())
+                    );
+                    elided += 1;
+                }
+                _ => {}
+            }
+        }
+        elided
+    }
+
+    /// Removes the first `cut` messages plus any tool results that follow them.
+    fn drain_front(&mut self, mut cut: usize) -> usize {
+        if cut == 0 {
+            return 0;
+        }
+        while cut < self.messages.len() && self.messages[cut].role == Role::Tool {
+            cut += 1;
+        }
+        self.messages.drain(..cut);
+        cut
+    }
+
     /// Returns a summary of the chat session.
     pub fn get_summary(&self) -> String {
         let mut summary = String::new();
@@ -266,6 +487,125 @@ impl ChatSession {
 
         summary
     }
+
+    /// Writes the session, with its system prompt and metadata, to a JSON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, serde_json::to_string_pretty(self)?).map_err(|e| {
+            HeliosError::ConfigError(format!("Failed to write session {}: {}", path.display(), e))
+        })
+    }
+
+    /// Reads a session written by [`ChatSession::save`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            HeliosError::ConfigError(format!("Failed to read session {}: {}", path.display(), e))
+        })?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Builds a session from OpenAI chat-format JSON.
+    ///
+    /// Accepts either a bare array of messages or an object with a `messages`
+    /// array, which covers Chat Completions request bodies and fine-tuning JSONL
+    /// lines. Content given as an array of parts is flattened to its text parts.
+    /// A leading system (or `developer`) message becomes the session's system prompt.
+    pub fn import_openai_json(json: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let messages = match &value {
+            serde_json::Value::Array(messages) => messages,
+            serde_json::Value::Object(object) => object
+                .get("messages")
+                .and_then(|m| m.as_array())
+                .ok_or_else(|| {
+                    HeliosError::ConfigError(
+                        "Expected an array of messages or an object with a 'messages' array"
+                            .to_string(),
+                    )
+                })?,
+            _ => {
+                return Err(HeliosError::ConfigError(
+                    "Expected an array of messages or an object with a 'messages' array"
+                        .to_string(),
+                ))
+            }
+        };
+
+        let mut session = Self::new();
+        for message in messages {
+            let mut message = message.clone();
+            if let Some(object) = message.as_object_mut() {
+                if object.get("role").and_then(|r| r.as_str()) == Some("developer") {
+                    object.insert("role".to_string(), "system".into());
+                }
+                let text = object.get("content").map(content_text).unwrap_or_default();
+                object.insert("content".to_string(), text.into());
+            }
+            let message: ChatMessage = serde_json::from_value(message)?;
+
+            if session.messages.is_empty()
+                && session.system_prompt.is_none()
+                && message.role == Role::System
+            {
+                session.system_prompt = Some(message.content);
+            } else {
+                session.messages.push(message);
+            }
+        }
+
+        session.set_metadata("source", "openai");
+        Ok(session)
+    }
+
+    /// Imports every conversation from a ChatGPT data export.
+    ///
+    /// `path` may be the export's `conversations.json` or the directory the export
+    /// archive was unzipped into. See [`ChatSession::import_chatgpt_json`] for how
+    /// conversations are converted.
+    pub fn import_chatgpt_export(path: impl AsRef<Path>) -> Result<Vec<Self>> {
+        let path = path.as_ref();
+        let file = if path.is_dir() {
+            path.join("conversations.json")
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+        {
+            return Err(HeliosError::ConfigError(format!(
+                "'{}' is a zip archive; unzip it and pass the extracted directory or its conversations.json",
+                path.display()
+            )));
+        } else {
+            path.to_path_buf()
+        };
+
+        let json = std::fs::read_to_string(&file).map_err(|e| {
+            HeliosError::ConfigError(format!("Failed to read '{}': {}", file.display(), e))
+        })?;
+        Self::import_chatgpt_json(&json)
+    }
+
+    /// Converts the contents of a ChatGPT export's `conversations.json`.
+    ///
+    /// Each conversation is a tree of messages (edits and regenerations create
+    /// branches); the branch that was on screen last is imported. Hidden messages,
+    /// tool traffic (browsing, code execution) and non-text content such as images
+    /// are dropped. The title, conversation ID and creation time are stored in the
+    /// session metadata under `title`, `conversation_id` and `created_at`.
+    pub fn import_chatgpt_json(json: &str) -> Result<Vec<Self>> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let conversations = match value {
+            serde_json::Value::Array(conversations) => conversations,
+            conversation @ serde_json::Value::Object(_) => vec![conversation],
+            _ => {
+                return Err(HeliosError::ConfigError(
+                    "Expected a ChatGPT conversation or an array of conversations".to_string(),
+                ))
+            }
+        };
+
+        conversations.iter().map(chatgpt_conversation).collect()
+    }
 }
 
 impl Default for ChatSession {
@@ -275,6 +615,281 @@ impl Default for ChatSession {
     }
 }
 
+/// A saved session, as listed by [`SessionStore::list`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionInfo {
+    /// The name the session was saved under.
+    pub name: String,
+    /// The number of messages, not counting the system prompt.
+    pub messages: usize,
+    /// When the session was last saved.
+    pub modified: chrono::DateTime<chrono::Utc>,
+}
+
+/// Keeps named chat sessions as JSON files in a directory, so conversations can
+/// be saved, listed and resumed later.
+///
+/// Names may contain letters, digits, `-`, `_` and `.`; each session is stored as
+/// `<name>.json`.
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    /// Creates a store in `dir`, which is created when the first session is saved.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the directory the sessions are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Saves `session` under `name`, replacing any session saved with that name.
+    pub fn save(&self, name: &str, session: &ChatSession) -> Result<()> {
+        let path = self.path(name)?;
+        std::fs::create_dir_all(&self.dir).map_err(|e| {
+            HeliosError::ConfigError(format!(
+                "Failed to create session directory {}: {}",
+                self.dir.display(),
+                e
+            ))
+        })?;
+        session.save(path)
+    }
+
+    /// Loads the session saved under `name`.
+    pub fn load(&self, name: &str) -> Result<ChatSession> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            return Err(HeliosError::ConfigError(format!(
+                "No saved session named '{}' in {}",
+                name,
+                self.dir.display()
+            )));
+        }
+        ChatSession::load(path)
+    }
+
+    /// Returns `true` if a session is saved under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.path(name).is_ok_and(|path| path.exists())
+    }
+
+    /// Deletes the session saved under `name`.
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let path = self.path(name)?;
+        std::fs::remove_file(&path).map_err(|e| {
+            HeliosError::ConfigError(format!(
+                "Failed to delete session {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Lists the saved sessions, most recently saved first.
+    pub fn list(&self) -> Result<Vec<SessionInfo>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut sessions = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let Some(name) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+                .filter(|name| check_session_name(name).is_ok())
+            else {
+                continue;
+            };
+            let session = ChatSession::load(&path)?;
+            sessions.push(SessionInfo {
+                name: name.to_string(),
+                messages: session.messages.len(),
+                modified: std::fs::metadata(&path)?.modified()?.into(),
+            });
+        }
+        sessions.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.name.cmp(&b.name)));
+        Ok(sessions)
+    }
+
+    /// Returns the file a session name is stored in.
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        check_session_name(name)?;
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+}
+
+/// Rejects session names that are empty, hidden or could escape the store directory.
+fn check_session_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(HeliosError::ConfigError(format!(
+            "Invalid session name '{}': use letters, digits, '-', '_' and '.'",
+            name
+        )))
+    }
+}
+
+/// Extracts the text of an OpenAI message `content`, which may be a string or an
+/// array of content parts.
+fn content_text(content: &serde_json::Value) -> String {
+    match content {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| match part {
+                serde_json::Value::String(text) => Some(text.as_str()),
+                _ => part.get("text").and_then(|t| t.as_str()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Converts one conversation from a ChatGPT export into a session.
+fn chatgpt_conversation(conversation: &serde_json::Value) -> Result<ChatSession> {
+    let mapping = conversation
+        .get("mapping")
+        .and_then(|m| m.as_object())
+        .ok_or_else(|| {
+            HeliosError::ConfigError("ChatGPT conversation has no 'mapping'".to_string())
+        })?;
+
+    // Follow parent links from the last visible node back to the root.
+    let leaf = conversation
+        .get("current_node")
+        .and_then(|n| n.as_str())
+        .filter(|id| mapping.contains_key(*id))
+        .map(str::to_string)
+        .or_else(|| latest_leaf(mapping));
+
+    let mut branch = Vec::new();
+    let mut node_id = leaf;
+    while let Some(id) = node_id {
+        if branch.len() > mapping.len() {
+            return Err(HeliosError::ConfigError(
+                "ChatGPT conversation contains a cycle".to_string(),
+            ));
+        }
+        let Some(node) = mapping.get(&id) else {
+            break;
+        };
+        branch.push(node);
+        node_id = node
+            .get("parent")
+            .and_then(|p| p.as_str())
+            .map(str::to_string);
+    }
+    branch.reverse();
+
+    let mut session = ChatSession::new();
+    for message in branch.iter().filter_map(|node| node.get("message")) {
+        let Some((role, text)) = chatgpt_message(message) else {
+            continue;
+        };
+        if role == Role::System && session.messages.is_empty() && session.system_prompt.is_none() {
+            session.system_prompt = Some(text);
+        } else {
+            session.messages.push(ChatMessage {
+                role,
+                content: text,
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+                reasoning: None,
+            });
+        }
+    }
+
+    session.set_metadata("source", "chatgpt");
+    if let Some(title) = conversation.get("title").and_then(|t| t.as_str()) {
+        session.set_metadata("title", title);
+    }
+    if let Some(id) = conversation
+        .get("conversation_id")
+        .or_else(|| conversation.get("id"))
+        .and_then(|id| id.as_str())
+    {
+        session.set_metadata("conversation_id", id);
+    }
+    if let Some(created) = conversation
+        .get("create_time")
+        .and_then(|t| t.as_f64())
+        .and_then(|t| chrono::DateTime::from_timestamp(t as i64, 0))
+    {
+        session.set_metadata("created_at", created.to_rfc3339());
+    }
+
+    Ok(session)
+}
+
+/// Picks the most recently created leaf node, for exports without `current_node`.
+fn latest_leaf(mapping: &serde_json::Map<String, serde_json::Value>) -> Option<String> {
+    mapping
+        .iter()
+        .filter(|(_, node)| {
+            node.get("children")
+                .and_then(|c| c.as_array())
+                .map_or(true, |c| c.is_empty())
+        })
+        .max_by(|(_, a), (_, b)| {
+            let time = |node: &serde_json::Value| {
+                node.pointer("/message/create_time")
+                    .and_then(|t| t.as_f64())
+                    .unwrap_or(0.0)
+            };
+            time(a).total_cmp(&time(b))
+        })
+        .map(|(id, _)| id.clone())
+}
+
+/// Returns the role and text of a ChatGPT export message, or `None` if it should
+/// not be imported.
+fn chatgpt_message(message: &serde_json::Value) -> Option<(Role, String)> {
+    let role = match message.pointer("/author/role")?.as_str()? {
+        "system" => Role::System,
+        "user" => Role::User,
+        "assistant" => Role::Assistant,
+        _ => return None,
+    };
+
+    let hidden = message
+        .pointer("/metadata/is_visually_hidden_from_conversation")
+        .and_then(|h| h.as_bool())
+        .unwrap_or(false);
+    // Messages addressed to a tool (e.g. "python" or "browser") are tool calls.
+    let to_tool = message
+        .get("recipient")
+        .and_then(|r| r.as_str())
+        .is_some_and(|r| r != "all");
+    if hidden || to_tool {
+        return None;
+    }
+
+    let content = message.get("content")?;
+    let text = match content.get("content_type")?.as_str()? {
+        "text" | "multimodal_text" => content_text(content.get("parts")?),
+        _ => return None,
+    };
+
+    let text = text.trim();
+    (!text.is_empty()).then(|| (role, text.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -314,6 +929,34 @@ mod tests {
         assert_eq!(tool_msg.tool_call_id, Some("tool_call_123".to_string()));
     }
 
+    /// Tests reading reasoning from provider fields and thinking blocks.
+    #[test]
+    fn test_chat_message_reasoning() {
+        let message: ChatMessage = serde_json::from_str(
+            r#"{"role": "assistant", "content": null, "reasoning_content": "Add them."}"#,
+        )
+        .unwrap();
+        assert_eq!(message.content, "");
+        assert_eq!(message.reasoning.as_deref(), Some("Add them."));
+
+        let message: ChatMessage = serde_json::from_str(
+            r#"{"role": "assistant", "content": [
+                {"type": "thinking", "thinking": "2 + 2 is 4.", "signature": "x"},
+                {"type": "text", "text": "It is 4."}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(message.content, "It is 4.");
+        assert_eq!(message.reasoning.as_deref(), Some("2 + 2 is 4."));
+
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["reasoning"], "2 + 2 is 4.");
+        assert!(serde_json::to_value(ChatMessage::user("Hi"))
+            .unwrap()
+            .get("reasoning")
+            .is_none());
+    }
+
     /// Tests the creation of a new `ChatSession`.
     #[test]
     fn test_chat_session_new() {
@@ -388,4 +1031,248 @@ mod tests {
         session.clear();
         assert!(session.messages.is_empty());
     }
+
+    /// Tests importing OpenAI chat-format JSON.
+    #[test]
+    fn test_import_openai_json() {
+        let json = r#"{"messages": [
+            {"role": "developer", "content": "Be terse."},
+            {"role": "user", "content": [{"type": "text", "text": "Hi"}, {"type": "image_url", "image_url": {"url": "x"}}]},
+            {"role": "assistant", "content": null, "tool_calls": [{"id": "c1", "type": "function", "function": {"name": "echo", "arguments": "{}"}}]},
+            {"role": "tool", "content": "done", "tool_call_id": "c1"},
+            {"role": "assistant", "content": "Hello"}
+        ]}"#;
+
+        let session = ChatSession::import_openai_json(json).unwrap();
+        assert_eq!(session.system_prompt.as_deref(), Some("Be terse."));
+        assert_eq!(session.messages.len(), 4);
+        assert_eq!(session.messages[0].content, "Hi");
+        assert_eq!(session.messages[1].tool_calls.as_ref().unwrap().len(), 1);
+        assert_eq!(session.messages[2].tool_call_id.as_deref(), Some("c1"));
+        assert_eq!(session.messages[3].content, "Hello");
+
+        assert!(ChatSession::import_openai_json(r#"{"foo": 1}"#).is_err());
+    }
+
+    /// Tests importing a ChatGPT export, following the last visible branch.
+    #[test]
+    fn test_import_chatgpt_json() {
+        let json = r#"[{
+            "title": "Rust help",
+            "create_time": 1700000000.5,
+            "conversation_id": "conv-1",
+            "current_node": "a2",
+            "mapping": {
+                "root": {"id": "root", "message": null, "parent": null, "children": ["sys"]},
+                "sys": {"id": "sys", "parent": "root", "children": ["u1"], "message": {
+                    "author": {"role": "system"}, "content": {"content_type": "text", "parts": [""]},
+                    "metadata": {"is_visually_hidden_from_conversation": true}}},
+                "u1": {"id": "u1", "parent": "sys", "children": ["a1", "a2"], "message": {
+                    "author": {"role": "user"}, "content": {"content_type": "text", "parts": ["What is a borrow?"]}}},
+                "a1": {"id": "a1", "parent": "u1", "children": [], "message": {
+                    "author": {"role": "assistant"}, "content": {"content_type": "text", "parts": ["Old answer"]}}},
+                "a2": {"id": "a2", "parent": "u1", "children": [], "message": {
+                    "author": {"role": "assistant"}, "content": {"content_type": "text", "parts": ["A reference."]}}}
+            }
+        }]"#;
+
+        let sessions = ChatSession::import_chatgpt_json(json).unwrap();
+        assert_eq!(sessions.len(), 1);
+        let session = &sessions[0];
+        assert!(session.system_prompt.is_none());
+        assert_eq!(session.messages.len(), 2);
+        assert_eq!(session.messages[0].content, "What is a borrow?");
+        assert_eq!(session.messages[1].content, "A reference.");
+        assert_eq!(session.get_metadata("title").unwrap(), "Rust help");
+        assert_eq!(session.get_metadata("conversation_id").unwrap(), "conv-1");
+        assert!(session
+            .get_metadata("created_at")
+            .unwrap()
+            .starts_with("2023-11-14"));
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("conversations.json"), json).unwrap();
+        assert_eq!(
+            ChatSession::import_chatgpt_export(dir.path())
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(ChatSession::import_chatgpt_export(dir.path().join("export.zip")).is_err());
+    }
+
+    /// Tests that pruning drops the oldest messages without orphaning tool results.
+    #[test]
+    fn test_prune_oldest_keeps_tool_exchanges_whole() {
+        let mut session = ChatSession::new().with_system_prompt("Be brief.");
+        session.add_user_message("What time is it?");
+        let mut call = ChatMessage::assistant("");
+        call.tool_calls = Some(vec![ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "timestamp".to_string(),
+                arguments: "{}".to_string(),
+            },
+        }]);
+        session.add_message(call);
+        session.add_message(ChatMessage::tool("12:00", "call_1"));
+        session.add_assistant_message("It is noon.");
+        session.add_user_message("Thanks");
+
+        let before = session.memory_usage();
+        assert_eq!(before.entries, 5);
+
+        // Keeping 3 would start the history at the tool result.
+        assert_eq!(session.prune_oldest(3), 3);
+        assert_eq!(session.messages.len(), 2);
+        assert_eq!(session.messages[0].content, "It is noon.");
+        assert_eq!(session.system_prompt.as_deref(), Some("Be brief."));
+        assert!(session.memory_usage().bytes < before.bytes);
+
+        session.add_user_message("x".repeat(4096));
+        assert_eq!(session.prune_to_bytes(1024), 3);
+        assert!(session.messages.is_empty());
+        assert_eq!(session.prune_oldest(10), 0);
+    }
+
+    /// Tests that only answered, bulky tool results from earlier turns are elided.
+    #[test]
+    fn test_elide_tool_results() {
+        let mut session = ChatSession::new();
+        let call = |id: &str| {
+            let mut call = ChatMessage::assistant("");
+            call.tool_calls = Some(vec![ToolCall {
+                id: id.to_string(),
+                call_type: "function".to_string(),
+                function: FunctionCall {
+                    name: "file_read".to_string(),
+                    arguments: "{}".to_string(),
+                },
+            }]);
+            call
+        };
+        session.add_user_message("Read both files.");
+        session.add_message(call("call_1"));
+        session.add_message(ChatMessage::tool("x".repeat(2048), "call_1"));
+        session.add_message(ChatMessage::tool("short", "call_1"));
+        session.add_assistant_message("Done.");
+        session.add_user_message("And the next one?");
+        session.add_message(call("call_2"));
+        session.add_message(ChatMessage::tool("y".repeat(2048), "call_2"));
+
+        // The current turn's result has not been answered yet.
+        assert_eq!(session.elide_tool_results(1024, 0), 1);
+        assert_eq!(session.messages[2].content, "[tool output elided, 2.0 KiB]");
+        assert_eq!(session.messages[2].tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(session.messages[3].content, "short");
+        assert_eq!(session.elide_tool_results(0, 0), 1);
+        assert_eq!(session.messages[3].content, "[tool output elided, 5 B]");
+
+        session.add_assistant_message("Here it is.");
+        session.add_user_message("Thanks");
+        assert_eq!(session.elide_tool_results(1024, 2), 0);
+        assert_eq!(session.elide_tool_results(1024, 1), 1);
+        assert!(session.messages[7]
+            .content
+            .starts_with("[tool output elided"));
+        assert_eq!(session.elide_tool_results(0, 1), 0);
+    }
+
+    /// Tests forking a session, including a cut inside a tool exchange.
+    #[test]
+    fn test_chat_session_fork_at() {
+        let mut session = ChatSession::new().with_system_prompt("Be brief.");
+        session.add_user_message("What time is it?");
+        let mut call = ChatMessage::assistant("");
+        call.tool_calls = Some(vec![ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "timestamp".to_string(),
+                arguments: "{}".to_string(),
+            },
+        }]);
+        session.add_message(call);
+        session.add_message(ChatMessage::tool("12:00", "call_1"));
+        session.add_assistant_message("It is noon.");
+
+        let mut fork = session.fork();
+        fork.add_user_message("Thanks");
+        assert_eq!(session.messages.len(), 4);
+        assert_eq!(fork.messages.len(), 5);
+
+        // Cutting after the tool call drops the unanswered call.
+        let fork = session.fork_at(2);
+        assert_eq!(fork.messages.len(), 1);
+        assert_eq!(fork.system_prompt.as_deref(), Some("Be brief."));
+        assert_eq!(session.fork_at(3).messages.len(), 3);
+        assert_eq!(session.fork_at(10).messages.len(), 4);
+    }
+
+    /// Tests that rewinding removes the last user message with its tool exchange.
+    #[test]
+    fn test_rewind_last_turn() {
+        let mut session = ChatSession::new();
+        assert!(session.rewind_last_turn().is_none());
+        session.add_user_message("Hi");
+        session.add_assistant_message("Hello!");
+        session.add_user_message("What time is it?");
+        let mut call = ChatMessage::assistant("");
+        call.tool_calls = Some(vec![ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "timestamp".to_string(),
+                arguments: "{}".to_string(),
+            },
+        }]);
+        session.add_message(call);
+        session.add_message(ChatMessage::tool("12:00", "call_1"));
+        session.add_assistant_message("It is noon.");
+
+        let turn = session.rewind_last_turn().unwrap();
+        assert_eq!(turn.content, "What time is it?");
+        assert_eq!(session.messages.len(), 2);
+        assert_eq!(session.messages[1].content, "Hello!");
+    }
+
+    /// Tests saving, listing, loading and deleting named sessions.
+    #[test]
+    fn test_session_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::new(dir.path().join("sessions"));
+        assert!(store.list().unwrap().is_empty());
+
+        let mut session = ChatSession::new().with_system_prompt("Be brief.");
+        session.add_user_message("Hi");
+        session.add_assistant_message("Hello!");
+        session.set_metadata("topic", "greetings");
+        store.save("greeting", &session).unwrap();
+        store.save("empty", &ChatSession::new()).unwrap();
+
+        let loaded = store.load("greeting").unwrap();
+        assert_eq!(loaded.system_prompt.as_deref(), Some("Be brief."));
+        assert_eq!(loaded.messages.len(), 2);
+        assert_eq!(loaded.messages[1].content, "Hello!");
+        assert_eq!(
+            loaded.get_metadata("topic").map(String::as_str),
+            Some("greetings")
+        );
+
+        let mut listed = store.list().unwrap();
+        listed.sort_by(|a, b| a.name.cmp(&b.name));
+        let summary: Vec<_> = listed
+            .iter()
+            .map(|s| (s.name.as_str(), s.messages))
+            .collect();
+        assert_eq!(summary, [("empty", 0), ("greeting", 2)]);
+
+        assert!(store.contains("greeting"));
+        store.delete("greeting").unwrap();
+        assert!(!store.contains("greeting"));
+        assert!(store.load("greeting").is_err());
+        assert!(store.save("../escape", &session).is_err());
+        assert!(store.save("", &session).is_err());
+    }
 }
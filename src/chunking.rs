@@ -0,0 +1,453 @@
+//! # Chunking Module
+//!
+//! This module splits long documents into pieces small enough to embed well.
+//! `RAGSystem::with_chunker` plugs a [`Chunker`] into the RAG pipeline so that
+//! `add_document` stores one vector per chunk instead of one per document.
+//!
+//! Four strategies are provided:
+//! - [`FixedSizeChunker`]: equal windows of characters.
+//! - [`SentenceChunker`]: whole sentences packed up to a size limit.
+//! - [`RecursiveChunker`]: paragraphs, then lines, then sentences, then words.
+//! - [`MarkdownChunker`]: one chunk per section, labelled with its headers.
+//!
+//! All sizes are measured in characters, and every strategy supports overlap so
+//! that text near a boundary appears in both neighbouring chunks.
+
+/// Splits a document into chunks for embedding.
+pub trait Chunker: Send + Sync {
+    /// Splits `text` into chunks. Returns no chunks for blank text.
+    fn chunk(&self, text: &str) -> Vec<String>;
+}
+
+/// Splits text into windows of a fixed number of characters.
+#[derive(Debug, Clone)]
+pub struct FixedSizeChunker {
+    chunk_size: usize,
+    overlap: usize,
+}
+
+impl FixedSizeChunker {
+    /// Creates a chunker producing chunks of at most `chunk_size` characters.
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            overlap: 0,
+        }
+    }
+
+    /// Sets how many characters consecutive chunks share.
+    ///
+    /// The overlap is capped below the chunk size so chunking always advances.
+    pub fn with_overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap.min(self.chunk_size - 1);
+        self
+    }
+}
+
+impl Chunker for FixedSizeChunker {
+    fn chunk(&self, text: &str) -> Vec<String> {
+        let chars: Vec<char> = text.trim().chars().collect();
+        let step = self.chunk_size - self.overlap;
+        let mut chunks = Vec::new();
+        let mut start = 0;
+
+        while start < chars.len() {
+            let end = (start + self.chunk_size).min(chars.len());
+            chunks.push(chars[start..end].iter().collect());
+            if end == chars.len() {
+                break;
+            }
+            start += step;
+        }
+
+        chunks
+    }
+}
+
+/// Packs whole sentences into chunks of up to a maximum size.
+///
+/// Sentences end at `.`, `!` or `?` followed by whitespace, or at a blank line.
+/// A sentence longer than the limit is split into fixed-size pieces.
+#[derive(Debug, Clone)]
+pub struct SentenceChunker {
+    max_chars: usize,
+    overlap_sentences: usize,
+}
+
+impl SentenceChunker {
+    /// Creates a chunker producing chunks of at most `max_chars` characters.
+    pub fn new(max_chars: usize) -> Self {
+        Self {
+            max_chars: max_chars.max(1),
+            overlap_sentences: 0,
+        }
+    }
+
+    /// Sets how many trailing sentences of a chunk are repeated at the start of the next.
+    pub fn with_overlap(mut self, sentences: usize) -> Self {
+        self.overlap_sentences = sentences;
+        self
+    }
+}
+
+/// Splits text into sentences, keeping their trailing punctuation.
+fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let boundary = match c {
+            '.' | '!' | '?' => chars.peek().map_or(true, |(_, next)| next.is_whitespace()),
+            '\n' => chars.peek().is_some_and(|(_, next)| *next == '\n'),
+            _ => false,
+        };
+        if boundary {
+            let end = i + c.len_utf8();
+            let sentence = text[start..end].trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence);
+            }
+            start = end;
+        }
+    }
+
+    let rest = text[start..].trim();
+    if !rest.is_empty() {
+        sentences.push(rest);
+    }
+    sentences
+}
+
+impl Chunker for SentenceChunker {
+    fn chunk(&self, text: &str) -> Vec<String> {
+        let mut chunks = Vec::new();
+        let mut current: Vec<&str> = Vec::new();
+        let mut current_len = 0;
+
+        let oversized = FixedSizeChunker::new(self.max_chars);
+        let mut pieces: Vec<String> = Vec::new();
+        for sentence in split_sentences(text) {
+            if sentence.chars().count() > self.max_chars {
+                pieces.extend(oversized.chunk(sentence));
+            } else {
+                pieces.push(sentence.to_string());
+            }
+        }
+
+        for piece in &pieces {
+            let len = piece.chars().count();
+            let joined_len = if current.is_empty() {
+                len
+            } else {
+                current_len + 1 + len
+            };
+
+            if joined_len > self.max_chars && !current.is_empty() {
+                chunks.push(current.join(" "));
+
+                // Carry trailing sentences over while they leave room for the new one.
+                let keep = self.overlap_sentences.min(current.len());
+                current.drain(..current.len() - keep);
+                current_len = current.iter().map(|s| s.chars().count() + 1).sum::<usize>();
+                while !current.is_empty() && current_len + len > self.max_chars {
+                    current_len -= current[0].chars().count() + 1;
+                    current.remove(0);
+                }
+                current_len = current_len.saturating_sub(1);
+            }
+
+            current_len = if current.is_empty() {
+                len
+            } else {
+                current_len + 1 + len
+            };
+            current.push(piece);
+        }
+
+        if !current.is_empty() {
+            chunks.push(current.join(" "));
+        }
+        chunks
+    }
+}
+
+/// The separators `RecursiveChunker` tries by default, coarsest first.
+const DEFAULT_SEPARATORS: &[&str] = &["\n\n", "\n", ". ", " ", ""];
+
+/// Splits on paragraphs first and falls back to finer separators for long pieces.
+///
+/// Adjacent pieces are merged back together up to the chunk size, so chunks are
+/// as large as possible while breaking at the most natural boundary available.
+#[derive(Debug, Clone)]
+pub struct RecursiveChunker {
+    chunk_size: usize,
+    overlap: usize,
+    separators: Vec<String>,
+}
+
+impl RecursiveChunker {
+    /// Creates a chunker producing chunks of at most `chunk_size` characters.
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            overlap: 0,
+            separators: DEFAULT_SEPARATORS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Sets roughly how many characters consecutive chunks share.
+    ///
+    /// Overlap is made of whole pieces from the end of the previous chunk, so it
+    /// may be shorter than requested. It is capped below the chunk size.
+    pub fn with_overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap.min(self.chunk_size - 1);
+        self
+    }
+
+    /// Replaces the separators, which are tried in order. An empty separator
+    /// splits between characters.
+    pub fn with_separators(mut self, separators: Vec<String>) -> Self {
+        self.separators = separators;
+        self
+    }
+
+    /// Splits `text` with the first separator it contains, recursing into pieces
+    /// that are still too long.
+    fn split(&self, text: &str, separators: &[String]) -> Vec<String> {
+        let Some(index) = separators
+            .iter()
+            .position(|s| s.is_empty() || text.contains(s.as_str()))
+        else {
+            return FixedSizeChunker::new(self.chunk_size)
+                .with_overlap(self.overlap)
+                .chunk(text);
+        };
+        let separator = &separators[index];
+        let finer = &separators[index + 1..];
+
+        let pieces: Vec<String> = if separator.is_empty() {
+            text.chars().map(String::from).collect()
+        } else {
+            // Keep the separator on each piece so merged chunks read naturally.
+            text.split_inclusive(separator.as_str())
+                .map(str::to_string)
+                .collect()
+        };
+
+        let mut chunks = Vec::new();
+        let mut pending: Vec<String> = Vec::new();
+        for piece in pieces {
+            if piece.chars().count() <= self.chunk_size {
+                pending.push(piece);
+                continue;
+            }
+            chunks.extend(self.merge(std::mem::take(&mut pending)));
+            chunks.extend(self.split(&piece, finer));
+        }
+        chunks.extend(self.merge(pending));
+        chunks
+    }
+
+    /// Joins consecutive pieces into chunks of up to `chunk_size` characters.
+    fn merge(&self, pieces: Vec<String>) -> Vec<String> {
+        let mut chunks = Vec::new();
+        let mut window: Vec<String> = Vec::new();
+        let mut window_len = 0;
+
+        for piece in pieces {
+            let len = piece.chars().count();
+            if window_len + len > self.chunk_size && !window.is_empty() {
+                chunks.push(window.concat());
+                // Keep trailing pieces for overlap, as long as the next piece still fits.
+                while !window.is_empty()
+                    && (window_len > self.overlap || window_len + len > self.chunk_size)
+                {
+                    window_len -= window[0].chars().count();
+                    window.remove(0);
+                }
+            }
+            window_len += len;
+            window.push(piece);
+        }
+
+        if !window.is_empty() {
+            chunks.push(window.concat());
+        }
+        chunks
+    }
+}
+
+impl Chunker for RecursiveChunker {
+    fn chunk(&self, text: &str) -> Vec<String> {
+        self.split(text, &self.separators)
+            .into_iter()
+            .map(|chunk| chunk.trim().to_string())
+            .filter(|chunk| !chunk.is_empty())
+            .collect()
+    }
+}
+
+/// Splits Markdown at headers and labels each chunk with its header path.
+///
+/// Every chunk starts with the headers of the section it came from (for example
+/// `# Guide > ## Install`), so a chunk still says what it is about when it is
+/// retrieved on its own. Sections longer than the limit are split further with a
+/// [`RecursiveChunker`]. Headers inside fenced code blocks are ignored.
+#[derive(Debug, Clone)]
+pub struct MarkdownChunker {
+    max_chars: usize,
+    overlap: usize,
+}
+
+impl MarkdownChunker {
+    /// Creates a chunker producing chunks of at most about `max_chars` characters.
+    pub fn new(max_chars: usize) -> Self {
+        Self {
+            max_chars: max_chars.max(1),
+            overlap: 0,
+        }
+    }
+
+    /// Sets the overlap used when a long section is split.
+    pub fn with_overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap;
+        self
+    }
+}
+
+/// Returns the level and title of a Markdown ATX header line.
+fn markdown_header(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim()))
+}
+
+impl Chunker for MarkdownChunker {
+    fn chunk(&self, text: &str) -> Vec<String> {
+        let mut sections: Vec<(String, String)> = Vec::new();
+        let mut headers: Vec<(usize, String)> = Vec::new();
+        let mut body = String::new();
+        let mut in_code = false;
+
+        let path = |headers: &[(usize, String)]| {
+            headers
+                .iter()
+                .map(|(level, title)| format!("{} {}", "#".repeat(*level), title))
+                .collect::<Vec<_>>()
+                .join(" > ")
+        };
+
+        for line in text.lines() {
+            if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+                in_code = !in_code;
+            }
+            match markdown_header(line).filter(|_| !in_code) {
+                Some((level, title)) => {
+                    sections.push((path(&headers), std::mem::take(&mut body)));
+                    headers.retain(|(l, _)| *l < level);
+                    headers.push((level, title.to_string()));
+                }
+                None => {
+                    body.push_str(line);
+                    body.push('\n');
+                }
+            }
+        }
+        sections.push((path(&headers), body));
+
+        let mut chunks = Vec::new();
+        for (path, body) in sections {
+            let body = body.trim();
+            if body.is_empty() {
+                continue;
+            }
+            let prefix = if path.is_empty() {
+                String::new()
+            } else {
+                format!("{}\n\n", path)
+            };
+
+            let room = self
+                .max_chars
+                .saturating_sub(prefix.chars().count())
+                .max(self.max_chars / 2)
+                .max(1);
+            let splitter = RecursiveChunker::new(room).with_overlap(self.overlap);
+            for piece in splitter.chunk(body) {
+                chunks.push(format!("{}{}", prefix, piece));
+            }
+        }
+        chunks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests fixed-size windows with overlap, including multi-byte characters.
+    #[test]
+    fn test_fixed_size_chunker() {
+        let chunks = FixedSizeChunker::new(4).with_overlap(1).chunk("abcdéfghij");
+        assert_eq!(chunks, vec!["abcd", "défg", "ghij"]);
+        assert!(FixedSizeChunker::new(4).chunk("   ").is_empty());
+    }
+
+    /// Tests that sentences are kept whole and overlap repeats trailing sentences.
+    #[test]
+    fn test_sentence_chunker() {
+        let text = "One is short. Two is here! Three? Four ends it.";
+        let chunks = SentenceChunker::new(30).chunk(text);
+        assert_eq!(
+            chunks,
+            vec!["One is short. Two is here!", "Three? Four ends it."]
+        );
+
+        let chunks = SentenceChunker::new(30).with_overlap(1).chunk(text);
+        assert_eq!(
+            chunks,
+            vec![
+                "One is short. Two is here!",
+                "Two is here! Three?",
+                "Three? Four ends it."
+            ]
+        );
+        assert!(chunks.iter().all(|c| c.chars().count() <= 30));
+    }
+
+    /// Tests that paragraphs are preferred and long paragraphs are split further.
+    #[test]
+    fn test_recursive_chunker() {
+        let text = "First paragraph.\n\nSecond paragraph is a bit longer than the rest of them.";
+        let chunks = RecursiveChunker::new(30).chunk(text);
+        assert_eq!(chunks[0], "First paragraph.");
+        assert!(chunks.len() > 2);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 30));
+
+        let chunks = RecursiveChunker::new(12)
+            .with_overlap(6)
+            .chunk("aa bb cc dd ee ff");
+        assert_eq!(chunks, vec!["aa bb cc dd", "cc dd ee ff"]);
+    }
+
+    /// Tests that Markdown chunks carry their header path and ignore code fences.
+    #[test]
+    fn test_markdown_chunker() {
+        let text = "Intro text.\n\n# Guide\nWelcome.\n## Install\nRun it.\n```\n# not a header\n```\n# Other\nMore.";
+        let chunks = MarkdownChunker::new(200).chunk(text);
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks[0], "Intro text.");
+        assert_eq!(chunks[1], "# Guide\n\nWelcome.");
+        assert!(chunks[2].starts_with("# Guide > ## Install\n\nRun it."));
+        assert!(chunks[2].contains("# not a header"));
+        assert_eq!(chunks[3], "# Other\n\nMore.");
+    }
+}
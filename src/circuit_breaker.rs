@@ -0,0 +1,323 @@
+//! # Circuit Breaker Module
+//!
+//! This module provides a circuit breaker that stops calling a failing dependency
+//! (an LLM provider or a tool) for a cooldown period. After `failure_threshold`
+//! consecutive failures the circuit opens and calls fail immediately with
+//! `HeliosError::CircuitOpen`. Once the cooldown elapses a single trial call is let
+//! through (half-open); its outcome either closes the circuit or opens it again. A
+//! trial that never reports back, because its call was cancelled, expires after
+//! `trial_timeout_ms` so the next caller can try again.
+
+use crate::error::{HeliosError, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Configuration for a circuit breaker.
+///
+/// ```toml
+/// [llm.circuit_breaker]
+/// failure_threshold = 5
+/// cooldown_ms = 30000
+/// trial_timeout_ms = 120000
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// The number of consecutive failures that opens the circuit. `0` disables the breaker.
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a trial call is allowed, in milliseconds.
+    #[serde(default = "default_cooldown_ms")]
+    pub cooldown_ms: u64,
+    /// How long a half-open trial call may go without reporting its outcome before
+    /// another trial is let through, in milliseconds.
+    #[serde(default = "default_trial_timeout_ms")]
+    pub trial_timeout_ms: u64,
+}
+
+/// Returns the default failure threshold.
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+/// Returns the default cooldown in milliseconds.
+fn default_cooldown_ms() -> u64 {
+    30_000
+}
+
+/// Returns the default trial timeout in milliseconds.
+fn default_trial_timeout_ms() -> u64 {
+    120_000
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: default_failure_threshold(),
+            cooldown_ms: default_cooldown_ms(),
+            trial_timeout_ms: default_trial_timeout_ms(),
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// Creates a configuration with the given threshold and cooldown.
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown_ms: cooldown.as_millis() as u64,
+            trial_timeout_ms: default_trial_timeout_ms(),
+        }
+    }
+
+    /// Sets how long a half-open trial call may run before another one is allowed.
+    pub fn with_trial_timeout(mut self, timeout: Duration) -> Self {
+        self.trial_timeout_ms = timeout.as_millis() as u64;
+        self
+    }
+
+    /// Returns a configuration that never opens the circuit.
+    pub fn disabled() -> Self {
+        Self {
+            failure_threshold: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the cooldown as a `Duration`.
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_millis(self.cooldown_ms)
+    }
+
+    /// Returns the trial timeout as a `Duration`.
+    pub fn trial_timeout(&self) -> Duration {
+        Duration::from_millis(self.trial_timeout_ms)
+    }
+}
+
+/// The state of a circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls flow normally.
+    Closed,
+    /// Calls fail immediately until the cooldown elapses.
+    Open,
+    /// The cooldown has elapsed and a single trial call is in flight.
+    HalfOpen,
+}
+
+/// The mutable state shared by clones of a `CircuitBreaker`.
+#[derive(Debug)]
+struct BreakerState {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_started_at: Option<Instant>,
+}
+
+/// A circuit breaker guarding calls to a single dependency.
+///
+/// Clones share the same state, so one breaker can guard concurrent callers.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    name: String,
+    config: CircuitBreakerConfig,
+    inner: Arc<Mutex<BreakerState>>,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed circuit breaker for the named dependency.
+    pub fn new(name: impl Into<String>, config: CircuitBreakerConfig) -> Self {
+        Self {
+            name: name.into(),
+            config,
+            inner: Arc::new(Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                trial_started_at: None,
+            })),
+        }
+    }
+
+    /// Returns the name of the guarded dependency.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the current state, reporting `HalfOpen` once an open circuit's cooldown has elapsed.
+    pub fn state(&self) -> CircuitState {
+        let inner = self.lock();
+        match (inner.state, inner.opened_at) {
+            (CircuitState::Open, Some(opened)) if opened.elapsed() >= self.config.cooldown() => {
+                CircuitState::HalfOpen
+            }
+            (state, _) => state,
+        }
+    }
+
+    /// Checks whether a call may proceed.
+    ///
+    /// Returns `HeliosError::CircuitOpen` while the circuit is open, or while a
+    /// half-open trial call is already in flight and has not yet timed out.
+    pub fn check(&self) -> Result<()> {
+        if self.config.failure_threshold == 0 {
+            return Ok(());
+        }
+
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => {
+                let elapsed = inner.opened_at.map(|t| t.elapsed()).unwrap_or_default();
+                let cooldown = self.config.cooldown();
+                if elapsed >= cooldown {
+                    inner.state = CircuitState::HalfOpen;
+                    inner.trial_started_at = Some(Instant::now());
+                    Ok(())
+                } else {
+                    Err(HeliosError::CircuitOpen(format!(
+                        "'{}' failed {} times in a row; retrying in {:.1}s",
+                        self.name,
+                        inner.consecutive_failures,
+                        (cooldown - elapsed).as_secs_f32()
+                    )))
+                }
+            }
+            CircuitState::HalfOpen => {
+                let trial_expired = inner
+                    .trial_started_at
+                    .map(|t| t.elapsed() >= self.config.trial_timeout())
+                    .unwrap_or(true);
+                if trial_expired {
+                    // The previous trial never reported back; let this call try instead
+                    inner.trial_started_at = Some(Instant::now());
+                    Ok(())
+                } else {
+                    Err(HeliosError::CircuitOpen(format!(
+                        "'{}' is being probed after repeated failures",
+                        self.name
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Records a successful call, closing the circuit.
+    pub fn record_success(&self) {
+        let mut inner = self.lock();
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.trial_started_at = None;
+    }
+
+    /// Records a failed call, opening the circuit when the threshold is reached
+    /// or when a half-open trial call fails.
+    pub fn record_failure(&self) {
+        if self.config.failure_threshold == 0 {
+            return;
+        }
+
+        let mut inner = self.lock();
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        if inner.state == CircuitState::HalfOpen
+            || inner.consecutive_failures >= self.config.failure_threshold
+        {
+            if inner.state != CircuitState::Open {
+                tracing::warn!(
+                    "Circuit for '{}' opened after {} consecutive failures",
+                    self.name,
+                    inner.consecutive_failures
+                );
+            }
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+            inner.trial_started_at = None;
+        }
+    }
+
+    /// Records the outcome of a call and passes the result through.
+    pub fn record<T>(&self, result: Result<T>) -> Result<T> {
+        match &result {
+            Ok(_) => self.record_success(),
+            Err(_) => self.record_failure(),
+        }
+        result
+    }
+
+    /// Forces the circuit closed and clears the failure count.
+    pub fn reset(&self) {
+        self.record_success();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that the circuit opens after the threshold and rejects calls.
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker =
+            CircuitBreaker::new("llm", CircuitBreakerConfig::new(2, Duration::from_secs(60)));
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.check().is_ok());
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(matches!(breaker.check(), Err(HeliosError::CircuitOpen(_))));
+
+        breaker.reset();
+        assert!(breaker.check().is_ok());
+    }
+
+    /// Tests the half-open trial call after the cooldown.
+    #[test]
+    fn test_half_open_trial() {
+        let breaker = CircuitBreaker::new("tool", CircuitBreakerConfig::new(1, Duration::ZERO));
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        // The first caller gets the trial, concurrent callers are rejected.
+        assert!(breaker.check().is_ok());
+        assert!(breaker.check().is_err());
+
+        // A failed trial reopens the circuit; a successful one closes it.
+        breaker.record_failure();
+        assert!(breaker.check().is_ok());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    /// Tests that a trial call that never reports back expires.
+    #[test]
+    fn test_abandoned_trial_expires() {
+        let breaker = CircuitBreaker::new(
+            "llm",
+            CircuitBreakerConfig::new(1, Duration::ZERO).with_trial_timeout(Duration::ZERO),
+        );
+        breaker.record_failure();
+
+        // The first trial is dropped without recording an outcome.
+        assert!(breaker.check().is_ok());
+        assert!(breaker.check().is_ok());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    /// Tests that a zero threshold disables the breaker.
+    #[test]
+    fn test_disabled_breaker() {
+        let breaker = CircuitBreaker::new("llm", CircuitBreakerConfig::disabled());
+        for _ in 0..10 {
+            breaker.record_failure();
+        }
+        assert!(breaker.check().is_ok());
+    }
+}
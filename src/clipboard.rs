@@ -0,0 +1,220 @@
+//! # Clipboard Module
+//!
+//! `ClipboardTool` reads and writes the system clipboard for desktop assistants.
+//!
+//! The clipboard is driven from a worker thread that lives as long as the tool. On
+//! X11 the program that wrote the clipboard has to stay around to hand the text to
+//! other applications, so text the agent copies stays available after the call.
+//! Under Wayland the X11 clipboard is reached through XWayland.
+//!
+//! Requires the `clipboard` feature.
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{required_str, Tool, ToolParameter, ToolPermission, ToolResult};
+use crate::truncation::truncate_chars;
+use async_trait::async_trait;
+use copypasta::{ClipboardContext, ClipboardProvider};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+type Reply<T> = oneshot::Sender<std::result::Result<T, String>>;
+
+/// A request to the clipboard thread.
+enum Request {
+    Read(Reply<String>),
+    Write(String, Reply<()>),
+}
+
+/// A tool that reads and writes the system clipboard.
+///
+/// ```rust,no_run
+/// use helios_engine::ClipboardTool;
+///
+/// let clipboard = ClipboardTool::new().max_read_chars(5_000);
+/// ```
+pub struct ClipboardTool {
+    worker: Mutex<Option<mpsc::Sender<Request>>>,
+    max_read_chars: usize,
+}
+
+impl ClipboardTool {
+    /// Creates the tool. The clipboard is opened on first use.
+    pub fn new() -> Self {
+        Self {
+            worker: Mutex::new(None),
+            max_read_chars: 20_000,
+        }
+    }
+
+    /// Limits the characters of clipboard text returned to the agent (20,000 by default).
+    pub fn max_read_chars(mut self, max: usize) -> Self {
+        self.max_read_chars = max;
+        self
+    }
+
+    /// Hands a request to the clipboard thread, starting it if needed.
+    fn send(&self, request: Request) -> Result<()> {
+        let mut worker = self
+            .worker
+            .lock()
+            .map_err(|e| HeliosError::ToolError(format!("Failed to lock clipboard: {}", e)))?;
+        let sender = worker.get_or_insert_with(|| {
+            let (sender, requests) = mpsc::channel();
+            std::thread::spawn(move || serve(requests));
+            sender
+        });
+        sender.send(request).map_err(|_| {
+            *worker = None;
+            HeliosError::ToolError("The clipboard thread stopped".to_string())
+        })
+    }
+
+    async fn read(&self) -> Result<ToolResult> {
+        let (reply, response) = oneshot::channel();
+        self.send(Request::Read(reply))?;
+        Ok(match response.await {
+            Ok(Ok(text)) if text.is_empty() => ToolResult::success("(the clipboard is empty)"),
+            Ok(Ok(text)) => {
+                let max = self.max_read_chars;
+                let marker = format!("\n... (truncated to {} characters)", max);
+                ToolResult::success(truncate_chars(&text, max, &marker))
+            }
+            Ok(Err(e)) => ToolResult::error(format!("Could not read the clipboard: {}", e)),
+            Err(_) => ToolResult::error("The clipboard thread stopped"),
+        })
+    }
+
+    async fn write(&self, text: String) -> Result<ToolResult> {
+        let chars = text.chars().count();
+        let (reply, response) = oneshot::channel();
+        self.send(Request::Write(text, reply))?;
+        Ok(match response.await {
+            Ok(Ok(())) => ToolResult::success(format!("Copied {} characters", chars)),
+            Ok(Err(e)) => ToolResult::error(format!("Could not write the clipboard: {}", e)),
+            Err(_) => ToolResult::error("The clipboard thread stopped"),
+        })
+    }
+}
+
+impl Default for ClipboardTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs on the clipboard thread until the tool is dropped.
+///
+/// Opening the clipboard is retried on each request, since a desktop session may
+/// not be ready when the tool is first used.
+fn serve(requests: mpsc::Receiver<Request>) {
+    let mut context = None;
+    for request in requests {
+        match request {
+            Request::Read(reply) => {
+                let result = open(&mut context)
+                    .and_then(|clipboard| clipboard.get_contents().map_err(|e| e.to_string()));
+                let _ = reply.send(result);
+            }
+            Request::Write(text, reply) => {
+                let result = open(&mut context)
+                    .and_then(|clipboard| clipboard.set_contents(text).map_err(|e| e.to_string()));
+                let _ = reply.send(result);
+            }
+        }
+    }
+}
+
+fn open(
+    context: &mut Option<ClipboardContext>,
+) -> std::result::Result<&mut ClipboardContext, String> {
+    match context {
+        Some(clipboard) => Ok(clipboard),
+        slot @ None => Ok(slot.insert(ClipboardContext::new().map_err(|e| e.to_string())?)),
+    }
+}
+
+#[async_trait]
+impl Tool for ClipboardTool {
+    fn name(&self) -> &str {
+        "clipboard"
+    }
+
+    fn description(&self) -> &str {
+        "Read the text on the system clipboard, or copy text to it so the user can paste it."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        params.insert(
+            "action".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "'read' or 'write'".to_string(),
+                required: Some(true),
+            },
+        );
+        params.insert(
+            "text".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "For write: the text to copy".to_string(),
+                required: Some(false),
+            },
+        );
+        params
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Standard
+    }
+
+    fn call_permission(&self, args: &Value) -> ToolPermission {
+        match args.get("action").and_then(Value::as_str) {
+            Some("read") => ToolPermission::ReadOnly,
+            _ => ToolPermission::Standard,
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let action = required_str(&args, "action")?;
+        match action {
+            "read" => self.read().await,
+            "write" => {
+                let text = required_str(&args, "text")?;
+                self.write(text.to_string()).await
+            }
+            other => Ok(ToolResult::error(format!(
+                "Unknown action '{}'; use 'read' or 'write'",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Tests argument checks and permissions, which do not need a desktop session.
+    #[tokio::test]
+    async fn test_clipboard_tool_arguments() {
+        let tool = ClipboardTool::new();
+        assert_eq!(
+            tool.call_permission(&json!({"action": "read"})),
+            ToolPermission::ReadOnly
+        );
+        assert_eq!(
+            tool.call_permission(&json!({"action": "write", "text": "x"})),
+            ToolPermission::Standard
+        );
+        assert!(tool.execute(json!({"action": "write"})).await.is_err());
+        let result = tool.execute(json!({"action": "paste"})).await.unwrap();
+        assert!(!result.success);
+        // No clipboard thread is started until the clipboard is actually used.
+        assert!(tool.worker.lock().unwrap().is_none());
+    }
+}
@@ -0,0 +1,268 @@
+//! # Clock Module
+//!
+//! The engine reads the current time and generates IDs through this module instead
+//! of calling `Utc::now()` and `Uuid::new_v4()` directly. Timestamps on forest
+//! messages and RAG documents, plan IDs, completion IDs, the `timestamp` tool and
+//! temporary file names all come from here, so a test or a replay can make them
+//! deterministic by swapping in a [`FixedClock`] and [`SequentialIds`].
+//!
+//! Providers can be replaced for the whole process with [`set_clock`] and
+//! [`set_id_generator`], or for a single task with [`scope`], which leaves other
+//! tests running in parallel unaffected:
+//!
+//! ```rust
+//! use helios_engine::clock::{self, FixedClock, SequentialIds};
+//! use std::sync::Arc;
+//!
+//! # async fn example() {
+//! let start = "2024-01-01T00:00:00Z".parse().unwrap();
+//! clock::scope(Arc::new(FixedClock::new(start)), Arc::new(SequentialIds::new()), async {
+//!     assert_eq!(clock::now(), start);
+//!     assert_eq!(clock::new_id().to_string(), "00000000-0000-0000-0000-000000000001");
+//! })
+//! .await;
+//! # }
+//! ```
+//!
+//! Retry delays, timeouts and cache expiry keep using the real time.
+
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use uuid::Uuid;
+
+/// A source of the current time.
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// A source of unique IDs.
+pub trait IdGenerator: Send + Sync {
+    /// Returns a new ID.
+    fn new_id(&self) -> Uuid;
+}
+
+/// The system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Random version 4 UUIDs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn new_id(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// A clock that only moves when told to.
+///
+/// With a step, every reading advances the clock by that much, so successive
+/// timestamps stay distinct and ordered.
+#[derive(Debug)]
+pub struct FixedClock {
+    time: Mutex<DateTime<Utc>>,
+    step: chrono::Duration,
+}
+
+impl FixedClock {
+    /// Creates a clock stopped at `time`.
+    pub fn new(time: DateTime<Utc>) -> Self {
+        Self {
+            time: Mutex::new(time),
+            step: chrono::Duration::zero(),
+        }
+    }
+
+    /// Advances the clock by `step` after every reading.
+    pub fn with_step(mut self, step: chrono::Duration) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Sets the time.
+    pub fn set(&self, time: DateTime<Utc>) {
+        *self.time.lock().unwrap_or_else(|e| e.into_inner()) = time;
+    }
+
+    /// Moves the clock forward.
+    pub fn advance(&self, duration: chrono::Duration) {
+        *self.time.lock().unwrap_or_else(|e| e.into_inner()) += duration;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        let mut time = self.time.lock().unwrap_or_else(|e| e.into_inner());
+        let now = *time;
+        *time += self.step;
+        now
+    }
+}
+
+/// IDs counting up from 1: `00000000-0000-0000-0000-000000000001`, `...02`, ...
+#[derive(Debug)]
+pub struct SequentialIds {
+    next: AtomicU64,
+}
+
+impl Default for SequentialIds {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SequentialIds {
+    /// Creates a generator whose first ID is 1.
+    pub fn new() -> Self {
+        Self::starting_at(1)
+    }
+
+    /// Creates a generator whose first ID is `first`.
+    pub fn starting_at(first: u64) -> Self {
+        Self {
+            next: AtomicU64::new(first),
+        }
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn new_id(&self) -> Uuid {
+        Uuid::from_u128(self.next.fetch_add(1, Ordering::SeqCst) as u128)
+    }
+}
+
+/// The clock and ID generator in use.
+#[derive(Clone)]
+struct Providers {
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+}
+
+static GLOBAL_CLOCK: RwLock<Option<Arc<dyn Clock>>> = RwLock::new(None);
+static GLOBAL_IDS: RwLock<Option<Arc<dyn IdGenerator>>> = RwLock::new(None);
+
+tokio::task_local! {
+    static SCOPED: Providers;
+}
+
+/// Returns the current time from the active clock.
+pub fn now() -> DateTime<Utc> {
+    if let Ok(now) = SCOPED.try_with(|providers| providers.clock.now()) {
+        return now;
+    }
+    match &*GLOBAL_CLOCK.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(clock) => clock.now(),
+        None => Utc::now(),
+    }
+}
+
+/// Returns a new ID from the active generator.
+pub fn new_id() -> Uuid {
+    if let Ok(id) = SCOPED.try_with(|providers| providers.ids.new_id()) {
+        return id;
+    }
+    match &*GLOBAL_IDS.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(ids) => ids.new_id(),
+        None => Uuid::new_v4(),
+    }
+}
+
+/// Replaces the clock for the whole process.
+pub fn set_clock(clock: impl Clock + 'static) {
+    *GLOBAL_CLOCK.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(clock));
+}
+
+/// Replaces the ID generator for the whole process.
+pub fn set_id_generator(ids: impl IdGenerator + 'static) {
+    *GLOBAL_IDS.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(ids));
+}
+
+/// Restores the system clock and random IDs for the whole process.
+pub fn reset() {
+    *GLOBAL_CLOCK.write().unwrap_or_else(|e| e.into_inner()) = None;
+    *GLOBAL_IDS.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Runs `future` with its own clock and ID generator.
+///
+/// The providers apply to code running inside the future's task, but not to tasks
+/// it spawns.
+pub async fn scope<F: Future>(
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    future: F,
+) -> F::Output {
+    SCOPED.scope(Providers { clock, ids }, future).await
+}
+
+/// Runs the closure `f` with its own clock and ID generator.
+pub fn sync_scope<R>(clock: Arc<dyn Clock>, ids: Arc<dyn IdGenerator>, f: impl FnOnce() -> R) -> R {
+    SCOPED.sync_scope(Providers { clock, ids }, f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start() -> DateTime<Utc> {
+        "2024-01-01T00:00:00Z".parse().unwrap()
+    }
+
+    /// Tests that scoped providers make timestamps and IDs deterministic.
+    #[tokio::test]
+    async fn test_scoped_providers() {
+        let clock = Arc::new(FixedClock::new(start()).with_step(chrono::Duration::seconds(1)));
+        let ids = Arc::new(SequentialIds::starting_at(41));
+
+        let (first, second, id) = scope(clock.clone(), ids, async {
+            let first = now();
+            tokio::task::yield_now().await;
+            (first, now(), new_id())
+        })
+        .await;
+        assert_eq!(first, start());
+        assert_eq!(second, start() + chrono::Duration::seconds(1));
+        assert_eq!(id.as_u128(), 41);
+
+        clock.advance(chrono::Duration::hours(1));
+        assert_eq!(clock.now(), start() + chrono::Duration::seconds(3602));
+        // Outside the scope the real clock is used again.
+        assert!(now() > start() + chrono::Duration::days(365));
+    }
+
+    /// Tests that forest messages take their timestamps from the clock.
+    #[test]
+    fn test_forest_timestamps_use_clock() {
+        let clock = Arc::new(FixedClock::new(start()));
+        let message = sync_scope(clock, Arc::new(SequentialIds::new()), || {
+            crate::forest::ForestMessage::new("a".to_string(), None, "hi".to_string())
+        });
+        assert_eq!(message.timestamp, start());
+    }
+
+    /// Tests that the timestamp tool reports the injected time.
+    #[tokio::test]
+    async fn test_timestamp_tool_uses_clock() {
+        use crate::tools::{TimestampTool, Tool};
+
+        let clock = Arc::new(FixedClock::new(start()));
+        let result = scope(clock, Arc::new(SequentialIds::new()), async {
+            TimestampTool
+                .execute(serde_json::json!({"operation": "now"}))
+                .await
+                .unwrap()
+        })
+        .await;
+        assert!(result.output.contains("Unix timestamp: 1704067200"));
+    }
+}
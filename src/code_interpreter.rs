@@ -0,0 +1,509 @@
+//! # Code Interpreter Module
+//!
+//! `CodeInterpreterTool` lets an agent run short Python or JavaScript programs,
+//! for the data wrangling and calculations `CalculatorTool` cannot do. Each run
+//! starts a fresh interpreter in a working directory with a cleared environment,
+//! a wall-clock timeout and, on Unix, limits on CPU time, memory and file size.
+//! Output is captured and cut to a fixed size.
+//!
+//! The working directory doubles as a file exchange: give the tool a workspace
+//! and files placed there are visible to the code, while files the code writes
+//! stay behind and are listed in the result.
+//!
+//! These limits keep runaway code in check, but the interpreter still runs as the
+//! current user with network access. Run the agent in a container when the code
+//! cannot be trusted.
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{read_limited, required_str, Tool, ToolParameter, ToolPermission, ToolResult};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime};
+
+/// The file the code is written to before it runs, without its extension.
+const SCRIPT_NAME: &str = ".helios_code";
+
+/// A language the interpreter can run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeLanguage {
+    /// Python 3, run in isolated mode (`python3 -I`).
+    Python,
+    /// JavaScript, run with Node.js.
+    JavaScript,
+}
+
+impl CodeLanguage {
+    /// Parses a language name as given by the model.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "python" | "python3" | "py" => Some(Self::Python),
+            "javascript" | "js" | "node" => Some(Self::JavaScript),
+            _ => None,
+        }
+    }
+
+    /// Returns the language's name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Python => "python",
+            Self::JavaScript => "javascript",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Python => "py",
+            Self::JavaScript => "js",
+        }
+    }
+}
+
+/// A tool that runs Python or JavaScript code with resource limits.
+///
+/// ```rust,no_run
+/// use helios_engine::{Agent, CodeInterpreterTool, Config};
+///
+/// # async fn example() -> helios_engine::Result<()> {
+/// let agent = Agent::builder("Analyst")
+///     .config(Config::from_file("config.toml")?)
+///     .tool(Box::new(
+///         CodeInterpreterTool::new()
+///             .workspace("./data")
+///             .memory_limit_mb(1024),
+///     ))
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CodeInterpreterTool {
+    languages: Vec<CodeLanguage>,
+    python: String,
+    node: String,
+    workspace: Option<PathBuf>,
+    default_timeout: Duration,
+    max_timeout: Duration,
+    memory_limit_mb: u64,
+    max_output_bytes: usize,
+}
+
+impl CodeInterpreterTool {
+    /// Creates an interpreter for Python and JavaScript with a 30 second timeout,
+    /// a 512 MB memory limit and 64 KB of output per stream. Each run gets a new
+    /// temporary directory.
+    pub fn new() -> Self {
+        Self {
+            languages: vec![CodeLanguage::Python, CodeLanguage::JavaScript],
+            python: if cfg!(windows) { "python" } else { "python3" }.to_string(),
+            node: "node".to_string(),
+            workspace: None,
+            default_timeout: Duration::from_secs(30),
+            max_timeout: Duration::from_secs(120),
+            memory_limit_mb: 512,
+            max_output_bytes: 64 * 1024,
+        }
+    }
+
+    /// Limits the tool to the given languages.
+    pub fn languages(mut self, languages: impl IntoIterator<Item = CodeLanguage>) -> Self {
+        self.languages = languages.into_iter().collect();
+        self
+    }
+
+    /// Sets the Python interpreter to run (`python3` by default, `python` on Windows).
+    pub fn python(mut self, program: impl Into<String>) -> Self {
+        self.python = program.into();
+        self
+    }
+
+    /// Sets the Node.js executable to run (`node` by default).
+    pub fn node(mut self, program: impl Into<String>) -> Self {
+        self.node = program.into();
+        self
+    }
+
+    /// Runs code in `dir`, which is created if needed, instead of a temporary
+    /// directory. Files in it can be read by the code, and files the code writes
+    /// are kept and listed in the result.
+    pub fn workspace(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.workspace = Some(dir.into());
+        self
+    }
+
+    /// Sets the timeout used when the call does not ask for one.
+    pub fn default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = timeout;
+        self
+    }
+
+    /// Sets the longest timeout a call may ask for.
+    pub fn max_timeout(mut self, timeout: Duration) -> Self {
+        self.max_timeout = timeout;
+        self
+    }
+
+    /// Sets the memory the code may use. Enforced on Unix only.
+    pub fn memory_limit_mb(mut self, megabytes: u64) -> Self {
+        self.memory_limit_mb = megabytes;
+        self
+    }
+
+    /// Sets the most bytes of stdout, and separately of stderr, returned to the model.
+    pub fn max_output_bytes(mut self, bytes: usize) -> Self {
+        self.max_output_bytes = bytes;
+        self
+    }
+
+    /// Builds the command that runs `script` in `dir`.
+    fn command(
+        &self,
+        language: CodeLanguage,
+        script: &Path,
+        dir: &Path,
+        timeout: Duration,
+    ) -> tokio::process::Command {
+        let (program, mut args) = match language {
+            CodeLanguage::Python => (self.python.clone(), vec!["-I".to_string()]),
+            // V8 reserves far more address space than it uses, so Node gets a heap
+            // limit instead of an address-space limit.
+            CodeLanguage::JavaScript => (
+                self.node.clone(),
+                vec![format!("--max-old-space-size={}", self.memory_limit_mb)],
+            ),
+        };
+        args.push(script.to_string_lossy().into_owned());
+
+        let mut command = if cfg!(unix) {
+            // The limits are set by a shell that then replaces itself with the interpreter.
+            // POSIX `ulimit` takes one limit at a time.
+            let mut limits = format!(
+                "ulimit -t {} && ulimit -f {}",
+                timeout.as_secs().max(1),
+                // Files the code writes may be at most 100 MB (in 1 KB blocks).
+                100 * 1024
+            );
+            if language == CodeLanguage::Python {
+                limits.push_str(&format!(" && ulimit -v {}", self.memory_limit_mb * 1024));
+            }
+            let mut command = tokio::process::Command::new("sh");
+            command
+                .arg("-c")
+                .arg(format!("{} && exec \"$0\" \"$@\"", limits))
+                .arg(program)
+                .args(args);
+            command
+        } else {
+            let mut command = tokio::process::Command::new(program);
+            command.args(args);
+            command
+        };
+
+        command
+            .current_dir(dir)
+            .env_clear()
+            .envs(std::env::vars().filter(|(name, _)| {
+                matches!(
+                    name.as_str(),
+                    "PATH" | "LANG" | "LC_ALL" | "TZ" | "SYSTEMROOT" | "PATHEXT"
+                )
+            }))
+            .env("HOME", dir)
+            .env("TMPDIR", dir)
+            .env("PYTHONDONTWRITEBYTECODE", "1")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        command
+    }
+
+    /// Runs `code` in `dir` and describes the outcome.
+    async fn run(
+        &self,
+        language: CodeLanguage,
+        code: &str,
+        dir: &Path,
+        timeout: Duration,
+    ) -> Result<ToolResult> {
+        let before = snapshot(dir);
+        let script = dir.join(format!("{}.{}", SCRIPT_NAME, language.extension()));
+        std::fs::write(&script, code)?;
+
+        let outcome = async {
+            let mut child = self
+                .command(language, &script, dir, timeout)
+                .spawn()
+                .map_err(|e| {
+                    HeliosError::ToolError(format!(
+                        "Failed to start the {} interpreter: {}",
+                        language.name(),
+                        e
+                    ))
+                })?;
+            let stdout = child.stdout.take();
+            let stderr = child.stderr.take();
+            let limit = self.max_output_bytes as u64;
+
+            tokio::time::timeout(timeout, async {
+                tokio::try_join!(
+                    child.wait(),
+                    read_limited(stdout, limit),
+                    read_limited(stderr, limit)
+                )
+            })
+            .await
+            .map_err(|_| {
+                HeliosError::ToolError(format!(
+                    "Code timed out after {} seconds",
+                    timeout.as_secs()
+                ))
+            })?
+            .map_err(|e| HeliosError::ToolError(format!("Failed to run code: {}", e)))
+        }
+        .await;
+        let _ = std::fs::remove_file(&script);
+        let (status, stdout, stderr) = outcome?;
+
+        let exit_code = status.code().unwrap_or(-1);
+        let mut result = format!("Exit code: {}\n", exit_code);
+        for (label, (bytes, dropped)) in [("Stdout", stdout), ("Stderr", stderr)] {
+            if bytes.is_empty() {
+                continue;
+            }
+            result.push_str(&format!(
+                "{}:\n{}\n",
+                label,
+                String::from_utf8_lossy(&bytes)
+            ));
+            if dropped > 0 {
+                result.push_str(&format!("[{} more bytes truncated]\n", dropped));
+            }
+        }
+
+        if self.workspace.is_some() {
+            let after = snapshot(dir);
+            let changed: Vec<_> = after
+                .iter()
+                .filter(|(name, modified)| before.get(*name) != Some(*modified))
+                .map(|(name, _)| name.as_str())
+                .collect();
+            if !changed.is_empty() {
+                result.push_str(&format!("Files written: {}\n", changed.join(", ")));
+            }
+        }
+
+        if exit_code == 0 {
+            Ok(ToolResult::success(result))
+        } else {
+            Ok(ToolResult::error(result))
+        }
+    }
+}
+
+impl Default for CodeInterpreterTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Records the files at the top of `dir` and when each was last modified.
+fn snapshot(dir: &Path) -> BTreeMap<String, Option<SystemTime>> {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .map(|entry| {
+            let modified = entry.metadata().and_then(|m| m.modified()).ok();
+            (entry.file_name().to_string_lossy().into_owned(), modified)
+        })
+        .filter(|(name, _)| !name.starts_with(SCRIPT_NAME))
+        .collect()
+}
+
+#[async_trait]
+impl Tool for CodeInterpreterTool {
+    fn name(&self) -> &str {
+        "code_interpreter"
+    }
+
+    fn description(&self) -> &str {
+        "Run a short Python or JavaScript program and return what it prints. Use it for calculations, \
+         data processing and anything else easier to do in code. Each run starts fresh; print the results \
+         you need. Files the code writes to its working directory are listed in the result."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        params.insert(
+            "code".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "The program to run".to_string(),
+                required: Some(true),
+            },
+        );
+        params.insert(
+            "language".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: format!(
+                    "The language of the program: {} (default: {})",
+                    self.languages
+                        .iter()
+                        .map(|l| l.name())
+                        .collect::<Vec<_>>()
+                        .join(" or "),
+                    self.languages.first().map(|l| l.name()).unwrap_or("python")
+                ),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "timeout_seconds".to_string(),
+            ToolParameter {
+                param_type: "number".to_string(),
+                description: format!(
+                    "Time limit in seconds (default: {}, max: {})",
+                    self.default_timeout.as_secs(),
+                    self.max_timeout.as_secs()
+                ),
+                required: Some(false),
+            },
+        );
+        params
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Destructive
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let code = required_str(&args, "code")?;
+
+        let language = match args.get("language").and_then(|v| v.as_str()) {
+            Some(name) => CodeLanguage::parse(name)
+                .filter(|language| self.languages.contains(language))
+                .ok_or_else(|| {
+                    HeliosError::ToolError(format!(
+                        "Unsupported language '{}'. Supported: {}",
+                        name,
+                        self.languages
+                            .iter()
+                            .map(|l| l.name())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                })?,
+            None => *self
+                .languages
+                .first()
+                .ok_or_else(|| HeliosError::ToolError("No languages are enabled".to_string()))?,
+        };
+
+        let timeout = args
+            .get("timeout_seconds")
+            .and_then(|v| v.as_u64())
+            .map(Duration::from_secs)
+            .unwrap_or(self.default_timeout)
+            .min(self.max_timeout);
+
+        match &self.workspace {
+            Some(dir) => {
+                std::fs::create_dir_all(dir)?;
+                self.run(language, code, dir, timeout).await
+            }
+            None => {
+                let dir = tempfile::tempdir()?;
+                self.run(language, code, dir.path(), timeout).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Returns whether `program` can be started, so tests can skip missing interpreters.
+    fn available(program: &str) -> bool {
+        std::process::Command::new(program)
+            .arg("--version")
+            .output()
+            .is_ok()
+    }
+
+    /// Tests running Python code and reading what it prints.
+    #[tokio::test]
+    async fn test_code_interpreter_runs_python() {
+        let tool = CodeInterpreterTool::new();
+        if !available(&tool.python) {
+            return;
+        }
+
+        let result = tool
+            .execute(json!({"code": "import sys\nprint(sum(range(10)))\nsys.exit(3)"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("Exit code: 3"));
+        assert!(result.output.contains("45"));
+    }
+
+    /// Tests that code running past its timeout is stopped.
+    #[tokio::test]
+    async fn test_code_interpreter_timeout() {
+        let tool = CodeInterpreterTool::new();
+        if !available(&tool.python) {
+            return;
+        }
+
+        let err = tool
+            .execute(json!({"code": "while True: pass", "timeout_seconds": 1}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+    }
+
+    /// Tests that the workspace is shared with the code and written files are reported.
+    #[tokio::test]
+    async fn test_code_interpreter_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("input.txt"), "3 4 5").unwrap();
+        let tool = CodeInterpreterTool::new().workspace(dir.path());
+        if !available(&tool.python) {
+            return;
+        }
+
+        let code = "nums = open('input.txt').read().split()\n\
+                    open('total.txt', 'w').write(str(sum(map(int, nums))))";
+        let result = tool.execute(json!({"code": code})).await.unwrap();
+        assert!(result.success, "{}", result.output);
+        assert!(result.output.contains("Files written: total.txt"));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("total.txt")).unwrap(),
+            "12"
+        );
+        assert!(!dir.path().join(".helios_code.py").exists());
+    }
+
+    /// Tests that languages outside the enabled set are refused.
+    #[tokio::test]
+    async fn test_code_interpreter_languages() {
+        let tool = CodeInterpreterTool::new().languages([CodeLanguage::Python]);
+        assert!(tool
+            .execute(json!({"code": "console.log(1)", "language": "javascript"}))
+            .await
+            .is_err());
+        assert!(tool
+            .execute(json!({"code": "x", "language": "ruby"}))
+            .await
+            .is_err());
+        assert_eq!(CodeLanguage::parse("JS"), Some(CodeLanguage::JavaScript));
+    }
+}
@@ -3,11 +3,23 @@
 //! This module defines the data structures for configuring the Helios Engine.
 //! It includes settings for both remote and local Language Models (LLMs),
 //! and provides methods for loading and saving configurations from/to TOML files.
+//!
+//! String values in a config file may refer to environment variables as `${NAME}`
+//! or `${NAME:-default}`; write `$${` for a literal `${`. API keys can also be read
+//! from a named environment variable or a file with `api_key_env` and
+//! `api_key_file`, so that the key itself never appears in the TOML.
 
+use crate::capabilities::{CapabilityOverrides, ProviderCapabilities, UnsupportedParameters};
+use crate::circuit_breaker::CircuitBreakerConfig;
 use crate::error::{HeliosError, Result};
+#[cfg(feature = "local")]
+use crate::model_pool::ModelPoolConfig;
+use crate::response_cache::ResponseCacheConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// The main configuration for the Helios Engine.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,10 +30,96 @@ pub struct Config {
     #[cfg(feature = "local")]
     #[serde(default)]
     pub local: Option<LocalConfig>,
+    /// Named local models, configured as `[local_models.<name>]` and picked per
+    /// agent with `AgentBuilder::local_model`.
+    #[cfg(feature = "local")]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub local_models: BTreeMap<String, LocalConfig>,
+    /// How many local models may stay loaded at once, configured as `[model_pool]`.
+    #[cfg(feature = "local")]
+    #[serde(default)]
+    pub model_pool: ModelPoolConfig,
     /// The configuration for the Candle-based local LLM (optional).
     #[cfg(feature = "candle")]
     #[serde(default)]
     pub candle: Option<CandleConfig>,
+    /// Named variations of `[llm]`, configured as `[profiles.<name>]` and picked
+    /// per agent with `AgentBuilder::llm_profile`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, LLMProfile>,
+    /// Built-in toolsets and tools given to every agent built with this config,
+    /// such as `tools = ["filesystem", "web"]`.
+    ///
+    /// Being a plain value, the list goes before the first `[section]` of the file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+}
+
+/// LLM settings that override the `[llm]` section for the agents using a profile.
+///
+/// Settings left out are taken from `[llm]`, so a profile usually only names a
+/// different model:
+///
+/// ```toml
+/// [profiles.fast]
+/// model_name = "gpt-4o-mini"
+///
+/// [profiles.smart]
+/// model_name = "gpt-4o"
+/// temperature = 0.2
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LLMProfile {
+    /// The model to use.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_name: Option<String>,
+    /// The API base URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// The API key, for profiles that use a different provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// The sampling temperature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// The maximum number of tokens to generate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// The request timeout in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// Whether the provider accepts tool definitions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_tools: Option<bool>,
+}
+
+impl LLMProfile {
+    /// Returns `base` with the settings of this profile applied.
+    pub fn apply(&self, base: &LLMConfig) -> LLMConfig {
+        let mut llm = base.clone();
+        if let Some(model_name) = &self.model_name {
+            llm.model_name = model_name.clone();
+        }
+        if let Some(base_url) = &self.base_url {
+            llm.base_url = base_url.clone();
+        }
+        if let Some(api_key) = &self.api_key {
+            llm.api_key = api_key.clone();
+        }
+        if let Some(temperature) = self.temperature {
+            llm.temperature = temperature;
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            llm.max_tokens = max_tokens;
+        }
+        if self.timeout_secs.is_some() {
+            llm.timeout_secs = self.timeout_secs;
+        }
+        if let Some(supports_tools) = self.supports_tools {
+            llm.supports_tools = supports_tools;
+        }
+        llm
+    }
 }
 
 /// Configuration for a remote Language Model (LLM).
@@ -32,13 +130,225 @@ pub struct LLMConfig {
     /// The base URL of the LLM API.
     pub base_url: String,
     /// The API key for the LLM API.
+    ///
+    /// May be left out when `api_key_env` or `api_key_file` is set.
+    #[serde(default)]
     pub api_key: String,
+    /// The environment variable to read the API key from when the config is loaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+    /// The file to read the API key from when the config is loaded, relative to the
+    /// config file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_file: Option<PathBuf>,
     /// The temperature to use for the LLM.
     #[serde(default = "default_temperature")]
     pub temperature: f32,
     /// The maximum number of tokens to generate.
     #[serde(default = "default_max_tokens")]
     pub max_tokens: u32,
+    /// The request timeout in seconds, covering the whole response including streams.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// Whether the provider accepts OpenAI-style tool definitions and tool messages.
+    ///
+    /// When `false`, tools are not sent and earlier tool calls and results in the
+    /// conversation are rewritten as plain text.
+    #[serde(default = "default_supports_tools")]
+    pub supports_tools: bool,
+    /// Corrections to the parameters and limits detected for this model, set as `[llm.capabilities]`.
+    #[serde(default, skip_serializing_if = "CapabilityOverrides::is_empty")]
+    pub capabilities: CapabilityOverrides,
+    /// Whether unsupported request parameters are dropped with a warning or rejected.
+    #[serde(default)]
+    pub unsupported_parameters: UnsupportedParameters,
+    /// The retry policy for transient failures (rate limits, server errors, dropped connections).
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// The circuit breaker that short-circuits calls after repeated failures.
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// The cache that answers repeated identical requests without calling the provider.
+    #[serde(default)]
+    pub cache: ResponseCacheConfig,
+    /// Stop sequences enforced by the client, in addition to any passed with a request.
+    ///
+    /// Responses are cut before the first match and streams are cancelled as soon as
+    /// one appears, which helps with providers that ignore the `stop` parameter.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+    /// Regular expressions that end a response the same way as `stop_sequences`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_patterns: Vec<String>,
+    /// Providers tried in order when this one fails, configured as `[[llm.fallbacks]]`.
+    ///
+    /// Fallbacks of a fallback are ignored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<LLMConfig>,
+}
+
+impl Default for LLMConfig {
+    fn default() -> Self {
+        Self {
+            model_name: "gpt-3.5-turbo".to_string(),
+            base_url: "https://api.openai.com/v1".to_string(),
+            api_key: "your-api-key-here".to_string(),
+            api_key_env: None,
+            api_key_file: None,
+            temperature: default_temperature(),
+            max_tokens: default_max_tokens(),
+            timeout_secs: None,
+            supports_tools: default_supports_tools(),
+            capabilities: CapabilityOverrides::default(),
+            unsupported_parameters: UnsupportedParameters::default(),
+            retry: RetryConfig::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            cache: ResponseCacheConfig::default(),
+            stop_sequences: Vec::new(),
+            stop_patterns: Vec::new(),
+            fallbacks: Vec::new(),
+        }
+    }
+}
+
+impl LLMConfig {
+    /// Reads the API key from `api_key_env` or `api_key_file`, if either is set.
+    ///
+    /// Relative key files are resolved against `base_dir`. Fallback providers are
+    /// resolved too.
+    pub fn resolve_api_key(&mut self, base_dir: &Path) -> Result<()> {
+        match (&self.api_key_env, &self.api_key_file) {
+            (Some(_), Some(_)) => {
+                return Err(HeliosError::ConfigError(format!(
+                    "Set only one of api_key_env and api_key_file for model '{}'",
+                    self.model_name
+                )))
+            }
+            (Some(name), None) => {
+                self.api_key = std::env::var(name).map_err(|_| {
+                    HeliosError::ConfigError(format!(
+                        "Environment variable '{}' for the API key of model '{}' is not set",
+                        name, self.model_name
+                    ))
+                })?;
+            }
+            (None, Some(file)) => {
+                let path = base_dir.join(file);
+                let key = fs::read_to_string(&path).map_err(|e| {
+                    HeliosError::ConfigError(format!(
+                        "Failed to read API key file {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                self.api_key = key.trim().to_string();
+            }
+            (None, None) => {}
+        }
+        for fallback in &mut self.fallbacks {
+            fallback.resolve_api_key(base_dir)?;
+        }
+        Ok(())
+    }
+
+    /// Returns a copy without the API keys that were read from the environment or
+    /// a file, so they are not written back into a config file.
+    fn without_resolved_keys(&self) -> LLMConfig {
+        let mut llm = self.clone();
+        if llm.api_key_env.is_some() || llm.api_key_file.is_some() {
+            llm.api_key.clear();
+        }
+        llm.fallbacks = llm
+            .fallbacks
+            .iter()
+            .map(LLMConfig::without_resolved_keys)
+            .collect();
+        llm
+    }
+
+    /// Returns the capabilities of the configured model.
+    ///
+    /// Capabilities are detected from the model name and base URL, then
+    /// `supports_tools` and `[llm.capabilities]` are applied on top.
+    pub fn provider_capabilities(&self) -> ProviderCapabilities {
+        let mut capabilities = ProviderCapabilities::detect(&self.base_url, &self.model_name);
+        capabilities.tools &= self.supports_tools;
+        capabilities.with_overrides(&self.capabilities)
+    }
+}
+
+/// Retry policy for requests to a remote LLM.
+///
+/// Failed requests are retried with exponential backoff. When the server sends a
+/// `Retry-After` header, that delay is used instead (capped at `max_backoff_ms`).
+///
+/// ```toml
+/// [llm.retry]
+/// max_retries = 5
+/// initial_backoff_ms = 1000
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// The maximum number of retries after the first attempt. `0` disables retrying.
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// The delay before the first retry, in milliseconds.
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// The upper bound for any single delay, in milliseconds.
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// The factor the delay is multiplied by after each attempt.
+    #[serde(default = "default_backoff_multiplier")]
+    pub multiplier: f64,
+    /// Whether to randomize delays to avoid synchronized retries.
+    #[serde(default = "default_jitter")]
+    pub jitter: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            multiplier: default_backoff_multiplier(),
+            jitter: default_jitter(),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Returns a policy that never retries.
+    pub fn disabled() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the delay before retry number `attempt` (starting at 0), without jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self.initial_backoff_ms as f64 * self.multiplier.max(1.0).powi(attempt as i32);
+        Duration::from_millis(delay.min(self.max_backoff_ms as f64) as u64)
+    }
+
+    /// Returns the delay before retry number `attempt`, applying "full jitter" when enabled.
+    ///
+    /// `random` is a uniformly distributed value in `[0, 1)`.
+    pub fn backoff_with_jitter(&self, attempt: u32, random: f64) -> Duration {
+        let delay = self.backoff(attempt);
+        if self.jitter {
+            delay.mul_f64(random.clamp(0.0, 1.0))
+        } else {
+            delay
+        }
+    }
+
+    /// Caps a server-provided delay to the configured maximum.
+    pub fn cap(&self, delay: Duration) -> Duration {
+        delay.min(Duration::from_millis(self.max_backoff_ms))
+    }
 }
 
 /// Configuration for a local Language Model (LLM).
@@ -58,6 +368,125 @@ pub struct LocalConfig {
     /// The maximum number of tokens to generate.
     #[serde(default = "default_max_tokens")]
     pub max_tokens: u32,
+    /// The number of model layers to offload to the GPU.
+    ///
+    /// When unset, every layer is offloaded if this build has GPU support, and the
+    /// model runs on the CPU otherwise. `0` always uses the CPU.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n_gpu_layers: Option<u32>,
+    /// The number of prompt tokens processed per decoding step.
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u32,
+    /// Whether to memory-map the model file instead of reading it into memory.
+    #[serde(default = "default_use_mmap")]
+    pub use_mmap: bool,
+    /// Whether to lock the model in RAM so it cannot be swapped out.
+    #[serde(default)]
+    pub use_mlock: bool,
+    /// The quantization to download, such as `Q4_K_M` or `Q8_0`.
+    ///
+    /// Replaces the quantization named at the end of `model_file`, so switching
+    /// between variants of a model only needs this setting changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quantization: Option<String>,
+    /// A small model that drafts tokens for this one to verify, configured as
+    /// `[local.draft]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub draft: Option<DraftModelConfig>,
+}
+
+/// The draft model used for speculative decoding.
+///
+/// The draft model guesses several tokens ahead, and the main model checks all
+/// of them in a single step, keeping the guesses up to the first one it would
+/// not have picked itself. The output is unchanged; long generations just need
+/// fewer steps of the large model. The draft model must share the main model's
+/// tokenizer, which in practice means a smaller model of the same family.
+#[cfg(feature = "local")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DraftModelConfig {
+    /// The Hugging Face repository of the draft model. Not needed when
+    /// `model_file` is a path to a file on disk.
+    #[serde(default)]
+    pub huggingface_repo: String,
+    /// The draft model file, or a path to a GGUF file on disk.
+    pub model_file: String,
+    /// The most tokens drafted before the main model checks them.
+    #[serde(default = "default_max_draft_tokens")]
+    pub max_draft_tokens: u32,
+    /// Drafting stops early once the draft model gives its next token a lower
+    /// probability than this, since such guesses are seldom accepted.
+    #[serde(default = "default_draft_min_probability")]
+    pub min_probability: f32,
+}
+
+#[cfg(feature = "local")]
+impl Default for LocalConfig {
+    fn default() -> Self {
+        Self {
+            huggingface_repo: String::new(),
+            model_file: String::new(),
+            context_size: default_context_size(),
+            temperature: default_temperature(),
+            max_tokens: default_max_tokens(),
+            n_gpu_layers: None,
+            batch_size: default_batch_size(),
+            use_mmap: default_use_mmap(),
+            use_mlock: false,
+            quantization: None,
+            draft: None,
+        }
+    }
+}
+
+#[cfg(feature = "local")]
+impl LocalConfig {
+    /// Returns the model file to load, with `quantization` applied.
+    ///
+    /// GGUF files usually end in their quantization, as in `Qwen3-0.6B-Q4_K_M.gguf`.
+    /// That suffix is replaced, or added when the file name has none.
+    pub fn model_file_name(&self) -> String {
+        let Some(quantization) = self.quantization.as_deref().filter(|q| !q.is_empty()) else {
+            return self.model_file.clone();
+        };
+        let stem = match self.model_file.rsplit_once('.') {
+            Some((stem, extension)) if extension.eq_ignore_ascii_case("gguf") => stem,
+            _ => self.model_file.as_str(),
+        };
+        let stem = match stem.rfind(['-', '.']) {
+            Some(split) if is_quantization(&stem[split + 1..]) => &stem[..split],
+            _ => stem,
+        };
+        format!("{}-{}.gguf", stem, quantization)
+    }
+
+    /// Returns the configuration that loads the draft model, if there is one.
+    ///
+    /// The draft model is loaded with the same GPU and memory settings as the
+    /// main model.
+    pub fn draft_model(&self) -> Option<LocalConfig> {
+        self.draft.as_ref().map(|draft| LocalConfig {
+            huggingface_repo: draft.huggingface_repo.clone(),
+            model_file: draft.model_file.clone(),
+            quantization: None,
+            draft: None,
+            ..self.clone()
+        })
+    }
+}
+
+/// Whether `tag` names a GGUF quantization, such as `Q4_K_M`, `IQ3_XS` or `F16`.
+#[cfg(feature = "local")]
+fn is_quantization(tag: &str) -> bool {
+    let tag = tag.to_ascii_uppercase();
+    if matches!(tag.as_str(), "F16" | "BF16" | "F32" | "FP16") {
+        return true;
+    }
+    let digits = tag
+        .strip_prefix("IQ")
+        .or_else(|| tag.strip_prefix('Q'))
+        .unwrap_or("");
+    digits.starts_with(|c: char| c.is_ascii_digit())
 }
 
 /// Configuration for a Candle-based local Language Model (LLM).
@@ -92,18 +521,91 @@ fn default_max_tokens() -> u32 {
     2048
 }
 
+/// Returns the default tool support setting.
+fn default_supports_tools() -> bool {
+    true
+}
+
+/// Returns the default maximum number of retries.
+fn default_max_retries() -> u32 {
+    3
+}
+
+/// Returns the default initial backoff in milliseconds.
+fn default_initial_backoff_ms() -> u64 {
+    500
+}
+
+/// Returns the default maximum backoff in milliseconds.
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+/// Returns the default backoff multiplier.
+fn default_backoff_multiplier() -> f64 {
+    2.0
+}
+
+/// Returns the default jitter setting.
+fn default_jitter() -> bool {
+    true
+}
+
 /// Returns the default context size.
 #[cfg(any(feature = "local", feature = "candle"))]
 fn default_context_size() -> usize {
     2048
 }
 
+/// Returns the default number of prompt tokens per decoding step.
+#[cfg(feature = "local")]
+fn default_batch_size() -> u32 {
+    512
+}
+
+/// Returns the default use_mmap setting.
+#[cfg(feature = "local")]
+fn default_use_mmap() -> bool {
+    true
+}
+
+/// Returns the default number of tokens drafted per step.
+#[cfg(feature = "local")]
+fn default_max_draft_tokens() -> u32 {
+    8
+}
+
+/// Returns the default probability below which drafting stops.
+#[cfg(feature = "local")]
+fn default_draft_min_probability() -> f32 {
+    0.75
+}
+
 /// Returns the default use_gpu setting.
 #[cfg(feature = "candle")]
 fn default_use_gpu() -> bool {
     true
 }
 
+/// Builds the error for a name missing from a named section, listing the names configured.
+fn unknown_name<'a>(
+    kind: &str,
+    name: &str,
+    known: impl Iterator<Item = &'a String>,
+) -> HeliosError {
+    let known: Vec<&str> = known.map(String::as_str).collect();
+    HeliosError::ConfigError(format!(
+        "Unknown {} '{}' (configured: {})",
+        kind,
+        name,
+        if known.is_empty() {
+            "none".to_string()
+        } else {
+            known.join(", ")
+        }
+    ))
+}
+
 impl Config {
     /// Loads the configuration from a TOML file.
     ///
@@ -115,30 +617,103 @@ impl Config {
     ///
     /// A `Result` containing the loaded `Config`.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let content = fs::read_to_string(path)
-            .map_err(|e| HeliosError::ConfigError(format!("Failed to read config file: {}", e)))?;
+        let path = path.as_ref();
+        let mut config: Config = read_config_file(path)?.try_into()?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        config.llm.resolve_api_key(base_dir)?;
+        Ok(config)
+    }
 
-        let config: Config = toml::from_str(&content)?;
+    /// Creates a configuration from environment variables alone.
+    ///
+    /// Reads `HELIOS_MODEL`, `HELIOS_BASE_URL`, `HELIOS_API_KEY` (or
+    /// `OPENAI_API_KEY`), `HELIOS_TEMPERATURE`, `HELIOS_MAX_TOKENS` and
+    /// `HELIOS_TIMEOUT_SECS`; unset variables keep their defaults.
+    pub fn from_env() -> Result<Self> {
+        fn parsed<T: std::str::FromStr>(name: &str) -> Result<Option<T>> {
+            match std::env::var(name) {
+                Ok(value) => value.trim().parse().map(Some).map_err(|_| {
+                    HeliosError::ConfigError(format!(
+                        "Environment variable {} has an invalid value '{}'",
+                        name, value
+                    ))
+                }),
+                Err(_) => Ok(None),
+            }
+        }
+
+        let mut config = Self::new_default();
+        let llm = &mut config.llm;
+        if let Ok(model_name) = std::env::var("HELIOS_MODEL") {
+            llm.model_name = model_name;
+        }
+        if let Ok(base_url) = std::env::var("HELIOS_BASE_URL") {
+            llm.base_url = base_url;
+        }
+        if let Ok(api_key) =
+            std::env::var("HELIOS_API_KEY").or_else(|_| std::env::var("OPENAI_API_KEY"))
+        {
+            llm.api_key = api_key;
+        }
+        if let Some(temperature) = parsed("HELIOS_TEMPERATURE")? {
+            llm.temperature = temperature;
+        }
+        if let Some(max_tokens) = parsed("HELIOS_MAX_TOKENS")? {
+            llm.max_tokens = max_tokens;
+        }
+        if let Some(timeout_secs) = parsed("HELIOS_TIMEOUT_SECS")? {
+            llm.timeout_secs = Some(timeout_secs);
+        }
         Ok(config)
     }
 
     /// Creates a new default configuration.
     pub fn new_default() -> Self {
         Self {
-            llm: LLMConfig {
-                model_name: "gpt-3.5-turbo".to_string(),
-                base_url: "https://api.openai.com/v1".to_string(),
-                api_key: "your-api-key-here".to_string(),
-                temperature: 0.7,
-                max_tokens: 2048,
-            },
+            llm: LLMConfig::default(),
             #[cfg(feature = "local")]
             local: None,
+            #[cfg(feature = "local")]
+            local_models: BTreeMap::new(),
+            #[cfg(feature = "local")]
+            model_pool: ModelPoolConfig::default(),
             #[cfg(feature = "candle")]
             candle: None,
+            profiles: BTreeMap::new(),
+            tools: Vec::new(),
+        }
+    }
+
+    /// Returns the `[llm]` configuration with the named profile applied.
+    pub fn profile(&self, name: &str) -> Result<LLMConfig> {
+        match self.profiles.get(name) {
+            Some(profile) => Ok(profile.apply(&self.llm)),
+            None => Err(unknown_name("LLM profile", name, self.profiles.keys())),
         }
     }
 
+    /// Returns the named `[local_models.<name>]` configuration.
+    #[cfg(feature = "local")]
+    pub fn local_model(&self, name: &str) -> Result<LocalConfig> {
+        self.local_models
+            .get(name)
+            .cloned()
+            .ok_or_else(|| unknown_name("local model", name, self.local_models.keys()))
+    }
+
+    /// Adds or replaces a named local model.
+    #[cfg(feature = "local")]
+    pub fn with_local_model(mut self, name: impl Into<String>, model: LocalConfig) -> Self {
+        self.local_models.insert(name.into(), model);
+        self
+    }
+
+    /// Adds or replaces a named profile.
+    pub fn with_profile(mut self, name: impl Into<String>, profile: LLMProfile) -> Self {
+        self.profiles.insert(name.into(), profile);
+        self
+    }
+
     /// Loads configuration from a file or falls back to defaults.
     ///
     /// This is a convenience method that attempts to load from the specified file
@@ -187,7 +762,9 @@ impl Config {
     ///
     /// A `Result` indicating success or failure.
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        let content = toml::to_string_pretty(self)
+        let mut config = self.clone();
+        config.llm = self.llm.without_resolved_keys();
+        let content = toml::to_string_pretty(&config)
             .map_err(|e| HeliosError::ConfigError(format!("Failed to serialize config: {}", e)))?;
 
         fs::write(path, content)
@@ -197,6 +774,87 @@ impl Config {
     }
 }
 
+/// Reads a TOML config file with its environment variable references expanded.
+///
+/// Sections with their own types, such as `[voice]` and `[rag]`, are read from the
+/// returned value.
+pub(crate) fn read_config_file(path: &Path) -> Result<toml::Value> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        HeliosError::ConfigError(format!(
+            "Failed to read config file '{}': {}",
+            path.display(),
+            e
+        ))
+    })?;
+    let mut value = toml::Value::Table(toml::from_str(&content)?);
+    expand_env_in_value(&mut value)?;
+    Ok(value)
+}
+
+/// Expands `${NAME}` and `${NAME:-default}` in every string of a parsed config.
+fn expand_env_in_value(value: &mut toml::Value) -> Result<()> {
+    match value {
+        toml::Value::String(s) => *s = expand_env_vars(s)?,
+        toml::Value::Array(items) => {
+            for item in items {
+                expand_env_in_value(item)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, item) in table.iter_mut() {
+                expand_env_in_value(item)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Replaces `${NAME}` with the value of the environment variable `NAME`.
+///
+/// `${NAME:-default}` falls back to `default` when the variable is unset or empty,
+/// and `$${` is a literal `${`. A variable that is unset and has no default is an
+/// error.
+pub fn expand_env_vars(text: &str) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(literal) = after.strip_prefix("${") {
+            out.push_str("${");
+            rest = literal;
+            continue;
+        }
+        let Some(body) = after.strip_prefix('{') else {
+            out.push('$');
+            rest = after;
+            continue;
+        };
+        let end = body.find('}').ok_or_else(|| {
+            HeliosError::ConfigError(format!("Unclosed '${{' in config value '{}'", text))
+        })?;
+        let reference = &body[..end];
+        let (name, default) = match reference.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (reference, None),
+        };
+        match (std::env::var(name).ok().filter(|v| !v.is_empty()), default) {
+            (Some(value), _) => out.push_str(&value),
+            (None, Some(default)) => out.push_str(default),
+            (None, None) => {
+                return Err(HeliosError::ConfigError(format!(
+                    "Environment variable '{}' used in the config is not set",
+                    name
+                )))
+            }
+        }
+        rest = &body[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
 /// A builder for creating configurations with a fluent API.
 pub struct ConfigBuilder {
     model_name: String,
@@ -204,6 +862,7 @@ pub struct ConfigBuilder {
     api_key: String,
     temperature: f32,
     max_tokens: u32,
+    retry: RetryConfig,
 }
 
 impl ConfigBuilder {
@@ -216,6 +875,7 @@ impl ConfigBuilder {
                 .unwrap_or_else(|_| "your-api-key-here".to_string()),
             temperature: 0.7,
             max_tokens: 2048,
+            retry: RetryConfig::default(),
         }
     }
 
@@ -274,6 +934,18 @@ impl ConfigBuilder {
         self.max_tokens(tokens)
     }
 
+    /// Sets the retry policy for transient LLM failures.
+    pub fn retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sets the maximum number of retries for transient LLM failures.
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.retry.max_retries = retries;
+        self
+    }
+
     /// Builds the configuration.
     pub fn build(self) -> Config {
         Config {
@@ -283,11 +955,19 @@ impl ConfigBuilder {
                 api_key: self.api_key,
                 temperature: self.temperature,
                 max_tokens: self.max_tokens,
+                retry: self.retry,
+                ..LLMConfig::default()
             },
             #[cfg(feature = "local")]
             local: None,
+            #[cfg(feature = "local")]
+            local_models: BTreeMap::new(),
+            #[cfg(feature = "local")]
+            model_pool: ModelPoolConfig::default(),
             #[cfg(feature = "candle")]
             candle: None,
+            profiles: BTreeMap::new(),
+            tools: Vec::new(),
         }
     }
 }
@@ -387,4 +1067,304 @@ max_tokens = 2048
         #[cfg(feature = "local")]
         assert_eq!(default_context_size(), 2048);
     }
+
+    /// Tests picking a quantization of a local model.
+    #[test]
+    #[cfg(feature = "local")]
+    fn test_local_model_quantization() {
+        let config = |model_file: &str, quantization: Option<&str>| LocalConfig {
+            model_file: model_file.to_string(),
+            quantization: quantization.map(str::to_string),
+            ..LocalConfig::default()
+        };
+        assert_eq!(
+            config("Qwen3-0.6B-Q4_K_M.gguf", None).model_file_name(),
+            "Qwen3-0.6B-Q4_K_M.gguf"
+        );
+        assert_eq!(
+            config("Qwen3-0.6B-Q4_K_M.gguf", Some("Q8_0")).model_file_name(),
+            "Qwen3-0.6B-Q8_0.gguf"
+        );
+        assert_eq!(
+            config("bge-small-en-v1.5-q8_0.gguf", Some("f16")).model_file_name(),
+            "bge-small-en-v1.5-f16.gguf"
+        );
+        assert_eq!(
+            config("model.gguf", Some("IQ3_XS")).model_file_name(),
+            "model-IQ3_XS.gguf"
+        );
+
+        let parsed: LocalConfig = toml::from_str(
+            "huggingface_repo = \"a/b\"\nmodel_file = \"m.gguf\"\nn_gpu_layers = 20",
+        )
+        .unwrap();
+        assert_eq!(parsed.n_gpu_layers, Some(20));
+        assert_eq!(parsed.batch_size, 512);
+        assert!(parsed.use_mmap && !parsed.use_mlock);
+    }
+
+    /// Tests configuring a draft model for speculative decoding.
+    #[test]
+    #[cfg(feature = "local")]
+    fn test_local_draft_model() {
+        let content = r#"
+huggingface_repo = "unsloth/Qwen3-8B-GGUF"
+model_file = "Qwen3-8B-Q4_K_M.gguf"
+quantization = "Q8_0"
+n_gpu_layers = 40
+
+[draft]
+model_file = "/models/Qwen3-0.6B-Q8_0.gguf"
+max_draft_tokens = 12
+"#;
+        let config: LocalConfig = toml::from_str(content).unwrap();
+        let settings = config.draft.clone().unwrap();
+        assert_eq!(settings.max_draft_tokens, 12);
+        assert_eq!(settings.min_probability, 0.75);
+
+        let draft = config.draft_model().unwrap();
+        assert_eq!(draft.model_file_name(), "/models/Qwen3-0.6B-Q8_0.gguf");
+        assert_eq!(draft.n_gpu_layers, Some(40));
+        assert!(draft.draft.is_none());
+        assert!(LocalConfig::default().draft_model().is_none());
+    }
+
+    /// Tests named local models and the model pool limits.
+    #[test]
+    #[cfg(feature = "local")]
+    fn test_local_models() {
+        let content = r#"
+[llm]
+model_name = "gpt-4o"
+base_url = "https://api.openai.com/v1"
+api_key = "test-key"
+
+[local_models.router]
+huggingface_repo = "unsloth/Qwen3-0.6B-GGUF"
+model_file = "Qwen3-0.6B-Q4_K_M.gguf"
+
+[local_models.answer]
+huggingface_repo = "unsloth/Qwen3-8B-GGUF"
+model_file = "Qwen3-8B-Q4_K_M.gguf"
+context_size = 8192
+
+[model_pool]
+max_memory_mb = 12000
+"#;
+        let config: Config = toml::from_str(content).unwrap();
+        assert_eq!(config.local_model("answer").unwrap().context_size, 8192);
+        assert_eq!(config.model_pool.max_models, 2);
+        assert_eq!(config.model_pool.max_memory_mb, Some(12000));
+
+        let err = config.local_model("coder").unwrap_err().to_string();
+        assert!(err.contains("'coder'") && err.contains("answer, router"));
+    }
+
+    /// Tests that the retry policy defaults apply and can be overridden from TOML.
+    #[test]
+    fn test_retry_config_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+[llm]
+model_name = "gpt-4"
+base_url = "https://api.openai.com/v1"
+api_key = "test-key"
+
+[llm.retry]
+max_retries = 5
+jitter = false
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.llm.retry.max_retries, 5);
+        assert!(!config.llm.retry.jitter);
+        assert_eq!(config.llm.retry.initial_backoff_ms, 500);
+
+        let config = Config::new_default();
+        assert_eq!(config.llm.retry.max_retries, 3);
+    }
+
+    /// Tests parsing fallback providers from `[[llm.fallbacks]]` and saving them back.
+    #[test]
+    fn test_llm_fallbacks_from_toml() {
+        let content = r#"
+[llm]
+model_name = "gpt-4"
+base_url = "https://api.openai.com/v1"
+api_key = "test-key"
+timeout_secs = 30
+
+[[llm.fallbacks]]
+model_name = "claude-proxy"
+base_url = "http://localhost:4000/v1"
+api_key = "other-key"
+
+[[llm.fallbacks]]
+model_name = "llama3"
+base_url = "http://localhost:11434/v1"
+api_key = "not-needed"
+supports_tools = false
+"#;
+        let config: Config = toml::from_str(content).unwrap();
+        assert_eq!(config.llm.timeout_secs, Some(30));
+        assert_eq!(config.llm.fallbacks.len(), 2);
+        assert_eq!(config.llm.fallbacks[0].model_name, "claude-proxy");
+        assert!(config.llm.fallbacks[0].supports_tools);
+        assert!(!config.llm.fallbacks[1].supports_tools);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        config.save(&path).unwrap();
+        let loaded = Config::from_file(&path).unwrap();
+        assert_eq!(loaded.llm.fallbacks.len(), 2);
+        assert_eq!(loaded.llm.fallbacks[1].model_name, "llama3");
+    }
+
+    /// Tests `${NAME}` references, defaults and escapes.
+    #[test]
+    fn test_expand_env_vars() {
+        std::env::set_var("HELIOS_TEST_EXPAND_HOST", "example.com");
+        assert_eq!(
+            expand_env_vars("https://${HELIOS_TEST_EXPAND_HOST}/v1").unwrap(),
+            "https://example.com/v1"
+        );
+        assert_eq!(
+            expand_env_vars("${HELIOS_TEST_EXPAND_UNSET:-fallback} costs $5").unwrap(),
+            "fallback costs $5"
+        );
+        assert_eq!(expand_env_vars("$${literal}").unwrap(), "${literal}");
+        assert!(expand_env_vars("${HELIOS_TEST_EXPAND_UNSET}").is_err());
+        assert!(expand_env_vars("${unclosed").is_err());
+    }
+
+    /// Tests that API keys are read from the environment or a key file and are not
+    /// written back when the config is saved.
+    #[test]
+    fn test_config_api_key_sources() {
+        std::env::set_var("HELIOS_TEST_SOURCE_KEY", "env-key");
+        std::env::set_var("HELIOS_TEST_SOURCE_MODEL", "gpt-4o");
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("key.txt"), "file-key\n").unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            r#"
+# Comments may mention ${UNSET_VARIABLES} freely.
+[llm]
+model_name = "${HELIOS_TEST_SOURCE_MODEL}"
+base_url = "https://api.openai.com/v1"
+api_key_env = "HELIOS_TEST_SOURCE_KEY"
+
+[[llm.fallbacks]]
+model_name = "backup"
+base_url = "http://localhost:4000/v1"
+api_key_file = "key.txt"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.llm.model_name, "gpt-4o");
+        assert_eq!(config.llm.api_key, "env-key");
+        assert_eq!(config.llm.fallbacks[0].api_key, "file-key");
+
+        let saved = dir.path().join("saved.toml");
+        config.save(&saved).unwrap();
+        let content = fs::read_to_string(&saved).unwrap();
+        assert!(!content.contains("env-key") && !content.contains("file-key"));
+        assert_eq!(Config::from_file(&saved).unwrap().llm.api_key, "env-key");
+
+        std::env::remove_var("HELIOS_TEST_SOURCE_KEY");
+        assert!(Config::from_file(&path).is_err());
+    }
+
+    /// Tests building a configuration from environment variables.
+    #[test]
+    fn test_config_from_env() {
+        std::env::set_var("HELIOS_MODEL", "llama3.1");
+        std::env::set_var("HELIOS_MAX_TOKENS", "512");
+        std::env::set_var("HELIOS_TIMEOUT_SECS", "30");
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.llm.model_name, "llama3.1");
+        assert_eq!(config.llm.max_tokens, 512);
+        assert_eq!(config.llm.timeout_secs, Some(30));
+
+        std::env::set_var("HELIOS_MAX_TOKENS", "many");
+        assert!(Config::from_env().is_err());
+        for name in ["HELIOS_MODEL", "HELIOS_MAX_TOKENS", "HELIOS_TIMEOUT_SECS"] {
+            std::env::remove_var(name);
+        }
+    }
+
+    /// Tests that profiles override only the settings they name.
+    #[test]
+    fn test_llm_profiles() {
+        let content = r#"
+[llm]
+model_name = "gpt-4o"
+base_url = "https://api.openai.com/v1"
+api_key = "test-key"
+temperature = 0.5
+
+[profiles.fast]
+model_name = "gpt-4o-mini"
+
+[profiles.local]
+model_name = "llama3"
+base_url = "http://localhost:11434/v1"
+supports_tools = false
+"#;
+        let config: Config = toml::from_str(content).unwrap();
+        let fast = config.profile("fast").unwrap();
+        assert_eq!(fast.model_name, "gpt-4o-mini");
+        assert_eq!(fast.api_key, "test-key");
+        assert_eq!(fast.temperature, 0.5);
+        let local = config.profile("local").unwrap();
+        assert_eq!(local.base_url, "http://localhost:11434/v1");
+        assert!(!local.supports_tools);
+
+        let err = config.profile("smart").unwrap_err().to_string();
+        assert!(err.contains("'smart'") && err.contains("fast, local"));
+    }
+
+    /// Tests that the top-level `tools` list loads and saves ahead of the sections.
+    #[test]
+    fn test_config_tools() {
+        let content = r#"
+tools = ["filesystem", "calculator"]
+
+[llm]
+model_name = "gpt-4o"
+base_url = "https://api.openai.com/v1"
+api_key = "test-key"
+"#;
+        let config: Config = toml::from_str(content).unwrap();
+        assert_eq!(config.tools, ["filesystem", "calculator"]);
+        let saved = toml::to_string_pretty(&config).unwrap();
+        assert!(saved.starts_with("tools = "));
+        assert_eq!(
+            toml::from_str::<Config>(&saved).unwrap().tools,
+            config.tools
+        );
+    }
+
+    /// Tests exponential backoff, its cap and jitter.
+    #[test]
+    fn test_retry_backoff() {
+        let retry = RetryConfig {
+            max_retries: 10,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1_000,
+            multiplier: 2.0,
+            jitter: true,
+        };
+        assert_eq!(retry.backoff(0), Duration::from_millis(100));
+        assert_eq!(retry.backoff(2), Duration::from_millis(400));
+        assert_eq!(retry.backoff(8), Duration::from_millis(1_000));
+        assert_eq!(
+            retry.backoff_with_jitter(1, 0.5),
+            Duration::from_millis(100)
+        );
+        assert_eq!(retry.cap(Duration::from_secs(60)), Duration::from_secs(1));
+    }
 }
@@ -0,0 +1,498 @@
+//! # Dataset Module
+//!
+//! This module turns recorded agent sessions into OpenAI-format fine-tuning data.
+//! Sessions can be filtered by rating and tags, and personally identifiable
+//! information (emails, phone numbers, card numbers, API keys, ...) is redacted
+//! before anything is written to disk.
+//!
+//! The output is JSONL where every line is `{"messages": [...]}`, which is the
+//! format expected by the OpenAI fine-tuning API.
+
+use crate::chat::{ChatMessage, ChatSession, Role};
+use crate::error::{HeliosError, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Session metadata key holding a numeric rating for the transcript.
+pub const RATING_METADATA_KEY: &str = "rating";
+
+/// Session metadata key holding a comma-separated list of tags.
+pub const TAGS_METADATA_KEY: &str = "tags";
+
+/// A recorded conversation with optional quality annotations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedSession {
+    /// Identifier of the recording.
+    #[serde(default)]
+    pub id: String,
+    /// The conversation, including the system prompt if there was one.
+    pub messages: Vec<ChatMessage>,
+    /// Optional quality rating (higher is better).
+    #[serde(default)]
+    pub rating: Option<f32>,
+    /// Free-form tags used for filtering.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Additional metadata.
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl RecordedSession {
+    /// Creates a new recorded session from a list of messages.
+    pub fn new(id: impl Into<String>, messages: Vec<ChatMessage>) -> Self {
+        Self {
+            id: id.into(),
+            messages,
+            rating: None,
+            tags: Vec::new(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Builds a recorded session from a `ChatSession`.
+    ///
+    /// The rating and tags are read from the session metadata keys
+    /// [`RATING_METADATA_KEY`] and [`TAGS_METADATA_KEY`].
+    pub fn from_chat_session(id: impl Into<String>, session: &ChatSession) -> Self {
+        let rating = session
+            .get_metadata(RATING_METADATA_KEY)
+            .and_then(|r| r.trim().parse::<f32>().ok());
+        let tags = session
+            .get_metadata(TAGS_METADATA_KEY)
+            .map(|t| {
+                t.split(',')
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            id: id.into(),
+            messages: session.get_messages(),
+            rating,
+            tags,
+            metadata: session.metadata.clone(),
+        }
+    }
+
+    /// Sets the rating and returns self for chaining.
+    pub fn with_rating(mut self, rating: f32) -> Self {
+        self.rating = Some(rating);
+        self
+    }
+
+    /// Adds a tag and returns self for chaining.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+}
+
+/// A single fine-tuning example in OpenAI chat format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FineTuningExample {
+    /// The messages of the example.
+    pub messages: Vec<ChatMessage>,
+}
+
+/// Redacts common kinds of personally identifiable information from text.
+pub struct PiiRedactor {
+    patterns: Vec<(Regex, String)>,
+}
+
+impl PiiRedactor {
+    /// Creates a redactor with the built-in patterns.
+    pub fn new() -> Self {
+        // Order matters: more specific patterns run before the generic number patterns
+        let builtin = [
+            (r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}", "[EMAIL]"),
+            (r"\b(?:sk|pk|rk)-[A-Za-z0-9_-]{16,}\b", "[API_KEY]"),
+            (r"\bgh[pousr]_[A-Za-z0-9]{20,}\b", "[API_KEY]"),
+            (r"\bAKIA[0-9A-Z]{16}\b", "[API_KEY]"),
+            (r"\b\d{3}-\d{2}-\d{4}\b", "[SSN]"),
+            (r"\b(?:\d[ -]?){13,16}\b", "[CARD_NUMBER]"),
+            (
+                r"\b(?:25[0-5]|2[0-4]\d|1?\d?\d)(?:\.(?:25[0-5]|2[0-4]\d|1?\d?\d)){3}\b",
+                "[IP_ADDRESS]",
+            ),
+            (
+                r"(?:\+\d{1,3}[ .-]?)?\(?\b\d{3}\)?[ .-]?\d{3}[ .-]?\d{4}\b",
+                "[PHONE]",
+            ),
+        ];
+
+        let patterns = builtin
+            .iter()
+            .map(|(pattern, replacement)| {
+                (
+                    Regex::new(pattern).expect("built-in PII pattern is valid"),
+                    replacement.to_string(),
+                )
+            })
+            .collect();
+
+        Self { patterns }
+    }
+
+    /// Adds a custom pattern and its replacement.
+    pub fn with_pattern(mut self, pattern: &str, replacement: impl Into<String>) -> Result<Self> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| HeliosError::ConfigError(format!("Invalid PII pattern: {}", e)))?;
+        self.patterns.push((regex, replacement.into()));
+        Ok(self)
+    }
+
+    /// Returns the text with all matches replaced.
+    pub fn redact(&self, text: &str) -> String {
+        let mut result = text.to_string();
+        for (regex, replacement) in &self.patterns {
+            result = regex
+                .replace_all(&result, replacement.as_str())
+                .into_owned();
+        }
+        result
+    }
+}
+
+impl Default for PiiRedactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds fine-tuning datasets from recorded sessions.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use helios_engine::dataset::{DatasetBuilder, RecordedSession};
+/// # use helios_engine::ChatMessage;
+/// # fn example() -> helios_engine::Result<()> {
+/// let session = RecordedSession::new(
+///     "run-1",
+///     vec![ChatMessage::user("Hi"), ChatMessage::assistant("Hello!")],
+/// )
+/// .with_rating(5.0);
+///
+/// let written = DatasetBuilder::new()
+///     .min_rating(4.0)
+///     .session(session)
+///     .write_jsonl("train.jsonl")?;
+/// println!("Wrote {} examples", written);
+/// # Ok(())
+/// # }
+/// ```
+pub struct DatasetBuilder {
+    sessions: Vec<RecordedSession>,
+    min_rating: Option<f32>,
+    required_tags: Vec<String>,
+    excluded_tags: Vec<String>,
+    redactor: Option<PiiRedactor>,
+    include_system: bool,
+    include_reasoning: bool,
+}
+
+impl DatasetBuilder {
+    /// Creates a new builder. PII redaction is enabled and model reasoning is left
+    /// out by default.
+    pub fn new() -> Self {
+        Self {
+            sessions: Vec::new(),
+            min_rating: None,
+            required_tags: Vec::new(),
+            excluded_tags: Vec::new(),
+            redactor: Some(PiiRedactor::new()),
+            include_system: true,
+            include_reasoning: false,
+        }
+    }
+
+    /// Adds a recorded session.
+    pub fn session(mut self, session: RecordedSession) -> Self {
+        self.sessions.push(session);
+        self
+    }
+
+    /// Adds multiple recorded sessions.
+    pub fn sessions(mut self, sessions: Vec<RecordedSession>) -> Self {
+        self.sessions.extend(sessions);
+        self
+    }
+
+    /// Loads recorded sessions from a `.json` file, a `.jsonl` file, or a directory of them.
+    pub fn load(mut self, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            let mut entries: Vec<_> = fs::read_dir(path)?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    matches!(
+                        p.extension().and_then(|e| e.to_str()),
+                        Some("json") | Some("jsonl")
+                    )
+                })
+                .collect();
+            entries.sort();
+            for entry in entries {
+                self.sessions.extend(load_sessions_from_file(&entry)?);
+            }
+        } else {
+            self.sessions.extend(load_sessions_from_file(path)?);
+        }
+        Ok(self)
+    }
+
+    /// Only keeps sessions rated at or above the given value. Unrated sessions are dropped.
+    pub fn min_rating(mut self, rating: f32) -> Self {
+        self.min_rating = Some(rating);
+        self
+    }
+
+    /// Only keeps sessions that have the given tag.
+    pub fn require_tag(mut self, tag: impl Into<String>) -> Self {
+        self.required_tags.push(tag.into());
+        self
+    }
+
+    /// Drops sessions that have the given tag.
+    pub fn exclude_tag(mut self, tag: impl Into<String>) -> Self {
+        self.excluded_tags.push(tag.into());
+        self
+    }
+
+    /// Replaces the PII redactor (e.g. one with custom patterns).
+    pub fn redactor(mut self, redactor: PiiRedactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Disables PII redaction.
+    pub fn without_redaction(mut self) -> Self {
+        self.redactor = None;
+        self
+    }
+
+    /// Sets whether system messages are kept in the examples.
+    pub fn include_system(mut self, include: bool) -> Self {
+        self.include_system = include;
+        self
+    }
+
+    /// Sets whether the model's reasoning is kept on assistant messages.
+    ///
+    /// Reasoning is redacted like the message content when it is kept.
+    pub fn include_reasoning(mut self, include: bool) -> Self {
+        self.include_reasoning = include;
+        self
+    }
+
+    /// Returns true if the session passes the rating and tag filters.
+    fn accepts(&self, session: &RecordedSession) -> bool {
+        if let Some(min) = self.min_rating {
+            match session.rating {
+                Some(rating) if rating >= min => {}
+                _ => return false,
+            }
+        }
+
+        let has_tag = |tag: &String| session.tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+
+        self.required_tags.iter().all(has_tag) && !self.excluded_tags.iter().any(has_tag)
+    }
+
+    /// Converts a session into a fine-tuning example, or `None` if it has no assistant reply.
+    fn to_example(&self, session: &RecordedSession) -> Option<FineTuningExample> {
+        let messages: Vec<ChatMessage> = session
+            .messages
+            .iter()
+            .filter(|m| self.include_system || m.role != Role::System)
+            .map(|m| {
+                let mut message = m.clone();
+                if !self.include_reasoning {
+                    message.reasoning = None;
+                }
+                if let Some(redactor) = &self.redactor {
+                    message.content = redactor.redact(&message.content);
+                    message.reasoning = message
+                        .reasoning
+                        .map(|reasoning| redactor.redact(&reasoning));
+                    if let Some(tool_calls) = message.tool_calls.as_mut() {
+                        for call in tool_calls {
+                            call.function.arguments = redactor.redact(&call.function.arguments);
+                        }
+                    }
+                }
+                message
+            })
+            .collect();
+
+        if !messages.iter().any(|m| m.role == Role::Assistant) {
+            return None;
+        }
+
+        Some(FineTuningExample { messages })
+    }
+
+    /// Builds the filtered, redacted examples.
+    pub fn build(&self) -> Vec<FineTuningExample> {
+        self.sessions
+            .iter()
+            .filter(|s| self.accepts(s))
+            .filter_map(|s| self.to_example(s))
+            .collect()
+    }
+
+    /// Renders the dataset as JSONL.
+    pub fn to_jsonl(&self) -> Result<String> {
+        render_jsonl(&self.build())
+    }
+
+    /// Writes the dataset to a JSONL file and returns the number of examples written.
+    pub fn write_jsonl(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let examples = self.build();
+        fs::write(path, render_jsonl(&examples)?)?;
+        Ok(examples.len())
+    }
+}
+
+impl Default for DatasetBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Serializes examples as one JSON object per line.
+fn render_jsonl(examples: &[FineTuningExample]) -> Result<String> {
+    let mut output = String::new();
+    for example in examples {
+        output.push_str(&serde_json::to_string(example)?);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Loads recorded sessions from a single `.json` (one session or an array) or `.jsonl` file.
+fn load_sessions_from_file(path: &Path) -> Result<Vec<RecordedSession>> {
+    let content = fs::read_to_string(path)?;
+
+    if path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
+        return content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(HeliosError::from))
+            .collect();
+    }
+
+    let value: serde_json::Value = serde_json::from_str(&content)?;
+    if value.is_array() {
+        Ok(serde_json::from_value(value)?)
+    } else {
+        Ok(vec![serde_json::from_value(value)?])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that common PII patterns are redacted.
+    #[test]
+    fn test_pii_redaction() {
+        let redactor = PiiRedactor::new();
+        let text = "Mail john.doe@example.com or call 555-123-4567, key sk-abcdefghijklmnopqrstuv";
+        let redacted = redactor.redact(text);
+        assert!(redacted.contains("[EMAIL]"));
+        assert!(redacted.contains("[PHONE]"));
+        assert!(redacted.contains("[API_KEY]"));
+        assert!(!redacted.contains("john.doe"));
+    }
+
+    /// Tests filtering by rating and tags.
+    #[test]
+    fn test_dataset_filtering() {
+        let good = RecordedSession::new(
+            "good",
+            vec![ChatMessage::user("Hi"), ChatMessage::assistant("Hello")],
+        )
+        .with_rating(5.0)
+        .with_tag("support");
+        let bad = RecordedSession::new(
+            "bad",
+            vec![ChatMessage::user("Hi"), ChatMessage::assistant("Go away")],
+        )
+        .with_rating(1.0)
+        .with_tag("support");
+        let unrated = RecordedSession::new(
+            "unrated",
+            vec![ChatMessage::user("Hi"), ChatMessage::assistant("Hey")],
+        );
+
+        let examples = DatasetBuilder::new()
+            .sessions(vec![good, bad, unrated])
+            .min_rating(4.0)
+            .require_tag("support")
+            .build();
+
+        assert_eq!(examples.len(), 1);
+        assert_eq!(examples[0].messages[1].content, "Hello");
+    }
+
+    /// Tests JSONL output and that sessions without an assistant turn are skipped.
+    #[test]
+    fn test_dataset_jsonl_output() {
+        let mut session = ChatSession::new().with_system_prompt("Be brief");
+        session.add_user_message("My email is a@b.co");
+        session.add_assistant_message("Noted");
+        session.set_metadata(TAGS_METADATA_KEY, "demo, email");
+
+        let recorded = RecordedSession::from_chat_session("s1", &session);
+        assert_eq!(recorded.tags, vec!["demo".to_string(), "email".to_string()]);
+
+        let incomplete = RecordedSession::new("s2", vec![ChatMessage::user("Hello?")]);
+
+        let jsonl = DatasetBuilder::new()
+            .session(recorded)
+            .session(incomplete)
+            .include_system(false)
+            .to_jsonl()
+            .unwrap();
+
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let value: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        let messages = value["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["content"], "My email is [EMAIL]");
+    }
+
+    /// Tests that reasoning is left out unless asked for, and redacted when kept.
+    #[test]
+    fn test_dataset_reasoning() {
+        let mut answer = ChatMessage::assistant("Sent");
+        answer.reasoning = Some("The user is john.doe@example.com".to_string());
+        let session = RecordedSession::new("r1", vec![ChatMessage::user("Mail me"), answer]);
+
+        let examples = DatasetBuilder::new().session(session.clone()).build();
+        assert!(examples[0].messages[1].reasoning.is_none());
+        assert!(!DatasetBuilder::new()
+            .session(session.clone())
+            .to_jsonl()
+            .unwrap()
+            .contains("john.doe"));
+
+        let examples = DatasetBuilder::new()
+            .session(session)
+            .include_reasoning(true)
+            .build();
+        assert_eq!(
+            examples[0].messages[1].reasoning.as_deref(),
+            Some("The user is [EMAIL]")
+        );
+    }
+}
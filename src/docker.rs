@@ -0,0 +1,597 @@
+//! # Docker Module
+//!
+//! `DockerTool` manages the local Docker engine for a devops assistant: it lists
+//! containers and images, inspects them, reads container logs, and starts, stops,
+//! restarts and removes containers.
+//!
+//! Reading is `ReadOnly` and starting a container is `Standard`. Stopping, restarting
+//! and removing interrupt whatever the container was doing, so those calls are
+//! `Destructive` and go through the agent's approval handler. Environment variable
+//! values are hidden when inspecting, since they often hold credentials.
+//!
+//! Requires the `docker` feature.
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{required_str, str_at, table, Tool, ToolParameter, ToolPermission, ToolResult};
+use crate::truncation::{truncate_chars, truncate_chars_tail};
+use async_trait::async_trait;
+use bollard::query_parameters::{
+    InspectContainerOptions, ListContainersOptions, ListImagesOptions, LogsOptions,
+    RemoveContainerOptions, RestartContainerOptions, StartContainerOptions, StopContainerOptions,
+};
+use bollard::Docker;
+use chrono::Utc;
+use futures::StreamExt;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A tool for managing containers and images on a Docker engine.
+///
+/// ```rust,no_run
+/// use helios_engine::DockerTool;
+///
+/// // Connects to DOCKER_HOST, or the local socket or named pipe.
+/// let tool = DockerTool::new()?;
+/// # Ok::<(), helios_engine::HeliosError>(())
+/// ```
+pub struct DockerTool {
+    docker: Docker,
+    max_log_lines: usize,
+    max_output_chars: usize,
+    reveal_env: bool,
+}
+
+impl DockerTool {
+    /// Connects to the engine named by `DOCKER_HOST`, or the platform's default socket.
+    pub fn new() -> Result<Self> {
+        let docker = Docker::connect_with_defaults()
+            .map_err(|e| HeliosError::ConfigError(format!("Could not connect to Docker: {}", e)))?;
+        Ok(Self::from_client(docker))
+    }
+
+    /// Uses an existing client.
+    pub fn from_client(docker: Docker) -> Self {
+        Self {
+            docker,
+            max_log_lines: 200,
+            max_output_chars: 20_000,
+            reveal_env: false,
+        }
+    }
+
+    /// Sets how many log lines `logs` returns by default (200).
+    pub fn max_log_lines(mut self, lines: usize) -> Self {
+        self.max_log_lines = lines;
+        self
+    }
+
+    /// Limits the characters of output returned to the agent (20,000 by default).
+    pub fn max_output_chars(mut self, max: usize) -> Self {
+        self.max_output_chars = max;
+        self
+    }
+
+    /// Shows environment variable values in `inspect` output instead of hiding them.
+    pub fn reveal_env(mut self, reveal: bool) -> Self {
+        self.reveal_env = reveal;
+        self
+    }
+
+    async fn list_containers(&self, args: &Value) -> Result<ToolResult> {
+        let all = args.get("all").and_then(Value::as_bool).unwrap_or(false);
+        let options = ListContainersOptions {
+            all,
+            ..Default::default()
+        };
+        let containers = match self.docker.list_containers(Some(options)).await {
+            Ok(containers) => containers,
+            Err(e) => return Ok(docker_error(e)),
+        };
+        if containers.is_empty() {
+            return Ok(ToolResult::success(if all {
+                "No containers"
+            } else {
+                "No running containers (pass all: true to include stopped ones)"
+            }));
+        }
+        let mut rows = Vec::new();
+        for container in &containers {
+            let container = serde_json::to_value(container)?;
+            let ports: Vec<String> = container
+                .get("Ports")
+                .and_then(Value::as_array)
+                .map(|ports| {
+                    ports
+                        .iter()
+                        .filter_map(|port| {
+                            let private = port.get("PrivatePort")?.as_u64()?;
+                            let kind = str_at(port, "/Type");
+                            Some(match port.get("PublicPort").and_then(Value::as_u64) {
+                                Some(public) => format!("{}->{}/{}", public, private, kind),
+                                None => format!("{}/{}", private, kind),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let mut ports = ports;
+            ports.dedup();
+            rows.push(vec![
+                short_id(str_at(&container, "/Id")),
+                container
+                    .pointer("/Names/0")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .trim_start_matches('/')
+                    .to_string(),
+                str_at(&container, "/Image").to_string(),
+                str_at(&container, "/Status").to_string(),
+                ports.join(", "),
+            ]);
+        }
+        Ok(ToolResult::success(self.limit(
+            table(&["ID", "NAME", "IMAGE", "STATUS", "PORTS"], &rows),
+            false,
+        )))
+    }
+
+    async fn list_images(&self) -> Result<ToolResult> {
+        let images = match self
+            .docker
+            .list_images(Some(ListImagesOptions::default()))
+            .await
+        {
+            Ok(images) => images,
+            Err(e) => return Ok(docker_error(e)),
+        };
+        if images.is_empty() {
+            return Ok(ToolResult::success("No images"));
+        }
+        let now = Utc::now().timestamp();
+        let mut rows = Vec::new();
+        for image in &images {
+            let tags = if image.repo_tags.is_empty() {
+                "<none>".to_string()
+            } else {
+                image.repo_tags.join(", ")
+            };
+            rows.push(vec![
+                short_id(image.id.trim_start_matches("sha256:")),
+                tags,
+                format_size(image.size),
+                format!("{} ago", format_age(now - image.created)),
+            ]);
+        }
+        Ok(ToolResult::success(self.limit(
+            table(&["ID", "TAGS", "SIZE", "CREATED"], &rows),
+            false,
+        )))
+    }
+
+    async fn inspect(&self, args: &Value) -> Result<ToolResult> {
+        let mut details = if let Some(container) = args.get("container").and_then(Value::as_str) {
+            match self
+                .docker
+                .inspect_container(container, None::<InspectContainerOptions>)
+                .await
+            {
+                Ok(details) => serde_json::to_value(details)?,
+                Err(e) => return Ok(docker_error(e)),
+            }
+        } else if let Some(image) = args.get("image").and_then(Value::as_str) {
+            match self.docker.inspect_image(image).await {
+                Ok(details) => serde_json::to_value(details)?,
+                Err(e) => return Ok(docker_error(e)),
+            }
+        } else {
+            return Ok(ToolResult::error(
+                "inspect needs a 'container' or an 'image'",
+            ));
+        };
+        if !self.reveal_env {
+            if let Some(env) = details
+                .pointer_mut("/Config/Env")
+                .and_then(Value::as_array_mut)
+            {
+                for variable in env.iter_mut() {
+                    if let Some((name, _)) = variable.as_str().and_then(|v| v.split_once('=')) {
+                        *variable = Value::String(format!("{}=<hidden>", name));
+                    }
+                }
+            }
+        }
+        remove_nulls(&mut details);
+        Ok(ToolResult::success(
+            self.limit(serde_json::to_string_pretty(&details)?, false),
+        ))
+    }
+
+    async fn logs(&self, args: &Value) -> Result<ToolResult> {
+        let container = required_str(args, "container")?;
+        let tail = args
+            .get("tail_lines")
+            .and_then(Value::as_u64)
+            .map(|lines| lines as usize)
+            .unwrap_or(self.max_log_lines);
+        let options = LogsOptions {
+            stdout: true,
+            stderr: true,
+            tail: tail.to_string(),
+            timestamps: args
+                .get("timestamps")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            ..Default::default()
+        };
+        let mut stream = self.docker.logs(container, Some(options));
+        let mut output = String::new();
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(chunk) => output.push_str(&chunk.to_string()),
+                Err(e) => return Ok(docker_error(e)),
+            }
+        }
+        if output.is_empty() {
+            return Ok(ToolResult::success("(no log output)"));
+        }
+        Ok(ToolResult::success(self.limit(output, true)))
+    }
+
+    async fn control(&self, action: &str, args: &Value) -> Result<ToolResult> {
+        let container = required_str(args, "container")?;
+        let timeout = args
+            .get("timeout_secs")
+            .and_then(Value::as_i64)
+            .map(|secs| secs as i32);
+        let result = match action {
+            "start" => {
+                self.docker
+                    .start_container(container, None::<StartContainerOptions>)
+                    .await
+            }
+            "stop" => {
+                let options = StopContainerOptions {
+                    t: timeout,
+                    ..Default::default()
+                };
+                self.docker.stop_container(container, Some(options)).await
+            }
+            "restart" => {
+                let options = RestartContainerOptions {
+                    t: timeout,
+                    ..Default::default()
+                };
+                self.docker
+                    .restart_container(container, Some(options))
+                    .await
+            }
+            _ => {
+                let options = RemoveContainerOptions {
+                    force: args.get("force").and_then(Value::as_bool).unwrap_or(false),
+                    ..Default::default()
+                };
+                self.docker.remove_container(container, Some(options)).await
+            }
+        };
+        let done = match action {
+            "start" => "started",
+            "stop" => "stopped",
+            "restart" => "restarted",
+            _ => "removed",
+        };
+        match result {
+            Ok(()) => Ok(ToolResult::success(format!(
+                "Container {} {}",
+                container, done
+            ))),
+            Err(e) => Ok(docker_error(e)),
+        }
+    }
+
+    /// Truncates output, keeping the end when `tail` is set (as for logs).
+    fn limit(&self, text: String, tail: bool) -> String {
+        let max = self.max_output_chars;
+        if tail {
+            truncate_chars_tail(&text, max, "... (earlier output omitted)\n")
+        } else {
+            truncate_chars(
+                &text,
+                max,
+                &format!("\n... (truncated to {} characters)", max),
+            )
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for DockerTool {
+    fn name(&self) -> &str {
+        "docker"
+    }
+
+    fn description(&self) -> &str {
+        "Manage the local Docker engine. Actions: 'list_containers', 'list_images', \
+         'inspect' a container or image, 'logs' of a container, and 'start', 'stop', \
+         'restart' or 'remove' a container."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        let mut add = |name: &str, param_type: &str, description: &str, required: bool| {
+            params.insert(
+                name.to_string(),
+                ToolParameter {
+                    param_type: param_type.to_string(),
+                    description: description.to_string(),
+                    required: Some(required),
+                },
+            );
+        };
+        add(
+            "action",
+            "string",
+            "One of: list_containers, list_images, inspect, logs, start, stop, restart, remove",
+            true,
+        );
+        add(
+            "container",
+            "string",
+            "Container name or ID, for inspect, logs and the lifecycle actions",
+            false,
+        );
+        add("image", "string", "Image name or ID, for inspect", false);
+        add(
+            "all",
+            "boolean",
+            "For list_containers: include stopped containers",
+            false,
+        );
+        add(
+            "tail_lines",
+            "integer",
+            "For logs: how many lines from the end to return",
+            false,
+        );
+        add(
+            "timestamps",
+            "boolean",
+            "For logs: prefix each line with its timestamp",
+            false,
+        );
+        add(
+            "timeout_secs",
+            "integer",
+            "For stop and restart: seconds to wait before killing the container",
+            false,
+        );
+        add(
+            "force",
+            "boolean",
+            "For remove: remove the container even if it is running",
+            false,
+        );
+        params
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Destructive
+    }
+
+    fn call_permission(&self, args: &Value) -> ToolPermission {
+        match args.get("action").and_then(Value::as_str) {
+            Some("stop" | "restart" | "remove") => ToolPermission::Destructive,
+            Some("start") => ToolPermission::Standard,
+            _ => ToolPermission::ReadOnly,
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        match required_str(&args, "action")? {
+            "list_containers" => self.list_containers(&args).await,
+            "list_images" => self.list_images().await,
+            "inspect" => self.inspect(&args).await,
+            "logs" => self.logs(&args).await,
+            action @ ("start" | "stop" | "restart" | "remove") => self.control(action, &args).await,
+            other => Ok(ToolResult::error(format!(
+                "Unknown action '{}'; use list_containers, list_images, inspect, logs, \
+                 start, stop, restart or remove",
+                other
+            ))),
+        }
+    }
+}
+
+fn docker_error(error: bollard::errors::Error) -> ToolResult {
+    match error {
+        bollard::errors::Error::DockerResponseServerError { message, .. } => {
+            ToolResult::error(format!("Docker error: {}", message))
+        }
+        other => ToolResult::error(format!("Docker request failed: {}", other)),
+    }
+}
+
+fn short_id(id: &str) -> String {
+    id.chars().take(12).collect()
+}
+
+/// Drops null fields, which make up much of an inspect response.
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        Value::Array(items) => items.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+fn format_size(bytes: i64) -> String {
+    let bytes = bytes.max(0) as f64;
+    if bytes >= 1e9 {
+        format!("{:.2}GB", bytes / 1e9)
+    } else if bytes >= 1e6 {
+        format!("{:.1}MB", bytes / 1e6)
+    } else {
+        format!("{:.0}kB", bytes / 1e3)
+    }
+}
+
+fn format_age(seconds: i64) -> String {
+    match seconds.max(0) {
+        s if s < 120 => format!("{}s", s),
+        s if s < 7200 => format!("{}m", s / 60),
+        s if s < 172_800 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::{Json, Router};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    /// Frames a log line the way the engine multiplexes stdout and stderr.
+    fn log_frame(stream: u8, line: &str) -> Vec<u8> {
+        let mut frame = vec![stream, 0, 0, 0];
+        frame.extend_from_slice(&(line.len() as u32).to_be_bytes());
+        frame.extend_from_slice(line.as_bytes());
+        frame
+    }
+
+    /// Tests each action against a mock Docker engine.
+    #[tokio::test]
+    async fn test_docker_tool() {
+        let calls = Arc::new(Mutex::new(Vec::<String>::new()));
+        let recorded = calls.clone();
+        let app = Router::new().fallback(move |request: Request<Body>| {
+            let recorded = recorded.clone();
+            async move {
+                // Strip the API version prefix, as in /v1.49/containers/json, if any.
+                let path = request.uri().path().trim_start_matches('/');
+                let path = match path.split_once('/') {
+                    Some((version, rest)) if version.starts_with("v1.") => rest,
+                    _ => path,
+                }
+                .to_string();
+                let query = request.uri().query().unwrap_or("").to_string();
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} /{}?{}", request.method(), path, query));
+                let response: Response = match (request.method().clone(), path.as_str()) {
+                    (Method::GET, "containers/json") => Json(json!([{
+                        "Id": "4f3c2b1a0e9d8c7b6a5f4e3d", "Names": ["/web"], "Image": "nginx:1.27",
+                        "Status": "Up 3 hours",
+                        "Ports": [{"PrivatePort": 80, "PublicPort": 8080, "Type": "tcp"}]
+                    }]))
+                    .into_response(),
+                    (Method::GET, "images/json") => Json(json!([{
+                        "Id": "sha256:0123456789abcdef0123", "ParentId": "", "RepoTags": ["nginx:1.27"],
+                        "RepoDigests": [], "Created": Utc::now().timestamp() - 3 * 86_400,
+                        "Size": 187_000_000, "SharedSize": -1, "Labels": {}, "Containers": 1
+                    }]))
+                    .into_response(),
+                    (Method::GET, "containers/web/json") => Json(json!({
+                        "Id": "4f3c2b1a0e9d", "Name": "/web", "Path": null,
+                        "Config": {"Image": "nginx:1.27", "Env": ["DB_PASSWORD=hunter2", "PORT=80"]}
+                    }))
+                    .into_response(),
+                    (Method::GET, "containers/web/logs") => {
+                        let mut body = log_frame(1, "listening on :80\n");
+                        body.extend(log_frame(2, "upstream timed out\n"));
+                        Response::builder()
+                            .header("content-type", "application/vnd.docker.multiplexed-stream")
+                            .body(Body::from(body))
+                            .unwrap()
+                    }
+                    (Method::POST, "containers/web/stop") => StatusCode::NO_CONTENT.into_response(),
+                    (Method::DELETE, "containers/web") => StatusCode::NO_CONTENT.into_response(),
+                    _ => (
+                        StatusCode::NOT_FOUND,
+                        Json(json!({"message": "No such container: ghost"})),
+                    )
+                        .into_response(),
+                };
+                response
+            }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let docker = Docker::connect_with_http(&url, 5, bollard::API_DEFAULT_VERSION).unwrap();
+        let tool = DockerTool::from_client(docker);
+
+        let result = tool
+            .execute(json!({"action": "list_containers"}))
+            .await
+            .unwrap();
+        assert!(result.success, "{}", result.output);
+        let lines: Vec<&str> = result.output.lines().collect();
+        assert_eq!(
+            lines[1],
+            "4f3c2b1a0e9d   web    nginx:1.27   Up 3 hours   8080->80/tcp"
+        );
+
+        let result = tool
+            .execute(json!({"action": "list_images"}))
+            .await
+            .unwrap();
+        assert!(result
+            .output
+            .contains("0123456789ab   nginx:1.27   187.0MB   3d ago"));
+
+        let result = tool
+            .execute(json!({"action": "inspect", "container": "web"}))
+            .await
+            .unwrap();
+        assert!(result.output.contains("DB_PASSWORD=<hidden>"));
+        assert!(!result.output.contains("hunter2"));
+        assert!(!result.output.contains("\"Path\""));
+
+        let result = tool
+            .execute(json!({"action": "logs", "container": "web", "tail_lines": 50}))
+            .await
+            .unwrap();
+        assert_eq!(result.output, "listening on :80\nupstream timed out\n");
+
+        let stop = json!({"action": "stop", "container": "web", "timeout_secs": 5});
+        assert_eq!(tool.call_permission(&stop), ToolPermission::Destructive);
+        assert_eq!(
+            tool.call_permission(&json!({"action": "logs"})),
+            ToolPermission::ReadOnly
+        );
+        let result = tool.execute(stop).await.unwrap();
+        assert_eq!(result.output, "Container web stopped");
+
+        let result = tool
+            .execute(json!({"action": "remove", "container": "web", "force": true}))
+            .await
+            .unwrap();
+        assert!(result.success);
+
+        let result = tool
+            .execute(json!({"action": "start", "container": "ghost"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("No such container: ghost"));
+
+        let calls = calls.lock().unwrap();
+        assert!(calls
+            .iter()
+            .any(|c| c.contains("/containers/web/logs?") && c.contains("tail=50")));
+        assert!(calls
+            .iter()
+            .any(|c| c.starts_with("POST /containers/web/stop?") && c.contains("t=5")));
+        assert!(calls
+            .iter()
+            .any(|c| c.starts_with("DELETE /containers/web?") && c.contains("force=true")));
+    }
+}
@@ -0,0 +1,1090 @@
+//! # Email Module
+//!
+//! `EmailTool` gives an agent a mailbox: it searches and reads messages over IMAP,
+//! produces digests of recent mail for triage, and drafts and sends replies over
+//! SMTP. Mailboxes are opened read-only, so reading never marks a message as seen.
+//!
+//! Sending is the only action that needs approval: the tool reports itself as
+//! `Destructive` for `send` calls only, so an agent with an approval handler
+//! asks before anything leaves the outbox.
+//!
+//! Credentials come from the `[email]` section of the config file, where
+//! `${VAR}` references keep passwords out of the file itself.
+//!
+//! Requires the `email` feature.
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{required_str, Tool, ToolParameter, ToolPermission, ToolResult};
+use crate::truncation::truncate_chars;
+use async_trait::async_trait;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use mail_parser::{Address, MessageParser, MimeHeaders};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// How long one IMAP session or SMTP delivery may take.
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The most messages a search or digest returns.
+const MAX_MESSAGES: usize = 50;
+
+/// How many characters of each body a digest shows.
+const PREVIEW_CHARS: usize = 300;
+
+/// The `[email]` section of the config file.
+///
+/// ```toml
+/// [email]
+/// from = "Ada Lovelace <ada@example.com>"
+///
+/// [email.imap]
+/// host = "imap.example.com"
+/// username = "ada@example.com"
+/// password = "${EMAIL_PASSWORD}"
+///
+/// [email.smtp]
+/// host = "smtp.example.com"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailConfig {
+    /// The sender of outgoing mail, as `Name <address>` or a bare address.
+    pub from: String,
+    /// The incoming mail server. Searching and reading are unavailable without it.
+    #[serde(default)]
+    pub imap: Option<ImapConfig>,
+    /// The outgoing mail server. Sending is unavailable without it.
+    #[serde(default)]
+    pub smtp: Option<SmtpConfig>,
+    /// How many characters of a message body `read` returns.
+    #[serde(default = "default_max_body_chars")]
+    pub max_body_chars: usize,
+}
+
+/// IMAP server settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImapConfig {
+    /// The server's host name.
+    pub host: String,
+    /// The server's port.
+    #[serde(default = "default_imap_port")]
+    pub port: u16,
+    /// The login name.
+    pub username: String,
+    /// The password, or an app password for providers that require one.
+    pub password: String,
+    /// The mailbox searched when a call does not name one.
+    #[serde(default = "default_mailbox")]
+    pub mailbox: String,
+    /// Whether to connect with TLS. Only turn this off for local test servers.
+    #[serde(default = "default_true")]
+    pub tls: bool,
+}
+
+/// SMTP server settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    /// The server's host name.
+    pub host: String,
+    /// The server's port. Defaults to 587 for STARTTLS, 465 for TLS and 25 without encryption.
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// The login name. Defaults to the IMAP login.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// The password. Defaults to the IMAP password.
+    #[serde(default)]
+    pub password: Option<String>,
+    /// How the connection is encrypted.
+    #[serde(default)]
+    pub security: SmtpSecurity,
+}
+
+/// How an SMTP connection is encrypted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Connect in plain text and upgrade with STARTTLS, which is required.
+    #[default]
+    StartTls,
+    /// Connect with TLS from the start.
+    Tls,
+    /// No encryption, for local test servers.
+    None,
+}
+
+/// Returns the default body length of 20,000 characters.
+fn default_max_body_chars() -> usize {
+    20_000
+}
+
+/// Returns the IMAPS port.
+fn default_imap_port() -> u16 {
+    993
+}
+
+/// Returns `INBOX`.
+fn default_mailbox() -> String {
+    "INBOX".to_string()
+}
+
+/// Returns `true`.
+fn default_true() -> bool {
+    true
+}
+
+impl EmailConfig {
+    /// Reads the `[email]` section of a config file, if it has one.
+    pub fn from_config_file<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+        #[derive(Deserialize)]
+        struct EmailSection {
+            #[serde(default)]
+            email: Option<EmailConfig>,
+        }
+
+        let section: EmailSection = crate::config::read_config_file(path.as_ref())?.try_into()?;
+        Ok(section.email)
+    }
+}
+
+/// A stream an IMAP session can run over.
+trait ImapStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> ImapStream for S {}
+
+/// One untagged server response, with the literals it carried.
+#[derive(Debug, Default)]
+struct ImapResponse {
+    text: String,
+    literals: Vec<Vec<u8>>,
+}
+
+/// A minimal IMAP4rev1 client: enough to log in, examine a mailbox, search and fetch.
+struct ImapClient<S> {
+    stream: BufReader<S>,
+    next_tag: u32,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> ImapClient<S> {
+    /// Reads the server greeting.
+    async fn new(stream: S) -> Result<Self> {
+        let mut client = Self {
+            stream: BufReader::new(stream),
+            next_tag: 1,
+        };
+        let greeting = client.read_line().await?;
+        let greeting = String::from_utf8_lossy(&greeting);
+        if !greeting.starts_with("* OK") && !greeting.starts_with("* PREAUTH") {
+            return Err(imap_error(format!(
+                "unexpected greeting '{}'",
+                greeting.trim_end()
+            )));
+        }
+        Ok(client)
+    }
+
+    async fn read_line(&mut self) -> Result<Vec<u8>> {
+        let mut line = Vec::new();
+        if self.stream.read_until(b'\n', &mut line).await? == 0 {
+            return Err(imap_error("the server closed the connection"));
+        }
+        Ok(line)
+    }
+
+    /// Sends a command and collects the untagged responses until it completes.
+    async fn command(&mut self, command: &str) -> Result<Vec<ImapResponse>> {
+        let tag = format!("A{}", self.next_tag);
+        self.next_tag += 1;
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(format!("{} {}\r\n", tag, command).as_bytes())
+            .await?;
+        stream.flush().await?;
+
+        let mut responses = Vec::new();
+        loop {
+            let mut line = self.read_line().await?;
+            let text = String::from_utf8_lossy(&line).trim_end().to_string();
+            if let Some(status) = text.strip_prefix(&format!("{} ", tag)) {
+                if status.starts_with("OK") {
+                    return Ok(responses);
+                }
+                let verb = command.split(' ').next().unwrap_or(command);
+                return Err(imap_error(format!("{} failed: {}", verb, status)));
+            }
+
+            let mut response = ImapResponse::default();
+            loop {
+                let text = String::from_utf8_lossy(&line).trim_end().to_string();
+                match literal_size(&text) {
+                    Some(size) => {
+                        let mut literal = vec![0; size];
+                        self.stream.read_exact(&mut literal).await?;
+                        response.text.push_str(&text);
+                        response.literals.push(literal);
+                        line = self.read_line().await?;
+                    }
+                    None => {
+                        response.text.push_str(&text);
+                        break;
+                    }
+                }
+            }
+            responses.push(response);
+        }
+    }
+
+    async fn login(&mut self, username: &str, password: &str) -> Result<()> {
+        self.command(&format!("LOGIN {} {}", quote(username)?, quote(password)?))
+            .await
+            .map(|_| ())
+    }
+
+    /// Opens a mailbox read-only, so fetching does not set the `\Seen` flag.
+    async fn examine(&mut self, mailbox: &str) -> Result<()> {
+        self.command(&format!("EXAMINE {}", quote(mailbox)?))
+            .await
+            .map(|_| ())
+    }
+
+    /// Returns the UIDs matching `criteria`, in ascending order.
+    async fn search(&mut self, criteria: &str) -> Result<Vec<u32>> {
+        let responses = self.command(&format!("UID SEARCH {}", criteria)).await?;
+        let mut uids: Vec<u32> = responses
+            .iter()
+            .filter_map(|r| r.text.strip_prefix("* SEARCH"))
+            .flat_map(|rest| rest.split_whitespace().filter_map(|n| n.parse().ok()))
+            .collect();
+        uids.sort_unstable();
+        Ok(uids)
+    }
+
+    /// Fetches `item` for each UID, returning the UID, whether it was seen and the data.
+    async fn fetch(&mut self, uids: &[u32], item: &str) -> Result<Vec<FetchedMessage>> {
+        if uids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let set = uids
+            .iter()
+            .map(|uid| uid.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let responses = self
+            .command(&format!("UID FETCH {} (UID FLAGS {})", set, item))
+            .await?;
+        Ok(responses
+            .into_iter()
+            .filter(|r| r.text.contains(" FETCH "))
+            .filter_map(|mut r| {
+                let uid = fetch_uid(&r.text)?;
+                let data = if r.literals.is_empty() {
+                    return None;
+                } else {
+                    r.literals.swap_remove(0)
+                };
+                Some(FetchedMessage {
+                    uid,
+                    seen: r.text.contains("\\Seen"),
+                    data,
+                })
+            })
+            .collect())
+    }
+
+    async fn logout(&mut self) -> Result<()> {
+        self.command("LOGOUT").await.map(|_| ())
+    }
+}
+
+/// A message returned by a `UID FETCH`.
+struct FetchedMessage {
+    uid: u32,
+    seen: bool,
+    data: Vec<u8>,
+}
+
+/// Returns the size of the literal a response line ends with, as in `{123}`.
+fn literal_size(line: &str) -> Option<usize> {
+    let open = line.rfind('{')?;
+    line[open + 1..].strip_suffix('}')?.parse().ok()
+}
+
+/// Returns the value of the `UID` item in a FETCH response.
+fn fetch_uid(text: &str) -> Option<u32> {
+    let start = text.find("UID ")? + 4;
+    text[start..]
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Quotes a string for an IMAP command.
+fn quote(value: &str) -> Result<String> {
+    if value.contains(['\r', '\n']) {
+        return Err(HeliosError::ToolError(
+            "Email arguments may not contain line breaks".to_string(),
+        ));
+    }
+    Ok(format!(
+        "\"{}\"",
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+fn imap_error(message: impl std::fmt::Display) -> HeliosError {
+    HeliosError::ToolError(format!("IMAP error: {}", message))
+}
+
+/// Builds IMAP search criteria from the tool's filter arguments.
+fn search_criteria(args: &Value) -> Result<String> {
+    let mut criteria = Vec::new();
+    for (arg, key) in [
+        ("from", "FROM"),
+        ("to", "TO"),
+        ("subject", "SUBJECT"),
+        ("text", "TEXT"),
+    ] {
+        if let Some(value) = args.get(arg).and_then(|v| v.as_str()) {
+            criteria.push(format!("{} {}", key, quote(value)?));
+        }
+    }
+    if let Some(since) = args.get("since").and_then(|v| v.as_str()) {
+        let date = chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d").map_err(|_| {
+            HeliosError::ToolError(format!("Invalid 'since' date '{}'; use YYYY-MM-DD", since))
+        })?;
+        criteria.push(format!("SINCE {}", date.format("%-d-%b-%Y")));
+    }
+    if args
+        .get("unread")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        criteria.push("UNSEEN".to_string());
+    }
+    if criteria.is_empty() {
+        criteria.push("ALL".to_string());
+    }
+    Ok(criteria.join(" "))
+}
+
+/// Formats an address header as `Name <address>, ...`.
+fn format_address(address: Option<&Address>) -> String {
+    let Some(address) = address else {
+        return String::new();
+    };
+    let addrs: Vec<_> = match address {
+        Address::List(list) => list.iter().collect(),
+        Address::Group(groups) => groups.iter().flat_map(|g| g.addresses.iter()).collect(),
+    };
+    addrs
+        .iter()
+        .map(|addr| match (addr.name(), addr.address()) {
+            (Some(name), Some(email)) => format!("{} <{}>", name, email),
+            (None, Some(email)) => email.to_string(),
+            (Some(name), None) => name.to_string(),
+            (None, None) => String::new(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// An email written with `draft` and waiting to be sent.
+#[derive(Debug, Clone)]
+struct Draft {
+    to: String,
+    cc: Option<String>,
+    subject: String,
+    body: String,
+    in_reply_to: Option<String>,
+    references: Option<String>,
+}
+
+impl Draft {
+    fn preview(&self) -> String {
+        let mut text = format!("To: {}\n", self.to);
+        if let Some(cc) = &self.cc {
+            text.push_str(&format!("Cc: {}\n", cc));
+        }
+        text.push_str(&format!("Subject: {}\n\n{}", self.subject, self.body));
+        text
+    }
+}
+
+/// A tool for triaging and answering email over IMAP and SMTP.
+///
+/// Actions:
+/// - `search`: lists matching messages, newest first
+/// - `read`: returns one message in full
+/// - `summarize`: lists matching messages with a short preview of each body
+/// - `draft`: composes a message, or a reply to one, without sending it
+/// - `send`: sends a draft or a new message; needs approval
+///
+/// ```rust,no_run
+/// use helios_engine::{Agent, Config, EmailConfig, EmailTool};
+///
+/// # async fn example() -> helios_engine::Result<()> {
+/// let email = EmailConfig::from_config_file("config.toml")?.expect("no [email] section");
+/// let agent = Agent::builder("Assistant")
+///     .config(Config::from_file("config.toml")?)
+///     .tool(Box::new(EmailTool::new(email)))
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct EmailTool {
+    config: EmailConfig,
+    drafts: Mutex<HashMap<String, Draft>>,
+    next_draft: Mutex<u32>,
+}
+
+impl EmailTool {
+    /// Creates a tool for the account described by `config`.
+    pub fn new(config: EmailConfig) -> Self {
+        Self {
+            config,
+            drafts: Mutex::new(HashMap::new()),
+            next_draft: Mutex::new(1),
+        }
+    }
+
+    /// Returns the account settings.
+    pub fn config(&self) -> &EmailConfig {
+        &self.config
+    }
+
+    fn imap_config(&self) -> Result<&ImapConfig> {
+        self.config.imap.as_ref().ok_or_else(|| {
+            HeliosError::ToolError("No IMAP server is configured in [email.imap]".to_string())
+        })
+    }
+
+    /// Connects, logs in and opens the mailbox named in `args` or the default one.
+    async fn open_mailbox(&self, args: &Value) -> Result<ImapClient<Box<dyn ImapStream>>> {
+        let imap = self.imap_config()?;
+        let mailbox = args
+            .get("mailbox")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&imap.mailbox);
+
+        let tcp = tokio::net::TcpStream::connect((imap.host.as_str(), imap.port))
+            .await
+            .map_err(|e| imap_error(format!("cannot connect to {}: {}", imap.host, e)))?;
+        let stream: Box<dyn ImapStream> = if imap.tls {
+            let connector = tokio_native_tls::native_tls::TlsConnector::new()
+                .map_err(|e| imap_error(e.to_string()))?;
+            let tls = tokio_native_tls::TlsConnector::from(connector)
+                .connect(&imap.host, tcp)
+                .await
+                .map_err(|e| imap_error(format!("TLS handshake failed: {}", e)))?;
+            Box::new(tls)
+        } else {
+            Box::new(tcp)
+        };
+
+        let mut client = ImapClient::new(stream).await?;
+        client.login(&imap.username, &imap.password).await?;
+        client.examine(mailbox).await?;
+        Ok(client)
+    }
+
+    /// Returns the newest UIDs matching the filters in `args`, newest first.
+    fn newest(uids: Vec<u32>, args: &Value) -> Vec<u32> {
+        let limit = args
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(10)
+            .clamp(1, MAX_MESSAGES);
+        uids.into_iter().rev().take(limit).collect()
+    }
+
+    async fn search(&self, args: &Value, with_preview: bool) -> Result<String> {
+        let criteria = search_criteria(args)?;
+        let item = if with_preview {
+            "BODY.PEEK[]"
+        } else {
+            "BODY.PEEK[HEADER]"
+        };
+        let (total, mut messages) = with_timeout(async {
+            let mut client = self.open_mailbox(args).await?;
+            let uids = client.search(&criteria).await?;
+            let total = uids.len();
+            let messages = client.fetch(&Self::newest(uids, args), item).await?;
+            let _ = client.logout().await;
+            Ok((total, messages))
+        })
+        .await?;
+        if messages.is_empty() {
+            return Ok("No messages found".to_string());
+        }
+        messages.sort_by_key(|m| std::cmp::Reverse(m.uid));
+
+        let parser = MessageParser::default();
+        let mut lines = vec![format!(
+            "{} of {} matching messages, newest first:",
+            messages.len(),
+            total
+        )];
+        for fetched in &messages {
+            let Some(message) = parser.parse(&fetched.data) else {
+                continue;
+            };
+            lines.push(format!(
+                "[uid {}]{} {} | From: {} | Subject: {}",
+                fetched.uid,
+                if fetched.seen { "" } else { " (unread)" },
+                message.date().map(|d| d.to_rfc3339()).unwrap_or_default(),
+                format_address(message.from()),
+                message.subject().unwrap_or("(no subject)")
+            ));
+            if with_preview {
+                let preview = message.body_preview(PREVIEW_CHARS).unwrap_or_default();
+                lines.push(format!(
+                    "    {}",
+                    preview.split_whitespace().collect::<Vec<_>>().join(" ")
+                ));
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// Fetches the message with `uid` from the mailbox in `args`.
+    async fn fetch_one(&self, args: &Value, uid: u32) -> Result<Vec<u8>> {
+        let mut messages = with_timeout(async {
+            let mut client = self.open_mailbox(args).await?;
+            let messages = client.fetch(&[uid], "BODY.PEEK[]").await?;
+            let _ = client.logout().await;
+            Ok(messages)
+        })
+        .await?;
+        messages
+            .pop()
+            .map(|m| m.data)
+            .ok_or_else(|| HeliosError::ToolError(format!("No message with uid {}", uid)))
+    }
+
+    async fn read(&self, args: &Value) -> Result<String> {
+        let uid = required_uid(args, "uid")?;
+        let data = self.fetch_one(args, uid).await?;
+        let message = MessageParser::default()
+            .parse(&data)
+            .ok_or_else(|| HeliosError::ToolError(format!("Cannot parse message {}", uid)))?;
+
+        let mut text = format!(
+            "From: {}\nTo: {}\n",
+            format_address(message.from()),
+            format_address(message.to())
+        );
+        if message.cc().is_some() {
+            text.push_str(&format!("Cc: {}\n", format_address(message.cc())));
+        }
+        text.push_str(&format!(
+            "Date: {}\nSubject: {}\n",
+            message.date().map(|d| d.to_rfc3339()).unwrap_or_default(),
+            message.subject().unwrap_or("(no subject)")
+        ));
+        let attachments: Vec<_> = message
+            .attachments()
+            .filter_map(|a| a.attachment_name())
+            .collect();
+        if !attachments.is_empty() {
+            text.push_str(&format!("Attachments: {}\n", attachments.join(", ")));
+        }
+        let body = message.body_text(0).unwrap_or_default();
+        text.push('\n');
+        text.push_str(&truncate_chars(
+            body.trim(),
+            self.config.max_body_chars,
+            "…",
+        ));
+        Ok(text)
+    }
+
+    async fn draft(&self, args: &Value) -> Result<String> {
+        let body = required_str(args, "body")?.to_string();
+        let mut draft = match args.get("reply_to_uid").and_then(|v| v.as_u64()) {
+            Some(uid) => {
+                let data = self.fetch_one(args, uid as u32).await?;
+                let original = MessageParser::default().parse(&data).ok_or_else(|| {
+                    HeliosError::ToolError(format!("Cannot parse message {}", uid))
+                })?;
+                let subject = original.subject().unwrap_or_default();
+                let message_id = original.message_id().map(|id| format!("<{}>", id));
+                let references = original
+                    .references()
+                    .as_text_list()
+                    .map(|ids| ids.iter().map(|id| format!("<{}>", id)).collect())
+                    .or_else(|| {
+                        original
+                            .references()
+                            .as_text()
+                            .map(|id| vec![format!("<{}>", id)])
+                    })
+                    .unwrap_or_default()
+                    .into_iter()
+                    .chain(message_id.clone())
+                    .collect::<Vec<_>>();
+                Draft {
+                    to: format_address(original.reply_to().or(original.from())),
+                    cc: None,
+                    subject: if subject.to_ascii_lowercase().starts_with("re:") {
+                        subject.to_string()
+                    } else {
+                        format!("Re: {}", subject)
+                    },
+                    body,
+                    in_reply_to: message_id,
+                    references: (!references.is_empty()).then(|| references.join(" ")),
+                }
+            }
+            None => Draft {
+                to: required_str(args, "to")?.to_string(),
+                cc: None,
+                subject: required_str(args, "subject")?.to_string(),
+                body,
+                in_reply_to: None,
+                references: None,
+            },
+        };
+        if let Some(to) = args.get("to").and_then(|v| v.as_str()) {
+            draft.to = to.to_string();
+        }
+        if let Some(subject) = args.get("subject").and_then(|v| v.as_str()) {
+            draft.subject = subject.to_string();
+        }
+        draft.cc = args.get("cc").and_then(|v| v.as_str()).map(String::from);
+        // Fail now rather than at send time if an address is malformed.
+        self.build_message(&draft)?;
+
+        let id = {
+            let mut next = self.next_draft.lock().unwrap();
+            let id = format!("draft-{}", *next);
+            *next += 1;
+            id
+        };
+        let preview = draft.preview();
+        self.drafts.lock().unwrap().insert(id.clone(), draft);
+        Ok(format!(
+            "Saved {}. Send it with action 'send' and draft_id '{}'.\n\n{}",
+            id, id, preview
+        ))
+    }
+
+    fn build_message(&self, draft: &Draft) -> Result<Message> {
+        let parse = |value: &str| {
+            value.parse::<Mailbox>().map_err(|e| {
+                HeliosError::ToolError(format!("Invalid email address '{}': {}", value, e))
+            })
+        };
+        let mut builder = Message::builder()
+            .from(parse(&self.config.from)?)
+            .subject(draft.subject.clone());
+        for to in draft.to.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            builder = builder.to(parse(to)?);
+        }
+        if let Some(cc) = &draft.cc {
+            for cc in cc.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                builder = builder.cc(parse(cc)?);
+            }
+        }
+        if let Some(id) = &draft.in_reply_to {
+            builder = builder.in_reply_to(id.clone());
+        }
+        if let Some(references) = &draft.references {
+            builder = builder.references(references.clone());
+        }
+        builder
+            .header(ContentType::TEXT_PLAIN)
+            .body(draft.body.clone())
+            .map_err(|e| HeliosError::ToolError(format!("Cannot build the email: {}", e)))
+    }
+
+    async fn send(&self, args: &Value) -> Result<String> {
+        let smtp = self.config.smtp.as_ref().ok_or_else(|| {
+            HeliosError::ToolError("No SMTP server is configured in [email.smtp]".to_string())
+        })?;
+        let draft_id = args.get("draft_id").and_then(|v| v.as_str());
+        let draft = match draft_id {
+            Some(id) => self
+                .drafts
+                .lock()
+                .unwrap()
+                .get(id)
+                .cloned()
+                .ok_or_else(|| HeliosError::ToolError(format!("No draft named '{}'", id)))?,
+            None => Draft {
+                to: required_str(args, "to")?.to_string(),
+                cc: args.get("cc").and_then(|v| v.as_str()).map(String::from),
+                subject: required_str(args, "subject")?.to_string(),
+                body: required_str(args, "body")?.to_string(),
+                in_reply_to: None,
+                references: None,
+            },
+        };
+        let message = self.build_message(&draft)?;
+
+        let smtp_error = |e: lettre::transport::smtp::Error| {
+            HeliosError::ToolError(format!("SMTP error: {}", e))
+        };
+        let mut transport = match smtp.security {
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)
+                    .map_err(smtp_error)?
+            }
+            SmtpSecurity::Tls => {
+                AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host).map_err(smtp_error)?
+            }
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host).port(25)
+            }
+        }
+        .timeout(Some(NETWORK_TIMEOUT));
+        if let Some(port) = smtp.port {
+            transport = transport.port(port);
+        }
+        let imap = self.config.imap.as_ref();
+        let username = smtp
+            .username
+            .clone()
+            .or_else(|| imap.map(|i| i.username.clone()));
+        let password = smtp
+            .password
+            .clone()
+            .or_else(|| imap.map(|i| i.password.clone()));
+        if let (Some(username), Some(password)) = (username, password) {
+            transport = transport.credentials(Credentials::new(username, password));
+        }
+
+        transport.build().send(message).await.map_err(smtp_error)?;
+        if let Some(id) = draft_id {
+            self.drafts.lock().unwrap().remove(id);
+        }
+        Ok(format!("Sent '{}' to {}", draft.subject, draft.to))
+    }
+}
+
+/// Gives up on an IMAP session that takes longer than the network timeout.
+async fn with_timeout<T>(session: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(NETWORK_TIMEOUT, session)
+        .await
+        .map_err(|_| imap_error("the server did not respond in time"))?
+}
+
+fn required_uid(args: &Value, name: &str) -> Result<u32> {
+    args.get(name)
+        .and_then(|v| v.as_u64())
+        .and_then(|uid| u32::try_from(uid).ok())
+        .ok_or_else(|| HeliosError::ToolError(format!("Missing '{}' parameter", name)))
+}
+
+#[async_trait]
+impl Tool for EmailTool {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    fn description(&self) -> &str {
+        "Work with the user's email. Actions: 'search' lists messages matching filters (newest first, with uids), \
+         'read' returns one message by uid, 'summarize' lists messages with a short preview of each body, \
+         'draft' composes a message or a reply (reply_to_uid) without sending it, \
+         and 'send' sends a draft (draft_id) or a new message. Always draft and show the user before sending."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        let mut add = |name: &str, param_type: &str, description: &str, required: bool| {
+            params.insert(
+                name.to_string(),
+                ToolParameter {
+                    param_type: param_type.to_string(),
+                    description: description.to_string(),
+                    required: Some(required),
+                },
+            );
+        };
+        add(
+            "action",
+            "string",
+            "One of: search, read, summarize, draft, send",
+            true,
+        );
+        add(
+            "mailbox",
+            "string",
+            "Mailbox to search or read (default: the configured one, usually INBOX)",
+            false,
+        );
+        add("from", "string", "Filter: sender contains this text", false);
+        add(
+            "subject",
+            "string",
+            "Filter: subject contains this text; for draft/send, the subject",
+            false,
+        );
+        add(
+            "text",
+            "string",
+            "Filter: headers or body contain this text",
+            false,
+        );
+        add(
+            "since",
+            "string",
+            "Filter: received on or after this date (YYYY-MM-DD)",
+            false,
+        );
+        add("unread", "boolean", "Filter: only unread messages", false);
+        add(
+            "limit",
+            "number",
+            "Most messages to list (default: 10, max: 50)",
+            false,
+        );
+        add("uid", "number", "The message to read", false);
+        add(
+            "to",
+            "string",
+            "Recipients, comma separated; as a search filter, recipient contains this text",
+            false,
+        );
+        add("cc", "string", "Cc recipients, comma separated", false);
+        add(
+            "body",
+            "string",
+            "The plain-text body to draft or send",
+            false,
+        );
+        add(
+            "reply_to_uid",
+            "number",
+            "Draft a reply to this message; fills in the recipient and subject",
+            false,
+        );
+        add("draft_id", "string", "The draft to send", false);
+        params
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Standard
+    }
+
+    fn call_permission(&self, args: &Value) -> ToolPermission {
+        match args.get("action").and_then(|v| v.as_str()) {
+            Some("send") => ToolPermission::Destructive,
+            _ => ToolPermission::Standard,
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let action = required_str(&args, "action")?;
+        let output = match action {
+            "search" => self.search(&args, false).await,
+            "summarize" => self.search(&args, true).await,
+            "read" => self.read(&args).await,
+            "draft" => self.draft(&args).await,
+            "send" => self.send(&args).await,
+            _ => {
+                return Err(HeliosError::ToolError(format!(
+                    "Unknown action '{}'. Valid actions: search, read, summarize, draft, send",
+                    action
+                )))
+            }
+        };
+        match output {
+            Ok(output) => Ok(ToolResult::success(output)),
+            Err(HeliosError::ToolError(message)) => Ok(ToolResult::error(message)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{ToolApprovalRequest, ToolRegistry};
+    use serde_json::json;
+
+    const MESSAGE: &str = "From: Bob <bob@example.com>\r\nTo: ada@example.com\r\nSubject: Lunch\r\nMessage-ID: <m1@example.com>\r\nDate: Tue, 1 Oct 2024 12:00:00 +0000\r\n\r\nAre you free on Friday?\r\n";
+
+    /// Serves one fake IMAP session on `listener`, checking each command against
+    /// `script` and answering with the scripted untagged responses.
+    async fn serve(listener: tokio::net::TcpListener, script: Vec<(&'static str, String)>) {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        stream
+            .get_mut()
+            .write_all(b"* OK fake IMAP ready\r\n")
+            .await
+            .unwrap();
+        for (expected, reply) in script {
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            let (tag, command) = line.trim_end().split_once(' ').unwrap();
+            assert!(
+                command.starts_with(expected),
+                "expected '{}', got '{}'",
+                expected,
+                command
+            );
+            let reply = format!("{}{} OK done\r\n", reply, tag);
+            stream.get_mut().write_all(reply.as_bytes()).await.unwrap();
+        }
+    }
+
+    fn tool_for(port: u16) -> EmailTool {
+        EmailTool::new(EmailConfig {
+            from: "Ada <ada@example.com>".to_string(),
+            imap: Some(ImapConfig {
+                host: "127.0.0.1".to_string(),
+                port,
+                username: "ada".to_string(),
+                password: "p\"w".to_string(),
+                mailbox: "INBOX".to_string(),
+                tls: false,
+            }),
+            smtp: None,
+            max_body_chars: default_max_body_chars(),
+        })
+    }
+
+    /// Tests searching and drafting a reply against a fake IMAP server.
+    #[tokio::test]
+    async fn test_email_search_and_reply() {
+        let fetch = format!(
+            "* 1 FETCH (UID 7 FLAGS () BODY[] {{{}}}\r\n{})\r\n",
+            MESSAGE.len(),
+            MESSAGE
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(serve(
+            listener,
+            vec![
+                ("LOGIN \"ada\" \"p\\\"w\"", String::new()),
+                ("EXAMINE \"INBOX\"", String::new()),
+                (
+                    "UID SEARCH FROM \"bob\" UNSEEN",
+                    "* SEARCH 3 7\r\n".to_string(),
+                ),
+                ("UID FETCH 7 (UID FLAGS BODY.PEEK[HEADER])", fetch.clone()),
+                ("LOGOUT", "* BYE\r\n".to_string()),
+            ],
+        ));
+        let tool = tool_for(port);
+        let result = tool
+            .execute(json!({"action": "search", "from": "bob", "unread": true, "limit": 1}))
+            .await
+            .unwrap();
+        server.await.unwrap();
+        assert!(result.success, "{}", result.output);
+        assert!(result.output.contains("1 of 2 matching"));
+        assert!(result.output.contains(
+            "[uid 7] (unread) 2024-10-01T12:00:00Z | From: Bob <bob@example.com> | Subject: Lunch"
+        ));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(serve(
+            listener,
+            vec![
+                ("LOGIN", String::new()),
+                ("EXAMINE", String::new()),
+                ("UID FETCH 7 (UID FLAGS BODY.PEEK[])", fetch),
+                ("LOGOUT", String::new()),
+            ],
+        ));
+        let tool = tool_for(port);
+        let result = tool
+            .execute(json!({"action": "draft", "reply_to_uid": 7, "body": "Yes!"}))
+            .await
+            .unwrap();
+        server.await.unwrap();
+        assert!(result.success, "{}", result.output);
+        assert!(result.output.contains("Saved draft-1"));
+        assert!(result.output.contains("To: Bob <bob@example.com>"));
+        assert!(result.output.contains("Subject: Re: Lunch"));
+        let drafts = tool.drafts.lock().unwrap();
+        assert_eq!(
+            drafts["draft-1"].in_reply_to.as_deref(),
+            Some("<m1@example.com>")
+        );
+    }
+
+    /// Tests that only sending goes through the approval handler.
+    #[tokio::test]
+    async fn test_email_send_needs_approval() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(EmailTool::new(EmailConfig {
+            from: "ada@example.com".to_string(),
+            imap: None,
+            smtp: None,
+            max_body_chars: default_max_body_chars(),
+        })));
+        registry.set_approval_handler(|_: &ToolApprovalRequest| false);
+
+        let result = registry
+            .execute(
+                "email",
+                json!({"action": "draft", "to": "bob@example.com", "subject": "Hi", "body": "Hello"}),
+            )
+            .await
+            .unwrap();
+        assert!(result.success, "{}", result.output);
+
+        let result = registry
+            .execute("email", json!({"action": "send", "draft_id": "draft-1"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("not approved"));
+    }
+
+    /// Tests building IMAP search criteria and parsing server responses.
+    #[test]
+    fn test_imap_helpers() {
+        assert_eq!(search_criteria(&json!({})).unwrap(), "ALL");
+        assert_eq!(
+            search_criteria(&json!({"subject": "a \"b\"", "since": "2024-03-05"})).unwrap(),
+            "SUBJECT \"a \\\"b\\\"\" SINCE 5-Mar-2024"
+        );
+        assert!(search_criteria(&json!({"text": "x\r\nA2 DELETE INBOX"})).is_err());
+        assert!(search_criteria(&json!({"since": "March"})).is_err());
+
+        assert_eq!(literal_size("* 1 FETCH (UID 4 BODY[] {120}"), Some(120));
+        assert_eq!(literal_size("* SEARCH 1 2"), None);
+        assert_eq!(
+            fetch_uid("* 1 FETCH (FLAGS (\\Seen) UID 42 BODY[] {3}"),
+            Some(42)
+        );
+    }
+
+    /// Tests reading the `[email]` section of a config file.
+    #[test]
+    fn test_email_config_from_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[email]\nfrom = \"ada@example.com\"\n[email.imap]\nhost = \"imap.example.com\"\nusername = \"ada\"\npassword = \"secret\"\n[email.smtp]\nhost = \"smtp.example.com\"\nsecurity = \"tls\"\n",
+        )
+        .unwrap();
+
+        let email = EmailConfig::from_config_file(&path).unwrap().unwrap();
+        let imap = email.imap.unwrap();
+        assert_eq!(imap.port, 993);
+        assert_eq!(imap.mailbox, "INBOX");
+        assert!(imap.tls);
+        assert_eq!(email.smtp.unwrap().security, SmtpSecurity::Tls);
+        assert_eq!(email.max_body_chars, 20_000);
+    }
+}
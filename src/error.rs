@@ -40,6 +40,26 @@ pub enum HeliosError {
     #[error("TOML parsing error: {0}")]
     TomlError(#[from] toml::de::Error),
 
+    /// A call was rejected because the circuit breaker for its target is open.
+    #[error("Circuit open: {0}")]
+    CircuitOpen(String),
+
+    /// The operation was stopped because an abort was requested.
+    #[error("Aborted: {0}")]
+    Aborted(String),
+
+    /// A moderator rejected the user's input or the agent's reply.
+    #[error("Blocked by moderation: {0}")]
+    Blocked(String),
+
+    /// An agent's session used up its token or cost budget.
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+
+    /// An agent turn did not finish before its deadline.
+    #[error("Deadline exceeded: {0}")]
+    DeadlineExceeded(String),
+
     /// An error from the Llama C++ backend.
     #[cfg(feature = "local")]
     #[error("Llama C++ error: {0}")]
@@ -0,0 +1,109 @@
+//! # Events Module
+//!
+//! Agents report what they are doing as typed [`AgentEvent`]s instead of printing
+//! to stdout. Applications that embed an agent in a GUI, a web service or a log
+//! pipeline register an [`AgentObserver`] with `AgentBuilder::observer`, or take a
+//! `Stream` of events from `Agent::subscribe`. [`ConsoleObserver`] restores the
+//! terminal output the CLI examples show.
+
+use serde_json::Value;
+use std::io::Write;
+use tokio::sync::mpsc;
+
+/// Something an agent did while answering a message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentEvent {
+    /// The agent is waiting for the LLM to respond.
+    ThinkingStarted,
+    /// The reasoning produced by ReAct mode before the agent acts.
+    Reasoning(String),
+    /// A piece of the response text as it streams in.
+    TokenDelta(String),
+    /// The agent is about to run a tool.
+    ToolCallStarted {
+        /// The name of the tool.
+        name: String,
+        /// The arguments the model passed.
+        args: Value,
+    },
+    /// A tool finished running.
+    ToolCallFinished {
+        /// The name of the tool.
+        name: String,
+        /// Whether the tool succeeded.
+        success: bool,
+        /// The tool's output.
+        output: String,
+    },
+    /// The agent's answer to the message.
+    FinalAnswer(String),
+}
+
+/// Receives the events of one or more agents.
+///
+/// Observers are called synchronously from the agent loop, so they should return
+/// quickly; forward events to a channel for anything slow.
+pub trait AgentObserver: Send + Sync {
+    /// Called for every event; `agent` is the name of the agent that emitted it.
+    fn on_event(&self, agent: &str, event: &AgentEvent);
+}
+
+impl<F> AgentObserver for F
+where
+    F: Fn(&str, &AgentEvent) + Send + Sync,
+{
+    fn on_event(&self, agent: &str, event: &AgentEvent) {
+        self(agent, event)
+    }
+}
+
+/// Prints streamed text, ReAct reasoning and tool activity to stdout.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsoleObserver;
+
+impl AgentObserver for ConsoleObserver {
+    fn on_event(&self, _agent: &str, event: &AgentEvent) {
+        match event {
+            AgentEvent::Reasoning(reasoning) => println!("\n💭 ReAct Reasoning:\n{}\n", reasoning),
+            AgentEvent::TokenDelta(text) => {
+                print!("{}", text);
+                let _ = std::io::stdout().flush();
+            }
+            AgentEvent::ToolCallStarted { name, .. } => println!("\n🔧 {}", name),
+            AgentEvent::FinalAnswer(_) => println!(),
+            AgentEvent::ThinkingStarted | AgentEvent::ToolCallFinished { .. } => {}
+        }
+    }
+}
+
+/// Forwards events to a channel; the receiving end is the stream returned by
+/// `Agent::subscribe` and `ForestOfAgents::subscribe`.
+pub(crate) struct ChannelObserver {
+    sender: mpsc::UnboundedSender<(String, AgentEvent)>,
+}
+
+impl ChannelObserver {
+    /// Creates an observer and the receiver its events arrive on.
+    pub(crate) fn new() -> (Self, mpsc::UnboundedReceiver<(String, AgentEvent)>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+}
+
+impl AgentObserver for ChannelObserver {
+    fn on_event(&self, agent: &str, event: &AgentEvent) {
+        // A dropped stream just means nobody is listening any more.
+        let _ = self.sender.send((agent.to_string(), event.clone()));
+    }
+}
+
+/// Delivers an event to every observer.
+pub(crate) fn notify(
+    observers: &[std::sync::Arc<dyn AgentObserver>],
+    agent: &str,
+    event: AgentEvent,
+) {
+    for observer in observers {
+        observer.on_event(agent, &event);
+    }
+}
@@ -0,0 +1,474 @@
+//! # Experiments Module
+//!
+//! Runs the same set of prompts against several agent configurations and compares
+//! the results, so choosing a model, a system prompt or a tool set becomes a
+//! repeatable measurement rather than a matter of impressions.
+//!
+//! Each variant is an [`Agent`] built however you like. Variants run in parallel;
+//! within a variant, cases run one after another on a cleared history. Every case
+//! records whether its checks passed, the latency, the tokens, the cost and the
+//! tools the agent called. [`ExperimentReport::to_markdown`] renders a comparison
+//! table and the report serializes to JSON for further analysis.
+//!
+//! ```rust,no_run
+//! use helios_engine::experiments::{Check, Experiment, ExperimentCase};
+//! use helios_engine::{Agent, Config};
+//!
+//! # async fn example() -> helios_engine::Result<()> {
+//! let config = Config::from_file("config.toml")?;
+//! let terse = Agent::builder("terse")
+//!     .config(config.clone())
+//!     .system_prompt("Answer in one word.")
+//!     .build()
+//!     .await?;
+//! let chatty = Agent::builder("chatty").config(config).build().await?;
+//!
+//! let report = Experiment::new("capitals")
+//!     .variant("terse", terse)
+//!     .variant("chatty", chatty)
+//!     .case(ExperimentCase::new("france", "What is the capital of France?")
+//!         .check(Check::Contains("Paris".to_string())))
+//!     .run()
+//!     .await?;
+//! println!("{}", report.to_markdown());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::agent::Agent;
+use crate::chat::Role;
+use crate::error::{HeliosError, Result};
+use crate::usage::TokenUsage;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Instant;
+
+/// A condition an agent's answer must meet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// The answer contains this text, ignoring case.
+    Contains(String),
+    /// The answer does not contain this text, ignoring case.
+    NotContains(String),
+    /// The answer, trimmed, equals this text.
+    Equals(String),
+    /// The answer matches this regular expression.
+    Matches(String),
+    /// The agent called this tool while answering.
+    UsesTool(String),
+}
+
+impl Check {
+    /// Returns whether `answer` and the tools called pass the check.
+    ///
+    /// An invalid regular expression fails; [`Experiment::run`] rejects those up front.
+    pub fn passes(&self, answer: &str, tools_called: &[String]) -> bool {
+        match self {
+            Check::Contains(text) => answer.to_lowercase().contains(&text.to_lowercase()),
+            Check::NotContains(text) => !answer.to_lowercase().contains(&text.to_lowercase()),
+            Check::Equals(text) => answer.trim() == text.trim(),
+            Check::Matches(pattern) => Regex::new(pattern).is_ok_and(|re| re.is_match(answer)),
+            Check::UsesTool(name) => tools_called.iter().any(|tool| tool == name),
+        }
+    }
+}
+
+/// A prompt and the checks its answer must pass.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentCase {
+    /// A short name for the case, used in reports.
+    pub name: String,
+    /// The message sent to the agent.
+    pub prompt: String,
+    /// The checks the answer must pass, written as `{contains: text}` and so on.
+    #[serde(default, with = "serde_norway::with::singleton_map_recursive")]
+    pub checks: Vec<Check>,
+}
+
+impl ExperimentCase {
+    /// Creates a case with no checks.
+    pub fn new(name: impl Into<String>, prompt: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            prompt: prompt.into(),
+            checks: Vec::new(),
+        }
+    }
+
+    /// Adds a check.
+    pub fn check(mut self, check: Check) -> Self {
+        self.checks.push(check);
+        self
+    }
+
+    /// Loads a list of cases from a YAML or JSON file (by extension).
+    pub fn load_all(path: impl AsRef<Path>) -> Result<Vec<Self>> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        let is_json = path.extension().and_then(|e| e.to_str()) == Some("json");
+        let cases = if is_json {
+            serde_json::from_str(&content).map_err(|e| e.to_string())
+        } else {
+            serde_norway::from_str(&content).map_err(|e| e.to_string())
+        };
+        cases.map_err(|e| {
+            HeliosError::ConfigError(format!(
+                "Invalid experiment cases '{}': {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+}
+
+/// The outcome of one case for one variant.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseResult {
+    /// The case name.
+    pub case: String,
+    /// The agent's answer, or empty if it failed.
+    pub answer: String,
+    /// The error, if the agent failed to answer.
+    pub error: Option<String>,
+    /// How many checks passed.
+    pub checks_passed: usize,
+    /// How many checks the case has.
+    pub checks_total: usize,
+    /// Wall-clock time to answer, in milliseconds.
+    pub latency_ms: u64,
+    /// Tokens used to answer.
+    pub usage: TokenUsage,
+    /// Dollar cost of the answer, according to the agent's price table.
+    pub cost: f64,
+    /// The tools the agent called, in order.
+    pub tools_called: Vec<String>,
+}
+
+impl CaseResult {
+    /// Returns `true` if the agent answered and every check passed.
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.checks_passed == self.checks_total
+    }
+}
+
+/// The results of every case for one variant.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariantReport {
+    /// The variant name.
+    pub name: String,
+    /// The model the variant's agent last answered with.
+    pub model: Option<String>,
+    /// One result per case, in case order.
+    pub results: Vec<CaseResult>,
+}
+
+impl VariantReport {
+    /// Returns the fraction of cases that passed, from 0 to 1.
+    pub fn pass_rate(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+        self.results.iter().filter(|r| r.passed()).count() as f64 / self.results.len() as f64
+    }
+
+    /// Returns the mean latency across cases, in milliseconds.
+    pub fn mean_latency_ms(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+        self.results
+            .iter()
+            .map(|r| r.latency_ms as f64)
+            .sum::<f64>()
+            / self.results.len() as f64
+    }
+
+    /// Returns the tokens used across all cases.
+    pub fn total_usage(&self) -> TokenUsage {
+        let mut total = TokenUsage::default();
+        for result in &self.results {
+            total += result.usage;
+        }
+        total
+    }
+
+    /// Returns the cost of all cases.
+    pub fn total_cost(&self) -> f64 {
+        self.results.iter().map(|r| r.cost).sum()
+    }
+
+    /// Returns the number of cases the agent failed to answer.
+    pub fn errors(&self) -> usize {
+        self.results.iter().filter(|r| r.error.is_some()).count()
+    }
+}
+
+/// The results of an experiment, one report per variant.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentReport {
+    /// The experiment name.
+    pub name: String,
+    /// One report per variant, in the order the variants were added.
+    pub variants: Vec<VariantReport>,
+}
+
+impl ExperimentReport {
+    /// Returns the variant with the highest pass rate, breaking ties by lower cost.
+    pub fn best(&self) -> Option<&VariantReport> {
+        self.variants.iter().max_by(|a, b| {
+            a.pass_rate()
+                .total_cmp(&b.pass_rate())
+                .then(b.total_cost().total_cmp(&a.total_cost()))
+        })
+    }
+
+    /// Renders a comparison table followed by a pass/fail grid per case.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# Experiment: {}\n\n", self.name);
+        out.push_str("| Variant | Model | Pass rate | Mean latency | Tokens | Cost | Errors |\n");
+        out.push_str("|---|---|---|---|---|---|---|\n");
+        for variant in &self.variants {
+            out.push_str(&format!(
+                "| {} | {} | {:.0}% | {:.0} ms | {} | ${:.4} | {} |\n",
+                variant.name,
+                variant.model.as_deref().unwrap_or("-"),
+                variant.pass_rate() * 100.0,
+                variant.mean_latency_ms(),
+                variant.total_usage().total_tokens,
+                variant.total_cost(),
+                variant.errors(),
+            ));
+        }
+
+        let Some(first) = self.variants.first() else {
+            return out;
+        };
+        out.push_str("\n| Case |");
+        for variant in &self.variants {
+            out.push_str(&format!(" {} |", variant.name));
+        }
+        out.push_str("\n|---|");
+        out.push_str(&"---|".repeat(self.variants.len()));
+        out.push('\n');
+        for (i, case) in first.results.iter().enumerate() {
+            out.push_str(&format!("| {} |", case.case));
+            for variant in &self.variants {
+                let cell = match variant.results.get(i) {
+                    Some(result) if result.error.is_some() => "error".to_string(),
+                    Some(result) => format!(
+                        "{} {}/{}",
+                        if result.passed() { "pass" } else { "fail" },
+                        result.checks_passed,
+                        result.checks_total
+                    ),
+                    None => "-".to_string(),
+                };
+                out.push_str(&format!(" {} |", cell));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Runs a set of cases against several agent variants.
+pub struct Experiment {
+    name: String,
+    variants: Vec<(String, Agent)>,
+    cases: Vec<ExperimentCase>,
+}
+
+impl Experiment {
+    /// Creates an experiment with no variants or cases.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            variants: Vec::new(),
+            cases: Vec::new(),
+        }
+    }
+
+    /// Adds an agent configuration to compare.
+    pub fn variant(mut self, name: impl Into<String>, agent: Agent) -> Self {
+        self.variants.push((name.into(), agent));
+        self
+    }
+
+    /// Adds a case.
+    pub fn case(mut self, case: ExperimentCase) -> Self {
+        self.cases.push(case);
+        self
+    }
+
+    /// Adds several cases.
+    pub fn cases(mut self, cases: impl IntoIterator<Item = ExperimentCase>) -> Self {
+        self.cases.extend(cases);
+        self
+    }
+
+    /// Runs every case against every variant, with the variants in parallel.
+    ///
+    /// A case the agent fails to answer is recorded as an error rather than
+    /// stopping the experiment.
+    pub async fn run(self) -> Result<ExperimentReport> {
+        for case in &self.cases {
+            for check in &case.checks {
+                if let Check::Matches(pattern) = check {
+                    Regex::new(pattern).map_err(|e| {
+                        HeliosError::ConfigError(format!(
+                            "Invalid pattern in case '{}': {}",
+                            case.name, e
+                        ))
+                    })?;
+                }
+            }
+        }
+
+        let cases = &self.cases;
+        let runs = self
+            .variants
+            .into_iter()
+            .map(|(name, agent)| run_variant(name, agent, cases));
+        Ok(ExperimentReport {
+            name: self.name,
+            variants: futures::future::join_all(runs).await,
+        })
+    }
+}
+
+/// Runs every case against one agent, clearing its history before each.
+async fn run_variant(name: String, mut agent: Agent, cases: &[ExperimentCase]) -> VariantReport {
+    let mut results = Vec::with_capacity(cases.len());
+    for case in cases {
+        agent.clear_history();
+        let started = Instant::now();
+        let outcome = agent.chat(case.prompt.as_str()).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let tools_called: Vec<String> = agent
+            .chat_session()
+            .messages
+            .iter()
+            .filter(|m| m.role == Role::Assistant)
+            .flat_map(|m| m.tool_calls.iter().flatten())
+            .map(|call| call.function.name.clone())
+            .collect();
+        let (answer, error) = match outcome {
+            Ok(answer) => (answer, None),
+            Err(e) => (String::new(), Some(e.to_string())),
+        };
+        let checks_passed = if error.is_some() {
+            0
+        } else {
+            case.checks
+                .iter()
+                .filter(|check| check.passes(&answer, &tools_called))
+                .count()
+        };
+
+        results.push(CaseResult {
+            case: case.name.clone(),
+            answer,
+            error,
+            checks_passed,
+            checks_total: case.checks.len(),
+            latency_ms,
+            usage: agent.session_usage(),
+            cost: agent.session_cost(),
+            tools_called,
+        });
+    }
+
+    VariantReport {
+        name,
+        model: agent.last_provider(),
+        results,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockLLMProvider;
+    use crate::tools::CalculatorTool;
+
+    /// Tests that variants are compared on the same cases.
+    #[tokio::test]
+    async fn test_experiment_compares_variants() {
+        let good = Agent::builder("good")
+            .llm_provider(
+                MockLLMProvider::new()
+                    .with_response("Paris")
+                    .with_tool_call("calculator", serde_json::json!({"expression": "6 * 7"}))
+                    .with_response("42"),
+            )
+            .tool(Box::new(CalculatorTool))
+            .build()
+            .await
+            .unwrap();
+        let bad = Agent::builder("bad")
+            .llm_provider(
+                MockLLMProvider::new()
+                    .with_response("Lyon")
+                    .with_response("42"),
+            )
+            .build()
+            .await
+            .unwrap();
+
+        let report = Experiment::new("smoke")
+            .variant("good", good)
+            .variant("bad", bad)
+            .case(
+                ExperimentCase::new("capital", "Capital of France?")
+                    .check(Check::Contains("paris".to_string())),
+            )
+            .case(
+                ExperimentCase::new("math", "6 * 7?")
+                    .check(Check::Matches(r"^\d+$".to_string()))
+                    .check(Check::UsesTool("calculator".to_string())),
+            )
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(report.variants[0].pass_rate(), 1.0);
+        assert_eq!(report.variants[0].results[1].tools_called, ["calculator"]);
+        assert_eq!(report.variants[1].pass_rate(), 0.0);
+        assert_eq!(report.variants[1].results[1].checks_passed, 1);
+        assert_eq!(report.best().unwrap().name, "good");
+
+        let markdown = report.to_markdown();
+        let model = report.variants[0].model.clone().unwrap();
+        assert!(markdown.contains(&format!("| good | {} | 100% |", model)));
+        assert!(markdown.contains("| math | pass 2/2 | fail 1/2 |"));
+    }
+
+    /// Tests that invalid patterns are rejected and cases load from YAML.
+    #[tokio::test]
+    async fn test_experiment_cases_validation_and_loading() {
+        let result = Experiment::new("bad")
+            .case(ExperimentCase::new("x", "y").check(Check::Matches("(".to_string())))
+            .run()
+            .await;
+        assert!(matches!(result, Err(HeliosError::ConfigError(_))));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cases.yaml");
+        std::fs::write(
+            &path,
+            "- name: greet\n  prompt: Say hi\n  checks:\n    - contains: hi\n    - uses_tool: echo\n",
+        )
+        .unwrap();
+        let cases = ExperimentCase::load_all(&path).unwrap();
+        assert_eq!(
+            cases[0].checks,
+            [
+                Check::Contains("hi".to_string()),
+                Check::UsesTool("echo".to_string())
+            ]
+        );
+    }
+}
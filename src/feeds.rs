@@ -0,0 +1,402 @@
+//! # Feeds Module
+//!
+//! A tool that reads RSS and Atom feeds and returns the entries the agent has not
+//! seen yet. Seen entries are recorded in a key-value store, which can be shared
+//! with a `MemoryDBTool` so a news-digest agent remembers what it already reported.
+
+use crate::error::{HeliosError, Result};
+use crate::rag::loaders::html_to_text;
+use crate::tools::{Tool, ToolParameter, ToolPermission, ToolResult};
+use crate::truncation::truncate_chars;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Prefix of the keys that record seen entries in the store.
+const SEEN_PREFIX: &str = "feed_seen:";
+
+/// A tool that fetches RSS and Atom feeds and returns their new entries.
+///
+/// ```rust
+/// use helios_engine::{FeedReaderTool, MemoryDBTool};
+/// use std::collections::HashMap;
+/// use std::sync::{Arc, Mutex};
+///
+/// // Share the store so the agent can also inspect or reset what it has seen.
+/// let db = Arc::new(Mutex::new(HashMap::new()));
+/// let feeds = FeedReaderTool::with_shared_db(db.clone()).max_entries(10);
+/// let memory = MemoryDBTool::with_shared_db(db);
+/// ```
+pub struct FeedReaderTool {
+    db: Arc<Mutex<HashMap<String, String>>>,
+    max_entries: usize,
+    max_summary_chars: usize,
+    max_output_chars: usize,
+    client: Client,
+}
+
+/// An entry ready to show to the agent.
+struct FeedItem {
+    key: String,
+    title: String,
+    date: Option<DateTime<Utc>>,
+    link: Option<String>,
+    summary: String,
+}
+
+impl FeedReaderTool {
+    /// Creates a tool that records seen entries in its own store.
+    pub fn new() -> Self {
+        Self::with_shared_db(Arc::new(Mutex::new(HashMap::new())))
+    }
+
+    /// Creates a tool that records seen entries in `db`, such as a `MemoryDBTool`'s store.
+    pub fn with_shared_db(db: Arc<Mutex<HashMap<String, String>>>) -> Self {
+        Self {
+            db,
+            max_entries: 20,
+            max_summary_chars: 400,
+            max_output_chars: 20_000,
+            client: Client::builder()
+                .user_agent("Helios-FeedReader/1.0")
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Limits the entries returned per feed when a call gives no `limit` (20 by default).
+    pub fn max_entries(mut self, max: usize) -> Self {
+        self.max_entries = max;
+        self
+    }
+
+    /// Limits the characters of each entry's summary (400 by default).
+    pub fn max_summary_chars(mut self, max: usize) -> Self {
+        self.max_summary_chars = max;
+        self
+    }
+
+    /// Limits the characters returned to the agent (20,000 by default).
+    pub fn max_output_chars(mut self, max: usize) -> Self {
+        self.max_output_chars = max;
+        self
+    }
+
+    /// Sets the request timeout (30 seconds by default).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.client = Client::builder()
+            .user_agent("Helios-FeedReader/1.0")
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default();
+        self
+    }
+
+    /// Fetches and parses the feed at `url`.
+    async fn fetch(&self, url: &str) -> Result<(String, Vec<FeedItem>)> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Failed to fetch {}: {}", url, e)))?;
+        if !response.status().is_success() {
+            return Err(HeliosError::ToolError(format!(
+                "{} returned HTTP {}",
+                url,
+                response.status()
+            )));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Failed to read {}: {}", url, e)))?;
+        let feed = feed_rs::parser::parse(&body[..]).map_err(|e| {
+            HeliosError::ToolError(format!("{} is not an RSS or Atom feed: {}", url, e))
+        })?;
+
+        let title = feed
+            .title
+            .map(|t| html_to_text(&t.content))
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| url.to_string());
+        let mut items: Vec<FeedItem> = feed
+            .entries
+            .into_iter()
+            .map(|entry| {
+                let summary = entry
+                    .summary
+                    .map(|s| s.content)
+                    .or_else(|| entry.content.and_then(|c| c.body))
+                    .map(|s| html_to_text(&s).replace('\n', " "))
+                    .unwrap_or_default();
+                FeedItem {
+                    key: format!("{}{}:{}", SEEN_PREFIX, url, entry.id),
+                    title: entry
+                        .title
+                        .map(|t| html_to_text(&t.content))
+                        .filter(|t| !t.is_empty())
+                        .unwrap_or_else(|| "(untitled)".to_string()),
+                    date: entry.published.or(entry.updated),
+                    link: entry.links.into_iter().next().map(|link| link.href),
+                    summary: truncate_chars(&summary, self.max_summary_chars, "..."),
+                }
+            })
+            .collect();
+        // Newest first; entries without a date keep their feed order at the end.
+        items.sort_by_key(|item| std::cmp::Reverse(item.date));
+        Ok((title, items))
+    }
+
+    fn limit(&self, text: String) -> String {
+        if text.chars().count() <= self.max_output_chars {
+            return text;
+        }
+        format!(
+            "{}\n... (truncated to {} characters; pass a smaller limit)",
+            truncate_chars(&text, self.max_output_chars, "..."),
+            self.max_output_chars
+        )
+    }
+}
+
+impl Default for FeedReaderTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for FeedReaderTool {
+    fn name(&self) -> &str {
+        "feed_reader"
+    }
+
+    fn description(&self) -> &str {
+        "Read RSS or Atom feeds and return entries not seen before, newest first, with title, \
+         date, link and summary. Entries returned are remembered, so later calls only show new ones."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        params.insert(
+            "urls".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "Feed URL to read, or several separated by spaces".to_string(),
+                required: Some(true),
+            },
+        );
+        params.insert(
+            "limit".to_string(),
+            ToolParameter {
+                param_type: "number".to_string(),
+                description: "Most entries to return per feed".to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "include_seen".to_string(),
+            ToolParameter {
+                param_type: "boolean".to_string(),
+                description: "Also return entries already seen (default false)".to_string(),
+                required: Some(false),
+            },
+        );
+        params
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::ReadOnly
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let urls: Vec<String> = match args.get("urls").or_else(|| args.get("url")) {
+            Some(Value::String(urls)) => urls
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|u| !u.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Some(Value::Array(urls)) => urls
+                .iter()
+                .filter_map(|u| u.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+        if urls.is_empty() {
+            return Err(HeliosError::ToolError(
+                "Missing 'urls' parameter".to_string(),
+            ));
+        }
+        let limit = args
+            .get("limit")
+            .and_then(Value::as_u64)
+            .map(|n| n as usize)
+            .unwrap_or(self.max_entries);
+        let include_seen = args
+            .get("include_seen")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let mut out = String::new();
+        let mut failures = 0;
+        for url in &urls {
+            let (title, items) = match self.fetch(url).await {
+                Ok(feed) => feed,
+                Err(e) => {
+                    failures += 1;
+                    out.push_str(&format!("## {}\nError: {}\n\n", url, e));
+                    continue;
+                }
+            };
+            let total = items.len();
+            let new: Vec<FeedItem> = {
+                let mut db = self.db.lock().map_err(|e| {
+                    HeliosError::ToolError(format!("Failed to lock database: {}", e))
+                })?;
+                let new: Vec<FeedItem> = items
+                    .into_iter()
+                    .filter(|item| include_seen || !db.contains_key(&item.key))
+                    .take(limit)
+                    .collect();
+                for item in &new {
+                    let seen = item.date.map(|d| d.to_rfc3339()).unwrap_or_default();
+                    db.insert(item.key.clone(), seen);
+                }
+                new
+            };
+
+            let label = if include_seen { "entries" } else { "new" };
+            out.push_str(&format!(
+                "## {} ({} {} of {})\n",
+                title,
+                new.len(),
+                label,
+                total
+            ));
+            for item in &new {
+                out.push_str(&format!("- {}\n", item.title));
+                if let Some(date) = item.date {
+                    out.push_str(&format!("  Date: {}\n", date.format("%Y-%m-%d %H:%M UTC")));
+                }
+                if let Some(link) = &item.link {
+                    out.push_str(&format!("  Link: {}\n", link));
+                }
+                if !item.summary.is_empty() {
+                    out.push_str(&format!("  {}\n", item.summary));
+                }
+            }
+            out.push('\n');
+        }
+
+        let out = self.limit(out.trim_end().to_string());
+        if failures == urls.len() {
+            Ok(ToolResult::error(out))
+        } else {
+            Ok(ToolResult::success(out))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::MemoryDBTool;
+    use axum::{http::header, routing::get, Router};
+    use serde_json::json;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Daily &amp; News</title>
+<item><guid>a1</guid><title>Older story</title><link>https://news.test/a1</link>
+<pubDate>Mon, 05 Oct 2026 08:00:00 GMT</pubDate><description>&lt;p&gt;First &lt;b&gt;paragraph&lt;/b&gt;&lt;/p&gt;</description></item>
+<item><guid>a2</guid><title>Newer story</title><link>https://news.test/a2</link>
+<pubDate>Tue, 06 Oct 2026 08:00:00 GMT</pubDate><description>Second</description></item>
+</channel></rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom"><title>Release Notes</title><id>urn:notes</id>
+<updated>2026-10-07T00:00:00Z</updated>
+<entry><id>urn:notes:1</id><title>v1.0 released</title><updated>2026-10-07T00:00:00Z</updated>
+<link href="https://notes.test/1"/><content type="html">&lt;p&gt;Big release&lt;/p&gt;</content></entry>
+</feed>"#;
+
+    /// Tests reading RSS and Atom feeds and skipping entries already seen.
+    #[tokio::test]
+    async fn test_feed_reader_tool() {
+        let app = Router::new()
+            .route(
+                "/rss",
+                get(|| async { ([(header::CONTENT_TYPE, "application/rss+xml")], RSS) }),
+            )
+            .route("/atom", get(|| async { ATOM }))
+            .route(
+                "/html",
+                get(|| async { "<html><body>not a feed</body></html>" }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let db = Arc::new(Mutex::new(HashMap::new()));
+        let tool = FeedReaderTool::with_shared_db(db.clone());
+        let rss = format!("{}/rss", base);
+        let atom = format!("{}/atom", base);
+
+        let result = tool
+            .execute(json!({"urls": [rss.clone()], "limit": 1}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(
+            result
+                .output
+                .starts_with("## Daily & News (1 new of 2)\n- Newer story\n"),
+            "{}",
+            result.output
+        );
+        assert!(result.output.contains("Date: 2026-10-06 08:00 UTC"));
+        assert!(!result.output.contains("Older story"));
+
+        let result = tool
+            .execute(json!({"urls": format!("{} {}", rss, atom)}))
+            .await
+            .unwrap();
+        assert!(result
+            .output
+            .contains("## Daily & News (1 new of 2)\n- Older story\n"));
+        assert!(result.output.contains("  First paragraph\n"));
+        assert!(result
+            .output
+            .contains("## Release Notes (1 new of 1)\n- v1.0 released\n"));
+        assert!(result
+            .output
+            .contains("  Link: https://notes.test/1\n  Big release"));
+
+        let result = tool.execute(json!({"url": rss.clone()})).await.unwrap();
+        assert_eq!(result.output, "## Daily & News (0 new of 2)");
+        let result = tool
+            .execute(json!({"url": rss.clone(), "include_seen": true}))
+            .await
+            .unwrap();
+        assert!(result.output.contains("(2 entries of 2)"));
+
+        // The seen entries live in the shared store, so clearing it there resets the tool.
+        let memory = MemoryDBTool::with_shared_db(db.clone());
+        assert_eq!(db.lock().unwrap().len(), 3);
+        memory.execute(json!({"operation": "clear"})).await.unwrap();
+        let result = tool.execute(json!({"url": rss})).await.unwrap();
+        assert!(result.output.contains("(2 new of 2)"));
+
+        let result = tool
+            .execute(json!({"url": format!("{}/html", base)}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("is not an RSS or Atom feed"));
+    }
+}
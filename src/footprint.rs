@@ -0,0 +1,203 @@
+//! # Footprint Module
+//!
+//! Approximate memory accounting for the parts of the engine that grow while an
+//! agent runs: chat histories, in-memory vector stores and forest contexts. The
+//! numbers are estimates of the heap held by strings, vectors and JSON values, not
+//! exact allocator figures, but they grow and shrink with the real usage and are
+//! cheap enough to check after every turn.
+//!
+//! Each of those types also has a pruning method (`ChatSession::prune_oldest`,
+//! `VectorStore::evict_cold`, `SharedContext::prune_history`), and agents can prune
+//! their own history automatically with `AgentBuilder::history_limit`.
+
+use crate::chat::ChatSession;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Types that can estimate how much memory they hold.
+pub trait MemoryFootprint {
+    /// Returns the approximate number of bytes held, including the value itself.
+    fn approx_bytes(&self) -> usize;
+}
+
+/// Entry count and approximate size of a store or history.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// The number of entries: messages, vectors, documents.
+    pub entries: usize,
+    /// The approximate number of bytes held in this process.
+    pub bytes: usize,
+}
+
+impl MemoryStats {
+    /// Adds another set of stats to this one.
+    pub fn merge(self, other: MemoryStats) -> MemoryStats {
+        MemoryStats {
+            entries: self.entries + other.entries,
+            bytes: self.bytes + other.bytes,
+        }
+    }
+}
+
+impl std::fmt::Display for MemoryStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} entries, {}", self.entries, format_bytes(self.bytes))
+    }
+}
+
+/// How much conversation history an agent keeps between turns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryLimit {
+    /// Keep at most this many messages, not counting the system prompt.
+    Messages(usize),
+    /// Keep at most about this many bytes of history.
+    Bytes(usize),
+}
+
+impl HistoryLimit {
+    /// Prunes the oldest messages of `session` to fit the limit.
+    ///
+    /// Returns the number of messages removed.
+    pub fn apply(&self, session: &mut ChatSession) -> usize {
+        match *self {
+            HistoryLimit::Messages(max) => session.prune_oldest(max),
+            HistoryLimit::Bytes(max) => session.prune_to_bytes(max),
+        }
+    }
+}
+
+/// Which tool results an agent replaces with placeholders at the start of each turn.
+///
+/// Once the model has answered a tool result, the full output rarely matters to
+/// later turns but still takes up context. Eliding it keeps the tool exchange in
+/// place with a note of how much was removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolResultPruning {
+    /// Results shorter than this many bytes are kept.
+    pub min_bytes: usize,
+    /// Results from this many of the most recent user turns, counting the new
+    /// one, are kept.
+    pub keep_turns: usize,
+}
+
+impl Default for ToolResultPruning {
+    /// Elides results of 1 KiB or more from before the current turn.
+    fn default() -> Self {
+        Self {
+            min_bytes: 1024,
+            keep_turns: 1,
+        }
+    }
+}
+
+impl ToolResultPruning {
+    /// Elides the results of `session` the policy covers.
+    ///
+    /// Returns the number of results elided.
+    pub fn apply(&self, session: &mut ChatSession) -> usize {
+        session.elide_tool_results(self.min_bytes, self.keep_turns)
+    }
+}
+
+/// Formats a byte count as `512 B`, `1.5 KiB`, `3.2 MiB` and so on.
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+impl MemoryFootprint for String {
+    fn approx_bytes(&self) -> usize {
+        std::mem::size_of::<String>() + self.capacity()
+    }
+}
+
+impl<T: MemoryFootprint> MemoryFootprint for Option<T> {
+    fn approx_bytes(&self) -> usize {
+        match self {
+            Some(value) => value.approx_bytes(),
+            None => std::mem::size_of::<Option<T>>(),
+        }
+    }
+}
+
+impl<T: MemoryFootprint> MemoryFootprint for Vec<T> {
+    fn approx_bytes(&self) -> usize {
+        std::mem::size_of::<Vec<T>>()
+            + (self.capacity() - self.len()) * std::mem::size_of::<T>()
+            + self.iter().map(|item| item.approx_bytes()).sum::<usize>()
+    }
+}
+
+impl<V: MemoryFootprint> MemoryFootprint for HashMap<String, V> {
+    fn approx_bytes(&self) -> usize {
+        std::mem::size_of::<HashMap<String, V>>()
+            + self
+                .iter()
+                .map(|(key, value)| key.approx_bytes() + value.approx_bytes())
+                .sum::<usize>()
+    }
+}
+
+impl MemoryFootprint for Value {
+    fn approx_bytes(&self) -> usize {
+        std::mem::size_of::<Value>()
+            + match self {
+                Value::String(s) => s.capacity(),
+                Value::Array(items) => items.iter().map(|item| item.approx_bytes()).sum(),
+                Value::Object(map) => map
+                    .iter()
+                    .map(|(key, value)| key.approx_bytes() + value.approx_bytes())
+                    .sum(),
+                Value::Null | Value::Bool(_) | Value::Number(_) => 0,
+            }
+    }
+}
+
+/// Returns the approximate size of an embedding together with its text and metadata.
+pub(crate) fn vector_entry_bytes(
+    id: &str,
+    embedding: &[f32],
+    text: &str,
+    metadata: &HashMap<String, Value>,
+) -> usize {
+    id.len()
+        + std::mem::size_of_val(embedding)
+        + text.len()
+        + metadata.approx_bytes()
+        + 3 * std::mem::size_of::<String>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that JSON values are counted recursively.
+    #[test]
+    fn test_value_footprint_grows_with_content() {
+        let small = serde_json::json!({"a": 1});
+        let large = serde_json::json!({"a": 1, "b": ["x".repeat(1000)]});
+        assert!(large.approx_bytes() > small.approx_bytes() + 1000);
+    }
+
+    /// Tests byte formatting.
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+        let stats = MemoryStats {
+            entries: 2,
+            bytes: 2048,
+        };
+        assert_eq!(stats.to_string(), "2 entries, 2.0 KiB");
+    }
+}
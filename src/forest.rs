@@ -6,12 +6,18 @@
 //! The ForestOfAgents struct manages a collection of agents and provides mechanisms
 //! for inter-agent communication and coordination.
 
+use crate::abort::AbortHandle;
 use crate::agent::{Agent, AgentBuilder};
 use crate::config::Config;
 use crate::error::{HeliosError, Result};
+use crate::events::{AgentEvent, AgentObserver, ChannelObserver};
+use crate::footprint::{MemoryFootprint, MemoryStats};
 use crate::tools::{Tool, ToolParameter, ToolResult};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -19,7 +25,7 @@ use tokio::sync::RwLock;
 pub type AgentId = String;
 
 /// A message sent between agents in the forest.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ForestMessage {
     /// The ID of the sender agent.
     pub from: AgentId,
@@ -41,7 +47,7 @@ impl ForestMessage {
             to,
             content,
             metadata: HashMap::new(),
-            timestamp: chrono::Utc::now(),
+            timestamp: crate::clock::now(),
         }
     }
 
@@ -57,8 +63,19 @@ impl ForestMessage {
     }
 }
 
+impl MemoryFootprint for ForestMessage {
+    fn approx_bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.from.capacity()
+            + self.to.as_ref().map_or(0, |to| to.capacity())
+            + self.content.capacity()
+            + self.metadata.approx_bytes()
+    }
+}
+
 /// Status of a task in the collaborative workflow.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TaskStatus {
     Pending,
     InProgress,
@@ -78,7 +95,7 @@ impl TaskStatus {
 }
 
 /// A task in the collaborative plan.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskItem {
     /// Unique identifier for the task.
     pub id: String,
@@ -96,6 +113,17 @@ pub struct TaskItem {
     pub metadata: HashMap<String, String>,
 }
 
+impl MemoryFootprint for TaskItem {
+    fn approx_bytes(&self) -> usize {
+        self.id.approx_bytes()
+            + self.description.approx_bytes()
+            + self.assigned_to.approx_bytes()
+            + self.result.approx_bytes()
+            + self.dependencies.approx_bytes()
+            + self.metadata.approx_bytes()
+    }
+}
+
 impl TaskItem {
     pub fn new(id: String, description: String, assigned_to: AgentId) -> Self {
         Self {
@@ -116,7 +144,7 @@ impl TaskItem {
 }
 
 /// A collaborative task plan created by the coordinator.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskPlan {
     /// Unique identifier for the plan.
     pub plan_id: String,
@@ -137,7 +165,7 @@ impl TaskPlan {
             objective,
             tasks: HashMap::new(),
             task_order: Vec::new(),
-            created_at: chrono::Utc::now(),
+            created_at: crate::clock::now(),
         }
     }
 
@@ -195,10 +223,299 @@ impl TaskPlan {
             .filter_map(|id| self.tasks.get(id))
             .collect()
     }
+
+    /// Applies a change to the plan.
+    ///
+    /// Tasks that are running or completed can't be removed or updated. Updating a
+    /// failed task makes it pending again. Nothing changes if the revision is
+    /// invalid.
+    pub fn revise(&mut self, revision: PlanRevision) -> Result<()> {
+        let invalid = |message: String| Err(HeliosError::AgentError(message));
+        let editable = |plan: &TaskPlan, id: &str| -> Result<()> {
+            match plan.tasks.get(id).map(|task| &task.status) {
+                None => invalid(format!("No task '{}' in the plan", id)),
+                Some(TaskStatus::InProgress | TaskStatus::Completed) => {
+                    invalid(format!("Task '{}' has already started", id))
+                }
+                Some(_) => Ok(()),
+            }
+        };
+
+        match revision {
+            PlanRevision::Add {
+                id,
+                description,
+                assigned_to,
+                dependencies,
+            } => {
+                if self.tasks.contains_key(&id) {
+                    return invalid(format!("Task '{}' already exists", id));
+                }
+                if let Some(missing) = dependencies.iter().find(|d| !self.tasks.contains_key(*d)) {
+                    return invalid(format!("Unknown dependency '{}'", missing));
+                }
+                self.add_task(
+                    TaskItem::new(id, description, assigned_to).with_dependencies(dependencies),
+                );
+            }
+            PlanRevision::Remove { id } => {
+                editable(self, &id)?;
+                self.tasks.remove(&id);
+                self.task_order.retain(|other| *other != id);
+                for task in self.tasks.values_mut() {
+                    task.dependencies.retain(|dep| *dep != id);
+                }
+            }
+            PlanRevision::Update {
+                id,
+                description,
+                assigned_to,
+                dependencies,
+            } => {
+                editable(self, &id)?;
+                if let Some(dependencies) = &dependencies {
+                    for dep in dependencies {
+                        if !self.tasks.contains_key(dep) {
+                            return invalid(format!("Unknown dependency '{}'", dep));
+                        }
+                        if *dep == id || self.depends_on(dep, &id) {
+                            return invalid(format!(
+                                "Task '{}' can't depend on '{}': that would be a cycle",
+                                id, dep
+                            ));
+                        }
+                    }
+                }
+                let task = self.tasks.get_mut(&id).expect("checked above");
+                if let Some(description) = description {
+                    task.description = description;
+                }
+                if let Some(assigned_to) = assigned_to {
+                    task.assigned_to = assigned_to;
+                }
+                if let Some(dependencies) = dependencies {
+                    task.dependencies = dependencies;
+                }
+                task.status = TaskStatus::Pending;
+                task.result = None;
+            }
+            PlanRevision::Reorder { order } => {
+                if let Some(missing) = order.iter().find(|id| !self.tasks.contains_key(*id)) {
+                    return invalid(format!("No task '{}' in the plan", missing));
+                }
+                let rest = self
+                    .task_order
+                    .iter()
+                    .filter(|id| !order.contains(id))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                self.task_order = order.into_iter().chain(rest).collect();
+                self.task_order.dedup();
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if `task_id` depends on `other`, directly or indirectly.
+    fn depends_on(&self, task_id: &str, other: &str) -> bool {
+        let mut stack = vec![task_id];
+        let mut seen = Vec::new();
+        while let Some(id) = stack.pop() {
+            if seen.contains(&id) {
+                continue;
+            }
+            seen.push(id);
+            if let Some(task) = self.tasks.get(id) {
+                for dep in &task.dependencies {
+                    if dep == other {
+                        return true;
+                    }
+                    stack.push(dep);
+                }
+            }
+        }
+        false
+    }
+
+    /// Marks every pending task that depends on `task_id`, directly or through
+    /// other tasks, as failed.
+    ///
+    /// Returns the number of tasks marked.
+    pub fn fail_dependents(&mut self, task_id: &str) -> usize {
+        let mut failed = vec![task_id.to_string()];
+        let mut marked = 0;
+        while let Some(cause) = failed.pop() {
+            for id in &self.task_order {
+                let Some(task) = self.tasks.get_mut(id) else {
+                    continue;
+                };
+                if task.status == TaskStatus::Pending && task.dependencies.contains(&cause) {
+                    task.status = TaskStatus::Failed;
+                    task.result = Some(format!("Skipped: depends on failed task '{}'", cause));
+                    failed.push(id.clone());
+                    marked += 1;
+                }
+            }
+        }
+        marked
+    }
+}
+
+/// A change to a [`TaskPlan`] made while it runs, e.g. by the coordinator's
+/// `revise_plan` tool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PlanRevision {
+    /// Adds a task at the end of the plan.
+    Add {
+        /// The new task's ID.
+        id: String,
+        /// What the task is.
+        description: String,
+        /// The agent that does it.
+        assigned_to: AgentId,
+        /// Tasks that must complete first.
+        #[serde(default)]
+        dependencies: Vec<String>,
+    },
+    /// Removes a task that hasn't started, and drops it from other tasks'
+    /// dependencies.
+    Remove {
+        /// The task's ID.
+        id: String,
+    },
+    /// Changes a task that hasn't started or has failed.
+    Update {
+        /// The task's ID.
+        id: String,
+        /// The new description, if it changes.
+        #[serde(default)]
+        description: Option<String>,
+        /// The new agent, if it changes.
+        #[serde(default)]
+        assigned_to: Option<AgentId>,
+        /// The new dependencies, if they change.
+        #[serde(default)]
+        dependencies: Option<Vec<String>>,
+    },
+    /// Moves the listed tasks to the front of the plan, in that order.
+    Reorder {
+        /// Task IDs in their new order.
+        order: Vec<String>,
+    },
+}
+
+/// What a forest does when a plan task still fails after its retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TaskFailurePolicy {
+    /// Stop and return the failure as an error.
+    #[default]
+    Abort,
+    /// Mark the task and everything that depends on it as failed and carry on.
+    Skip,
+    /// Ask the coordinator for a new plan for the remaining work. Falls back to
+    /// `Skip` when it doesn't make one or has replanned too often.
+    Replan,
+}
+
+/// How a forest retries plan tasks that error or return an unusable answer.
+///
+/// A failed task is retried on the same agent up to `max_retries` times, then,
+/// with `reassign`, handed to each of the other involved agents in turn with the
+/// same number of retries. If it still fails, `on_failure` decides what happens to
+/// the plan. Attempts and the last error are recorded in the task's metadata.
+#[derive(Clone)]
+pub struct TaskRetryPolicy {
+    /// How many times a failed task is retried on the same agent.
+    pub max_retries: usize,
+    /// Whether a task that keeps failing is handed to another agent.
+    pub reassign: bool,
+    /// What happens when a task can't be completed.
+    pub on_failure: TaskFailurePolicy,
+    /// How many times `Replan` may ask for a new plan during one task.
+    pub max_replans: usize,
+    validator: Option<TaskValidator>,
+}
+
+/// Decides whether an agent's answer to a task is usable.
+type TaskValidator = Arc<dyn Fn(&TaskItem, &str) -> bool + Send + Sync>;
+
+impl Default for TaskRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 1,
+            reassign: false,
+            on_failure: TaskFailurePolicy::Abort,
+            max_replans: 2,
+            validator: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for TaskRetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TaskRetryPolicy")
+            .field("max_retries", &self.max_retries)
+            .field("reassign", &self.reassign)
+            .field("on_failure", &self.on_failure)
+            .field("max_replans", &self.max_replans)
+            .field("validator", &self.validator.is_some())
+            .finish()
+    }
+}
+
+impl TaskRetryPolicy {
+    /// Creates the default policy: one retry, no reassignment, abort on failure.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many times a failed task is retried on the same agent.
+    pub fn max_retries(mut self, retries: usize) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Sets whether a task that keeps failing is handed to another agent.
+    pub fn reassign(mut self, reassign: bool) -> Self {
+        self.reassign = reassign;
+        self
+    }
+
+    /// Sets what happens when a task can't be completed.
+    pub fn on_failure(mut self, policy: TaskFailurePolicy) -> Self {
+        self.on_failure = policy;
+        self
+    }
+
+    /// Sets how many times `Replan` may ask for a new plan.
+    pub fn max_replans(mut self, replans: usize) -> Self {
+        self.max_replans = replans;
+        self
+    }
+
+    /// Sets the check that decides whether an agent's answer to a task is usable.
+    ///
+    /// By default any answer that isn't blank is accepted.
+    pub fn validator(
+        mut self,
+        validator: impl Fn(&TaskItem, &str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+
+    /// Returns `true` if `answer` is a usable result for `task`.
+    pub fn accepts(&self, task: &TaskItem, answer: &str) -> bool {
+        match &self.validator {
+            Some(validator) => validator(task, answer),
+            None => !answer.trim().is_empty(),
+        }
+    }
 }
 
 /// Shared context that can be accessed by all agents in the forest.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SharedContext {
     /// Key-value store for shared data.
     pub data: HashMap<String, Value>,
@@ -267,6 +584,157 @@ impl SharedContext {
     pub fn clear_plan(&mut self) {
         self.current_plan = None;
     }
+
+    /// Returns the number of history messages and the approximate memory held.
+    pub fn memory_usage(&self) -> MemoryStats {
+        let plan = self.current_plan.as_ref().map_or(0, |plan| {
+            plan.plan_id.approx_bytes()
+                + plan.objective.approx_bytes()
+                + plan.tasks.approx_bytes()
+                + plan.task_order.approx_bytes()
+        });
+        MemoryStats {
+            entries: self.message_history.len(),
+            bytes: self.data.approx_bytes()
+                + self.message_history.approx_bytes()
+                + self.metadata.approx_bytes()
+                + plan,
+        }
+    }
+
+    /// Drops the oldest messages so that at most `max_messages` remain in the history.
+    ///
+    /// Returns the number of messages removed.
+    pub fn prune_history(&mut self, max_messages: usize) -> usize {
+        let excess = self.message_history.len().saturating_sub(max_messages);
+        self.message_history.drain(..excess);
+        excess
+    }
+
+    /// Renders the task plan and the message history as a diagram.
+    ///
+    /// Plan tasks become nodes labelled with their agent, status and run time,
+    /// with an edge from each dependency. Messages become numbered edges between
+    /// agents, timed from the first message; broadcasts go to an `all` node.
+    pub fn export_trace(&self, format: TraceFormat) -> String {
+        let mut tasks = Vec::new();
+        let mut dependencies = Vec::new();
+        if let Some(plan) = &self.current_plan {
+            let index: HashMap<&str, usize> = plan
+                .task_order
+                .iter()
+                .enumerate()
+                .map(|(i, id)| (id.as_str(), i))
+                .collect();
+            for (i, task) in plan.tasks_in_order().into_iter().enumerate() {
+                let mut status = task.status.as_str().to_string();
+                if let Some(seconds) = task_duration(task) {
+                    status.push_str(&format!(" ({:.1}s)", seconds));
+                }
+                tasks.push(vec![task.id.clone(), task.assigned_to.clone(), status]);
+                for dependency in &task.dependencies {
+                    if let Some(&from) = index.get(dependency.as_str()) {
+                        dependencies.push((from, i));
+                    }
+                }
+            }
+        }
+
+        let mut agents: Vec<String> = Vec::new();
+        let mut node = |agent: &str| match agents.iter().position(|a| a == agent) {
+            Some(i) => i,
+            None => {
+                agents.push(agent.to_string());
+                agents.len() - 1
+            }
+        };
+        let start = self.message_history.first().map(|m| m.timestamp);
+        let mut messages = Vec::new();
+        for (n, message) in self.message_history.iter().enumerate() {
+            let from = node(&message.from);
+            let to = node(message.to.as_deref().unwrap_or("all"));
+            let offset = start.map_or(0.0, |start| {
+                (message.timestamp - start).num_milliseconds() as f64 / 1000.0
+            });
+            let label = format!(
+                "#{} +{:.1}s: {}",
+                n + 1,
+                offset,
+                truncate_label(&message.content, 40)
+            );
+            messages.push((from, to, label));
+        }
+
+        let objective = self
+            .current_plan
+            .as_ref()
+            .map(|plan| format!("Plan: {}", truncate_label(&plan.objective, 60)))
+            .unwrap_or_else(|| "Plan".to_string());
+        let mut out = String::new();
+        match format {
+            TraceFormat::Dot => {
+                let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+                out.push_str("digraph forest {\n    rankdir=LR;\n");
+                out.push_str("    subgraph cluster_plan {\n");
+                out.push_str(&format!("        label=\"{}\";\n", quote(&objective)));
+                for (i, lines) in tasks.iter().enumerate() {
+                    let label: Vec<String> = lines.iter().map(|l| quote(l)).collect();
+                    out.push_str(&format!(
+                        "        task{} [shape=box, label=\"{}\"];\n",
+                        i,
+                        label.join("\\n")
+                    ));
+                }
+                for (from, to) in &dependencies {
+                    out.push_str(&format!("        task{} -> task{};\n", from, to));
+                }
+                out.push_str("    }\n    subgraph cluster_messages {\n");
+                out.push_str("        label=\"Messages\";\n");
+                for (i, agent) in agents.iter().enumerate() {
+                    out.push_str(&format!(
+                        "        agent{} [shape=ellipse, label=\"{}\"];\n",
+                        i,
+                        quote(agent)
+                    ));
+                }
+                for (from, to, label) in &messages {
+                    out.push_str(&format!(
+                        "        agent{} -> agent{} [label=\"{}\"];\n",
+                        from,
+                        to,
+                        quote(label)
+                    ));
+                }
+                out.push_str("    }\n}\n");
+            }
+            TraceFormat::Mermaid => {
+                let quote = |s: &str| s.replace('"', "#quot;");
+                out.push_str("flowchart LR\n");
+                out.push_str(&format!("    subgraph plan[\"{}\"]\n", quote(&objective)));
+                for (i, lines) in tasks.iter().enumerate() {
+                    let label: Vec<String> = lines.iter().map(|l| quote(l)).collect();
+                    out.push_str(&format!("        task{}[\"{}\"]\n", i, label.join("<br/>")));
+                }
+                for (from, to) in &dependencies {
+                    out.push_str(&format!("        task{} --> task{}\n", from, to));
+                }
+                out.push_str("    end\n    subgraph messages[\"Messages\"]\n");
+                for (i, agent) in agents.iter().enumerate() {
+                    out.push_str(&format!("        agent{}([\"{}\"])\n", i, quote(agent)));
+                }
+                for (from, to, label) in &messages {
+                    out.push_str(&format!(
+                        "        agent{} -->|\"{}\"| agent{}\n",
+                        from,
+                        quote(label),
+                        to
+                    ));
+                }
+                out.push_str("    end\n");
+            }
+        }
+        out
+    }
 }
 
 impl Default for SharedContext {
@@ -275,6 +743,184 @@ impl Default for SharedContext {
     }
 }
 
+/// The diagram language of [`SharedContext::export_trace`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// A Graphviz `digraph`, for `dot -Tsvg`.
+    Dot,
+    /// A Mermaid `flowchart`, which GitHub and many Markdown viewers render.
+    Mermaid,
+}
+
+/// How the agents of a forest work together on a task run with
+/// [`ForestOfAgents::run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForestTopology {
+    /// The coordinator breaks the task into a plan and delegates the subtasks to
+    /// the workers; see [`ForestOfAgents::execute_collaborative_task`].
+    Coordinator {
+        /// The agent that plans and synthesizes the final answer.
+        coordinator: AgentId,
+        /// The agents subtasks can be assigned to.
+        workers: Vec<AgentId>,
+    },
+    /// The workers answer the task and the supervisor reviews their work, sending
+    /// it back with feedback until it approves or runs out of revisions.
+    Supervisor {
+        /// The agent that reviews the work and gives the final answer.
+        supervisor: AgentId,
+        /// The agents that do the work.
+        workers: Vec<AgentId>,
+        /// How many times the work may be sent back.
+        max_revisions: usize,
+    },
+    /// Each agent works on the output of the one before it; the last agent's
+    /// output is the answer.
+    Pipeline(Vec<AgentId>),
+    /// The agents take turns discussing the task for a number of rounds; the last
+    /// contribution is the answer.
+    RoundRobin {
+        /// The agents in speaking order.
+        agents: Vec<AgentId>,
+        /// How many times every agent speaks.
+        rounds: usize,
+    },
+}
+
+impl ForestTopology {
+    /// Returns every agent the topology uses.
+    pub fn agents(&self) -> Vec<&AgentId> {
+        match self {
+            ForestTopology::Coordinator {
+                coordinator,
+                workers,
+            } => std::iter::once(coordinator).chain(workers).collect(),
+            ForestTopology::Supervisor {
+                supervisor,
+                workers,
+                ..
+            } => std::iter::once(supervisor).chain(workers).collect(),
+            ForestTopology::Pipeline(agents) | ForestTopology::RoundRobin { agents, .. } => {
+                agents.iter().collect()
+            }
+        }
+    }
+}
+
+/// The saved state of a collaborative task, written while it runs so that an
+/// interrupted run can continue with [`ForestOfAgents::resume`].
+///
+/// Agents themselves are not saved: resume on a forest built with the same agent
+/// IDs. Agents' own chat histories start fresh; each task prompt carries the
+/// results of completed tasks from the shared context.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForestCheckpoint {
+    /// The checkpoint format version.
+    pub version: u32,
+    /// The coordinator of the task.
+    pub initiator: AgentId,
+    /// The task being worked on.
+    pub task_description: String,
+    /// The agents available for the plan's tasks.
+    pub involved_agents: Vec<AgentId>,
+    /// The shared data, message history and task plan.
+    pub context: SharedContext,
+    /// Messages sent but not yet delivered.
+    pub pending_messages: Vec<ForestMessage>,
+    /// The coordinator's final answer, once the task is done.
+    pub final_result: Option<String>,
+    /// When the checkpoint was written.
+    pub saved_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ForestCheckpoint {
+    /// The format version written by this version of the crate.
+    pub const VERSION: u32 = 1;
+
+    /// Reads a checkpoint from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            HeliosError::ConfigError(format!("Failed to read '{}': {}", path.display(), e))
+        })?;
+        let checkpoint: Self = serde_json::from_str(&content)?;
+        if checkpoint.version > Self::VERSION {
+            return Err(HeliosError::ConfigError(format!(
+                "Checkpoint '{}' has version {}, but this version of helios-engine reads up to {}",
+                path.display(),
+                checkpoint.version,
+                Self::VERSION
+            )));
+        }
+        Ok(checkpoint)
+    }
+
+    /// Writes the checkpoint as JSON.
+    ///
+    /// The file is replaced atomically, so a crash while saving leaves the previous
+    /// checkpoint intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let temp = path.with_file_name(format!(
+            "{}.tmp.{}",
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            crate::clock::new_id().simple()
+        ));
+        std::fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&temp, path).map_err(|e| {
+            let _ = std::fs::remove_file(&temp);
+            HeliosError::ConfigError(format!(
+                "Failed to write checkpoint {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+}
+
+/// How a [debate](ForestOfAgents::debate) picks its final answer.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum DebateVerdict {
+    /// Every debater votes for the best final answer other than its own; the
+    /// answer with the most votes wins, ties going to the earlier debater.
+    #[default]
+    MajorityVote,
+    /// This agent reads the final answers and synthesizes the answer. It doesn't
+    /// take part in the debate.
+    Judge(AgentId),
+}
+
+/// One contribution to a debate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebateTurn {
+    /// `0` for the independent answers, then the critique rounds.
+    pub round: usize,
+    /// The agent that spoke.
+    pub agent: AgentId,
+    /// What it said.
+    pub content: String,
+}
+
+/// The result of a [debate](ForestOfAgents::debate).
+#[derive(Debug, Clone)]
+pub struct DebateOutcome {
+    /// The final answer.
+    pub answer: String,
+    /// The debater whose answer won the vote; `None` when a judge decided.
+    pub winner: Option<AgentId>,
+    /// Votes received per debater; empty when a judge decided.
+    pub votes: HashMap<AgentId, usize>,
+    /// Every answer and critique, in order.
+    pub transcript: Vec<DebateTurn>,
+}
+
 /// The main Forest of Agents structure that manages multiple agents.
 pub struct ForestOfAgents {
     /// The agents in the forest, keyed by their IDs.
@@ -285,6 +931,34 @@ pub struct ForestOfAgents {
     message_queue: Arc<RwLock<Vec<ForestMessage>>>,
     /// Maximum number of iterations for agent interactions.
     max_iterations: usize,
+    /// Stops every agent in the forest when tripped.
+    abort: AbortHandle,
+    /// Attached to every agent, current and future.
+    observers: Vec<Arc<dyn AgentObserver>>,
+    /// Used by `run`, if set.
+    topology: Option<ForestTopology>,
+    /// How many agents may work on plan tasks at the same time.
+    max_concurrent_tasks: usize,
+    /// How failed plan tasks are retried.
+    retry_policy: TaskRetryPolicy,
+    /// Whether the coordinator reviews the plan after every batch of tasks.
+    dynamic_replanning: bool,
+    /// How debates pick their answer.
+    debate_verdict: DebateVerdict,
+    /// Where collaborative tasks save their progress, if anywhere.
+    checkpoint_path: Option<PathBuf>,
+}
+
+/// Reports an agent's events under its ID in the forest rather than its name.
+struct ForestObserver {
+    id: AgentId,
+    inner: Arc<dyn AgentObserver>,
+}
+
+impl AgentObserver for ForestObserver {
+    fn on_event(&self, _agent: &str, event: &AgentEvent) {
+        self.inner.on_event(&self.id, event);
+    }
 }
 
 impl ForestOfAgents {
@@ -295,6 +969,14 @@ impl ForestOfAgents {
             shared_context: Arc::new(RwLock::new(SharedContext::new())),
             message_queue: Arc::new(RwLock::new(Vec::new())),
             max_iterations: 10,
+            abort: AbortHandle::new(),
+            observers: Vec::new(),
+            topology: None,
+            max_concurrent_tasks: 4,
+            retry_policy: TaskRetryPolicy::default(),
+            dynamic_replanning: false,
+            debate_verdict: DebateVerdict::default(),
+            checkpoint_path: None,
         }
     }
 
@@ -357,10 +1039,45 @@ impl ForestOfAgents {
         ));
         agent.register_tool(create_plan_tool);
 
+        let revise_plan_tool = Box::new(RevisePlanTool::new(
+            id.clone(),
+            Arc::clone(&self.shared_context),
+        ));
+        agent.register_tool(revise_plan_tool);
+
+        for observer in &self.observers {
+            agent.add_observer(Arc::new(ForestObserver {
+                id: id.clone(),
+                inner: Arc::clone(observer),
+            }));
+        }
+
+        agent.abort_handle().link(&self.abort);
         self.agents.insert(id, agent);
         Ok(())
     }
 
+    /// Registers an observer for the events of every agent in the forest.
+    ///
+    /// Events are reported under the agent's ID in the forest. Agents added later
+    /// are observed too.
+    pub fn add_observer(&mut self, observer: Arc<dyn AgentObserver>) {
+        for (id, agent) in &mut self.agents {
+            agent.add_observer(Arc::new(ForestObserver {
+                id: id.clone(),
+                inner: Arc::clone(&observer),
+            }));
+        }
+        self.observers.push(observer);
+    }
+
+    /// Returns a stream of `(agent ID, event)` pairs for every agent in the forest.
+    pub fn subscribe(&mut self) -> impl futures::Stream<Item = (AgentId, AgentEvent)> {
+        let (observer, receiver) = ChannelObserver::new();
+        self.add_observer(Arc::new(observer));
+        tokio_stream::wrappers::UnboundedReceiverStream::new(receiver)
+    }
+
     /// Removes an agent from the forest.
     ///
     /// # Arguments
@@ -477,6 +1194,8 @@ impl ForestOfAgents {
         task_description: String,
         involved_agents: Vec<AgentId>,
     ) -> Result<String> {
+        self.abort.check()?;
+
         // Verify all involved agents exist
         for agent_id in &involved_agents {
             if !self.agents.contains_key(agent_id) {
@@ -545,11 +1264,128 @@ impl ForestOfAgents {
             return Ok(_planning_result);
         }
 
+        self.execute_plan(initiator, task_description, involved_agents)
+            .await
+    }
+
+    /// Continues a collaborative task from a checkpoint written by an earlier run.
+    ///
+    /// Completed tasks keep their results and aren't run again; tasks that were
+    /// running when the checkpoint was written start over. If the checkpoint holds
+    /// a finished task, its final answer is returned without calling any agent.
+    /// Progress keeps being saved to the checkpoint's path.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, ForestBuilder};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// # let config = Config::new_default();
+    /// let mut forest = ForestBuilder::new()
+    ///     .config(config)
+    ///     .agent("coordinator".to_string(), Agent::builder("coordinator"))
+    ///     .agent("researcher".to_string(), Agent::builder("researcher"))
+    ///     .checkpoint_path("runs/report.json")
+    ///     .build()
+    ///     .await?;
+    ///
+    /// let result = if std::path::Path::new("runs/report.json").exists() {
+    ///     forest.resume("runs/report.json").await?
+    /// } else {
+    ///     forest
+    ///         .execute_collaborative_task(
+    ///             &"coordinator".to_string(),
+    ///             "Write a market report".to_string(),
+    ///             vec!["researcher".to_string()],
+    ///         )
+    ///         .await?
+    /// };
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn resume(&mut self, path: impl AsRef<Path>) -> Result<String> {
+        let path = path.as_ref();
+        let mut checkpoint = ForestCheckpoint::load(path)?;
+        if let Some(result) = checkpoint.final_result {
+            return Ok(result);
+        }
+        for id in std::iter::once(&checkpoint.initiator).chain(&checkpoint.involved_agents) {
+            if !self.agents.contains_key(id) {
+                return Err(HeliosError::AgentError(format!(
+                    "Checkpoint needs agent '{}', which is not in the forest",
+                    id
+                )));
+            }
+        }
+        self.checkpoint_path = Some(path.to_path_buf());
+
+        let Some(plan) = checkpoint.context.get_plan_mut() else {
+            // Interrupted while planning: start over.
+            return self
+                .execute_collaborative_task(
+                    &checkpoint.initiator,
+                    checkpoint.task_description,
+                    checkpoint.involved_agents,
+                )
+                .await;
+        };
+        for task in plan.tasks.values_mut() {
+            if task.status == TaskStatus::InProgress {
+                task.status = TaskStatus::Pending;
+            }
+        }
+        *self.shared_context.write().await = checkpoint.context;
+        *self.message_queue.write().await = checkpoint.pending_messages;
+
+        self.execute_plan(
+            &checkpoint.initiator,
+            checkpoint.task_description,
+            checkpoint.involved_agents,
+        )
+        .await
+    }
+
+    /// Saves the progress of a collaborative task, if a checkpoint path is set.
+    async fn save_checkpoint(
+        &self,
+        initiator: &AgentId,
+        task_description: &str,
+        involved_agents: &[AgentId],
+        final_result: Option<&str>,
+    ) -> Result<()> {
+        let Some(path) = &self.checkpoint_path else {
+            return Ok(());
+        };
+        ForestCheckpoint {
+            version: ForestCheckpoint::VERSION,
+            initiator: initiator.clone(),
+            task_description: task_description.to_string(),
+            involved_agents: involved_agents.to_vec(),
+            context: self.shared_context.read().await.clone(),
+            pending_messages: self.message_queue.read().await.clone(),
+            final_result: final_result.map(str::to_string),
+            saved_at: crate::clock::now(),
+        }
+        .save(path)
+    }
+
+    /// Runs the tasks of the current plan and has the coordinator synthesize the
+    /// results.
+    async fn execute_plan(
+        &mut self,
+        initiator: &AgentId,
+        task_description: String,
+        involved_agents: Vec<AgentId>,
+    ) -> Result<String> {
+        self.save_checkpoint(initiator, &task_description, &involved_agents, None)
+            .await?;
+
         // Phase 2: Execute tasks according to the plan
         let mut iteration = 0;
         let max_task_iterations = self.max_iterations * 3; // Allow more iterations for complex plans
 
         while iteration < max_task_iterations {
+            self.abort.check()?;
             // Get next ready tasks
             let ready_tasks: Vec<(String, String, AgentId)> = {
                 let context = self.shared_context.read().await;
@@ -599,83 +1435,135 @@ impl ForestOfAgents {
                 continue;
             }
 
-            // Execute ready tasks
-            for (task_id, task_desc, agent_id) in ready_tasks {
-                // Mark task as in progress
-                {
-                    let mut context = self.shared_context.write().await;
-                    if let Some(plan) = context.get_plan_mut() {
-                        if let Some(task) = plan.get_task_mut(&task_id) {
+            // Mark the batch as in progress
+            {
+                let mut context = self.shared_context.write().await;
+                if let Some(plan) = context.get_plan_mut() {
+                    for (task_id, _, _) in &ready_tasks {
+                        if let Some(task) = plan.get_task_mut(task_id) {
                             task.status = TaskStatus::InProgress;
                         }
                     }
                 }
+            }
 
-                // Get shared memory context for the agent
-                let shared_memory_info = {
-                    let context = self.shared_context.read().await;
-                    let mut info = String::from("\n=== SHARED TASK MEMORY ===\n");
-
-                    if let Some(plan) = context.get_plan() {
-                        info.push_str(&format!("Overall Objective: {}\n", plan.objective));
-                        info.push_str(&format!(
-                            "Progress: {}/{} tasks completed\n\n",
-                            plan.get_progress().0,
-                            plan.get_progress().1
-                        ));
+            // Get shared memory context for the agents
+            let shared_memory_info = {
+                let context = self.shared_context.read().await;
+                let mut info = String::from("\n=== SHARED TASK MEMORY ===\n");
 
-                        info.push_str("Completed Tasks:\n");
-                        for task in plan.tasks_in_order() {
-                            if task.status == TaskStatus::Completed {
-                                info.push_str(&format!(
-                                    "  ✓ [{}] {}: {}\n",
-                                    task.assigned_to,
-                                    task.description,
-                                    task.result.as_ref().unwrap_or(&"No result".to_string())
-                                ));
-                            }
+                if let Some(plan) = context.get_plan() {
+                    info.push_str(&format!("Overall Objective: {}\n", plan.objective));
+                    info.push_str(&format!(
+                        "Progress: {}/{} tasks completed\n\n",
+                        plan.get_progress().0,
+                        plan.get_progress().1
+                    ));
+
+                    info.push_str("Completed Tasks:\n");
+                    for task in plan.tasks_in_order() {
+                        if task.status == TaskStatus::Completed {
+                            info.push_str(&format!(
+                                "  ✓ [{}] {}: {}\n",
+                                task.assigned_to,
+                                task.description,
+                                task.result.as_ref().unwrap_or(&"No result".to_string())
+                            ));
                         }
                     }
+                }
 
-                    info.push_str("\nShared Data:\n");
-                    for (key, value) in &context.data {
-                        if !key.starts_with("current_task")
-                            && !key.starts_with("involved_agents")
-                            && !key.starts_with("task_status")
-                        {
-                            info.push_str(&format!("  • {}: {}\n", key, value));
-                        }
+                info.push_str("\nShared Data:\n");
+                for (key, value) in &context.data {
+                    if !key.starts_with("current_task")
+                        && !key.starts_with("involved_agents")
+                        && !key.starts_with("task_status")
+                    {
+                        info.push_str(&format!("  • {}: {}\n", key, value));
                     }
-                    info.push_str("=========================\n\n");
-                    info
-                };
-
-                // Execute the task
-                if let Some(agent) = self.agents.get_mut(&agent_id) {
-                    let task_prompt = format!(
-                        "{}Your assigned task: {}\n\n\
-                        Complete this task and use the 'update_task_memory' tool to save your results to the shared memory. \
-                        The task_id is '{}'. Include key findings and data that other agents might need.\n\n\
-                        Provide a complete response with your results.",
-                        shared_memory_info, task_desc, task_id
-                    );
-
-                    let result = agent.chat(task_prompt).await?;
+                }
+                info.push_str("=========================\n\n");
+                info
+            };
 
-                    // If agent didn't update memory, do it automatically
-                    {
-                        let mut context = self.shared_context.write().await;
-                        if let Some(plan) = context.get_plan_mut() {
-                            if let Some(task) = plan.get_task_mut(&task_id) {
-                                if task.status == TaskStatus::InProgress {
-                                    task.status = TaskStatus::Completed;
-                                    task.result = Some(result.clone());
-                                }
-                            }
+            // Ready tasks don't depend on each other, so different agents can work
+            // on them at the same time. Tasks for the same agent run in order.
+            let batch_ids: Vec<String> = ready_tasks.iter().map(|(id, _, _)| id.clone()).collect();
+            let mut batches: Vec<(AgentId, Vec<(String, String)>)> = Vec::new();
+            for (task_id, task_desc, agent_id) in ready_tasks {
+                match batches.iter_mut().find(|(id, _)| *id == agent_id) {
+                    Some((_, tasks)) => tasks.push((task_id, task_desc)),
+                    None => batches.push((agent_id, vec![(task_id, task_desc)])),
+                }
+            }
+            let mut lease = AgentLease::new(&mut self.agents);
+            let mut batch_tasks = Vec::new();
+            for (agent_id, tasks) in batches {
+                if lease.take(agent_id) {
+                    batch_tasks.push(tasks);
+                }
+            }
+            let runs: Vec<_> = lease
+                .taken
+                .iter_mut()
+                .zip(batch_tasks)
+                .map(|((agent_id, agent), tasks)| {
+                    run_agent_tasks(agent_id.clone(), agent, tasks, shared_memory_info.clone())
+                })
+                .collect();
+            let finished: Vec<_> = futures::stream::iter(runs)
+                .buffer_unordered(self.max_concurrent_tasks.max(1))
+                .collect()
+                .await;
+            // Put every agent back before reporting any error.
+            drop(lease);
+
+            let mut aborted = None;
+            let mut failures = Vec::new();
+            for (_, results) in finished {
+                for (task_id, result, started, finished) in results {
+                    let mut context = self.shared_context.write().await;
+                    let Some(task) = context
+                        .get_plan_mut()
+                        .and_then(|plan| plan.get_task_mut(&task_id))
+                    else {
+                        continue;
+                    };
+                    task.metadata
+                        .insert("started_at".to_string(), started.to_rfc3339());
+                    task.metadata
+                        .insert("finished_at".to_string(), finished.to_rfc3339());
+                    if task.status != TaskStatus::InProgress {
+                        // The agent saved its own result with update_task_memory.
+                        continue;
+                    }
+                    match result {
+                        Ok(answer) if self.retry_policy.accepts(task, &answer) => {
+                            // If agent didn't update memory, do it automatically
+                            task.status = TaskStatus::Completed;
+                            task.result = Some(answer);
+                        }
+                        Ok(_) => failures.push((task_id, "the answer was unusable".to_string())),
+                        Err(e @ HeliosError::Aborted(_)) => {
+                            aborted.get_or_insert(e);
                         }
+                        Err(e) => failures.push((task_id, e.to_string())),
                     }
                 }
             }
+            if let Some(e) = aborted {
+                return Err(e);
+            }
+            for (task_id, reason) in failures {
+                self.handle_task_failure(initiator, &involved_agents, &task_id, reason)
+                    .await?;
+            }
+            if self.dynamic_replanning {
+                self.review_plan(initiator, &involved_agents, &batch_ids)
+                    .await?;
+            }
+            self.save_checkpoint(initiator, &task_description, &involved_agents, None)
+                .await?;
 
             iteration += 1;
         }
@@ -688,11 +1576,18 @@ impl ForestOfAgents {
 
             if let Some(plan) = context.get_plan() {
                 summary.push_str(&format!("Objective: {}\n", plan.objective));
-                summary.push_str(&format!(
-                    "Status: All tasks completed ({}/{} tasks)\n\n",
-                    plan.get_progress().0,
-                    plan.get_progress().1
-                ));
+                let (completed, total) = plan.get_progress();
+                if completed == total {
+                    summary.push_str(&format!(
+                        "Status: All tasks completed ({}/{} tasks)\n\n",
+                        completed, total
+                    ));
+                } else {
+                    summary.push_str(&format!(
+                        "Status: {}/{} tasks completed; the others failed\n\n",
+                        completed, total
+                    ));
+                }
 
                 summary.push_str("Task Results:\n");
                 for task in plan.tasks_in_order() {
@@ -724,23 +1619,681 @@ impl ForestOfAgents {
                 Value::String("completed".to_string()),
             );
         }
+        self.save_checkpoint(
+            initiator,
+            &task_description,
+            &involved_agents,
+            Some(&final_result),
+        )
+        .await?;
 
         Ok(final_result)
     }
 
-    /// Processes pending messages and triggers responses from agents.
-    ///
-    /// This method iterates through pending messages, delivers them to recipient agents,
-    /// and triggers their responses. It continues until no more messages are generated
-    /// or max_iterations is reached.
-    #[allow(dead_code)]
-    async fn process_messages_and_trigger_responses(
+    /// Applies the retry policy to a plan task that errored or gave an unusable
+    /// answer.
+    async fn handle_task_failure(
         &mut self,
+        initiator: &AgentId,
         involved_agents: &[AgentId],
+        task_id: &str,
+        reason: String,
     ) -> Result<()> {
-        let mut iteration = 0;
-
+        let policy = self.retry_policy.clone();
+        {
+            let mut context = self.shared_context.write().await;
+            let Some(task) = context
+                .get_plan_mut()
+                .and_then(|plan| plan.get_task_mut(task_id))
+            else {
+                return Ok(());
+            };
+            let attempts = task
+                .metadata
+                .get("attempts")
+                .and_then(|attempts| attempts.parse::<usize>().ok())
+                .unwrap_or(0)
+                + 1;
+            task.metadata
+                .insert("attempts".to_string(), attempts.to_string());
+            task.metadata
+                .insert("last_error".to_string(), reason.clone());
+
+            if attempts <= policy.max_retries {
+                task.status = TaskStatus::Pending;
+                return Ok(());
+            }
+            if policy.reassign {
+                let mut tried: Vec<AgentId> = task
+                    .metadata
+                    .get("tried_agents")
+                    .map(|tried| tried.split(',').map(str::to_string).collect())
+                    .unwrap_or_default();
+                tried.push(task.assigned_to.clone());
+                let next = involved_agents
+                    .iter()
+                    .find(|id| !tried.contains(id) && self.agents.contains_key(*id));
+                if let Some(next) = next {
+                    task.metadata
+                        .insert("tried_agents".to_string(), tried.join(","));
+                    task.metadata
+                        .insert("attempts".to_string(), "0".to_string());
+                    task.assigned_to = next.clone();
+                    task.status = TaskStatus::Pending;
+                    return Ok(());
+                }
+            }
+            task.status = TaskStatus::Failed;
+            task.result = Some(format!("Failed: {}", reason));
+        }
+
+        match policy.on_failure {
+            TaskFailurePolicy::Abort => Err(HeliosError::AgentError(format!(
+                "Task '{}' failed: {}",
+                task_id, reason
+            ))),
+            TaskFailurePolicy::Skip => {
+                self.skip_dependents(task_id).await;
+                Ok(())
+            }
+            TaskFailurePolicy::Replan => {
+                self.replan(
+                    initiator,
+                    involved_agents,
+                    task_id,
+                    &reason,
+                    policy.max_replans,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Shows the coordinator the results of the last batch and lets it revise the
+    /// rest of the plan.
+    async fn review_plan(
+        &mut self,
+        initiator: &AgentId,
+        involved_agents: &[AgentId],
+        batch_ids: &[String],
+    ) -> Result<()> {
+        let prompt = {
+            let context = self.shared_context.read().await;
+            let Some(plan) = context.get_plan() else {
+                return Ok(());
+            };
+            let mut finished = String::new();
+            for id in batch_ids {
+                if let Some(task) = plan.get_task(id) {
+                    if task.status == TaskStatus::Completed {
+                        finished.push_str(&format!(
+                            "- {} [{}] {}: {}\n",
+                            task.id,
+                            task.assigned_to,
+                            task.description,
+                            task.result.as_deref().unwrap_or("No result")
+                        ));
+                    }
+                }
+            }
+            if finished.is_empty() {
+                return Ok(());
+            }
+            let mut remaining = String::new();
+            for task in plan.tasks_in_order() {
+                if task.status != TaskStatus::Completed {
+                    remaining.push_str(&format!(
+                        "- {} [{}] ({}) {}; depends on: {}\n",
+                        task.id,
+                        task.assigned_to,
+                        task.status.as_str(),
+                        task.description,
+                        if task.dependencies.is_empty() {
+                            "nothing".to_string()
+                        } else {
+                            task.dependencies.join(", ")
+                        }
+                    ));
+                }
+            }
+            format!(
+                "Progress on the plan for: {}\n\n\
+                Just completed:\n{}\n\
+                Remaining tasks:\n{}\n\
+                Team members: {}\n\n\
+                If these results change what still needs to be done, use the revise_plan tool \
+                to add, remove, update or reorder tasks. Otherwise reply 'no changes'.",
+                plan.objective,
+                finished,
+                if remaining.is_empty() {
+                    "(none)\n"
+                } else {
+                    &remaining
+                },
+                involved_agents.join(", ")
+            )
+        };
+
+        let coordinator = self.agents.get_mut(initiator).ok_or_else(|| {
+            HeliosError::AgentError(format!("Initiator agent '{}' not found", initiator))
+        })?;
+        coordinator.chat(prompt).await?;
+        Ok(())
+    }
+
+    async fn skip_dependents(&self, task_id: &str) {
+        if let Some(plan) = self.shared_context.write().await.get_plan_mut() {
+            plan.fail_dependents(task_id);
+        }
+    }
+
+    /// Asks the coordinator for a new plan for the work left after `task_id`
+    /// failed. Completed tasks are carried over into the new plan.
+    async fn replan(
+        &mut self,
+        initiator: &AgentId,
+        involved_agents: &[AgentId],
+        task_id: &str,
+        reason: &str,
+        max_replans: usize,
+    ) -> Result<()> {
+        let old_plan = {
+            let mut context = self.shared_context.write().await;
+            let replans = context
+                .metadata
+                .get("replans")
+                .and_then(|replans| replans.parse::<usize>().ok())
+                .unwrap_or(0);
+            if replans >= max_replans {
+                None
+            } else {
+                context
+                    .metadata
+                    .insert("replans".to_string(), (replans + 1).to_string());
+                context.current_plan.take()
+            }
+        };
+        let Some(old_plan) = old_plan else {
+            self.skip_dependents(task_id).await;
+            return Ok(());
+        };
+
+        let mut completed = String::new();
+        for task in old_plan.tasks_in_order() {
+            if task.status == TaskStatus::Completed {
+                completed.push_str(&format!(
+                    "- {} [{}] {}: {}\n",
+                    task.id,
+                    task.assigned_to,
+                    task.description,
+                    task.result.as_deref().unwrap_or("No result")
+                ));
+            }
+        }
+        let failed = old_plan
+            .get_task(task_id)
+            .map(|task| format!("{} [{}] {}", task.id, task.assigned_to, task.description))
+            .unwrap_or_else(|| task_id.to_string());
+        let prompt = format!(
+            "A task in your plan failed and the plan needs to change.\n\n\
+            Objective: {}\n\
+            Failed task: {}\n\
+            Reason: {}\n\n\
+            Completed tasks (their IDs can be used as dependencies):\n{}\n\
+            Available team members: {}\n\n\
+            Use the create_plan tool to create a new plan for the remaining work. \
+            Do not repeat completed tasks.",
+            old_plan.objective,
+            failed,
+            reason,
+            if completed.is_empty() {
+                "(none)\n"
+            } else {
+                &completed
+            },
+            involved_agents.join(", ")
+        );
+
+        let coordinator = self.agents.get_mut(initiator).ok_or_else(|| {
+            HeliosError::AgentError(format!("Initiator agent '{}' not found", initiator))
+        })?;
+        let outcome = coordinator.chat(prompt).await;
+
+        let mut context = self.shared_context.write().await;
+        match context.current_plan.take() {
+            Some(mut new_plan) if outcome.is_ok() => {
+                for id in old_plan.task_order.iter().rev() {
+                    let Some(task) = old_plan.tasks.get(id) else {
+                        continue;
+                    };
+                    if task.status == TaskStatus::Completed && !new_plan.tasks.contains_key(id) {
+                        new_plan.task_order.insert(0, id.clone());
+                        new_plan.tasks.insert(id.clone(), task.clone());
+                    }
+                }
+                context.set_plan(new_plan);
+            }
+            _ => {
+                let mut old_plan = old_plan;
+                old_plan.fail_dependents(task_id);
+                context.set_plan(old_plan);
+            }
+        }
+        outcome.map(|_| ())
+    }
+
+    /// Sets how many agents may work on plan tasks at the same time.
+    ///
+    /// Tasks whose dependencies are met run concurrently when they are assigned to
+    /// different agents; `1` runs them one at a time. The default is 4.
+    pub fn set_max_concurrent_tasks(&mut self, max: usize) {
+        self.max_concurrent_tasks = max.max(1);
+    }
+
+    /// Sets whether the coordinator reviews the plan after every batch of tasks.
+    ///
+    /// When enabled, the coordinator sees each batch's results and can change the
+    /// remaining work with the `revise_plan` tool; the changes take effect on the
+    /// next scheduling pass. This costs one extra LLM call per batch.
+    pub fn set_dynamic_replanning(&mut self, enabled: bool) {
+        self.dynamic_replanning = enabled;
+    }
+
+    /// Sets how plan tasks that fail are retried, reassigned or given up on.
+    pub fn set_retry_policy(&mut self, policy: TaskRetryPolicy) {
+        self.retry_policy = policy;
+    }
+
+    /// Sets how the agents work together in [`ForestOfAgents::run`].
+    pub fn set_topology(&mut self, topology: ForestTopology) {
+        self.topology = Some(topology);
+    }
+
+    /// Returns the topology used by [`ForestOfAgents::run`], if one is set.
+    pub fn topology(&self) -> Option<&ForestTopology> {
+        self.topology.as_ref()
+    }
+
+    /// Runs a task with the forest's topology and returns the final answer.
+    ///
+    /// Every agent's contribution is also recorded in the shared context's
+    /// message history.
+    pub async fn run(&mut self, task: impl Into<String>) -> Result<String> {
+        let task = task.into();
+        let topology = self.topology.clone().ok_or_else(|| {
+            HeliosError::AgentError(
+                "The forest has no topology; set one with ForestBuilder::topology".to_string(),
+            )
+        })?;
+        if topology.agents().is_empty() {
+            return Err(HeliosError::AgentError(
+                "The forest topology has no agents".to_string(),
+            ));
+        }
+        for id in topology.agents() {
+            if !self.agents.contains_key(id) {
+                return Err(HeliosError::AgentError(format!("Agent '{}' not found", id)));
+            }
+        }
+
+        match topology {
+            ForestTopology::Coordinator {
+                coordinator,
+                workers,
+            } => {
+                self.execute_collaborative_task(&coordinator, task, workers)
+                    .await
+            }
+            ForestTopology::Supervisor {
+                supervisor,
+                workers,
+                max_revisions,
+            } => {
+                self.run_supervised(&task, &supervisor, &workers, max_revisions)
+                    .await
+            }
+            ForestTopology::Pipeline(agents) => self.run_pipeline(&task, &agents).await,
+            ForestTopology::RoundRobin { agents, rounds } => {
+                self.run_round_robin(&task, &agents, rounds).await
+            }
+        }
+    }
+
+    /// Sends `prompt` to an agent and records its reply in the shared history.
+    async fn contribute(&mut self, id: &AgentId, prompt: String) -> Result<String> {
+        self.abort.check()?;
+        let agent = self
+            .agents
+            .get_mut(id)
+            .ok_or_else(|| HeliosError::AgentError(format!("Agent '{}' not found", id)))?;
+        let reply = agent.chat(prompt).await?;
+        self.shared_context
+            .write()
+            .await
+            .add_message(ForestMessage::broadcast(id.clone(), reply.clone()));
+        Ok(reply)
+    }
+
+    async fn run_supervised(
+        &mut self,
+        task: &str,
+        supervisor: &AgentId,
+        workers: &[AgentId],
+        max_revisions: usize,
+    ) -> Result<String> {
+        let mut feedback: Option<String> = None;
+        for revision in 0..=max_revisions {
+            let mut work = String::new();
+            for worker in workers {
+                let prompt = match &feedback {
+                    None => format!("Your task: {}\n\nProvide your complete work.", task),
+                    Some(feedback) => format!(
+                        "Your supervisor sent the work back with this feedback:\n{}\n\n\
+                        Revise your work on the task: {}",
+                        feedback, task
+                    ),
+                };
+                let output = self.contribute(worker, prompt).await?;
+                work.push_str(&format!("[{}]\n{}\n\n", worker, output));
+            }
+
+            let last_chance = revision == max_revisions;
+            let instructions = if last_chance {
+                "This is the final review. Reply with `APPROVED:` followed by the best \
+                final answer you can give from this work."
+            } else {
+                "If the work fully answers the task, reply with `APPROVED:` followed by the \
+                final answer. Otherwise reply with `REVISE:` followed by specific feedback \
+                for the workers."
+            };
+            let review = self
+                .contribute(
+                    supervisor,
+                    format!(
+                        "Task: {}\n\nWork submitted by your team:\n\n{}{}",
+                        task, work, instructions
+                    ),
+                )
+                .await?;
+
+            match strip_verdict(&review, "APPROVED") {
+                Some(answer) => return Ok(answer),
+                None if last_chance => return Ok(review),
+                None => {
+                    feedback = Some(strip_verdict(&review, "REVISE").unwrap_or(review));
+                }
+            }
+        }
+        unreachable!("the last review always returns")
+    }
+
+    async fn run_pipeline(&mut self, task: &str, agents: &[AgentId]) -> Result<String> {
+        let mut output: Option<(AgentId, String)> = None;
+        for id in agents {
+            let prompt = match &output {
+                None => task.to_string(),
+                Some((previous, text)) => format!(
+                    "Overall task: {}\n\nOutput from the previous step ({}):\n{}\n\n\
+                    Do your part of the task using this output.",
+                    task, previous, text
+                ),
+            };
+            let text = self.contribute(id, prompt).await?;
+            output = Some((id.clone(), text));
+        }
+        Ok(output.map(|(_, text)| text).unwrap_or_default())
+    }
+
+    async fn run_round_robin(
+        &mut self,
+        task: &str,
+        agents: &[AgentId],
+        rounds: usize,
+    ) -> Result<String> {
+        let mut transcript: Vec<(AgentId, String)> = Vec::new();
+        // How much of the transcript each agent has already been shown.
+        let mut seen: HashMap<AgentId, usize> = HashMap::new();
+        for round in 1..=rounds {
+            for id in agents {
+                let start = seen.get(id).copied().unwrap_or(0);
+                let mut prompt = if start == 0 {
+                    format!(
+                        "You are taking part in a discussion with {}.\nTopic: {}\n\n",
+                        agents.join(", "),
+                        task
+                    )
+                } else {
+                    String::new()
+                };
+                if start < transcript.len() {
+                    prompt.push_str("New contributions:\n");
+                    for (speaker, text) in &transcript[start..] {
+                        prompt.push_str(&format!("[{}]: {}\n", speaker, text));
+                    }
+                    prompt.push('\n');
+                }
+                prompt.push_str(&format!(
+                    "Round {} of {}. Add your contribution; build on or challenge what \
+                    others said.",
+                    round, rounds
+                ));
+
+                let reply = self.contribute(id, prompt).await?;
+                transcript.push((id.clone(), reply));
+                seen.insert(id.clone(), transcript.len());
+            }
+        }
+        Ok(transcript.pop().map(|(_, text)| text).unwrap_or_default())
+    }
+
+    /// Saves the progress of collaborative tasks to `path` after planning and
+    /// after every batch of tasks, so an interrupted run can be continued with
+    /// [`ForestOfAgents::resume`]. `None` stops saving.
+    pub fn set_checkpoint_path(&mut self, path: Option<PathBuf>) {
+        self.checkpoint_path = path;
+    }
+
+    /// Sets how [`ForestOfAgents::debate`] picks its final answer.
+    pub fn set_debate_verdict(&mut self, verdict: DebateVerdict) {
+        self.debate_verdict = verdict;
+    }
+
+    /// Has the agents debate a question and settle on one answer.
+    ///
+    /// Every agent except the judge, if there is one, first answers on its own.
+    /// Then, for each of `rounds` rounds, every debater reads the others' latest
+    /// answers, critiques them and gives a revised answer. Finally the judge or a
+    /// majority vote decides; see [`DebateVerdict`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, DebateVerdict, ForestBuilder};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// # let config = Config::new_default();
+    /// let mut forest = ForestBuilder::new()
+    ///     .config(config)
+    ///     .agent("optimist".to_string(), Agent::builder("optimist"))
+    ///     .agent("skeptic".to_string(), Agent::builder("skeptic"))
+    ///     .agent("judge".to_string(), Agent::builder("judge"))
+    ///     .debate_verdict(DebateVerdict::Judge("judge".to_string()))
+    ///     .build()
+    ///     .await?;
+    /// let outcome = forest.debate("Is this drug interaction dangerous?", 2).await?;
+    /// println!("{}", outcome.answer);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn debate(
+        &mut self,
+        question: impl Into<String>,
+        rounds: usize,
+    ) -> Result<DebateOutcome> {
+        let question = question.into();
+        let verdict = self.debate_verdict.clone();
+        let judge = match &verdict {
+            DebateVerdict::Judge(judge) => {
+                if !self.agents.contains_key(judge) {
+                    return Err(HeliosError::AgentError(format!(
+                        "Judge agent '{}' not found",
+                        judge
+                    )));
+                }
+                Some(judge.clone())
+            }
+            DebateVerdict::MajorityVote => None,
+        };
+        let mut debaters: Vec<AgentId> = self
+            .agents
+            .keys()
+            .filter(|id| Some(*id) != judge.as_ref())
+            .cloned()
+            .collect();
+        debaters.sort();
+        if debaters.len() < 2 {
+            return Err(HeliosError::AgentError(
+                "A debate needs at least two agents besides the judge".to_string(),
+            ));
+        }
+
+        let mut transcript = Vec::new();
+        let mut latest: Vec<(AgentId, String)> = Vec::new();
+        for id in &debaters {
+            let answer = self
+                .contribute(
+                    id,
+                    format!(
+                        "Answer this question on your own, with your reasoning:\n{}",
+                        question
+                    ),
+                )
+                .await?;
+            latest.push((id.clone(), answer));
+        }
+        transcript.extend(latest.iter().map(|(agent, content)| DebateTurn {
+            round: 0,
+            agent: agent.clone(),
+            content: content.clone(),
+        }));
+
+        for round in 1..=rounds {
+            let mut revised = Vec::new();
+            for id in &debaters {
+                let others = latest
+                    .iter()
+                    .filter(|(other, _)| other != id)
+                    .map(|(other, answer)| format!("[{}]\n{}", other, answer))
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                let answer = self
+                    .contribute(
+                        id,
+                        format!(
+                            "Debate round {} of {} on: {}\n\n\
+                            The other participants answered:\n\n{}\n\n\
+                            Point out any errors or unsupported claims in their answers, then \
+                            give your revised answer. Change your position only if their \
+                            arguments are convincing.",
+                            round, rounds, question, others
+                        ),
+                    )
+                    .await?;
+                revised.push((id.clone(), answer));
+            }
+            transcript.extend(revised.iter().map(|(agent, content)| DebateTurn {
+                round,
+                agent: agent.clone(),
+                content: content.clone(),
+            }));
+            latest = revised;
+        }
+
+        let answers = latest
+            .iter()
+            .map(|(id, answer)| format!("[{}]\n{}", id, answer))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        if let Some(judge) = judge {
+            let answer = self
+                .contribute(
+                    &judge,
+                    format!(
+                        "You are judging a debate on: {}\n\n\
+                        The participants' final answers:\n\n{}\n\n\
+                        Weigh their arguments, discard claims that don't hold up, and give \
+                        the best final answer to the question.",
+                        question, answers
+                    ),
+                )
+                .await?;
+            return Ok(DebateOutcome {
+                answer,
+                winner: None,
+                votes: HashMap::new(),
+                transcript,
+            });
+        }
+
+        let mut votes: HashMap<AgentId, usize> = HashMap::new();
+        for id in &debaters {
+            let candidates: Vec<&AgentId> = debaters.iter().filter(|other| *other != id).collect();
+            let ballot = self
+                .contribute(
+                    id,
+                    format!(
+                        "The debate on \"{}\" is over. The final answers are:\n\n{}\n\n\
+                        Vote for the most accurate answer other than your own. Reply with \
+                        `VOTE:` followed by one of: {}",
+                        question,
+                        answers,
+                        candidates
+                            .iter()
+                            .map(|c| c.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                )
+                .await?;
+            if let Some(choice) = parse_vote(&ballot, &candidates) {
+                *votes.entry(choice).or_insert(0) += 1;
+            }
+        }
+
+        // The first debater with the most votes wins.
+        let winner = debaters
+            .iter()
+            .rev()
+            .max_by_key(|id| votes.get(*id).copied().unwrap_or(0))
+            .cloned()
+            .expect("at least two debaters");
+        let answer = latest
+            .into_iter()
+            .find(|(id, _)| *id == winner)
+            .map(|(_, answer)| answer)
+            .unwrap_or_default();
+        Ok(DebateOutcome {
+            answer,
+            winner: Some(winner),
+            votes,
+            transcript,
+        })
+    }
+
+    /// Processes pending messages and triggers responses from agents.
+    ///
+    /// This method iterates through pending messages, delivers them to recipient agents,
+    /// and triggers their responses. It continues until no more messages are generated
+    /// or max_iterations is reached.
+    #[allow(dead_code)]
+    async fn process_messages_and_trigger_responses(
+        &mut self,
+        involved_agents: &[AgentId],
+    ) -> Result<()> {
+        let mut iteration = 0;
+
         while iteration < self.max_iterations {
+            self.abort.check()?;
             // First, deliver all pending messages
             self.process_messages().await?;
 
@@ -779,16 +2332,81 @@ impl ForestOfAgents {
         Ok(())
     }
 
+    /// Stops every agent in the forest immediately.
+    ///
+    /// In-flight LLM calls and tool executions are cancelled, and collaborative
+    /// tasks fail with `HeliosError::Aborted` until `reset_abort` is called.
+    pub fn abort_all(&self) {
+        self.abort.abort();
+    }
+
+    /// Returns a handle that aborts the whole forest, usable while a task is running.
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.abort.clone()
+    }
+
+    /// Returns `true` if the forest has been aborted.
+    pub fn is_aborted(&self) -> bool {
+        self.abort.is_aborted()
+    }
+
+    /// Clears a forest-wide abort so agents accept work again.
+    pub fn reset_abort(&self) {
+        self.abort.reset();
+    }
+
     /// Gets the shared context.
     pub async fn get_shared_context(&self) -> SharedContext {
         self.shared_context.read().await.clone()
     }
 
+    /// Renders the current task plan, its timings and the agents' messages as a
+    /// Graphviz or Mermaid diagram; see [`SharedContext::export_trace`].
+    pub async fn export_trace(&self, format: TraceFormat) -> String {
+        self.shared_context.read().await.export_trace(format)
+    }
+
     /// Sets a value in the shared context.
     pub async fn set_shared_context(&self, key: String, value: Value) {
         let mut context = self.shared_context.write().await;
         context.set(key, value);
     }
+
+    /// Returns the approximate memory held by the agents' histories, the shared
+    /// context and the pending message queue.
+    ///
+    /// `entries` counts messages across all of them.
+    pub async fn memory_usage(&self) -> MemoryStats {
+        let agents = self
+            .agents
+            .values()
+            .map(|agent| agent.memory_usage())
+            .fold(MemoryStats::default(), MemoryStats::merge);
+        let queue = self.message_queue.read().await;
+        let queued = MemoryStats {
+            entries: queue.len(),
+            bytes: queue.approx_bytes(),
+        };
+        agents
+            .merge(self.shared_context.read().await.memory_usage())
+            .merge(queued)
+    }
+
+    /// Prunes every agent's history and the shared message history to at most
+    /// `max_messages` messages each.
+    ///
+    /// Returns the total number of messages removed.
+    pub async fn prune_history(&mut self, max_messages: usize) -> usize {
+        let mut removed = self
+            .shared_context
+            .write()
+            .await
+            .prune_history(max_messages);
+        for agent in self.agents.values_mut() {
+            removed += agent.chat_session_mut().prune_oldest(max_messages);
+        }
+        removed
+    }
 }
 
 impl Default for ForestOfAgents {
@@ -1060,7 +2678,7 @@ impl Tool for ShareContextTool {
         // Store the value with its metadata in a nested object
         let metadata = serde_json::json!({
             "shared_by": self.agent_id,
-            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "timestamp": crate::clock::now().to_rfc3339(),
             "description": description
         });
 
@@ -1164,7 +2782,7 @@ impl Tool for UpdateTaskMemoryTool {
                             "agent": self.agent_id,
                             "task": task_description,
                             "data": additional_data,
-                            "timestamp": chrono::Utc::now().to_rfc3339()
+                            "timestamp": crate::clock::now().to_rfc3339()
                         }),
                     );
                 }
@@ -1249,7 +2867,7 @@ impl Tool for CreatePlanTool {
         let tasks_array: Vec<Value> = serde_json::from_str(tasks_json)
             .map_err(|e| HeliosError::ToolError(format!("Invalid JSON for tasks: {}", e)))?;
 
-        let plan_id = format!("plan_{}", chrono::Utc::now().timestamp());
+        let plan_id = format!("plan_{}", crate::clock::now().timestamp());
         let mut plan = TaskPlan::new(plan_id.clone(), objective.to_string());
 
         for task_value in tasks_array {
@@ -1316,66 +2934,360 @@ impl Tool for CreatePlanTool {
     }
 }
 
-/// Builder for creating a Forest of Agents with multiple agents.
-pub struct ForestBuilder {
-    config: Option<Config>,
-    agents: Vec<(AgentId, AgentBuilder)>,
-    max_iterations: usize,
+/// A tool for the coordinator to change the task plan while it runs.
+pub struct RevisePlanTool {
+    #[allow(dead_code)]
+    agent_id: AgentId,
+    shared_context: Arc<RwLock<SharedContext>>,
 }
 
-impl ForestBuilder {
-    /// Creates a new ForestBuilder.
-    pub fn new() -> Self {
+impl RevisePlanTool {
+    pub fn new(agent_id: AgentId, shared_context: Arc<RwLock<SharedContext>>) -> Self {
         Self {
-            config: None,
-            agents: Vec::new(),
-            max_iterations: 10,
+            agent_id,
+            shared_context,
         }
     }
+}
 
-    /// Sets the configuration for all agents in the forest.
-    pub fn config(mut self, config: Config) -> Self {
-        self.config = Some(config);
-        self
-    }
-
-    /// Adds an agent to the forest with a builder.
-    pub fn agent(mut self, id: AgentId, builder: AgentBuilder) -> Self {
-        self.agents.push((id, builder));
-        self
-    }
-
-    /// Adds multiple agents to the forest at once.
-    ///
-    /// # Example
-    ///
-    /// ```rust,no_run
-    /// # use helios_engine::{Agent, Config, ForestBuilder};
-    /// # async fn example() -> helios_engine::Result<()> {
-    /// # let config = Config::new_default();
-    /// let forest = ForestBuilder::new()
-    ///     .config(config)
-    ///     .agents(vec![
-    ///         ("worker1".to_string(), Agent::builder("worker1")),
-    ///         ("worker2".to_string(), Agent::builder("worker2")),
-    ///     ])
-    ///     .build()
-    ///     .await?;
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn agents(mut self, agents: Vec<(AgentId, AgentBuilder)>) -> Self {
-        self.agents.extend(agents);
-        self
+#[async_trait::async_trait]
+impl Tool for RevisePlanTool {
+    fn name(&self) -> &str {
+        "revise_plan"
     }
 
-    /// Sets the maximum iterations for agent interactions.
-    pub fn max_iterations(mut self, max: usize) -> Self {
-        self.max_iterations = max;
-        self
+    fn description(&self) -> &str {
+        "Change the current task plan based on intermediate results: add, remove, update or reorder tasks that have not started yet."
     }
 
-    /// Builds the Forest of Agents.
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        params.insert(
+            "changes".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "JSON array of changes, applied in order. Each has an 'action': \
+                    {\"action\": \"add\", \"id\", \"description\", \"assigned_to\", \"dependencies\"}, \
+                    {\"action\": \"remove\", \"id\"}, \
+                    {\"action\": \"update\", \"id\", and any of \"description\", \"assigned_to\", \"dependencies\"}, \
+                    {\"action\": \"reorder\", \"order\": [task IDs to run first]}"
+                    .to_string(),
+                required: Some(true),
+            },
+        );
+        params
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let changes_json = args
+            .get("changes")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| HeliosError::ToolError("Missing 'changes' parameter".to_string()))?;
+        let changes: Vec<PlanRevision> = serde_json::from_str(changes_json)
+            .map_err(|e| HeliosError::ToolError(format!("Invalid JSON for changes: {}", e)))?;
+
+        let mut context = self.shared_context.write().await;
+        let plan = context
+            .get_plan_mut()
+            .ok_or_else(|| HeliosError::ToolError("There is no plan to revise".to_string()))?;
+
+        // Apply all changes or none.
+        let mut revised = plan.clone();
+        for change in changes {
+            if let Err(e) = revised.revise(change) {
+                return Ok(ToolResult::error(format!("Plan not changed: {}", e)));
+            }
+        }
+        *plan = revised;
+
+        let task_summary = plan
+            .tasks_in_order()
+            .iter()
+            .map(|t| {
+                format!(
+                    "  • [{}] {} (assigned to: {}, {})",
+                    t.id,
+                    t.description,
+                    t.assigned_to,
+                    t.status.as_str()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Ok(ToolResult::success(format!(
+            "Plan revised; it now has {} tasks:\n{}",
+            plan.tasks.len(),
+            task_summary
+        )))
+    }
+}
+
+/// A task ID, the agent's answer, and when the agent started and finished.
+type TaskRun = (
+    String,
+    Result<String>,
+    chrono::DateTime<chrono::Utc>,
+    chrono::DateTime<chrono::Utc>,
+);
+
+/// Runs an agent's share of a batch of ready plan tasks, in order.
+///
+/// The agent is handed back with the results so the forest can reclaim it. An
+/// abort stops the agent's remaining tasks.
+async fn run_agent_tasks(
+    agent_id: AgentId,
+    agent: &mut Agent,
+    tasks: Vec<(String, String)>,
+    shared_memory_info: String,
+) -> (AgentId, Vec<TaskRun>) {
+    let mut results = Vec::new();
+    for (task_id, task_desc) in tasks {
+        let task_prompt = format!(
+            "{}Your assigned task: {}\n\n\
+            Complete this task and use the 'update_task_memory' tool to save your results to the shared memory. \
+            The task_id is '{}'. Include key findings and data that other agents might need.\n\n\
+            Provide a complete response with your results.",
+            shared_memory_info, task_desc, task_id
+        );
+        let started = crate::clock::now();
+        let result = agent.chat(task_prompt).await;
+        let aborted = matches!(result, Err(HeliosError::Aborted(_)));
+        results.push((task_id, result, started, crate::clock::now()));
+        if aborted {
+            break;
+        }
+    }
+    (agent_id, results)
+}
+
+/// Agents taken out of a forest to work on a batch of tasks.
+///
+/// The agents are put back when the lease is dropped, so they stay in the forest
+/// even if the batch is cancelled part-way, e.g. by `tokio::time::timeout`.
+struct AgentLease<'a> {
+    agents: &'a mut HashMap<AgentId, Agent>,
+    taken: Vec<(AgentId, Agent)>,
+}
+
+impl<'a> AgentLease<'a> {
+    fn new(agents: &'a mut HashMap<AgentId, Agent>) -> Self {
+        Self {
+            agents,
+            taken: Vec::new(),
+        }
+    }
+
+    /// Takes an agent out of the forest, returning `false` if there is none with that ID.
+    fn take(&mut self, agent_id: AgentId) -> bool {
+        match self.agents.remove(&agent_id) {
+            Some(agent) => {
+                self.taken.push((agent_id, agent));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Drop for AgentLease<'_> {
+    fn drop(&mut self) {
+        for (agent_id, agent) in self.taken.drain(..) {
+            self.agents.insert(agent_id, agent);
+        }
+    }
+}
+
+/// Finds the candidate a ballot votes for: the one named after `VOTE:`, or else
+/// the only one mentioned at all.
+/// Returns how long a plan task ran, in seconds, from its timing metadata.
+fn task_duration(task: &TaskItem) -> Option<f64> {
+    let time = |key: &str| {
+        task.metadata
+            .get(key)
+            .and_then(|value| chrono::DateTime::parse_from_rfc3339(value).ok())
+    };
+    let elapsed = time("finished_at")? - time("started_at")?;
+    Some(elapsed.num_milliseconds() as f64 / 1000.0)
+}
+
+/// Shortens text to one line of at most `max` characters for a diagram label.
+fn truncate_label(text: &str, max: usize) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= max {
+        line
+    } else {
+        let mut short: String = line.chars().take(max).collect();
+        short.push('…');
+        short
+    }
+}
+
+fn parse_vote(ballot: &str, candidates: &[&AgentId]) -> Option<AgentId> {
+    let upper = ballot.to_uppercase();
+    if let Some(position) = upper.rfind("VOTE") {
+        let after = ballot[position + 4..]
+            .trim_start_matches(|c: char| c == ':' || c == '*' || c.is_whitespace())
+            .trim_start_matches(['[', '`', '"']);
+        if let Some(choice) = candidates
+            .iter()
+            .filter(|c| after.starts_with(c.as_str()))
+            .max_by_key(|c| c.len())
+        {
+            return Some((*choice).clone());
+        }
+    }
+    let mentioned: Vec<&&AgentId> = candidates
+        .iter()
+        .filter(|c| ballot.contains(c.as_str()))
+        .collect();
+    match mentioned.as_slice() {
+        [only] => Some((**only).clone()),
+        _ => None,
+    }
+}
+
+/// Returns the rest of `reply` if it starts with `verdict`, ignoring case,
+/// surrounding markdown and a following colon.
+fn strip_verdict(reply: &str, verdict: &str) -> Option<String> {
+    let trimmed = reply.trim().trim_start_matches(['*', '`', '#', ' ']);
+    let head = trimmed.get(..verdict.len())?;
+    if !head.eq_ignore_ascii_case(verdict) {
+        return None;
+    }
+    let rest = trimmed[verdict.len()..].trim_start_matches(['*', '`', ':', ' ']);
+    Some(rest.trim().to_string())
+}
+
+/// Builder for creating a Forest of Agents with multiple agents.
+pub struct ForestBuilder {
+    config: Option<Config>,
+    agents: Vec<(AgentId, AgentBuilder)>,
+    max_iterations: usize,
+    topology: Option<ForestTopology>,
+    max_concurrent_tasks: Option<usize>,
+    retry_policy: Option<TaskRetryPolicy>,
+    dynamic_replanning: bool,
+    debate_verdict: Option<DebateVerdict>,
+    checkpoint_path: Option<PathBuf>,
+}
+
+impl ForestBuilder {
+    /// Creates a new ForestBuilder.
+    pub fn new() -> Self {
+        Self {
+            config: None,
+            agents: Vec::new(),
+            max_iterations: 10,
+            topology: None,
+            max_concurrent_tasks: None,
+            retry_policy: None,
+            dynamic_replanning: false,
+            debate_verdict: None,
+            checkpoint_path: None,
+        }
+    }
+
+    /// Sets the configuration for all agents in the forest.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Adds an agent to the forest with a builder.
+    pub fn agent(mut self, id: AgentId, builder: AgentBuilder) -> Self {
+        self.agents.push((id, builder));
+        self
+    }
+
+    /// Adds multiple agents to the forest at once.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, ForestBuilder};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// # let config = Config::new_default();
+    /// let forest = ForestBuilder::new()
+    ///     .config(config)
+    ///     .agents(vec![
+    ///         ("worker1".to_string(), Agent::builder("worker1")),
+    ///         ("worker2".to_string(), Agent::builder("worker2")),
+    ///     ])
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn agents(mut self, agents: Vec<(AgentId, AgentBuilder)>) -> Self {
+        self.agents.extend(agents);
+        self
+    }
+
+    /// Sets the maximum iterations for agent interactions.
+    pub fn max_iterations(mut self, max: usize) -> Self {
+        self.max_iterations = max;
+        self
+    }
+
+    /// Sets how many agents may work on plan tasks at the same time.
+    pub fn max_concurrent_tasks(mut self, max: usize) -> Self {
+        self.max_concurrent_tasks = Some(max);
+        self
+    }
+
+    /// Saves the progress of collaborative tasks to a JSON checkpoint file.
+    pub fn checkpoint_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.checkpoint_path = Some(path.into());
+        self
+    }
+
+    /// Sets how debates pick their final answer.
+    pub fn debate_verdict(mut self, verdict: DebateVerdict) -> Self {
+        self.debate_verdict = Some(verdict);
+        self
+    }
+
+    /// Lets the coordinator revise the plan after every batch of tasks.
+    pub fn dynamic_replanning(mut self, enabled: bool) -> Self {
+        self.dynamic_replanning = enabled;
+        self
+    }
+
+    /// Sets how plan tasks that fail are retried, reassigned or given up on.
+    pub fn retry_policy(mut self, policy: TaskRetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Sets how the agents work together when the forest is [run](ForestOfAgents::run).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{Agent, Config, ForestBuilder, ForestTopology};
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// # let config = Config::new_default();
+    /// let mut forest = ForestBuilder::new()
+    ///     .config(config)
+    ///     .agent("drafter".to_string(), Agent::builder("drafter"))
+    ///     .agent("editor".to_string(), Agent::builder("editor"))
+    ///     .topology(ForestTopology::Pipeline(vec![
+    ///         "drafter".to_string(),
+    ///         "editor".to_string(),
+    ///     ]))
+    ///     .build()
+    ///     .await?;
+    /// let article = forest.run("Write a short article about tide pools").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn topology(mut self, topology: ForestTopology) -> Self {
+        self.topology = Some(topology);
+        self
+    }
+
+    /// Builds the Forest of Agents.
     pub async fn build(self) -> Result<ForestOfAgents> {
         let config = self
             .config
@@ -1387,6 +3299,18 @@ impl ForestBuilder {
             let agent = builder.config(config.clone()).build().await?;
             forest.add_agent(id, agent)?;
         }
+        forest.topology = self.topology;
+        if let Some(max) = self.max_concurrent_tasks {
+            forest.set_max_concurrent_tasks(max);
+        }
+        if let Some(policy) = self.retry_policy {
+            forest.set_retry_policy(policy);
+        }
+        forest.set_dynamic_replanning(self.dynamic_replanning);
+        if let Some(verdict) = self.debate_verdict {
+            forest.set_debate_verdict(verdict);
+        }
+        forest.set_checkpoint_path(self.checkpoint_path);
 
         Ok(forest)
     }
@@ -1405,6 +3329,72 @@ mod tests {
     use crate::tools::Tool;
     use serde_json::Value;
 
+    /// Tests that forest observers see every agent's events under its forest ID.
+    #[tokio::test]
+    async fn test_forest_event_stream() {
+        use futures::StreamExt;
+
+        let mut forest = ForestOfAgents::new();
+        let early = Agent::builder("agent-one")
+            .llm_provider(crate::mock::MockLLMProvider::new().with_response("hi"))
+            .build()
+            .await
+            .unwrap();
+        forest.add_agent("writer".to_string(), early).unwrap();
+        let events = forest.subscribe();
+        let late = Agent::builder("agent-two")
+            .llm_provider(crate::mock::MockLLMProvider::new().with_response("ok"))
+            .build()
+            .await
+            .unwrap();
+        forest.add_agent("critic".to_string(), late).unwrap();
+
+        for id in ["writer", "critic"] {
+            let agent = forest.get_agent_mut(&id.to_string()).unwrap();
+            agent.chat("go").await.unwrap();
+        }
+        drop(forest);
+
+        let answers: Vec<(AgentId, AgentEvent)> = events
+            .filter(|(_, event)| std::future::ready(matches!(event, AgentEvent::FinalAnswer(_))))
+            .collect()
+            .await;
+        assert_eq!(
+            answers,
+            [
+                (
+                    "writer".to_string(),
+                    AgentEvent::FinalAnswer("hi".to_string())
+                ),
+                (
+                    "critic".to_string(),
+                    AgentEvent::FinalAnswer("ok".to_string())
+                ),
+            ]
+        );
+    }
+
+    /// Tests shared context memory accounting and history pruning.
+    #[test]
+    fn test_shared_context_memory_and_pruning() {
+        let mut context = SharedContext::new();
+        for i in 0..5 {
+            context.add_message(ForestMessage::broadcast(
+                "a".to_string(),
+                "x".repeat(100 * i),
+            ));
+        }
+        let before = context.memory_usage();
+        assert_eq!(before.entries, 5);
+
+        assert_eq!(context.prune_history(2), 3);
+        let after = context.memory_usage();
+        assert_eq!(after.entries, 2);
+        assert!(after.bytes < before.bytes);
+        assert_eq!(context.message_history[0].content.len(), 300);
+        assert_eq!(context.prune_history(2), 0);
+    }
+
     /// Tests basic ForestOfAgents creation and agent management.
     #[tokio::test]
     async fn test_forest_creation_and_agent_management() {
@@ -1776,6 +3766,703 @@ mod tests {
         assert!(context.get("involved_agents").is_some());
     }
 
+    async fn scripted_forest(
+        agents: Vec<(&str, crate::mock::MockLLMProvider)>,
+        topology: ForestTopology,
+    ) -> ForestOfAgents {
+        let mut builder = ForestBuilder::new()
+            .config(Config::new_default())
+            .topology(topology);
+        for (id, mock) in agents {
+            builder = builder.agent(id.to_string(), Agent::builder(id).llm_provider(mock));
+        }
+        builder.build().await.unwrap()
+    }
+
+    /// Tests that a pipeline feeds each agent's output to the next one.
+    #[tokio::test]
+    async fn test_pipeline_topology() {
+        let drafter = crate::mock::MockLLMProvider::new().with_response("draft text");
+        let editor = crate::mock::MockLLMProvider::new().with_response("edited text");
+        let mut forest = scripted_forest(
+            vec![("drafter", drafter), ("editor", editor.clone())],
+            ForestTopology::Pipeline(vec!["drafter".to_string(), "editor".to_string()]),
+        )
+        .await;
+
+        assert_eq!(forest.run("Write a poem").await.unwrap(), "edited text");
+        let requests = editor.requests();
+        let prompt = &requests[0].messages.last().unwrap().content;
+        assert!(prompt.contains("Output from the previous step (drafter):\ndraft text"));
+        assert_eq!(forest.get_shared_context().await.message_history.len(), 2);
+    }
+
+    /// Tests that a supervisor sends work back until it approves it.
+    #[tokio::test]
+    async fn test_supervisor_topology() {
+        let worker = crate::mock::MockLLMProvider::new()
+            .with_response("first try")
+            .with_response("second try");
+        let supervisor = crate::mock::MockLLMProvider::new()
+            .with_response("REVISE: add more detail")
+            .with_response("**APPROVED:** final answer");
+        let mut forest = scripted_forest(
+            vec![("worker", worker.clone()), ("boss", supervisor.clone())],
+            ForestTopology::Supervisor {
+                supervisor: "boss".to_string(),
+                workers: vec!["worker".to_string()],
+                max_revisions: 2,
+            },
+        )
+        .await;
+
+        assert_eq!(forest.run("Explain tides").await.unwrap(), "final answer");
+        let requests = worker.requests();
+        let revision_prompt = &requests[1].messages.last().unwrap().content;
+        assert!(revision_prompt.contains("add more detail"));
+        assert!(supervisor.requests()[1]
+            .messages
+            .last()
+            .unwrap()
+            .content
+            .contains("second try"));
+    }
+
+    /// Tests that a round-robin discussion shows each agent what the others said.
+    #[tokio::test]
+    async fn test_round_robin_topology() {
+        let alice = crate::mock::MockLLMProvider::new()
+            .with_response("a1")
+            .with_response("a2");
+        let bob = crate::mock::MockLLMProvider::new()
+            .with_response("b1")
+            .with_response("b2");
+        let mut forest = scripted_forest(
+            vec![("alice", alice.clone()), ("bob", bob.clone())],
+            ForestTopology::RoundRobin {
+                agents: vec!["alice".to_string(), "bob".to_string()],
+                rounds: 2,
+            },
+        )
+        .await;
+
+        assert_eq!(forest.run("Cats or dogs?").await.unwrap(), "b2");
+        let requests = alice.requests();
+        let second_turn = &requests[1].messages.last().unwrap().content;
+        assert!(second_turn.contains("[bob]: b1"));
+        assert!(!second_turn.contains("[alice]: a1"));
+
+        forest.set_topology(ForestTopology::Pipeline(vec!["carol".to_string()]));
+        assert!(forest.run("task").await.is_err());
+    }
+
+    /// Waits until every agent sharing the barrier has called it.
+    struct RendezvousTool(Arc<tokio::sync::Barrier>);
+
+    #[async_trait::async_trait]
+    impl Tool for RendezvousTool {
+        fn name(&self) -> &str {
+            "rendezvous"
+        }
+
+        fn description(&self) -> &str {
+            "Waits for the other agents"
+        }
+
+        fn parameters(&self) -> HashMap<String, ToolParameter> {
+            HashMap::new()
+        }
+
+        async fn execute(&self, _args: Value) -> Result<ToolResult> {
+            self.0.wait().await;
+            Ok(ToolResult::success("everyone is here"))
+        }
+    }
+
+    /// Tests that independent plan tasks assigned to different agents run at the
+    /// same time.
+    #[tokio::test]
+    async fn test_collaborative_tasks_run_concurrently() {
+        let tasks = serde_json::json!([
+            {"id": "t1", "description": "part one", "assigned_to": "a", "dependencies": []},
+            {"id": "t2", "description": "part two", "assigned_to": "b", "dependencies": []}
+        ]);
+        let coordinator = crate::mock::MockLLMProvider::new()
+            .with_tool_call(
+                "create_plan",
+                serde_json::json!({"objective": "both parts", "tasks": tasks.to_string()}),
+            )
+            .with_response("planned")
+            .with_response("all done");
+        let barrier = Arc::new(tokio::sync::Barrier::new(2));
+        let worker = |answer: &str| {
+            Agent::builder("worker")
+                .llm_provider(
+                    crate::mock::MockLLMProvider::new()
+                        .with_tool_call("rendezvous", serde_json::json!({}))
+                        .with_response(answer),
+                )
+                .tool(Box::new(RendezvousTool(Arc::clone(&barrier))))
+        };
+        let mut forest = ForestBuilder::new()
+            .config(Config::new_default())
+            .agent(
+                "lead".to_string(),
+                Agent::builder("lead").llm_provider(coordinator),
+            )
+            .agent("a".to_string(), worker("one done"))
+            .agent("b".to_string(), worker("two done"))
+            .max_concurrent_tasks(2)
+            .build()
+            .await
+            .unwrap();
+
+        // If the tasks ran one at a time, the first worker would wait forever.
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            forest.execute_collaborative_task(
+                &"lead".to_string(),
+                "Do both parts".to_string(),
+                vec!["a".to_string(), "b".to_string()],
+            ),
+        )
+        .await
+        .expect("tasks ran one at a time")
+        .unwrap();
+
+        assert_eq!(result, "all done");
+        assert_eq!(forest.list_agents().len(), 3);
+        let context = forest.get_shared_context().await;
+        let plan = context.get_plan().unwrap();
+        assert!(plan.is_complete());
+        assert_eq!(
+            plan.get_task("t2").unwrap().result.as_deref(),
+            Some("two done")
+        );
+    }
+
+    /// Tests that agents stay in the forest when a collaborative task is cancelled.
+    #[tokio::test]
+    async fn test_cancelled_collaborative_task_keeps_agents() {
+        let tasks = serde_json::json!([
+            {"id": "t1", "description": "part one", "assigned_to": "a", "dependencies": []},
+            {"id": "t2", "description": "part two", "assigned_to": "b", "dependencies": []}
+        ]);
+        let coordinator = crate::mock::MockLLMProvider::new()
+            .with_tool_call(
+                "create_plan",
+                serde_json::json!({"objective": "both parts", "tasks": tasks.to_string()}),
+            )
+            .with_response("planned");
+        // Nobody else arrives at the barrier, so both workers wait until cancelled.
+        let barrier = Arc::new(tokio::sync::Barrier::new(3));
+        let worker = || {
+            Agent::builder("worker")
+                .llm_provider(
+                    crate::mock::MockLLMProvider::new()
+                        .with_tool_call("rendezvous", serde_json::json!({})),
+                )
+                .tool(Box::new(RendezvousTool(Arc::clone(&barrier))))
+        };
+        let mut forest = ForestBuilder::new()
+            .config(Config::new_default())
+            .agent(
+                "lead".to_string(),
+                Agent::builder("lead").llm_provider(coordinator),
+            )
+            .agent("a".to_string(), worker())
+            .agent("b".to_string(), worker())
+            .max_concurrent_tasks(2)
+            .build()
+            .await
+            .unwrap();
+
+        let result = tokio::time::timeout(
+            std::time::Duration::from_millis(200),
+            forest.execute_collaborative_task(
+                &"lead".to_string(),
+                "Do both parts".to_string(),
+                vec!["a".to_string(), "b".to_string()],
+            ),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(forest.list_agents().len(), 3);
+        assert!(forest.get_agent(&"a".to_string()).is_some());
+    }
+
+    /// Builds a forest whose "lead" agent creates a plan with `tasks` and then
+    /// answers "final".
+    async fn planned_forest(
+        tasks: Value,
+        workers: Vec<(&str, crate::mock::MockLLMProvider)>,
+        policy: TaskRetryPolicy,
+    ) -> ForestOfAgents {
+        let coordinator = crate::mock::MockLLMProvider::new()
+            .with_tool_call(
+                "create_plan",
+                serde_json::json!({"objective": "the job", "tasks": tasks.to_string()}),
+            )
+            .with_response("planned")
+            .with_response("final");
+        let mut builder = ForestBuilder::new()
+            .config(Config::new_default())
+            .agent(
+                "lead".to_string(),
+                Agent::builder("lead").llm_provider(coordinator),
+            )
+            .retry_policy(policy);
+        for (id, mock) in workers {
+            builder = builder.agent(id.to_string(), Agent::builder(id).llm_provider(mock));
+        }
+        builder.build().await.unwrap()
+    }
+
+    /// Tests that an unusable answer is retried and then reassigned.
+    #[tokio::test]
+    async fn test_failed_task_is_retried_and_reassigned() {
+        let tasks = serde_json::json!([
+            {"id": "t1", "description": "research", "assigned_to": "a", "dependencies": []}
+        ]);
+        let a = crate::mock::MockLLMProvider::new()
+            .with_response(" ")
+            .with_response("");
+        let b = crate::mock::MockLLMProvider::new().with_response("rescued");
+        let policy = TaskRetryPolicy::new().max_retries(1).reassign(true);
+        let mut forest = planned_forest(tasks, vec![("a", a.clone()), ("b", b)], policy).await;
+
+        let result = forest
+            .execute_collaborative_task(
+                &"lead".to_string(),
+                "Do the job".to_string(),
+                vec!["a".to_string(), "b".to_string()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(result, "final");
+        assert_eq!(a.requests().len(), 2);
+        let context = forest.get_shared_context().await;
+        let task = context.get_plan().unwrap().get_task("t1").unwrap();
+        assert_eq!(task.status, TaskStatus::Completed);
+        assert_eq!(task.result.as_deref(), Some("rescued"));
+        assert_eq!(task.assigned_to, "b");
+        assert_eq!(
+            task.metadata.get("tried_agents").map(String::as_str),
+            Some("a")
+        );
+    }
+
+    /// Tests the abort and skip failure policies.
+    #[tokio::test]
+    async fn test_task_failure_policies() {
+        let tasks = serde_json::json!([
+            {"id": "t1", "description": "research", "assigned_to": "a", "dependencies": []},
+            {"id": "t2", "description": "write", "assigned_to": "b", "dependencies": ["t1"]},
+            {"id": "t3", "description": "check", "assigned_to": "b", "dependencies": ["t2"]}
+        ]);
+        let workers = || {
+            vec![
+                ("a", crate::mock::MockLLMProvider::new().with_response("")),
+                ("b", crate::mock::MockLLMProvider::new()),
+            ]
+        };
+        let involved = vec!["a".to_string(), "b".to_string()];
+
+        let policy = TaskRetryPolicy::new().max_retries(0);
+        let mut forest = planned_forest(tasks.clone(), workers(), policy).await;
+        let error = forest
+            .execute_collaborative_task(&"lead".to_string(), "Do it".to_string(), involved.clone())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Task 't1' failed"));
+
+        let policy = TaskRetryPolicy::new()
+            .max_retries(0)
+            .on_failure(TaskFailurePolicy::Skip);
+        let mut forest = planned_forest(tasks, workers(), policy).await;
+        let result = forest
+            .execute_collaborative_task(&"lead".to_string(), "Do it".to_string(), involved)
+            .await
+            .unwrap();
+        assert_eq!(result, "final");
+        let context = forest.get_shared_context().await;
+        let plan = context.get_plan().unwrap();
+        assert!(plan.is_complete());
+        assert_eq!(plan.get_progress(), (0, 3));
+        assert_eq!(
+            plan.get_task("t3").unwrap().result.as_deref(),
+            Some("Skipped: depends on failed task 't2'")
+        );
+    }
+
+    /// Tests that the replan policy carries completed work into the new plan.
+    #[tokio::test]
+    async fn test_task_failure_replans() {
+        let tasks = serde_json::json!([
+            {"id": "t1", "description": "research", "assigned_to": "a", "dependencies": []},
+            {"id": "t2", "description": "write", "assigned_to": "a", "dependencies": ["t1"]}
+        ]);
+        let new_tasks = serde_json::json!([
+            {"id": "t3", "description": "write differently", "assigned_to": "a", "dependencies": ["t1"]}
+        ]);
+        let coordinator = crate::mock::MockLLMProvider::new()
+            .with_tool_call(
+                "create_plan",
+                serde_json::json!({"objective": "the job", "tasks": tasks.to_string()}),
+            )
+            .with_response("planned")
+            .with_tool_call(
+                "create_plan",
+                serde_json::json!({"objective": "the job", "tasks": new_tasks.to_string()}),
+            )
+            .with_response("replanned")
+            .with_response("final");
+        let a = crate::mock::MockLLMProvider::new()
+            .with_response("facts")
+            .with_response("")
+            .with_response("essay");
+        let mut forest = ForestBuilder::new()
+            .config(Config::new_default())
+            .agent(
+                "lead".to_string(),
+                Agent::builder("lead").llm_provider(coordinator.clone()),
+            )
+            .agent("a".to_string(), Agent::builder("a").llm_provider(a))
+            .retry_policy(
+                TaskRetryPolicy::new()
+                    .max_retries(0)
+                    .on_failure(TaskFailurePolicy::Replan),
+            )
+            .build()
+            .await
+            .unwrap();
+
+        let result = forest
+            .execute_collaborative_task(
+                &"lead".to_string(),
+                "Do it".to_string(),
+                vec!["a".to_string()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(result, "final");
+        let requests = coordinator.requests();
+        assert!(requests[2]
+            .messages
+            .last()
+            .unwrap()
+            .content
+            .contains("Failed task: t2"));
+        let context = forest.get_shared_context().await;
+        let plan = context.get_plan().unwrap();
+        assert_eq!(plan.task_order, vec!["t1", "t3"]);
+        assert_eq!(
+            plan.get_task("t3").unwrap().result.as_deref(),
+            Some("essay")
+        );
+    }
+
+    /// Tests adding, removing, updating and reordering plan tasks.
+    #[test]
+    fn test_plan_revisions() {
+        let mut plan = TaskPlan::new("p".to_string(), "goal".to_string());
+        plan.add_task(TaskItem::new("t1".into(), "one".into(), "a".into()));
+        plan.add_task(
+            TaskItem::new("t2".into(), "two".into(), "a".into())
+                .with_dependencies(vec!["t1".into()]),
+        );
+        plan.get_task_mut("t1").unwrap().status = TaskStatus::Completed;
+
+        let changes: Vec<PlanRevision> = serde_json::from_str(
+            r#"[
+                {"action": "add", "id": "t3", "description": "three", "assigned_to": "b", "dependencies": ["t2"]},
+                {"action": "reorder", "order": ["t3"]},
+                {"action": "update", "id": "t2", "assigned_to": "b"}
+            ]"#,
+        )
+        .unwrap();
+        for change in changes {
+            plan.revise(change).unwrap();
+        }
+        assert_eq!(plan.task_order, vec!["t3", "t1", "t2"]);
+        assert_eq!(plan.get_task("t2").unwrap().assigned_to, "b");
+
+        // Started tasks, unknown dependencies and cycles are rejected.
+        assert!(plan
+            .revise(PlanRevision::Remove { id: "t1".into() })
+            .is_err());
+        let cycle = PlanRevision::Update {
+            id: "t2".into(),
+            description: None,
+            assigned_to: None,
+            dependencies: Some(vec!["t3".into()]),
+        };
+        assert!(plan.revise(cycle).is_err());
+
+        plan.revise(PlanRevision::Remove { id: "t2".into() })
+            .unwrap();
+        assert!(plan.get_task("t3").unwrap().dependencies.is_empty());
+    }
+
+    /// Tests that the coordinator can revise the plan between batches.
+    #[tokio::test]
+    async fn test_dynamic_replanning() {
+        let tasks = serde_json::json!([
+            {"id": "t1", "description": "research", "assigned_to": "a", "dependencies": []},
+            {"id": "t2", "description": "write a poem", "assigned_to": "a", "dependencies": ["t1"]}
+        ]);
+        let changes = serde_json::json!([
+            {"action": "remove", "id": "t2"},
+            {"action": "add", "id": "t3", "description": "write a summary", "assigned_to": "a", "dependencies": ["t1"]}
+        ]);
+        let coordinator = crate::mock::MockLLMProvider::new()
+            .with_tool_call(
+                "create_plan",
+                serde_json::json!({"objective": "the job", "tasks": tasks.to_string()}),
+            )
+            .with_response("planned")
+            .with_tool_call(
+                "revise_plan",
+                serde_json::json!({"changes": changes.to_string()}),
+            )
+            .with_response("revised")
+            .with_response("no changes")
+            .with_response("final");
+        let a = crate::mock::MockLLMProvider::new()
+            .with_response("facts")
+            .with_response("summary");
+        let mut forest = ForestBuilder::new()
+            .config(Config::new_default())
+            .agent(
+                "lead".to_string(),
+                Agent::builder("lead").llm_provider(coordinator.clone()),
+            )
+            .agent("a".to_string(), Agent::builder("a").llm_provider(a.clone()))
+            .dynamic_replanning(true)
+            .build()
+            .await
+            .unwrap();
+
+        let result = forest
+            .execute_collaborative_task(
+                &"lead".to_string(),
+                "Do it".to_string(),
+                vec!["a".to_string()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(result, "final");
+        let requests = a.requests();
+        assert!(requests[1]
+            .messages
+            .last()
+            .unwrap()
+            .content
+            .contains("write a summary"));
+        let context = forest.get_shared_context().await;
+        let plan = context.get_plan().unwrap();
+        assert_eq!(plan.task_order, vec!["t1", "t3"]);
+        assert_eq!(
+            plan.get_task("t3").unwrap().result.as_deref(),
+            Some("summary")
+        );
+    }
+
+    /// Tests a debate decided by majority vote.
+    #[tokio::test]
+    async fn test_debate_majority_vote() {
+        let debater = |answer: &str, revised: &str, vote: &str| {
+            crate::mock::MockLLMProvider::new()
+                .with_response(answer)
+                .with_response(revised)
+                .with_response(vote)
+        };
+        let a = debater("4", "4, as b says", "VOTE: b");
+        let b = debater("four", "It is 4.", "I prefer c's wording. VOTE: c");
+        let c = debater("5", "4", "**VOTE:** b");
+        let mut forest = ForestOfAgents::new();
+        for (id, mock) in [("a", a.clone()), ("b", b), ("c", c)] {
+            let agent = Agent::builder(id).llm_provider(mock).build().await.unwrap();
+            forest.add_agent(id.to_string(), agent).unwrap();
+        }
+
+        let outcome = forest.debate("What is 2 + 2?", 1).await.unwrap();
+        assert_eq!(outcome.winner.as_deref(), Some("b"));
+        assert_eq!(outcome.answer, "It is 4.");
+        assert_eq!(outcome.votes.get("b"), Some(&2));
+        assert_eq!(outcome.transcript.len(), 6);
+        let requests = a.requests();
+        let critique = &requests[1].messages.last().unwrap().content;
+        assert!(critique.contains("[b]\nfour") && critique.contains("[c]\n5"));
+    }
+
+    /// Tests a debate decided by a judge.
+    #[tokio::test]
+    async fn test_debate_judge() {
+        let judge = crate::mock::MockLLMProvider::new().with_response("The answer is 4.");
+        let mut forest = ForestBuilder::new()
+            .config(Config::new_default())
+            .agent(
+                "a".to_string(),
+                Agent::builder("a")
+                    .llm_provider(crate::mock::MockLLMProvider::new().with_response("4")),
+            )
+            .agent(
+                "b".to_string(),
+                Agent::builder("b")
+                    .llm_provider(crate::mock::MockLLMProvider::new().with_response("5")),
+            )
+            .agent(
+                "judge".to_string(),
+                Agent::builder("judge").llm_provider(judge.clone()),
+            )
+            .debate_verdict(DebateVerdict::Judge("judge".to_string()))
+            .build()
+            .await
+            .unwrap();
+
+        let outcome = forest.debate("What is 2 + 2?", 0).await.unwrap();
+        assert_eq!(outcome.answer, "The answer is 4.");
+        assert!(outcome.winner.is_none());
+        let requests = judge.requests();
+        assert!(requests[0]
+            .messages
+            .last()
+            .unwrap()
+            .content
+            .contains("[b]\n5"));
+    }
+
+    /// Tests that ballots are parsed leniently.
+    #[test]
+    fn test_parse_vote() {
+        let (alpha, beta) = ("alpha".to_string(), "alpha2".to_string());
+        let candidates = vec![&alpha, &beta];
+        assert_eq!(parse_vote("Vote: alpha2", &candidates), Some(beta.clone()));
+        assert_eq!(
+            parse_vote("vote: `alpha`", &candidates),
+            Some(alpha.clone())
+        );
+        assert_eq!(parse_vote("I'd go with alpha2.", &candidates), None);
+        assert_eq!(parse_vote("no idea", &candidates), None);
+    }
+
+    /// Tests that an interrupted collaborative task resumes from its checkpoint
+    /// without redoing completed tasks.
+    #[tokio::test]
+    async fn test_resume_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.json");
+        let tasks = serde_json::json!([
+            {"id": "t1", "description": "research", "assigned_to": "a", "dependencies": []},
+            {"id": "t2", "description": "write", "assigned_to": "b", "dependencies": ["t1"]}
+        ]);
+        let build = |lead: crate::mock::MockLLMProvider,
+                     a: crate::mock::MockLLMProvider,
+                     b: crate::mock::MockLLMProvider| {
+            ForestBuilder::new()
+                .config(Config::new_default())
+                .agent(
+                    "lead".to_string(),
+                    Agent::builder("lead").llm_provider(lead),
+                )
+                .agent("a".to_string(), Agent::builder("a").llm_provider(a))
+                .agent("b".to_string(), Agent::builder("b").llm_provider(b))
+                .retry_policy(TaskRetryPolicy::new().max_retries(0))
+                .checkpoint_path(&path)
+                .build()
+        };
+
+        // The first run breaks down on the second task.
+        let lead = crate::mock::MockLLMProvider::new()
+            .with_tool_call(
+                "create_plan",
+                serde_json::json!({"objective": "report", "tasks": tasks.to_string()}),
+            )
+            .with_response("planned");
+        let a = crate::mock::MockLLMProvider::new().with_response("facts");
+        let b = crate::mock::MockLLMProvider::new().with_response("");
+        let mut forest = build(lead, a, b).await.unwrap();
+        assert!(forest
+            .execute_collaborative_task(
+                &"lead".to_string(),
+                "Write a report".to_string(),
+                vec!["a".to_string(), "b".to_string()],
+            )
+            .await
+            .is_err());
+
+        let checkpoint = ForestCheckpoint::load(&path).unwrap();
+        let plan = checkpoint.context.get_plan().unwrap();
+        assert_eq!(plan.get_task("t1").unwrap().status, TaskStatus::Completed);
+        assert_eq!(plan.get_task("t2").unwrap().status, TaskStatus::Pending);
+
+        // A new forest picks up where the first stopped.
+        let lead = crate::mock::MockLLMProvider::new().with_response("final report");
+        let a = crate::mock::MockLLMProvider::new();
+        let b = crate::mock::MockLLMProvider::new().with_response("essay");
+        let mut forest = build(lead, a.clone(), b.clone()).await.unwrap();
+        assert_eq!(forest.resume(&path).await.unwrap(), "final report");
+        assert!(a.requests().is_empty());
+        let requests = b.requests();
+        assert!(requests[0]
+            .messages
+            .last()
+            .unwrap()
+            .content
+            .contains("facts"));
+
+        // A finished run just reports its answer.
+        assert_eq!(forest.resume(&path).await.unwrap(), "final report");
+    }
+
+    /// Tests that traces show the task graph with timings and the messages.
+    #[test]
+    fn test_export_trace() {
+        let start = chrono::Utc::now();
+        let mut plan = TaskPlan::new("p".to_string(), "Write a \"report\"".to_string());
+        let mut research = TaskItem::new("t1".to_string(), "research".to_string(), "a".into());
+        research.status = TaskStatus::Completed;
+        research
+            .metadata
+            .insert("started_at".to_string(), start.to_rfc3339());
+        research.metadata.insert(
+            "finished_at".to_string(),
+            (start + chrono::Duration::milliseconds(2500)).to_rfc3339(),
+        );
+        plan.add_task(research);
+        let mut write = TaskItem::new("t2".to_string(), "write".to_string(), "b".into());
+        write.dependencies.push("t1".to_string());
+        plan.add_task(write);
+        let mut context = SharedContext::new();
+        context.set_plan(plan);
+        context.add_message(ForestMessage::new(
+            "a".to_string(),
+            Some("b".to_string()),
+            "found it".to_string(),
+        ));
+        context.add_message(ForestMessage::new(
+            "b".to_string(),
+            None,
+            "done".to_string(),
+        ));
+
+        let dot = context.export_trace(TraceFormat::Dot);
+        assert!(dot.starts_with("digraph forest {"));
+        assert!(dot.contains("label=\"Plan: Write a \\\"report\\\"\""));
+        assert!(dot.contains("task0 [shape=box, label=\"t1\\na\\ncompleted (2.5s)\"]"));
+        assert!(dot.contains("task0 -> task1;"));
+        assert!(dot.contains("agent0 -> agent1 [label=\"#1 +0.0s: found it\"]"));
+        assert!(dot.contains("agent2 [shape=ellipse, label=\"all\"]"));
+
+        let mermaid = context.export_trace(TraceFormat::Mermaid);
+        assert!(mermaid.starts_with("flowchart LR"));
+        assert!(mermaid.contains("subgraph plan[\"Plan: Write a #quot;report#quot;\"]"));
+        assert!(mermaid.contains("task1[\"t2<br/>b<br/>pending\"]"));
+        assert!(mermaid.contains("agent1 -->|\"#2 "));
+    }
+
     /// Tests the ForestBuilder functionality.
     #[tokio::test]
     async fn test_forest_builder() {
@@ -1842,6 +4529,47 @@ mod tests {
         assert!(result.is_err());
     }
 
+    /// Tests that aborting the forest stops its agents until the abort is reset.
+    #[tokio::test]
+    async fn test_forest_abort_all() {
+        let mut forest = ForestOfAgents::new();
+        let agent = Agent::builder("worker")
+            .config(Config::new_default())
+            .build()
+            .await
+            .unwrap();
+        forest.add_agent("worker".to_string(), agent).unwrap();
+
+        forest.abort_all();
+        assert!(forest.is_aborted());
+        assert!(forest
+            .get_agent(&"worker".to_string())
+            .unwrap()
+            .is_aborted());
+
+        let result = forest
+            .get_agent_mut(&"worker".to_string())
+            .unwrap()
+            .chat("hello")
+            .await;
+        assert!(matches!(result, Err(HeliosError::Aborted(_))));
+
+        let result = forest
+            .execute_collaborative_task(
+                &"worker".to_string(),
+                "test task".to_string(),
+                vec!["worker".to_string()],
+            )
+            .await;
+        assert!(matches!(result, Err(HeliosError::Aborted(_))));
+
+        forest.reset_abort();
+        assert!(!forest
+            .get_agent(&"worker".to_string())
+            .unwrap()
+            .is_aborted());
+    }
+
     /// Tests ForestMessage creation and properties.
     #[tokio::test]
     async fn test_forest_message() {
@@ -0,0 +1,777 @@
+//! # GitHub Module
+//!
+//! `GitHubTool` gives an agent the parts of the GitHub API a code reviewer or
+//! triager needs: issues, pull requests and their diffs, file contents, code
+//! search and comments. Each action takes plain parameters, so the model never has
+//! to assemble REST URLs or headers itself.
+//!
+//! Creating issues, opening pull requests and commenting are `Destructive` calls
+//! and go through the approval handler. In read-only mode they are refused.
+//!
+//! Requires the `github` feature.
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{required_str, str_at, Tool, ToolParameter, ToolPermission, ToolResult};
+use crate::truncation::truncate_chars;
+use async_trait::async_trait;
+use base64::Engine;
+use octocrab::Octocrab;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Actions that change something on GitHub.
+const WRITE_ACTIONS: &[&str] = &["create_issue", "create_pr", "comment"];
+
+/// A tool for working with GitHub issues, pull requests and code.
+///
+/// ```rust,no_run
+/// use helios_engine::GitHubTool;
+///
+/// let github = GitHubTool::new(std::env::var("GITHUB_TOKEN").unwrap())?
+///     .repo("rust-lang/rust")
+///     .read_only(true);
+/// # Ok::<(), helios_engine::HeliosError>(())
+/// ```
+pub struct GitHubTool {
+    client: Octocrab,
+    repo: Option<String>,
+    read_only: bool,
+    max_output_chars: usize,
+}
+
+impl GitHubTool {
+    /// Creates a tool authenticated with a personal access token.
+    pub fn new(token: impl Into<String>) -> Result<Self> {
+        let client = Octocrab::builder()
+            .personal_token(token.into())
+            .build()
+            .map_err(|e| {
+                HeliosError::ConfigError(format!("Could not create GitHub client: {}", e))
+            })?;
+        Ok(Self::from_client(client))
+    }
+
+    /// Uses an existing client, for example one for GitHub Enterprise or a GitHub App.
+    pub fn from_client(client: Octocrab) -> Self {
+        Self {
+            client,
+            repo: None,
+            read_only: false,
+            max_output_chars: 20_000,
+        }
+    }
+
+    /// Sets the `owner/name` repository used when a call does not name one.
+    pub fn repo(mut self, repo: impl Into<String>) -> Self {
+        self.repo = Some(repo.into());
+        self
+    }
+
+    /// Refuses actions that create issues, pull requests or comments.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Limits the characters of output returned to the agent (20,000 by default).
+    pub fn max_output_chars(mut self, max: usize) -> Self {
+        self.max_output_chars = max;
+        self
+    }
+
+    fn repo_of<'a>(&'a self, args: &'a Value) -> Result<&'a str> {
+        let repo = args
+            .get("repo")
+            .and_then(Value::as_str)
+            .or(self.repo.as_deref())
+            .ok_or_else(|| {
+                HeliosError::ToolError("Missing 'repo' parameter (owner/name)".to_string())
+            })?;
+        match repo.split_once('/') {
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+                Ok(repo)
+            }
+            _ => Err(HeliosError::ToolError(format!(
+                "'{}' is not a repository; use owner/name",
+                repo
+            ))),
+        }
+    }
+
+    async fn get(&self, route: &str, query: Option<&Value>) -> Result<Value> {
+        self.client.get(route, query).await.map_err(github_error)
+    }
+
+    async fn post(&self, route: &str, body: &Value) -> Result<Value> {
+        self.client
+            .post(route, Some(body))
+            .await
+            .map_err(github_error)
+    }
+
+    async fn run(&self, action: &str, args: &Value) -> Result<String> {
+        if self.read_only && WRITE_ACTIONS.contains(&action) {
+            return Err(HeliosError::ToolError(format!(
+                "'{}' is not allowed: this tool is read-only",
+                action
+            )));
+        }
+        match action {
+            "list_issues" => {
+                let repo = self.repo_of(args)?;
+                let mut query = json!({
+                    "state": str_arg(args, "state").unwrap_or("open"),
+                    "per_page": per_page(args),
+                });
+                if let Some(labels) = str_arg(args, "labels") {
+                    query["labels"] = json!(labels);
+                }
+                let issues = self
+                    .get(&format!("/repos/{}/issues", repo), Some(&query))
+                    .await?;
+                // The issues endpoint includes pull requests; they have their own action.
+                let lines: Vec<String> = as_array(&issues)
+                    .iter()
+                    .filter(|issue| issue.get("pull_request").is_none())
+                    .map(format_issue)
+                    .collect();
+                Ok(or_none(lines, "No issues"))
+            }
+            "get_issue" => {
+                let repo = self.repo_of(args)?;
+                let number = number(args)?;
+                let issue = self
+                    .get(&format!("/repos/{}/issues/{}", repo, number), None)
+                    .await?;
+                let comments = self
+                    .get(
+                        &format!("/repos/{}/issues/{}/comments", repo, number),
+                        Some(&json!({"per_page": 50})),
+                    )
+                    .await?;
+                let mut out = format!(
+                    "{}\n\n{}\n",
+                    format_issue(&issue),
+                    str_at(&issue, "/body").trim()
+                );
+                for comment in as_array(&comments) {
+                    out.push_str(&format!(
+                        "\n--- @{} ({}):\n{}\n",
+                        str_at(comment, "/user/login"),
+                        str_at(comment, "/created_at"),
+                        str_at(comment, "/body").trim()
+                    ));
+                }
+                Ok(out)
+            }
+            "create_issue" => {
+                let repo = self.repo_of(args)?;
+                let mut body = json!({
+                    "title": required_str(args, "title")?,
+                    "body": str_arg(args, "body").unwrap_or(""),
+                });
+                if let Some(labels) = str_arg(args, "labels") {
+                    body["labels"] = json!(split_list(labels));
+                }
+                let issue = self.post(&format!("/repos/{}/issues", repo), &body).await?;
+                Ok(format!(
+                    "Created issue #{}: {}",
+                    issue.get("number").and_then(Value::as_u64).unwrap_or(0),
+                    str_at(&issue, "/html_url")
+                ))
+            }
+            "list_prs" => {
+                let repo = self.repo_of(args)?;
+                let query = json!({
+                    "state": str_arg(args, "state").unwrap_or("open"),
+                    "per_page": per_page(args),
+                });
+                let prs = self
+                    .get(&format!("/repos/{}/pulls", repo), Some(&query))
+                    .await?;
+                let lines: Vec<String> = as_array(&prs).iter().map(format_pr).collect();
+                Ok(or_none(lines, "No pull requests"))
+            }
+            "get_pr" => {
+                let repo = self.repo_of(args)?;
+                let number = number(args)?;
+                let pr = self
+                    .get(&format!("/repos/{}/pulls/{}", repo, number), None)
+                    .await?;
+                let files = self
+                    .get(
+                        &format!("/repos/{}/pulls/{}/files", repo, number),
+                        Some(&json!({"per_page": 100})),
+                    )
+                    .await?;
+                let mut out = format!(
+                    "{}\n{} commits, +{} -{} in {} files\n\n{}\n\nFiles:\n",
+                    format_pr(&pr),
+                    pr.get("commits").and_then(Value::as_u64).unwrap_or(0),
+                    pr.get("additions").and_then(Value::as_u64).unwrap_or(0),
+                    pr.get("deletions").and_then(Value::as_u64).unwrap_or(0),
+                    pr.get("changed_files").and_then(Value::as_u64).unwrap_or(0),
+                    str_at(&pr, "/body").trim()
+                );
+                for file in as_array(&files) {
+                    out.push_str(&format!(
+                        "  {} {} (+{} -{})\n",
+                        str_at(file, "/status"),
+                        str_at(file, "/filename"),
+                        file.get("additions").and_then(Value::as_u64).unwrap_or(0),
+                        file.get("deletions").and_then(Value::as_u64).unwrap_or(0)
+                    ));
+                }
+                Ok(out)
+            }
+            "get_diff" => {
+                let repo = self.repo_of(args)?;
+                let number = number(args)?;
+                let mut headers = axum::http::HeaderMap::new();
+                headers.insert(
+                    axum::http::header::ACCEPT,
+                    axum::http::HeaderValue::from_static("application/vnd.github.diff"),
+                );
+                let route = format!("/repos/{}/pulls/{}", repo, number);
+                let response = self
+                    .client
+                    ._get_with_headers(route.as_str(), Some(headers))
+                    .await
+                    .map_err(github_error)?;
+                let response = octocrab::map_github_error(response)
+                    .await
+                    .map_err(github_error)?;
+                let diff = self
+                    .client
+                    .body_to_string(response)
+                    .await
+                    .map_err(github_error)?;
+                match str_arg(args, "path") {
+                    Some(path) => {
+                        let file_diff = diff_for_file(&diff, path);
+                        if file_diff.is_empty() {
+                            Err(HeliosError::ToolError(format!(
+                                "The pull request does not change {}",
+                                path
+                            )))
+                        } else {
+                            Ok(file_diff)
+                        }
+                    }
+                    None => Ok(diff),
+                }
+            }
+            "create_pr" => {
+                let repo = self.repo_of(args)?;
+                let body = json!({
+                    "title": required_str(args, "title")?,
+                    "head": required_str(args, "head")?,
+                    "base": required_str(args, "base")?,
+                    "body": str_arg(args, "body").unwrap_or(""),
+                    "draft": args.get("draft").and_then(Value::as_bool).unwrap_or(false),
+                });
+                let pr = self.post(&format!("/repos/{}/pulls", repo), &body).await?;
+                Ok(format!(
+                    "Opened pull request #{}: {}",
+                    pr.get("number").and_then(Value::as_u64).unwrap_or(0),
+                    str_at(&pr, "/html_url")
+                ))
+            }
+            "comment" => {
+                let repo = self.repo_of(args)?;
+                let number = number(args)?;
+                let comment = self
+                    .post(
+                        &format!("/repos/{}/issues/{}/comments", repo, number),
+                        &json!({"body": required_str(args, "body")?}),
+                    )
+                    .await?;
+                Ok(format!(
+                    "Commented on #{}: {}",
+                    number,
+                    str_at(&comment, "/html_url")
+                ))
+            }
+            "get_file" => {
+                let repo = self.repo_of(args)?;
+                let path = required_str(args, "path")?.trim_start_matches('/');
+                let query = str_arg(args, "ref").map(|r| json!({"ref": r}));
+                let file = self
+                    .get(
+                        &format!("/repos/{}/contents/{}", repo, path),
+                        query.as_ref(),
+                    )
+                    .await?;
+                if file.is_array() {
+                    let entries: Vec<String> = as_array(&file)
+                        .iter()
+                        .map(|entry| {
+                            format!("{} {}", str_at(entry, "/type"), str_at(entry, "/path"))
+                        })
+                        .collect();
+                    return Ok(or_none(entries, "Empty directory"));
+                }
+                let encoded: String = str_at(&file, "/content")
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect();
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .map_err(|e| HeliosError::ToolError(format!("Could not decode file: {}", e)))?;
+                String::from_utf8(bytes)
+                    .map_err(|_| HeliosError::ToolError(format!("{} is not a text file", path)))
+            }
+            "search_code" => {
+                let mut query = required_str(args, "query")?.to_string();
+                if !query.contains("repo:") {
+                    if let Ok(repo) = self.repo_of(args) {
+                        query = format!("{} repo:{}", query, repo);
+                    }
+                }
+                let mut headers = axum::http::HeaderMap::new();
+                headers.insert(
+                    axum::http::header::ACCEPT,
+                    axum::http::HeaderValue::from_static("application/vnd.github.text-match+json"),
+                );
+                let results: Value = self
+                    .client
+                    .get_with_headers(
+                        "/search/code",
+                        Some(&json!({"q": query, "per_page": per_page(args)})),
+                        Some(headers),
+                    )
+                    .await
+                    .map_err(github_error)?;
+                let mut out = format!(
+                    "{} results\n",
+                    results
+                        .get("total_count")
+                        .and_then(Value::as_u64)
+                        .unwrap_or(0)
+                );
+                for item in results
+                    .get("items")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                {
+                    out.push_str(&format!(
+                        "\n{} ({})\n",
+                        str_at(item, "/path"),
+                        str_at(item, "/repository/full_name")
+                    ));
+                    for fragment in item
+                        .get("text_matches")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                    {
+                        for line in str_at(fragment, "/fragment").lines() {
+                            out.push_str(&format!("    {}\n", line));
+                        }
+                    }
+                }
+                Ok(out)
+            }
+            other => Err(HeliosError::ToolError(format!(
+                "Unknown action '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn limit(&self, text: String) -> String {
+        let max = self.max_output_chars;
+        truncate_chars(
+            &text,
+            max,
+            &format!("\n... (truncated to {} characters)", max),
+        )
+    }
+}
+
+#[async_trait]
+impl Tool for GitHubTool {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    fn description(&self) -> &str {
+        "Work with GitHub. Actions: list_issues, get_issue, create_issue, list_prs, get_pr, \
+         get_diff (optionally for one path), get_file, search_code, create_pr and comment \
+         (on an issue or pull request). Repositories are written owner/name."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        let mut add = |name: &str, param_type: &str, description: &str, required: bool| {
+            params.insert(
+                name.to_string(),
+                ToolParameter {
+                    param_type: param_type.to_string(),
+                    description: description.to_string(),
+                    required: Some(required),
+                },
+            );
+        };
+        add("action", "string", "The action to perform", true);
+        add(
+            "repo",
+            "string",
+            "Repository as owner/name, if not the default",
+            false,
+        );
+        add("number", "integer", "Issue or pull request number", false);
+        add(
+            "state",
+            "string",
+            "For list_issues and list_prs: open (default), closed or all",
+            false,
+        );
+        add(
+            "labels",
+            "string",
+            "Comma-separated labels, to filter issues or set on a new one",
+            false,
+        );
+        add(
+            "title",
+            "string",
+            "For create_issue and create_pr: the title",
+            false,
+        );
+        add(
+            "body",
+            "string",
+            "Text of a new issue, pull request or comment (Markdown)",
+            false,
+        );
+        add(
+            "head",
+            "string",
+            "For create_pr: the branch with the changes",
+            false,
+        );
+        add(
+            "base",
+            "string",
+            "For create_pr: the branch to merge into",
+            false,
+        );
+        add(
+            "draft",
+            "boolean",
+            "For create_pr: open it as a draft",
+            false,
+        );
+        add(
+            "path",
+            "string",
+            "For get_file: the file or directory. For get_diff: only this file's changes",
+            false,
+        );
+        add(
+            "ref",
+            "string",
+            "For get_file: branch, tag or commit (default branch if omitted)",
+            false,
+        );
+        add(
+            "query",
+            "string",
+            "For search_code: GitHub code search syntax",
+            false,
+        );
+        add(
+            "limit",
+            "integer",
+            "How many results to list (at most 100)",
+            false,
+        );
+        params
+    }
+
+    fn permission(&self) -> ToolPermission {
+        if self.read_only {
+            ToolPermission::ReadOnly
+        } else {
+            ToolPermission::Destructive
+        }
+    }
+
+    fn call_permission(&self, args: &Value) -> ToolPermission {
+        match args.get("action").and_then(Value::as_str) {
+            Some(action) if WRITE_ACTIONS.contains(&action) => ToolPermission::Destructive,
+            _ => ToolPermission::ReadOnly,
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let action = required_str(&args, "action")?;
+        Ok(match self.run(action, &args).await {
+            Ok(output) => ToolResult::success(self.limit(output)),
+            Err(e) => ToolResult::error(e.to_string()),
+        })
+    }
+}
+
+fn github_error(error: octocrab::Error) -> HeliosError {
+    match error {
+        octocrab::Error::GitHub { source, .. } => HeliosError::ToolError(format!(
+            "GitHub error (HTTP {}): {}",
+            source.status_code.as_u16(),
+            source.message
+        )),
+        other => HeliosError::ToolError(format!("GitHub request failed: {}", other)),
+    }
+}
+
+fn str_arg<'a>(args: &'a Value, name: &str) -> Option<&'a str> {
+    required_str(args, name).ok().filter(|s| !s.is_empty())
+}
+
+fn number(args: &Value) -> Result<u64> {
+    let number = args.get("number");
+    number
+        .and_then(Value::as_u64)
+        .or_else(|| {
+            number
+                .and_then(Value::as_str)
+                .and_then(|s| s.trim_start_matches('#').parse().ok())
+        })
+        .ok_or_else(|| HeliosError::ToolError("Missing 'number' parameter".to_string()))
+}
+
+fn per_page(args: &Value) -> u64 {
+    args.get("limit")
+        .and_then(Value::as_u64)
+        .unwrap_or(20)
+        .clamp(1, 100)
+}
+
+fn split_list(list: &str) -> Vec<&str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn as_array(value: &Value) -> &[Value] {
+    value.as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+fn or_none(lines: Vec<String>, none: &str) -> String {
+    if lines.is_empty() {
+        none.to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+fn format_issue(issue: &Value) -> String {
+    let labels: Vec<&str> = as_array(issue.get("labels").unwrap_or(&Value::Null))
+        .iter()
+        .filter_map(|label| label.get("name").and_then(Value::as_str))
+        .collect();
+    let mut line = format!(
+        "#{} [{}] {} by @{}",
+        issue.get("number").and_then(Value::as_u64).unwrap_or(0),
+        str_at(issue, "/state"),
+        str_at(issue, "/title"),
+        str_at(issue, "/user/login")
+    );
+    if !labels.is_empty() {
+        line.push_str(&format!(" ({})", labels.join(", ")));
+    }
+    if let Some(comments) = issue.get("comments").and_then(Value::as_u64) {
+        if comments > 0 {
+            line.push_str(&format!(", {} comments", comments));
+        }
+    }
+    line
+}
+
+fn format_pr(pr: &Value) -> String {
+    let state = if pr.get("merged_at").is_some_and(|m| !m.is_null()) {
+        "merged"
+    } else if pr.get("draft").and_then(Value::as_bool) == Some(true) {
+        "draft"
+    } else {
+        str_at(pr, "/state")
+    };
+    format!(
+        "#{} [{}] {} by @{} ({} -> {})",
+        pr.get("number").and_then(Value::as_u64).unwrap_or(0),
+        state,
+        str_at(pr, "/title"),
+        str_at(pr, "/user/login"),
+        str_at(pr, "/head/ref"),
+        str_at(pr, "/base/ref")
+    )
+}
+
+/// Picks out one file's section of a unified diff.
+fn diff_for_file(diff: &str, path: &str) -> String {
+    let mut out = String::new();
+    let mut keep = false;
+    for line in diff.split_inclusive('\n') {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            keep = header.split_whitespace().any(|name| {
+                name.strip_prefix("a/").or_else(|| name.strip_prefix("b/")) == Some(path)
+            });
+        }
+        if keep {
+            out.push_str(line);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::Query;
+    use axum::http::HeaderMap;
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use std::sync::{Arc, Mutex};
+
+    const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-old\n+new\ndiff --git a/README.md b/README.md\n--- a/README.md\n+++ b/README.md\n@@ -1 +1 @@\n-a\n+b\n";
+
+    /// Tests reading and writing through the GitHub tool against a mock API.
+    #[tokio::test]
+    async fn test_github_tool() {
+        let comments = Arc::new(Mutex::new(Vec::<Value>::new()));
+        let recorded = comments.clone();
+        let app = Router::new()
+            .route(
+                "/repos/acme/app/issues",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    assert_eq!(query["state"], "open");
+                    Json(json!([
+                        {"number": 7, "state": "open", "title": "Crash on start", "user": {"login": "ann"},
+                         "labels": [{"name": "bug"}], "comments": 2},
+                        {"number": 8, "state": "open", "title": "Fix crash", "user": {"login": "bob"},
+                         "pull_request": {}}
+                    ]))
+                }),
+            )
+            .route(
+                "/repos/acme/app/pulls/{number}",
+                get(|headers: HeaderMap| async move {
+                    if headers.get("accept").is_some_and(|a| a == "application/vnd.github.diff") {
+                        DIFF.to_string().into_response()
+                    } else {
+                        Json(json!({
+                            "number": 8, "state": "open", "title": "Fix crash", "user": {"login": "bob"},
+                            "head": {"ref": "fix"}, "base": {"ref": "main"}, "merged_at": null,
+                            "body": "Closes #7", "commits": 1, "additions": 1, "deletions": 1, "changed_files": 2
+                        }))
+                        .into_response()
+                    }
+                }),
+            )
+            .route(
+                "/repos/acme/app/pulls/{number}/files",
+                get(|| async {
+                    Json(json!([
+                        {"filename": "src/lib.rs", "status": "modified", "additions": 1, "deletions": 1}
+                    ]))
+                }),
+            )
+            .route(
+                "/repos/acme/app/contents/{*path}",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    assert_eq!(query["ref"], "fix");
+                    Json(json!({"type": "file", "content": "bmV3\nCg==\n"}))
+                }),
+            )
+            .route(
+                "/repos/acme/app/issues/{number}/comments",
+                axum::routing::post(move |Json(body): Json<Value>| {
+                    let recorded = recorded.clone();
+                    async move {
+                        recorded.lock().unwrap().push(body);
+                        Json(json!({"html_url": "https://github.com/acme/app/pull/8#c1"}))
+                    }
+                }),
+            )
+            .route(
+                "/search/code",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    assert_eq!(query["q"], "fn main repo:acme/app");
+                    Json(json!({"total_count": 1, "items": [
+                        {"path": "src/main.rs", "repository": {"full_name": "acme/app"},
+                         "text_matches": [{"fragment": "fn main() {\n    run();"}]}
+                    ]}))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = Octocrab::builder()
+            .base_uri(url)
+            .unwrap()
+            .personal_token("token".to_string())
+            .build()
+            .unwrap();
+        let tool = GitHubTool::from_client(client).repo("acme/app");
+        let run = |args: Value| tool.execute(args);
+
+        let result = run(json!({"action": "list_issues"})).await.unwrap();
+        assert_eq!(
+            result.output,
+            "#7 [open] Crash on start by @ann (bug), 2 comments"
+        );
+
+        let result = run(json!({"action": "get_pr", "number": 8})).await.unwrap();
+        assert!(result
+            .output
+            .starts_with("#8 [open] Fix crash by @bob (fix -> main)\n1 commits, +1 -1 in 2 files"));
+        assert!(result.output.contains("  modified src/lib.rs (+1 -1)"));
+
+        let result = run(json!({"action": "get_diff", "number": "#8"}))
+            .await
+            .unwrap();
+        assert_eq!(result.output, DIFF);
+        let result = run(json!({"action": "get_diff", "number": 8, "path": "README.md"}))
+            .await
+            .unwrap();
+        assert!(result.output.starts_with("diff --git a/README.md"));
+        assert!(!result.output.contains("src/lib.rs"));
+
+        let result = run(json!({"action": "get_file", "path": "src/lib.rs", "ref": "fix"}))
+            .await
+            .unwrap();
+        assert_eq!(result.output, "new\n");
+
+        let result = run(json!({"action": "search_code", "query": "fn main"}))
+            .await
+            .unwrap();
+        assert!(result
+            .output
+            .contains("src/main.rs (acme/app)\n    fn main() {"));
+
+        let comment = json!({"action": "comment", "number": 8, "body": "LGTM"});
+        assert_eq!(tool.call_permission(&comment), ToolPermission::Destructive);
+        let result = run(comment.clone()).await.unwrap();
+        assert!(result.output.starts_with("Commented on #8"));
+        assert_eq!(comments.lock().unwrap()[0]["body"], "LGTM");
+
+        let result = run(json!({"action": "get_issue", "number": 99, "repo": "nope"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("use owner/name"));
+
+        let read_only = GitHubTool::from_client(Octocrab::builder().build().unwrap())
+            .repo("acme/app")
+            .read_only(true);
+        assert_eq!(read_only.permission(), ToolPermission::ReadOnly);
+        let result = read_only.execute(comment).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("read-only"));
+    }
+}
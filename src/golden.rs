@@ -0,0 +1,458 @@
+//! # Golden Module
+//!
+//! Snapshot testing for agent runs. A [`Transcript`] captures the messages of a
+//! conversation, normalizes the parts that change from run to run (UUIDs,
+//! timestamps, tool call IDs) and renders them as plain text. Comparing that text
+//! against a stored "golden" file catches unintended changes to prompts, tool
+//! definitions or the agent loop, and a failure shows a line diff.
+//!
+//! ```rust,no_run
+//! use helios_engine::{Agent, CalculatorTool, MockLLMProvider, Transcript};
+//! use serde_json::json;
+//!
+//! # async fn example() -> helios_engine::Result<()> {
+//! let mock = MockLLMProvider::new()
+//!     .with_tool_call("calculator", json!({"expression": "2+2"}))
+//!     .with_response("2 + 2 = 4");
+//! let mut agent = Agent::builder("math")
+//!     .llm_provider(mock)
+//!     .tool(Box::new(CalculatorTool))
+//!     .build()
+//!     .await?;
+//! agent.chat("What is 2+2?").await?;
+//!
+//! Transcript::from_agent(&agent).assert_matches("tests/goldens/calculator.txt");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A missing golden file is written on the first run. Set `HELIOS_UPDATE_GOLDENS=1`
+//! to rewrite goldens after an intended change. When the `CI` environment variable
+//! is set, a missing golden fails the test instead of being created.
+
+use crate::agent::Agent;
+use crate::chat::{ChatMessage, ChatSession, Role};
+use crate::error::Result;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The environment variable that makes [`Transcript::assert_matches`] rewrite goldens.
+pub const UPDATE_GOLDENS_ENV: &str = "HELIOS_UPDATE_GOLDENS";
+
+/// Replaces volatile values in transcripts with stable placeholders.
+///
+/// By default UUIDs become `<uuid>`, RFC 3339 timestamps become `<timestamp>` and
+/// tool call IDs are renumbered `call_1`, `call_2`, ... in order of appearance.
+#[derive(Debug, Clone)]
+pub struct Normalizer {
+    replacements: Vec<(Regex, String)>,
+    ignored_arguments: Vec<String>,
+    renumber_tool_calls: bool,
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Normalizer {
+    /// Creates a normalizer with the default rules.
+    pub fn new() -> Self {
+        Self::empty()
+            .replace(
+                r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}",
+                "<uuid>",
+            )
+            .replace(
+                r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(\.\d+)?(Z|[+-]\d{2}:\d{2})?",
+                "<timestamp>",
+            )
+            .renumber_tool_calls(true)
+    }
+
+    /// Creates a normalizer that changes nothing.
+    pub fn empty() -> Self {
+        Self {
+            replacements: Vec::new(),
+            ignored_arguments: Vec::new(),
+            renumber_tool_calls: false,
+        }
+    }
+
+    /// Replaces every match of the regular expression `pattern` with `replacement`.
+    ///
+    /// The replacement may refer to capture groups as `$1` or `${name}`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` is not a valid regular expression.
+    pub fn replace(mut self, pattern: &str, replacement: impl Into<String>) -> Self {
+        let regex = Regex::new(pattern)
+            .unwrap_or_else(|e| panic!("Invalid normalizer pattern '{}': {}", pattern, e));
+        self.replacements.push((regex, replacement.into()));
+        self
+    }
+
+    /// Replaces the value of the tool argument `name` with `"<ignored>"`.
+    pub fn ignore_argument(mut self, name: impl Into<String>) -> Self {
+        self.ignored_arguments.push(name.into());
+        self
+    }
+
+    /// Sets whether tool call IDs are renumbered in order of appearance.
+    pub fn renumber_tool_calls(mut self, renumber: bool) -> Self {
+        self.renumber_tool_calls = renumber;
+        self
+    }
+
+    /// Applies the replacement rules to a piece of text.
+    pub fn normalize_text(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (regex, replacement) in &self.replacements {
+            text = regex.replace_all(&text, replacement.as_str()).into_owned();
+        }
+        text
+    }
+
+    /// Normalizes tool arguments, which are rendered as compact JSON with sorted keys.
+    fn normalize_arguments(&self, arguments: &str) -> String {
+        let Ok(mut value) = serde_json::from_str::<Value>(arguments) else {
+            return self.normalize_text(arguments);
+        };
+        if let Value::Object(map) = &mut value {
+            for name in &self.ignored_arguments {
+                if let Some(argument) = map.get_mut(name) {
+                    *argument = Value::String("<ignored>".to_string());
+                }
+            }
+        }
+        self.normalize_text(&sort_keys(value).to_string())
+    }
+}
+
+/// Rebuilds a JSON value with object keys in sorted order.
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_keys).collect()),
+        other => other,
+    }
+}
+
+/// The messages of a conversation, ready to compare against a golden file.
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    messages: Vec<ChatMessage>,
+    normalizer: Normalizer,
+}
+
+impl Transcript {
+    /// Creates a transcript of `messages` with the default normalizer.
+    pub fn from_messages(messages: &[ChatMessage]) -> Self {
+        Self {
+            messages: messages.to_vec(),
+            normalizer: Normalizer::new(),
+        }
+    }
+
+    /// Creates a transcript of a chat session, starting with its system prompt.
+    pub fn from_session(session: &ChatSession) -> Self {
+        let mut messages = Vec::with_capacity(session.messages.len() + 1);
+        if let Some(prompt) = &session.system_prompt {
+            messages.push(ChatMessage::system(prompt.clone()));
+        }
+        messages.extend(session.messages.iter().cloned());
+        Self::from_messages(&messages)
+    }
+
+    /// Creates a transcript of an agent's conversation so far.
+    pub fn from_agent(agent: &Agent) -> Self {
+        Self::from_session(agent.chat_session())
+    }
+
+    /// Replaces the normalizer.
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Returns the messages in the transcript.
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    /// Renders the normalized transcript as text.
+    ///
+    /// Each message starts with its role in brackets. Tool calls are shown as
+    /// `-> name(arguments) [id]` and tool results name the call they answer.
+    pub fn render(&self) -> String {
+        let mut call_ids: HashMap<String, String> = HashMap::new();
+        let mut call_id = |id: &str| -> String {
+            if !self.normalizer.renumber_tool_calls {
+                return id.to_string();
+            }
+            let next = call_ids.len() + 1;
+            call_ids
+                .entry(id.to_string())
+                .or_insert_with(|| format!("call_{}", next))
+                .clone()
+        };
+
+        let mut sections = Vec::with_capacity(self.messages.len());
+        for message in &self.messages {
+            let role = match message.role {
+                Role::System => "system",
+                Role::User => "user",
+                Role::Assistant => "assistant",
+                Role::Tool => "tool",
+            };
+            let mut header = format!("[{}", role);
+            if let Some(name) = &message.name {
+                header.push_str(&format!(" {}", name));
+            }
+            if let Some(id) = &message.tool_call_id {
+                header.push_str(&format!(" {}", call_id(id)));
+            }
+            header.push(']');
+
+            let mut lines = vec![header];
+            let content = self.normalizer.normalize_text(message.content.trim_end());
+            if !content.is_empty() {
+                lines.push(content);
+            }
+            for call in message.tool_calls.iter().flatten() {
+                lines.push(format!(
+                    "-> {}({}) [{}]",
+                    call.function.name,
+                    self.normalizer
+                        .normalize_arguments(&call.function.arguments),
+                    call_id(&call.id)
+                ));
+            }
+            sections.push(lines.join("\n"));
+        }
+
+        let mut text = sections.join("\n\n");
+        text.push('\n');
+        text
+    }
+
+    /// Compares the transcript with the golden file at `path`.
+    ///
+    /// Returns `None` if they match, or a line diff from the golden to the
+    /// transcript if they don't. A missing file is reported as a diff against an
+    /// empty golden.
+    pub fn compare<P: AsRef<Path>>(&self, path: P) -> Result<Option<String>> {
+        let actual = self.render();
+        let expected = match std::fs::read_to_string(path.as_ref()) {
+            Ok(expected) => expected,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let expected = expected.replace("\r\n", "\n");
+        Ok((expected != actual).then(|| diff_lines(&expected, &actual)))
+    }
+
+    /// Writes the rendered transcript to `path`, creating parent directories as needed.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        std::fs::write(path, self.render())?;
+        Ok(())
+    }
+
+    /// Asserts that the transcript matches the golden file at `path`.
+    ///
+    /// The golden is written instead of compared when it doesn't exist yet (unless
+    /// `CI` is set) or when `HELIOS_UPDATE_GOLDENS` is set to anything but `0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a diff if the transcript differs from the golden, or if the
+    /// golden can't be read or written.
+    #[track_caller]
+    pub fn assert_matches<P: AsRef<Path>>(&self, path: P) {
+        let path = path.as_ref();
+        let update = std::env::var(UPDATE_GOLDENS_ENV).is_ok_and(|value| value != "0");
+        let missing = !path.exists();
+        if update || (missing && std::env::var_os("CI").is_none()) {
+            if let Err(e) = self.save(path) {
+                panic!("Failed to write golden '{}': {}", path.display(), e);
+            }
+            return;
+        }
+
+        match self.compare(path) {
+            Ok(None) => {}
+            Ok(Some(diff)) => panic!(
+                "Transcript does not match golden '{}' (- golden, + actual). \
+                 Set {}=1 to update it.\n\n{}",
+                path.display(),
+                UPDATE_GOLDENS_ENV,
+                diff
+            ),
+            Err(e) => panic!("Failed to read golden '{}': {}", path.display(), e),
+        }
+    }
+}
+
+/// The number of unchanged lines shown around each change in a diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// Produces a line diff from `expected` to `actual`.
+///
+/// Removed lines start with `-`, added lines with `+` and context lines with a
+/// space. Each hunk starts with `@@ line N @@`, giving the line in `expected`.
+pub fn diff_lines(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // Longest common subsequence table, filled from the end.
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Each operation is (tag, line in `expected`, text).
+    let mut ops: Vec<(char, usize, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push((' ', i, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', i, old[i]));
+            i += 1;
+        } else {
+            ops.push(('+', i, new[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| op.0 != ' ')
+        .map(|(index, _)| index)
+        .collect();
+    let mut output = String::new();
+    let mut index = 0;
+    while index < changed.len() {
+        let start = changed[index].saturating_sub(DIFF_CONTEXT);
+        let mut end = changed[index];
+        while index < changed.len() && changed[index] <= end + 2 * DIFF_CONTEXT {
+            end = changed[index];
+            index += 1;
+        }
+        let end = (end + DIFF_CONTEXT + 1).min(ops.len());
+        output.push_str(&format!("@@ line {} @@\n", ops[start].1 + 1));
+        for (tag, _, text) in &ops[start..end] {
+            output.push_str(&format!("{}{}\n", tag, text));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{FunctionCall, ToolCall};
+
+    fn transcript() -> Transcript {
+        let mut call = ChatMessage::assistant("");
+        call.tool_calls = Some(vec![ToolCall {
+            id: "call_9f2c1e".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "lookup".to_string(),
+                arguments: r#"{"b": 2, "a": "2024-05-01T12:30:00Z", "nonce": 7}"#.to_string(),
+            },
+        }]);
+        Transcript::from_messages(&[
+            ChatMessage::system("Be brief."),
+            ChatMessage::user("Find order 123e4567-e89b-12d3-a456-426614174000"),
+            call,
+            ChatMessage::tool("found", "call_9f2c1e"),
+            ChatMessage::assistant("Done."),
+        ])
+        .with_normalizer(Normalizer::new().ignore_argument("nonce"))
+    }
+
+    /// Tests that volatile values are normalized in the rendered transcript.
+    #[test]
+    fn test_transcript_render() {
+        assert_eq!(
+            transcript().render(),
+            "[system]\nBe brief.\n\n\
+             [user]\nFind order <uuid>\n\n\
+             [assistant]\n-> lookup({\"a\":\"<timestamp>\",\"b\":2,\"nonce\":\"<ignored>\"}) [call_1]\n\n\
+             [tool call_1]\nfound\n\n\
+             [assistant]\nDone.\n"
+        );
+    }
+
+    /// Tests comparing against a golden file and the diff of a mismatch.
+    #[test]
+    fn test_golden_comparison() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("goldens/run.txt");
+        let transcript = transcript();
+
+        assert!(transcript.compare(&path).unwrap().is_some());
+        transcript.save(&path).unwrap();
+        assert_eq!(transcript.compare(&path).unwrap(), None);
+        transcript.assert_matches(&path);
+
+        let changed = Transcript::from_messages(&[
+            ChatMessage::system("Be very brief."),
+            ChatMessage::user("Find order 123e4567-e89b-12d3-a456-426614174000"),
+        ]);
+        let diff = changed.compare(&path).unwrap().unwrap();
+        assert!(diff.starts_with("@@ line 1 @@\n [system]\n-Be brief.\n+Be very brief.\n"));
+        assert!(diff.contains("-[tool call_1]"));
+    }
+
+    /// Tests a transcript of a real agent run, including its tool round trip.
+    #[tokio::test]
+    async fn test_agent_transcript() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_tool_call("calculator", serde_json::json!({"expression": "2+2"}))
+            .with_response("2 + 2 = 4");
+        let mut agent = Agent::builder("math")
+            .system_prompt("You do arithmetic.")
+            .llm_provider(mock)
+            .tool(Box::new(crate::tools::CalculatorTool))
+            .build()
+            .await
+            .unwrap();
+        agent.chat("What is 2+2?").await.unwrap();
+
+        let rendered = Transcript::from_agent(&agent).render();
+        assert!(rendered.starts_with("[system]\nYou do arithmetic.\n\n[user]\nWhat is 2+2?\n"));
+        assert!(rendered.contains("-> calculator({\"expression\":\"2+2\"}) [call_1]"));
+        assert!(rendered.contains("[tool call_1]\n"));
+        assert!(rendered.ends_with("[assistant]\n2 + 2 = 4\n"));
+    }
+}
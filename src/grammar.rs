@@ -0,0 +1,476 @@
+//! # Grammar Module
+//!
+//! GBNF grammars for constrained sampling with local models. With a grammar, the
+//! `local` provider only samples tokens the grammar allows, so a model asked for
+//! JSON cannot produce text that fails to parse.
+//!
+//! A [`Grammar`] can be written by hand in llama.cpp's GBNF, or built from a JSON
+//! schema with [`Grammar::json_schema`]. Agents using [`ToolProtocol::React`]
+//! constrain every reply to the protocol with [`Grammar::react`], which also makes
+//! each tool's `Action Input:` match that tool's parameters.
+//!
+//! Remote providers cannot take a grammar and ignore it.
+//!
+//! [`ToolProtocol::React`]: crate::tool_protocol::ToolProtocol::React
+
+use crate::error::{HeliosError, Result};
+use crate::tools::ToolDefinition;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// The rules shared by every JSON grammar, after llama.cpp's `json.gbnf`.
+const JSON_PRIMITIVES: &[(&str, &str)] = &[
+    ("value", "object | array | string | number | boolean | null"),
+    (
+        "object",
+        r#""{" ws ( string ":" ws value ( "," ws string ":" ws value )* )? "}" ws"#,
+    ),
+    ("array", r#""[" ws ( value ( "," ws value )* )? "]" ws"#),
+    (
+        "string",
+        r#""\"" ( [^"\\\x7F\x00-\x1F] | "\\" ( ["\\/bfnrt] | "u" [0-9a-fA-F]{4} ) )* "\"" ws"#,
+    ),
+    (
+        "number",
+        r#""-"? ( [0-9] | [1-9] [0-9]{0,15} ) ( "." [0-9]+ )? ( [eE] [-+]? [0-9]{1,3} )? ws"#,
+    ),
+    ("integer", r#""-"? ( [0-9] | [1-9] [0-9]{0,15} ) ws"#),
+    ("boolean", r#"( "true" | "false" ) ws"#),
+    ("null", r#""null" ws"#),
+    ("ws", r#"[ \t\n]{0,20}"#),
+];
+
+/// A GBNF grammar whose start rule is `root`.
+///
+/// ```rust
+/// use helios_engine::Grammar;
+/// use serde_json::json;
+///
+/// let grammar = Grammar::json_schema(&json!({
+///     "type": "object",
+///     "properties": {
+///         "city": {"type": "string"},
+///         "days": {"type": "integer"}
+///     },
+///     "required": ["city"]
+/// }))
+/// .unwrap();
+/// assert!(grammar.as_gbnf().starts_with("root ::="));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grammar {
+    gbnf: String,
+}
+
+impl Grammar {
+    /// Uses a grammar written in GBNF. It must define a `root` rule.
+    pub fn new(gbnf: impl Into<String>) -> Self {
+        Self { gbnf: gbnf.into() }
+    }
+
+    /// A grammar for any JSON value.
+    pub fn json() -> Self {
+        let mut rules = Rules::default();
+        rules.add("root", "value".to_string());
+        rules.into_grammar()
+    }
+
+    /// A grammar for JSON documents matching `schema`.
+    ///
+    /// Supports `type` (including a list of types), `properties` and `required`,
+    /// `items`, `enum`, `const`, `anyOf` / `oneOf`, and local `$ref`s into `$defs` or
+    /// `definitions`. Other keywords, such as string formats and numeric ranges, are
+    /// not enforced. Object properties are produced in the schema's order.
+    pub fn json_schema(schema: &Value) -> Result<Self> {
+        let mut rules = Rules::default();
+        let root = SchemaConverter {
+            root: schema,
+            rules: &mut rules,
+            refs: HashSet::new(),
+        }
+        .visit(schema, "root")?;
+        if root != "root" {
+            rules.add("root", root);
+        }
+        Ok(rules.into_grammar())
+    }
+
+    /// A grammar for replies in the ReAct tool protocol with `tools`.
+    ///
+    /// A reply is an optional `Thought:` line followed by either an `Action:` naming
+    /// one of the tools, with an `Action Input:` matching its parameters, or a
+    /// `Final Answer:`.
+    pub fn react(tools: &[ToolDefinition]) -> Self {
+        let mut rules = Rules::default();
+        let mut tools: Vec<&ToolDefinition> = tools.iter().collect();
+        tools.sort_by(|a, b| a.function.name.cmp(&b.function.name));
+
+        let mut actions = Vec::new();
+        for tool in tools {
+            let name = &tool.function.name;
+            let schema = serde_json::to_value(&tool.function.parameters).unwrap_or_default();
+            let arguments = SchemaConverter {
+                root: &schema,
+                rules: &mut rules,
+                refs: HashSet::new(),
+            }
+            .visit(&schema, &format!("{}-arguments", rule_name(name)))
+            // A tool whose schema cannot be converted still gets JSON arguments.
+            .unwrap_or_else(|_| "object".to_string());
+            actions.push(format!(
+                "{} \"\\nAction Input: \" {}",
+                literal(name),
+                arguments
+            ));
+        }
+
+        let root = if actions.is_empty() {
+            "thought? answer"
+        } else {
+            rules.add(
+                "action",
+                format!("\"Action: \" ( {} )", actions.join(" | ")),
+            );
+            "thought? ( action | answer )"
+        };
+        rules.add("root", root.to_string());
+        rules.add("thought", r#""Thought: " [^\n]* "\n""#.to_string());
+        rules.add("answer", r#""Final Answer: " [^\x00]*"#.to_string());
+        rules.into_grammar()
+    }
+
+    /// Returns the grammar in GBNF.
+    pub fn as_gbnf(&self) -> &str {
+        &self.gbnf
+    }
+}
+
+/// The rules of a grammar being built, with the JSON primitives added on demand.
+#[derive(Default)]
+struct Rules {
+    rules: Vec<(String, String)>,
+    names: HashSet<String>,
+}
+
+impl Rules {
+    /// Adds a rule, returning its name. A taken name gets a numeric suffix.
+    fn add(&mut self, name: &str, body: String) -> String {
+        let mut unique = name.to_string();
+        let mut n = 1;
+        while !self.names.insert(unique.clone()) {
+            unique = format!("{}-{}", name, n);
+            n += 1;
+        }
+        self.rules.push((unique.clone(), body));
+        unique
+    }
+
+    fn into_grammar(mut self) -> Grammar {
+        // `root` first, so the grammar reads top-down.
+        if let Some(index) = self.rules.iter().position(|(name, _)| name == "root") {
+            let root = self.rules.remove(index);
+            self.rules.insert(0, root);
+        }
+        for (name, body) in JSON_PRIMITIVES {
+            if !self.names.contains(*name) {
+                self.rules.push((name.to_string(), body.to_string()));
+            }
+        }
+        let gbnf = self
+            .rules
+            .iter()
+            .map(|(name, body)| format!("{} ::= {}\n", name, body))
+            .collect();
+        Grammar { gbnf }
+    }
+}
+
+/// Turns a JSON schema into grammar rules.
+struct SchemaConverter<'a> {
+    root: &'a Value,
+    rules: &'a mut Rules,
+    /// `$ref`s already given a rule, which lets schemas refer to themselves.
+    refs: HashSet<String>,
+}
+
+impl SchemaConverter<'_> {
+    /// Returns the rule (or primitive) matching `schema`, adding rules named after `name`.
+    fn visit(&mut self, schema: &Value, name: &str) -> Result<String> {
+        let schema = match schema {
+            Value::Bool(true) => return Ok("value".to_string()),
+            Value::Object(schema) => schema,
+            other => {
+                return Err(HeliosError::ConfigError(format!(
+                    "Unsupported JSON schema: {}",
+                    other
+                )))
+            }
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            return self.visit_ref(reference);
+        }
+        if let Some(value) = schema.get("const") {
+            return Ok(self.rules.add(name, json_literal(value)));
+        }
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            let choices: Vec<String> = values.iter().map(json_literal).collect();
+            return Ok(self.rules.add(name, format!("( {} )", choices.join(" | "))));
+        }
+        if let Some(Value::Array(options)) = schema.get("anyOf").or_else(|| schema.get("oneOf")) {
+            return self.visit_alternatives(options, name);
+        }
+
+        match schema.get("type") {
+            Some(Value::Array(types)) => {
+                let options: Vec<Value> = types
+                    .iter()
+                    .map(|kind| {
+                        let mut option = schema.clone();
+                        option.insert("type".to_string(), kind.clone());
+                        Value::Object(option)
+                    })
+                    .collect();
+                self.visit_alternatives(&options, name)
+            }
+            Some(Value::String(kind)) => match kind.as_str() {
+                "object" => self.visit_object(schema, name),
+                "array" => self.visit_array(schema, name),
+                "string" | "number" | "integer" | "boolean" | "null" => Ok(kind.clone()),
+                other => Err(HeliosError::ConfigError(format!(
+                    "Unsupported JSON schema type '{}'",
+                    other
+                ))),
+            },
+            _ if schema.contains_key("properties") => self.visit_object(schema, name),
+            _ => Ok("value".to_string()),
+        }
+    }
+
+    fn visit_alternatives(&mut self, options: &[Value], name: &str) -> Result<String> {
+        let mut alternatives = Vec::new();
+        for (i, option) in options.iter().enumerate() {
+            alternatives.push(self.visit(option, &format!("{}-{}", name, i))?);
+        }
+        Ok(self
+            .rules
+            .add(name, format!("( {} )", alternatives.join(" | "))))
+    }
+
+    fn visit_ref(&mut self, reference: &str) -> Result<String> {
+        let name = format!(
+            "ref-{}",
+            rule_name(reference.rsplit('/').next().unwrap_or(""))
+        );
+        if !self.refs.insert(reference.to_string()) {
+            return Ok(name);
+        }
+        let target = reference
+            .strip_prefix('#')
+            .and_then(|pointer| self.root.pointer(pointer))
+            .ok_or_else(|| {
+                HeliosError::ConfigError(format!("Cannot resolve JSON schema $ref '{}'", reference))
+            })?;
+        // Claim the name before visiting, so a recursive reference finds it.
+        self.rules.names.insert(name.clone());
+        let index = self.rules.rules.len();
+        let body = self.visit(target, &format!("{}-body", name))?;
+        self.rules.rules.insert(index, (name.clone(), body));
+        Ok(name)
+    }
+
+    fn visit_object(
+        &mut self,
+        schema: &serde_json::Map<String, Value>,
+        name: &str,
+    ) -> Result<String> {
+        let Some(Value::Object(properties)) = schema.get("properties") else {
+            return Ok("object".to_string());
+        };
+        if properties.is_empty() {
+            return Ok(self.rules.add(name, r#""{" ws "}" ws"#.to_string()));
+        }
+        let required: HashSet<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|names| names.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        let mut mandatory = Vec::new();
+        let mut optional = Vec::new();
+        for (key, property) in properties {
+            let value = self.visit(property, &format!("{}-{}", name, rule_name(key)))?;
+            let pair = format!("{} \":\" ws {}", literal(&json_literal_string(key)), value);
+            if required.contains(key.as_str()) {
+                mandatory.push(pair);
+            } else {
+                optional.push(pair);
+            }
+        }
+
+        // Required properties come first, then any of the optional ones, in order.
+        let body = if mandatory.is_empty() {
+            let starts: Vec<String> = (0..optional.len())
+                .map(|first| {
+                    let mut chain = optional[first].clone();
+                    for rest in &optional[first + 1..] {
+                        chain.push_str(&format!(" ( \",\" ws {} )?", rest));
+                    }
+                    chain
+                })
+                .collect();
+            format!("\"{{\" ws ( {} )? \"}}\" ws", starts.join(" | "))
+        } else {
+            let mut body = format!("\"{{\" ws {}", mandatory.join(" \",\" ws "));
+            for rest in &optional {
+                body.push_str(&format!(" ( \",\" ws {} )?", rest));
+            }
+            body.push_str(" \"}\" ws");
+            body
+        };
+        Ok(self.rules.add(name, body))
+    }
+
+    fn visit_array(
+        &mut self,
+        schema: &serde_json::Map<String, Value>,
+        name: &str,
+    ) -> Result<String> {
+        let item = match schema.get("items") {
+            Some(items) => self.visit(items, &format!("{}-item", name))?,
+            None => return Ok("array".to_string()),
+        };
+        Ok(self.rules.add(
+            name,
+            format!("\"[\" ws ( {} ( \",\" ws {} )* )? \"]\" ws", item, item),
+        ))
+    }
+}
+
+/// Makes a rule name from arbitrary text. GBNF names allow letters, digits and `-`.
+fn rule_name(text: &str) -> String {
+    let name: String = text
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    if name.is_empty() {
+        "rule".to_string()
+    } else {
+        name
+    }
+}
+
+/// Quotes text as a GBNF string literal.
+fn literal(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_literal_string(text: &str) -> String {
+    Value::String(text.to_string()).to_string()
+}
+
+/// A rule body matching exactly `value` written as JSON.
+fn json_literal(value: &Value) -> String {
+    format!("{} ws", literal(&value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{CalculatorTool, Tool};
+    use serde_json::json;
+
+    /// Tests the rules built for objects, optional properties, enums and arrays.
+    #[test]
+    fn test_json_schema_grammar() {
+        let grammar = Grammar::json_schema(&json!({
+            "type": "object",
+            "properties": {
+                "city": {"type": "string"},
+                "unit": {"enum": ["celsius", "fahrenheit"]},
+                "days": {"type": "array", "items": {"type": "integer"}}
+            },
+            "required": ["city"]
+        }))
+        .unwrap();
+        let gbnf = grammar.as_gbnf();
+        assert!(gbnf.starts_with(
+            r#"root ::= "{" ws "\"city\"" ":" ws string ( "," ws "\"days\"" ":" ws root-days )? ( "," ws "\"unit\"" ":" ws root-unit )? "}" ws"#
+        ));
+        assert!(gbnf.contains(r#"root-unit ::= ( "\"celsius\"" ws | "\"fahrenheit\"" ws )"#));
+        assert!(gbnf.contains(r#"root-days ::= "[" ws ( integer ( "," ws integer )* )? "]" ws"#));
+        assert!(gbnf.contains("\nstring ::= "));
+        assert!(gbnf.contains("\nws ::= "));
+
+        // Without required properties, any of them may come first.
+        let grammar = Grammar::json_schema(&json!({
+            "properties": {"a": {"type": "boolean"}, "b": {"type": ["string", "null"]}}
+        }))
+        .unwrap();
+        assert!(grammar.as_gbnf().contains(
+            r#"root ::= "{" ws ( "\"a\"" ":" ws boolean ( "," ws "\"b\"" ":" ws root-b )? | "\"b\"" ":" ws root-b )? "}" ws"#
+        ));
+        assert!(grammar.as_gbnf().contains("root-b ::= ( string | null )"));
+
+        assert_eq!(
+            Grammar::json().as_gbnf().lines().next(),
+            Some("root ::= value")
+        );
+        assert!(Grammar::json_schema(&json!({"type": "date"})).is_err());
+        assert!(Grammar::json_schema(&json!({"$ref": "#/$defs/missing"})).is_err());
+    }
+
+    /// Tests that recursive `$ref`s get a single rule.
+    #[test]
+    fn test_json_schema_grammar_refs() {
+        let grammar = Grammar::json_schema(&json!({
+            "$ref": "#/$defs/node",
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "children": {"type": "array", "items": {"$ref": "#/$defs/node"}}
+                    },
+                    "required": ["name"]
+                }
+            }
+        }))
+        .unwrap();
+        let gbnf = grammar.as_gbnf();
+        assert!(gbnf.starts_with("root ::= ref-node\n"));
+        assert!(gbnf.contains("ref-node ::= ref-node-body\n"));
+        assert!(gbnf.contains(
+            r#"ref-node-body-children ::= "[" ws ( ref-node ( "," ws ref-node )* )? "]" ws"#
+        ));
+    }
+
+    /// Tests the grammar for the ReAct tool protocol.
+    #[test]
+    fn test_react_grammar() {
+        let grammar = Grammar::react(&[CalculatorTool.to_definition()]);
+        let gbnf = grammar.as_gbnf();
+        assert!(gbnf.starts_with("root ::= thought? ( action | answer )\n"));
+        assert!(gbnf.contains(
+            r#"action ::= "Action: " ( "calculator" "\nAction Input: " calculator-arguments )"#
+        ));
+        assert!(gbnf
+            .contains(r#"calculator-arguments ::= "{" ws "\"expression\"" ":" ws string "}" ws"#));
+        assert!(gbnf.contains(r#"answer ::= "Final Answer: ""#));
+
+        let grammar = Grammar::react(&[]);
+        assert!(grammar.as_gbnf().starts_with("root ::= thought? answer\n"));
+    }
+}
@@ -0,0 +1,690 @@
+//! # GraphQL Module
+//!
+//! A tool that lets an agent explore and query a GraphQL API. The agent first
+//! introspects the schema, then runs queries with variables. Mutations are refused
+//! unless enabled, and need approval like other destructive tools.
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{required_str, Tool, ToolParameter, ToolPermission, ToolResult};
+use crate::truncation::truncate_chars;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// The introspection query, trimmed to what the tool shows the agent.
+const INTROSPECTION_QUERY: &str = r#"
+query HeliosIntrospection {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    types {
+      kind name description
+      fields(includeDeprecated: false) {
+        name description
+        args { name type { ...TypeRef } defaultValue }
+        type { ...TypeRef }
+      }
+      inputFields { name type { ...TypeRef } defaultValue }
+      enumValues(includeDeprecated: false) { name }
+      possibleTypes { name }
+    }
+  }
+}
+fragment TypeRef on __Type {
+  kind name
+  ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } }
+}
+"#;
+
+/// A tool that introspects a GraphQL endpoint and runs queries against it.
+///
+/// ```rust,no_run
+/// use helios_engine::GraphQLTool;
+///
+/// let github = GraphQLTool::new("https://api.github.com/graphql")
+///     .with_name("github")
+///     .header("Authorization", format!("Bearer {}", std::env::var("GITHUB_TOKEN").unwrap()));
+/// ```
+pub struct GraphQLTool {
+    name: String,
+    description: String,
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    allow_mutations: bool,
+    max_result_chars: usize,
+    client: Client,
+    schema: Mutex<Option<Value>>,
+}
+
+impl GraphQLTool {
+    /// Creates a tool for the GraphQL API at `endpoint`.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        let endpoint = endpoint.into();
+        Self {
+            name: "graphql".to_string(),
+            description: format!(
+                "Query the GraphQL API at {}. Use action 'introspect' to see the schema \
+                 (pass type_name for one type's fields), then 'query' to run a query with variables.",
+                endpoint
+            ),
+            endpoint,
+            headers: Vec::new(),
+            allow_mutations: false,
+            max_result_chars: 20_000,
+            client: Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_default(),
+            schema: Mutex::new(None),
+        }
+    }
+
+    /// Sets the tool's name, for agents that talk to more than one API.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Replaces the tool's description, for example to say what the API is for.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Adds a header to every request, such as `Authorization`.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Lets the agent run mutations. Each one is a destructive call.
+    pub fn allow_mutations(mut self, allow: bool) -> Self {
+        self.allow_mutations = allow;
+        self
+    }
+
+    /// Limits the characters of a result returned to the agent (20,000 by default).
+    pub fn max_result_chars(mut self, max: usize) -> Self {
+        self.max_result_chars = max;
+        self
+    }
+
+    /// Sets the request timeout (30 seconds by default).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default();
+        self
+    }
+
+    /// Sends a GraphQL request and returns the response body.
+    async fn post(&self, body: Value) -> Result<Value> {
+        let mut request = self.client.post(&self.endpoint).json(&body);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("GraphQL request failed: {}", e)))?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        match serde_json::from_str::<Value>(&text) {
+            Ok(value) if value.get("data").is_some() || value.get("errors").is_some() => Ok(value),
+            _ => Err(HeliosError::ToolError(format!(
+                "GraphQL endpoint returned HTTP {}: {}",
+                status,
+                truncate_chars(&text, 500, "...")
+            ))),
+        }
+    }
+
+    /// Returns the schema, introspecting it on first use.
+    async fn schema(&self) -> Result<Value> {
+        let mut cached = self.schema.lock().await;
+        if let Some(schema) = cached.as_ref() {
+            return Ok(schema.clone());
+        }
+        let response = self.post(json!({"query": INTROSPECTION_QUERY})).await?;
+        let schema = response.pointer("/data/__schema").cloned().ok_or_else(|| {
+            HeliosError::ToolError(format!(
+                "Introspection failed: {}",
+                response.get("errors").cloned().unwrap_or(Value::Null)
+            ))
+        })?;
+        *cached = Some(schema.clone());
+        Ok(schema)
+    }
+
+    async fn introspect(&self, type_name: Option<&str>) -> Result<ToolResult> {
+        let schema = match self.schema().await {
+            Ok(schema) => schema,
+            Err(e) => return Ok(ToolResult::error(e.to_string())),
+        };
+        let types = schema
+            .get("types")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        let find = |name: &str| {
+            types
+                .iter()
+                .find(|t| t.get("name").and_then(Value::as_str) == Some(name))
+        };
+
+        if let Some(type_name) = type_name {
+            return Ok(match find(type_name) {
+                Some(t) => ToolResult::success(self.limit(describe_type(t))),
+                None => ToolResult::error(format!("The schema has no type '{}'", type_name)),
+            });
+        }
+
+        let mut out = String::new();
+        let root = |key: &str| {
+            schema
+                .pointer(&format!("/{}/name", key))
+                .and_then(Value::as_str)
+                .and_then(&find)
+        };
+        if let Some(query) = root("queryType") {
+            out.push_str(&describe_type(query));
+        }
+        if let Some(mutation) = root("mutationType") {
+            if self.allow_mutations {
+                out.push('\n');
+                out.push_str(&describe_type(mutation));
+            } else {
+                out.push_str("\n# Mutations are disabled for this tool.\n");
+            }
+        }
+
+        let mut groups: Vec<(&str, Vec<&str>)> = vec![
+            ("OBJECT", Vec::new()),
+            ("INTERFACE", Vec::new()),
+            ("UNION", Vec::new()),
+            ("INPUT_OBJECT", Vec::new()),
+            ("ENUM", Vec::new()),
+            ("SCALAR", Vec::new()),
+        ];
+        for t in &types {
+            let (Some(name), Some(kind)) = (
+                t.get("name").and_then(Value::as_str),
+                t.get("kind").and_then(Value::as_str),
+            ) else {
+                continue;
+            };
+            if name.starts_with("__") {
+                continue;
+            }
+            if let Some((_, names)) = groups.iter_mut().find(|(k, _)| *k == kind) {
+                names.push(name);
+            }
+        }
+        out.push_str("\nOther types:\n");
+        for (kind, names) in groups.iter().filter(|(_, names)| !names.is_empty()) {
+            out.push_str(&format!(
+                "  {}: {}\n",
+                kind.to_lowercase(),
+                names.join(", ")
+            ));
+        }
+        out.push_str("\nPass type_name to see the fields of one type.");
+        Ok(ToolResult::success(self.limit(out)))
+    }
+
+    async fn query(&self, args: &Value) -> Result<ToolResult> {
+        let query = required_str(args, "query")?;
+        let operation_name = args.get("operation_name").and_then(Value::as_str);
+        if is_mutation(query, operation_name) && !self.allow_mutations {
+            return Ok(ToolResult::error(
+                "Mutations are disabled for this tool; only queries can be run.",
+            ));
+        }
+
+        let mut body = json!({"query": query});
+        match args.get("variables") {
+            Some(Value::Object(variables)) => body["variables"] = Value::Object(variables.clone()),
+            // Some models send the variables as a JSON string.
+            Some(Value::String(text)) if !text.trim().is_empty() => {
+                match serde_json::from_str::<Value>(text) {
+                    Ok(variables @ Value::Object(_)) => body["variables"] = variables,
+                    _ => {
+                        return Ok(ToolResult::error(
+                            "'variables' must be a JSON object".to_string(),
+                        ))
+                    }
+                }
+            }
+            _ => {}
+        }
+        if let Some(operation_name) = operation_name {
+            body["operationName"] = json!(operation_name);
+        }
+
+        let response = match self.post(body).await {
+            Ok(response) => response,
+            Err(e) => return Ok(ToolResult::error(e.to_string())),
+        };
+        let output = self.limit(serde_json::to_string_pretty(&response)?);
+        let has_data = response.get("data").is_some_and(|data| !data.is_null());
+        if response.get("errors").is_some() && !has_data {
+            Ok(ToolResult::error(output))
+        } else {
+            Ok(ToolResult::success(output))
+        }
+    }
+
+    fn limit(&self, text: String) -> String {
+        if text.chars().count() <= self.max_result_chars {
+            return text;
+        }
+        format!(
+            "{}\n... (truncated to {} characters; select fewer fields or paginate)",
+            truncate_chars(&text, self.max_result_chars, "..."),
+            self.max_result_chars
+        )
+    }
+}
+
+#[async_trait]
+impl Tool for GraphQLTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        params.insert(
+            "action".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "'introspect' to read the schema, 'query' to run a query".to_string(),
+                required: Some(true),
+            },
+        );
+        params.insert(
+            "type_name".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "For introspect: a type whose fields to show".to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "query".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "For query: the GraphQL document".to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "variables".to_string(),
+            ToolParameter {
+                param_type: "object".to_string(),
+                description: "For query: values for the document's $variables".to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "operation_name".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "For query: which operation to run if the document has several"
+                    .to_string(),
+                required: Some(false),
+            },
+        );
+        params
+    }
+
+    fn permission(&self) -> ToolPermission {
+        if self.allow_mutations {
+            ToolPermission::Destructive
+        } else {
+            ToolPermission::ReadOnly
+        }
+    }
+
+    fn call_permission(&self, args: &Value) -> ToolPermission {
+        let query = args.get("query").and_then(Value::as_str).unwrap_or("");
+        let operation_name = args.get("operation_name").and_then(Value::as_str);
+        if args.get("action").and_then(Value::as_str) == Some("query")
+            && is_mutation(query, operation_name)
+        {
+            ToolPermission::Destructive
+        } else {
+            ToolPermission::ReadOnly
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let action = required_str(&args, "action")?;
+        match action {
+            "introspect" => {
+                self.introspect(args.get("type_name").and_then(Value::as_str))
+                    .await
+            }
+            "query" => self.query(&args).await,
+            other => Ok(ToolResult::error(format!(
+                "Unknown action '{}'; use 'introspect' or 'query'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Returns whether the operation a document would run is a mutation.
+///
+/// With several operations and no `operation_name`, any mutation counts.
+fn is_mutation(query: &str, operation_name: Option<&str>) -> bool {
+    let mut depth = 0usize;
+    let mut word = String::new();
+    let mut words_at_top: Vec<String> = Vec::new();
+    let mut chars = query.chars().peekable();
+    let flush = |word: &mut String, depth: usize, words: &mut Vec<String>| {
+        if depth == 0 && !word.is_empty() {
+            words.push(std::mem::take(word));
+        }
+        word.clear();
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => {
+                flush(&mut word, depth, &mut words_at_top);
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                flush(&mut word, depth, &mut words_at_top);
+                let mut escaped = false;
+                for c in chars.by_ref() {
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+            }
+            '{' | '(' => {
+                flush(&mut word, depth, &mut words_at_top);
+                depth += 1;
+            }
+            '}' | ')' => {
+                flush(&mut word, depth, &mut words_at_top);
+                depth = depth.saturating_sub(1);
+            }
+            c if c.is_alphanumeric() || c == '_' => word.push(c),
+            _ => flush(&mut word, depth, &mut words_at_top),
+        }
+    }
+    flush(&mut word, depth, &mut words_at_top);
+
+    // Top-level words come as `<keyword> [Name]` pairs for each operation.
+    let mut mutation = false;
+    for (i, keyword) in words_at_top.iter().enumerate() {
+        if keyword != "mutation" {
+            continue;
+        }
+        let name = words_at_top.get(i + 1).map(String::as_str);
+        match operation_name {
+            Some(wanted) => mutation |= name == Some(wanted),
+            None => mutation = true,
+        }
+    }
+    mutation
+}
+
+/// Renders a GraphQL type reference such as `[String!]!`.
+fn type_ref(t: &Value) -> String {
+    match t.get("kind").and_then(Value::as_str) {
+        Some("NON_NULL") => format!("{}!", type_ref(&t["ofType"])),
+        Some("LIST") => format!("[{}]", type_ref(&t["ofType"])),
+        _ => t
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("?")
+            .to_string(),
+    }
+}
+
+/// Renders one introspected type in SDL-like form.
+fn describe_type(t: &Value) -> String {
+    let name = t.get("name").and_then(Value::as_str).unwrap_or("?");
+    let mut out = String::new();
+    if let Some(description) = t
+        .get("description")
+        .and_then(Value::as_str)
+        .filter(|d| !d.is_empty())
+    {
+        out.push_str(&format!("# {}\n", description.replace('\n', " ")));
+    }
+    let keyword = match t.get("kind").and_then(Value::as_str) {
+        Some("INPUT_OBJECT") => "input",
+        Some("ENUM") => "enum",
+        Some("INTERFACE") => "interface",
+        Some("UNION") => "union",
+        Some("SCALAR") => return format!("{}scalar {}\n", out, name),
+        _ => "type",
+    };
+
+    if keyword == "union" {
+        let members: Vec<&str> = t
+            .get("possibleTypes")
+            .and_then(Value::as_array)
+            .map(|types| {
+                types
+                    .iter()
+                    .filter_map(|t| t.get("name").and_then(Value::as_str))
+                    .collect()
+            })
+            .unwrap_or_default();
+        return format!("{}union {} = {}\n", out, name, members.join(" | "));
+    }
+
+    out.push_str(&format!("{} {} {{\n", keyword, name));
+    if let Some(values) = t.get("enumValues").and_then(Value::as_array) {
+        for value in values {
+            if let Some(value) = value.get("name").and_then(Value::as_str) {
+                out.push_str(&format!("  {}\n", value));
+            }
+        }
+    }
+    let members = t
+        .get("fields")
+        .and_then(Value::as_array)
+        .or_else(|| t.get("inputFields").and_then(Value::as_array));
+    for field in members.into_iter().flatten() {
+        let field_name = field.get("name").and_then(Value::as_str).unwrap_or("?");
+        let args: Vec<String> = field
+            .get("args")
+            .and_then(Value::as_array)
+            .map(|args| {
+                args.iter()
+                    .map(|arg| {
+                        let mut text = format!(
+                            "{}: {}",
+                            arg.get("name").and_then(Value::as_str).unwrap_or("?"),
+                            type_ref(&arg["type"])
+                        );
+                        if let Some(default) = arg.get("defaultValue").and_then(Value::as_str) {
+                            text.push_str(&format!(" = {}", default));
+                        }
+                        text
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let args = if args.is_empty() {
+            String::new()
+        } else {
+            format!("({})", args.join(", "))
+        };
+        let description = field
+            .get("description")
+            .and_then(Value::as_str)
+            .filter(|d| !d.is_empty())
+            .map(|d| format!("  # {}", truncate_chars(&d.replace('\n', " "), 120, "...")))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "  {}{}: {}{}\n",
+            field_name,
+            args,
+            type_ref(&field["type"]),
+            description
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn named(kind: &str, name: &str) -> Value {
+        json!({"kind": kind, "name": name, "ofType": null})
+    }
+
+    fn non_null(inner: Value) -> Value {
+        json!({"kind": "NON_NULL", "name": null, "ofType": inner})
+    }
+
+    fn schema() -> Value {
+        json!({
+            "queryType": {"name": "Query"},
+            "mutationType": {"name": "Mutation"},
+            "types": [
+                {"kind": "OBJECT", "name": "Query", "description": null, "fields": [
+                    {"name": "user", "description": "Look up a user", "args": [
+                        {"name": "login", "type": non_null(named("SCALAR", "String")), "defaultValue": null}
+                    ], "type": named("OBJECT", "User")}
+                ]},
+                {"kind": "OBJECT", "name": "Mutation", "description": null, "fields": [
+                    {"name": "deleteUser", "description": null, "args": [], "type": named("SCALAR", "Boolean")}
+                ]},
+                {"kind": "OBJECT", "name": "User", "description": "A person", "fields": [
+                    {"name": "login", "description": null, "args": [], "type": non_null(named("SCALAR", "String"))},
+                    {"name": "roles", "description": null, "args": [],
+                     "type": {"kind": "LIST", "name": null, "ofType": named("ENUM", "Role")}}
+                ]},
+                {"kind": "ENUM", "name": "Role", "description": null, "enumValues": [{"name": "ADMIN"}, {"name": "MEMBER"}]},
+                {"kind": "SCALAR", "name": "String", "description": null},
+                {"kind": "OBJECT", "name": "__Schema", "description": null, "fields": []}
+            ]
+        })
+    }
+
+    /// Tests introspection and queries against a mock GraphQL server.
+    #[tokio::test]
+    async fn test_graphql_tool() {
+        let introspections = Arc::new(AtomicUsize::new(0));
+        let counter = introspections.clone();
+        let app = Router::new().route(
+            "/graphql",
+            post(
+                move |headers: axum::http::HeaderMap, Json(body): Json<Value>| {
+                    let counter = counter.clone();
+                    async move {
+                        assert_eq!(headers["authorization"], "Bearer token");
+                        let query = body["query"].as_str().unwrap();
+                        if query.contains("__schema") {
+                            counter.fetch_add(1, Ordering::SeqCst);
+                            return Json(json!({"data": {"__schema": schema()}}));
+                        }
+                        match body["variables"]["login"].as_str() {
+                            Some("ghost") => {
+                                Json(json!({"data": null, "errors": [{"message": "not found"}]}))
+                            }
+                            login => Json(
+                                json!({"data": {"user": {"login": login, "notes": "x".repeat(200)}}}),
+                            ),
+                        }
+                    }
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/graphql", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let tool = GraphQLTool::new(url)
+            .header("Authorization", "Bearer token")
+            .max_result_chars(150);
+
+        let result = tool.execute(json!({"action": "introspect"})).await.unwrap();
+        assert!(result.success);
+        assert!(
+            result.output.contains("user(login: String!): User"),
+            "{}",
+            result.output
+        );
+        assert!(result.output.contains("Mutations are disabled"));
+        assert!(!result.output.contains("deleteUser"));
+        assert!(!result.output.contains("__Schema"));
+
+        let result = tool
+            .execute(json!({"action": "introspect", "type_name": "User"}))
+            .await
+            .unwrap();
+        assert!(result
+            .output
+            .contains("# A person\ntype User {\n  login: String!\n  roles: [Role]\n}"));
+        assert_eq!(introspections.load(Ordering::SeqCst), 1);
+
+        let result = tool
+            .execute(json!({
+                "action": "query",
+                "query": "query($login: String!) { user(login: $login) { login notes } }",
+                "variables": "{\"login\": \"ada\"}"
+            }))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result.output.contains("\"login\": \"ada\""));
+        assert!(result.output.contains("truncated to 150 characters"));
+
+        let result = tool
+            .execute(json!({"action": "query", "query": "query { user(login: $l) { login } }", "variables": {"login": "ghost"}}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("not found"));
+
+        let mutation = json!({"action": "query", "query": "mutation { deleteUser }"});
+        assert_eq!(tool.call_permission(&mutation), ToolPermission::Destructive);
+        let result = tool.execute(mutation).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("Mutations are disabled"));
+    }
+
+    /// Tests telling queries from mutations.
+    #[test]
+    fn test_is_mutation() {
+        assert!(!is_mutation("{ user { login } }", None));
+        assert!(!is_mutation("query Q { mutation: user { login } }", None));
+        assert!(!is_mutation(
+            "# mutation\nquery { a(s: \"mutation {\") }",
+            None
+        ));
+        assert!(is_mutation("mutation M { deleteUser }", None));
+        let both = "query Q { a } mutation M { b }";
+        assert!(is_mutation(both, None));
+        assert!(!is_mutation(both, Some("Q")));
+        assert!(is_mutation(both, Some("M")));
+    }
+}
@@ -0,0 +1,451 @@
+//! # Image Generation Module
+//!
+//! `ImageGenerationTool` lets an agent produce illustrations and diagrams. It
+//! calls an OpenAI-compatible `/images/generations` endpoint (DALL·E, gpt-image-1
+//! and servers that mimic them) or Stability AI's Stable Image API, saves every
+//! image to an output directory, and returns the file paths so later steps can
+//! attach or reference them.
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{required_str, Tool, ToolParameter, ToolPermission, ToolResult};
+use async_trait::async_trait;
+use base64::Engine;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// The API an `ImageGenerationTool` talks to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageProvider {
+    /// An OpenAI-compatible `/images/generations` endpoint.
+    OpenAI,
+    /// Stability AI's `/v2beta/stable-image/generate/{model}` endpoint.
+    Stability,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIImagesResponse {
+    data: Vec<OpenAIImage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIImage {
+    #[serde(default)]
+    b64_json: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    revised_prompt: Option<String>,
+}
+
+/// One generated image.
+struct GeneratedImage {
+    data: Vec<u8>,
+    url: Option<String>,
+    revised_prompt: Option<String>,
+}
+
+/// A tool that generates images from text prompts and saves them to disk.
+///
+/// ```rust,no_run
+/// use helios_engine::{Agent, Config, ImageGenerationTool};
+///
+/// # async fn example() -> helios_engine::Result<()> {
+/// let config = Config::from_file("config.toml")?;
+/// let images = ImageGenerationTool::openai(&config.llm.base_url, &config.llm.api_key)
+///     .with_model("dall-e-3")
+///     .with_output_dir("./images");
+/// let agent = Agent::builder("Illustrator")
+///     .config(config)
+///     .tool(Box::new(images))
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ImageGenerationTool {
+    provider: ImageProvider,
+    base_url: String,
+    api_key: String,
+    model: String,
+    output_dir: PathBuf,
+    default_size: String,
+    client: Client,
+}
+
+impl ImageGenerationTool {
+    /// Creates a tool for the OpenAI-compatible API at `base_url` (for example
+    /// `https://api.openai.com/v1`), using `dall-e-3` at 1024x1024.
+    pub fn openai(base_url: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self {
+            provider: ImageProvider::OpenAI,
+            base_url: base_url.into(),
+            api_key: api_key.into(),
+            model: "dall-e-3".to_string(),
+            output_dir: PathBuf::from("generated_images"),
+            default_size: "1024x1024".to_string(),
+            client: Client::new(),
+        }
+    }
+
+    /// Creates a tool for Stability AI's Stable Image API, using the `core` model.
+    pub fn stability(api_key: impl Into<String>) -> Self {
+        Self {
+            provider: ImageProvider::Stability,
+            base_url: "https://api.stability.ai".to_string(),
+            model: "core".to_string(),
+            ..Self::openai("", api_key)
+        }
+    }
+
+    /// Sets the base URL, for proxies and self-hosted servers.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Sets the model, such as `dall-e-3` or `gpt-image-1` for OpenAI, or
+    /// `core`, `ultra` or `sd3` for Stability.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Sets the directory images are saved in (`generated_images` by default).
+    pub fn with_output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = dir.into();
+        self
+    }
+
+    /// Sets the OpenAI image size used when a call does not give one.
+    pub fn with_size(mut self, size: impl Into<String>) -> Self {
+        self.default_size = size.into();
+        self
+    }
+
+    /// Returns the API the tool talks to.
+    pub fn provider(&self) -> ImageProvider {
+        self.provider
+    }
+
+    async fn generate_openai(&self, prompt: &str, args: &Value) -> Result<Vec<GeneratedImage>> {
+        let size = args
+            .get("size")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&self.default_size);
+        let count = args
+            .get("count")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1)
+            .clamp(1, 4);
+        let mut request = json!({
+            "model": self.model,
+            "prompt": prompt,
+            "size": size,
+            "n": count,
+        });
+        // gpt-image-1 always returns base64 and rejects the parameter.
+        if self.model.starts_with("dall-e") {
+            request["response_format"] = json!("b64_json");
+        }
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/images/generations",
+                self.base_url.trim_end_matches('/')
+            ))
+            .bearer_auth(&self.api_key)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Image API error: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(api_error(response).await);
+        }
+        let images: OpenAIImagesResponse = response.json().await.map_err(|e| {
+            HeliosError::ToolError(format!("Failed to parse image response: {}", e))
+        })?;
+
+        let mut generated = Vec::new();
+        for image in images.data {
+            let data = match (&image.b64_json, &image.url) {
+                (Some(b64), _) => base64::engine::general_purpose::STANDARD
+                    .decode(b64)
+                    .map_err(|e| HeliosError::ToolError(format!("Invalid image data: {}", e)))?,
+                (None, Some(url)) => self
+                    .client
+                    .get(url)
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .map_err(|e| {
+                        HeliosError::ToolError(format!("Failed to download image: {}", e))
+                    })?
+                    .bytes()
+                    .await
+                    .map_err(|e| {
+                        HeliosError::ToolError(format!("Failed to download image: {}", e))
+                    })?
+                    .to_vec(),
+                (None, None) => continue,
+            };
+            generated.push(GeneratedImage {
+                data,
+                url: image.url,
+                revised_prompt: image.revised_prompt,
+            });
+        }
+        Ok(generated)
+    }
+
+    async fn generate_stability(&self, prompt: &str, args: &Value) -> Result<Vec<GeneratedImage>> {
+        let mut form = reqwest::multipart::Form::new()
+            .text("prompt", prompt.to_string())
+            .text("output_format", "png");
+        for key in ["negative_prompt", "aspect_ratio"] {
+            if let Some(value) = args.get(key).and_then(|v| v.as_str()) {
+                form = form.text(key, value.to_string());
+            }
+        }
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/v2beta/stable-image/generate/{}",
+                self.base_url.trim_end_matches('/'),
+                self.model
+            ))
+            .bearer_auth(&self.api_key)
+            .header(reqwest::header::ACCEPT, "image/*")
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Image API error: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(api_error(response).await);
+        }
+        let data = response
+            .bytes()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Failed to read image: {}", e)))?;
+        Ok(vec![GeneratedImage {
+            data: data.to_vec(),
+            url: None,
+            revised_prompt: None,
+        }])
+    }
+}
+
+/// Reads the error body of a failed image API response.
+async fn api_error(response: reqwest::Response) -> HeliosError {
+    let status = response.status();
+    let error_text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+    HeliosError::ToolError(format!(
+        "Image generation failed ({}): {}",
+        status, error_text
+    ))
+}
+
+/// Returns the file extension for encoded image data, from its magic bytes.
+fn image_extension(data: &[u8]) -> &'static str {
+    if data.starts_with(b"\xFF\xD8\xFF") {
+        "jpg"
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        "webp"
+    } else {
+        "png"
+    }
+}
+
+#[async_trait]
+impl Tool for ImageGenerationTool {
+    fn name(&self) -> &str {
+        "image_generation"
+    }
+
+    fn description(&self) -> &str {
+        "Generate images such as illustrations and diagrams from a detailed text description. \
+         The images are saved to disk and their file paths are returned."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        params.insert(
+            "prompt".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "A detailed description of the image".to_string(),
+                required: Some(true),
+            },
+        );
+        match self.provider {
+            ImageProvider::OpenAI => {
+                params.insert(
+                    "size".to_string(),
+                    ToolParameter {
+                        param_type: "string".to_string(),
+                        description: format!(
+                            "Image size, such as 1024x1024, 1792x1024 or 1024x1792 (default: {})",
+                            self.default_size
+                        ),
+                        required: Some(false),
+                    },
+                );
+                params.insert(
+                    "count".to_string(),
+                    ToolParameter {
+                        param_type: "number".to_string(),
+                        description: "How many images to generate, 1 to 4 (default: 1)".to_string(),
+                        required: Some(false),
+                    },
+                );
+            }
+            ImageProvider::Stability => {
+                params.insert(
+                    "aspect_ratio".to_string(),
+                    ToolParameter {
+                        param_type: "string".to_string(),
+                        description: "Aspect ratio, such as 1:1, 16:9 or 9:16 (default: 1:1)"
+                            .to_string(),
+                        required: Some(false),
+                    },
+                );
+                params.insert(
+                    "negative_prompt".to_string(),
+                    ToolParameter {
+                        param_type: "string".to_string(),
+                        description: "What the image should not contain".to_string(),
+                        required: Some(false),
+                    },
+                );
+            }
+        }
+        params
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Standard
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let prompt = required_str(&args, "prompt")?;
+        if prompt.trim().is_empty() {
+            return Err(HeliosError::ToolError(
+                "'prompt' parameter cannot be empty".to_string(),
+            ));
+        }
+
+        let images = match self.provider {
+            ImageProvider::OpenAI => self.generate_openai(prompt, &args).await?,
+            ImageProvider::Stability => self.generate_stability(prompt, &args).await?,
+        };
+        if images.is_empty() {
+            return Ok(ToolResult::error("The API returned no images"));
+        }
+
+        std::fs::create_dir_all(&self.output_dir)?;
+        let stamp = crate::clock::now().format("%Y%m%d-%H%M%S%.3f");
+        let mut lines = Vec::new();
+        for (i, image) in images.iter().enumerate() {
+            let path = self.output_dir.join(format!(
+                "image-{}-{}.{}",
+                stamp,
+                i + 1,
+                image_extension(&image.data)
+            ));
+            std::fs::write(&path, &image.data)?;
+            let mut line = format!("Saved {}", path.display());
+            if let Some(url) = &image.url {
+                line.push_str(&format!(" (from {})", url));
+            }
+            if let Some(revised) = &image.revised_prompt {
+                line.push_str(&format!("\n  Revised prompt: {}", revised));
+            }
+            lines.push(line);
+        }
+        Ok(ToolResult::success(lines.join("\n")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\nfake";
+
+    async fn mock_server() -> String {
+        let app = Router::new()
+            .route(
+                "/images/generations",
+                post(|Json(body): Json<Value>| async move {
+                    assert_eq!(body["response_format"], "b64_json");
+                    let image = base64::engine::general_purpose::STANDARD.encode(PNG);
+                    Json(json!({"data": [{
+                        "b64_json": image,
+                        "revised_prompt": format!("{}, watercolor", body["prompt"].as_str().unwrap())
+                    }]}))
+                }),
+            )
+            .route(
+                "/v2beta/stable-image/generate/core",
+                post(|| async { b"\xFF\xD8\xFFjpeg".to_vec() }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    /// Tests generating an image with an OpenAI-compatible API and saving it.
+    #[tokio::test]
+    async fn test_image_generation_openai() {
+        let url = mock_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let tool = ImageGenerationTool::openai(url, "key").with_output_dir(dir.path());
+
+        let result = tool
+            .execute(json!({"prompt": "a lighthouse"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result
+            .output
+            .contains("Revised prompt: a lighthouse, watercolor"));
+
+        let files: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].extension().unwrap(), "png");
+        assert_eq!(std::fs::read(&files[0]).unwrap(), PNG);
+        assert!(result.output.contains(&files[0].display().to_string()));
+    }
+
+    /// Tests generating an image with the Stability API.
+    #[tokio::test]
+    async fn test_image_generation_stability() {
+        let url = mock_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let tool = ImageGenerationTool::stability("key")
+            .with_base_url(url)
+            .with_output_dir(dir.path());
+        assert!(tool.parameters().contains_key("aspect_ratio"));
+
+        let result = tool
+            .execute(json!({"prompt": "a lighthouse", "aspect_ratio": "16:9"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result.output.ends_with(".jpg"));
+
+        assert!(tool.execute(json!({"prompt": " "})).await.is_err());
+    }
+}
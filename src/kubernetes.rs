@@ -0,0 +1,821 @@
+//! # Kubernetes Module
+//!
+//! `KubernetesTool` talks to the Kubernetes API directly, so an SRE agent can inspect
+//! a cluster without shelling out to `kubectl`. It lists resources, fetches pod logs
+//! and describes objects together with their recent events. With `allow_apply` it
+//! can also server-side apply manifests; those calls are `Destructive`, so they go
+//! through the agent's approval handler. A server-side dry run is read-only.
+//!
+//! Secrets are never listed or described.
+//!
+//! Requires the `kubernetes` feature.
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{required_str, str_at, table, Tool, ToolParameter, ToolPermission, ToolResult};
+use crate::truncation::{truncate_chars, truncate_chars_tail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{Api, DynamicObject, ListParams, LogParams, Patch, PatchParams};
+use kube::core::{ApiResource, GroupVersionKind};
+use kube::discovery::Scope;
+use kube::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The field manager recorded on objects the tool applies.
+const FIELD_MANAGER: &str = "helios-engine";
+
+/// A resource kind: names the agent may use, group, version, kind, plural, namespaced.
+type KindEntry = (
+    &'static [&'static str],
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    bool,
+);
+
+/// Resource kinds the tool can list and describe.
+#[rustfmt::skip]
+const KINDS: &[KindEntry] = &[
+    (&["pod", "pods", "po"], "", "v1", "Pod", "pods", true),
+    (&["service", "services", "svc"], "", "v1", "Service", "services", true),
+    (&["configmap", "configmaps", "cm"], "", "v1", "ConfigMap", "configmaps", true),
+    (&["persistentvolumeclaim", "persistentvolumeclaims", "pvc"], "", "v1", "PersistentVolumeClaim", "persistentvolumeclaims", true),
+    (&["event", "events", "ev"], "", "v1", "Event", "events", true),
+    (&["node", "nodes", "no"], "", "v1", "Node", "nodes", false),
+    (&["namespace", "namespaces", "ns"], "", "v1", "Namespace", "namespaces", false),
+    (&["deployment", "deployments", "deploy"], "apps", "v1", "Deployment", "deployments", true),
+    (&["replicaset", "replicasets", "rs"], "apps", "v1", "ReplicaSet", "replicasets", true),
+    (&["statefulset", "statefulsets", "sts"], "apps", "v1", "StatefulSet", "statefulsets", true),
+    (&["daemonset", "daemonsets", "ds"], "apps", "v1", "DaemonSet", "daemonsets", true),
+    (&["job", "jobs"], "batch", "v1", "Job", "jobs", true),
+    (&["cronjob", "cronjobs", "cj"], "batch", "v1", "CronJob", "cronjobs", true),
+    (&["ingress", "ingresses", "ing"], "networking.k8s.io", "v1", "Ingress", "ingresses", true),
+];
+
+/// A tool for inspecting, and optionally changing, a Kubernetes cluster.
+///
+/// ```rust,no_run
+/// use helios_engine::KubernetesTool;
+///
+/// # async fn example() -> helios_engine::Result<()> {
+/// // Uses ~/.kube/config, or the service account when running in a cluster.
+/// let tool = KubernetesTool::new().await?.namespace("production");
+/// # Ok(())
+/// # }
+/// ```
+pub struct KubernetesTool {
+    client: Client,
+    namespace: String,
+    allow_apply: bool,
+    max_log_lines: i64,
+    max_output_chars: usize,
+}
+
+impl KubernetesTool {
+    /// Connects using the local kubeconfig or the in-cluster service account.
+    pub async fn new() -> Result<Self> {
+        let client = Client::try_default().await.map_err(|e| {
+            HeliosError::ConfigError(format!("Could not load Kubernetes config: {}", e))
+        })?;
+        Ok(Self::from_client(client))
+    }
+
+    /// Uses an existing client, starting in its default namespace.
+    pub fn from_client(client: Client) -> Self {
+        Self {
+            namespace: client.default_namespace().to_string(),
+            client,
+            allow_apply: false,
+            max_log_lines: 200,
+            max_output_chars: 20_000,
+        }
+    }
+
+    /// Sets the namespace used when a call does not name one.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Lets the agent apply manifests. Each apply needs approval.
+    pub fn allow_apply(mut self, allow: bool) -> Self {
+        self.allow_apply = allow;
+        self
+    }
+
+    /// Sets how many log lines `logs` returns by default (200).
+    pub fn max_log_lines(mut self, lines: i64) -> Self {
+        self.max_log_lines = lines;
+        self
+    }
+
+    /// Limits the characters of output returned to the agent (20,000 by default).
+    pub fn max_output_chars(mut self, max: usize) -> Self {
+        self.max_output_chars = max;
+        self
+    }
+
+    /// Looks up a kind by name, returning its API resource and whether it is namespaced.
+    fn resource(kind: &str) -> std::result::Result<(ApiResource, bool), String> {
+        let lower = kind.to_lowercase();
+        if matches!(lower.as_str(), "secret" | "secrets") {
+            return Err("Secrets are not available through this tool".to_string());
+        }
+        KINDS
+            .iter()
+            .find(|(names, ..)| names.contains(&lower.as_str()))
+            .map(|(_, group, version, kind, plural, namespaced)| {
+                let gvk = GroupVersionKind::gvk(group, version, kind);
+                (ApiResource::from_gvk_with_plural(&gvk, plural), *namespaced)
+            })
+            .ok_or_else(|| {
+                let known: Vec<&str> = KINDS.iter().map(|(names, ..)| names[1]).collect();
+                format!("Unknown kind '{}'; use one of: {}", kind, known.join(", "))
+            })
+    }
+
+    fn api(&self, resource: &ApiResource, namespaced: bool, namespace: &str) -> Api<DynamicObject> {
+        if !namespaced || namespace == "all" || namespace == "*" {
+            Api::all_with(self.client.clone(), resource)
+        } else {
+            Api::namespaced_with(self.client.clone(), namespace, resource)
+        }
+    }
+
+    async fn list(&self, args: &Value) -> Result<ToolResult> {
+        let kind = required_str(args, "kind")?;
+        let (resource, namespaced) = match Self::resource(kind) {
+            Ok(found) => found,
+            Err(e) => return Ok(ToolResult::error(e)),
+        };
+        let namespace = args
+            .get("namespace")
+            .and_then(Value::as_str)
+            .unwrap_or(&self.namespace);
+        let all_namespaces = namespaced && (namespace == "all" || namespace == "*");
+
+        let mut params = ListParams::default();
+        if let Some(selector) = args.get("label_selector").and_then(Value::as_str) {
+            params = params.labels(selector);
+        }
+        let list = match self
+            .api(&resource, namespaced, namespace)
+            .list(&params)
+            .await
+        {
+            Ok(list) => list,
+            Err(e) => return Ok(api_error(e)),
+        };
+        if list.items.is_empty() {
+            return Ok(ToolResult::success(format!(
+                "No {} found{}",
+                resource.plural,
+                if namespaced && !all_namespaces {
+                    format!(" in namespace {}", namespace)
+                } else {
+                    String::new()
+                }
+            )));
+        }
+
+        let now = Utc::now();
+        let mut rows = Vec::new();
+        for item in &list.items {
+            let object = serde_json::to_value(item)?;
+            let mut row = Vec::new();
+            if all_namespaces {
+                row.push(str_at(&object, "/metadata/namespace").to_string());
+            }
+            row.push(str_at(&object, "/metadata/name").to_string());
+            row.extend(status_columns(&resource.kind, &object));
+            row.push(age(&object, now));
+            rows.push(row);
+        }
+        let mut header: Vec<&str> = Vec::new();
+        if all_namespaces {
+            header.push("NAMESPACE");
+        }
+        header.push("NAME");
+        header.extend(status_header(&resource.kind));
+        header.push("AGE");
+        Ok(ToolResult::success(
+            self.limit(table(&header, &rows), false),
+        ))
+    }
+
+    async fn logs(&self, args: &Value) -> Result<ToolResult> {
+        let pod = required_str(args, "name")?;
+        let namespace = args
+            .get("namespace")
+            .and_then(Value::as_str)
+            .unwrap_or(&self.namespace);
+        let params = LogParams {
+            container: args
+                .get("container")
+                .and_then(Value::as_str)
+                .map(String::from),
+            previous: args
+                .get("previous")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            tail_lines: Some(
+                args.get("tail_lines")
+                    .and_then(Value::as_i64)
+                    .unwrap_or(self.max_log_lines),
+            ),
+            ..LogParams::default()
+        };
+        let pods: Api<Pod> = Api::namespaced(self.client.clone(), namespace);
+        match pods.logs(pod, &params).await {
+            Ok(logs) if logs.is_empty() => Ok(ToolResult::success("(no log output)")),
+            Ok(logs) => Ok(ToolResult::success(self.limit(logs, true))),
+            Err(e) => Ok(api_error(e)),
+        }
+    }
+
+    async fn describe(&self, args: &Value) -> Result<ToolResult> {
+        let kind = required_str(args, "kind")?;
+        let name = required_str(args, "name")?;
+        let (resource, namespaced) = match Self::resource(kind) {
+            Ok(found) => found,
+            Err(e) => return Ok(ToolResult::error(e)),
+        };
+        let namespace = args
+            .get("namespace")
+            .and_then(Value::as_str)
+            .unwrap_or(&self.namespace);
+        let object = match self.api(&resource, namespaced, namespace).get(name).await {
+            Ok(object) => object,
+            Err(e) => return Ok(api_error(e)),
+        };
+        let mut object = serde_json::to_value(&object)?;
+        if let Some(metadata) = object.get_mut("metadata").and_then(Value::as_object_mut) {
+            metadata.remove("managedFields");
+            if let Some(annotations) = metadata
+                .get_mut("annotations")
+                .and_then(Value::as_object_mut)
+            {
+                annotations.remove("kubectl.kubernetes.io/last-applied-configuration");
+            }
+        }
+        let mut out = serde_norway::to_string(&object)
+            .map_err(|e| HeliosError::ToolError(format!("Could not render object: {}", e)))?;
+
+        // Events for cluster-scoped objects are recorded in the default namespace.
+        let events_namespace = if namespaced { namespace } else { "default" };
+        let events: Api<DynamicObject> = Api::namespaced_with(
+            self.client.clone(),
+            events_namespace,
+            &Self::resource("event").map_err(HeliosError::ToolError)?.0,
+        );
+        let selector = format!(
+            "involvedObject.name={},involvedObject.kind={}",
+            name, resource.kind
+        );
+        if let Ok(list) = events.list(&ListParams::default().fields(&selector)).await {
+            let mut events: Vec<Value> = list
+                .items
+                .iter()
+                .filter_map(|event| serde_json::to_value(event).ok())
+                .collect();
+            events.sort_by_key(event_time);
+            out.push_str("\nEvents:\n");
+            if events.is_empty() {
+                out.push_str("  <none>\n");
+            }
+            let now = Utc::now();
+            for event in events.iter().rev().take(20).rev() {
+                let ago = event_time(event)
+                    .map(|time| format_age(now - time))
+                    .unwrap_or_else(|| "?".to_string());
+                let count = event.get("count").and_then(Value::as_i64).unwrap_or(1);
+                out.push_str(&format!(
+                    "  {} ago  {}  {}{}: {}\n",
+                    ago,
+                    str_at(event, "/type"),
+                    str_at(event, "/reason"),
+                    if count > 1 {
+                        format!(" (x{})", count)
+                    } else {
+                        String::new()
+                    },
+                    str_at(event, "/message").trim()
+                ));
+            }
+        }
+        Ok(ToolResult::success(self.limit(out, false)))
+    }
+
+    async fn apply(&self, args: &Value) -> Result<ToolResult> {
+        if !self.allow_apply {
+            return Ok(ToolResult::error(
+                "Applying manifests is disabled for this tool",
+            ));
+        }
+        let manifest = required_str(args, "manifest")?;
+        let dry_run = args
+            .get("dry_run")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let mut params = PatchParams::apply(FIELD_MANAGER);
+        params.dry_run = dry_run;
+
+        let mut documents = Vec::new();
+        for document in serde_norway::Deserializer::from_str(manifest) {
+            match Value::deserialize(document) {
+                Ok(Value::Null) => {}
+                Ok(value) => documents.push(value),
+                Err(e) => return Ok(ToolResult::error(format!("Invalid manifest: {}", e))),
+            }
+        }
+        if documents.is_empty() {
+            return Ok(ToolResult::error("The manifest has no objects"));
+        }
+
+        let mut applied = Vec::new();
+        for mut document in documents {
+            let (Some(api_version), Some(kind), Some(name)) = (
+                document.get("apiVersion").and_then(Value::as_str),
+                document.get("kind").and_then(Value::as_str),
+                document.pointer("/metadata/name").and_then(Value::as_str),
+            ) else {
+                return Ok(ToolResult::error(
+                    "Every object needs apiVersion, kind and metadata.name",
+                ));
+            };
+            let (name, kind) = (name.to_string(), kind.to_string());
+            if kind == "Secret" {
+                return Ok(ToolResult::error(
+                    "Secrets are not available through this tool",
+                ));
+            }
+            let (group, version) = api_version.split_once('/').unwrap_or(("", api_version));
+            let gvk = GroupVersionKind::gvk(group, version, &kind);
+            let (resource, capabilities) =
+                match kube::discovery::pinned_kind(&self.client, &gvk).await {
+                    Ok(found) => found,
+                    Err(e) => return Ok(api_error(e)),
+                };
+            let api: Api<DynamicObject> = if capabilities.scope == Scope::Namespaced {
+                let namespace = document
+                    .pointer("/metadata/namespace")
+                    .and_then(Value::as_str)
+                    .unwrap_or(&self.namespace)
+                    .to_string();
+                document["metadata"]["namespace"] = Value::String(namespace.clone());
+                Api::namespaced_with(self.client.clone(), &namespace, &resource)
+            } else {
+                Api::all_with(self.client.clone(), &resource)
+            };
+            if let Err(e) = api.patch(&name, &params, &Patch::Apply(&document)).await {
+                let mut result = api_error(e);
+                if !applied.is_empty() {
+                    result.output = format!("{}\n{}", applied.join("\n"), result.output);
+                }
+                return Ok(result);
+            }
+            applied.push(format!(
+                "{}/{} applied{}",
+                kind.to_lowercase(),
+                name,
+                if dry_run { " (server dry run)" } else { "" }
+            ));
+        }
+        Ok(ToolResult::success(applied.join("\n")))
+    }
+
+    /// Truncates output, keeping the end when `tail` is set (as for logs).
+    fn limit(&self, text: String, tail: bool) -> String {
+        let max = self.max_output_chars;
+        if tail {
+            truncate_chars_tail(&text, max, "... (earlier output omitted)\n")
+        } else {
+            truncate_chars(
+                &text,
+                max,
+                &format!("\n... (truncated to {} characters)", max),
+            )
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for KubernetesTool {
+    fn name(&self) -> &str {
+        "kubernetes"
+    }
+
+    fn description(&self) -> &str {
+        "Inspect a Kubernetes cluster. Actions: 'list' resources of a kind, 'logs' of a pod, \
+         'describe' one object with its recent events, and 'apply' a YAML manifest \
+         (when enabled). Use namespace 'all' to list across namespaces."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        let mut add = |name: &str, param_type: &str, description: &str, required: bool| {
+            params.insert(
+                name.to_string(),
+                ToolParameter {
+                    param_type: param_type.to_string(),
+                    description: description.to_string(),
+                    required: Some(required),
+                },
+            );
+        };
+        add(
+            "action",
+            "string",
+            "One of: list, logs, describe, apply",
+            true,
+        );
+        add(
+            "kind",
+            "string",
+            "For list and describe: pods, deployments, services, nodes, events, ...",
+            false,
+        );
+        add(
+            "name",
+            "string",
+            "For describe: the object's name. For logs: the pod's name",
+            false,
+        );
+        add(
+            "namespace",
+            "string",
+            "Namespace to use instead of the default ('all' for list)",
+            false,
+        );
+        add(
+            "label_selector",
+            "string",
+            "For list: a label selector such as 'app=web'",
+            false,
+        );
+        add(
+            "container",
+            "string",
+            "For logs: the container, if the pod has several",
+            false,
+        );
+        add(
+            "tail_lines",
+            "integer",
+            "For logs: how many lines from the end to return",
+            false,
+        );
+        add(
+            "previous",
+            "boolean",
+            "For logs: read the previous (crashed) container's logs",
+            false,
+        );
+        add(
+            "manifest",
+            "string",
+            "For apply: YAML, possibly with several documents",
+            false,
+        );
+        add(
+            "dry_run",
+            "boolean",
+            "For apply: validate on the server without changing anything",
+            false,
+        );
+        params
+    }
+
+    fn permission(&self) -> ToolPermission {
+        if self.allow_apply {
+            ToolPermission::Destructive
+        } else {
+            ToolPermission::ReadOnly
+        }
+    }
+
+    fn call_permission(&self, args: &Value) -> ToolPermission {
+        let dry_run = args
+            .get("dry_run")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        if args.get("action").and_then(Value::as_str) == Some("apply") && !dry_run {
+            ToolPermission::Destructive
+        } else {
+            ToolPermission::ReadOnly
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        match required_str(&args, "action")? {
+            "list" => self.list(&args).await,
+            "logs" => self.logs(&args).await,
+            "describe" => self.describe(&args).await,
+            "apply" => self.apply(&args).await,
+            other => Ok(ToolResult::error(format!(
+                "Unknown action '{}'; use list, logs, describe or apply",
+                other
+            ))),
+        }
+    }
+}
+
+fn api_error(error: kube::Error) -> ToolResult {
+    match error {
+        kube::Error::Api(response) => {
+            ToolResult::error(format!("Kubernetes API error: {}", response.message))
+        }
+        other => ToolResult::error(format!("Kubernetes request failed: {}", other)),
+    }
+}
+
+fn i64_at(value: &Value, pointer: &str) -> i64 {
+    value.pointer(pointer).and_then(Value::as_i64).unwrap_or(0)
+}
+
+fn status_header(kind: &str) -> Vec<&'static str> {
+    match kind {
+        "Pod" => vec!["READY", "STATUS", "RESTARTS", "NODE"],
+        "Deployment" | "StatefulSet" | "ReplicaSet" => vec!["READY", "UP-TO-DATE", "AVAILABLE"],
+        "DaemonSet" => vec!["DESIRED", "READY"],
+        "Service" => vec!["TYPE", "CLUSTER-IP"],
+        "Node" => vec!["STATUS", "VERSION"],
+        "Namespace" | "PersistentVolumeClaim" => vec!["STATUS"],
+        "Job" => vec!["SUCCEEDED", "FAILED"],
+        "Event" => vec!["TYPE", "REASON", "MESSAGE"],
+        _ => Vec::new(),
+    }
+}
+
+fn status_columns(kind: &str, object: &Value) -> Vec<String> {
+    match kind {
+        "Pod" => {
+            let statuses = object
+                .pointer("/status/containerStatuses")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default();
+            let ready = statuses
+                .iter()
+                .filter(|s| s.get("ready").and_then(Value::as_bool) == Some(true))
+                .count();
+            let restarts: i64 = statuses.iter().map(|s| i64_at(s, "/restartCount")).sum();
+            // A waiting container's reason (CrashLoopBackOff, ...) says more than the phase.
+            let status = statuses
+                .iter()
+                .find_map(|s| s.pointer("/state/waiting/reason").and_then(Value::as_str))
+                .unwrap_or_else(|| str_at(object, "/status/phase"));
+            vec![
+                format!("{}/{}", ready, statuses.len()),
+                status.to_string(),
+                restarts.to_string(),
+                str_at(object, "/spec/nodeName").to_string(),
+            ]
+        }
+        "Deployment" | "StatefulSet" | "ReplicaSet" => vec![
+            format!(
+                "{}/{}",
+                i64_at(object, "/status/readyReplicas"),
+                i64_at(object, "/spec/replicas")
+            ),
+            i64_at(object, "/status/updatedReplicas").to_string(),
+            i64_at(object, "/status/availableReplicas").to_string(),
+        ],
+        "DaemonSet" => vec![
+            i64_at(object, "/status/desiredNumberScheduled").to_string(),
+            i64_at(object, "/status/numberReady").to_string(),
+        ],
+        "Service" => vec![
+            str_at(object, "/spec/type").to_string(),
+            str_at(object, "/spec/clusterIP").to_string(),
+        ],
+        "Node" => {
+            let ready = object
+                .pointer("/status/conditions")
+                .and_then(Value::as_array)
+                .and_then(|conditions| {
+                    conditions
+                        .iter()
+                        .find(|c| c.get("type").and_then(Value::as_str) == Some("Ready"))
+                })
+                .map(|c| str_at(c, "/status") == "True");
+            vec![
+                match ready {
+                    Some(true) => "Ready",
+                    Some(false) => "NotReady",
+                    None => "Unknown",
+                }
+                .to_string(),
+                str_at(object, "/status/nodeInfo/kubeletVersion").to_string(),
+            ]
+        }
+        "Namespace" | "PersistentVolumeClaim" => {
+            vec![str_at(object, "/status/phase").to_string()]
+        }
+        "Job" => vec![
+            i64_at(object, "/status/succeeded").to_string(),
+            i64_at(object, "/status/failed").to_string(),
+        ],
+        "Event" => vec![
+            str_at(object, "/type").to_string(),
+            str_at(object, "/reason").to_string(),
+            str_at(object, "/message").trim().to_string(),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+fn event_time(event: &Value) -> Option<DateTime<Utc>> {
+    [
+        "/lastTimestamp",
+        "/eventTime",
+        "/metadata/creationTimestamp",
+    ]
+    .iter()
+    .find_map(|pointer| event.pointer(pointer).and_then(Value::as_str))
+    .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+    .map(|time| time.with_timezone(&Utc))
+}
+
+fn age(object: &Value, now: DateTime<Utc>) -> String {
+    DateTime::parse_from_rfc3339(str_at(object, "/metadata/creationTimestamp"))
+        .map(|created| format_age(now - created.with_timezone(&Utc)))
+        .unwrap_or_else(|_| "?".to_string())
+}
+
+/// Formats a duration the way `kubectl` shows ages: `45s`, `12m`, `3h`, `5d`.
+fn format_age(duration: chrono::Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    match seconds {
+        s if s < 120 => format!("{}s", s),
+        s if s < 7200 => format!("{}m", s / 60),
+        s if s < 172_800 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Path, Query};
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    /// Tests list, logs, describe and apply against a mock API server.
+    #[tokio::test]
+    async fn test_kubernetes_tool() {
+        let created = (Utc::now() - chrono::Duration::hours(3)).to_rfc3339();
+        let pod = json!({
+            "apiVersion": "v1", "kind": "Pod",
+            "metadata": {"name": "web-1", "namespace": "default", "creationTimestamp": created,
+                         "managedFields": [{"manager": "kubectl"}]},
+            "spec": {"nodeName": "node-a"},
+            "status": {"phase": "Running", "containerStatuses": [
+                {"name": "web", "ready": false, "restartCount": 4,
+                 "state": {"waiting": {"reason": "CrashLoopBackOff"}}}
+            ]}
+        });
+        let applied = Arc::new(Mutex::new(Vec::<(String, Value)>::new()));
+
+        let pods = pod.clone();
+        let one_pod = pod.clone();
+        let patches = applied.clone();
+        let app = Router::new()
+            .route(
+                "/api/v1/namespaces/default/pods",
+                get(move || {
+                    let pod = pods.clone();
+                    async move { Json(json!({"apiVersion": "v1", "kind": "PodList", "metadata": {}, "items": [pod]})) }
+                }),
+            )
+            .route(
+                "/api/v1/namespaces/default/pods/{name}",
+                get(move |Path(name): Path<String>| {
+                    let pod = one_pod.clone();
+                    async move {
+                        if name == "web-1" {
+                            (axum::http::StatusCode::OK, Json(pod))
+                        } else {
+                            (
+                                axum::http::StatusCode::NOT_FOUND,
+                                Json(json!({"kind": "Status", "apiVersion": "v1", "metadata": {},
+                                            "status": "Failure", "message": format!("pods \"{}\" not found", name),
+                                            "reason": "NotFound", "code": 404})),
+                            )
+                        }
+                    }
+                }),
+            )
+            .route(
+                "/api/v1/namespaces/default/pods/{name}/log",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    assert_eq!(query.get("tailLines").map(String::as_str), Some("2"));
+                    "panic: database unreachable\nexit status 1\n"
+                }),
+            )
+            .route(
+                "/api/v1/namespaces/default/events",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    assert!(query["fieldSelector"].contains("involvedObject.name=web-1"));
+                    Json(json!({"apiVersion": "v1", "kind": "EventList", "metadata": {}, "items": [{
+                        "metadata": {"name": "web-1.1"}, "type": "Warning", "reason": "BackOff",
+                        "message": "Back-off restarting failed container", "count": 12,
+                        "lastTimestamp": Utc::now().to_rfc3339()
+                    }]}))
+                }),
+            )
+            .route(
+                "/apis/apps/v1",
+                get(|| async {
+                    Json(json!({"kind": "APIResourceList", "groupVersion": "apps/v1", "resources": [
+                        {"name": "deployments", "singularName": "deployment", "namespaced": true,
+                         "kind": "Deployment", "verbs": ["get", "list", "patch"]}
+                    ]}))
+                }),
+            )
+            .route(
+                "/apis/apps/v1/namespaces/default/deployments/{name}",
+                axum::routing::patch(
+                    move |Path(name): Path<String>,
+                          Query(query): Query<HashMap<String, String>>,
+                          body: String| {
+                        let patches = patches.clone();
+                        async move {
+                            assert_eq!(query["fieldManager"], FIELD_MANAGER);
+                            let body: Value = serde_json::from_str(&body).unwrap();
+                            patches.lock().unwrap().push((name, body.clone()));
+                            Json(body)
+                        }
+                    },
+                ),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let config = kube::Config::new(url.parse().unwrap());
+        let client = Client::try_from(config).unwrap();
+        let tool = KubernetesTool::from_client(client);
+
+        let result = tool
+            .execute(json!({"action": "list", "kind": "po"}))
+            .await
+            .unwrap();
+        assert!(result.success, "{}", result.output);
+        let lines: Vec<&str> = result.output.lines().collect();
+        assert!(lines[0].starts_with("NAME    READY   STATUS"));
+        assert_eq!(
+            lines[1],
+            "web-1   0/1     CrashLoopBackOff   4          node-a   3h"
+        );
+
+        let result = tool
+            .execute(json!({"action": "logs", "name": "web-1", "tail_lines": 2}))
+            .await
+            .unwrap();
+        assert!(result.output.contains("database unreachable"));
+
+        let result = tool
+            .execute(json!({"action": "describe", "kind": "pod", "name": "web-1"}))
+            .await
+            .unwrap();
+        assert!(result.output.contains("nodeName: node-a"));
+        assert!(!result.output.contains("managedFields"));
+        assert!(result
+            .output
+            .contains("Warning  BackOff (x12): Back-off restarting failed container"));
+
+        let result = tool
+            .execute(json!({"action": "describe", "kind": "pod", "name": "web-2"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("pods \"web-2\" not found"));
+
+        let result = tool
+            .execute(json!({"action": "list", "kind": "secrets"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+
+        let manifest =
+            "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: web\nspec:\n  replicas: 3\n";
+        let apply = json!({"action": "apply", "manifest": manifest});
+        assert!(!tool.execute(apply.clone()).await.unwrap().success);
+
+        let tool = tool.allow_apply(true);
+        assert_eq!(tool.call_permission(&apply), ToolPermission::Destructive);
+        let result = tool.execute(apply).await.unwrap();
+        assert_eq!(result.output, "deployment/web applied");
+        let applied = applied.lock().unwrap();
+        assert_eq!(applied[0].0, "web");
+        assert_eq!(applied[0].1["metadata"]["namespace"], "default");
+        assert_eq!(applied[0].1["spec"]["replicas"], 3);
+    }
+}
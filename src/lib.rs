@@ -20,6 +20,7 @@
 //!         api_key: std::env::var("OPENAI_API_KEY").unwrap(),
 //!         temperature: 0.7,
 //!         max_tokens: 2048,
+//!         ..Default::default()
 //!     };
 //!
 //!     let client = LLMClient::new(helios_engine::llm::LLMProviderType::Remote(llm_config)).await?;
@@ -103,12 +104,170 @@ pub mod rag;
 /// RAG tool implementation for agent use.
 pub mod rag_tool;
 
+/// Document chunking strategies used by the RAG system.
+pub mod chunking;
+
 /// Forest of Agents - Multi-agent collaboration system.
 pub mod forest;
 
 /// AutoForest - Automatic orchestration of agent forests for complex tasks.
 pub mod auto_forest;
 
+/// Project scanning and overview generation for code-assistant agents.
+pub mod project_context;
+
+/// Fine-tuning dataset export from recorded sessions.
+pub mod dataset;
+
+/// Token usage tracking and cost accounting.
+pub mod usage;
+
+/// Circuit breakers for failing LLM providers and tools.
+pub mod circuit_breaker;
+
+/// Abort handles for stopping agents and forests mid-run.
+pub mod abort;
+
+/// Audit log of the tool calls agents make.
+pub mod audit;
+
+/// Limits on how much tool output reaches the conversation.
+pub mod truncation;
+
+/// In-memory and on-disk caching of LLM responses.
+pub mod response_cache;
+
+/// Scripted and record/replay LLM providers for testing.
+pub mod mock;
+
+/// Cross-platform path handling for the file tools.
+pub mod path_utils;
+
+/// Middleware hooks that run around an agent's LLM and tool calls.
+pub mod middleware;
+
+/// Moderation of agent input and output.
+pub mod moderation;
+
+/// Screening tool results for prompt injections.
+pub mod injection;
+
+/// Branching conversations into alternative continuations.
+pub mod branching;
+
+/// Shareable agent bundles with prompts, tool manifests and guardrails.
+pub mod bundle;
+
+/// Per-provider capability map used to shape requests.
+pub mod capabilities;
+
+/// Batched chat requests through the provider's Batch API or bounded concurrency.
+pub mod batch;
+
+/// Caching of agent answers by question similarity.
+pub mod semantic_cache;
+
+/// Named groups of tools, such as the built-in filesystem and web toolsets.
+pub mod toolset;
+
+/// Policies controlling what the shell command tool may run.
+pub mod shell_policy;
+
+/// Parsing of `robots.txt` crawling rules for the web scraper.
+pub mod robots;
+
+/// Speech-to-text and text-to-speech providers.
+pub mod voice;
+
+/// Declarative pipelines that chain prompts, agents, tools and RAG queries.
+pub mod pipeline;
+
+/// Golden-transcript snapshot testing for agent runs.
+pub mod golden;
+
+/// Injectable clock and ID providers for deterministic runs.
+pub mod clock;
+
+/// Approximate memory accounting and pruning for long-running agents.
+pub mod footprint;
+
+/// A/B experiments that compare agent configurations on the same prompts.
+pub mod experiments;
+
+/// Typed events reported by agents while they work.
+pub mod events;
+
+/// Prompt templates with variables, partials and conditional sections.
+pub mod prompt;
+
+/// How agents offer tools to the model, including a text protocol for models
+/// without function calling.
+pub mod tool_protocol;
+
+/// GBNF grammars for constrained sampling with local models.
+pub mod grammar;
+
+/// Keeping several local models loaded, with least-recently-used eviction.
+pub mod model_pool;
+
+/// A tool that runs Python and JavaScript code with resource limits.
+pub mod code_interpreter;
+
+/// Tools generated from OpenAPI documents.
+pub mod openapi;
+
+/// A tool for introspecting and querying GraphQL APIs.
+pub mod graphql;
+
+/// Slack and Discord tools, and Slack events for the server.
+pub mod messaging;
+
+/// A tool that reads RSS and Atom feeds and reports new entries.
+#[cfg(feature = "feeds")]
+pub mod feeds;
+
+/// A tool that lets an agent ask the user a question and wait for the answer.
+pub mod ask_user;
+
+/// A tool that generates images with OpenAI-compatible or Stability APIs.
+pub mod image_generation;
+
+/// A headless browser tool for pages rendered with JavaScript.
+#[cfg(feature = "browser")]
+pub mod browser;
+
+/// An IMAP and SMTP email tool.
+#[cfg(feature = "email")]
+pub mod email;
+
+/// Tools loaded at runtime from sandboxed WebAssembly plugins.
+#[cfg(feature = "wasm-plugins")]
+pub mod plugins;
+
+/// A Kubernetes tool for listing, describing and applying cluster resources.
+#[cfg(feature = "kubernetes")]
+pub mod kubernetes;
+
+/// A Docker tool for managing containers and images.
+#[cfg(feature = "docker")]
+pub mod docker;
+
+/// A GitHub tool for issues, pull requests and code search.
+#[cfg(feature = "github")]
+pub mod github;
+
+/// A tool for creating and extracting zip and tar archives.
+#[cfg(feature = "archive")]
+pub mod archive;
+
+/// A tool that reads and writes the system clipboard.
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+
+/// A tool that captures the screen or the focused window.
+#[cfg(feature = "screenshot")]
+pub mod screenshot;
+
 /// Candle backend provider for running local models.
 #[cfg(feature = "candle")]
 pub mod candle_provider;
@@ -119,18 +278,95 @@ pub mod candle_models;
 
 // Re-exports
 
-/// Re-export of the `Agent` and `AgentBuilder` for convenient access.
-pub use agent::{Agent, AgentBuilder};
+/// Re-export of the `Agent`, `AgentBuilder` and `AgentAsTool` for convenient access.
+pub use agent::{Agent, AgentAsTool, AgentBuilder};
+
+/// Re-export of the agent event types.
+pub use events::{AgentEvent, AgentObserver, ConsoleObserver};
+
+/// Re-export of the prompt template and system prompt builder types.
+pub use prompt::{PromptTemplate, SystemPromptBuilder};
+
+/// Re-export of the tool protocol selector.
+pub use tool_protocol::ToolProtocol;
+
+/// Re-export of the sampling grammar.
+pub use grammar::Grammar;
+
+/// Re-export of the local model pool.
+#[cfg(feature = "local")]
+pub use model_pool::LocalModelPool;
+pub use model_pool::ModelPoolConfig;
+
+/// Re-export of the memory accounting types.
+pub use footprint::{HistoryLimit, MemoryFootprint, MemoryStats, ToolResultPruning};
+
+/// Re-export of the clock and ID providers.
+pub use clock::{Clock, FixedClock, IdGenerator, RandomIds, SequentialIds, SystemClock};
 
 /// Re-export of chat-related types.
-pub use chat::{ChatMessage, ChatSession, Role};
+pub use chat::{ChatMessage, ChatSession, Role, SessionInfo, SessionStore};
+
+/// Re-export of conversation branching types.
+pub use branching::{Branch, ConversationTree, MAIN_BRANCH};
 
 #[cfg(feature = "candle")]
 pub use config::CandleConfig;
-#[cfg(feature = "local")]
-pub use config::LocalConfig;
 /// Re-export of configuration types.
-pub use config::{Config, ConfigBuilder, LLMConfig};
+pub use config::{Config, ConfigBuilder, LLMConfig, LLMProfile, RetryConfig};
+#[cfg(feature = "local")]
+pub use config::{DraftModelConfig, LocalConfig};
+
+/// Re-export of agent bundle types.
+pub use bundle::{AgentBundle, Guardrails, ModelProfile, ToolManifestEntry};
+
+/// Re-export of provider capability types.
+pub use capabilities::{CapabilityOverrides, ProviderCapabilities, UnsupportedParameters};
+
+/// Re-export of batch types.
+pub use batch::{BatchMode, BatchOptions};
+
+/// Re-export of semantic cache types.
+pub use semantic_cache::{CachedAnswer, SemanticCache, SemanticCacheMode};
+
+/// Re-export of tool audit types.
+pub use audit::{ToolAudit, ToolInvocation, ToolStats};
+
+/// Re-export of tool output limit types.
+pub use truncation::{OutputLimit, OutputSummarizer, TruncationStrategy};
+
+/// Re-export of toolsets.
+pub use toolset::{Toolset, BUILTIN_TOOLSETS};
+
+/// Re-export of circuit breaker types.
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
+
+/// Re-export of the abort handle.
+pub use abort::AbortHandle;
+
+/// Re-export of the file tool sandbox.
+pub use path_utils::PathJail;
+
+/// Re-export of shell policy types.
+pub use shell_policy::{Shell, ShellPolicy};
+
+/// Re-export of middleware types.
+pub use middleware::{AgentMiddleware, ContextInjector, LoggingMiddleware};
+
+/// Re-export of the prompt-injection guard.
+pub use injection::{InjectionAction, InjectionGuard, InjectionReport};
+
+/// Re-export of moderation types.
+pub use moderation::{
+    KeywordModerator, ModerationAction, ModerationTarget, ModerationVerdict, Moderator,
+    OpenAIModerator,
+};
+
+/// Re-export of the testing providers.
+pub use mock::{Cassette, Interaction, MockLLMProvider, RecordingProvider};
+
+/// Re-export of response cache types.
+pub use response_cache::{CacheStats, ResponseCache, ResponseCacheConfig};
 
 /// Re-export of the custom error and result types.
 pub use error::{HeliosError, Result};
@@ -138,30 +374,128 @@ pub use error::{HeliosError, Result};
 /// Re-export of LLM-related types.
 #[cfg(feature = "local")]
 pub use llm::{
-    Delta, LLMClient, LLMProvider, LLMRequest, LLMResponse, LocalLLMProvider, StreamChoice,
-    StreamChunk,
+    Delta, LLMClient, LLMProvider, LLMRequest, LLMResponse, LocalLLMProvider, Logprobs,
+    ReasoningSplitter, StreamChoice, StreamChunk, StreamEvent, TokenLogprob, ToolCallAccumulator,
+    TopLogprob,
 };
 #[cfg(not(feature = "local"))]
-pub use llm::{Delta, LLMClient, LLMProvider, LLMRequest, LLMResponse, StreamChoice, StreamChunk};
+pub use llm::{
+    Delta, LLMClient, LLMProvider, LLMRequest, LLMResponse, Logprobs, ReasoningSplitter,
+    StreamChoice, StreamChunk, StreamEvent, TokenLogprob, ToolCallAccumulator, TopLogprob,
+};
 pub use tools::{
-    CalculatorTool, EchoTool, FileEditTool, FileIOTool, FileListTool, FileReadTool, FileSearchTool,
-    FileWriteTool, HttpRequestTool, JsonParserTool, MemoryDBTool, QdrantRAGTool, ShellCommandTool,
-    SystemInfoTool, TextProcessorTool, TimestampTool, Tool, ToolParameter, ToolRegistry,
-    ToolResult, WebScraperTool,
+    builtin_tool, CalculatorTool, ConsoleApprovalHandler, EchoTool, FileEditTool, FileIOTool,
+    FileListTool, FileReadTool, FileSearchTool, FileWriteTool, HttpRequestTool, JsonParserTool,
+    MemoryDBTool, QdrantRAGTool, SandboxedTool, ShellCommandTool, SystemInfoTool,
+    TextProcessorTool, TimestampTool, Tool, ToolApprovalHandler, ToolApprovalRequest,
+    ToolParameter, ToolPermission, ToolRegistry, ToolResult, WebScraperTool, BUILTIN_TOOLS,
 };
 
+/// Re-export of the code interpreter tool.
+pub use code_interpreter::{CodeInterpreterTool, CodeLanguage};
+
+/// Re-export of the OpenAPI tool generator.
+pub use openapi::{OpenApiSpec, OpenApiTool};
+
+/// Re-export of the GraphQL tool.
+pub use graphql::GraphQLTool;
+
+/// Re-export of the messaging tools and Slack event handling.
+pub use messaging::{DiscordTool, SlackEvents, SlackRequest, SlackTool};
+
+/// Re-export of the feed reader tool.
+#[cfg(feature = "feeds")]
+pub use feeds::FeedReaderTool;
+
+/// Re-export of the ask-user tool and question handlers.
+pub use ask_user::{
+    AskUserTool, ConsoleQuestionHandler, PendingQuestion, QuestionHandler, UserQuestion,
+};
+
+/// Re-export of the image generation tool.
+pub use image_generation::{ImageGenerationTool, ImageProvider};
+
 /// Re-export of tool builder for simplified tool creation.
 pub use tool_builder::ToolBuilder;
 
 /// Re-export of RAG system components.
 pub use rag::{
     Document, EmbeddingProvider, InMemoryVectorStore, OpenAIEmbeddings, QdrantVectorStore,
-    RAGSystem, SearchResult, VectorStore,
+    RAGSystem, RagConfig, SearchResult, VectorStore,
 };
 
+/// Re-export of incremental indexing results.
+pub use rag::incremental::{IndexedDocument, IngestReport, UpsertOutcome};
+
+/// Re-export of metadata filters.
+pub use rag::filter::MetadataFilter;
+
+/// Re-export of hybrid search components.
+pub use rag::hybrid::{Bm25Index, HybridSearchConfig};
+
+/// Re-export of RAG rerankers.
+pub use rag::rerank::{ApiReranker, LlmReranker, RerankConfig, Reranker};
+
+/// Re-export of the SQLite vector store.
+#[cfg(feature = "sqlite")]
+pub use rag::SqliteVectorStore;
+
+/// Re-export of the pgvector store.
+#[cfg(feature = "pgvector")]
+pub use rag::PgVectorStore;
+
+/// Re-export of the local embedding provider.
+#[cfg(feature = "local")]
+pub use rag::LocalEmbeddings;
+
 /// Re-export of RAG tool.
 pub use rag_tool::RAGTool;
 
+/// Re-export of the headless browser tool.
+#[cfg(feature = "browser")]
+pub use browser::BrowserTool;
+
+/// Re-export of the email tool and its configuration.
+#[cfg(feature = "email")]
+pub use email::{EmailConfig, EmailTool, ImapConfig, SmtpConfig, SmtpSecurity};
+
+/// Re-export of the WebAssembly plugin host and plugin tools.
+#[cfg(feature = "wasm-plugins")]
+pub use plugins::{load_plugins, PluginLimits, WasmPluginHost, WasmTool};
+
+/// Re-export of the Kubernetes tool.
+#[cfg(feature = "kubernetes")]
+pub use kubernetes::KubernetesTool;
+
+/// Re-export of the Docker tool.
+#[cfg(feature = "docker")]
+pub use docker::DockerTool;
+
+/// Re-export of the GitHub tool.
+#[cfg(feature = "github")]
+pub use github::GitHubTool;
+
+/// Re-export of the archive tool.
+#[cfg(feature = "archive")]
+pub use archive::ArchiveTool;
+
+/// Re-export of the clipboard tool.
+#[cfg(feature = "clipboard")]
+pub use clipboard::ClipboardTool;
+
+/// Re-export of the screenshot tool.
+#[cfg(feature = "screenshot")]
+pub use screenshot::ScreenshotTool;
+
+/// Re-export of golden-transcript helpers.
+pub use golden::{Normalizer, Transcript};
+
+/// Re-export of pipeline components.
+pub use pipeline::{Condition, Pipeline, PipelineOutput, PipelineSpec, Step, StepKind};
+
+/// Re-export of document chunkers.
+pub use chunking::{Chunker, FixedSizeChunker, MarkdownChunker, RecursiveChunker, SentenceChunker};
+
 /// Re-export of serve functionality.
 pub use serve::{
     load_custom_endpoints_config, start_server, start_server_with_agent,
@@ -176,12 +510,25 @@ pub use endpoint_builder::{
 
 /// Re-export of Forest of Agents functionality.
 pub use forest::{
-    AgentId, CreatePlanTool, DelegateTaskTool, ForestBuilder, ForestMessage, ForestOfAgents,
-    SendMessageTool, ShareContextTool, SharedContext, TaskItem, TaskPlan, TaskStatus,
-    UpdateTaskMemoryTool,
+    AgentId, CreatePlanTool, DebateOutcome, DebateTurn, DebateVerdict, DelegateTaskTool,
+    ForestBuilder, ForestCheckpoint, ForestMessage, ForestOfAgents, ForestTopology, PlanRevision,
+    RevisePlanTool, SendMessageTool, ShareContextTool, SharedContext, TaskFailurePolicy, TaskItem,
+    TaskPlan, TaskRetryPolicy, TaskStatus, TraceFormat, UpdateTaskMemoryTool,
 };
 
 /// Re-export of AutoForest functionality.
 pub use auto_forest::{
     AgentConfig, AutoForest, AutoForestBuilder, OrchestrationPlan, SpawnedAgent,
 };
+
+/// Re-export of project context scanning.
+pub use project_context::{ProjectContext, ProjectContextBuilder, ProjectFile};
+
+/// Re-export of usage tracking types.
+pub use usage::{Budget, ModelPrice, PriceTable, TokenUsage, UsageTracker};
+
+/// Re-export of voice types.
+pub use voice::{
+    CommandTextToSpeech, OpenAISpeechToText, OpenAITextToSpeech, SpeechAudio, SpeechToText,
+    TextToSpeech, VoiceConfig, WhisperCppSpeechToText,
+};
@@ -4,10 +4,16 @@
 //! It supports both remote LLMs (like OpenAI) and local LLMs (via `llama.cpp`).
 //! The `LLMClient` provides a unified interface for both types of providers.
 
+use crate::batch::{BatchMode, BatchOptions};
+use crate::capabilities::{ProviderCapabilities, UnsupportedParameters};
 use crate::chat::ChatMessage;
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
 use crate::config::LLMConfig;
 use crate::error::{HeliosError, Result};
+use crate::grammar::Grammar;
+use crate::response_cache::ResponseCache;
 use crate::tools::ToolDefinition;
+use crate::usage::{TokenUsage, UsageTracker};
 use async_trait::async_trait;
 use futures::stream::StreamExt;
 use reqwest::Client;
@@ -15,12 +21,14 @@ use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "local")]
 use {
-    crate::config::LocalConfig,
+    crate::config::{DraftModelConfig, LocalConfig},
+    crate::model_pool::LocalModelPool,
     llama_cpp_2::{
-        context::params::LlamaContextParams,
+        context::{params::LlamaContextParams, LlamaContext},
         llama_backend::LlamaBackend,
         llama_batch::LlamaBatch,
         model::{params::LlamaModelParams, AddBos, LlamaModel, Special},
+        sampling::LlamaSampler,
         token::LlamaToken,
     },
     std::{fs::File, os::fd::AsRawFd, sync::Arc},
@@ -38,6 +46,17 @@ impl From<llama_cpp_2::LLamaCppError> for HeliosError {
     }
 }
 
+/// Speech-to-text and text-to-speech over the same OpenAI-compatible API as chat.
+///
+/// The providers live in [`crate::voice`]; this module gathers the ones that talk to
+/// `/audio/transcriptions` and `/audio/speech`. [`LLMClient::transcribe`] and
+/// [`LLMClient::speak`] call them with the client's own base URL and API key.
+pub mod audio {
+    pub use crate::voice::{
+        OpenAISpeechToText, OpenAITextToSpeech, SpeechAudio, SpeechToText, TextToSpeech,
+    };
+}
+
 /// The type of LLM provider to use.
 #[derive(Clone)]
 pub enum LLMProviderType {
@@ -51,6 +70,19 @@ pub enum LLMProviderType {
     Candle(crate::config::CandleConfig),
 }
 
+impl LLMProviderType {
+    /// Returns the model name used for requests to this provider.
+    pub fn model_name(&self) -> String {
+        match self {
+            LLMProviderType::Remote(config) => config.model_name.clone(),
+            #[cfg(feature = "local")]
+            LLMProviderType::Local(_) => "local-model".to_string(),
+            #[cfg(feature = "candle")]
+            LLMProviderType::Candle(config) => config.huggingface_repo.clone(),
+        }
+    }
+}
+
 /// A request to an LLM.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LLMRequest {
@@ -64,6 +96,9 @@ pub struct LLMRequest {
     /// The maximum number of tokens to generate.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
+    /// The maximum number of tokens to generate, for models that replace `max_tokens` with it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
     /// The tools to make available to the model.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<ToolDefinition>>,
@@ -76,6 +111,15 @@ pub struct LLMRequest {
     /// Stop sequences for the request.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop: Option<Vec<String>>,
+    /// A grammar the response must follow. Only the `local` provider can apply it.
+    #[serde(skip)]
+    pub grammar: Option<Grammar>,
+    /// Whether to return the log probability of each generated token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<bool>,
+    /// How many of the most likely alternatives to return at each token position.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_logprobs: Option<u8>,
 }
 
 /// A chunk of a streamed response.
@@ -91,6 +135,9 @@ pub struct StreamChunk {
     pub model: String,
     /// The choices in the chunk.
     pub choices: Vec<StreamChoice>,
+    /// The usage statistics, sent by some providers on the final chunk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
 }
 
 /// A choice in a streamed response.
@@ -102,6 +149,12 @@ pub struct StreamChoice {
     pub delta: Delta,
     /// The reason the stream finished.
     pub finish_reason: Option<String>,
+    /// The log probabilities of the tokens in this delta, if they were requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<Logprobs>,
+    /// Any other fields the provider sent with the choice.
+    #[serde(flatten)]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
 }
 
 /// A tool call in a streamed delta.
@@ -137,11 +190,273 @@ pub struct Delta {
     /// The content of the delta.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
+    /// A piece of the model's reasoning, sent as `reasoning` or `reasoning_content`.
+    #[serde(
+        default,
+        alias = "reasoning_content",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reasoning: Option<String>,
     /// The tool calls in the delta.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<DeltaToolCall>>,
 }
 
+/// Progress reported while a response streams in.
+///
+/// `LLMClient::chat_stream_events` reports text and tool-call fragments; the agent's
+/// streaming methods also report the tool calls they run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// A piece of the response text.
+    Content(String),
+    /// A piece of the reasoning the model does before it answers.
+    ///
+    /// Reasoning comes from providers that report it separately and from
+    /// `<think>` or `<thinking>` tags in the text, which never reach
+    /// [`StreamEvent::Content`].
+    Reasoning(String),
+    /// A fragment of a tool call the model is writing.
+    ///
+    /// The ID and name usually arrive with the first fragment of each call; the
+    /// arguments arrive in pieces that concatenate to a JSON object.
+    ToolCallDelta {
+        /// The position of the call in the response.
+        index: usize,
+        /// The ID of the call, if this fragment carries it.
+        id: Option<String>,
+        /// The name of the tool, if this fragment carries it.
+        name: Option<String>,
+        /// The piece of the arguments in this fragment.
+        arguments: String,
+    },
+    /// The agent is about to run a tool call.
+    ToolStarted {
+        /// The ID of the call.
+        id: String,
+        /// The name of the tool.
+        name: String,
+        /// The arguments, as a JSON string.
+        arguments: String,
+    },
+    /// The agent finished running a tool call.
+    ToolFinished {
+        /// The ID of the call.
+        id: String,
+        /// The name of the tool.
+        name: String,
+        /// Whether the tool succeeded.
+        success: bool,
+        /// The tool's output.
+        output: String,
+    },
+}
+
+/// Assembles complete tool calls from the fragments in streamed deltas.
+#[derive(Debug, Clone, Default)]
+pub struct ToolCallAccumulator {
+    calls: Vec<Option<crate::chat::ToolCall>>,
+}
+
+impl ToolCallAccumulator {
+    /// Creates an empty accumulator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a fragment and returns it as a [`StreamEvent::ToolCallDelta`].
+    pub fn push(&mut self, delta: &DeltaToolCall) -> StreamEvent {
+        let index = delta.index as usize;
+        while self.calls.len() <= index {
+            self.calls.push(None);
+        }
+        let call = self.calls[index].get_or_insert_with(|| crate::chat::ToolCall {
+            id: String::new(),
+            call_type: "function".to_string(),
+            function: crate::chat::FunctionCall {
+                name: String::new(),
+                arguments: String::new(),
+            },
+        });
+
+        let name = delta.function.as_ref().and_then(|f| f.name.clone());
+        let arguments = delta
+            .function
+            .as_ref()
+            .and_then(|f| f.arguments.clone())
+            .unwrap_or_default();
+        if let Some(id) = &delta.id {
+            call.id = id.clone();
+        }
+        if let Some(name) = &name {
+            call.function.name = name.clone();
+        }
+        call.function.arguments.push_str(&arguments);
+
+        StreamEvent::ToolCallDelta {
+            index,
+            id: delta.id.clone(),
+            name,
+            arguments,
+        }
+    }
+
+    /// Returns `true` if no fragments have been added.
+    pub fn is_empty(&self) -> bool {
+        self.calls.iter().all(Option::is_none)
+    }
+
+    /// Returns the assembled tool calls, or `None` if the response had none.
+    pub fn finish(self) -> Option<Vec<crate::chat::ToolCall>> {
+        let calls: Vec<_> = self.calls.into_iter().flatten().collect();
+        (!calls.is_empty()).then_some(calls)
+    }
+}
+
+/// The tags reasoning models wrap their thinking in.
+const REASONING_TAGS: [(&str, &str); 2] = [("<think>", "</think>"), ("<thinking>", "</thinking>")];
+
+/// Separates reasoning in `<think>` or `<thinking>` tags from the answer as text streams in.
+///
+/// Tags split across chunks are recognized; text that might start a tag is held
+/// back until the next chunk shows whether it does.
+///
+/// ```rust
+/// use helios_engine::llm::{ReasoningSplitter, StreamEvent};
+///
+/// let mut events = Vec::new();
+/// let mut splitter = ReasoningSplitter::new();
+/// for chunk in ["<thi", "nk>Easy.</th", "ink>\n\nIt is 4."] {
+///     splitter.push(chunk, &mut |event| events.push(event));
+/// }
+/// splitter.finish(&mut |event| events.push(event));
+/// assert_eq!(
+///     events,
+///     vec![
+///         StreamEvent::Reasoning("Easy.".to_string()),
+///         StreamEvent::Content("It is 4.".to_string()),
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ReasoningSplitter {
+    pending: String,
+    /// The closing tag of the reasoning block the text is in, if any.
+    closing: Option<&'static str>,
+    /// Whether whitespace after a reasoning block is still being skipped.
+    after_reasoning: bool,
+}
+
+impl ReasoningSplitter {
+    /// Creates a splitter outside any reasoning block.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a chunk of text, reporting what is certain of it as events.
+    pub fn push(&mut self, text: &str, on_event: &mut impl FnMut(StreamEvent)) {
+        self.pending.push_str(text);
+        loop {
+            let found = match self.closing {
+                Some(closing) => self.pending.find(closing).map(|at| (at, closing, None)),
+                None => REASONING_TAGS
+                    .iter()
+                    .filter_map(|&(open, close)| {
+                        self.pending.find(open).map(|at| (at, open, Some(close)))
+                    })
+                    .min_by_key(|&(at, _, _)| at),
+            };
+            let Some((at, tag, closing)) = found else {
+                break;
+            };
+            let before = self.pending[..at].to_string();
+            self.pending.drain(..at + tag.len());
+            self.emit(before, on_event);
+            self.after_reasoning = closing.is_none();
+            self.closing = closing;
+        }
+
+        // Keep back a tail that could be the start of a tag.
+        let tags: Vec<&str> = match self.closing {
+            Some(closing) => vec![closing],
+            None => REASONING_TAGS.iter().map(|&(open, _)| open).collect(),
+        };
+        let held = (1..=self.pending.len())
+            .rev()
+            .filter(|&len| self.pending.is_char_boundary(self.pending.len() - len))
+            .find(|&len| {
+                let tail = &self.pending[self.pending.len() - len..];
+                tags.iter().any(|tag| tag.starts_with(tail))
+            })
+            .unwrap_or(0);
+        let ready: String = self.pending.drain(..self.pending.len() - held).collect();
+        self.emit(ready, on_event);
+    }
+
+    /// Reports the text held back, once the stream has ended.
+    pub fn finish(&mut self, on_event: &mut impl FnMut(StreamEvent)) {
+        let rest = std::mem::take(&mut self.pending);
+        self.emit(rest, on_event);
+    }
+
+    fn emit(&mut self, text: String, on_event: &mut impl FnMut(StreamEvent)) {
+        if self.closing.is_some() {
+            if !text.is_empty() {
+                on_event(StreamEvent::Reasoning(text));
+            }
+            return;
+        }
+        let text = if self.after_reasoning {
+            text.trim_start().to_string()
+        } else {
+            text
+        };
+        if !text.is_empty() {
+            self.after_reasoning = false;
+            on_event(StreamEvent::Content(text));
+        }
+    }
+}
+
+/// Splits `text` into the reasoning in its `<think>` or `<thinking>` tags and the answer.
+///
+/// Returns `None` for the reasoning when the text has no such tags, in which case
+/// the answer is the text unchanged.
+pub fn split_reasoning(text: &str) -> (Option<String>, String) {
+    let mut reasoning = String::new();
+    let mut content = String::new();
+    let mut splitter = ReasoningSplitter::new();
+    let mut collect = |event| match event {
+        StreamEvent::Reasoning(text) => reasoning.push_str(&text),
+        StreamEvent::Content(text) => content.push_str(&text),
+        _ => {}
+    };
+    splitter.push(text, &mut collect);
+    splitter.finish(&mut collect);
+    if reasoning.is_empty() && content.len() == text.len() {
+        return (None, content);
+    }
+    (
+        Some(reasoning.trim().to_string()),
+        content.trim().to_string(),
+    )
+}
+
+/// Moves reasoning in tags out of a message's content into its `reasoning`.
+fn extract_reasoning(message: &mut ChatMessage) {
+    let (Some(reasoning), content) = split_reasoning(&message.content) else {
+        return;
+    };
+    message.content = content;
+    if reasoning.is_empty() {
+        return;
+    }
+    message.reasoning = Some(match message.reasoning.take() {
+        Some(native) => format!("{}\n{}", native, reasoning),
+        None => reasoning,
+    });
+}
+
 /// A response from an LLM.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LLMResponse {
@@ -156,9 +471,17 @@ pub struct LLMResponse {
     /// The choices in the response.
     pub choices: Vec<Choice>,
     /// The usage statistics for the response.
+    #[serde(default)]
     pub usage: Usage,
 }
 
+impl LLMResponse {
+    /// Returns the reasoning of the first choice, if the model reported any.
+    pub fn reasoning(&self) -> Option<&str> {
+        self.choices.first()?.message.reasoning.as_deref()
+    }
+}
+
 /// A choice in an LLM response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Choice {
@@ -168,10 +491,92 @@ pub struct Choice {
     pub message: ChatMessage,
     /// The reason the generation finished.
     pub finish_reason: Option<String>,
+    /// The log probabilities of the generated tokens, if they were requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<Logprobs>,
+    /// Any other fields the provider sent with the choice.
+    #[serde(flatten)]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The log probabilities of the tokens of a choice.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Logprobs {
+    /// The tokens of the content, in order.
+    #[serde(default, deserialize_with = "deserialize_null_as_default")]
+    pub content: Vec<TokenLogprob>,
+}
+
+impl Logprobs {
+    /// Returns the mean log probability of the tokens, or `None` without tokens.
+    ///
+    /// Values near zero mean the model was confident in what it wrote.
+    pub fn mean_logprob(&self) -> Option<f64> {
+        if self.content.is_empty() {
+            return None;
+        }
+        let sum: f64 = self.content.iter().map(|token| token.logprob).sum();
+        Some(sum / self.content.len() as f64)
+    }
+
+    /// Returns the perplexity of the tokens, or `None` without tokens.
+    pub fn perplexity(&self) -> Option<f64> {
+        self.mean_logprob().map(|mean| (-mean).exp())
+    }
+
+    /// Returns the token the model was least sure of.
+    pub fn least_likely(&self) -> Option<&TokenLogprob> {
+        self.content
+            .iter()
+            .min_by(|a, b| a.logprob.total_cmp(&b.logprob))
+    }
+}
+
+/// A generated token with its log probability.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenLogprob {
+    /// The token.
+    pub token: String,
+    /// The natural logarithm of the token's probability.
+    pub logprob: f64,
+    /// The UTF-8 bytes of the token, for tokens that are not valid text alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<Vec<u8>>,
+    /// The most likely tokens at this position, with their log probabilities.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+impl TokenLogprob {
+    /// Returns the token's probability, between 0 and 1.
+    pub fn probability(&self) -> f64 {
+        self.logprob.exp()
+    }
+}
+
+/// One of the most likely tokens at a position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopLogprob {
+    /// The token.
+    pub token: String,
+    /// The natural logarithm of the token's probability.
+    pub logprob: f64,
+    /// The UTF-8 bytes of the token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<Vec<u8>>,
+}
+
+/// Deserializes `null` as the type's default value.
+fn deserialize_null_as_default<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Option::unwrap_or_default)
 }
 
 /// The usage statistics for an LLM response.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Usage {
     /// The number of tokens in the prompt.
     pub prompt_tokens: u32,
@@ -181,6 +586,33 @@ pub struct Usage {
     pub total_tokens: u32,
 }
 
+impl Usage {
+    /// Creates usage statistics from prompt and completion token counts.
+    pub fn new(prompt_tokens: u32, completion_tokens: u32) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+
+    /// Returns `true` if no tokens were reported.
+    pub fn is_empty(&self) -> bool {
+        self.prompt_tokens == 0 && self.completion_tokens == 0 && self.total_tokens == 0
+    }
+
+    /// Estimates usage from the prompt messages and the completion text.
+    ///
+    /// Used for providers that do not report token counts themselves.
+    pub fn estimate(messages: &[ChatMessage], completion: &str) -> Self {
+        let prompt_tokens = messages
+            .iter()
+            .map(|m| crate::usage::estimate_tokens(&m.content))
+            .sum();
+        Self::new(prompt_tokens, crate::usage::estimate_tokens(completion))
+    }
+}
+
 /// A trait for LLM providers.
 #[async_trait]
 pub trait LLMProvider: Send + Sync {
@@ -194,6 +626,17 @@ pub trait LLMProvider: Send + Sync {
 pub struct LLMClient {
     provider: Box<dyn LLMProvider + Send + Sync>,
     provider_type: LLMProviderType,
+    usage: UsageTracker,
+    breaker: CircuitBreaker,
+    fallbacks: Vec<FallbackProvider>,
+    last_provider: std::sync::Mutex<Option<String>>,
+    cache: Option<ResponseCache>,
+}
+
+/// A remote provider tried when the providers before it in the chain fail.
+struct FallbackProvider {
+    client: RemoteLLMClient,
+    breaker: CircuitBreaker,
 }
 
 impl LLMClient {
@@ -211,30 +654,336 @@ impl LLMClient {
             }
         };
 
+        let (breaker_config, fallbacks) = match &provider_type {
+            LLMProviderType::Remote(config) => (
+                config.circuit_breaker.clone(),
+                config
+                    .fallbacks
+                    .iter()
+                    .map(|fallback| FallbackProvider {
+                        client: RemoteLLMClient::new(fallback.clone()),
+                        breaker: CircuitBreaker::new(
+                            fallback.model_name.clone(),
+                            fallback.circuit_breaker.clone(),
+                        ),
+                    })
+                    .collect(),
+            ),
+            #[allow(unreachable_patterns)]
+            _ => (CircuitBreakerConfig::default(), Vec::new()),
+        };
+
+        let breaker = CircuitBreaker::new(provider_type.model_name(), breaker_config);
+        let cache = match &provider_type {
+            LLMProviderType::Remote(config) if config.cache.enabled => {
+                Some(ResponseCache::new(config.cache.clone()))
+            }
+            _ => None,
+        };
+
         Ok(Self {
             provider,
             provider_type,
+            usage: UsageTracker::new(),
+            breaker,
+            fallbacks,
+            last_provider: std::sync::Mutex::new(None),
+            cache,
         })
     }
 
+    /// Creates a client backed by a custom provider, such as a `MockLLMProvider`.
+    ///
+    /// `config` supplies the model name, default generation parameters, circuit
+    /// breaker and response cache; its fallbacks are ignored. Custom providers do
+    /// not stream, so streaming calls deliver the whole response as one chunk.
+    pub fn from_provider(provider: impl LLMProvider + 'static, config: LLMConfig) -> Self {
+        Self::from_boxed_provider(Box::new(provider), config)
+    }
+
+    /// Creates a client backed by an already boxed custom provider.
+    pub(crate) fn from_boxed_provider(provider: Box<dyn LLMProvider>, config: LLMConfig) -> Self {
+        Self {
+            provider,
+            breaker: CircuitBreaker::new(config.model_name.clone(), config.circuit_breaker.clone()),
+            cache: config
+                .cache
+                .enabled
+                .then(|| ResponseCache::new(config.cache.clone())),
+            provider_type: LLMProviderType::Remote(config),
+            usage: UsageTracker::new(),
+            fallbacks: Vec::new(),
+            last_provider: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Returns the model name of the provider that answered the most recent request.
+    ///
+    /// With `[[llm.fallbacks]]` configured this tells whether the primary provider or
+    /// one of its fallbacks produced the last response.
+    pub fn last_provider(&self) -> Option<String> {
+        self.last_provider
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Records which provider answered the most recent request.
+    fn set_last_provider(&self, model: &str) {
+        *self.last_provider.lock().unwrap_or_else(|e| e.into_inner()) = Some(model.to_string());
+    }
+
+    /// Returns the response cache, if `[llm.cache]` is enabled.
+    pub fn response_cache(&self) -> Option<&ResponseCache> {
+        self.cache.as_ref()
+    }
+
+    /// Returns the cache key for a request, if the request may be cached.
+    fn cache_key(&self, request: &LLMRequest) -> Option<String> {
+        self.cache.as_ref()?.key_for(request)
+    }
+
     /// Returns the type of the LLM provider.
     pub fn provider_type(&self) -> &LLMProviderType {
         &self.provider_type
     }
+
+    /// Returns the config of the remote provider, for the audio endpoints.
+    fn audio_config(&self) -> Result<&LLMConfig> {
+        #[allow(irrefutable_let_patterns)]
+        if let LLMProviderType::Remote(config) = &self.provider_type {
+            Ok(config)
+        } else {
+            Err(HeliosError::LLMError(
+                "Audio endpoints are only available with a remote provider".to_string(),
+            ))
+        }
+    }
+
+    /// Transcribes an audio file with the provider's `/audio/transcriptions`
+    /// endpoint, using the `whisper-1` model.
+    ///
+    /// For another model or a language hint, use [`audio::OpenAISpeechToText`].
+    pub async fn transcribe(&self, audio: &std::path::Path) -> Result<String> {
+        use audio::SpeechToText;
+
+        let config = self.audio_config()?;
+        audio::OpenAISpeechToText::new(&config.base_url, &config.api_key)
+            .transcribe(audio)
+            .await
+    }
+
+    /// Reads `text` aloud with the provider's `/audio/speech` endpoint, using the
+    /// `tts-1` model and the `alloy` voice, and returns MP3 audio.
+    ///
+    /// For another model, voice or format, use [`audio::OpenAITextToSpeech`].
+    pub async fn speak(&self, text: &str) -> Result<audio::SpeechAudio> {
+        use audio::TextToSpeech;
+
+        let config = self.audio_config()?;
+        audio::OpenAITextToSpeech::new(&config.base_url, &config.api_key)
+            .synthesize(text)
+            .await
+    }
+
+    /// Returns the token usage accumulated by this client across all calls.
+    pub fn usage(&self) -> TokenUsage {
+        self.usage.total()
+    }
+
+    /// Returns the tracker recording this client's token usage per model.
+    pub fn usage_tracker(&self) -> &UsageTracker {
+        &self.usage
+    }
+
+    /// Records usage in `tracker` instead of this client's own counters, so that a
+    /// replacement client continues the totals of the one it replaces.
+    pub fn with_usage_tracker(mut self, tracker: UsageTracker) -> Self {
+        self.usage = tracker;
+        self
+    }
+
+    /// Returns the circuit breaker guarding calls to the provider.
+    pub fn circuit_breaker(&self) -> &CircuitBreaker {
+        &self.breaker
+    }
+
+    /// Returns the model name used for requests and usage accounting.
+    pub fn model_name(&self) -> String {
+        self.provider_type.model_name()
+    }
+
+    /// Returns the parameters and limits supported by the primary provider.
+    ///
+    /// Local providers report [`ProviderCapabilities::default`].
+    pub fn capabilities(&self) -> ProviderCapabilities {
+        match &self.provider_type {
+            LLMProviderType::Remote(config) => config.provider_capabilities(),
+            #[allow(unreachable_patterns)]
+            _ => ProviderCapabilities::default(),
+        }
+    }
+
+    /// Checks that the client can reach a provider to serve requests.
+    ///
+    /// A remote provider is asked for its model list, and the fallbacks are tried
+    /// when it does not answer. Local and custom providers hold their model in
+    /// memory once created, so they are always ready.
+    pub async fn check_ready(&self) -> Result<()> {
+        let Some(remote) = self.provider.as_any().downcast_ref::<RemoteLLMClient>() else {
+            return Ok(());
+        };
+        let mut result = remote.check_reachable().await;
+        for fallback in &self.fallbacks {
+            if result.is_ok() {
+                break;
+            }
+            result = fallback.client.check_reachable().await;
+        }
+        result
+    }
+}
+
+/// Client-side stop conditions applied to a response as it streams in.
+///
+/// Text is passed on as it arrives, except for a short tail that could still be
+/// the start of a stop sequence. Once a stop sequence or pattern matches, the
+/// response is cut at the start of the match and no further text is passed on.
+/// Patterns are matched against the whole response, so a pattern whose match
+/// begins in text that was already passed on still truncates the final message.
+pub(crate) struct StopMatcher {
+    sequences: Vec<String>,
+    patterns: Vec<regex::Regex>,
+    text: String,
+    emitted: usize,
+    holdback: usize,
+    stopped: bool,
+}
+
+impl StopMatcher {
+    /// Builds a matcher, or returns `None` when there is nothing to match.
+    pub(crate) fn new(sequences: &[String], patterns: &[String]) -> Result<Option<Self>> {
+        let sequences: Vec<String> = sequences
+            .iter()
+            .filter(|s| !s.is_empty())
+            .cloned()
+            .collect();
+        let patterns = patterns
+            .iter()
+            .map(|p| {
+                regex::Regex::new(p).map_err(|e| {
+                    HeliosError::ConfigError(format!("Invalid stop pattern '{}': {}", p, e))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if sequences.is_empty() && patterns.is_empty() {
+            return Ok(None);
+        }
+
+        let holdback = sequences.iter().map(|s| s.len()).max().unwrap_or(1) - 1;
+        Ok(Some(Self {
+            sequences,
+            patterns,
+            text: String::new(),
+            emitted: 0,
+            holdback,
+            stopped: false,
+        }))
+    }
+
+    /// Builds a matcher from a remote config's stop conditions and a request's stop sequences.
+    pub(crate) fn for_request(config: &LLMConfig, stop: Option<&[String]>) -> Result<Option<Self>> {
+        let mut sequences = config.stop_sequences.clone();
+        sequences.extend(stop.unwrap_or_default().iter().cloned());
+        Self::new(&sequences, &config.stop_patterns)
+    }
+
+    /// Adds a chunk of the response and returns the text that may be passed on.
+    pub(crate) fn push(&mut self, chunk: &str) -> &str {
+        if self.stopped {
+            return "";
+        }
+        self.text.push_str(chunk);
+
+        let start = self.emitted;
+        let cut = self
+            .sequences
+            .iter()
+            .filter_map(|s| self.text[start..].find(s.as_str()).map(|i| start + i))
+            .chain(
+                self.patterns
+                    .iter()
+                    .filter_map(|p| p.find(&self.text).map(|m| m.start())),
+            )
+            .min();
+
+        match cut {
+            Some(cut) => {
+                self.stopped = true;
+                self.text.truncate(cut);
+                self.emitted = cut;
+                // A pattern may match text that was already passed on.
+                if cut < start {
+                    return "";
+                }
+                &self.text[start..cut]
+            }
+            None => {
+                let mut end = self.text.len().saturating_sub(self.holdback).max(start);
+                while !self.text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                self.emitted = end;
+                &self.text[start..end]
+            }
+        }
+    }
+
+    /// Returns the held-back tail once the response has ended without a match.
+    pub(crate) fn finish(&mut self) -> &str {
+        let start = self.emitted;
+        self.emitted = self.text.len();
+        &self.text[start..]
+    }
+
+    /// Returns whether a stop condition has matched.
+    pub(crate) fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Returns the response received so far, truncated at any match.
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Cuts a complete response at the first stop condition.
+    pub(crate) fn truncate(mut self, content: &mut String) {
+        self.push(content);
+        if self.stopped {
+            *content = self.text;
+        }
+    }
 }
 
 /// A client for interacting with a remote LLM.
 pub struct RemoteLLMClient {
     config: LLMConfig,
+    capabilities: ProviderCapabilities,
     client: Client,
 }
 
 impl RemoteLLMClient {
     /// Creates a new `RemoteLLMClient`.
     pub fn new(config: LLMConfig) -> Self {
+        let mut builder = Client::builder();
+        if let Some(secs) = config.timeout_secs {
+            builder = builder.timeout(std::time::Duration::from_secs(secs));
+        }
         Self {
+            capabilities: config.provider_capabilities(),
             config,
-            client: Client::new(),
+            client: builder.build().unwrap_or_else(|_| Client::new()),
         }
     }
 
@@ -242,11 +991,225 @@ impl RemoteLLMClient {
     pub fn config(&self) -> &LLMConfig {
         &self.config
     }
+
+    /// Returns the parameters and limits this client's model supports.
+    pub fn capabilities(&self) -> &ProviderCapabilities {
+        &self.capabilities
+    }
+
+    /// Returns the HTTP client requests are sent with.
+    pub(crate) fn http(&self) -> &Client {
+        &self.client
+    }
+
+    /// Shapes a request to the model's capabilities and strips what the API
+    /// must not receive, leaving it ready to send.
+    pub(crate) fn prepare(&self, request: &LLMRequest) -> Result<LLMRequest> {
+        let mut request = request.clone();
+        self.capabilities
+            .shape(&mut request, self.config.unsupported_parameters)?;
+        if !self.capabilities.tools {
+            request.messages = flatten_tool_messages(&request.messages);
+        }
+        for message in &mut request.messages {
+            message.reasoning = None;
+        }
+        Ok(request)
+    }
+
+    /// Checks that the API answers, by asking it for its model list.
+    ///
+    /// APIs without a `/models` endpoint count as reachable; a rejected API key
+    /// does not.
+    pub(crate) async fn check_reachable(&self) -> Result<()> {
+        let url = format!("{}/models", self.config.base_url);
+        let response = self
+            .authorize(self.client.get(&url))
+            .timeout(std::time::Duration::from_secs(5))
+            .send()
+            .await
+            .map_err(|e| {
+                HeliosError::LLMError(format!("{} is unreachable: {}", self.config.base_url, e))
+            })?;
+        let status = response.status();
+        if status.is_success()
+            || status == reqwest::StatusCode::NOT_FOUND
+            || status == reqwest::StatusCode::METHOD_NOT_ALLOWED
+        {
+            Ok(())
+        } else {
+            Err(HeliosError::LLMError(format!(
+                "{} answered with status {}",
+                self.config.base_url, status
+            )))
+        }
+    }
+
+    /// Adds the API key to a request, unless it goes to a local server.
+    pub(crate) fn authorize(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        // Only add authorization header if not a local vLLM instance
+        if !self.config.base_url.contains("10.")
+            && !self.config.base_url.contains("localhost")
+            && !self.config.base_url.contains("127.0.0.1")
+        {
+            builder.header("Authorization", format!("Bearer {}", self.config.api_key))
+        } else {
+            builder
+        }
+    }
+
+    /// Posts a request to the chat completions endpoint.
+    ///
+    /// Rate limits, server errors and connection failures are retried according to the
+    /// configured [`RetryConfig`](crate::config::RetryConfig), honoring `Retry-After`.
+    async fn send_request(&self, request: &LLMRequest) -> Result<reqwest::Response> {
+        let url = format!("{}/chat/completions", self.config.base_url);
+        let retry = &self.config.retry;
+        let mut attempt = 0;
+
+        let request = &self.prepare(request)?;
+
+        loop {
+            let request_builder = self.authorize(
+                self.client
+                    .post(&url)
+                    .header("Content-Type", "application/json"),
+            );
+
+            match request_builder.json(request).send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status();
+                    if attempt < retry.max_retries && is_retryable_status(status) {
+                        let max_delay = std::time::Duration::from_millis(retry.max_backoff_ms);
+                        let delay = retry_after(response.headers(), max_delay)
+                            .unwrap_or_else(|| retry.backoff_with_jitter(attempt, jitter_sample()));
+                        tracing::warn!(
+                            "LLM API returned {}, retrying in {:?} (attempt {}/{})",
+                            status,
+                            delay,
+                            attempt + 1,
+                            retry.max_retries
+                        );
+                        tokio::time::sleep(delay).await;
+                        attempt += 1;
+                        continue;
+                    }
+
+                    let error_text = response
+                        .text()
+                        .await
+                        .unwrap_or_else(|_| "Unknown error".to_string());
+                    return Err(HeliosError::LLMError(format!(
+                        "LLM API request failed with status {}: {}",
+                        status, error_text
+                    )));
+                }
+                Err(e) if attempt < retry.max_retries && is_retryable_error(&e) => {
+                    let delay = retry.backoff_with_jitter(attempt, jitter_sample());
+                    tracing::warn!(
+                        "LLM API request failed: {}, retrying in {:?} (attempt {}/{})",
+                        e,
+                        delay,
+                        attempt + 1,
+                        retry.max_retries
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// Rewrites tool calls and tool results as plain text for providers without tool support.
+///
+/// This keeps a conversation that started on a tool-capable provider readable when it
+/// continues on one that would reject `tool` messages.
+fn flatten_tool_messages(messages: &[ChatMessage]) -> Vec<ChatMessage> {
+    use crate::chat::Role;
+
+    messages
+        .iter()
+        .map(|message| match (&message.role, &message.tool_calls) {
+            (Role::Assistant, Some(calls)) => {
+                let mut content = message.content.clone();
+                for call in calls {
+                    if !content.is_empty() {
+                        content.push('\n');
+                    }
+                    content.push_str(&format!(
+                        "[Called tool {} with arguments {}]",
+                        call.function.name, call.function.arguments
+                    ));
+                }
+                ChatMessage::assistant(content)
+            }
+            (Role::Tool, _) => ChatMessage::user(format!("[Tool result] {}", message.content)),
+            _ => message.clone(),
+        })
+        .collect()
+}
+
+/// Returns `true` for HTTP statuses worth retrying: rate limits, timeouts and server errors.
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+        || status.is_server_error()
+}
+
+/// Returns `true` for transport errors worth retrying, such as refused or reset connections.
+fn is_retryable_error(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout() || error.is_request()
+}
+
+/// Parses the delay requested by the server via `retry-after-ms` or `Retry-After`.
+///
+/// `Retry-After` may be either a number of seconds or an HTTP date. The delay is
+/// capped at `max_delay`; values that are not finite numbers are ignored.
+fn retry_after(
+    headers: &reqwest::header::HeaderMap,
+    max_delay: std::time::Duration,
+) -> Option<std::time::Duration> {
+    let clamp = |seconds: f64| {
+        if !seconds.is_finite() {
+            return None;
+        }
+        std::time::Duration::try_from_secs_f64(seconds.clamp(0.0, max_delay.as_secs_f64())).ok()
+    };
+
+    if let Some(ms) = headers
+        .get("retry-after-ms")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<f64>().ok())
+    {
+        return clamp(ms / 1000.0);
+    }
+
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse::<f64>() {
+        return clamp(seconds);
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&chrono::Utc) - crate::clock::now();
+    Some(delay.to_std().unwrap_or_default().min(max_delay))
+}
+
+/// Returns a uniformly distributed value in `[0, 1)` used to jitter retry delays.
+fn jitter_sample() -> f64 {
+    const MANTISSA_BITS: u32 = 53;
+    let bits = uuid::Uuid::new_v4().as_u128() & ((1u128 << MANTISSA_BITS) - 1);
+    bits as f64 / (1u64 << MANTISSA_BITS) as f64
 }
 
 /// Suppresses stdout and stderr.
 #[cfg(feature = "local")]
-fn suppress_output() -> (i32, i32) {
+pub(crate) fn suppress_output() -> (i32, i32) {
     // Open /dev/null for writing
     let dev_null = File::open("/dev/null").expect("Failed to open /dev/null");
 
@@ -265,7 +1228,7 @@ fn suppress_output() -> (i32, i32) {
 
 /// Restores stdout and stderr.
 #[cfg(feature = "local")]
-fn restore_output(stdout_backup: i32, stderr_backup: i32) {
+pub(crate) fn restore_output(stdout_backup: i32, stderr_backup: i32) {
     unsafe {
         libc::dup2(stdout_backup, 1); // restore stdout
         libc::dup2(stderr_backup, 2); // restore stderr
@@ -294,57 +1257,400 @@ fn restore_stderr(stderr_backup: i32) {
     }
 }
 
-/// A provider for a local LLM.
+/// Returns the process-wide llama.cpp backend, initializing it on first use.
+///
+/// llama.cpp can only be initialized once per process, so local chat models and
+/// local embedding models share this handle.
 #[cfg(feature = "local")]
-pub struct LocalLLMProvider {
-    model: Arc<LlamaModel>,
-    backend: Arc<LlamaBackend>,
-}
+pub(crate) fn llama_backend() -> Result<Arc<LlamaBackend>> {
+    static BACKEND: std::sync::Mutex<Option<Arc<LlamaBackend>>> = std::sync::Mutex::new(None);
 
-#[cfg(feature = "local")]
-impl LocalLLMProvider {
-    /// Creates a new `LocalLLMProvider`.
-    pub async fn new(config: LocalConfig) -> Result<Self> {
-        // Suppress verbose output during model loading in offline mode
-        let (stdout_backup, stderr_backup) = suppress_output();
+    let mut backend = BACKEND.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(backend) = backend.as_ref() {
+        return Ok(Arc::clone(backend));
+    }
 
-        // Initialize llama backend
-        let backend = LlamaBackend::init().map_err(|e| {
-            restore_output(stdout_backup, stderr_backup);
-            HeliosError::LLMError(format!("Failed to initialize llama backend: {:?}", e))
-        })?;
+    let initialized = Arc::new(LlamaBackend::init().map_err(|e| {
+        HeliosError::LLMError(format!("Failed to initialize llama backend: {:?}", e))
+    })?);
+    *backend = Some(Arc::clone(&initialized));
+    Ok(initialized)
+}
 
-        // Download model from HuggingFace if needed
-        let model_path = Self::download_model(&config).await.map_err(|e| {
-            restore_output(stdout_backup, stderr_backup);
-            e
-        })?;
+/// Returns the parameters for loading the model of `config`.
+///
+/// GPU offload depends on the backends llama.cpp was built with, which is only
+/// known at runtime. Asking for GPU layers without one is reported as an error
+/// rather than silently running on the CPU.
+#[cfg(feature = "local")]
+pub(crate) fn model_params(
+    config: &LocalConfig,
+    backend: &LlamaBackend,
+) -> Result<LlamaModelParams> {
+    let gpu = backend.supports_gpu_offload();
+    let n_gpu_layers = match config.n_gpu_layers {
+        Some(layers) if layers > 0 && !gpu => {
+            return Err(HeliosError::ConfigError(format!(
+                "n_gpu_layers = {} needs GPU support, but this build of llama.cpp only runs on \
+                 the CPU. Rebuild with `--features cuda` (NVIDIA), `--features metal` (Apple) \
+                 or `--features vulkan`, or set n_gpu_layers = 0.",
+                layers
+            )))
+        }
+        Some(layers) => layers,
+        // More layers than any model has offloads all of them.
+        None if gpu => 999,
+        None => 0,
+    };
+    if config.use_mlock && !backend.supports_mlock() {
+        tracing::warn!("use_mlock is set, but this platform cannot lock memory; ignoring it");
+    }
+    tracing::debug!(
+        "Loading local model with {} GPU layers (GPU offload {})",
+        n_gpu_layers,
+        if gpu { "available" } else { "unavailable" }
+    );
+    Ok(LlamaModelParams::default()
+        .with_n_gpu_layers(n_gpu_layers)
+        .with_use_mmap(config.use_mmap && backend.supports_mmap())
+        .with_use_mlock(config.use_mlock && backend.supports_mlock()))
+}
 
-        // Load the model
-        let model_params = LlamaModelParams::default().with_n_gpu_layers(99); // Use GPU if available
+/// Returns the parameters for a context of `context_size` tokens.
+#[cfg(feature = "local")]
+fn context_params(context_size: u32, batch_size: u32) -> LlamaContextParams {
+    LlamaContextParams::default()
+        .with_n_ctx(std::num::NonZeroU32::new(context_size))
+        .with_n_batch(batch_size)
+}
 
-        let model =
-            LlamaModel::load_from_file(&backend, &model_path, &model_params).map_err(|e| {
-                restore_output(stdout_backup, stderr_backup);
-                HeliosError::LLMError(format!("Failed to load model: {:?}", e))
+/// Feeds the prompt to the model, at most `batch_size` tokens at a time.
+#[cfg(feature = "local")]
+fn decode_prompt(
+    context: &mut LlamaContext<'_>,
+    tokens: &[LlamaToken],
+    context_size: u32,
+    batch_size: u32,
+) -> Result<()> {
+    if tokens.len() >= context_size as usize {
+        return Err(HeliosError::LLMError(format!(
+            "The prompt is {} tokens, which does not fit the context size of {}; \
+             raise context_size in [local]",
+            tokens.len(),
+            context_size
+        )));
+    }
+    let batch_size = batch_size as usize;
+    let mut batch = LlamaBatch::new(batch_size, 1);
+    for (chunk_index, chunk) in tokens.chunks(batch_size).enumerate() {
+        batch.clear();
+        for (i, &token) in chunk.iter().enumerate() {
+            let position = chunk_index * batch_size + i;
+            // Generation only needs the logits after the last prompt token.
+            let last = position + 1 == tokens.len();
+            batch.add(token, position as i32, &[0], last).map_err(|e| {
+                HeliosError::LLMError(format!("Failed to add prompt token to batch: {:?}", e))
             })?;
-
-        // Restore output
-        restore_output(stdout_backup, stderr_backup);
-
-        Ok(Self {
-            model: Arc::new(model),
-            backend: Arc::new(backend),
-        })
+        }
+        context
+            .decode(&mut batch)
+            .map_err(|e| HeliosError::LLMError(format!("Failed to decode prompt: {:?}", e)))?;
     }
+    Ok(())
+}
 
-    /// Downloads a model from Hugging Face.
-    async fn download_model(config: &LocalConfig) -> Result<std::path::PathBuf> {
+/// Builds the sampler for a request with a grammar.
+///
+/// Without one, generation keeps picking the most likely token directly.
+#[cfg(feature = "local")]
+fn grammar_sampler(model: &LlamaModel, grammar: Option<&Grammar>) -> Result<Option<LlamaSampler>> {
+    grammar
+        .map(|grammar| {
+            LlamaSampler::grammar(model, grammar.as_gbnf(), "root")
+                .map(|grammar| LlamaSampler::chain_simple([grammar, LlamaSampler::greedy()]))
+                .map_err(|e| HeliosError::LLMError(format!("Invalid grammar: {:?}", e)))
+        })
+        .transpose()
+}
+
+/// Returns the most likely token in `logits` and its probability.
+#[cfg(feature = "local")]
+fn most_likely(logits: &[f32]) -> (LlamaToken, f32) {
+    let (index, max) =
+        logits
+            .iter()
+            .copied()
+            .enumerate()
+            .fold((0, f32::NEG_INFINITY), |best, (i, logit)| {
+                if logit > best.1 {
+                    (i, logit)
+                } else {
+                    best
+                }
+            });
+    let total: f32 = logits.iter().map(|logit| (logit - max).exp()).sum();
+    (LlamaToken(index as i32), 1.0 / total)
+}
+
+/// Decodes `tokens` from `position` on, with logits for every token or only the last.
+#[cfg(feature = "local")]
+fn decode_tokens(
+    context: &mut LlamaContext<'_>,
+    tokens: &[LlamaToken],
+    position: usize,
+    all_logits: bool,
+) -> Result<()> {
+    let mut batch = LlamaBatch::new(tokens.len().max(1), 1);
+    for (i, &token) in tokens.iter().enumerate() {
+        let logits = all_logits || i + 1 == tokens.len();
+        batch
+            .add(token, (position + i) as i32, &[0], logits)
+            .map_err(|e| HeliosError::LLMError(format!("Failed to add token to batch: {:?}", e)))?;
+    }
+    context
+        .decode(&mut batch)
+        .map_err(|e| HeliosError::LLMError(format!("Failed to decode token: {:?}", e)))
+}
+
+/// Drops everything from position `length` on from the KV cache.
+#[cfg(feature = "local")]
+fn truncate_cache(context: &mut LlamaContext<'_>, length: usize) -> Result<()> {
+    context
+        .clear_kv_cache_seq(Some(0), Some(length as u32), None)
+        .map(drop)
+        .map_err(|e| HeliosError::LLMError(format!("Failed to roll back the KV cache: {:?}", e)))
+}
+
+/// A draft model's context during speculative decoding.
+#[cfg(feature = "local")]
+struct Draft<'a> {
+    context: LlamaContext<'a>,
+    /// How many tokens of the generation so far are in the draft model's KV cache.
+    cached: usize,
+    max_tokens: usize,
+    min_probability: f32,
+}
+
+#[cfg(feature = "local")]
+impl<'a> Draft<'a> {
+    /// Creates a context for the draft model and feeds it the prompt.
+    fn new(
+        model: &'a LlamaModel,
+        backend: &LlamaBackend,
+        settings: &DraftModelConfig,
+        prompt: &[LlamaToken],
+        context_size: u32,
+        batch_size: u32,
+    ) -> Result<Self> {
+        let mut context = model
+            .new_context(backend, context_params(context_size, batch_size))
+            .map_err(|e| {
+                HeliosError::LLMError(format!("Failed to create draft context: {:?}", e))
+            })?;
+        decode_prompt(&mut context, prompt, context_size, batch_size)?;
+        Ok(Self {
+            context,
+            cached: prompt.len(),
+            // Everything fed to the draft model in one step must fit a batch.
+            max_tokens: (settings.max_draft_tokens as usize).min(batch_size as usize - 1),
+            min_probability: settings.min_probability,
+        })
+    }
+
+    /// Guesses up to `limit` tokens following `history`.
+    fn propose(
+        &mut self,
+        history: &[LlamaToken],
+        limit: usize,
+        eos: LlamaToken,
+    ) -> Result<Vec<LlamaToken>> {
+        let limit = limit.min(self.max_tokens);
+        let mut proposal = Vec::new();
+        if limit == 0 {
+            return Ok(proposal);
+        }
+        // Catch up on the tokens the main model produced since the last round.
+        truncate_cache(&mut self.context, self.cached)?;
+        decode_tokens(
+            &mut self.context,
+            &history[self.cached..],
+            self.cached,
+            false,
+        )?;
+        self.cached = history.len();
+        loop {
+            let (token, probability) = most_likely(self.context.get_logits());
+            if token == eos || probability < self.min_probability {
+                break;
+            }
+            proposal.push(token);
+            if proposal.len() == limit {
+                break;
+            }
+            decode_tokens(&mut self.context, &[token], self.cached, false)?;
+            self.cached += 1;
+        }
+        Ok(proposal)
+    }
+}
+
+/// Generates greedily with a draft model, returning the number of tokens generated.
+///
+/// Each round the draft model proposes a few tokens and the main model decodes
+/// them all at once. Proposed tokens are kept up to the first one that differs
+/// from the main model's own pick, which takes its place, so the text matches
+/// what plain greedy decoding would produce. `on_text` receives the text of each
+/// token and returns `false` to stop.
+#[cfg(feature = "local")]
+fn generate_speculative(
+    model: &LlamaModel,
+    context: &mut LlamaContext<'_>,
+    draft: &mut Draft<'_>,
+    prompt: &[LlamaToken],
+    max_new_tokens: usize,
+    context_size: usize,
+    mut on_text: impl FnMut(String) -> bool,
+) -> Result<usize> {
+    let eos = model.token_eos();
+    let mut history = prompt.to_vec();
+    let mut emit = |token: LlamaToken, history: &mut Vec<LlamaToken>| {
+        history.push(token);
+        match model.token_to_str(token, Special::Plaintext) {
+            Ok(text) => on_text(text),
+            Err(_) => true,
+        }
+    };
+    let (mut drafted, mut accepted) = (0, 0);
+
+    let mut next = most_likely(context.get_logits()).0;
+    'generation: loop {
+        let generated = history.len() - prompt.len();
+        if next == eos
+            || generated >= max_new_tokens
+            || history.len() >= context_size
+            || !emit(next, &mut history)
+        {
+            break;
+        }
+
+        let remaining = (max_new_tokens - generated - 1).min(context_size - history.len());
+        let proposal = draft.propose(&history, remaining, eos)?;
+        drafted += proposal.len();
+
+        // The main model scores the last token and every proposed one in one step.
+        let mut batch = vec![next];
+        batch.extend_from_slice(&proposal);
+        decode_tokens(context, &batch, history.len() - 1, true)?;
+        next = most_likely(context.get_logits_ith(proposal.len() as i32)).0;
+        for (i, &token) in proposal.iter().enumerate() {
+            let choice = most_likely(context.get_logits_ith(i as i32)).0;
+            if choice != token {
+                next = choice;
+                break;
+            }
+            accepted += 1;
+            if !emit(token, &mut history) {
+                break 'generation;
+            }
+        }
+
+        // Forget the rejected tokens in both caches.
+        truncate_cache(context, history.len())?;
+        draft.cached = draft.cached.min(history.len());
+    }
+
+    tracing::debug!(
+        "Speculative decoding accepted {} of {} drafted tokens",
+        accepted,
+        drafted
+    );
+    Ok(history.len() - prompt.len())
+}
+
+/// A provider for a local LLM.
+///
+/// The weights live in the [`LocalModelPool`], which the provider asks for its
+/// model on every request.
+#[cfg(feature = "local")]
+pub struct LocalLLMProvider {
+    config: LocalConfig,
+    model_path: std::path::PathBuf,
+    /// The load configuration and file of the draft model, if there is one.
+    draft: Option<(LocalConfig, std::path::PathBuf)>,
+    backend: Arc<LlamaBackend>,
+    context_size: u32,
+    batch_size: u32,
+}
+
+#[cfg(feature = "local")]
+impl LocalLLMProvider {
+    /// Creates a new `LocalLLMProvider`.
+    ///
+    /// The model is downloaded if needed and loaded into the pool, unless an
+    /// agent with the same model already loaded it.
+    pub async fn new(config: LocalConfig) -> Result<Self> {
+        let backend = llama_backend()?;
+
+        // Download model from HuggingFace if needed
+        let model_path = Self::download_model(&config).await?;
+        let model = LocalModelPool::load(&config, &model_path).await?;
+
+        let draft = match config.draft_model() {
+            Some(draft_config) => {
+                let draft_path = Self::download_model(&draft_config).await?;
+                let draft_model = LocalModelPool::load(&draft_config, &draft_path).await?;
+                if draft_model.n_vocab() != model.n_vocab() {
+                    return Err(HeliosError::ConfigError(format!(
+                        "The draft model {} has {} tokens in its vocabulary but the main model \
+                         has {}; the draft model must use the same tokenizer",
+                        draft_config.model_file,
+                        draft_model.n_vocab(),
+                        model.n_vocab()
+                    )));
+                }
+                Some((draft_config, draft_path))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            context_size: config.context_size.clamp(1, u32::MAX as usize) as u32,
+            batch_size: config.batch_size.max(1),
+            config,
+            model_path,
+            draft,
+            backend,
+        })
+    }
+
+    /// Returns the model from the pool, loading it again if it was evicted.
+    async fn model(&self) -> Result<Arc<LlamaModel>> {
+        LocalModelPool::load(&self.config, &self.model_path).await
+    }
+
+    /// Returns the draft model from the pool and its settings, if one is configured.
+    async fn draft_model(&self) -> Result<Option<(Arc<LlamaModel>, DraftModelConfig)>> {
+        let (Some((config, path)), Some(settings)) = (&self.draft, &self.config.draft) else {
+            return Ok(None);
+        };
+        let model = LocalModelPool::load(config, path).await?;
+        Ok(Some((model, settings.clone())))
+    }
+
+    /// Downloads a model from Hugging Face.
+    ///
+    /// A `model_file` naming a file on disk is used as is.
+    pub(crate) async fn download_model(config: &LocalConfig) -> Result<std::path::PathBuf> {
         use std::process::Command;
 
+        let model_file = config.model_file_name();
+        let local_file = std::path::Path::new(&model_file);
+        if local_file.is_file() {
+            return Ok(local_file.to_path_buf());
+        }
+
         // Check if model is already in HuggingFace cache
-        if let Some(cached_path) =
-            Self::find_model_in_cache(&config.huggingface_repo, &config.model_file)
+        if let Some(cached_path) = Self::find_model_in_cache(&config.huggingface_repo, &model_file)
         {
             // Model found in cache - no output needed in offline mode
             return Ok(cached_path);
@@ -357,7 +1663,7 @@ impl LocalLLMProvider {
             .args([
                 "download",
                 &config.huggingface_repo,
-                &config.model_file,
+                &model_file,
                 "--local-dir",
                 ".cache/models",
                 "--local-dir-use-symlinks",
@@ -375,7 +1681,7 @@ impl LocalLLMProvider {
             )));
         }
 
-        let model_path = std::path::PathBuf::from(".cache/models").join(&config.model_file);
+        let model_path = std::path::PathBuf::from(".cache/models").join(&model_file);
         if !model_path.exists() {
             return Err(HeliosError::LLMError(format!(
                 "Model file not found after download: {}",
@@ -479,35 +1785,7 @@ impl LLMProvider for RemoteLLMClient {
     }
 
     async fn generate(&self, request: LLMRequest) -> Result<LLMResponse> {
-        let url = format!("{}/chat/completions", self.config.base_url);
-
-        let mut request_builder = self
-            .client
-            .post(&url)
-            .header("Content-Type", "application/json");
-
-        // Only add authorization header if not a local vLLM instance
-        if !self.config.base_url.contains("10.")
-            && !self.config.base_url.contains("localhost")
-            && !self.config.base_url.contains("127.0.0.1")
-        {
-            request_builder =
-                request_builder.header("Authorization", format!("Bearer {}", self.config.api_key));
-        }
-
-        let response = request_builder.json(&request).send().await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(HeliosError::LLMError(format!(
-                "LLM API request failed with status {}: {}",
-                status, error_text
-            )));
-        }
+        let response = self.send_request(&request).await?;
 
         let llm_response: LLMResponse = response.json().await?;
         Ok(llm_response)
@@ -529,6 +1807,7 @@ impl RemoteLLMClient {
             messages,
             temperature: temperature.or(Some(self.config.temperature)),
             max_tokens: max_tokens.or(Some(self.config.max_tokens)),
+            max_completion_tokens: None,
             tools: tools.clone(),
             tool_choice: if tools.is_some() {
                 Some("auto".to_string())
@@ -537,6 +1816,9 @@ impl RemoteLLMClient {
             },
             stream: None,
             stop,
+            grammar: None,
+            logprobs: None,
+            top_logprobs: None,
         };
 
         let response = self.generate(request).await?;
@@ -557,16 +1839,86 @@ impl RemoteLLMClient {
         temperature: Option<f32>,
         max_tokens: Option<u32>,
         stop: Option<Vec<String>>,
-        mut on_chunk: F,
+        on_chunk: F,
     ) -> Result<ChatMessage>
     where
         F: FnMut(&str) + Send,
     {
+        self.chat_stream_with_usage(messages, tools, temperature, max_tokens, stop, on_chunk)
+            .await
+            .map(|(message, _)| message)
+    }
+
+    /// Sends a streaming chat request and returns the usage reported by the provider.
+    ///
+    /// Providers only report usage for streams when they support it; otherwise the
+    /// returned usage is `None`.
+    pub async fn chat_stream_with_usage<F>(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        stop: Option<Vec<String>>,
+        mut on_chunk: F,
+    ) -> Result<(ChatMessage, Option<Usage>)>
+    where
+        F: FnMut(&str) + Send,
+    {
+        self.chat_stream_events_with_usage(
+            messages,
+            tools,
+            temperature,
+            max_tokens,
+            stop,
+            |event| {
+                if let StreamEvent::Content(text) = event {
+                    on_chunk(&text);
+                }
+            },
+        )
+        .await
+    }
+
+    /// Sends a streaming chat request, reporting text and tool-call fragments as
+    /// [`StreamEvent`]s.
+    ///
+    /// The returned message carries the assembled `tool_calls`.
+    pub async fn chat_stream_events_with_usage<F>(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        stop: Option<Vec<String>>,
+        mut on_event: F,
+    ) -> Result<(ChatMessage, Option<Usage>)>
+    where
+        F: FnMut(StreamEvent) + Send,
+    {
+        if !self.capabilities.streaming {
+            if self.config.unsupported_parameters == UnsupportedParameters::Reject {
+                return Err(HeliosError::LLMError(format!(
+                    "Model '{}' does not support streaming",
+                    self.config.model_name
+                )));
+            }
+            let mut message = self
+                .chat(messages, tools, temperature, max_tokens, stop.clone())
+                .await?;
+            if let Some(matcher) = StopMatcher::for_request(&self.config, stop.as_deref())? {
+                matcher.truncate(&mut message.content);
+            }
+            emit_whole_message(&message, &mut on_event);
+            return Ok((message, None));
+        }
+
         let request = LLMRequest {
             model: self.config.model_name.clone(),
             messages,
             temperature: temperature.or(Some(self.config.temperature)),
             max_tokens: max_tokens.or(Some(self.config.max_tokens)),
+            max_completion_tokens: None,
             tools: tools.clone(),
             tool_choice: if tools.is_some() {
                 Some("auto".to_string())
@@ -575,45 +1927,23 @@ impl RemoteLLMClient {
             },
             stream: Some(true),
             stop,
+            grammar: None,
+            logprobs: None,
+            top_logprobs: None,
         };
 
-        let url = format!("{}/chat/completions", self.config.base_url);
-
-        let mut request_builder = self
-            .client
-            .post(&url)
-            .header("Content-Type", "application/json");
-
-        // Only add authorization header if not a local vLLM instance
-        if !self.config.base_url.contains("10.")
-            && !self.config.base_url.contains("localhost")
-            && !self.config.base_url.contains("127.0.0.1")
-        {
-            request_builder =
-                request_builder.header("Authorization", format!("Bearer {}", self.config.api_key));
-        }
-
-        let response = request_builder.json(&request).send().await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(HeliosError::LLMError(format!(
-                "LLM API request failed with status {}: {}",
-                status, error_text
-            )));
-        }
+        let mut matcher = StopMatcher::for_request(&self.config, request.stop.as_deref())?;
+        let response = self.send_request(&request).await?;
 
         let mut stream = response.bytes_stream();
         let mut full_content = String::new();
+        let mut full_reasoning = String::new();
         let mut role = None;
-        let mut tool_calls = Vec::new();
+        let mut tool_calls = ToolCallAccumulator::new();
         let mut buffer = String::new();
+        let mut usage = None;
 
-        while let Some(chunk_result) = stream.next().await {
+        'stream: while let Some(chunk_result) = stream.next().await {
             let chunk = chunk_result?;
             let chunk_str = String::from_utf8_lossy(&chunk);
             buffer.push_str(&chunk_str);
@@ -630,47 +1960,37 @@ impl RemoteLLMClient {
                 if let Some(data) = line.strip_prefix("data: ") {
                     match serde_json::from_str::<StreamChunk>(data) {
                         Ok(stream_chunk) => {
+                            if stream_chunk.usage.is_some() {
+                                usage = stream_chunk.usage.clone();
+                            }
                             if let Some(choice) = stream_chunk.choices.first() {
                                 if let Some(r) = &choice.delta.role {
                                     role = Some(r.clone());
                                 }
+                                if let Some(reasoning) =
+                                    choice.delta.reasoning.as_ref().filter(|r| !r.is_empty())
+                                {
+                                    full_reasoning.push_str(reasoning);
+                                    on_event(StreamEvent::Reasoning(reasoning.clone()));
+                                }
                                 if let Some(content) = &choice.delta.content {
-                                    full_content.push_str(content);
-                                    on_chunk(content);
+                                    if let Some(matcher) = matcher.as_mut() {
+                                        let text = matcher.push(content);
+                                        if !text.is_empty() {
+                                            on_event(StreamEvent::Content(text.to_string()));
+                                        }
+                                        if matcher.stopped() {
+                                            // Dropping the stream cancels the request.
+                                            break 'stream;
+                                        }
+                                    } else {
+                                        full_content.push_str(content);
+                                        on_event(StreamEvent::Content(content.clone()));
+                                    }
                                 }
                                 if let Some(delta_tool_calls) = &choice.delta.tool_calls {
                                     for delta_tool_call in delta_tool_calls {
-                                        // Find or create the tool call at this index
-                                        while tool_calls.len() <= delta_tool_call.index as usize {
-                                            tool_calls.push(None);
-                                        }
-                                        let tool_call_slot =
-                                            &mut tool_calls[delta_tool_call.index as usize];
-
-                                        if tool_call_slot.is_none() {
-                                            *tool_call_slot = Some(crate::chat::ToolCall {
-                                                id: String::new(),
-                                                call_type: "function".to_string(),
-                                                function: crate::chat::FunctionCall {
-                                                    name: String::new(),
-                                                    arguments: String::new(),
-                                                },
-                                            });
-                                        }
-
-                                        if let Some(tool_call) = tool_call_slot.as_mut() {
-                                            if let Some(id) = &delta_tool_call.id {
-                                                tool_call.id = id.clone();
-                                            }
-                                            if let Some(function) = &delta_tool_call.function {
-                                                if let Some(name) = &function.name {
-                                                    tool_call.function.name = name.clone();
-                                                }
-                                                if let Some(args) = &function.arguments {
-                                                    tool_call.function.arguments.push_str(args);
-                                                }
-                                            }
-                                        }
+                                        on_event(tool_calls.push(delta_tool_call));
                                     }
                                 }
                             }
@@ -683,20 +2003,25 @@ impl RemoteLLMClient {
             }
         }
 
-        let final_tool_calls = tool_calls.into_iter().flatten().collect::<Vec<_>>();
-        let tool_calls_option = if final_tool_calls.is_empty() {
-            None
-        } else {
-            Some(final_tool_calls)
-        };
+        drop(stream);
+        if let Some(mut matcher) = matcher {
+            let tail = matcher.finish();
+            if !tail.is_empty() {
+                on_event(StreamEvent::Content(tail.to_string()));
+            }
+            full_content = matcher.text().to_string();
+        }
 
-        Ok(ChatMessage {
+        let message = ChatMessage {
             role: crate::chat::Role::from(role.as_deref().unwrap_or("assistant")),
             content: full_content,
             name: None,
-            tool_calls: tool_calls_option,
+            tool_calls: tool_calls.finish(),
             tool_call_id: None,
-        })
+            reasoning: (!full_reasoning.is_empty()).then_some(full_reasoning),
+        };
+
+        Ok((message, usage))
     }
 }
 
@@ -709,21 +2034,20 @@ impl LLMProvider for LocalLLMProvider {
 
     async fn generate(&self, request: LLMRequest) -> Result<LLMResponse> {
         let prompt = self.format_messages(&request.messages);
+        let grammar = request.grammar;
+        let model = self.model().await?;
+        let draft = self.draft_model().await?;
 
         // Suppress output during inference in offline mode
         let (stdout_backup, stderr_backup) = suppress_output();
 
         // Run inference in a blocking task
-        let model = Arc::clone(&self.model);
         let backend = Arc::clone(&self.backend);
+        let (context_size, batch_size) = (self.context_size, self.batch_size);
         let result = task::spawn_blocking(move || {
             // Create a fresh context per request (model/back-end are reused across calls)
-            use std::num::NonZeroU32;
-            let ctx_params =
-                LlamaContextParams::default().with_n_ctx(Some(NonZeroU32::new(2048).unwrap()));
-
             let mut context = model
-                .new_context(&backend, ctx_params)
+                .new_context(&backend, context_params(context_size, batch_size))
                 .map_err(|e| HeliosError::LLMError(format!("Failed to create context: {:?}", e)))?;
 
             // Tokenize the prompt
@@ -732,44 +2056,61 @@ impl LLMProvider for LocalLLMProvider {
                 .str_to_token(&prompt, AddBos::Always)
                 .map_err(|e| HeliosError::LLMError(format!("Tokenization failed: {:?}", e)))?;
 
-            // Create batch for prompt
-            let mut prompt_batch = LlamaBatch::new(tokens.len(), 1);
-            for (i, &token) in tokens.iter().enumerate() {
-                let compute_logits = true; // Compute logits for all tokens (they accumulate)
-                prompt_batch
-                    .add(token, i as i32, &[0], compute_logits)
-                    .map_err(|e| {
-                        HeliosError::LLMError(format!(
-                            "Failed to add prompt token to batch: {:?}",
-                            e
-                        ))
-                    })?;
-            }
-
             // Decode the prompt
-            context
-                .decode(&mut prompt_batch)
-                .map_err(|e| HeliosError::LLMError(format!("Failed to decode prompt: {:?}", e)))?;
+            decode_prompt(&mut context, &tokens, context_size, batch_size)?;
+
+            // Speculate with the draft model unless a grammar constrains sampling
+            if let Some((draft_model, settings)) = draft.as_ref().filter(|_| grammar.is_none()) {
+                let mut draft = Draft::new(
+                    draft_model,
+                    &backend,
+                    settings,
+                    &tokens,
+                    context_size,
+                    batch_size,
+                )?;
+                let mut generated_text = String::new();
+                let completion_tokens = generate_speculative(
+                    &model,
+                    &mut context,
+                    &mut draft,
+                    &tokens,
+                    512,
+                    context_size as usize,
+                    |text| {
+                        generated_text.push_str(&text);
+                        true
+                    },
+                )?;
+                return Ok((generated_text, tokens.len(), completion_tokens));
+            }
 
             // Generate response tokens
+            let mut sampler = grammar_sampler(&model, grammar.as_ref())?;
             let mut generated_text = String::new();
             let max_new_tokens = 512; // Increased limit for better responses
             let mut next_pos = tokens.len() as i32; // Start after the prompt tokens
 
             for _ in 0..max_new_tokens {
-                // Get logits from the last decoded position (get_logits returns logits for the last token)
-                let logits = context.get_logits();
-
-                let token_idx = logits
-                    .iter()
-                    .enumerate()
-                    .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
-                    .map(|(idx, _)| idx)
-                    .unwrap_or_else(|| {
-                        let eos = context.model.token_eos();
-                        eos.0 as usize
-                    });
-                let token = LlamaToken(token_idx as i32);
+                let token = match sampler.as_mut() {
+                    // Sampling through the grammar also advances it past the token.
+                    Some(sampler) => sampler.sample(&context, -1),
+                    None => {
+                        // Get logits from the last decoded position (get_logits returns logits for the last token)
+                        let logits = context.get_logits();
+
+                        let token_idx = logits
+                            .iter()
+                            .enumerate()
+                            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+                            .map(|(idx, _)| idx)
+                            .unwrap_or_else(|| {
+                                let eos = context.model.token_eos();
+                                eos.0 as usize
+                            });
+                        LlamaToken(token_idx as i32)
+                    }
+                };
 
                 // Check for end of sequence
                 if token == context.model.token_eos() {
@@ -801,7 +2142,12 @@ impl LLMProvider for LocalLLMProvider {
                 next_pos += 1;
             }
 
-            Ok::<String, HeliosError>(generated_text)
+            let completion_tokens = next_pos as usize - tokens.len();
+            Ok::<(String, usize, usize), HeliosError>((
+                generated_text,
+                tokens.len(),
+                completion_tokens,
+            ))
         })
         .await
         .map_err(|e| {
@@ -812,10 +2158,12 @@ impl LLMProvider for LocalLLMProvider {
         // Restore output after inference completes
         restore_output(stdout_backup, stderr_backup);
 
+        let (result, prompt_tokens, completion_tokens) = result;
+
         let response = LLMResponse {
-            id: format!("local-{}", chrono::Utc::now().timestamp()),
+            id: format!("local-{}", crate::clock::new_id()),
             object: "chat.completion".to_string(),
-            created: chrono::Utc::now().timestamp() as u64,
+            created: crate::clock::now().timestamp() as u64,
             model: "local-model".to_string(),
             choices: vec![Choice {
                 index: 0,
@@ -825,14 +2173,13 @@ impl LLMProvider for LocalLLMProvider {
                     name: None,
                     tool_calls: None,
                     tool_call_id: None,
+                    reasoning: None,
                 },
                 finish_reason: Some("stop".to_string()),
+                logprobs: None,
+                metadata: Default::default(),
             }],
-            usage: Usage {
-                prompt_tokens: 0,     // TODO: Calculate actual token count
-                completion_tokens: 0, // TODO: Calculate actual token count
-                total_tokens: 0,      // TODO: Calculate actual token count
-            },
+            usage: Usage::new(prompt_tokens as u32, completion_tokens as u32),
         };
 
         Ok(response)
@@ -847,13 +2194,17 @@ impl LocalLLMProvider {
         messages: Vec<ChatMessage>,
         _temperature: Option<f32>,
         _max_tokens: Option<u32>,
-        _stop: Option<Vec<String>>,
+        stop: Option<Vec<String>>,
+        grammar: Option<Grammar>,
         mut on_chunk: F,
     ) -> Result<ChatMessage>
     where
         F: FnMut(&str) + Send,
     {
+        let mut matcher = StopMatcher::new(&stop.unwrap_or_default(), &[])?;
         let prompt = self.format_messages(&messages);
+        let model = self.model().await?;
+        let draft = self.draft_model().await?;
 
         // Suppress only stderr so llama.cpp context logs are hidden but stdout streaming remains visible
         let stderr_backup = suppress_stderr();
@@ -862,16 +2213,12 @@ impl LocalLLMProvider {
         let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
 
         // Spawn blocking task for generation
-        let model = Arc::clone(&self.model);
         let backend = Arc::clone(&self.backend);
+        let (context_size, batch_size) = (self.context_size, self.batch_size);
         let generation_task = task::spawn_blocking(move || {
             // Create a fresh context per request (model/back-end are reused across calls)
-            use std::num::NonZeroU32;
-            let ctx_params =
-                LlamaContextParams::default().with_n_ctx(Some(NonZeroU32::new(2048).unwrap()));
-
             let mut context = model
-                .new_context(&backend, ctx_params)
+                .new_context(&backend, context_params(context_size, batch_size))
                 .map_err(|e| HeliosError::LLMError(format!("Failed to create context: {:?}", e)))?;
 
             // Tokenize the prompt
@@ -880,43 +2227,60 @@ impl LocalLLMProvider {
                 .str_to_token(&prompt, AddBos::Always)
                 .map_err(|e| HeliosError::LLMError(format!("Tokenization failed: {:?}", e)))?;
 
-            // Create batch for prompt
-            let mut prompt_batch = LlamaBatch::new(tokens.len(), 1);
-            for (i, &token) in tokens.iter().enumerate() {
-                let compute_logits = true;
-                prompt_batch
-                    .add(token, i as i32, &[0], compute_logits)
-                    .map_err(|e| {
-                        HeliosError::LLMError(format!(
-                            "Failed to add prompt token to batch: {:?}",
-                            e
-                        ))
-                    })?;
-            }
-
             // Decode the prompt
-            context
-                .decode(&mut prompt_batch)
-                .map_err(|e| HeliosError::LLMError(format!("Failed to decode prompt: {:?}", e)))?;
+            decode_prompt(&mut context, &tokens, context_size, batch_size)?;
+
+            // Speculate with the draft model unless a grammar constrains sampling
+            if let Some((draft_model, settings)) = draft.as_ref().filter(|_| grammar.is_none()) {
+                let mut draft = Draft::new(
+                    draft_model,
+                    &backend,
+                    settings,
+                    &tokens,
+                    context_size,
+                    batch_size,
+                )?;
+                let mut generated_text = String::new();
+                generate_speculative(
+                    &model,
+                    &mut context,
+                    &mut draft,
+                    &tokens,
+                    512,
+                    context_size as usize,
+                    |text| {
+                        generated_text.push_str(&text);
+                        // Stop if the receiver is dropped
+                        tx.send(text).is_ok()
+                    },
+                )?;
+                return Ok(generated_text);
+            }
 
             // Generate response tokens with streaming
+            let mut sampler = grammar_sampler(&model, grammar.as_ref())?;
             let mut generated_text = String::new();
             let max_new_tokens = 512;
             let mut next_pos = tokens.len() as i32;
 
             for _ in 0..max_new_tokens {
-                let logits = context.get_logits();
-
-                let token_idx = logits
-                    .iter()
-                    .enumerate()
-                    .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
-                    .map(|(idx, _)| idx)
-                    .unwrap_or_else(|| {
-                        let eos = context.model.token_eos();
-                        eos.0 as usize
-                    });
-                let token = LlamaToken(token_idx as i32);
+                let token = match sampler.as_mut() {
+                    Some(sampler) => sampler.sample(&context, -1),
+                    None => {
+                        let logits = context.get_logits();
+
+                        let token_idx = logits
+                            .iter()
+                            .enumerate()
+                            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+                            .map(|(idx, _)| idx)
+                            .unwrap_or_else(|| {
+                                let eos = context.model.token_eos();
+                                eos.0 as usize
+                            });
+                        LlamaToken(token_idx as i32)
+                    }
+                };
 
                 // Check for end of sequence
                 if token == context.model.token_eos() {
@@ -957,8 +2321,21 @@ impl LocalLLMProvider {
 
         // Receive and process tokens as they arrive
         while let Some(token) = rx.recv().await {
-            on_chunk(&token);
+            match matcher.as_mut() {
+                Some(matcher) => {
+                    let text = matcher.push(&token);
+                    if !text.is_empty() {
+                        on_chunk(text);
+                    }
+                    if matcher.stopped() {
+                        // Dropping the receiver makes the generation loop stop.
+                        break;
+                    }
+                }
+                None => on_chunk(&token),
+            }
         }
+        drop(rx);
 
         // Wait for generation to complete and get the result
         let result = match generation_task.await {
@@ -976,12 +2353,24 @@ impl LocalLLMProvider {
         // Restore stderr after generation completes
         restore_stderr(stderr_backup);
 
+        let result = match matcher.as_mut() {
+            Some(matcher) => {
+                let tail = matcher.finish();
+                if !tail.is_empty() {
+                    on_chunk(tail);
+                }
+                matcher.text().to_string()
+            }
+            None => result,
+        };
+
         Ok(ChatMessage {
             role: crate::chat::Role::Assistant,
             content: result,
             name: None,
             tool_calls: None,
             tool_call_id: None,
+            reasoning: None,
         })
     }
 }
@@ -993,7 +2382,79 @@ impl LLMProvider for LLMClient {
     }
 
     async fn generate(&self, request: LLMRequest) -> Result<LLMResponse> {
-        self.provider.generate(request).await
+        let messages = request.messages.clone();
+        let (model, response) = self.generate_with_fallbacks(request).await?;
+        Ok(self.finish_response(&model, &messages, response))
+    }
+}
+
+impl LLMClient {
+    /// Splits out reasoning and records the usage of a response from `model`,
+    /// estimating it from `messages` when the provider did not report it.
+    fn finish_response(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        mut response: LLMResponse,
+    ) -> LLMResponse {
+        for choice in &mut response.choices {
+            extract_reasoning(&mut choice.message);
+        }
+
+        if response.usage.is_empty() {
+            let completion = response
+                .choices
+                .first()
+                .map(|choice| choice.message.content.as_str())
+                .unwrap_or_default();
+            response.usage = Usage::estimate(messages, completion);
+        }
+        self.usage.record(model, &response.usage);
+        response
+    }
+}
+
+impl LLMClient {
+    /// Sends a request to the primary provider, falling back to each configured
+    /// fallback in turn. Returns the model name of the provider that answered.
+    async fn generate_with_fallbacks(&self, request: LLMRequest) -> Result<(String, LLMResponse)> {
+        let primary: (&dyn LLMProvider, &CircuitBreaker, String) =
+            (self.provider.as_ref(), &self.breaker, request.model.clone());
+        let candidates = std::iter::once(primary).chain(self.fallbacks.iter().map(|fallback| {
+            (
+                &fallback.client as &dyn LLMProvider,
+                &fallback.breaker,
+                fallback.client.config().model_name.clone(),
+            )
+        }));
+
+        let mut last_error = None;
+        for (provider, breaker, model) in candidates {
+            if let Some(error) = &last_error {
+                tracing::warn!(
+                    "LLM provider failed ({}), falling back to '{}'",
+                    error,
+                    model
+                );
+            }
+            if let Err(e) = breaker.check() {
+                last_error = Some(e);
+                continue;
+            }
+
+            let mut attempt = request.clone();
+            attempt.model = model.clone();
+            match breaker.record(provider.generate(attempt).await) {
+                Ok(response) => {
+                    self.set_last_provider(&model);
+                    return Ok((model, response));
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error
+            .unwrap_or_else(|| HeliosError::LLMError("No LLM provider available".to_string())))
     }
 }
 
@@ -1007,6 +2468,240 @@ impl LLMClient {
         max_tokens: Option<u32>,
         stop: Option<Vec<String>>,
     ) -> Result<ChatMessage> {
+        self.chat_with_usage(messages, tools, temperature, max_tokens, stop)
+            .await
+            .map(|(message, _)| message)
+    }
+
+    /// Sends a chat request to the LLM and returns the token usage of the call.
+    ///
+    /// The usage is also recorded in the client's [`UsageTracker`].
+    pub async fn chat_with_usage(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        stop: Option<Vec<String>>,
+    ) -> Result<(ChatMessage, Usage)> {
+        self.complete(messages, tools, temperature, max_tokens, stop, None)
+            .await
+    }
+
+    /// Sends a chat request whose response must follow `grammar`.
+    ///
+    /// With the `local` provider only tokens the grammar allows are sampled, so a
+    /// grammar from [`Grammar::json_schema`] guarantees a response that parses.
+    /// Remote providers ignore the grammar.
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{ChatMessage, Grammar, LLMClient};
+    /// # async fn example(client: LLMClient) -> helios_engine::Result<()> {
+    /// let grammar = Grammar::json_schema(&serde_json::json!({
+    ///     "type": "object",
+    ///     "properties": {"sentiment": {"enum": ["positive", "negative"]}},
+    ///     "required": ["sentiment"]
+    /// }))?;
+    /// let reply = client
+    ///     .chat_with_grammar(vec![ChatMessage::user("Classify: great product!")], grammar)
+    ///     .await?;
+    /// let parsed: serde_json::Value = serde_json::from_str(&reply.content)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn chat_with_grammar(
+        &self,
+        messages: Vec<ChatMessage>,
+        grammar: Grammar,
+    ) -> Result<ChatMessage> {
+        self.complete(messages, None, None, None, None, Some(grammar))
+            .await
+            .map(|(message, _)| message)
+    }
+
+    /// Sends a chat request, constrained by `grammar` if the provider supports it.
+    pub(crate) async fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        stop: Option<Vec<String>>,
+        grammar: Option<Grammar>,
+    ) -> Result<(ChatMessage, Usage)> {
+        let mut request =
+            self.build_request(messages, tools, temperature, max_tokens, stop.clone());
+        request.grammar = grammar;
+
+        // Cache hits cost nothing, so they report and record no usage.
+        let cache_key = self.cache_key(&request);
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+            if let Some(message) = cache.get(key) {
+                return Ok((message, Usage::default()));
+            }
+        }
+
+        let response = self.generate(request).await?;
+        let usage = response.usage;
+
+        let mut message = response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message)
+            .ok_or_else(|| HeliosError::LLMError("No response from LLM".to_string()))?;
+        #[allow(irrefutable_let_patterns)]
+        if let LLMProviderType::Remote(config) = &self.provider_type {
+            if let Some(matcher) = StopMatcher::for_request(config, stop.as_deref())? {
+                matcher.truncate(&mut message.content);
+            }
+        }
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+            cache.put(key, &message);
+        }
+        Ok((message, usage))
+    }
+
+    /// Sends a chat request asking for the log probability of each generated token.
+    ///
+    /// Returns the whole response, so each choice's `logprobs`, `finish_reason`
+    /// and any other fields the provider sent can be inspected. `top_logprobs`
+    /// asks for that many of the most likely alternatives at each position; most
+    /// providers allow up to 20. Providers without log probabilities, including
+    /// the local ones, return choices without them, or fail when
+    /// `unsupported_parameters` is `reject`.
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{ChatMessage, LLMClient};
+    /// # async fn example(client: LLMClient) -> helios_engine::Result<()> {
+    /// let response = client
+    ///     .chat_with_logprobs(vec![ChatMessage::user("Who wrote Dune?")], 3)
+    ///     .await?;
+    /// let choice = &response.choices[0];
+    /// if let Some(perplexity) = choice.logprobs.as_ref().and_then(|l| l.perplexity()) {
+    ///     println!("{} (perplexity {:.2})", choice.message.content, perplexity);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn chat_with_logprobs(
+        &self,
+        messages: Vec<ChatMessage>,
+        top_logprobs: u8,
+    ) -> Result<LLMResponse> {
+        let mut request = self.build_request(messages, None, None, None, None);
+        request.logprobs = Some(true);
+        request.top_logprobs = (top_logprobs > 0).then_some(top_logprobs);
+        self.generate(request).await
+    }
+
+    /// Sends one chat request per conversation and returns the replies in order.
+    ///
+    /// Runs with the default [`BatchOptions`]: through the provider's Batch API
+    /// when it has one, which can take up to a day, and otherwise as direct
+    /// requests, eight at a time. See [`LLMClient::chat_batch_with`].
+    pub async fn chat_batch(
+        &self,
+        conversations: Vec<Vec<ChatMessage>>,
+    ) -> Result<Vec<Result<ChatMessage>>> {
+        self.chat_batch_with(conversations, &BatchOptions::default())
+            .await
+    }
+
+    /// Sends one chat request per conversation, as configured by `options`.
+    ///
+    /// Each conversation gets its own result, in the order they were given, so a
+    /// failed request does not fail the others. The call itself only fails when
+    /// a provider batch cannot be submitted or is rejected as a whole. Batched
+    /// requests skip the response cache and fallbacks, and their usage is
+    /// recorded like any other call.
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::{BatchOptions, ChatMessage, LLMClient};
+    /// # async fn example(client: LLMClient, reviews: Vec<String>) -> helios_engine::Result<()> {
+    /// let conversations = reviews
+    ///     .iter()
+    ///     .map(|review| {
+    ///         vec![
+    ///             ChatMessage::system("Label the review as positive or negative."),
+    ///             ChatMessage::user(review.as_str()),
+    ///         ]
+    ///     })
+    ///     .collect();
+    /// let labels = client
+    ///     .chat_batch_with(conversations, &BatchOptions::default().max_concurrency(16))
+    ///     .await?;
+    /// for (review, label) in reviews.iter().zip(labels) {
+    ///     match label {
+    ///         Ok(label) => println!("{}: {}", label.content, review),
+    ///         Err(e) => eprintln!("failed: {}", e),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn chat_batch_with(
+        &self,
+        conversations: Vec<Vec<ChatMessage>>,
+        options: &BatchOptions,
+    ) -> Result<Vec<Result<ChatMessage>>> {
+        let remote = self.provider.as_any().downcast_ref::<RemoteLLMClient>();
+        let remote = match options.mode {
+            BatchMode::Concurrent => None,
+            BatchMode::Auto => remote.filter(|remote| remote.capabilities().batch_api),
+            BatchMode::Provider => Some(remote.ok_or_else(|| {
+                HeliosError::LLMError(
+                    "Provider batches need a remote provider with the Batch API".to_string(),
+                )
+            })?),
+        };
+        let Some(remote) = remote else {
+            return Ok(futures::stream::iter(conversations)
+                .map(|messages| self.chat(messages, None, None, None, None))
+                .buffered(options.max_concurrency.max(1))
+                .collect()
+                .await);
+        };
+
+        let requests: Vec<LLMRequest> = conversations
+            .into_iter()
+            .map(|messages| self.build_request(messages, None, None, None, None))
+            .collect();
+        let prompts: Vec<Vec<ChatMessage>> = requests
+            .iter()
+            .map(|request| request.messages.clone())
+            .collect();
+        let model = remote.config().model_name.clone();
+        let results = crate::batch::run(remote, requests, options).await?;
+
+        Ok(results
+            .into_iter()
+            .zip(prompts)
+            .map(|(result, messages)| {
+                let response = self.finish_response(&model, &messages, result?);
+                let mut message = response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .map(|choice| choice.message)
+                    .ok_or_else(|| HeliosError::LLMError("No response from LLM".to_string()))?;
+                if let Some(matcher) = StopMatcher::for_request(remote.config(), None)? {
+                    matcher.truncate(&mut message.content);
+                }
+                Ok(message)
+            })
+            .collect())
+    }
+
+    /// Builds a request, filling in the provider's default generation parameters.
+    fn build_request(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        stop: Option<Vec<String>>,
+    ) -> LLMRequest {
         let (model_name, default_temperature, default_max_tokens) = match &self.provider_type {
             LLMProviderType::Remote(config) => (
                 config.model_name.clone(),
@@ -1027,11 +2722,12 @@ impl LLMClient {
             ),
         };
 
-        let request = LLMRequest {
+        LLMRequest {
             model: model_name,
             messages,
             temperature: temperature.or(Some(default_temperature)),
             max_tokens: max_tokens.or(Some(default_max_tokens)),
+            max_completion_tokens: None,
             tools: tools.clone(),
             tool_choice: if tools.is_some() {
                 Some("auto".to_string())
@@ -1040,16 +2736,10 @@ impl LLMClient {
             },
             stream: None,
             stop,
-        };
-
-        let response = self.generate(request).await?;
-
-        response
-            .choices
-            .into_iter()
-            .next()
-            .map(|choice| choice.message)
-            .ok_or_else(|| HeliosError::LLMError("No response from LLM".to_string()))
+            grammar: None,
+            logprobs: None,
+            top_logprobs: None,
+        }
     }
 
     /// Sends a streaming chat request to the LLM.
@@ -1064,23 +2754,252 @@ impl LLMClient {
     ) -> Result<ChatMessage>
     where
         F: FnMut(&str) + Send,
+    {
+        self.chat_stream_with_usage(messages, tools, temperature, max_tokens, stop, on_chunk)
+            .await
+            .map(|(message, _)| message)
+    }
+
+    /// Sends a streaming chat request to the LLM and returns the token usage of the call.
+    ///
+    /// When the provider does not report usage for streamed responses, it is estimated
+    /// from the prompt and completion text. The usage is also recorded in the client's
+    /// [`UsageTracker`].
+    pub async fn chat_stream_with_usage<F>(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        stop: Option<Vec<String>>,
+        mut on_chunk: F,
+    ) -> Result<(ChatMessage, Usage)>
+    where
+        F: FnMut(&str) + Send,
+    {
+        self.chat_stream_events_with_usage(
+            messages,
+            tools,
+            temperature,
+            max_tokens,
+            stop,
+            |event| {
+                if let StreamEvent::Content(text) = event {
+                    on_chunk(&text);
+                }
+            },
+        )
+        .await
+    }
+
+    /// Sends a streaming chat request, reporting text and tool-call fragments as
+    /// [`StreamEvent`]s.
+    ///
+    /// Tool-call fragments are assembled into the `tool_calls` of the returned
+    /// message. Providers that don't stream report the whole response at once,
+    /// with one fragment per tool call.
+    pub async fn chat_stream_events<F>(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        stop: Option<Vec<String>>,
+        on_event: F,
+    ) -> Result<ChatMessage>
+    where
+        F: FnMut(StreamEvent) + Send,
+    {
+        self.chat_stream_events_with_usage(messages, tools, temperature, max_tokens, stop, on_event)
+            .await
+            .map(|(message, _)| message)
+    }
+
+    /// Like [`LLMClient::chat_stream_events`], also returning the token usage of the call.
+    pub async fn chat_stream_events_with_usage<F>(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        stop: Option<Vec<String>>,
+        on_event: F,
+    ) -> Result<(ChatMessage, Usage)>
+    where
+        F: FnMut(StreamEvent) + Send,
+    {
+        self.stream(
+            messages,
+            tools,
+            temperature,
+            max_tokens,
+            stop,
+            None,
+            on_event,
+        )
+        .await
+    }
+
+    /// Streams a chat request, constrained by `grammar` if the provider supports it.
+    ///
+    /// Reasoning in tags is reported as [`StreamEvent::Reasoning`] and moved to the
+    /// returned message's `reasoning`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn stream<F>(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        stop: Option<Vec<String>>,
+        grammar: Option<Grammar>,
+        mut on_event: F,
+    ) -> Result<(ChatMessage, Usage)>
+    where
+        F: FnMut(StreamEvent) + Send,
+    {
+        let mut splitter = ReasoningSplitter::new();
+        let result = self
+            .stream_raw(
+                messages,
+                tools,
+                temperature,
+                max_tokens,
+                stop,
+                grammar,
+                |event| match event {
+                    StreamEvent::Content(text) => splitter.push(&text, &mut on_event),
+                    event => on_event(event),
+                },
+            )
+            .await;
+        splitter.finish(&mut on_event);
+        let (mut message, usage) = result?;
+        extract_reasoning(&mut message);
+        Ok((message, usage))
+    }
+
+    /// Streams a chat request as the provider sends it.
+    #[allow(clippy::too_many_arguments)]
+    async fn stream_raw<F>(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        stop: Option<Vec<String>>,
+        grammar: Option<Grammar>,
+        mut on_event: F,
+    ) -> Result<(ChatMessage, Usage)>
+    where
+        F: FnMut(StreamEvent) + Send,
     {
         match &self.provider_type {
             LLMProviderType::Remote(_) => {
-                if let Some(provider) = self.provider.as_any().downcast_ref::<RemoteLLMClient>() {
-                    provider
-                        .chat_stream(messages, tools, temperature, max_tokens, stop, on_chunk)
-                        .await
-                } else {
-                    Err(HeliosError::AgentError("Provider type mismatch".into()))
+                let Some(primary) = self.provider.as_any().downcast_ref::<RemoteLLMClient>() else {
+                    // Providers set with `from_provider` do not stream.
+                    let (message, usage) = self
+                        .complete(messages, tools, temperature, max_tokens, stop, grammar)
+                        .await?;
+                    emit_whole_message(&message, &mut on_event);
+                    return Ok((message, usage));
+                };
+
+                let cache_key = self.cache_key(&self.build_request(
+                    messages.clone(),
+                    tools.clone(),
+                    temperature,
+                    max_tokens,
+                    stop.clone(),
+                ));
+                if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+                    if let Some(message) = cache.get(key) {
+                        emit_whole_message(&message, &mut on_event);
+                        return Ok((message, Usage::default()));
+                    }
+                }
+
+                let candidates = std::iter::once((primary, &self.breaker)).chain(
+                    self.fallbacks
+                        .iter()
+                        .map(|fallback| (&fallback.client, &fallback.breaker)),
+                );
+
+                let mut last_error = None;
+                for (provider, breaker) in candidates {
+                    let model = provider.config().model_name.clone();
+                    if let Some(error) = &last_error {
+                        tracing::warn!(
+                            "LLM provider failed ({}), falling back to '{}'",
+                            error,
+                            model
+                        );
+                    }
+                    if let Err(e) = breaker.check() {
+                        last_error = Some(e);
+                        continue;
+                    }
+
+                    // Once text has reached the caller, switching providers would repeat it.
+                    let mut emitted = false;
+                    let result = {
+                        let emitted = &mut emitted;
+                        let on_event = &mut on_event;
+                        provider
+                            .chat_stream_events_with_usage(
+                                messages.clone(),
+                                tools.clone(),
+                                temperature,
+                                max_tokens,
+                                stop.clone(),
+                                move |event: StreamEvent| {
+                                    *emitted = true;
+                                    on_event(event);
+                                },
+                            )
+                            .await
+                    };
+
+                    match breaker.record(result) {
+                        Ok((message, usage)) => {
+                            let usage = usage
+                                .filter(|u| !u.is_empty())
+                                .unwrap_or_else(|| Usage::estimate(&messages, &message.content));
+                            self.usage.record(&model, &usage);
+                            self.set_last_provider(&model);
+                            if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+                                cache.put(key, &message);
+                            }
+                            return Ok((message, usage));
+                        }
+                        Err(e) if emitted => return Err(e),
+                        Err(e) => last_error = Some(e),
+                    }
                 }
+
+                Err(last_error
+                    .unwrap_or_else(|| HeliosError::LLMError("No LLM provider available".into())))
             }
             #[cfg(feature = "local")]
             LLMProviderType::Local(_) => {
                 if let Some(provider) = self.provider.as_any().downcast_ref::<LocalLLMProvider>() {
-                    provider
-                        .chat_stream_local(messages, temperature, max_tokens, stop, on_chunk)
-                        .await
+                    let prompt = messages.clone();
+                    self.breaker.check()?;
+                    let result = provider
+                        .chat_stream_local(
+                            messages,
+                            temperature,
+                            max_tokens,
+                            stop,
+                            grammar,
+                            |text| on_event(StreamEvent::Content(text.to_string())),
+                        )
+                        .await;
+                    let message = self.breaker.record(result)?;
+                    let usage = Usage::estimate(&prompt, &message.content);
+                    self.usage.record("local-model", &usage);
+                    self.set_last_provider("local-model");
+                    Ok((message, usage))
                 } else {
                     Err(HeliosError::AgentError("Provider type mismatch".into()))
                 }
@@ -1099,6 +3018,7 @@ impl LLMClient {
                     messages,
                     temperature: temperature.or(Some(default_temperature)),
                     max_tokens: max_tokens.or(Some(default_max_tokens)),
+                    max_completion_tokens: None,
                     tools: tools.clone(),
                     tool_choice: if tools.is_some() {
                         Some("auto".to_string())
@@ -1107,21 +3027,794 @@ impl LLMClient {
                     },
                     stream: None,
                     stop,
+                    grammar: None,
+                    logprobs: None,
+                    top_logprobs: None,
                 };
 
-                let response = self.provider.generate(request).await?;
+                let response = self.generate(request).await?;
                 if let Some(choice) = response.choices.first() {
-                    on_chunk(&choice.message.content);
+                    emit_whole_message(&choice.message, &mut on_event);
                 }
+                let usage = response.usage;
                 response
                     .choices
                     .into_iter()
                     .next()
-                    .map(|choice| choice.message)
+                    .map(|choice| (choice.message, usage))
                     .ok_or_else(|| HeliosError::LLMError("No response from LLM".to_string()))
             }
         }
     }
 }
 
-// Test module added
+/// Reports a response that arrived in one piece as stream events.
+fn emit_whole_message(message: &ChatMessage, on_event: &mut impl FnMut(StreamEvent)) {
+    if let Some(reasoning) = &message.reasoning {
+        on_event(StreamEvent::Reasoning(reasoning.clone()));
+    }
+    if !message.content.is_empty() {
+        on_event(StreamEvent::Content(message.content.clone()));
+    }
+    for (index, call) in message.tool_calls.iter().flatten().enumerate() {
+        on_event(StreamEvent::ToolCallDelta {
+            index,
+            id: Some(call.id.clone()),
+            name: Some(call.function.name.clone()),
+            arguments: call.function.arguments.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RetryConfig;
+    use crate::tools::Tool;
+    use axum::{http::StatusCode, routing::post, Json, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc as StdArc;
+
+    /// A mock completions server.
+    struct MockServer {
+        url: String,
+        calls: StdArc<AtomicUsize>,
+        last_request: StdArc<std::sync::Mutex<Option<serde_json::Value>>>,
+    }
+
+    /// Starts a mock completions server that fails `failures` times with `status`.
+    async fn mock_server(failures: usize, status: StatusCode) -> MockServer {
+        let calls = StdArc::new(AtomicUsize::new(0));
+        let last_request = StdArc::new(std::sync::Mutex::new(None));
+        let counter = calls.clone();
+        let recorder = last_request.clone();
+        let app = Router::new().route(
+            "/chat/completions",
+            post(move |Json(body): Json<serde_json::Value>| {
+                let counter = counter.clone();
+                let recorder = recorder.clone();
+                async move {
+                    *recorder.lock().unwrap() = Some(body);
+                    let call = counter.fetch_add(1, Ordering::SeqCst);
+                    if call < failures {
+                        return Err((status, [("retry-after", "0")], "busy"));
+                    }
+                    Ok(Json(serde_json::json!({
+                        "id": "chatcmpl-1",
+                        "object": "chat.completion",
+                        "created": 0,
+                        "model": "mock",
+                        "choices": [{
+                            "index": 0,
+                            "message": {"role": "assistant", "content": "ok"},
+                            "finish_reason": "stop"
+                        }]
+                    })))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        MockServer {
+            url: format!("http://{}", addr),
+            calls,
+            last_request,
+        }
+    }
+
+    fn config(base_url: String, max_retries: u32) -> LLMConfig {
+        LLMConfig {
+            model_name: "mock".to_string(),
+            base_url,
+            retry: RetryConfig {
+                max_retries,
+                initial_backoff_ms: 1,
+                ..RetryConfig::default()
+            },
+            ..LLMConfig::default()
+        }
+    }
+
+    /// Tests that rate-limited requests are retried until they succeed.
+    #[tokio::test]
+    async fn test_chat_retries_rate_limits() {
+        let MockServer { url, calls, .. } = mock_server(2, StatusCode::TOO_MANY_REQUESTS).await;
+        let client = LLMClient::new(LLMProviderType::Remote(config(url, 3)))
+            .await
+            .unwrap();
+
+        let response = client
+            .chat(vec![ChatMessage::user("hi")], None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(response.content, "ok");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    /// Tests that concurrent batches keep their order and report errors per item.
+    #[tokio::test]
+    async fn test_chat_batch_concurrent() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_response("first")
+            .with_response("second");
+        let client = LLMClient::from_provider(mock.clone(), LLMConfig::default());
+
+        let results = client
+            .chat_batch_with(
+                vec![
+                    vec![ChatMessage::user("a")],
+                    vec![ChatMessage::user("b")],
+                    vec![ChatMessage::user("c")],
+                ],
+                &BatchOptions::default().max_concurrency(1),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().content, "first");
+        assert_eq!(results[1].as_ref().unwrap().content, "second");
+        assert!(results[2].is_err());
+
+        let error = client
+            .chat_batch_with(vec![], &BatchOptions::default().mode(BatchMode::Provider))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("remote provider"));
+    }
+
+    /// Tests a provider batch from upload to reading the output and error files.
+    #[tokio::test]
+    async fn test_chat_batch_provider() {
+        use axum::extract::Path;
+        use axum::routing::get;
+
+        let polls = StdArc::new(AtomicUsize::new(0));
+        let upload = StdArc::new(std::sync::Mutex::new(String::new()));
+        let reply = |content: &str| {
+            serde_json::json!({
+                "id": "resp",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "mock",
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": content}, "finish_reason": "stop"}],
+                "usage": {"prompt_tokens": 3, "completion_tokens": 1, "total_tokens": 4}
+            })
+        };
+        let output = [
+            serde_json::json!({"custom_id": "request-2", "response": {"status_code": 200, "body": reply("third")}}),
+            serde_json::json!({"custom_id": "request-0", "response": {"status_code": 200, "body": reply("first")}}),
+        ]
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect::<String>();
+        let errors = format!(
+            "{}\n",
+            serde_json::json!({"custom_id": "request-1", "response": {"status_code": 400, "body": {"error": {"message": "bad prompt"}}}})
+        );
+        let app = Router::new()
+            .route(
+                "/files",
+                post({
+                    let upload = StdArc::clone(&upload);
+                    move |body: String| async move {
+                        *upload.lock().unwrap() = body;
+                        Json(serde_json::json!({"id": "file-in"}))
+                    }
+                }),
+            )
+            .route(
+                "/batches",
+                post(|Json(body): Json<serde_json::Value>| async move {
+                    assert_eq!(body["input_file_id"], "file-in");
+                    assert_eq!(body["endpoint"], "/v1/chat/completions");
+                    Json(serde_json::json!({"id": "batch_1", "status": "validating"}))
+                }),
+            )
+            .route(
+                "/batches/{id}",
+                get({
+                    let polls = StdArc::clone(&polls);
+                    move || async move {
+                        let status = if polls.fetch_add(1, Ordering::SeqCst) == 0 {
+                            "in_progress"
+                        } else {
+                            "completed"
+                        };
+                        Json(serde_json::json!({
+                            "id": "batch_1",
+                            "status": status,
+                            "output_file_id": "file-out",
+                            "error_file_id": "file-err"
+                        }))
+                    }
+                }),
+            )
+            .route(
+                "/files/{id}/content",
+                get(move |Path(id): Path<String>| async move {
+                    if id == "file-out" {
+                        output
+                    } else {
+                        errors
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = LLMClient::new(LLMProviderType::Remote(config(
+            format!("http://{}", addr),
+            0,
+        )))
+        .await
+        .unwrap();
+
+        let options = BatchOptions::default()
+            .mode(BatchMode::Provider)
+            .poll_interval(std::time::Duration::from_millis(1));
+        let results = client
+            .chat_batch_with(
+                vec![
+                    vec![ChatMessage::user("a")],
+                    vec![ChatMessage::user("b")],
+                    vec![ChatMessage::user("c")],
+                ],
+                &options,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(results[0].as_ref().unwrap().content, "first");
+        assert!(results[1]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("bad prompt"));
+        assert_eq!(results[2].as_ref().unwrap().content, "third");
+        assert_eq!(polls.load(Ordering::SeqCst), 2);
+        let upload = upload.lock().unwrap().clone();
+        assert!(upload.contains(r#""custom_id":"request-1""#));
+        assert!(upload.contains(r#""url":"/v1/chat/completions""#));
+        assert_eq!(client.usage().requests, 2);
+    }
+
+    /// Tests that log probabilities are requested and per-choice metadata is kept.
+    #[tokio::test]
+    async fn test_chat_with_logprobs() {
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let app = Router::new().route(
+            "/chat/completions",
+            post({
+                let seen = std::sync::Arc::clone(&seen);
+                move |axum::Json(body): axum::Json<serde_json::Value>| {
+                    *seen.lock().unwrap() = Some(body);
+                    async move {
+                        axum::Json(serde_json::json!({
+                            "id": "resp",
+                            "object": "chat.completion",
+                            "created": 0,
+                            "model": "mock",
+                            "choices": [{
+                                "index": 0,
+                                "message": {"role": "assistant", "content": "Frank Herbert"},
+                                "finish_reason": "stop",
+                                "logprobs": {"content": [
+                                    {"token": "Frank", "logprob": -0.1, "bytes": [70, 114, 97, 110, 107],
+                                     "top_logprobs": [{"token": "Frank", "logprob": -0.1}, {"token": "Brian", "logprob": -2.5}]},
+                                    {"token": " Herbert", "logprob": -0.3, "top_logprobs": []}
+                                ]},
+                                "stop_reason": 128009
+                            }],
+                            "usage": {"prompt_tokens": 5, "completion_tokens": 2, "total_tokens": 7}
+                        }))
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = LLMClient::new(LLMProviderType::Remote(config(
+            format!("http://{}", addr),
+            0,
+        )))
+        .await
+        .unwrap();
+
+        let response = client
+            .chat_with_logprobs(vec![ChatMessage::user("Who wrote Dune?")], 2)
+            .await
+            .unwrap();
+        let body = seen.lock().unwrap().take().unwrap();
+        assert_eq!(body["logprobs"], true);
+        assert_eq!(body["top_logprobs"], 2);
+
+        let choice = &response.choices[0];
+        assert_eq!(choice.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(choice.metadata["stop_reason"], 128009);
+        let logprobs = choice.logprobs.as_ref().unwrap();
+        assert_eq!(logprobs.content.len(), 2);
+        assert_eq!(logprobs.content[0].top_logprobs[1].token, "Brian");
+        assert_eq!(logprobs.least_likely().unwrap().token, " Herbert");
+        assert!((logprobs.mean_logprob().unwrap() + 0.2).abs() < 1e-9);
+        assert!((logprobs.perplexity().unwrap() - 0.2f64.exp()).abs() < 1e-9);
+
+        let without: Choice = serde_json::from_value(serde_json::json!({
+            "index": 0,
+            "message": {"role": "assistant", "content": "hi"},
+            "finish_reason": null,
+            "logprobs": null
+        }))
+        .unwrap();
+        assert!(without.logprobs.is_none() && without.metadata.is_empty());
+        assert!(Logprobs::default().perplexity().is_none());
+    }
+
+    /// Tests that retrying stops once the retry budget is exhausted.
+    #[tokio::test]
+    async fn test_chat_gives_up_after_max_retries() {
+        let MockServer { url, calls, .. } =
+            mock_server(usize::MAX, StatusCode::SERVICE_UNAVAILABLE).await;
+        let client = LLMClient::new(LLMProviderType::Remote(config(url, 1)))
+            .await
+            .unwrap();
+
+        let result = client
+            .chat(vec![ChatMessage::user("hi")], None, None, None, None)
+            .await;
+        assert!(matches!(result, Err(HeliosError::LLMError(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    /// Tests that client errors other than rate limits are not retried.
+    #[tokio::test]
+    async fn test_chat_does_not_retry_client_errors() {
+        let MockServer { url, calls, .. } = mock_server(usize::MAX, StatusCode::BAD_REQUEST).await;
+        let client = LLMClient::new(LLMProviderType::Remote(config(url, 3)))
+            .await
+            .unwrap();
+
+        assert!(client
+            .chat(vec![ChatMessage::user("hi")], None, None, None, None)
+            .await
+            .is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    /// Tests that identical deterministic requests are answered from the cache.
+    #[tokio::test]
+    async fn test_chat_uses_response_cache() {
+        let MockServer { url, calls, .. } = mock_server(0, StatusCode::OK).await;
+        let llm_config = LLMConfig {
+            temperature: 0.0,
+            cache: crate::response_cache::ResponseCacheConfig::in_memory(),
+            ..config(url, 0)
+        };
+        let client = LLMClient::new(LLMProviderType::Remote(llm_config))
+            .await
+            .unwrap();
+
+        for _ in 0..2 {
+            let (message, _) = client
+                .chat_with_usage(vec![ChatMessage::user("hi")], None, None, None, None)
+                .await
+                .unwrap();
+            assert_eq!(message.content, "ok");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(client.usage().requests, 1);
+
+        // A sampled request bypasses the cache.
+        client
+            .chat(vec![ChatMessage::user("hi")], None, Some(0.7), None, None)
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    /// Tests that a failing primary provider falls back to the next provider in the chain.
+    #[tokio::test]
+    async fn test_chat_falls_back_to_next_provider() {
+        let primary = mock_server(usize::MAX, StatusCode::INTERNAL_SERVER_ERROR).await;
+        let fallback = mock_server(0, StatusCode::OK).await;
+
+        let mut llm_config = config(primary.url.clone(), 0);
+        llm_config.fallbacks = vec![LLMConfig {
+            model_name: "fallback".to_string(),
+            supports_tools: false,
+            ..config(fallback.url.clone(), 0)
+        }];
+        let client = LLMClient::new(LLMProviderType::Remote(llm_config))
+            .await
+            .unwrap();
+
+        let mut assistant = ChatMessage::assistant("");
+        assistant.tool_calls = Some(vec![crate::chat::ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: crate::chat::FunctionCall {
+                name: "calculator".to_string(),
+                arguments: "{}".to_string(),
+            },
+        }]);
+        let messages = vec![
+            ChatMessage::user("hi"),
+            assistant,
+            ChatMessage::tool("2", "call_1"),
+        ];
+        let tools = vec![crate::tools::CalculatorTool.to_definition()];
+
+        let response = client
+            .chat(messages, Some(tools), None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(response.content, "ok");
+        assert_eq!(primary.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(client.last_provider().as_deref(), Some("fallback"));
+        assert_eq!(client.usage_tracker().by_model()["fallback"].requests, 1);
+
+        // The fallback does not support tools, so tool traffic is sent as plain text.
+        let body = fallback.last_request.lock().unwrap().clone().unwrap();
+        assert_eq!(body["model"], "fallback");
+        assert!(body.get("tools").is_none());
+        assert!(body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|m| m["role"] != "tool" && m.get("tool_calls").is_none()));
+    }
+
+    /// Tests that stop sequences and patterns are found across chunk boundaries.
+    #[test]
+    fn test_stop_matcher() {
+        let mut matcher = StopMatcher::new(&["END".to_string()], &[])
+            .unwrap()
+            .unwrap();
+        let mut streamed = String::new();
+        for chunk in ["Hello E", "N", "D world"] {
+            streamed.push_str(matcher.push(chunk));
+        }
+        assert!(matcher.stopped());
+        assert_eq!(streamed, "Hello ");
+        assert_eq!(matcher.text(), "Hello ");
+
+        let mut matcher = StopMatcher::new(&["###".to_string()], &[])
+            .unwrap()
+            .unwrap();
+        let mut streamed = matcher.push("ab#").to_string();
+        streamed.push_str(matcher.finish());
+        assert!(!matcher.stopped());
+        assert_eq!(streamed, "ab#");
+
+        let mut content = "Answer: 42\nObservation: done".to_string();
+        StopMatcher::new(&[], &[r"(?m)^Observation:".to_string()])
+            .unwrap()
+            .unwrap()
+            .truncate(&mut content);
+        assert_eq!(content, "Answer: 42\n");
+
+        assert!(StopMatcher::new(&[], &[]).unwrap().is_none());
+        assert!(StopMatcher::new(&[], &["(".to_string()]).is_err());
+    }
+
+    /// Tests that a streamed response is cut and cancelled at a client-side stop sequence.
+    #[tokio::test]
+    async fn test_chat_stream_client_side_stop() {
+        let chunks = ["Step 1", " done.\nUs", "er: next", " question"];
+        let body: String = chunks
+            .iter()
+            .map(|c| {
+                format!(
+                    "data: {}\n\n",
+                    serde_json::json!({
+                        "id": "chunk",
+                        "object": "chat.completion.chunk",
+                        "created": 0,
+                        "model": "mock",
+                        "choices": [{"index": 0, "delta": {"content": c}}]
+                    })
+                )
+            })
+            .collect::<String>()
+            + "data: [DONE]\n\n";
+        let app = Router::new().route(
+            "/chat/completions",
+            post(move || {
+                let body = body.clone();
+                async move { ([("content-type", "text/event-stream")], body) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let llm_config = LLMConfig {
+            stop_sequences: vec!["\nUser:".to_string()],
+            ..config(format!("http://{}", addr), 0)
+        };
+        let client = LLMClient::new(LLMProviderType::Remote(llm_config))
+            .await
+            .unwrap();
+
+        let mut streamed = String::new();
+        let response = client
+            .chat_stream(vec![ChatMessage::user("hi")], None, None, None, None, |c| {
+                streamed.push_str(c)
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.content, "Step 1 done.");
+        assert_eq!(streamed, "Step 1 done.");
+    }
+
+    /// Tests that streamed tool-call fragments are reported and assembled.
+    #[tokio::test]
+    async fn test_chat_stream_events_assembles_tool_calls() {
+        let deltas = [
+            serde_json::json!({"content": "Checking."}),
+            serde_json::json!({"tool_calls": [{"index": 0, "id": "call_1", "function": {"name": "calculator", "arguments": ""}}]}),
+            serde_json::json!({"tool_calls": [{"index": 0, "function": {"arguments": "{\"expression\":"}}]}),
+            serde_json::json!({"tool_calls": [{"index": 0, "function": {"arguments": " \"2+2\"}"}}]}),
+            serde_json::json!({"tool_calls": [{"index": 1, "id": "call_2", "function": {"name": "timestamp", "arguments": "{}"}}]}),
+        ];
+        let body: String = deltas
+            .iter()
+            .map(|delta| {
+                format!(
+                    "data: {}\n\n",
+                    serde_json::json!({
+                        "id": "chunk",
+                        "object": "chat.completion.chunk",
+                        "created": 0,
+                        "model": "mock",
+                        "choices": [{"index": 0, "delta": delta}]
+                    })
+                )
+            })
+            .collect::<String>()
+            + "data: [DONE]\n\n";
+        let app = Router::new().route(
+            "/chat/completions",
+            post(move || {
+                let body = body.clone();
+                async move { ([("content-type", "text/event-stream")], body) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = LLMClient::new(LLMProviderType::Remote(config(
+            format!("http://{}", addr),
+            0,
+        )))
+        .await
+        .unwrap();
+
+        let mut events = Vec::new();
+        let response = client
+            .chat_stream_events(
+                vec![ChatMessage::user("hi")],
+                Some(vec![crate::tools::CalculatorTool.to_definition()]),
+                None,
+                None,
+                None,
+                |event| events.push(event),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[0], StreamEvent::Content("Checking.".to_string()));
+        assert_eq!(
+            events[2],
+            StreamEvent::ToolCallDelta {
+                index: 0,
+                id: None,
+                name: None,
+                arguments: "{\"expression\":".to_string(),
+            }
+        );
+
+        let calls = response.tool_calls.unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].function.name, "calculator");
+        assert_eq!(calls[0].function.arguments, "{\"expression\": \"2+2\"}");
+        assert_eq!(calls[1].function.name, "timestamp");
+    }
+
+    /// Tests that native and tagged reasoning stream apart from the answer.
+    #[tokio::test]
+    async fn test_chat_stream_events_separates_reasoning() {
+        let deltas = [
+            serde_json::json!({"role": "assistant", "reasoning_content": "Native."}),
+            serde_json::json!({"content": "<thi"}),
+            serde_json::json!({"content": "nk>Tagged.</think>\n\nIt is"}),
+            serde_json::json!({"content": " 4."}),
+        ];
+        let body: String = deltas
+            .iter()
+            .map(|delta| {
+                format!(
+                    "data: {}\n\n",
+                    serde_json::json!({
+                        "id": "chunk",
+                        "object": "chat.completion.chunk",
+                        "created": 0,
+                        "model": "mock",
+                        "choices": [{"index": 0, "delta": delta}]
+                    })
+                )
+            })
+            .collect::<String>()
+            + "data: [DONE]\n\n";
+        let app = Router::new().route(
+            "/chat/completions",
+            post(move || {
+                let body = body.clone();
+                async move { ([("content-type", "text/event-stream")], body) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = LLMClient::new(LLMProviderType::Remote(config(
+            format!("http://{}", addr),
+            0,
+        )))
+        .await
+        .unwrap();
+
+        let mut events = Vec::new();
+        let response = client
+            .chat_stream_events(
+                vec![ChatMessage::user("What is 2 + 2?")],
+                None,
+                None,
+                None,
+                None,
+                |event| events.push(event),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Reasoning("Native.".to_string()),
+                StreamEvent::Reasoning("Tagged.".to_string()),
+                StreamEvent::Content("It is".to_string()),
+                StreamEvent::Content(" 4.".to_string()),
+            ]
+        );
+        assert_eq!(response.content, "It is 4.");
+        assert_eq!(response.reasoning.as_deref(), Some("Native.\nTagged."));
+    }
+
+    /// Tests splitting tagged reasoning from whole responses.
+    #[tokio::test]
+    async fn test_split_reasoning() {
+        assert_eq!(
+            split_reasoning("No tags <b>here</b>"),
+            (None, "No tags <b>here</b>".to_string())
+        );
+        assert_eq!(
+            split_reasoning("<thinking>Café?</thinking>\nOui."),
+            (Some("Café?".to_string()), "Oui.".to_string())
+        );
+        assert_eq!(
+            split_reasoning("Ends with <thi"),
+            (None, "Ends with <thi".to_string())
+        );
+
+        let mock =
+            crate::mock::MockLLMProvider::new().with_response("<think>Greet back.</think>Hello!");
+        let client = LLMClient::from_provider(mock, LLMConfig::default());
+        let message = client
+            .chat(vec![ChatMessage::user("Hi")], None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(message.content, "Hello!");
+        assert_eq!(message.reasoning.as_deref(), Some("Greet back."));
+    }
+
+    /// Tests parsing of the `Retry-After` header forms.
+    #[test]
+    fn test_retry_after_parsing() {
+        let max = std::time::Duration::from_secs(30);
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert!(retry_after(&headers, max).is_none());
+
+        headers.insert("retry-after", "2".parse().unwrap());
+        assert_eq!(
+            retry_after(&headers, max),
+            Some(std::time::Duration::from_secs(2))
+        );
+
+        for huge in ["1e30", "3600"] {
+            headers.insert("retry-after", huge.parse().unwrap());
+            assert_eq!(retry_after(&headers, max), Some(max));
+        }
+        for invalid in ["inf", "NaN", "-inf"] {
+            headers.insert("retry-after", invalid.parse().unwrap());
+            assert!(retry_after(&headers, max).is_none());
+        }
+
+        // HTTP dates are measured from the engine's clock
+        let now = chrono::DateTime::parse_from_rfc2822("Tue, 1 Sep 2026 12:00:00 +0000")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let clock = std::sync::Arc::new(crate::clock::FixedClock::new(now));
+        let ids = std::sync::Arc::new(crate::clock::SequentialIds::new());
+        headers.insert(
+            "retry-after",
+            "Tue, 01 Sep 2026 12:00:10 GMT".parse().unwrap(),
+        );
+        let delay = crate::clock::sync_scope(clock, ids, || retry_after(&headers, max));
+        assert_eq!(delay, Some(std::time::Duration::from_secs(10)));
+
+        headers.insert("retry-after-ms", "150".parse().unwrap());
+        assert_eq!(
+            retry_after(&headers, max),
+            Some(std::time::Duration::from_millis(150))
+        );
+
+        let sample = jitter_sample();
+        assert!((0.0..1.0).contains(&sample));
+    }
+
+    /// Tests that the audio helpers call the provider's audio endpoints.
+    #[tokio::test]
+    async fn test_transcribe_and_speak() {
+        let app = Router::new()
+            .route(
+                "/audio/transcriptions",
+                post(|| async { Json(serde_json::json!({"text": " hello there \n"})) }),
+            )
+            .route(
+                "/audio/speech",
+                post(|Json(body): Json<serde_json::Value>| async move {
+                    assert_eq!(body["voice"], "alloy");
+                    body["input"].as_str().unwrap().as_bytes().to_vec()
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = LLMClient::new(LLMProviderType::Remote(config(url, 0)))
+            .await
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let recording = dir.path().join("in.wav");
+        std::fs::write(&recording, b"RIFF").unwrap();
+        assert_eq!(client.transcribe(&recording).await.unwrap(), "hello there");
+
+        let speech = client.speak("hi").await.unwrap();
+        assert_eq!(speech.data, b"hi");
+        assert_eq!(speech.format, "mp3");
+    }
+}
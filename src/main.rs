@@ -8,13 +8,19 @@
 #![allow(unused_variables)]
 
 use clap::{Parser, Subcommand};
-use helios_engine::{ChatMessage, Config, LLMClient};
+use helios_engine::llm::StreamEvent;
+use helios_engine::{AbortHandle, ChatMessage, Config, HeliosError, LLMClient, VoiceConfig};
 use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
-/// A helper struct to track and display thinking tags in streamed responses.
+#[cfg(feature = "tui")]
+mod tui;
+
+/// Shows the model's reasoning as a line of dots while a reply streams.
 struct ThinkingTracker {
     in_thinking: bool,
-    thinking_buffer: String,
+    reasoning_chars: usize,
 }
 
 impl ThinkingTracker {
@@ -22,122 +28,37 @@ impl ThinkingTracker {
     fn new() -> Self {
         Self {
             in_thinking: false,
-            thinking_buffer: String::new(),
+            reasoning_chars: 0,
         }
     }
 
-    /// Processes a chunk of a streamed response and returns the processed output.
-    fn process_chunk(&mut self, chunk: &str) -> Option<String> {
+    /// Processes a piece of a streamed reply and returns what to print for it.
+    fn process_event(&mut self, event: &StreamEvent) -> Option<String> {
         let mut output = String::new();
-        let mut chars = chunk.chars().peekable();
-
-        while let Some(c) = chars.next() {
-            if c == '<' {
-                // Check if this is start of a thinking tag
-                let remaining: String = chars.clone().collect();
-                if remaining.starts_with("thinking>") {
-                    self.in_thinking = true;
-                    self.thinking_buffer.clear();
-                    output.push_str("\n💭 [Thinking");
-                    // Skip "thinking>"
-                    for _ in 0..9 {
-                        chars.next();
-                    }
-                    continue;
-                } else if remaining.starts_with("/thinking>") {
-                    self.in_thinking = false;
-                    output.push_str("]\n");
-                    // Skip "/thinking>"
-                    for _ in 0..10 {
-                        chars.next();
-                    }
-                    continue;
-                } else if remaining.starts_with("think>") {
+        match event {
+            StreamEvent::Reasoning(text) => {
+                if !self.in_thinking {
                     self.in_thinking = true;
-                    self.thinking_buffer.clear();
                     output.push_str("\n💭 [Thinking");
-                    // Skip "think>"
-                    for _ in 0..6 {
-                        chars.next();
-                    }
-                    continue;
-                } else if remaining.starts_with("/think>") {
-                    self.in_thinking = false;
-                    output.push_str("]\n");
-                    // Skip "/think>"
-                    for _ in 0..7 {
-                        chars.next();
-                    }
-                    continue;
                 }
-            }
-
-            if self.in_thinking {
-                self.thinking_buffer.push(c);
-                if self.thinking_buffer.len() % 3 == 0 {
-                    output.push('.');
+                for _ in text.chars() {
+                    self.reasoning_chars += 1;
+                    if self.reasoning_chars % 3 == 0 {
+                        output.push('.');
+                    }
                 }
-            } else {
-                output.push(c);
             }
-        }
-
-        if !output.is_empty() {
-            Some(output)
-        } else {
-            None
-        }
-    }
-}
-
-/// Processes thinking tags in the content of a non-streaming response.
-#[allow(dead_code)]
-fn process_thinking_tags_in_content(content: &str) -> String {
-    let mut result = String::new();
-    let mut in_thinking = false;
-    let mut chars = content.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        if c == '<' {
-            // Check if this is a thinking tag
-            let remaining: String = chars.clone().collect();
-            if remaining.starts_with("thinking>") {
-                in_thinking = true;
-                // Skip "thinking>"
-                for _ in 0..9 {
-                    chars.next();
-                }
-                continue;
-            } else if remaining.starts_with("/thinking>") {
-                in_thinking = false;
-                // Skip "/thinking>"
-                for _ in 0..10 {
-                    chars.next();
-                }
-                continue;
-            } else if remaining.starts_with("think>") {
-                in_thinking = true;
-                // Skip "think>"
-                for _ in 0..6 {
-                    chars.next();
-                }
-                continue;
-            } else if remaining.starts_with("/think>") {
-                in_thinking = false;
-                // Skip "/think>"
-                for _ in 0..7 {
-                    chars.next();
+            StreamEvent::Content(text) => {
+                if self.in_thinking {
+                    self.in_thinking = false;
+                    output.push_str("]\n");
                 }
-                continue;
+                output.push_str(text);
             }
+            _ => {}
         }
-
-        if !in_thinking {
-            result.push(c);
-        }
+        (!output.is_empty()).then_some(output)
     }
-
-    result
 }
 
 /// The command-line interface for the Helios Engine.
@@ -170,9 +91,37 @@ enum Commands {
         #[arg(short, long)]
         system_prompt: Option<String>,
 
-        /// The maximum number of iterations for tool calls.
-        #[arg(short, long, default_value = "5")]
-        max_iterations: usize,
+        /// The maximum number of tool-calling rounds per message in agent mode
+        /// [default: `max_iterations` under `[agent]`, or 5].
+        #[arg(short, long)]
+        max_iterations: Option<usize>,
+
+        /// Chat with an agent that can use the built-in tools listed under `[agent]`
+        /// in the config file.
+        #[arg(long, conflicts_with = "voice")]
+        agent: bool,
+
+        /// Talk instead of typing, using the `[voice]` section of the config file.
+        #[arg(long)]
+        voice: bool,
+
+        /// Chat in a full-screen terminal interface.
+        #[cfg(feature = "tui")]
+        #[arg(long)]
+        tui: bool,
+
+        /// A TOML price table used to show the session cost in the terminal interface.
+        #[cfg(feature = "tui")]
+        #[arg(long)]
+        prices: Option<String>,
+
+        /// Continue a session saved with `/save`.
+        #[arg(long, conflicts_with = "voice")]
+        resume: Option<String>,
+
+        /// The directory saved sessions are kept in [default: ~/.helios/sessions].
+        #[arg(long)]
+        sessions_dir: Option<String>,
     },
 
     /// Initialize a new configuration file.
@@ -201,23 +150,256 @@ enum Commands {
         /// Path to custom endpoints configuration file (TOML format).
         #[arg(long)]
         custom_endpoints: Option<String>,
+
+        /// Serve an agent defined by a bundle file (TOML, or JSON with a `.json`
+        /// extension) instead of the bare model.
+        #[arg(long)]
+        agent_config: Option<String>,
+
+        /// Built-in tools or toolsets to give the served agent, comma separated.
+        #[arg(long, value_delimiter = ',')]
+        tools: Vec<String>,
+
+        /// The system prompt of the served agent.
+        #[arg(long)]
+        system_prompt: Option<String>,
+
+        /// The model name advertised by the API; defaults to the agent's or model's name.
+        #[arg(long)]
+        model_name: Option<String>,
+
+        /// A key clients must send as `Authorization: Bearer <key>`; repeat for several.
+        #[arg(long = "api-key", value_name = "KEY")]
+        api_keys: Vec<String>,
+
+        /// A file of accepted API keys, one per line.
+        #[arg(long)]
+        api_keys_file: Option<String>,
+
+        /// Save `/v1/sessions` conversations in this directory, so they survive
+        /// eviction and restarts.
+        #[arg(long)]
+        sessions_dir: Option<String>,
+
+        /// Evict `/v1/sessions` conversations unused for this many seconds.
+        #[arg(long, default_value = "1800")]
+        session_idle_secs: u64,
+    },
+
+    /// Manage the RAG document index.
+    Rag {
+        #[command(subcommand)]
+        command: RagCommands,
+    },
+
+    /// Run declarative pipelines.
+    Pipeline {
+        #[command(subcommand)]
+        command: PipelineCommands,
+    },
+
+    /// List the built-in tools or run one directly, without an LLM.
+    Tools {
+        #[command(subcommand)]
+        command: ToolsCommands,
     },
 }
 
+/// The subcommands of `helios-engine pipeline`.
+#[derive(Subcommand)]
+enum PipelineCommands {
+    /// Run a pipeline file and print its output.
+    Run {
+        /// The pipeline file (YAML, or JSON with a `.json` extension).
+        file: String,
+
+        /// A pipeline input as `name=value`; repeat for several inputs.
+        #[arg(short, long = "input", value_name = "NAME=VALUE")]
+        inputs: Vec<String>,
+
+        /// The Qdrant server searched by `rag_query` steps.
+        #[arg(long)]
+        qdrant_url: Option<String>,
+
+        /// The Qdrant collection searched by `rag_query` steps.
+        #[arg(long, default_value = "helios_documents")]
+        collection: String,
+
+        /// The SQLite database searched by `rag_query` steps.
+        #[cfg(feature = "sqlite")]
+        #[arg(long)]
+        sqlite: Option<String>,
+
+        /// The embedding model, served from the configured base URL.
+        #[arg(long, default_value = "text-embedding-ada-002")]
+        embedding_model: String,
+
+        /// Print every step result as JSON after the output.
+        #[arg(long)]
+        show_steps: bool,
+    },
+}
+
+/// The subcommands of `helios-engine tools`.
+#[derive(Subcommand)]
+enum ToolsCommands {
+    /// Print the name, description and parameters of every built-in tool.
+    List {
+        /// Print the tool definitions sent to the model, as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Run a built-in tool with the given arguments and print its output.
+    Run {
+        /// The name of the tool.
+        name: String,
+
+        /// The tool arguments as a JSON object.
+        #[arg(long, default_value = "{}")]
+        args: String,
+    },
+}
+
+/// The subcommands of `helios-engine rag`.
+#[derive(Subcommand)]
+enum RagCommands {
+    /// Load text, Markdown, HTML, PDF or DOCX files into a vector store.
+    Ingest {
+        /// The file or directory to ingest.
+        path: String,
+
+        #[command(flatten)]
+        store: RagArgs,
+
+        /// The chunk size in characters; 0 stores whole files [default: 1000].
+        #[arg(long)]
+        chunk_size: Option<usize>,
+
+        /// The overlap between chunks in characters [default: 100].
+        #[arg(long)]
+        chunk_overlap: Option<usize>,
+    },
+
+    /// Search the vector store and print the best matches.
+    Search {
+        /// The text to search for.
+        query: String,
+
+        /// The number of results to show.
+        #[arg(short = 'k', long, default_value = "5")]
+        limit: usize,
+
+        #[command(flatten)]
+        store: RagArgs,
+    },
+
+    /// List the indexed files and the number of stored entries.
+    List {
+        #[command(flatten)]
+        store: RagArgs,
+    },
+
+    /// Delete every document from the vector store and the index manifest.
+    Clear {
+        /// Do not ask for confirmation.
+        #[arg(short, long)]
+        yes: bool,
+
+        #[command(flatten)]
+        store: RagArgs,
+    },
+}
+
+/// Where the `rag` subcommands keep documents and how they embed them. Options
+/// left out are taken from the `[rag]` section of the config file.
+#[derive(clap::Args)]
+struct RagArgs {
+    /// The Qdrant server to store documents in.
+    #[arg(long)]
+    qdrant_url: Option<String>,
+
+    /// The Qdrant collection to store documents in [default: helios_documents].
+    #[arg(long)]
+    collection: Option<String>,
+
+    /// The SQLite database file to store documents in.
+    #[cfg(feature = "sqlite")]
+    #[arg(long)]
+    sqlite: Option<String>,
+
+    /// The embedding model, served from the configured base URL
+    /// [default: text-embedding-ada-002].
+    #[arg(long)]
+    embedding_model: Option<String>,
+
+    /// The file recording what was indexed, so unchanged files are skipped next
+    /// time. Defaults to `<collection>.index.json` or `<sqlite file>.index.json`.
+    #[arg(long)]
+    manifest: Option<String>,
+}
+
+impl RagArgs {
+    /// Returns the `[rag]` settings of the config file overridden by these options.
+    fn resolve(&self, config_path: &str) -> helios_engine::Result<helios_engine::RagConfig> {
+        let mut rag = helios_engine::RagConfig::from_config_file(config_path)?.unwrap_or_default();
+        if let Some(url) = &self.qdrant_url {
+            rag.qdrant_url = Some(url.clone());
+            rag.sqlite_path = None;
+        }
+        #[cfg(feature = "sqlite")]
+        if let Some(path) = &self.sqlite {
+            rag.sqlite_path = Some(path.clone());
+            rag.qdrant_url = None;
+        }
+        if let Some(collection) = &self.collection {
+            rag.collection = collection.clone();
+        }
+        if let Some(model) = &self.embedding_model {
+            rag.embedding_model = model.clone();
+        }
+        if let Some(manifest) = &self.manifest {
+            rag.manifest = Some(manifest.clone());
+        }
+        Ok(rag)
+    }
+}
+
 /// The main entry point for the Helios Engine CLI.
 #[tokio::main]
 async fn main() -> helios_engine::Result<()> {
     let cli = Cli::parse();
 
     // Initialize tracing
-    if cli.verbose {
-        tracing_subscriber::fmt()
-            .with_max_level(tracing::Level::DEBUG)
-            .init();
+    let level = if cli.verbose {
+        tracing::Level::DEBUG
     } else {
-        tracing_subscriber::fmt()
-            .with_max_level(tracing::Level::INFO)
-            .init();
+        tracing::Level::INFO
+    };
+
+    // The terminal interface shows log lines in its activity pane instead of stdout
+    #[cfg(feature = "tui")]
+    let mut tui_channel =
+        matches!(cli.command, Some(Commands::Chat { tui: true, .. })).then(tui::channel);
+    #[cfg(feature = "tui")]
+    let log_to_stdout = match &tui_channel {
+        Some((writer, ..)) => {
+            let writer = writer.clone();
+            tracing_subscriber::fmt()
+                .with_max_level(level)
+                .with_ansi(false)
+                .without_time()
+                .with_target(false)
+                .with_writer(move || writer.clone())
+                .init();
+            false
+        }
+        None => true,
+    };
+    #[cfg(not(feature = "tui"))]
+    let log_to_stdout = true;
+    if log_to_stdout {
+        tracing_subscriber::fmt().with_max_level(level).init();
     }
 
     match &cli.command {
@@ -230,36 +412,408 @@ async fn main() -> helios_engine::Result<()> {
         Some(Commands::Chat {
             system_prompt,
             max_iterations,
+            agent,
+            voice,
+            #[cfg(feature = "tui")]
+            prices,
+            resume,
+            sessions_dir,
+            ..
         }) => {
-            let sys_prompt = system_prompt.as_ref().map(|s| s.as_str()).unwrap_or(
-                "You are a helpful AI assistant with access to various tools. Use them when needed to help the user."
-            );
-            interactive_chat(&cli.config, sys_prompt, *max_iterations, &cli.mode).await?;
+            let options = ChatOptions {
+                system_prompt: system_prompt
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_CHAT_PROMPT.to_string()),
+                max_iterations: *max_iterations,
+                agent: *agent,
+                sessions: session_store(sessions_dir.as_deref()),
+                resume: resume.clone(),
+            };
+            #[cfg(feature = "tui")]
+            if let Some((_, sender, events)) = tui_channel.take() {
+                return tui_chat(
+                    &cli.config,
+                    &cli.mode,
+                    prices.as_deref(),
+                    options,
+                    sender,
+                    events,
+                )
+                .await;
+            }
+            if *voice {
+                return voice_chat(&cli.config, &options.system_prompt, &cli.mode).await;
+            }
+            interactive_chat(&cli.config, &cli.mode, options).await?;
         }
         Some(Commands::Serve {
             port,
             host,
             custom_endpoints,
+            agent_config,
+            tools,
+            system_prompt,
+            model_name,
+            api_keys,
+            api_keys_file,
+            sessions_dir,
+            session_idle_secs,
         }) => {
-            serve_server(
+            let options = ServeOptions {
+                custom_endpoints: custom_endpoints.clone(),
+                agent_config: agent_config.clone(),
+                tools: tools.clone(),
+                system_prompt: system_prompt.clone(),
+                model_name: model_name.clone(),
+                api_keys: api_keys.clone(),
+                api_keys_file: api_keys_file.clone(),
+                sessions_dir: sessions_dir.clone(),
+                session_idle_secs: *session_idle_secs,
+            };
+            serve_server(&cli.config, host, *port, &cli.mode, options).await?;
+        }
+        Some(Commands::Rag { command }) => {
+            rag_command(&cli.config, command).await?;
+        }
+        Some(Commands::Pipeline {
+            command:
+                PipelineCommands::Run {
+                    file,
+                    inputs,
+                    qdrant_url,
+                    collection,
+                    #[cfg(feature = "sqlite")]
+                    sqlite,
+                    embedding_model,
+                    show_steps,
+                },
+        }) => {
+            #[cfg(feature = "sqlite")]
+            let sqlite_store = sqlite.clone().map(RagStore::Sqlite);
+            #[cfg(not(feature = "sqlite"))]
+            let sqlite_store = None;
+            let store = qdrant_url
+                .clone()
+                .map(|url| RagStore::Qdrant(url, collection.clone()))
+                .or(sqlite_store);
+            run_pipeline(
                 &cli.config,
-                host,
-                *port,
                 &cli.mode,
-                custom_endpoints.clone(),
+                file,
+                inputs,
+                store,
+                embedding_model,
+                *show_steps,
             )
             .await?;
         }
+        Some(Commands::Tools { command }) => {
+            tools_command(command).await?;
+        }
         None => {
             // Default to chat command
-            let sys_prompt = "You are a helpful AI assistant with access to various tools. Use them when needed to help the user.";
-            interactive_chat(&cli.config, sys_prompt, 5, &cli.mode).await?;
+            let options = ChatOptions {
+                system_prompt: DEFAULT_CHAT_PROMPT.to_string(),
+                max_iterations: None,
+                agent: false,
+                sessions: session_store(None),
+                resume: None,
+            };
+            interactive_chat(&cli.config, &cli.mode, options).await?;
         }
     }
 
     Ok(())
 }
 
+/// Where `rag` subcommands keep their documents.
+enum RagStore {
+    /// A Qdrant server URL and collection name.
+    Qdrant(String, String),
+    /// A SQLite database file.
+    #[cfg(feature = "sqlite")]
+    Sqlite(String),
+}
+
+impl RagStore {
+    /// The store named by the `[rag]` settings.
+    fn from_config(rag: &helios_engine::RagConfig) -> helios_engine::Result<Self> {
+        if let Some(url) = &rag.qdrant_url {
+            return Ok(RagStore::Qdrant(url.clone(), rag.collection.clone()));
+        }
+        #[cfg(feature = "sqlite")]
+        if let Some(path) = &rag.sqlite_path {
+            return Ok(RagStore::Sqlite(path.clone()));
+        }
+        Err(HeliosError::ConfigError(
+            "Choose where to store documents with --qdrant-url or --sqlite (requires the sqlite feature), or set qdrant_url or sqlite_path under [rag]"
+                .to_string(),
+        ))
+    }
+
+    /// The default index manifest file for this store.
+    fn default_manifest(&self) -> String {
+        match self {
+            RagStore::Qdrant(_, collection) => format!("{}.index.json", collection),
+            #[cfg(feature = "sqlite")]
+            RagStore::Sqlite(db) => format!("{}.index.json", db),
+        }
+    }
+}
+
+/// Opens a RAG system over `store`, embedding with the `[rag]` settings.
+fn open_rag(
+    config: &Config,
+    store: RagStore,
+    rag: &helios_engine::RagConfig,
+) -> helios_engine::Result<helios_engine::RAGSystem> {
+    use helios_engine::{QdrantVectorStore, RAGSystem};
+
+    let vector_store: Box<dyn helios_engine::VectorStore> = match store {
+        RagStore::Qdrant(url, collection) => Box::new(QdrantVectorStore::new(url, collection)),
+        #[cfg(feature = "sqlite")]
+        RagStore::Sqlite(db) => Box::new(helios_engine::SqliteVectorStore::open(db)?),
+    };
+    Ok(RAGSystem::new(
+        Box::new(rag.embeddings(&config.llm)),
+        vector_store,
+    ))
+}
+
+/// Runs a `helios-engine rag` subcommand.
+async fn rag_command(config_path: &str, command: &RagCommands) -> helios_engine::Result<()> {
+    use helios_engine::RecursiveChunker;
+
+    let config = load_config(config_path)?;
+    let args = match command {
+        RagCommands::Ingest { store, .. }
+        | RagCommands::Search { store, .. }
+        | RagCommands::List { store }
+        | RagCommands::Clear { store, .. } => store,
+    };
+    let mut settings = args.resolve(config_path)?;
+    if let RagCommands::Ingest {
+        chunk_size,
+        chunk_overlap,
+        ..
+    } = command
+    {
+        settings.chunk_size = chunk_size.unwrap_or(settings.chunk_size);
+        settings.chunk_overlap = chunk_overlap.unwrap_or(settings.chunk_overlap);
+    }
+    let store = RagStore::from_config(&settings)?;
+    let manifest = settings
+        .manifest
+        .clone()
+        .unwrap_or_else(|| store.default_manifest());
+    let mut rag = open_rag(&config, store, &settings)?.with_index_manifest(manifest)?;
+
+    match command {
+        RagCommands::Ingest { path, .. } => {
+            if settings.chunk_size > 0 {
+                rag = rag.with_chunker(
+                    RecursiveChunker::new(settings.chunk_size).with_overlap(settings.chunk_overlap),
+                );
+            }
+            println!("📚 Ingesting {}...", path);
+            let report = rag.ingest(path).await?;
+            println!(
+                "✓ {} added, {} updated, {} unchanged, {} removed; the index now holds {} entries",
+                report.added.len(),
+                report.updated.len(),
+                report.unchanged.len(),
+                report.removed.len(),
+                rag.count().await?
+            );
+        }
+        RagCommands::Search { query, limit, .. } => {
+            let results = rag.search(query, *limit).await?;
+            if results.is_empty() {
+                println!("No matches.");
+            }
+            for (rank, result) in results.iter().enumerate() {
+                let source = result
+                    .metadata
+                    .as_ref()
+                    .and_then(|metadata| metadata.get("source"))
+                    .and_then(|source| source.as_str())
+                    .unwrap_or(&result.id);
+                println!("{}. [{:.3}] {}", rank + 1, result.score, source);
+                let preview: String = result.text.chars().take(300).collect();
+                let ellipsis = if result.text.chars().count() > 300 {
+                    "…"
+                } else {
+                    ""
+                };
+                println!("   {}{}\n", preview.replace('\n', "\n   "), ellipsis);
+            }
+        }
+        RagCommands::List { .. } => {
+            let documents = rag.indexed_documents().await;
+            for document in &documents {
+                println!(
+                    "{}  ({} {})",
+                    document.key,
+                    document.chunks,
+                    if document.chunks == 1 {
+                        "entry"
+                    } else {
+                        "entries"
+                    }
+                );
+            }
+            println!(
+                "{} indexed document(s); the store holds {} entries",
+                documents.len(),
+                rag.count().await?
+            );
+        }
+        RagCommands::Clear { yes, .. } => {
+            if !*yes {
+                print!(
+                    "Delete all {} entries from the store? Type 'yes' to confirm: ",
+                    rag.count().await?
+                );
+                io::stdout().flush()?;
+                let mut answer = String::new();
+                io::stdin().read_line(&mut answer)?;
+                if answer.trim() != "yes" {
+                    println!("Nothing was deleted.");
+                    return Ok(());
+                }
+            }
+            rag.clear().await?;
+            println!("✓ The store and index manifest are empty");
+        }
+    }
+    Ok(())
+}
+
+/// Runs a `helios-engine tools` subcommand.
+async fn tools_command(command: &ToolsCommands) -> helios_engine::Result<()> {
+    use helios_engine::tools::{builtin_tool, BUILTIN_TOOLS};
+
+    match command {
+        ToolsCommands::List { json } => {
+            let tools: Vec<_> = BUILTIN_TOOLS
+                .iter()
+                .filter_map(|name| builtin_tool(name, None))
+                .collect();
+            if *json {
+                let definitions: Vec<_> = tools.iter().map(|tool| tool.to_definition()).collect();
+                println!("{}", serde_json::to_string_pretty(&definitions)?);
+                return Ok(());
+            }
+            for tool in &tools {
+                println!("{} ({:?})", tool.name(), tool.permission());
+                println!("  {}", tool.description());
+                let mut parameters: Vec<_> = tool.parameters().into_iter().collect();
+                parameters.sort_by(|a, b| a.0.cmp(&b.0));
+                for (name, parameter) in parameters {
+                    let required = if parameter.required.unwrap_or(false) {
+                        ", required"
+                    } else {
+                        ""
+                    };
+                    println!(
+                        "    {} ({}{}): {}",
+                        name, parameter.param_type, required, parameter.description
+                    );
+                }
+                println!();
+            }
+        }
+        ToolsCommands::Run { name, args } => {
+            let tool = builtin_tool(name, None).ok_or_else(|| {
+                HeliosError::ToolError(format!(
+                    "Unknown tool '{}'. Built-in tools: {}",
+                    name,
+                    BUILTIN_TOOLS.join(", ")
+                ))
+            })?;
+            let args: serde_json::Value = serde_json::from_str(args)
+                .map_err(|e| HeliosError::ToolError(format!("--args is not valid JSON: {}", e)))?;
+            if !args.is_object() {
+                return Err(HeliosError::ToolError(
+                    "--args must be a JSON object, e.g. '{\"expression\": \"2 + 2\"}'".to_string(),
+                ));
+            }
+            let result = tool.execute(args).await?;
+            if !result.success {
+                return Err(HeliosError::ToolError(format!(
+                    "{} failed: {}",
+                    name, result.output
+                )));
+            }
+            println!("{}", result.output);
+        }
+    }
+    Ok(())
+}
+
+/// Runs a pipeline file with the configured LLM and the built-in tools it names.
+async fn run_pipeline(
+    config_path: &str,
+    mode: &str,
+    file: &str,
+    inputs: &[String],
+    store: Option<RagStore>,
+    embedding_model: &str,
+    show_steps: bool,
+) -> helios_engine::Result<()> {
+    use helios_engine::Pipeline;
+    use std::collections::HashMap;
+
+    let inputs = inputs
+        .iter()
+        .map(|input| {
+            let (name, value) = input.split_once('=').ok_or_else(|| {
+                HeliosError::ConfigError(format!(
+                    "Pipeline inputs are written NAME=VALUE, got '{}'",
+                    input
+                ))
+            })?;
+            Ok((
+                name.trim().to_string(),
+                serde_json::Value::String(value.to_string()),
+            ))
+        })
+        .collect::<helios_engine::Result<HashMap<_, _>>>()?;
+
+    let mut config = load_config(config_path)?;
+    apply_mode_override(&mut config, mode);
+    let mut pipeline = Pipeline::from_file(file)?;
+    for name in pipeline.required_tools() {
+        let tool = helios_engine::builtin_tool(&name, None).ok_or_else(|| {
+            HeliosError::ConfigError(format!(
+                "Pipeline step uses '{}', which is not a built-in tool",
+                name
+            ))
+        })?;
+        pipeline = pipeline.with_tool(tool);
+    }
+    if let Some(store) = store {
+        let mut settings =
+            helios_engine::RagConfig::from_config_file(config_path)?.unwrap_or_default();
+        settings.embedding_model = embedding_model.to_string();
+        pipeline = pipeline.with_rag(open_rag(&config, store, &settings)?);
+    }
+    let client = LLMClient::new(select_provider(config)).await?;
+    pipeline = pipeline.with_llm(client);
+
+    println!("⚙ Running pipeline '{}'...", pipeline.spec().name);
+    let result = pipeline.run(inputs).await?;
+    println!("{}", result.output);
+    if show_steps {
+        let mut names: Vec<_> = result.variables.keys().collect();
+        names.sort();
+        for name in names {
+            println!("\n[{}]\n{}", name, result.variables[name]);
+        }
+    }
+    Ok(())
+}
+
 /// Initializes a new configuration file.
 fn init_config(output: &str) -> helios_engine::Result<()> {
     if std::path::Path::new(output).exists() {
@@ -297,36 +851,7 @@ async fn ask_once(config_path: &str, message: &str, mode: &str) -> helios_engine
     let mut config = load_config(config_path)?;
     apply_mode_override(&mut config, mode);
 
-    // Priority: Candle > Local > Remote (API)
-    #[cfg(feature = "candle")]
-    let provider_type = if let Some(candle_config) = config.candle {
-        helios_engine::llm::LLMProviderType::Candle(candle_config)
-    } else {
-        #[cfg(feature = "local")]
-        {
-            if let Some(local_config) = config.local {
-                helios_engine::llm::LLMProviderType::Local(local_config)
-            } else {
-                helios_engine::llm::LLMProviderType::Remote(config.llm)
-            }
-        }
-        #[cfg(not(feature = "local"))]
-        {
-            helios_engine::llm::LLMProviderType::Remote(config.llm)
-        }
-    };
-
-    #[cfg(all(feature = "local", not(feature = "candle")))]
-    let provider_type = if let Some(local_config) = config.local {
-        helios_engine::llm::LLMProviderType::Local(local_config)
-    } else {
-        helios_engine::llm::LLMProviderType::Remote(config.llm)
-    };
-
-    #[cfg(not(any(feature = "local", feature = "candle")))]
-    let provider_type = helios_engine::llm::LLMProviderType::Remote(config.llm);
-
-    let client = LLMClient::new(provider_type).await?;
+    let client = LLMClient::new(select_provider(config)).await?;
     let messages = vec![
         ChatMessage::system("You are a helpful AI assistant. Provide direct, concise answers without internal reasoning or thinking tags."),
         ChatMessage::user(message),
@@ -339,8 +864,8 @@ async fn ask_once(config_path: &str, message: &str, mode: &str) -> helios_engine
 
     // Use streaming for both local and remote models
     let response = client
-        .chat_stream(messages, None, None, None, None, |chunk| {
-            if let Some(output) = tracker.process_chunk(chunk) {
+        .chat_stream_events(messages, None, None, None, None, |event| {
+            if let Some(output) = tracker.process_event(&event) {
                 print!("{}", output);
                 io::stdout().flush().unwrap();
             }
@@ -355,9 +880,8 @@ async fn ask_once(config_path: &str, message: &str, mode: &str) -> helios_engine
 /// Starts an interactive chat session with the LLM.
 async fn interactive_chat(
     config_path: &str,
-    system_prompt: &str,
-    _max_iterations: usize,
     mode: &str,
+    options: ChatOptions,
 ) -> helios_engine::Result<()> {
     println!("🚀 Helios Engine - LLM Agent Framework");
     println!("========================================\n");
@@ -365,43 +889,72 @@ async fn interactive_chat(
     let mut config = load_config(config_path)?;
     apply_mode_override(&mut config, mode);
 
-    // Create LLM client for streaming
-    // Priority: Candle > Local > Remote (API)
-    #[cfg(feature = "candle")]
-    let provider_type = if let Some(candle_config) = config.candle {
-        helios_engine::llm::LLMProviderType::Candle(candle_config)
-    } else {
-        #[cfg(feature = "local")]
-        {
-            if let Some(local_config) = config.local {
-                helios_engine::llm::LLMProviderType::Local(local_config)
-            } else {
-                helios_engine::llm::LLMProviderType::Remote(config.llm)
-            }
-        }
-        #[cfg(not(feature = "local"))]
-        {
-            helios_engine::llm::LLMProviderType::Remote(config.llm)
-        }
-    };
-
-    #[cfg(all(feature = "local", not(feature = "candle")))]
-    let provider_type = if let Some(local_config) = config.local {
-        helios_engine::llm::LLMProviderType::Local(local_config)
+    let ChatOptions {
+        system_prompt,
+        sessions,
+        resume,
+        ..
+    } = &options;
+    let mut responder = if options.agent {
+        let mut agent = build_chat_agent(
+            config_path,
+            config,
+            &options,
+            helios_engine::ConsoleApprovalHandler,
+        )
+        .await?;
+        // On the terminal the agent can stop and ask instead of guessing.
+        agent.register_tool(Box::new(helios_engine::AskUserTool::console()));
+        println!(
+            "✓ Agent mode: {} tools available ('tools' lists them)",
+            agent.tool_registry().list_tools().len()
+        );
+        Responder::Agent(Box::new(agent))
     } else {
-        helios_engine::llm::LLMProviderType::Remote(config.llm)
+        Responder::Client(Box::new(LLMClient::new(select_provider(config)).await?))
     };
-
-    #[cfg(not(any(feature = "local", feature = "candle")))]
-    let provider_type = helios_engine::llm::LLMProviderType::Remote(config.llm);
-
-    let client = LLMClient::new(provider_type).await?;
     let mut session = helios_engine::ChatSession::new().with_system_prompt(system_prompt);
+    // The name the conversation is saved under; it is saved again after every reply.
+    let mut session_name = None;
+    if let Some(name) = resume {
+        session = sessions.load(name)?;
+        if session.system_prompt.is_none() {
+            session.system_prompt = Some(system_prompt.to_string());
+        }
+        session_name = Some(name.to_string());
+        println!(
+            "✓ Resumed session '{}' ({} messages)",
+            name,
+            session.messages.len()
+        );
+    }
 
     println!("✓ Streaming mode enabled");
     println!("✓ Thinking tags will be shown when available");
+    println!("✓ Press Ctrl+C to stop a response that is still generating");
     println!("\n💬 Chat with the AI (type 'exit' to quit, 'clear' to clear history, 'help' for commands):\n");
 
+    // Ctrl+C aborts the response in flight; at the prompt it exits as usual
+    let abort = match &responder {
+        Responder::Agent(agent) => agent.abort_handle(),
+        Responder::Client(_) => AbortHandle::new(),
+    };
+    let responding = Arc::new(AtomicBool::new(false));
+    {
+        let abort = abort.clone();
+        let responding = Arc::clone(&responding);
+        tokio::spawn(async move {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if responding.load(Ordering::SeqCst) {
+                    abort.abort();
+                } else {
+                    println!("\n👋 Goodbye!");
+                    std::process::exit(130);
+                }
+            }
+        });
+    }
+
     // Interactive chat loop
     loop {
         print!("You: ");
@@ -415,8 +968,19 @@ async fn interactive_chat(
             continue;
         }
 
+        if let Some(command) = input.strip_prefix('/') {
+            match session_command(command, sessions, &mut session, &mut session_name) {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => {
+                    eprintln!("❌ {}\n", e);
+                    continue;
+                }
+            }
+        }
+
         // Handle commands
-        match input.to_lowercase().as_str() {
+        match input.trim_start_matches('/').to_lowercase().as_str() {
             "exit" | "quit" => {
                 println!("\n👋 Goodbye!");
                 break;
@@ -443,33 +1007,457 @@ async fn interactive_chat(
                 println!("{}", session.get_summary());
                 continue;
             }
+            "tools" => {
+                match &responder {
+                    Responder::Agent(agent) => {
+                        let mut names = agent.tool_registry().list_tools();
+                        names.sort();
+                        println!("\n🔧 Tools: {}\n", names.join(", "));
+                    }
+                    Responder::Client(_) => {
+                        println!("No tools in plain chat; start with --agent to use them\n")
+                    }
+                }
+                continue;
+            }
             _ => {}
         }
 
-        // Add user message to session
-        session.add_user_message(input);
-
         // Stream response
         let mut tracker = ThinkingTracker::new();
         print!("\n🤖: ");
         io::stdout().flush()?;
 
-        match client
-            .chat_stream(session.get_messages(), None, None, None, None, |chunk| {
-                if let Some(output) = tracker.process_chunk(chunk) {
+        responding.store(true, Ordering::SeqCst);
+        let result = match &mut responder {
+            Responder::Agent(agent) => {
+                // The agent continues from the conversation shown here, which may
+                // have been cleared or swapped for a saved one since the last reply.
+                *agent.chat_session_mut() = session.clone();
+                let reply = agent
+                    .chat_stream(input, |event| print_stream_event(&mut tracker, event))
+                    .await;
+                reply.map(|_| agent.chat_session().clone())
+            }
+            Responder::Client(client) => {
+                let mut turn = session.clone();
+                turn.add_user_message(input);
+                abort
+                    .run(client.chat_stream_events(
+                        turn.get_messages(),
+                        None,
+                        None,
+                        None,
+                        None,
+                        |event| print_stream_event(&mut tracker, event),
+                    ))
+                    .await
+                    .and_then(|result| result)
+                    .map(|response| {
+                        turn.add_assistant_message(&response.content);
+                        turn
+                    })
+            }
+        };
+        responding.store(false, Ordering::SeqCst);
+
+        match result {
+            Ok(updated) => {
+                session = updated;
+                println!("\n");
+                if let Some(name) = &session_name {
+                    if let Err(e) = sessions.save(name, &session) {
+                        eprintln!("⚠ Could not save session '{}': {}\n", name, e);
+                    }
+                }
+            }
+            Err(HeliosError::Aborted(_)) => {
+                println!("\n⏹  Response aborted\n");
+                abort.reset();
+            }
+            Err(e) => {
+                eprintln!("\n❌ Error: {}\n", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The system prompt of `chat` when none is given.
+const DEFAULT_CHAT_PROMPT: &str = "You are a helpful AI assistant with access to various tools. Use them when needed to help the user.";
+
+/// The tools `chat --agent` uses when the config file does not list any.
+const DEFAULT_CHAT_TOOLS: &[&str] = &[
+    "calculator",
+    "timestamp",
+    "file_search",
+    "file_read",
+    "file_list",
+    "json_parser",
+    "text_processor",
+    "system_info",
+];
+
+/// The options of `helios-engine chat`.
+struct ChatOptions {
+    /// The system prompt of new conversations.
+    system_prompt: String,
+    /// The maximum number of tool-calling rounds per message in agent mode.
+    max_iterations: Option<usize>,
+    /// Whether to chat with a tool-using agent instead of the bare model.
+    agent: bool,
+    /// Where conversations are saved.
+    sessions: helios_engine::SessionStore,
+    /// The saved conversation to continue.
+    resume: Option<String>,
+}
+
+/// What answers messages in the terminal chat.
+enum Responder {
+    /// The bare model.
+    Client(Box<LLMClient>),
+    /// An agent with tools.
+    Agent(Box<helios_engine::Agent>),
+}
+
+/// The `[agent]` section of the config file, read by `chat --agent`.
+#[derive(serde::Deserialize)]
+struct ChatAgentSettings {
+    /// The built-in tools and toolsets the agent may use.
+    #[serde(default = "default_chat_tools")]
+    tools: Vec<String>,
+    /// Run destructive tools such as `file_write` and `shell_command` without asking.
+    #[serde(default)]
+    allow_destructive: bool,
+    /// The workspace, shell policy, permissions and limits, as in agent bundles.
+    #[serde(flatten)]
+    guardrails: helios_engine::Guardrails,
+}
+
+fn default_chat_tools() -> Vec<String> {
+    DEFAULT_CHAT_TOOLS
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+impl ChatAgentSettings {
+    /// Reads the `[agent]` section of the config file, if the file has one.
+    fn from_config_file(path: &str) -> helios_engine::Result<Self> {
+        #[derive(serde::Deserialize)]
+        struct AgentSection {
+            agent: Option<ChatAgentSettings>,
+        }
+
+        let section = match std::fs::read_to_string(path) {
+            Ok(content) => {
+                let content = helios_engine::config::expand_env_vars(&content)?;
+                toml::from_str::<AgentSection>(&content)?.agent
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(section.unwrap_or_else(|| ChatAgentSettings {
+            tools: default_chat_tools(),
+            allow_destructive: false,
+            guardrails: Default::default(),
+        }))
+    }
+}
+
+/// Builds the agent of `chat --agent` from the `[agent]` section of the config file.
+///
+/// Unless the section sets `allow_destructive`, destructive tool calls are passed
+/// to `approval` first.
+async fn build_chat_agent(
+    config_path: &str,
+    config: Config,
+    options: &ChatOptions,
+    approval: impl helios_engine::ToolApprovalHandler + 'static,
+) -> helios_engine::Result<helios_engine::Agent> {
+    let settings = ChatAgentSettings::from_config_file(config_path)?;
+    let tools = helios_engine::toolset::resolve_tool_names(&settings.tools)
+        .map_err(|e| HeliosError::ConfigError(format!("In the [agent] section: {}", e)))?;
+
+    let mut bundle = helios_engine::AgentBundle::new("helios");
+    bundle.system_prompt = Some(options.system_prompt.clone());
+    bundle.tools = tools
+        .into_iter()
+        .map(|name| helios_engine::ToolManifestEntry {
+            name,
+            description: None,
+            parameters: None,
+        })
+        .collect();
+    bundle.guardrails = settings.guardrails;
+    bundle.guardrails.max_iterations = options
+        .max_iterations
+        .or(bundle.guardrails.max_iterations)
+        .or(Some(5));
+
+    let mut builder = bundle.into_builder(config, Vec::new())?;
+    if !settings.allow_destructive {
+        builder = builder.tool_approval(approval);
+    }
+    builder.build().await
+}
+
+/// Prints a piece of a streamed reply: text as it arrives, tool calls on their own lines.
+fn print_stream_event(tracker: &mut ThinkingTracker, event: StreamEvent) {
+    match event {
+        StreamEvent::Content(_) | StreamEvent::Reasoning(_) => {
+            if let Some(output) = tracker.process_event(&event) {
+                print!("{}", output);
+            }
+        }
+        StreamEvent::ToolStarted {
+            name, arguments, ..
+        } => println!("\n🔧 {}({})", name, preview(&arguments, 120)),
+        StreamEvent::ToolFinished {
+            success, output, ..
+        } => {
+            let icon = if success { "✓" } else { "✗" };
+            let first_line = output.lines().next().unwrap_or("");
+            print!("   {} {}\n\n🤖: ", icon, preview(first_line, 120));
+        }
+        StreamEvent::ToolCallDelta { .. } => {}
+    }
+    let _ = io::stdout().flush();
+}
+
+/// Shortens `text` to at most `max` characters, marking the cut with an ellipsis.
+fn preview(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(max.saturating_sub(1)).collect();
+    short.push('…');
+    short
+}
+
+/// The session store used by `chat`: `dir`, or `.helios/sessions` in the home directory.
+fn session_store(dir: Option<&str>) -> helios_engine::SessionStore {
+    let dir = dir.map(std::path::PathBuf::from).unwrap_or_else(|| {
+        let home = std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .unwrap_or_else(|_| ".".to_string());
+        std::path::Path::new(&home).join(".helios").join("sessions")
+    });
+    helios_engine::SessionStore::new(dir)
+}
+
+/// Runs the `/save`, `/load`, `/sessions` and `/fork` chat commands.
+///
+/// `command` is the input without its leading slash. Returns `false` when it is
+/// not one of these commands.
+fn session_command(
+    command: &str,
+    sessions: &helios_engine::SessionStore,
+    session: &mut helios_engine::ChatSession,
+    session_name: &mut Option<String>,
+) -> helios_engine::Result<bool> {
+    let (command, argument) = command
+        .split_once(char::is_whitespace)
+        .map(|(command, argument)| (command, argument.trim()))
+        .unwrap_or((command, ""));
+    let argument = (!argument.is_empty()).then_some(argument);
+
+    match command {
+        "save" => {
+            let name = argument
+                .map(String::from)
+                .or_else(|| session_name.clone())
+                .ok_or_else(|| HeliosError::ConfigError("Usage: /save <name>".to_string()))?;
+            sessions.save(&name, session)?;
+            println!(
+                "✓ Saved session '{}' ({} messages); it is saved after every reply from now on\n",
+                name,
+                session.messages.len()
+            );
+            *session_name = Some(name);
+        }
+        "load" => {
+            let name = argument
+                .ok_or_else(|| HeliosError::ConfigError("Usage: /load <name>".to_string()))?;
+            *session = sessions.load(name)?;
+            *session_name = Some(name.to_string());
+            println!(
+                "✓ Loaded session '{}' ({} messages)\n",
+                name,
+                session.messages.len()
+            );
+        }
+        "sessions" => {
+            let saved = sessions.list()?;
+            if saved.is_empty() {
+                println!("No saved sessions in {}\n", sessions.dir().display());
+                return Ok(true);
+            }
+            println!("\n💾 Saved sessions in {}:", sessions.dir().display());
+            for info in saved {
+                let marker = if session_name.as_deref() == Some(info.name.as_str()) {
+                    "*"
+                } else {
+                    " "
+                };
+                println!(
+                    "  {} {}  ({} messages, saved {})",
+                    marker,
+                    info.name,
+                    info.messages,
+                    info.modified
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                );
+            }
+            println!();
+        }
+        "fork" => {
+            // Keep the conversation so far under its own name before branching off.
+            if let Some(name) = session_name.as_deref() {
+                sessions.save(name, session)?;
+            }
+            let name = match argument {
+                Some(name) => name.to_string(),
+                None => {
+                    // Forks of forks are numbered rather than named `x-fork-fork`.
+                    let original = session_name.as_deref().unwrap_or("session");
+                    let base = format!(
+                        "{}-fork",
+                        original.split("-fork").next().unwrap_or(original)
+                    );
+                    let mut name = base.clone();
+                    let mut n = 2;
+                    while sessions.contains(&name) {
+                        name = format!("{}-{}", base, n);
+                        n += 1;
+                    }
+                    name
+                }
+            };
+            if sessions.contains(&name) {
+                return Err(HeliosError::ConfigError(format!(
+                    "A session named '{}' already exists",
+                    name
+                )));
+            }
+            sessions.save(&name, session)?;
+            match session_name.replace(name.clone()) {
+                Some(original) => println!(
+                    "✓ Forked '{}' into '{}'; '{}' keeps the conversation so far\n",
+                    original, name, original
+                ),
+                None => println!("✓ Forked the conversation into '{}'\n", name),
+            }
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Starts a spoken chat session.
+///
+/// Each turn records a question (or takes a typed one), transcribes it, streams the
+/// reply and reads it aloud when a speech engine is configured.
+async fn voice_chat(
+    config_path: &str,
+    system_prompt: &str,
+    mode: &str,
+) -> helios_engine::Result<()> {
+    println!("🎙  Helios Engine - Voice Chat");
+    println!("========================================\n");
+
+    let mut config = load_config(config_path)?;
+    apply_mode_override(&mut config, mode);
+    let voice = VoiceConfig::from_config_file(config_path)?.unwrap_or_default();
+    let stt = voice.speech_to_text(&config.llm);
+    let tts = voice.text_to_speech(&config.llm);
+
+    let client = LLMClient::new(select_provider(config)).await?;
+    let mut session = helios_engine::ChatSession::new().with_system_prompt(system_prompt);
+
+    println!(
+        "✓ Press Enter to speak for {} seconds, or type a message",
+        voice.record_seconds
+    );
+    if tts.is_none() {
+        println!("✓ Replies are printed only; add a [voice.tts] section to hear them");
+    }
+    println!("✓ Type 'exit' to quit\n");
+
+    loop {
+        print!("🎤 ");
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            break;
+        }
+        let typed = line.trim();
+        if matches!(typed.to_lowercase().as_str(), "exit" | "quit") {
+            println!("\n👋 Goodbye!");
+            break;
+        }
+
+        let input = if typed.is_empty() {
+            let recording = tempfile::Builder::new()
+                .suffix(".wav")
+                .tempfile()?
+                .into_temp_path();
+            println!("⏺  Listening...");
+            let transcript = match voice.record(&recording).await {
+                Ok(()) => stt.transcribe(&recording).await,
+                Err(e) => Err(e),
+            };
+            match transcript {
+                Ok(text) if !text.is_empty() => {
+                    println!("You: {}", text);
+                    text
+                }
+                Ok(_) => {
+                    println!("(nothing heard)\n");
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("❌ Error: {}\n", e);
+                    continue;
+                }
+            }
+        } else {
+            typed.to_string()
+        };
+
+        session.add_user_message(&input);
+        let mut tracker = ThinkingTracker::new();
+        print!("\n🤖: ");
+        io::stdout().flush()?;
+
+        let result = client
+            .chat_stream_events(session.get_messages(), None, None, None, None, |event| {
+                if let Some(output) = tracker.process_event(&event) {
                     print!("{}", output);
                     io::stdout().flush().unwrap();
                 }
             })
-            .await
-        {
+            .await;
+        match result {
             Ok(response) => {
                 session.add_assistant_message(&response.content);
                 println!("\n");
+                if let Some(tts) = &tts {
+                    // The reply's reasoning is kept apart, so none of it is read aloud.
+                    let played = match tts.synthesize(&response.content).await {
+                        Ok(audio) => voice.play(&audio).await,
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = played {
+                        eprintln!("❌ Speech error: {}\n", e);
+                    }
+                }
             }
             Err(e) => {
                 eprintln!("\n❌ Error: {}\n", e);
-                // Remove the last user message since it failed
                 session.messages.pop();
             }
         }
@@ -478,6 +1466,63 @@ async fn interactive_chat(
     Ok(())
 }
 
+/// Removes thinking blocks so they are not read aloud.
+/// Picks the provider the CLI talks to.
+///
+/// Priority: Candle > Local > Remote (API)
+fn select_provider(config: Config) -> helios_engine::llm::LLMProviderType {
+    #[cfg(feature = "candle")]
+    if let Some(candle_config) = config.candle {
+        return helios_engine::llm::LLMProviderType::Candle(candle_config);
+    }
+    #[cfg(feature = "local")]
+    if let Some(local_config) = config.local {
+        return helios_engine::llm::LLMProviderType::Local(local_config);
+    }
+    helios_engine::llm::LLMProviderType::Remote(config.llm)
+}
+
+/// Starts a chat session in the full-screen terminal interface.
+#[cfg(feature = "tui")]
+async fn tui_chat(
+    config_path: &str,
+    mode: &str,
+    prices: Option<&str>,
+    options: ChatOptions,
+    sender: tokio::sync::mpsc::UnboundedSender<tui::AppEvent>,
+    events: tokio::sync::mpsc::UnboundedReceiver<tui::AppEvent>,
+) -> helios_engine::Result<()> {
+    let mut config = load_config(config_path)?;
+    apply_mode_override(&mut config, mode);
+    let prices = match prices {
+        Some(path) => helios_engine::PriceTable::from_file(path)?,
+        None => helios_engine::PriceTable::new(),
+    };
+
+    let model = select_provider(config.clone()).model_name();
+    let mut agent = if options.agent {
+        // The interface cannot ask for confirmation, so destructive calls are refused
+        // unless the [agent] section allows them.
+        let refuse = |_: &helios_engine::ToolApprovalRequest| false;
+        build_chat_agent(config_path, config, &options, refuse).await?
+    } else {
+        helios_engine::Agent::builder("helios")
+            .config(config)
+            .system_prompt(&options.system_prompt)
+            .build()
+            .await?
+    };
+    agent.set_price_table(prices);
+    if let Some(name) = &options.resume {
+        let mut session = options.sessions.load(name)?;
+        if session.system_prompt.is_none() {
+            session.system_prompt = agent.chat_session().system_prompt.clone();
+        }
+        *agent.chat_session_mut() = session;
+    }
+    tui::run(agent, model, sender, events).await
+}
+
 /// Loads the configuration from a file.
 fn load_config(config_path: &str) -> helios_engine::Result<Config> {
     match Config::from_file(config_path) {
@@ -499,19 +1544,27 @@ fn load_config(config_path: &str) -> helios_engine::Result<Config> {
     }
 }
 
+/// Forces online mode by removing the local and candle model configuration.
+#[cfg_attr(
+    not(any(feature = "local", feature = "candle")),
+    allow(unused_variables)
+)]
+fn remove_local_models(config: &mut Config) {
+    #[cfg(feature = "local")]
+    {
+        config.local = None;
+    }
+    #[cfg(feature = "candle")]
+    {
+        config.candle = None;
+    }
+}
+
 /// Applies the mode override to the configuration.
 fn apply_mode_override(config: &mut Config, mode: &str) {
     match mode {
         "online" => {
-            // Force online mode by removing local and candle config
-            #[cfg(feature = "local")]
-            {
-                config.local = None;
-            }
-            #[cfg(feature = "candle")]
-            {
-                config.candle = None;
-            }
+            remove_local_models(config);
             println!("🌐 Online mode: Using remote API");
 
             // Check if API key is set for online mode
@@ -577,28 +1630,134 @@ fn apply_mode_override(config: &mut Config, mode: &str) {
     }
 }
 
+/// The options of `helios-engine serve` beyond the address.
+struct ServeOptions {
+    /// The custom endpoints file.
+    custom_endpoints: Option<String>,
+    /// The agent bundle to serve.
+    agent_config: Option<String>,
+    /// Built-in tools and toolsets for the served agent.
+    tools: Vec<String>,
+    /// The served agent's system prompt.
+    system_prompt: Option<String>,
+    /// The advertised model name.
+    model_name: Option<String>,
+    /// Accepted API keys.
+    api_keys: Vec<String>,
+    /// A file of accepted API keys.
+    api_keys_file: Option<String>,
+    /// The directory `/v1/sessions` conversations are saved in.
+    sessions_dir: Option<String>,
+    /// How long an unused session is kept, in seconds.
+    session_idle_secs: u64,
+}
+
 /// Starts the HTTP server.
 async fn serve_server(
     config_path: &str,
     host: &str,
     port: u16,
     mode: &str,
-    custom_endpoints_path: Option<String>,
+    options: ServeOptions,
 ) -> helios_engine::Result<()> {
+    use helios_engine::serve::ServerState;
+    use helios_engine::tools::builtin_tool;
+
     let mut config = load_config(config_path)?;
     apply_mode_override(&mut config, mode);
 
     let address = format!("{}:{}", host, port);
 
     // Load custom endpoints if provided
-    let custom_endpoints = if let Some(path) = custom_endpoints_path {
-        Some(helios_engine::serve::load_custom_endpoints_config(&path)?)
+    let custom_endpoints = if let Some(path) = &options.custom_endpoints {
+        Some(helios_engine::serve::load_custom_endpoints_config(path)?)
     } else {
         None
     };
 
-    helios_engine::serve::start_server_with_custom_endpoints(config, &address, custom_endpoints)
-        .await?;
+    let mut api_keys = options.api_keys.clone();
+    if let Some(path) = &options.api_keys_file {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            HeliosError::ConfigError(format!("Failed to read API keys file '{}': {}", path, e))
+        })?;
+        api_keys.extend(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(String::from),
+        );
+    }
+
+    let bundle = options
+        .agent_config
+        .as_deref()
+        .map(helios_engine::AgentBundle::load)
+        .transpose()?;
+    let serve_agent =
+        bundle.is_some() || !options.tools.is_empty() || options.system_prompt.is_some();
+    let state = if serve_agent {
+        let tools: Vec<_> = helios_engine::toolset::resolve_tool_names(&options.tools)?
+            .iter()
+            .filter_map(|name| builtin_tool(name, None))
+            .collect();
+        let mut builder = match &bundle {
+            Some(bundle) => bundle.clone().into_builder(config.clone(), Vec::new())?,
+            None => helios_engine::Agent::builder("helios")
+                .config(config.clone())
+                .system_prompt("You are a helpful AI assistant."),
+        };
+        if let Some(prompt) = &options.system_prompt {
+            builder = builder.system_prompt(prompt);
+        }
+        let agent = builder.tools(tools).build().await?;
+        let model_name = options
+            .model_name
+            .clone()
+            .or_else(|| bundle.as_ref().map(|bundle| bundle.name.clone()))
+            .unwrap_or_else(|| config.llm.model_name.clone());
+        println!(
+            "🤖 Serving agent '{}' as model '{}'",
+            agent.name(),
+            model_name
+        );
+        ServerState::with_agent(agent, model_name)
+    } else {
+        let mut state = ServerState::from_config(&config).await?;
+        if let Some(model_name) = &options.model_name {
+            state.model_name = model_name.clone();
+        }
+        state
+    };
+    if !api_keys.is_empty() {
+        println!("🔒 Requiring one of {} API key(s)", api_keys.len());
+    }
+    // Embeddings use the `[rag]` settings, as the `rag` commands do.
+    let rag = helios_engine::RagConfig::from_config_file(config_path)?.unwrap_or_default();
+    let mut sessions = helios_engine::serve::sessions::ServerSessions::new()
+        .idle_timeout(std::time::Duration::from_secs(options.session_idle_secs));
+    if let Some(dir) = &options.sessions_dir {
+        println!("💾 Saving sessions in {}", dir);
+        sessions = sessions.persist_to(helios_engine::SessionStore::new(dir));
+    }
+    let state = state
+        .with_api_keys(api_keys)
+        .with_embeddings(rag.embeddings(&config.llm))
+        .with_sessions(sessions);
+
+    // Reloads keep the mode chosen on the command line and the bundle's model settings.
+    let online = mode == "online";
+    let _watcher = state.watch_config_with(config_path, move |config| {
+        if online {
+            remove_local_models(config);
+        }
+        if let Some(bundle) = &bundle {
+            bundle.apply_to_config(config);
+        }
+    })?;
+    println!("👀 Watching {} for changes", config_path);
+
+    helios_engine::serve::start_server_with_state(state, &address, custom_endpoints).await?;
 
     Ok(())
 }
@@ -611,6 +1770,13 @@ fn print_help() {
     println!("  history     - Show conversation history");
     println!("  summary     - Show session summary with metadata");
     println!("  help        - Show this help message");
+    println!(
+        "  /save [name]   - Save the conversation; it is saved after every reply from then on"
+    );
+    println!("  /load <name>   - Switch to a saved conversation");
+    println!("  /sessions      - List saved conversations");
+    println!("  /fork [name]   - Continue in a copy, keeping the saved conversation as it is");
+    println!("  Ctrl+C      - Abort the response being generated (exits at the prompt)");
     println!("\n💡 Features:");
     println!("  • Streaming responses for real-time output (local & remote)");
     println!("  • Thinking tags displayed when model uses them");
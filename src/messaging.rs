@@ -0,0 +1,1121 @@
+//! # Messaging Module
+//!
+//! Tools for team chat: `SlackTool` and `DiscordTool` post messages, read channels
+//! and reply in threads through each platform's web API. Posting is `Destructive`,
+//! so an agent with an approval handler asks before it says anything in public.
+//!
+//! [`SlackEvents`] goes the other way: added to a server with
+//! [`ServerState::with_slack`](crate::serve::ServerState::with_slack), it answers
+//! Slack's Events API at `/slack/events` and turns mentions of the bot, and direct
+//! messages to it, into conversations with the served agent, one per thread.
+
+use crate::chat::ChatMessage;
+use crate::error::{HeliosError, Result};
+use crate::tools::{required_str, Tool, ToolParameter, ToolPermission, ToolResult};
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use reqwest::Client;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Discord rejects messages longer than this many characters.
+const DISCORD_MESSAGE_LIMIT: usize = 2000;
+
+fn http_client() -> Client {
+    Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .unwrap_or_default()
+}
+
+fn param(param_type: &str, description: &str, required: bool) -> ToolParameter {
+    ToolParameter {
+        param_type: param_type.to_string(),
+        description: description.to_string(),
+        required: Some(required),
+    }
+}
+
+fn limit(args: &Value, default: u64) -> u64 {
+    args.get("limit")
+        .and_then(Value::as_u64)
+        .unwrap_or(default)
+        .clamp(1, 100)
+}
+
+/// A tool for posting to and reading from Slack.
+///
+/// ```rust,no_run
+/// use helios_engine::SlackTool;
+///
+/// let slack = SlackTool::new(std::env::var("SLACK_BOT_TOKEN").unwrap())
+///     .allow_channels(["C0123456789"]);
+/// ```
+#[derive(Clone)]
+pub struct SlackTool {
+    token: String,
+    base_url: String,
+    allowed_channels: Vec<String>,
+    client: Client,
+}
+
+impl SlackTool {
+    /// Creates a tool that acts as the bot with the given `xoxb-` token.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            base_url: "https://slack.com/api".to_string(),
+            allowed_channels: Vec::new(),
+            client: http_client(),
+        }
+    }
+
+    /// Sends requests to another Web API base URL.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Restricts the tool to these channel IDs.
+    pub fn allow_channels(mut self, channels: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowed_channels = channels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Posts `text` to a channel, in the thread started by `thread_ts` if given.
+    ///
+    /// Returns the new message's timestamp.
+    pub async fn post_message(
+        &self,
+        channel: &str,
+        text: &str,
+        thread_ts: Option<&str>,
+    ) -> Result<String> {
+        let mut body = json!({"channel": channel, "text": text});
+        if let Some(thread_ts) = thread_ts {
+            body["thread_ts"] = json!(thread_ts);
+        }
+        let response = self
+            .client
+            .post(format!("{}/chat.postMessage", self.base_url))
+            .bearer_auth(&self.token)
+            .json(&body)
+            .send()
+            .await;
+        let response = self.check(response).await?;
+        Ok(response
+            .get("ts")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    async fn get(&self, method: &str, query: &[(&str, String)]) -> Result<Value> {
+        let response = self
+            .client
+            .get(format!("{}/{}", self.base_url, method))
+            .bearer_auth(&self.token)
+            .query(query)
+            .send()
+            .await;
+        self.check(response).await
+    }
+
+    /// Turns a Web API response into its body, or an error for `"ok": false`.
+    async fn check(
+        &self,
+        response: std::result::Result<reqwest::Response, reqwest::Error>,
+    ) -> Result<Value> {
+        let response =
+            response.map_err(|e| HeliosError::ToolError(format!("Slack request failed: {}", e)))?;
+        let status = response.status();
+        let body: Value = response.json().await.map_err(|_| {
+            HeliosError::ToolError(format!(
+                "Slack returned HTTP {} without a JSON body",
+                status
+            ))
+        })?;
+        if body.get("ok").and_then(Value::as_bool) == Some(true) {
+            Ok(body)
+        } else {
+            Err(HeliosError::ToolError(format!(
+                "Slack error: {}",
+                body.get("error")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown")
+            )))
+        }
+    }
+
+    fn channel<'a>(&self, args: &'a Value) -> Result<&'a str> {
+        let channel = required_str(args, "channel")?;
+        if !self.allowed_channels.is_empty() && !self.allowed_channels.iter().any(|c| c == channel)
+        {
+            return Err(HeliosError::ToolError(format!(
+                "Channel {} is not one this tool may use",
+                channel
+            )));
+        }
+        Ok(channel)
+    }
+
+    fn format_messages(messages: &[Value]) -> String {
+        let lines: Vec<String> = messages
+            .iter()
+            .map(|message| {
+                let author = message
+                    .get("user")
+                    .or_else(|| message.get("username"))
+                    .or_else(|| message.get("bot_id"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown");
+                let mut line = format!(
+                    "[{}] <{}>: {}",
+                    message.get("ts").and_then(Value::as_str).unwrap_or(""),
+                    author,
+                    message.get("text").and_then(Value::as_str).unwrap_or("")
+                );
+                if let Some(replies) = message.get("reply_count").and_then(Value::as_u64) {
+                    line.push_str(&format!(" ({} replies in thread)", replies));
+                }
+                line
+            })
+            .collect();
+        if lines.is_empty() {
+            "No messages".to_string()
+        } else {
+            lines.join("\n")
+        }
+    }
+
+    async fn run(&self, args: &Value) -> Result<String> {
+        match required_str(args, "action")? {
+            "post" => {
+                let channel = self.channel(args)?;
+                let text = required_str(args, "text")?;
+                let thread_ts = args.get("thread_ts").and_then(Value::as_str);
+                let ts = self.post_message(channel, text, thread_ts).await?;
+                Ok(format!("Posted message {} to {}", ts, channel))
+            }
+            "read" => {
+                let channel = self.channel(args)?;
+                let body = self
+                    .get(
+                        "conversations.history",
+                        &[
+                            ("channel", channel.to_string()),
+                            ("limit", limit(args, 20).to_string()),
+                        ],
+                    )
+                    .await?;
+                // Slack returns the newest message first.
+                let mut messages = body
+                    .get("messages")
+                    .and_then(Value::as_array)
+                    .cloned()
+                    .unwrap_or_default();
+                messages.reverse();
+                Ok(Self::format_messages(&messages))
+            }
+            "read_thread" => {
+                let channel = self.channel(args)?;
+                let thread_ts = required_str(args, "thread_ts")?;
+                let body = self
+                    .get(
+                        "conversations.replies",
+                        &[
+                            ("channel", channel.to_string()),
+                            ("ts", thread_ts.to_string()),
+                            ("limit", limit(args, 50).to_string()),
+                        ],
+                    )
+                    .await?;
+                let messages = body
+                    .get("messages")
+                    .and_then(Value::as_array)
+                    .cloned()
+                    .unwrap_or_default();
+                Ok(Self::format_messages(&messages))
+            }
+            "list_channels" => {
+                let body = self
+                    .get(
+                        "conversations.list",
+                        &[
+                            ("types", "public_channel,private_channel".to_string()),
+                            ("exclude_archived", "true".to_string()),
+                            ("limit", "200".to_string()),
+                        ],
+                    )
+                    .await?;
+                let lines: Vec<String> = body
+                    .get("channels")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|channel| {
+                        let id = channel.get("id")?.as_str()?;
+                        if !self.allowed_channels.is_empty()
+                            && !self.allowed_channels.iter().any(|c| c == id)
+                        {
+                            return None;
+                        }
+                        let name = channel.get("name").and_then(Value::as_str).unwrap_or("");
+                        Some(format!("{} #{}", id, name))
+                    })
+                    .collect();
+                Ok(if lines.is_empty() {
+                    "No channels".to_string()
+                } else {
+                    lines.join("\n")
+                })
+            }
+            other => Err(HeliosError::ToolError(format!(
+                "Unknown action '{}'; use post, read, read_thread or list_channels",
+                other
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for SlackTool {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    fn description(&self) -> &str {
+        "Use Slack. Actions: 'post' text to a channel (pass thread_ts to reply in a thread), \
+         'read' a channel's recent messages, 'read_thread' for a thread's replies, and \
+         'list_channels'. Channels are IDs such as C0123456789."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        HashMap::from([
+            (
+                "action".to_string(),
+                param(
+                    "string",
+                    "One of: post, read, read_thread, list_channels",
+                    true,
+                ),
+            ),
+            ("channel".to_string(), param("string", "Channel ID", false)),
+            (
+                "text".to_string(),
+                param("string", "For post: the message (Slack mrkdwn)", false),
+            ),
+            (
+                "thread_ts".to_string(),
+                param(
+                    "string",
+                    "Timestamp of the thread's first message, to reply in or read a thread",
+                    false,
+                ),
+            ),
+            (
+                "limit".to_string(),
+                param(
+                    "integer",
+                    "For read: how many messages (at most 100)",
+                    false,
+                ),
+            ),
+        ])
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Standard
+    }
+
+    fn call_permission(&self, args: &Value) -> ToolPermission {
+        match args.get("action").and_then(Value::as_str) {
+            Some("post") => ToolPermission::Destructive,
+            _ => ToolPermission::ReadOnly,
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        Ok(match self.run(&args).await {
+            Ok(output) => ToolResult::success(output),
+            Err(e) => ToolResult::error(e.to_string()),
+        })
+    }
+}
+
+/// A tool for posting to and reading from Discord as a bot.
+///
+/// ```rust,no_run
+/// use helios_engine::DiscordTool;
+///
+/// let discord = DiscordTool::new(std::env::var("DISCORD_BOT_TOKEN").unwrap());
+/// ```
+#[derive(Clone)]
+pub struct DiscordTool {
+    token: String,
+    base_url: String,
+    allowed_channels: Vec<String>,
+    client: Client,
+}
+
+impl DiscordTool {
+    /// Creates a tool that acts as the bot with the given token.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            base_url: "https://discord.com/api/v10".to_string(),
+            allowed_channels: Vec::new(),
+            client: http_client(),
+        }
+    }
+
+    /// Sends requests to another API base URL.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Restricts the tool to these channel and thread IDs.
+    pub fn allow_channels(mut self, channels: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowed_channels = channels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    async fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value> {
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", self.base_url, path))
+            .header("Authorization", format!("Bot {}", self.token));
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Discord request failed: {}", e)))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if status.is_success() {
+            return Ok(body);
+        }
+        let message = body
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("request failed");
+        Err(HeliosError::ToolError(match body.get("retry_after") {
+            Some(retry_after) => format!(
+                "Discord error (HTTP {}): {}; retry after {}s",
+                status.as_u16(),
+                message,
+                retry_after
+            ),
+            None => format!("Discord error (HTTP {}): {}", status.as_u16(), message),
+        }))
+    }
+
+    fn channel<'a>(&self, args: &'a Value) -> Result<&'a str> {
+        let channel = required_str(args, "channel")?;
+        if !self.allowed_channels.is_empty() && !self.allowed_channels.iter().any(|c| c == channel)
+        {
+            return Err(HeliosError::ToolError(format!(
+                "Channel {} is not one this tool may use",
+                channel
+            )));
+        }
+        Ok(channel)
+    }
+
+    async fn run(&self, args: &Value) -> Result<String> {
+        match required_str(args, "action")? {
+            "post" => {
+                let channel = self.channel(args)?;
+                let text = required_str(args, "text")?;
+                let reply_to = args.get("reply_to").and_then(Value::as_str);
+                let mut ids = Vec::new();
+                for (i, chunk) in split_message(text, DISCORD_MESSAGE_LIMIT)
+                    .into_iter()
+                    .enumerate()
+                {
+                    let mut body = json!({"content": chunk});
+                    if let (0, Some(reply_to)) = (i, reply_to) {
+                        body["message_reference"] = json!({"message_id": reply_to});
+                    }
+                    let message = self
+                        .request(
+                            reqwest::Method::POST,
+                            &format!("/channels/{}/messages", channel),
+                            Some(body),
+                        )
+                        .await?;
+                    if let Some(id) = message.get("id").and_then(Value::as_str) {
+                        ids.push(id.to_string());
+                    }
+                }
+                Ok(match ids.as_slice() {
+                    [id] => format!("Posted message {} to {}", id, channel),
+                    _ => format!(
+                        "Posted {} messages to {}: {}",
+                        ids.len(),
+                        channel,
+                        ids.join(", ")
+                    ),
+                })
+            }
+            "read" => {
+                let channel = self.channel(args)?;
+                let body = self
+                    .request(
+                        reqwest::Method::GET,
+                        &format!("/channels/{}/messages?limit={}", channel, limit(args, 20)),
+                        None,
+                    )
+                    .await?;
+                let mut messages = body.as_array().cloned().unwrap_or_default();
+                // Discord returns the newest message first.
+                messages.reverse();
+                let lines: Vec<String> = messages
+                    .iter()
+                    .map(|message| {
+                        let mut line = format!(
+                            "[{}] {}: {}",
+                            message.get("id").and_then(Value::as_str).unwrap_or(""),
+                            message
+                                .pointer("/author/username")
+                                .and_then(Value::as_str)
+                                .unwrap_or("unknown"),
+                            message.get("content").and_then(Value::as_str).unwrap_or("")
+                        );
+                        if let Some(reply_to) = message
+                            .pointer("/message_reference/message_id")
+                            .and_then(Value::as_str)
+                        {
+                            line.push_str(&format!(" (reply to {})", reply_to));
+                        }
+                        if let Some(thread) = message.pointer("/thread/id").and_then(Value::as_str)
+                        {
+                            line.push_str(&format!(" (thread {})", thread));
+                        }
+                        line
+                    })
+                    .collect();
+                Ok(if lines.is_empty() {
+                    "No messages".to_string()
+                } else {
+                    lines.join("\n")
+                })
+            }
+            "start_thread" => {
+                let channel = self.channel(args)?;
+                let message_id = required_str(args, "message_id")?;
+                let name = args
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Discussion");
+                let thread = self
+                    .request(
+                        reqwest::Method::POST,
+                        &format!("/channels/{}/messages/{}/threads", channel, message_id),
+                        Some(json!({"name": name})),
+                    )
+                    .await?;
+                let id = thread.get("id").and_then(Value::as_str).unwrap_or_default();
+                Ok(format!(
+                    "Started thread {}; post to it with channel {}",
+                    id, id
+                ))
+            }
+            other => Err(HeliosError::ToolError(format!(
+                "Unknown action '{}'; use post, read or start_thread",
+                other
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for DiscordTool {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    fn description(&self) -> &str {
+        "Use Discord. Actions: 'post' text to a channel or thread (reply_to a message ID to \
+         reply to it), 'read' a channel's recent messages, and 'start_thread' from a message. \
+         A thread is a channel: read and post to it with its ID."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        HashMap::from([
+            (
+                "action".to_string(),
+                param("string", "One of: post, read, start_thread", true),
+            ),
+            (
+                "channel".to_string(),
+                param("string", "Channel or thread ID", false),
+            ),
+            (
+                "text".to_string(),
+                param("string", "For post: the message (Markdown)", false),
+            ),
+            (
+                "reply_to".to_string(),
+                param("string", "For post: ID of the message to reply to", false),
+            ),
+            (
+                "message_id".to_string(),
+                param(
+                    "string",
+                    "For start_thread: the message to start it from",
+                    false,
+                ),
+            ),
+            (
+                "name".to_string(),
+                param("string", "For start_thread: the thread's name", false),
+            ),
+            (
+                "limit".to_string(),
+                param(
+                    "integer",
+                    "For read: how many messages (at most 100)",
+                    false,
+                ),
+            ),
+        ])
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Standard
+    }
+
+    fn call_permission(&self, args: &Value) -> ToolPermission {
+        match args.get("action").and_then(Value::as_str) {
+            Some("post" | "start_thread") => ToolPermission::Destructive,
+            _ => ToolPermission::ReadOnly,
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        Ok(match self.run(&args).await {
+            Ok(output) => ToolResult::success(output),
+            Err(e) => ToolResult::error(e.to_string()),
+        })
+    }
+}
+
+/// Splits text into pieces of at most `max` characters, preferring line breaks.
+fn split_message(text: &str, max: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for line in text.split_inclusive('\n') {
+        if current.chars().count() + line.chars().count() > max && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+        let mut line = line;
+        while line.chars().count() > max {
+            let split = line.char_indices().nth(max).map_or(line.len(), |(i, _)| i);
+            chunks.push(line[..split].to_string());
+            line = &line[split..];
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() || chunks.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// What the server should do with a request from Slack's Events API.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlackRequest {
+    /// Answer Slack's URL verification with this challenge.
+    Challenge(String),
+    /// Reply to a message in its thread.
+    Message {
+        /// The channel the message was posted in.
+        channel: String,
+        /// The thread to reply in.
+        thread_ts: String,
+        /// The message, without the mention of the bot.
+        text: String,
+    },
+    /// Acknowledge the request and do nothing.
+    Ignore,
+    /// The request is not signed by Slack.
+    Unauthorized,
+}
+
+/// Turns Slack events into conversations with the served agent.
+///
+/// Each Slack thread is a separate conversation. The bot answers when it is
+/// mentioned and when it gets a direct message, and its answers go in the
+/// message's thread.
+///
+/// ```rust,no_run
+/// use helios_engine::{serve::ServerState, SlackEvents};
+///
+/// # fn example(state: ServerState) {
+/// let state = state.with_slack(SlackEvents::new(
+///     std::env::var("SLACK_SIGNING_SECRET").unwrap(),
+///     std::env::var("SLACK_BOT_TOKEN").unwrap(),
+/// ));
+/// # }
+/// ```
+pub struct SlackEvents {
+    signing_secret: String,
+    slack: SlackTool,
+    max_threads: usize,
+    max_history: usize,
+    conversations: Mutex<HashMap<String, Vec<ChatMessage>>>,
+    /// Thread keys, least recently used first.
+    recent: Mutex<VecDeque<String>>,
+    /// IDs of events already handled, since Slack retries deliveries.
+    seen: Mutex<VecDeque<String>>,
+}
+
+impl SlackEvents {
+    /// Creates a handler for an app with the given signing secret and bot token.
+    pub fn new(signing_secret: impl Into<String>, bot_token: impl Into<String>) -> Self {
+        Self::with_tool(signing_secret, SlackTool::new(bot_token))
+    }
+
+    /// Creates a handler that replies through `slack`.
+    pub fn with_tool(signing_secret: impl Into<String>, slack: SlackTool) -> Self {
+        Self {
+            signing_secret: signing_secret.into(),
+            slack,
+            max_threads: 1000,
+            max_history: 50,
+            conversations: Mutex::new(HashMap::new()),
+            recent: Mutex::new(VecDeque::new()),
+            seen: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Sets how many thread conversations are remembered (1,000 by default).
+    pub fn max_threads(mut self, max: usize) -> Self {
+        self.max_threads = max.max(1);
+        self
+    }
+
+    /// Sets how many messages of each thread are sent to the agent (50 by default).
+    pub fn max_history(mut self, max: usize) -> Self {
+        self.max_history = max.max(1);
+        self
+    }
+
+    /// The client replies are posted with.
+    pub fn slack(&self) -> &SlackTool {
+        &self.slack
+    }
+
+    /// Checks a request's signature and works out what it asks for.
+    pub fn parse(&self, headers: &HeaderMap, body: &[u8]) -> SlackRequest {
+        if !self.verify(headers, body) {
+            return SlackRequest::Unauthorized;
+        }
+        let Ok(payload) = serde_json::from_slice::<Value>(body) else {
+            return SlackRequest::Ignore;
+        };
+        match payload.get("type").and_then(Value::as_str) {
+            Some("url_verification") => {
+                return SlackRequest::Challenge(
+                    payload
+                        .get("challenge")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                )
+            }
+            Some("event_callback") => {}
+            _ => return SlackRequest::Ignore,
+        }
+
+        if let Some(event_id) = payload.get("event_id").and_then(Value::as_str) {
+            let mut seen = self.seen.lock().unwrap();
+            if seen.iter().any(|id| id == event_id) {
+                return SlackRequest::Ignore;
+            }
+            seen.push_back(event_id.to_string());
+            if seen.len() > 1000 {
+                seen.pop_front();
+            }
+        }
+
+        let event = &payload["event"];
+        let wanted = match event.get("type").and_then(Value::as_str) {
+            Some("app_mention") => true,
+            Some("message") => event.get("channel_type").and_then(Value::as_str) == Some("im"),
+            _ => false,
+        };
+        // Skip bots (including this one) and edits, deletions and joins.
+        if !wanted || event.get("bot_id").is_some() || event.get("subtype").is_some() {
+            return SlackRequest::Ignore;
+        }
+        let (Some(channel), Some(ts), Some(text)) = (
+            event.get("channel").and_then(Value::as_str),
+            event.get("ts").and_then(Value::as_str),
+            event.get("text").and_then(Value::as_str),
+        ) else {
+            return SlackRequest::Ignore;
+        };
+        let text = strip_mentions(text);
+        if text.is_empty() {
+            return SlackRequest::Ignore;
+        }
+        SlackRequest::Message {
+            channel: channel.to_string(),
+            thread_ts: event
+                .get("thread_ts")
+                .and_then(Value::as_str)
+                .unwrap_or(ts)
+                .to_string(),
+            text,
+        }
+    }
+
+    /// Adds a user message to a thread's conversation and returns the messages to send.
+    pub fn begin_turn(&self, channel: &str, thread_ts: &str, text: &str) -> Vec<ChatMessage> {
+        let key = format!("{}:{}", channel, thread_ts);
+        let mut conversations = self.conversations.lock().unwrap();
+        let mut recent = self.recent.lock().unwrap();
+        recent.retain(|k| k != &key);
+        recent.push_back(key.clone());
+        while recent.len() > self.max_threads {
+            if let Some(oldest) = recent.pop_front() {
+                conversations.remove(&oldest);
+            }
+        }
+        let history = conversations.entry(key).or_default();
+        history.push(ChatMessage::user(text));
+        let start = history.len().saturating_sub(self.max_history);
+        history[start..].to_vec()
+    }
+
+    /// Records the agent's reply in a thread's conversation.
+    pub fn finish_turn(&self, channel: &str, thread_ts: &str, reply: &str) {
+        let key = format!("{}:{}", channel, thread_ts);
+        if let Some(history) = self.conversations.lock().unwrap().get_mut(&key) {
+            history.push(ChatMessage::assistant(reply));
+        }
+    }
+
+    /// Verifies Slack's `v0` request signature and rejects requests over five minutes old.
+    fn verify(&self, headers: &HeaderMap, body: &[u8]) -> bool {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let (Some(timestamp), Some(signature)) = (
+            header("x-slack-request-timestamp"),
+            header("x-slack-signature"),
+        ) else {
+            return false;
+        };
+        let Ok(sent) = timestamp.parse::<i64>() else {
+            return false;
+        };
+        if crate::clock::now().timestamp().abs_diff(sent) > 300 {
+            return false;
+        }
+        let mut base = format!("v0:{}:", timestamp).into_bytes();
+        base.extend_from_slice(body);
+        let expected: String = hmac_sha256(self.signing_secret.as_bytes(), &base)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        crate::serve::keys_match(&format!("v0={}", expected), signature)
+    }
+}
+
+/// Removes `<@U123>` mentions from a Slack message.
+fn strip_mentions(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<@") {
+        out.push_str(&rest[..start]);
+        match rest[start..].find('>') {
+            Some(end) => rest = &rest[start + end + 1..],
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        }
+    }
+    out.push_str(rest);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// HMAC-SHA256, as Slack signs its requests.
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(message);
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::extract::{Path, Query};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use std::sync::Arc;
+
+    /// Signs a request body as Slack would.
+    pub(crate) fn slack_headers(secret: &str, body: &str) -> HeaderMap {
+        let timestamp = crate::clock::now().timestamp().to_string();
+        let base = format!("v0:{}:{}", timestamp, body);
+        let signature: String = hmac_sha256(secret.as_bytes(), base.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-slack-request-timestamp", timestamp.parse().unwrap());
+        headers.insert(
+            "x-slack-signature",
+            format!("v0={}", signature).parse().unwrap(),
+        );
+        headers
+    }
+
+    /// Starts a mock Slack Web API that records posted messages.
+    pub(crate) async fn mock_slack() -> (String, Arc<Mutex<Vec<Value>>>) {
+        let posted = Arc::new(Mutex::new(Vec::new()));
+        let recorded = posted.clone();
+        let app = Router::new()
+            .route(
+                "/chat.postMessage",
+                post(move |Json(body): Json<Value>| {
+                    let recorded = recorded.clone();
+                    async move {
+                        if body["channel"] == "C_LOCKED" {
+                            return Json(json!({"ok": false, "error": "not_in_channel"}));
+                        }
+                        recorded.lock().unwrap().push(body);
+                        Json(json!({"ok": true, "ts": "1700000000.000200"}))
+                    }
+                }),
+            )
+            .route(
+                "/conversations.history",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    assert_eq!(query["limit"], "2");
+                    Json(json!({"ok": true, "messages": [
+                        {"ts": "2.0", "user": "U2", "text": "on it"},
+                        {"ts": "1.0", "user": "U1", "text": "deploy failed", "reply_count": 3}
+                    ]}))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, posted)
+    }
+
+    /// Tests posting and reading with the Slack tool.
+    #[tokio::test]
+    async fn test_slack_tool() {
+        let (url, posted) = mock_slack().await;
+        let tool = SlackTool::new("xoxb-test")
+            .with_base_url(url)
+            .allow_channels(["C1", "C_LOCKED"]);
+
+        let post =
+            json!({"action": "post", "channel": "C1", "text": "Rolling back", "thread_ts": "1.0"});
+        assert_eq!(tool.call_permission(&post), ToolPermission::Destructive);
+        let result = tool.execute(post).await.unwrap();
+        assert_eq!(result.output, "Posted message 1700000000.000200 to C1");
+        assert_eq!(posted.lock().unwrap()[0]["thread_ts"], "1.0");
+
+        let result = tool
+            .execute(json!({"action": "read", "channel": "C1", "limit": 2}))
+            .await
+            .unwrap();
+        assert_eq!(
+            result.output,
+            "[1.0] <U1>: deploy failed (3 replies in thread)\n[2.0] <U2>: on it"
+        );
+
+        let result = tool
+            .execute(json!({"action": "post", "channel": "C_LOCKED", "text": "hi"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("not_in_channel"));
+
+        let result = tool
+            .execute(json!({"action": "read", "channel": "C9"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("not one this tool may use"));
+    }
+
+    /// Tests posting, reading and threads with the Discord tool.
+    #[tokio::test]
+    async fn test_discord_tool() {
+        let posted = Arc::new(Mutex::new(Vec::<Value>::new()));
+        let recorded = posted.clone();
+        let app = Router::new()
+            .route(
+                "/channels/{channel}/messages",
+                post(
+                    move |headers: axum::http::HeaderMap, Json(body): Json<Value>| {
+                        let recorded = recorded.clone();
+                        async move {
+                            assert_eq!(headers["authorization"], "Bot token");
+                            let mut recorded = recorded.lock().unwrap();
+                            recorded.push(body);
+                            Json(json!({"id": format!("m{}", recorded.len())}))
+                        }
+                    },
+                )
+                .get(|Path(channel): Path<String>| async move {
+                    if channel == "missing" {
+                        return (
+                            axum::http::StatusCode::NOT_FOUND,
+                            Json(json!({"message": "Unknown Channel", "code": 10003})),
+                        );
+                    }
+                    (
+                        axum::http::StatusCode::OK,
+                        Json(json!([
+                            {"id": "11", "content": "thanks", "author": {"username": "bo"},
+                             "message_reference": {"message_id": "10"}},
+                            {"id": "10", "content": "build is red", "author": {"username": "al"},
+                             "thread": {"id": "77"}}
+                        ])),
+                    )
+                }),
+            )
+            .route(
+                "/channels/{channel}/messages/{message}/threads",
+                post(|Json(body): Json<Value>| async move {
+                    assert_eq!(body["name"], "Build");
+                    Json(json!({"id": "77"}))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let tool = DiscordTool::new("token").with_base_url(url);
+
+        let result = tool
+            .execute(json!({"action": "read", "channel": "5"}))
+            .await
+            .unwrap();
+        assert_eq!(
+            result.output,
+            "[10] al: build is red (thread 77)\n[11] bo: thanks (reply to 10)"
+        );
+
+        let result = tool
+            .execute(json!({"action": "start_thread", "channel": "5", "message_id": "10", "name": "Build"}))
+            .await
+            .unwrap();
+        assert!(result.output.starts_with("Started thread 77"));
+
+        let long = format!("{}\n{}", "a".repeat(1500), "b".repeat(1500));
+        let result = tool
+            .execute(json!({"action": "post", "channel": "77", "text": long, "reply_to": "10"}))
+            .await
+            .unwrap();
+        assert_eq!(result.output, "Posted 2 messages to 77: m1, m2");
+        {
+            let posted = posted.lock().unwrap();
+            assert_eq!(posted[0]["message_reference"]["message_id"], "10");
+            assert!(posted[1].get("message_reference").is_none());
+            assert_eq!(posted[1]["content"], "b".repeat(1500));
+        }
+
+        let result = tool
+            .execute(json!({"action": "read", "channel": "missing"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("HTTP 404): Unknown Channel"));
+    }
+
+    /// Tests signature checks and event parsing for Slack events.
+    #[test]
+    fn test_slack_events_parse() {
+        let events = SlackEvents::new("secret", "xoxb-test");
+        let challenge = r#"{"type":"url_verification","challenge":"abc"}"#;
+        assert_eq!(
+            events.parse(&slack_headers("secret", challenge), challenge.as_bytes()),
+            SlackRequest::Challenge("abc".to_string())
+        );
+        assert_eq!(
+            events.parse(&slack_headers("wrong", challenge), challenge.as_bytes()),
+            SlackRequest::Unauthorized
+        );
+        for timestamp in [i64::MIN.to_string(), i64::MAX.to_string()] {
+            let mut headers = slack_headers("secret", challenge);
+            headers.insert("x-slack-request-timestamp", timestamp.parse().unwrap());
+            assert_eq!(
+                events.parse(&headers, challenge.as_bytes()),
+                SlackRequest::Unauthorized
+            );
+        }
+
+        let mention = r#"{"type":"event_callback","event_id":"Ev1","event":{"type":"app_mention","channel":"C1","ts":"5.0","text":"<@UBOT>  why is   prod down?"}}"#;
+        let headers = slack_headers("secret", mention);
+        assert_eq!(
+            events.parse(&headers, mention.as_bytes()),
+            SlackRequest::Message {
+                channel: "C1".to_string(),
+                thread_ts: "5.0".to_string(),
+                text: "why is prod down?".to_string(),
+            }
+        );
+        // Slack retries deliver the same event again.
+        assert_eq!(
+            events.parse(&headers, mention.as_bytes()),
+            SlackRequest::Ignore
+        );
+
+        let bot = r#"{"type":"event_callback","event":{"type":"message","channel_type":"im","bot_id":"B1","channel":"D1","ts":"6.0","text":"hi"}}"#;
+        assert_eq!(
+            events.parse(&slack_headers("secret", bot), bot.as_bytes()),
+            SlackRequest::Ignore
+        );
+
+        let first = events.begin_turn("C1", "5.0", "why is prod down?");
+        assert_eq!(first.len(), 1);
+        events.finish_turn("C1", "5.0", "The database is full.");
+        let second = events.begin_turn("C1", "5.0", "fix it");
+        assert_eq!(second.len(), 3);
+        assert_eq!(second[1].content, "The database is full.");
+    }
+
+    /// Tests splitting long Discord messages.
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("short", 10), vec!["short"]);
+        assert_eq!(
+            split_message("aaaa\nbbbb\ncc", 10),
+            vec!["aaaa\nbbbb\n", "cc"]
+        );
+        assert_eq!(
+            split_message("abcdefghijkl", 5),
+            vec!["abcde", "fghij", "kl"]
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! # Middleware Module
+//!
+//! This module defines the `AgentMiddleware` trait, which lets applications inspect
+//! and rewrite what an agent sends to and receives from the LLM and its tools.
+//! Middleware are registered with `AgentBuilder::middleware` and are the place for
+//! guardrails such as PII redaction, prompt-injection detection, logging and policy
+//! enforcement, without forking the agent loop.
+//!
+//! The module also provides [`LoggingMiddleware`], which traces every call, and
+//! [`ContextInjector`], which tells the model the current date and time.
+
+use crate::chat::{ChatMessage, Role};
+use crate::error::Result;
+use crate::tools::ToolResult;
+use async_trait::async_trait;
+use chrono::{FixedOffset, Local};
+use serde_json::Value;
+
+/// Hooks that run around every LLM call and tool call an agent makes.
+///
+/// Every hook has a default implementation that does nothing, so a middleware only
+/// implements the hooks it needs. Hooks run in the order the middleware were
+/// registered.
+///
+/// Returning an error from `before_llm_call`, `after_llm_call` or `after_tool_call`
+/// fails the agent's turn with that error. Returning an error from
+/// `before_tool_call` blocks the tool: it is not executed and the error is reported
+/// back to the LLM as a failed tool result, so the model can recover. Return
+/// `HeliosError::Aborted` to stop the run outright.
+#[async_trait]
+pub trait AgentMiddleware: Send + Sync {
+    /// Returns the name used to identify the middleware in error messages.
+    fn name(&self) -> &str {
+        "middleware"
+    }
+
+    /// Called with the messages about to be sent to the LLM.
+    ///
+    /// Changes affect the outgoing request only; the agent's chat history keeps the
+    /// original messages.
+    async fn before_llm_call(&self, messages: &mut Vec<ChatMessage>) -> Result<()> {
+        let _ = messages;
+        Ok(())
+    }
+
+    /// Called with the LLM's response before the agent stores it or runs its tool calls.
+    async fn after_llm_call(&self, response: &mut ChatMessage) -> Result<()> {
+        let _ = response;
+        Ok(())
+    }
+
+    /// Called with the tool name and arguments before a tool is executed.
+    async fn before_tool_call(&self, tool_name: &str, arguments: &mut Value) -> Result<()> {
+        let _ = (tool_name, arguments);
+        Ok(())
+    }
+
+    /// Called with a tool's result before it is returned to the LLM.
+    async fn after_tool_call(&self, tool_name: &str, result: &mut ToolResult) -> Result<()> {
+        let _ = (tool_name, result);
+        Ok(())
+    }
+}
+
+/// A middleware that logs LLM and tool calls with `tracing`.
+///
+/// ```rust,no_run
+/// # use helios_engine::{Agent, Config, LoggingMiddleware};
+/// # async fn example() -> helios_engine::Result<()> {
+/// let agent = Agent::builder("assistant")
+///     .config(Config::new_default())
+///     .middleware(LoggingMiddleware)
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingMiddleware;
+
+#[async_trait]
+impl AgentMiddleware for LoggingMiddleware {
+    fn name(&self) -> &str {
+        "logging"
+    }
+
+    async fn before_llm_call(&self, messages: &mut Vec<ChatMessage>) -> Result<()> {
+        tracing::info!("LLM call with {} message(s)", messages.len());
+        Ok(())
+    }
+
+    async fn after_llm_call(&self, response: &mut ChatMessage) -> Result<()> {
+        let tool_calls = response.tool_calls.as_ref().map_or(0, |calls| calls.len());
+        tracing::info!(
+            "LLM responded with {} character(s) and {} tool call(s)",
+            response.content.len(),
+            tool_calls
+        );
+        Ok(())
+    }
+
+    async fn before_tool_call(&self, tool_name: &str, arguments: &mut Value) -> Result<()> {
+        tracing::info!("Calling tool '{}' with {}", tool_name, arguments);
+        Ok(())
+    }
+
+    async fn after_tool_call(&self, tool_name: &str, result: &mut ToolResult) -> Result<()> {
+        if result.success {
+            tracing::info!("Tool '{}' succeeded", tool_name);
+        } else {
+            tracing::warn!("Tool '{}' failed: {}", tool_name, result.output);
+        }
+        Ok(())
+    }
+}
+
+/// A middleware that tells the model the current date and time and where it runs.
+///
+/// Before every LLM call it puts a short block with the date and time, the
+/// timezone, the operating system and the working directory at the start of the
+/// system prompt, adding a system message if there is none. The block is rebuilt
+/// for each call, so it stays current over long sessions, and it is not stored in
+/// the chat history. Each item can be turned off.
+///
+/// ```rust,no_run
+/// # use helios_engine::{Agent, Config, ContextInjector};
+/// # async fn example() -> helios_engine::Result<()> {
+/// let agent = Agent::builder("assistant")
+///     .config(Config::new_default())
+///     .middleware(ContextInjector::new().working_directory(false))
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContextInjector {
+    date_time: bool,
+    timezone: bool,
+    os: bool,
+    working_directory: bool,
+    utc_offset: Option<FixedOffset>,
+}
+
+impl Default for ContextInjector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContextInjector {
+    /// Creates an injector that reports every item, in the machine's local timezone.
+    pub fn new() -> Self {
+        Self {
+            date_time: true,
+            timezone: true,
+            os: true,
+            working_directory: true,
+            utc_offset: None,
+        }
+    }
+
+    /// Sets whether to report the current date and time.
+    pub fn date_time(mut self, enabled: bool) -> Self {
+        self.date_time = enabled;
+        self
+    }
+
+    /// Sets whether to report the timezone.
+    pub fn timezone(mut self, enabled: bool) -> Self {
+        self.timezone = enabled;
+        self
+    }
+
+    /// Sets whether to report the operating system.
+    pub fn os(mut self, enabled: bool) -> Self {
+        self.os = enabled;
+        self
+    }
+
+    /// Sets whether to report the working directory.
+    pub fn working_directory(mut self, enabled: bool) -> Self {
+        self.working_directory = enabled;
+        self
+    }
+
+    /// Reports times at `offset` from UTC instead of in the local timezone, for
+    /// servers whose users live elsewhere.
+    pub fn utc_offset(mut self, offset: FixedOffset) -> Self {
+        self.utc_offset = Some(offset);
+        self
+    }
+
+    /// Returns the context block as it would be injected now.
+    pub fn context(&self) -> String {
+        let now = crate::clock::now();
+        let (time, zone) = match self.utc_offset {
+            Some(offset) => (now.with_timezone(&offset), None),
+            None => (
+                now.with_timezone(&Local).fixed_offset(),
+                std::env::var("TZ").ok().filter(|zone| !zone.is_empty()),
+            ),
+        };
+        let mut lines = Vec::new();
+        if self.date_time {
+            lines.push(format!(
+                "Current date and time: {}",
+                time.format("%A, %Y-%m-%d %H:%M")
+            ));
+        }
+        if self.timezone {
+            let offset = time.format("UTC%:z");
+            lines.push(match zone {
+                Some(zone) => format!("Timezone: {} ({})", zone, offset),
+                None => format!("Timezone: {}", offset),
+            });
+        }
+        if self.os {
+            lines.push(format!(
+                "Operating system: {} ({})",
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            ));
+        }
+        if self.working_directory {
+            if let Ok(dir) = std::env::current_dir() {
+                lines.push(format!("Working directory: {}", dir.display()));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+#[async_trait]
+impl AgentMiddleware for ContextInjector {
+    fn name(&self) -> &str {
+        "context"
+    }
+
+    async fn before_llm_call(&self, messages: &mut Vec<ChatMessage>) -> Result<()> {
+        let context = self.context();
+        if context.is_empty() {
+            return Ok(());
+        }
+        match messages.first_mut() {
+            Some(message) if message.role == Role::System => {
+                message.content = format!("{}\n\n{}", context, message.content);
+            }
+            _ => messages.insert(0, ChatMessage::system(context)),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{self, FixedClock, SequentialIds};
+    use std::sync::Arc;
+
+    /// Tests that the context block leads the system prompt and follows the clock.
+    #[tokio::test]
+    async fn test_context_injector() {
+        let clock = Arc::new(FixedClock::new("2024-03-01T22:30:00Z".parse().unwrap()));
+        let injector = ContextInjector::new()
+            .utc_offset(FixedOffset::east_opt(2 * 3600).unwrap())
+            .os(false)
+            .working_directory(false);
+
+        clock::scope(clock.clone(), Arc::new(SequentialIds::new()), async {
+            let mut messages = vec![ChatMessage::system("Be brief."), ChatMessage::user("Hi")];
+            injector.before_llm_call(&mut messages).await.unwrap();
+            assert_eq!(
+                messages[0].content,
+                "Current date and time: Saturday, 2024-03-02 00:30\nTimezone: UTC+02:00\n\nBe brief."
+            );
+            assert_eq!(messages.len(), 2);
+
+            clock.advance(chrono::Duration::days(1));
+            let mut messages = vec![ChatMessage::user("Hi")];
+            injector
+                .clone()
+                .timezone(false)
+                .before_llm_call(&mut messages)
+                .await
+                .unwrap();
+            assert_eq!(messages[0].role, Role::System);
+            assert_eq!(
+                messages[0].content,
+                "Current date and time: Sunday, 2024-03-03 00:30"
+            );
+        })
+        .await;
+
+        let mut messages = vec![ChatMessage::user("Hi")];
+        ContextInjector::new()
+            .date_time(false)
+            .timezone(false)
+            .os(false)
+            .working_directory(false)
+            .before_llm_call(&mut messages)
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), 1);
+    }
+}
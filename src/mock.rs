@@ -0,0 +1,335 @@
+//! # Mock Module
+//!
+//! This module provides LLM providers for testing agents without live API keys.
+//! `MockLLMProvider` answers with scripted responses, including tool calls, and
+//! `RecordingProvider` captures real traffic to a cassette file that a
+//! `MockLLMProvider` can later replay.
+//!
+//! Either provider is plugged into an agent with `AgentBuilder::llm_provider`, or
+//! into a client with `LLMClient::from_provider`.
+
+use crate::chat::{ChatMessage, FunctionCall, ToolCall};
+use crate::config::LLMConfig;
+use crate::error::{HeliosError, Result};
+use crate::llm::{Choice, LLMProvider, LLMRequest, LLMResponse, RemoteLLMClient, Usage};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// A request and the response it received, as stored in a cassette.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    /// The request sent to the provider.
+    pub request: LLMRequest,
+    /// The response the provider returned.
+    pub response: LLMResponse,
+}
+
+/// A recording of LLM traffic that can be replayed in tests.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    /// The recorded interactions, in the order they happened.
+    #[serde(default)]
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Loads a cassette from a JSON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            HeliosError::ConfigError(format!(
+                "Failed to read cassette '{}': {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Saves the cassette to a JSON file, creating parent directories as needed.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// The script and request log shared by clones of a `MockLLMProvider`.
+#[derive(Debug, Default)]
+struct MockState {
+    responses: VecDeque<LLMResponse>,
+    requests: Vec<LLMRequest>,
+    tool_calls: usize,
+}
+
+/// An LLM provider that returns scripted responses in order.
+///
+/// Clones share the same script and request log, so a test can keep a clone to
+/// inspect what the agent sent after handing the provider over.
+///
+/// ```rust
+/// use helios_engine::{Agent, MockLLMProvider, CalculatorTool};
+/// use serde_json::json;
+///
+/// # async fn example() -> helios_engine::Result<()> {
+/// let mock = MockLLMProvider::new()
+///     .with_tool_call("calculator", json!({"expression": "2 + 2"}))
+///     .with_response("2 + 2 is 4.");
+///
+/// let mut agent = Agent::builder("tester")
+///     .llm_provider(mock.clone())
+///     .tool(Box::new(CalculatorTool))
+///     .build()
+///     .await?;
+///
+/// assert_eq!(agent.chat("What is 2 + 2?").await?, "2 + 2 is 4.");
+/// assert_eq!(mock.requests().len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockLLMProvider {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockLLMProvider {
+    /// Creates a provider with an empty script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a provider that replays the responses recorded in a cassette file.
+    ///
+    /// Responses are returned in recording order; the requests are not compared.
+    pub fn from_cassette<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let cassette = Cassette::load(path)?;
+        let mock = Self::new();
+        mock.lock().responses = cassette
+            .interactions
+            .into_iter()
+            .map(|interaction| interaction.response)
+            .collect();
+        Ok(mock)
+    }
+
+    /// Scripts a plain text response.
+    pub fn with_response(self, content: impl Into<String>) -> Self {
+        self.with_message(ChatMessage::assistant(content))
+    }
+
+    /// Scripts a response that calls `tool` with `arguments`.
+    pub fn with_tool_call(self, tool: impl Into<String>, arguments: Value) -> Self {
+        let id = {
+            let mut state = self.lock();
+            state.tool_calls += 1;
+            format!("call_{}", state.tool_calls)
+        };
+        let mut message = ChatMessage::assistant("");
+        message.tool_calls = Some(vec![ToolCall {
+            id,
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: tool.into(),
+                arguments: arguments.to_string(),
+            },
+        }]);
+        self.with_message(message)
+    }
+
+    /// Scripts an arbitrary assistant message.
+    pub fn with_message(self, message: ChatMessage) -> Self {
+        self.push_message(message);
+        self
+    }
+
+    /// Appends an assistant message to the script of a provider that is already in use.
+    pub fn push_message(&self, message: ChatMessage) {
+        let finish_reason = if message.tool_calls.is_some() {
+            "tool_calls"
+        } else {
+            "stop"
+        };
+        let mut state = self.lock();
+        let index = state.requests.len() + state.responses.len();
+        state.responses.push_back(LLMResponse {
+            id: format!("mock-{}", index),
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: "mock".to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message,
+                finish_reason: Some(finish_reason.to_string()),
+                logprobs: None,
+                metadata: Default::default(),
+            }],
+            usage: Usage::default(),
+        });
+    }
+
+    /// Returns the requests received so far.
+    pub fn requests(&self) -> Vec<LLMRequest> {
+        self.lock().requests.clone()
+    }
+
+    /// Returns the number of scripted responses not yet returned.
+    pub fn remaining(&self) -> usize {
+        self.lock().responses.len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl LLMProvider for MockLLMProvider {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    async fn generate(&self, request: LLMRequest) -> Result<LLMResponse> {
+        let mut state = self.lock();
+        let mut response = state.responses.pop_front().ok_or_else(|| {
+            HeliosError::LLMError(format!(
+                "MockLLMProvider has no scripted response for request {}",
+                state.requests.len() + 1
+            ))
+        })?;
+        response.model = request.model.clone();
+        state.requests.push(request);
+        Ok(response)
+    }
+}
+
+/// An LLM provider that forwards requests to another provider and records every
+/// interaction to a cassette file.
+///
+/// The cassette is rewritten after each call, so a recording survives a test run
+/// that fails part-way. Replay it with `MockLLMProvider::from_cassette`.
+pub struct RecordingProvider {
+    inner: Box<dyn LLMProvider>,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl RecordingProvider {
+    /// Records the traffic of `inner` to a new cassette at `path`.
+    pub fn new(inner: impl LLMProvider + 'static, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner: Box::new(inner),
+            path: path.into(),
+            cassette: Mutex::new(Cassette::default()),
+        }
+    }
+
+    /// Records the traffic of a remote provider built from `config`.
+    pub fn remote(config: LLMConfig, path: impl Into<PathBuf>) -> Self {
+        Self::new(RemoteLLMClient::new(config), path)
+    }
+
+    /// Returns the path of the cassette being recorded.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl LLMProvider for RecordingProvider {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    async fn generate(&self, request: LLMRequest) -> Result<LLMResponse> {
+        let response = self.inner.generate(request.clone()).await?;
+
+        let mut cassette = self.cassette.lock().unwrap_or_else(|e| e.into_inner());
+        cassette.interactions.push(Interaction {
+            request,
+            response: response.clone(),
+        });
+        cassette.save(&self.path)?;
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::LLMClient;
+
+    /// Tests that scripted responses are returned in order and requests are logged.
+    #[tokio::test]
+    async fn test_mock_returns_scripted_responses() {
+        let mock = MockLLMProvider::new()
+            .with_tool_call("calculator", serde_json::json!({"expression": "1 + 1"}))
+            .with_response("done");
+        let client = LLMClient::from_provider(mock.clone(), LLMConfig::default());
+
+        let first = client
+            .chat(vec![ChatMessage::user("hi")], None, None, None, None)
+            .await
+            .unwrap();
+        let call = &first.tool_calls.unwrap()[0];
+        assert_eq!(call.id, "call_1");
+        assert_eq!(call.function.name, "calculator");
+
+        let second = client
+            .chat_stream(
+                vec![ChatMessage::user("again")],
+                None,
+                None,
+                None,
+                None,
+                |_| {},
+            )
+            .await
+            .unwrap();
+        assert_eq!(second.content, "done");
+
+        assert_eq!(mock.requests().len(), 2);
+        assert_eq!(mock.requests()[1].messages[0].content, "again");
+        assert!(client
+            .chat(vec![ChatMessage::user("more")], None, None, None, None)
+            .await
+            .is_err());
+    }
+
+    /// Tests that recorded traffic can be replayed from the cassette file.
+    #[tokio::test]
+    async fn test_record_and_replay_cassette() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassettes").join("chat.json");
+
+        let live = MockLLMProvider::new().with_response("recorded answer");
+        let recorder = RecordingProvider::new(live, &path);
+        let client = LLMClient::from_provider(recorder, LLMConfig::default());
+        client
+            .chat(vec![ChatMessage::user("question")], None, None, None, None)
+            .await
+            .unwrap();
+
+        let cassette = Cassette::load(&path).unwrap();
+        assert_eq!(cassette.interactions.len(), 1);
+        assert_eq!(
+            cassette.interactions[0].request.messages[0].content,
+            "question"
+        );
+
+        let replay = MockLLMProvider::from_cassette(&path).unwrap();
+        let client = LLMClient::from_provider(replay, LLMConfig::default());
+        let answer = client
+            .chat(vec![ChatMessage::user("question")], None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(answer.content, "recorded answer");
+    }
+}
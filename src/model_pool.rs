@@ -0,0 +1,345 @@
+//! # Model Pool Module
+//!
+//! Loading a local model's weights takes seconds and a large share of VRAM or
+//! RAM. The model pool keeps loaded models around so that agents using the same
+//! model share one copy, and switching an agent between models does not load
+//! anything that is already resident.
+//!
+//! When the pool grows past its limits, the least recently used models are
+//! unloaded. The limits are configured in the `[model_pool]` section:
+//!
+//! ```toml
+//! [model_pool]
+//! max_models = 2
+//! max_memory_mb = 12000
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "local")]
+use {
+    crate::config::LocalConfig,
+    crate::error::{HeliosError, Result},
+    crate::llm::{llama_backend, model_params, restore_output, suppress_output, LocalLLMProvider},
+    llama_cpp_2::model::LlamaModel,
+    std::path::{Path, PathBuf},
+    std::sync::{Arc, OnceLock},
+};
+
+/// Limits on the local models kept loaded at the same time.
+///
+/// A model being used by a request is never unloaded mid-generation; eviction
+/// only drops the pool's reference, and the weights are freed once the request
+/// finishes. The most recently used model always stays, even if it alone is
+/// over `max_memory_mb`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelPoolConfig {
+    /// The most models kept loaded.
+    #[serde(default = "default_max_models")]
+    pub max_models: usize,
+    /// The most memory, in megabytes, that loaded models may take up, measured
+    /// by the size of their model files. `None` means no limit on memory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb: Option<u64>,
+}
+
+fn default_max_models() -> usize {
+    2
+}
+
+impl Default for ModelPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_models: default_max_models(),
+            max_memory_mb: None,
+        }
+    }
+}
+
+impl ModelPoolConfig {
+    /// Returns whether `count` models taking `bytes` in total exceed the limits.
+    fn exceeded(&self, count: usize, bytes: u64) -> bool {
+        count > self.max_models
+            || self
+                .max_memory_mb
+                .is_some_and(|mb| bytes > mb.saturating_mul(1024 * 1024))
+    }
+}
+
+/// A loaded model and the memory it takes.
+struct PooledModel<K, V> {
+    key: K,
+    model: V,
+    bytes: u64,
+}
+
+/// The least-recently-used bookkeeping behind the local model pool.
+///
+/// Entries are ordered from least to most recently used. Evicted models are
+/// returned to the caller so they can be dropped outside any lock.
+#[cfg_attr(not(feature = "local"), allow(dead_code))]
+pub(crate) struct ModelCache<K, V> {
+    entries: Vec<PooledModel<K, V>>,
+    limits: ModelPoolConfig,
+}
+
+#[cfg_attr(not(feature = "local"), allow(dead_code))]
+impl<K: PartialEq, V> ModelCache<K, V> {
+    /// Creates an empty cache with the given limits.
+    pub(crate) fn new(limits: ModelPoolConfig) -> Self {
+        Self {
+            entries: Vec::new(),
+            limits,
+        }
+    }
+
+    /// Changes the limits, returning the models that no longer fit.
+    pub(crate) fn set_limits(&mut self, limits: ModelPoolConfig) -> Vec<V> {
+        self.limits = limits;
+        self.evict()
+    }
+
+    /// Returns the model for `key` and marks it as the most recently used.
+    pub(crate) fn get(&mut self, key: &K) -> Option<&V> {
+        let index = self.entries.iter().position(|entry| &entry.key == key)?;
+        let entry = self.entries.remove(index);
+        self.entries.push(entry);
+        self.entries.last().map(|entry| &entry.model)
+    }
+
+    /// Adds a model taking `bytes` of memory, returning the models evicted to make room.
+    ///
+    /// A model already cached under `key` is replaced and returned as well.
+    pub(crate) fn insert(&mut self, key: K, model: V, bytes: u64) -> Vec<V> {
+        let mut evicted: Vec<V> = match self.entries.iter().position(|entry| entry.key == key) {
+            Some(index) => vec![self.entries.remove(index).model],
+            None => Vec::new(),
+        };
+        self.entries.push(PooledModel { key, model, bytes });
+        evicted.extend(self.evict());
+        evicted
+    }
+
+    /// Evicts the models that would not fit next to a new one taking `bytes`.
+    ///
+    /// Used before loading a model, so that its memory is freed first.
+    pub(crate) fn reserve(&mut self, bytes: u64) -> Vec<V> {
+        let mut evicted = Vec::new();
+        while !self.entries.is_empty()
+            && self
+                .limits
+                .exceeded(self.entries.len() + 1, self.bytes().saturating_add(bytes))
+        {
+            evicted.push(self.entries.remove(0).model);
+        }
+        evicted
+    }
+
+    /// Removes every model.
+    pub(crate) fn clear(&mut self) -> Vec<V> {
+        self.entries.drain(..).map(|entry| entry.model).collect()
+    }
+
+    /// Returns the cached keys, most recently used first.
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().rev().map(|entry| &entry.key)
+    }
+
+    /// Returns the memory taken by the cached models.
+    pub(crate) fn bytes(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.bytes).sum()
+    }
+
+    /// Drops the least recently used models until the rest fit the limits.
+    fn evict(&mut self) -> Vec<V> {
+        let mut evicted = Vec::new();
+        while self.entries.len() > 1 && self.limits.exceeded(self.entries.len(), self.bytes()) {
+            evicted.push(self.entries.remove(0).model);
+        }
+        evicted
+    }
+}
+
+/// The load settings that decide whether two local models can share weights.
+#[cfg(feature = "local")]
+#[derive(Debug, Clone, PartialEq)]
+struct ModelKey {
+    path: PathBuf,
+    n_gpu_layers: Option<u32>,
+    use_mmap: bool,
+    use_mlock: bool,
+}
+
+#[cfg(feature = "local")]
+type LoadedModels = tokio::sync::Mutex<ModelCache<ModelKey, Arc<LlamaModel>>>;
+
+/// Returns the process-wide cache of loaded models.
+#[cfg(feature = "local")]
+fn loaded_models() -> &'static LoadedModels {
+    static MODELS: OnceLock<LoadedModels> = OnceLock::new();
+    MODELS.get_or_init(|| tokio::sync::Mutex::new(ModelCache::new(ModelPoolConfig::default())))
+}
+
+/// The local models loaded in this process.
+///
+/// Every [`LocalLLMProvider`] takes its weights from the pool on each request,
+/// so agents configured with the same model file and load settings share one
+/// copy, and a model evicted in the meantime is loaded again transparently.
+/// Agents built from a config with `[local]` apply its `[model_pool]` limits.
+///
+/// ```rust,no_run
+/// use helios_engine::{Agent, Config, LocalModelPool};
+///
+/// # async fn example() -> helios_engine::Result<()> {
+/// let config = Config::from_file("config.toml")?;
+/// // Load both models up front so the first requests do not wait for them.
+/// LocalModelPool::preload(&config.local_model("router")?).await?;
+/// LocalModelPool::preload(&config.local_model("answer")?).await?;
+///
+/// let router = Agent::builder("router")
+///     .config(config.clone())
+///     .local_model("router")
+///     .build()
+///     .await?;
+/// let answerer = Agent::builder("answerer")
+///     .config(config)
+///     .local_model("answer")
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "local")]
+pub struct LocalModelPool;
+
+#[cfg(feature = "local")]
+impl LocalModelPool {
+    /// Changes the pool limits, unloading the models that no longer fit.
+    pub async fn configure(limits: ModelPoolConfig) {
+        let evicted = loaded_models().lock().await.set_limits(limits);
+        drop(evicted);
+    }
+
+    /// Downloads and loads the model of `config` ahead of its first request.
+    pub async fn preload(config: &LocalConfig) -> Result<()> {
+        let path = LocalLLMProvider::download_model(config).await?;
+        Self::load(config, &path).await.map(drop)
+    }
+
+    /// Returns the files of the loaded models, most recently used first.
+    pub async fn loaded() -> Vec<PathBuf> {
+        let models = loaded_models().lock().await;
+        models.keys().map(|key| key.path.clone()).collect()
+    }
+
+    /// Unloads every model.
+    ///
+    /// Models still generating a response are freed once they finish.
+    pub async fn clear() {
+        let evicted = loaded_models().lock().await.clear();
+        drop(evicted);
+    }
+
+    /// Returns the model in the file at `path`, loading it unless it is in the pool.
+    ///
+    /// Models are loaded one at a time, after the models that would not fit
+    /// next to the new one have been unloaded.
+    pub(crate) async fn load(config: &LocalConfig, path: &Path) -> Result<Arc<LlamaModel>> {
+        let key = ModelKey {
+            path: path.to_path_buf(),
+            n_gpu_layers: config.n_gpu_layers,
+            use_mmap: config.use_mmap,
+            use_mlock: config.use_mlock,
+        };
+        let mut models = loaded_models().lock().await;
+        if let Some(model) = models.get(&key) {
+            return Ok(Arc::clone(model));
+        }
+
+        let bytes = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        drop(models.reserve(bytes));
+        tracing::debug!("Loading local model {}", path.display());
+        let model = Arc::new(load_model(config, path)?);
+        let evicted = models.insert(key, Arc::clone(&model), bytes);
+        drop(models);
+        drop(evicted);
+        Ok(model)
+    }
+}
+
+/// Loads a model from disk, on the GPU if configured and available.
+#[cfg(feature = "local")]
+fn load_model(config: &LocalConfig, path: &Path) -> Result<LlamaModel> {
+    // llama.cpp logs every tensor while loading
+    let (stdout_backup, stderr_backup) = suppress_output();
+    let model = llama_backend().and_then(|backend| {
+        let params = model_params(config, &backend)?;
+        LlamaModel::load_from_file(&backend, path, &params)
+            .map_err(|e| HeliosError::LLMError(format!("Failed to load model: {:?}", e)))
+    });
+    restore_output(stdout_backup, stderr_backup);
+    model
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    /// Tests that the least recently used model is evicted first.
+    #[test]
+    fn test_model_cache_lru() {
+        let mut cache = ModelCache::new(ModelPoolConfig::default());
+        assert!(cache.insert("router", 1, MB).is_empty());
+        assert!(cache.insert("answer", 2, MB).is_empty());
+
+        // Using the router makes the answer model the least recently used.
+        assert_eq!(cache.get(&"router"), Some(&1));
+        assert_eq!(cache.insert("coder", 3, MB), vec![2]);
+        assert_eq!(cache.keys().collect::<Vec<_>>(), vec![&"coder", &"router"]);
+        assert_eq!(cache.get(&"answer"), None);
+
+        // Replacing a model hands back the old copy.
+        assert_eq!(cache.insert("coder", 4, MB), vec![3]);
+        assert_eq!(cache.clear(), vec![1, 4]);
+        assert_eq!(cache.bytes(), 0);
+    }
+
+    /// Tests the memory limit, including a single model that is over it.
+    #[test]
+    fn test_model_cache_memory_limit() {
+        let mut cache = ModelCache::new(ModelPoolConfig {
+            max_models: 10,
+            max_memory_mb: Some(10),
+        });
+        assert!(cache.insert("small", 1, 2 * MB).is_empty());
+        assert!(cache.insert("medium", 2, 6 * MB).is_empty());
+        assert_eq!(cache.insert("large", 3, 8 * MB), vec![1, 2]);
+        assert_eq!(cache.insert("huge", 4, 20 * MB), vec![3]);
+        assert_eq!(cache.keys().collect::<Vec<_>>(), vec![&"huge"]);
+
+        assert_eq!(cache.insert("tiny", 5, MB), vec![4]);
+        assert!(cache.reserve(9 * MB).is_empty());
+        assert_eq!(cache.reserve(10 * MB), vec![5]);
+        assert!(cache.insert("tiny", 5, MB).is_empty());
+        assert!(cache
+            .set_limits(ModelPoolConfig {
+                max_models: 0,
+                max_memory_mb: None,
+            })
+            .is_empty());
+        assert_eq!(cache.keys().collect::<Vec<_>>(), vec![&"tiny"]);
+    }
+
+    /// Tests reading the `[model_pool]` section.
+    #[test]
+    fn test_model_pool_config() {
+        let config: ModelPoolConfig = toml::from_str("max_memory_mb = 8000").unwrap();
+        assert_eq!(config.max_models, 2);
+        assert_eq!(config.max_memory_mb, Some(8000));
+        assert!(config.exceeded(1, 8001 * MB));
+        assert!(!ModelPoolConfig::default().exceeded(2, u64::MAX));
+        assert!(ModelPoolConfig::default().exceeded(3, 0));
+    }
+}
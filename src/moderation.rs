@@ -0,0 +1,473 @@
+//! # Moderation Module
+//!
+//! A `Moderator` checks what users send to an agent and what the agent answers,
+//! and decides whether the text may pass, should be redacted or annotated, or must
+//! be blocked. Moderators are added with `AgentBuilder::moderator` or
+//! `ServerState::with_moderator`; they run on the user's message before the agent
+//! sees it and on the final reply before it is returned.
+//!
+//! Two implementations are included: `OpenAIModerator`, which asks an
+//! OpenAI-compatible `/moderations` endpoint, and `KeywordModerator`, which
+//! applies local keyword and regex rules.
+
+use crate::chat::{ChatMessage, Role};
+use crate::error::{HeliosError, Result};
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Which side of the conversation a text comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModerationTarget {
+    /// A message from the user.
+    Input,
+    /// The agent's final reply.
+    Output,
+}
+
+/// What a moderator decided about a text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModerationVerdict {
+    /// The text passes unchanged.
+    Allow,
+    /// The text passes with a note appended, such as a content warning.
+    Annotate(String),
+    /// The text is replaced by this redacted version.
+    Redact(String),
+    /// The text is rejected for this reason.
+    Block(String),
+}
+
+/// What to do with text that matches a rule or is flagged by a moderation API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModerationAction {
+    /// Reject the text.
+    Block,
+    /// Replace the matching text, or the whole text for API moderators, with this.
+    Redact(String),
+    /// Let the text pass with this note appended.
+    Annotate(String),
+}
+
+/// Checks text going into or coming out of an agent.
+#[async_trait]
+pub trait Moderator: Send + Sync {
+    /// Returns the name used to identify the moderator in block messages.
+    fn name(&self) -> &str {
+        "moderator"
+    }
+
+    /// Decides what happens to `text`.
+    async fn moderate(&self, text: &str, target: ModerationTarget) -> Result<ModerationVerdict>;
+}
+
+/// Runs `text` through each moderator in turn and returns the text that passes.
+///
+/// Redactions and annotations carry over to the moderators that follow. A block
+/// stops the chain with [`HeliosError::Blocked`].
+pub async fn moderate<M: AsRef<dyn Moderator> + Sync>(
+    moderators: &[M],
+    text: &str,
+    target: ModerationTarget,
+) -> Result<String> {
+    let mut text = text.to_string();
+    for moderator in moderators {
+        let moderator = moderator.as_ref();
+        match moderator.moderate(&text, target).await? {
+            ModerationVerdict::Allow => {}
+            ModerationVerdict::Annotate(note) => {
+                tracing::info!(moderator = moderator.name(), ?target, "annotated: {}", note);
+                text = format!("{}\n\n[{}]", text, note);
+            }
+            ModerationVerdict::Redact(redacted) => {
+                tracing::info!(moderator = moderator.name(), ?target, "redacted content");
+                text = redacted;
+            }
+            ModerationVerdict::Block(reason) => {
+                tracing::warn!(moderator = moderator.name(), ?target, "blocked: {}", reason);
+                return Err(HeliosError::Blocked(format!(
+                    "{} rejected the {}: {}",
+                    moderator.name(),
+                    match target {
+                        ModerationTarget::Input => "input",
+                        ModerationTarget::Output => "response",
+                    },
+                    reason
+                )));
+            }
+        }
+    }
+    Ok(text)
+}
+
+/// Moderates the last user message of a conversation in place.
+pub(crate) async fn moderate_last_input<M: AsRef<dyn Moderator> + Sync>(
+    moderators: &[M],
+    messages: &mut [ChatMessage],
+) -> Result<()> {
+    if moderators.is_empty() {
+        return Ok(());
+    }
+    if let Some(message) = messages.iter_mut().rev().find(|m| m.role == Role::User) {
+        message.content = moderate(moderators, &message.content, ModerationTarget::Input).await?;
+    }
+    Ok(())
+}
+
+/// A keyword or regex rule of a [`KeywordModerator`].
+struct Rule {
+    pattern: Regex,
+    action: ModerationAction,
+    targets: Vec<ModerationTarget>,
+}
+
+/// A moderator that applies local keyword and regex rules.
+///
+/// Rules are checked in the order they were added. The first matching blocking
+/// rule rejects the text; otherwise every redaction is applied and the notes of all
+/// matching annotation rules are appended.
+///
+/// ```rust
+/// use helios_engine::{KeywordModerator, ModerationAction};
+///
+/// # fn example() -> helios_engine::Result<()> {
+/// let moderator = KeywordModerator::new()
+///     .words(["bomb", "nerve agent"], ModerationAction::Block)?
+///     .pattern(r"\b\d{3}-\d{2}-\d{4}\b", ModerationAction::Redact("[SSN]".into()))?
+///     .pattern(
+///         r"(?i)\binvest(ment)?\b",
+///         ModerationAction::Annotate("This is not financial advice.".into()),
+///     )?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct KeywordModerator {
+    rules: Vec<Rule>,
+}
+
+impl KeywordModerator {
+    /// Creates a moderator with no rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule for a regular expression, applied to input and output.
+    pub fn pattern(self, pattern: &str, action: ModerationAction) -> Result<Self> {
+        self.rule(
+            pattern,
+            action,
+            &[ModerationTarget::Input, ModerationTarget::Output],
+        )
+    }
+
+    /// Adds a rule matching any of `words` as whole words, ignoring case.
+    pub fn words<I, S>(self, words: I, action: ModerationAction) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let alternatives: Vec<String> = words
+            .into_iter()
+            .map(|word| regex::escape(word.as_ref()))
+            .collect();
+        if alternatives.is_empty() {
+            return Ok(self);
+        }
+        self.pattern(&format!(r"(?i)\b(?:{})\b", alternatives.join("|")), action)
+    }
+
+    /// Adds a rule for a regular expression that only applies to `targets`.
+    pub fn rule(
+        mut self,
+        pattern: &str,
+        action: ModerationAction,
+        targets: &[ModerationTarget],
+    ) -> Result<Self> {
+        let pattern = Regex::new(pattern).map_err(|e| {
+            HeliosError::ConfigError(format!("Invalid moderation pattern '{}': {}", pattern, e))
+        })?;
+        self.rules.push(Rule {
+            pattern,
+            action,
+            targets: targets.to_vec(),
+        });
+        Ok(self)
+    }
+}
+
+#[async_trait]
+impl Moderator for KeywordModerator {
+    fn name(&self) -> &str {
+        "keyword"
+    }
+
+    async fn moderate(&self, text: &str, target: ModerationTarget) -> Result<ModerationVerdict> {
+        let mut redacted: Option<String> = None;
+        let mut notes: Vec<&str> = Vec::new();
+        for rule in self.rules.iter().filter(|r| r.targets.contains(&target)) {
+            let current = redacted.as_deref().unwrap_or(text);
+            let Some(found) = rule.pattern.find(current) else {
+                continue;
+            };
+            match &rule.action {
+                ModerationAction::Block => {
+                    return Ok(ModerationVerdict::Block(format!(
+                        "matched '{}'",
+                        found.as_str()
+                    )))
+                }
+                ModerationAction::Annotate(note) => notes.push(note),
+                ModerationAction::Redact(replacement) => {
+                    redacted = Some(
+                        rule.pattern
+                            .replace_all(current, regex::NoExpand(replacement))
+                            .into_owned(),
+                    );
+                }
+            }
+        }
+        Ok(match (redacted, notes.is_empty()) {
+            (None, true) => ModerationVerdict::Allow,
+            (None, false) => ModerationVerdict::Annotate(notes.join(" ")),
+            (Some(redacted), true) => ModerationVerdict::Redact(redacted),
+            // A verdict carries one change, so fold the notes into the redaction.
+            (Some(redacted), false) => {
+                ModerationVerdict::Redact(format!("{}\n\n[{}]", redacted, notes.join(" ")))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ModerationResponse {
+    results: Vec<ModerationResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModerationResult {
+    flagged: bool,
+    #[serde(default)]
+    categories: BTreeMap<String, bool>,
+}
+
+/// A moderator backed by an OpenAI-compatible `/moderations` endpoint.
+///
+/// Flagged text is blocked by default; [`OpenAIModerator::on_flagged`] changes
+/// that to a redaction of the whole text or an annotation.
+pub struct OpenAIModerator {
+    base_url: String,
+    api_key: String,
+    model: String,
+    action: ModerationAction,
+    targets: Vec<ModerationTarget>,
+    client: Client,
+}
+
+impl OpenAIModerator {
+    /// Creates a moderator for the API at `base_url` (for example
+    /// `https://api.openai.com/v1`), using `omni-moderation-latest`.
+    pub fn new(base_url: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            api_key: api_key.into(),
+            model: "omni-moderation-latest".to_string(),
+            action: ModerationAction::Block,
+            targets: vec![ModerationTarget::Input, ModerationTarget::Output],
+            client: Client::new(),
+        }
+    }
+
+    /// Sets the moderation model.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Sets what happens to flagged text.
+    pub fn on_flagged(mut self, action: ModerationAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Only checks text from `targets`, for example to save a request per reply.
+    pub fn targets(mut self, targets: &[ModerationTarget]) -> Self {
+        self.targets = targets.to_vec();
+        self
+    }
+}
+
+#[async_trait]
+impl Moderator for OpenAIModerator {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    async fn moderate(&self, text: &str, target: ModerationTarget) -> Result<ModerationVerdict> {
+        if !self.targets.contains(&target) || text.trim().is_empty() {
+            return Ok(ModerationVerdict::Allow);
+        }
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/moderations",
+                self.base_url.trim_end_matches('/')
+            ))
+            .bearer_auth(&self.api_key)
+            .json(&serde_json::json!({"model": self.model, "input": text}))
+            .send()
+            .await
+            .map_err(|e| HeliosError::LLMError(format!("Moderation API error: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(HeliosError::LLMError(format!(
+                "Moderation failed ({}): {}",
+                status, body
+            )));
+        }
+        let response: ModerationResponse = response.json().await.map_err(|e| {
+            HeliosError::LLMError(format!("Failed to parse moderation response: {}", e))
+        })?;
+
+        let flagged: Vec<&str> = response
+            .results
+            .iter()
+            .filter(|result| result.flagged)
+            .flat_map(|result| {
+                result
+                    .categories
+                    .iter()
+                    .filter(|(_, hit)| **hit)
+                    .map(|(category, _)| category.as_str())
+            })
+            .collect();
+        if !response.results.iter().any(|result| result.flagged) {
+            return Ok(ModerationVerdict::Allow);
+        }
+
+        let categories = if flagged.is_empty() {
+            "unspecified".to_string()
+        } else {
+            flagged.join(", ")
+        };
+        Ok(match &self.action {
+            ModerationAction::Block => {
+                ModerationVerdict::Block(format!("flagged for {}", categories))
+            }
+            ModerationAction::Redact(replacement) => ModerationVerdict::Redact(replacement.clone()),
+            ModerationAction::Annotate(note) => {
+                ModerationVerdict::Annotate(format!("{} (flagged for {})", note, categories))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    /// Tests keyword blocking, redaction, annotation and per-target rules.
+    #[tokio::test]
+    async fn test_keyword_moderator() {
+        let moderator = KeywordModerator::new()
+            .words(["Forbidden"], ModerationAction::Block)
+            .unwrap()
+            .pattern(r"\d{4}-\d{4}", ModerationAction::Redact("[card]".into()))
+            .unwrap()
+            .rule(
+                r"(?i)medical",
+                ModerationAction::Annotate("Not medical advice.".into()),
+                &[ModerationTarget::Output],
+            )
+            .unwrap();
+        let moderators: Vec<Box<dyn Moderator>> = vec![Box::new(moderator)];
+
+        let err = moderate(&moderators, "a forbidden topic", ModerationTarget::Input)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HeliosError::Blocked(ref m) if m.contains("matched 'forbidden'")));
+        // Whole words only.
+        assert!(
+            moderate(&moderators, "unforbiddenness", ModerationTarget::Input)
+                .await
+                .is_ok()
+        );
+
+        assert_eq!(
+            moderate(&moderators, "pay 1234-5678 now", ModerationTarget::Input)
+                .await
+                .unwrap(),
+            "pay [card] now"
+        );
+        assert_eq!(
+            moderate(&moderators, "medical question", ModerationTarget::Input)
+                .await
+                .unwrap(),
+            "medical question"
+        );
+        assert_eq!(
+            moderate(&moderators, "Medical answer", ModerationTarget::Output)
+                .await
+                .unwrap(),
+            "Medical answer\n\n[Not medical advice.]"
+        );
+    }
+
+    /// Tests the OpenAI moderator against a mock `/moderations` endpoint.
+    #[tokio::test]
+    async fn test_openai_moderator() {
+        let app = Router::new().route(
+            "/moderations",
+            post(|Json(body): Json<Value>| async move {
+                let flagged = body["input"].as_str().unwrap().contains("bad");
+                Json(json!({"results": [{
+                    "flagged": flagged,
+                    "categories": {"violence": flagged, "hate": false}
+                }]}))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let moderator = OpenAIModerator::new(&url, "key");
+        assert_eq!(
+            moderator
+                .moderate("hello", ModerationTarget::Input)
+                .await
+                .unwrap(),
+            ModerationVerdict::Allow
+        );
+        assert_eq!(
+            moderator
+                .moderate("bad things", ModerationTarget::Input)
+                .await
+                .unwrap(),
+            ModerationVerdict::Block("flagged for violence".to_string())
+        );
+
+        let moderator = OpenAIModerator::new(&url, "key")
+            .on_flagged(ModerationAction::Redact("[removed]".into()))
+            .targets(&[ModerationTarget::Output]);
+        assert_eq!(
+            moderator
+                .moderate("bad input", ModerationTarget::Input)
+                .await
+                .unwrap(),
+            ModerationVerdict::Allow
+        );
+        assert_eq!(
+            moderator
+                .moderate("bad output", ModerationTarget::Output)
+                .await
+                .unwrap(),
+            ModerationVerdict::Redact("[removed]".to_string())
+        );
+    }
+}
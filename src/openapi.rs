@@ -0,0 +1,985 @@
+//! # OpenAPI Module
+//!
+//! Turns an OpenAPI 3 or Swagger 2 document into tools, one per operation, that
+//! call the API over HTTP. Each tool's parameters come from the operation's path,
+//! query and header parameters and the properties of its JSON request body.
+//!
+//! ```rust,no_run
+//! use helios_engine::{OpenApiSpec, ToolRegistry};
+//!
+//! # fn example() -> helios_engine::Result<()> {
+//! let spec = OpenApiSpec::from_file("petstore.yaml")?
+//!     .allow(["listPets", "showPetById"])
+//!     .header("Authorization", "Bearer ${PETSTORE_TOKEN}");
+//! let registry = ToolRegistry::from_openapi(&spec)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{Tool, ToolParameter, ToolPermission, ToolResult};
+use crate::truncation::truncate_chars;
+use async_trait::async_trait;
+use reqwest::{Client, Method, Url};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+const HTTP_METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// The most characters of a response body returned to the model.
+const MAX_RESPONSE_CHARS: usize = 20_000;
+
+/// An OpenAPI (or Swagger) document and the settings for the tools made from it.
+#[derive(Debug, Clone)]
+pub struct OpenApiSpec {
+    document: Value,
+    base_url: Option<String>,
+    allowed: Option<Vec<String>>,
+    headers: Vec<(String, String)>,
+    timeout: Duration,
+}
+
+impl OpenApiSpec {
+    /// Parses a document written in JSON or YAML.
+    pub fn parse(text: &str) -> Result<Self> {
+        let document: Value = match serde_json::from_str(text) {
+            Ok(document) => document,
+            Err(_) => serde_norway::from_str(text).map_err(|e| {
+                HeliosError::ConfigError(format!("Failed to parse OpenAPI document: {}", e))
+            })?,
+        };
+        Self::from_value(document)
+    }
+
+    /// Reads a JSON or YAML document from a file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            HeliosError::ConfigError(format!(
+                "Failed to read OpenAPI document '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::parse(&text)
+    }
+
+    /// Wraps an already parsed document.
+    pub fn from_value(document: Value) -> Result<Self> {
+        if document.get("openapi").is_none() && document.get("swagger").is_none() {
+            return Err(HeliosError::ConfigError(
+                "Not an OpenAPI document: missing 'openapi' or 'swagger' version".to_string(),
+            ));
+        }
+        Ok(Self {
+            document,
+            base_url: None,
+            allowed: None,
+            headers: Vec::new(),
+            timeout: Duration::from_secs(30),
+        })
+    }
+
+    /// Sends requests to `url` instead of the server named in the document.
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+
+    /// Only makes tools for these operations, by operation ID or generated tool name.
+    pub fn allow<I, S>(mut self, operations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed = Some(operations.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Adds a header to every request, such as an API key.
+    ///
+    /// `${VAR}` in the value is replaced with the environment variable `VAR`.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the timeout of each request (30 seconds by default).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the tool names of all operations in the document, allowed or not.
+    pub fn operation_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .operations()
+            .into_iter()
+            .map(|(method, path, operation)| operation_name(method, path, operation))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Makes a tool for each allowed operation.
+    ///
+    /// Naming an operation in [`OpenApiSpec::allow`] that the document does not
+    /// contain is an error, so a typo does not silently drop a tool.
+    pub fn tools(&self) -> Result<Vec<Box<dyn Tool>>> {
+        let base_url = self.resolve_base_url()?;
+        let client = Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map_err(|e| HeliosError::ToolError(format!("Failed to create HTTP client: {}", e)))?;
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), crate::config::expand_env_vars(value)?)))
+            .collect::<Result<Vec<(String, String)>>>()?;
+
+        if let Some(allowed) = &self.allowed {
+            let known: HashSet<String> = self
+                .operations()
+                .into_iter()
+                .flat_map(|(method, path, operation)| {
+                    let mut names = vec![operation_name(method, path, operation)];
+                    if let Some(id) = operation.get("operationId").and_then(Value::as_str) {
+                        names.push(id.to_string());
+                    }
+                    names
+                })
+                .collect();
+            if let Some(unknown) = allowed.iter().find(|name| !known.contains(*name)) {
+                return Err(HeliosError::ConfigError(format!(
+                    "Operation '{}' is not in the OpenAPI document",
+                    unknown
+                )));
+            }
+        }
+
+        let mut tools: Vec<Box<dyn Tool>> = Vec::new();
+        for (method, path, operation) in self.operations() {
+            let name = operation_name(method, path, operation);
+            if let Some(allowed) = &self.allowed {
+                let id = operation.get("operationId").and_then(Value::as_str);
+                if !allowed
+                    .iter()
+                    .any(|allowed| *allowed == name || Some(allowed.as_str()) == id)
+                {
+                    continue;
+                }
+            }
+            tools.push(Box::new(self.build_tool(
+                name, method, path, operation, &base_url, &client, &headers,
+            )?));
+        }
+        Ok(tools)
+    }
+
+    /// Lists `(method, path, operation)` for every operation in the document.
+    fn operations(&self) -> Vec<(&'static str, &str, &Value)> {
+        let mut operations = Vec::new();
+        if let Some(paths) = self.document.get("paths").and_then(Value::as_object) {
+            for (path, item) in paths {
+                let item = self.resolve(item);
+                for method in HTTP_METHODS {
+                    if let Some(operation) = item.get(*method) {
+                        operations.push((*method, path.as_str(), operation));
+                    }
+                }
+            }
+        }
+        operations
+    }
+
+    /// Follows a local `$ref` such as `#/components/schemas/Pet`.
+    fn resolve<'a>(&'a self, value: &'a Value) -> &'a Value {
+        let mut value = value;
+        // Bounded so a reference cycle cannot loop forever.
+        for _ in 0..16 {
+            let Some(reference) = value.get("$ref").and_then(Value::as_str) else {
+                break;
+            };
+            let target = reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.document.pointer(pointer));
+            match target {
+                Some(target) => value = target,
+                None => break,
+            }
+        }
+        value
+    }
+
+    fn resolve_base_url(&self) -> Result<String> {
+        let url = if let Some(url) = &self.base_url {
+            url.clone()
+        } else if let Some(server) = self
+            .document
+            .get("servers")
+            .and_then(Value::as_array)
+            .and_then(|servers| servers.first())
+        {
+            let mut url = server
+                .get("url")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            if let Some(variables) = server.get("variables").and_then(Value::as_object) {
+                for (name, variable) in variables {
+                    if let Some(default) = variable.get("default").and_then(Value::as_str) {
+                        url = url.replace(&format!("{{{}}}", name), default);
+                    }
+                }
+            }
+            url
+        } else if let Some(host) = self.document.get("host").and_then(Value::as_str) {
+            let scheme = self
+                .document
+                .get("schemes")
+                .and_then(Value::as_array)
+                .and_then(|schemes| schemes.first())
+                .and_then(Value::as_str)
+                .unwrap_or("https");
+            let base_path = self
+                .document
+                .get("basePath")
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            format!("{}://{}{}", scheme, host, base_path)
+        } else {
+            String::new()
+        };
+
+        if Url::parse(&url).is_err() {
+            return Err(HeliosError::ConfigError(format!(
+                "The OpenAPI document has no absolute server URL ('{}'); set one with base_url",
+                url
+            )));
+        }
+        Ok(url)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_tool(
+        &self,
+        name: String,
+        method: &str,
+        path: &str,
+        operation: &Value,
+        base_url: &str,
+        client: &Client,
+        headers: &[(String, String)],
+    ) -> Result<OpenApiTool> {
+        let path_item = self
+            .document
+            .get("paths")
+            .and_then(|paths| paths.get(path))
+            .map(|item| self.resolve(item));
+        let mut declared: Vec<&Value> = Vec::new();
+        for parameters in [
+            path_item.and_then(|item| item.get("parameters")),
+            operation.get("parameters"),
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_array)
+        {
+            for parameter in parameters {
+                let parameter = self.resolve(parameter);
+                // Operation parameters override path-level ones with the same name.
+                declared.retain(|existing| {
+                    existing.get("name") != parameter.get("name")
+                        || existing.get("in") != parameter.get("in")
+                });
+                declared.push(parameter);
+            }
+        }
+
+        let mut params = Vec::new();
+        let mut body = None;
+        for parameter in declared {
+            let Some(param_name) = parameter.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            let location = match parameter.get("in").and_then(Value::as_str) {
+                Some("path") => ParamLocation::Path,
+                Some("query") => ParamLocation::Query,
+                Some("header") => ParamLocation::Header,
+                Some("body") => {
+                    // Swagger 2 describes the request body as a parameter.
+                    let required = parameter
+                        .get("required")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false);
+                    body = parameter
+                        .get("schema")
+                        .map(|schema| (self.resolve(schema), required, "application/json"));
+                    continue;
+                }
+                _ => continue,
+            };
+            let schema = parameter
+                .get("schema")
+                .map(|schema| self.resolve(schema))
+                .unwrap_or(parameter);
+            params.push(OperationParam {
+                name: param_name.to_string(),
+                location,
+                required: location == ParamLocation::Path
+                    || parameter
+                        .get("required")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                param_type: schema_type(schema),
+                description: describe(parameter.get("description").and_then(Value::as_str), schema),
+            });
+        }
+
+        if let Some(request_body) = operation.get("requestBody") {
+            let request_body = self.resolve(request_body);
+            let required = request_body
+                .get("required")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            if let Some(content) = request_body.get("content").and_then(Value::as_object) {
+                let media = content
+                    .keys()
+                    .find(|media| media.contains("json"))
+                    .or_else(|| content.keys().next());
+                if let Some(media) = media {
+                    let schema = content[media]
+                        .get("schema")
+                        .map(|schema| self.resolve(schema))
+                        .unwrap_or(&Value::Null);
+                    body = Some((schema, required, media.as_str()));
+                }
+            }
+        }
+
+        let body = body.map(|(schema, required, media)| {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            match properties {
+                Some(properties) if media.contains("json") => {
+                    let required_fields: Vec<&str> = schema
+                        .get("required")
+                        .and_then(Value::as_array)
+                        .map(|fields| fields.iter().filter_map(Value::as_str).collect())
+                        .unwrap_or_default();
+                    let fields = properties
+                        .iter()
+                        .map(|(field, field_schema)| {
+                            let field_schema = self.resolve(field_schema);
+                            let argument = if params.iter().any(|p| p.name == *field) {
+                                format!("body_{}", field)
+                            } else {
+                                field.clone()
+                            };
+                            BodyField {
+                                argument,
+                                field: field.clone(),
+                                required: required && required_fields.contains(&field.as_str()),
+                                param_type: schema_type(field_schema),
+                                description: describe(None, field_schema),
+                            }
+                        })
+                        .collect();
+                    RequestBody::Fields(fields)
+                }
+                _ => RequestBody::Whole {
+                    required,
+                    media_type: media.to_string(),
+                    param_type: schema_type(schema),
+                    description: describe(
+                        schema.get("description").and_then(Value::as_str),
+                        schema,
+                    ),
+                },
+            }
+        });
+
+        let description = [
+            operation.get("summary").and_then(Value::as_str),
+            operation.get("description").and_then(Value::as_str),
+        ]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" - ");
+        let description = if description.is_empty() {
+            format!("{} {}", method.to_uppercase(), path)
+        } else {
+            truncate_chars(&description, 1000, "...")
+        };
+
+        Ok(OpenApiTool {
+            name,
+            description,
+            method: Method::from_bytes(method.to_uppercase().as_bytes())
+                .map_err(|e| HeliosError::ConfigError(format!("Invalid HTTP method: {}", e)))?,
+            base_url: base_url.to_string(),
+            path: path.to_string(),
+            params,
+            body,
+            headers: headers.to_vec(),
+            client: client.clone(),
+        })
+    }
+}
+
+/// Returns the tool name of an operation: its operation ID, or the method and path.
+fn operation_name(method: &str, path: &str, operation: &Value) -> String {
+    let raw = match operation.get("operationId").and_then(Value::as_str) {
+        Some(id) => id.to_string(),
+        None => format!("{}_{}", method, path),
+    };
+    let mut name = String::new();
+    for c in raw.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+            name.push(c);
+        } else if !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    let name = name.trim_matches('_');
+    name.chars().take(64).collect()
+}
+
+fn schema_type(schema: &Value) -> String {
+    match schema.get("type") {
+        Some(Value::String(kind)) => kind.clone(),
+        // OpenAPI 3.1 allows a list such as ["string", "null"].
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null")
+            .unwrap_or("string")
+            .to_string(),
+        _ if schema.get("properties").is_some() => "object".to_string(),
+        _ => "string".to_string(),
+    }
+}
+
+/// Builds a parameter description from the spec's text, format and allowed values.
+fn describe(description: Option<&str>, schema: &Value) -> String {
+    let mut text = description
+        .or_else(|| schema.get("description").and_then(Value::as_str))
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    if let Some(format) = schema.get("format").and_then(Value::as_str) {
+        text = format!("{} (format: {})", text, format).trim().to_string();
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        let values: Vec<String> = values
+            .iter()
+            .map(|value| match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect();
+        text = format!("{} (one of: {})", text, values.join(", "))
+            .trim()
+            .to_string();
+    }
+    truncate_chars(&text, 500, "...")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParamLocation {
+    Path,
+    Query,
+    Header,
+}
+
+#[derive(Debug, Clone)]
+struct OperationParam {
+    name: String,
+    location: ParamLocation,
+    required: bool,
+    param_type: String,
+    description: String,
+}
+
+#[derive(Debug, Clone)]
+struct BodyField {
+    /// The tool argument, prefixed with `body_` if it clashes with a parameter.
+    argument: String,
+    field: String,
+    required: bool,
+    param_type: String,
+    description: String,
+}
+
+#[derive(Debug, Clone)]
+enum RequestBody {
+    /// The properties of a JSON object body, each passed as its own argument.
+    Fields(Vec<BodyField>),
+    /// Any other body, passed as a single `body` argument.
+    Whole {
+        required: bool,
+        media_type: String,
+        param_type: String,
+        description: String,
+    },
+}
+
+/// A tool that calls one operation of an HTTP API described by an OpenAPI document.
+///
+/// `GET` and `HEAD` operations are read-only and `DELETE` operations are
+/// destructive, so an approval handler is consulted before anything is deleted.
+pub struct OpenApiTool {
+    name: String,
+    description: String,
+    method: Method,
+    base_url: String,
+    path: String,
+    params: Vec<OperationParam>,
+    body: Option<RequestBody>,
+    headers: Vec<(String, String)>,
+    client: Client,
+}
+
+impl OpenApiTool {
+    fn build_url(&self, args: &Value) -> Result<Url> {
+        let mut url = Url::parse(&self.base_url)
+            .map_err(|e| HeliosError::ToolError(format!("Invalid base URL: {}", e)))?;
+        {
+            let mut segments = url.path_segments_mut().map_err(|_| {
+                HeliosError::ToolError(format!("Base URL '{}' cannot have a path", self.base_url))
+            })?;
+            segments.pop_if_empty();
+            for segment in self.path.split('/').filter(|s| !s.is_empty()) {
+                let mut segment = segment.to_string();
+                for param in self
+                    .params
+                    .iter()
+                    .filter(|p| p.location == ParamLocation::Path)
+                {
+                    let placeholder = format!("{{{}}}", param.name);
+                    if segment.contains(&placeholder) {
+                        let value = args.get(&param.name).ok_or_else(|| {
+                            HeliosError::ToolError(format!("Missing '{}' parameter", param.name))
+                        })?;
+                        segment = segment.replace(&placeholder, &value_to_string(value));
+                    }
+                }
+                segments.push(&segment);
+            }
+        }
+        Ok(url)
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[async_trait]
+impl Tool for OpenApiTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut parameters: HashMap<String, ToolParameter> = self
+            .params
+            .iter()
+            .map(|param| {
+                (
+                    param.name.clone(),
+                    ToolParameter {
+                        param_type: param.param_type.clone(),
+                        description: param.description.clone(),
+                        required: Some(param.required),
+                    },
+                )
+            })
+            .collect();
+        match &self.body {
+            Some(RequestBody::Fields(fields)) => {
+                for field in fields {
+                    parameters.insert(
+                        field.argument.clone(),
+                        ToolParameter {
+                            param_type: field.param_type.clone(),
+                            description: field.description.clone(),
+                            required: Some(field.required),
+                        },
+                    );
+                }
+            }
+            Some(RequestBody::Whole {
+                required,
+                media_type,
+                param_type,
+                description,
+            }) => {
+                let description = if description.is_empty() {
+                    format!("The request body ({})", media_type)
+                } else {
+                    format!("The request body ({}): {}", media_type, description)
+                };
+                parameters.insert(
+                    "body".to_string(),
+                    ToolParameter {
+                        param_type: param_type.clone(),
+                        description,
+                        required: Some(*required),
+                    },
+                );
+            }
+            None => {}
+        }
+        parameters
+    }
+
+    fn permission(&self) -> ToolPermission {
+        match self.method {
+            Method::GET | Method::HEAD | Method::OPTIONS => ToolPermission::ReadOnly,
+            Method::DELETE => ToolPermission::Destructive,
+            _ => ToolPermission::Standard,
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        for param in self.params.iter().filter(|p| p.required) {
+            if args.get(&param.name).map_or(true, Value::is_null) {
+                return Err(HeliosError::ToolError(format!(
+                    "Missing '{}' parameter",
+                    param.name
+                )));
+            }
+        }
+
+        let url = self.build_url(&args)?;
+        let mut request = self.client.request(self.method.clone(), url.clone());
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+
+        let mut query: Vec<(String, String)> = Vec::new();
+        for param in &self.params {
+            let Some(value) = args.get(&param.name).filter(|v| !v.is_null()) else {
+                continue;
+            };
+            match param.location {
+                ParamLocation::Query => match value {
+                    Value::Array(items) => query.extend(
+                        items
+                            .iter()
+                            .map(|item| (param.name.clone(), value_to_string(item))),
+                    ),
+                    _ => query.push((param.name.clone(), value_to_string(value))),
+                },
+                ParamLocation::Header => {
+                    request = request.header(&param.name, value_to_string(value));
+                }
+                ParamLocation::Path => {}
+            }
+        }
+        if !query.is_empty() {
+            request = request.query(&query);
+        }
+
+        match &self.body {
+            Some(RequestBody::Fields(fields)) => {
+                let mut body = Map::new();
+                for field in fields {
+                    match args.get(&field.argument).filter(|v| !v.is_null()) {
+                        Some(value) => {
+                            body.insert(field.field.clone(), value.clone());
+                        }
+                        None if field.required => {
+                            return Err(HeliosError::ToolError(format!(
+                                "Missing '{}' parameter",
+                                field.argument
+                            )))
+                        }
+                        None => {}
+                    }
+                }
+                if !body.is_empty() {
+                    request = request.json(&body);
+                }
+            }
+            Some(RequestBody::Whole {
+                required,
+                media_type,
+                ..
+            }) => match args.get("body").filter(|v| !v.is_null()) {
+                Some(body) if media_type.contains("json") => request = request.json(body),
+                Some(body) => {
+                    request = request
+                        .header(reqwest::header::CONTENT_TYPE, media_type.as_str())
+                        .body(value_to_string(body));
+                }
+                None if *required => {
+                    return Err(HeliosError::ToolError(
+                        "Missing 'body' parameter".to_string(),
+                    ))
+                }
+                None => {}
+            },
+            None => {}
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                return Ok(ToolResult::error(format!(
+                    "{} {} failed: {}",
+                    self.method, url, e
+                )))
+            }
+        };
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let output = format!(
+            "HTTP {}\n\n{}",
+            status,
+            truncate_chars(&body, MAX_RESPONSE_CHARS, "...")
+        );
+        if status.is_success() {
+            Ok(ToolResult::success(output))
+        } else {
+            Ok(ToolResult::error(output))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ToolRegistry;
+    use axum::{
+        extract::{Path as UrlPath, Query},
+        routing::get,
+        Json, Router,
+    };
+
+    const PETSTORE: &str = r##"
+openapi: 3.0.0
+info:
+  title: Petstore
+  version: 1.0.0
+servers:
+  - url: http://petstore.example/v1
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      summary: List all pets
+      parameters:
+        - name: limit
+          in: query
+          description: How many pets to return
+          schema:
+            type: integer
+        - name: kind
+          in: query
+          schema:
+            type: string
+            enum: [cat, dog]
+    post:
+      operationId: createPet
+      summary: Create a pet
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              $ref: '#/components/schemas/NewPet'
+  /pets/{petId}:
+    parameters:
+      - $ref: '#/components/parameters/PetId'
+    get:
+      operationId: showPetById
+      summary: Info for a specific pet
+    delete:
+      summary: Delete a pet
+components:
+  parameters:
+    PetId:
+      name: petId
+      in: path
+      description: The id of the pet
+      schema:
+        type: string
+  schemas:
+    NewPet:
+      type: object
+      required: [name]
+      properties:
+        name:
+          type: string
+        tag:
+          type: string
+"##;
+
+    /// Tests that operations become tools with parameters derived from the spec.
+    #[test]
+    fn test_openapi_tool_definitions() {
+        let spec = OpenApiSpec::parse(PETSTORE).unwrap();
+        assert_eq!(
+            spec.operation_names(),
+            ["createPet", "delete_pets_petId", "listPets", "showPetById"]
+        );
+
+        let registry = ToolRegistry::from_openapi(&spec).unwrap();
+        let mut tools = registry.list_tools();
+        tools.sort();
+        assert_eq!(tools.len(), 4);
+
+        let definitions = registry.get_definitions();
+        let list = definitions
+            .iter()
+            .find(|d| d.function.name == "listPets")
+            .unwrap();
+        assert_eq!(list.function.description, "List all pets");
+        let kind = &list.function.parameters.properties["kind"];
+        assert_eq!(kind.description, "(one of: cat, dog)");
+        assert_eq!(
+            list.function.parameters.properties["limit"].param_type,
+            "integer"
+        );
+
+        let create = definitions
+            .iter()
+            .find(|d| d.function.name == "createPet")
+            .unwrap();
+        assert_eq!(
+            create.function.parameters.required,
+            Some(vec!["name".to_string()])
+        );
+        assert_eq!(
+            registry.permission("delete_pets_petId"),
+            Some(ToolPermission::Destructive)
+        );
+        assert_eq!(
+            registry.permission("showPetById"),
+            Some(ToolPermission::ReadOnly)
+        );
+
+        let allowed = OpenApiSpec::parse(PETSTORE)
+            .unwrap()
+            .allow(["listPets", "showPetById"]);
+        let registry = ToolRegistry::from_openapi(&allowed).unwrap();
+        let mut tools = registry.list_tools();
+        tools.sort();
+        assert_eq!(tools, ["listPets", "showPetById"]);
+
+        let typo = OpenApiSpec::parse(PETSTORE).unwrap().allow(["listPet"]);
+        assert!(ToolRegistry::from_openapi(&typo).is_err());
+
+        let swagger = OpenApiSpec::parse(
+            r#"{"swagger": "2.0", "host": "api.example.com", "basePath": "/v2",
+                "paths": {"/users/{id}": {"put": {"operationId": "updateUser", "parameters": [
+                    {"name": "id", "in": "path", "required": true, "type": "integer"},
+                    {"name": "user", "in": "body", "required": true,
+                     "schema": {"type": "object", "required": ["email"],
+                                "properties": {"email": {"type": "string", "format": "email"}}}}
+                ]}}}}"#,
+        )
+        .unwrap();
+        let tools = swagger.tools().unwrap();
+        let params = tools[0].parameters();
+        assert_eq!(params["id"].param_type, "integer");
+        assert_eq!(params["email"].description, "(format: email)");
+        assert_eq!(params["email"].required, Some(true));
+    }
+
+    /// Tests calling an API through generated tools.
+    #[tokio::test]
+    async fn test_openapi_tool_calls() {
+        let app = Router::new()
+            .route(
+                "/v1/pets",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    Json(
+                        serde_json::json!({"limit": query.get("limit"), "kind": query.get("kind")}),
+                    )
+                })
+                .post(
+                    |headers: axum::http::HeaderMap, Json(body): Json<Value>| async move {
+                        Json(serde_json::json!({
+                            "created": body,
+                            "key": headers.get("x-api-key").and_then(|v| v.to_str().ok())
+                        }))
+                    },
+                ),
+            )
+            .route(
+                "/v1/pets/{pet_id}",
+                get(|UrlPath(pet_id): UrlPath<String>| async move {
+                    if pet_id == "missing" {
+                        (axum::http::StatusCode::NOT_FOUND, "no such pet".to_string())
+                    } else {
+                        (axum::http::StatusCode::OK, format!("pet {}", pet_id))
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let spec = OpenApiSpec::parse(PETSTORE)
+            .unwrap()
+            .base_url(url)
+            .header("X-Api-Key", "secret");
+        let registry = ToolRegistry::from_openapi(&spec).unwrap();
+
+        let result = registry
+            .execute("listPets", serde_json::json!({"limit": 5, "kind": "cat"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(
+            result.output.contains(r#""limit":"5""#),
+            "{}",
+            result.output
+        );
+        assert!(result.output.contains(r#""kind":"cat""#));
+
+        let result = registry
+            .execute(
+                "createPet",
+                serde_json::json!({"name": "Rex", "tag": "dog"}),
+            )
+            .await
+            .unwrap();
+        assert!(result.output.contains(r#""name":"Rex""#));
+        assert!(result.output.contains(r#""key":"secret""#));
+
+        let result = registry
+            .execute("showPetById", serde_json::json!({"petId": "a b"}))
+            .await
+            .unwrap();
+        assert!(result.output.ends_with("pet a b"), "{}", result.output);
+
+        let result = registry
+            .execute("showPetById", serde_json::json!({"petId": "missing"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.starts_with("HTTP 404"));
+
+        assert!(registry
+            .execute("createPet", serde_json::json!({"tag": "dog"}))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,490 @@
+//! # Path Utilities Module
+//!
+//! This module provides the cross-platform path handling shared by the file tools.
+//! Paths produced by a model are normalized for the host platform: Windows accepts
+//! either separator and gets the `\\?\` prefix for paths beyond `MAX_PATH`, while
+//! Unix-like systems reject drive-letter and UNC paths with a clear error instead
+//! of silently treating them as relative file names.
+//!
+//! It also provides case-insensitive name matching (the default on Windows and
+//! macOS, whose file systems usually ignore case), a rename that falls back to
+//! copying when source and destination are on different volumes, and `PathJail`,
+//! which confines tool paths to a single directory tree.
+
+use crate::error::{HeliosError, Result};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The longest path Windows accepts without the `\\?\` prefix.
+#[cfg_attr(not(windows), allow(dead_code))]
+const WINDOWS_MAX_PATH: usize = 260;
+
+/// How many dangling symlinks `PathJail::resolve` follows before giving up.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Resolves a path argument supplied to a tool into a path for the host platform.
+///
+/// Surrounding whitespace and quotes are removed. On Windows, forward slashes are
+/// converted to backslashes and long absolute paths are given the `\\?\` prefix.
+/// On other platforms, Windows drive-letter and UNC paths are rejected.
+pub fn resolve_path(input: &str) -> Result<PathBuf> {
+    let trimmed = input.trim().trim_matches(|c| c == '"' || c == '\'');
+    if trimmed.is_empty() {
+        return Err(HeliosError::ToolError("Path cannot be empty".to_string()));
+    }
+
+    #[cfg(windows)]
+    {
+        Ok(to_long_path(&PathBuf::from(trimmed.replace('/', "\\"))))
+    }
+
+    #[cfg(not(windows))]
+    {
+        if is_windows_absolute(trimmed) {
+            return Err(HeliosError::ToolError(format!(
+                "'{}' is a Windows path, but this system uses '/'-separated paths",
+                trimmed
+            )));
+        }
+        Ok(PathBuf::from(trimmed))
+    }
+}
+
+/// Returns `true` if `path` is a Windows drive-letter (`C:\`, `C:/`) or UNC (`\\server`) path.
+pub fn is_windows_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    let drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+    drive || path.starts_with("\\\\")
+}
+
+/// Adds the `\\?\` prefix to absolute Windows paths longer than `MAX_PATH`.
+///
+/// Verbatim paths are not normalized by Windows, so `.` and `..` components are
+/// resolved first. On other platforms the path is returned unchanged.
+pub fn to_long_path(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        let raw = path.as_os_str().to_string_lossy();
+        if raw.len() < WINDOWS_MAX_PATH || raw.starts_with(r"\\?\") || !path.is_absolute() {
+            return path.to_path_buf();
+        }
+        let normalized = lexically_normalize(path);
+        let normalized = normalized.to_string_lossy();
+        match normalized.strip_prefix(r"\\") {
+            Some(unc) => PathBuf::from(format!(r"\\?\UNC\{}", unc)),
+            None => PathBuf::from(format!(r"\\?\{}", normalized)),
+        }
+    }
+
+    #[cfg(not(windows))]
+    {
+        path.to_path_buf()
+    }
+}
+
+/// Confines paths to a root directory.
+///
+/// Paths are resolved against the root, `..` components are collapsed and symlinks
+/// are followed, and anything that ends up outside the root is rejected. Wrap file
+/// tools in a `SandboxedTool` to apply a jail to them.
+///
+/// ```rust
+/// use helios_engine::path_utils::PathJail;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let jail = PathJail::new(dir.path()).unwrap();
+/// assert!(jail.resolve("notes/today.txt").is_ok());
+/// assert!(jail.resolve("../escape.txt").is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct PathJail {
+    root: PathBuf,
+}
+
+impl PathJail {
+    /// Creates a jail rooted at an existing directory.
+    pub fn new(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref().canonicalize().map_err(|e| {
+            HeliosError::ConfigError(format!(
+                "Invalid sandbox root '{}': {}",
+                root.as_ref().display(),
+                e
+            ))
+        })?;
+        if !root.is_dir() {
+            return Err(HeliosError::ConfigError(format!(
+                "Sandbox root '{}' is not a directory",
+                root.display()
+            )));
+        }
+        Ok(Self { root })
+    }
+
+    /// Returns the canonical root directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolves a tool path argument to an absolute path inside the root.
+    ///
+    /// Relative paths are taken relative to the root. The path does not need to
+    /// exist, but its deepest existing ancestor is canonicalized so a symlink cannot
+    /// lead outside the root. Dangling symlinks are followed to their target, so a
+    /// link to a missing file outside the root is rejected as well.
+    pub fn resolve(&self, input: &str) -> Result<PathBuf> {
+        let path = resolve_path(input)?;
+        let mut pending = lexically_normalize(&self.root.join(path));
+
+        for _ in 0..=MAX_SYMLINK_HOPS {
+            let mut existing = pending.as_path();
+            let mut missing = Vec::new();
+            let resolved = loop {
+                match existing.canonicalize() {
+                    Ok(canonical) => break Some(canonical),
+                    Err(_) if is_symlink(existing) => break None,
+                    Err(_) => match (existing.parent(), existing.file_name()) {
+                        (Some(parent), Some(name)) => {
+                            missing.push(name.to_os_string());
+                            existing = parent;
+                        }
+                        _ => return Err(self.escape_error(input)),
+                    },
+                }
+            };
+
+            let mut next = match resolved {
+                Some(canonical) => canonical,
+                None => {
+                    // `existing` is a dangling symlink: continue from its target.
+                    let target =
+                        std::fs::read_link(existing).map_err(|_| self.escape_error(input))?;
+                    let parent = existing
+                        .parent()
+                        .and_then(|parent| parent.canonicalize().ok())
+                        .ok_or_else(|| self.escape_error(input))?;
+                    let mut next = lexically_normalize(&parent.join(target));
+                    for name in missing.iter().rev() {
+                        next.push(name);
+                    }
+                    pending = next;
+                    continue;
+                }
+            };
+            for name in missing.iter().rev() {
+                next.push(name);
+            }
+
+            return if next.starts_with(&self.root) {
+                Ok(next)
+            } else {
+                Err(self.escape_error(input))
+            };
+        }
+
+        Err(HeliosError::ToolError(format!(
+            "Path '{}' has too many levels of symbolic links",
+            input
+        )))
+    }
+
+    fn escape_error(&self, input: &str) -> HeliosError {
+        HeliosError::ToolError(format!(
+            "Path '{}' is outside the allowed directory '{}'",
+            input,
+            display_path(&self.root)
+        ))
+    }
+}
+
+/// Returns `true` if `path` itself is a symlink, whether or not its target exists.
+fn is_symlink(path: &Path) -> bool {
+    path.symlink_metadata()
+        .map(|metadata| metadata.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+/// Resolves `.` and `..` components without touching the file system.
+pub fn lexically_normalize(path: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Returns whether file names are matched case-insensitively by default on this platform.
+///
+/// Windows and macOS file systems ignore case unless configured otherwise.
+pub fn default_case_insensitive() -> bool {
+    cfg!(any(windows, target_os = "macos"))
+}
+
+/// Compares two file names, optionally ignoring case.
+pub fn names_equal(a: &str, b: &str, case_insensitive: bool) -> bool {
+    if case_insensitive {
+        a.to_lowercase() == b.to_lowercase()
+    } else {
+        a == b
+    }
+}
+
+/// Returns `true` if the entry should be treated as hidden.
+///
+/// Dot-files are hidden everywhere; on Windows the hidden attribute is honoured too.
+pub fn is_hidden(path: &Path, metadata: Option<&std::fs::Metadata>) -> bool {
+    let dot_file = path
+        .file_name()
+        .map(|name| name.to_string_lossy().starts_with('.'))
+        .unwrap_or(false);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        dot_file
+            || metadata
+                .map(|m| m.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+                .unwrap_or(false)
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = metadata;
+        dot_file
+    }
+}
+
+/// Formats a path for tool output using `/` separators on every platform.
+///
+/// The `\\?\` prefix added for long Windows paths is removed.
+pub fn display_path(path: &Path) -> String {
+    let raw = path.to_string_lossy();
+    let raw = raw.strip_prefix(r"\\?\").unwrap_or(&raw);
+    if cfg!(windows) {
+        raw.replace('\\', "/")
+    } else {
+        raw.to_string()
+    }
+}
+
+/// Moves `from` to `to`, copying and deleting the source when they are on different volumes.
+///
+/// An existing file at `to` is replaced.
+pub fn rename_or_copy(from: &Path, to: &Path) -> io::Result<()> {
+    match std::fs::rename(from, to) {
+        Err(e) if is_cross_device(&e) => {
+            if std::fs::metadata(from)?.is_dir() {
+                copy_dir(from, to)?;
+                std::fs::remove_dir_all(from)
+            } else {
+                std::fs::copy(from, to)?;
+                std::fs::remove_file(from)
+            }
+        }
+        result => result,
+    }
+}
+
+/// Recursively copies a directory.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns `true` if a rename failed because source and destination are on different volumes.
+fn is_cross_device(error: &io::Error) -> bool {
+    // EXDEV on Unix-like systems, ERROR_NOT_SAME_DEVICE on Windows.
+    #[cfg(windows)]
+    const CROSS_DEVICE: i32 = 17;
+    #[cfg(not(windows))]
+    const CROSS_DEVICE: i32 = 18;
+
+    error.raw_os_error() == Some(CROSS_DEVICE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that quotes and whitespace are stripped and empty paths rejected.
+    #[test]
+    fn test_resolve_path_trims_input() {
+        assert_eq!(
+            resolve_path("  \"notes.txt\" ").unwrap(),
+            PathBuf::from("notes.txt")
+        );
+        assert!(resolve_path("   ").is_err());
+    }
+
+    /// Tests drive-letter and UNC detection.
+    #[test]
+    fn test_is_windows_absolute() {
+        assert!(is_windows_absolute(r"C:\Users\me"));
+        assert!(is_windows_absolute("d:/data"));
+        assert!(is_windows_absolute(r"\\server\share"));
+        assert!(!is_windows_absolute("/home/me"));
+        assert!(!is_windows_absolute("C:relative"));
+    }
+
+    /// Tests that `.` and `..` are resolved lexically.
+    #[test]
+    fn test_lexically_normalize() {
+        assert_eq!(
+            lexically_normalize(Path::new("a/./b/../c")),
+            PathBuf::from("a").join("c")
+        );
+        assert_eq!(
+            lexically_normalize(Path::new("../a")),
+            PathBuf::from("../a")
+        );
+    }
+
+    /// Tests case-insensitive name comparison.
+    #[test]
+    fn test_names_equal() {
+        assert!(names_equal("README.md", "readme.MD", true));
+        assert!(!names_equal("README.md", "readme.MD", false));
+    }
+
+    /// Tests that a rename replaces an existing destination.
+    #[test]
+    fn test_rename_or_copy_replaces_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from.txt");
+        let to = dir.path().join("to.txt");
+        std::fs::write(&from, "new").unwrap();
+        std::fs::write(&to, "old").unwrap();
+
+        rename_or_copy(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(std::fs::read_to_string(&to).unwrap(), "new");
+    }
+
+    /// Tests that the jail accepts paths inside the root and rejects escapes.
+    #[test]
+    fn test_path_jail() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("docs")).unwrap();
+        let jail = PathJail::new(dir.path()).unwrap();
+
+        let inside = jail.resolve("docs/../docs/new/file.txt").unwrap();
+        assert!(inside.starts_with(jail.root()));
+        assert!(inside.ends_with("docs/new/file.txt"));
+        assert!(jail.resolve("../outside.txt").is_err());
+
+        let absolute = jail.root().join("docs").to_string_lossy().to_string();
+        assert!(jail.resolve(&absolute).is_ok());
+        assert!(jail
+            .resolve(&std::env::temp_dir().to_string_lossy())
+            .is_err());
+    }
+
+    /// Tests that a symlink pointing outside the root cannot be used to escape.
+    #[cfg(unix)]
+    #[test]
+    fn test_path_jail_rejects_symlink_escape() {
+        let outside = tempfile::tempdir().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+        let jail = PathJail::new(dir.path()).unwrap();
+
+        assert!(jail.resolve("link/secret.txt").is_err());
+        assert!(jail.resolve("link").is_err());
+    }
+
+    /// Tests that a dangling symlink is followed to its target before checking the root.
+    #[cfg(unix)]
+    #[test]
+    fn test_path_jail_rejects_dangling_symlink_escape() {
+        let outside = tempfile::tempdir().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let target = outside.path().join("created.txt");
+        std::os::unix::fs::symlink(&target, dir.path().join("dangling")).unwrap();
+        std::os::unix::fs::symlink(outside.path().join("missing"), dir.path().join("dir")).unwrap();
+        std::os::unix::fs::symlink("inside.txt", dir.path().join("local")).unwrap();
+        let jail = PathJail::new(dir.path()).unwrap();
+
+        assert!(jail.resolve("dangling").is_err());
+        assert!(jail.resolve("dir/nested/file.txt").is_err());
+        assert_eq!(
+            jail.resolve("local").unwrap(),
+            jail.root().join("inside.txt")
+        );
+    }
+
+    /// Tests that Windows paths are rejected on Unix-like systems.
+    #[cfg(not(windows))]
+    #[test]
+    fn test_windows_paths_rejected_on_unix() {
+        assert!(resolve_path(r"C:\Users\me\file.txt").is_err());
+        assert!(resolve_path(r"\\server\share\file.txt").is_err());
+        assert!(!default_case_insensitive() || cfg!(target_os = "macos"));
+    }
+
+    /// Tests that separators are normalized and long paths prefixed on Windows.
+    #[cfg(windows)]
+    #[test]
+    fn test_windows_separators_and_long_paths() {
+        assert_eq!(
+            resolve_path("C:/Users/me/file.txt").unwrap(),
+            PathBuf::from(r"C:\Users\me\file.txt")
+        );
+
+        let long = format!(r"C:\{}\file.txt", "d".repeat(300));
+        let resolved = resolve_path(&long).unwrap();
+        assert!(resolved.to_string_lossy().starts_with(r"\\?\C:\"));
+        assert_eq!(display_path(&resolved), long.replace('\\', "/"));
+
+        let unc = format!(r"\\server\share\{}", "d".repeat(300));
+        assert!(to_long_path(Path::new(&unc))
+            .to_string_lossy()
+            .starts_with(r"\\?\UNC\server\share"));
+    }
+
+    /// Tests that Windows and macOS default to case-insensitive matching.
+    #[cfg(any(windows, target_os = "macos"))]
+    #[test]
+    fn test_case_insensitive_by_default() {
+        assert!(default_case_insensitive());
+    }
+
+    /// Tests that the hidden attribute is honoured on Windows.
+    #[cfg(windows)]
+    #[test]
+    fn test_hidden_attribute() {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret.txt");
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .attributes(0x2)
+            .open(&path)
+            .unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+        assert!(is_hidden(&path, Some(&metadata)));
+    }
+}
@@ -0,0 +1,618 @@
+//! # Pipeline Module
+//!
+//! Declarative workflows that chain prompts, agents, tools and RAG queries without
+//! writing orchestration code. A pipeline is a list of steps read from YAML (or
+//! JSON); each step stores its result under its `id`, and later steps refer to
+//! earlier results with `{{id}}` placeholders.
+//!
+//! ```yaml
+//! name: answer-from-docs
+//! steps:
+//!   - id: context
+//!     type: rag_query
+//!     query: "{{question}}"
+//!     limit: 3
+//!   - id: answer
+//!     type: prompt
+//!     prompt: |
+//!       Answer using only this context:
+//!       {{context}}
+//!
+//!       Question: {{question}}
+//! output: "{{answer}}"
+//! ```
+//!
+//! Step types are `prompt` (one LLM call), `agent` (a message to a registered
+//! agent), `tool` (a tool call with templated arguments), `rag_query` (a search),
+//! `branch` (runs `then` or `else` steps depending on a condition) and `map` (runs
+//! a step once per item of a list, with the item in `{{item}}`).
+
+use crate::agent::Agent;
+use crate::chat::ChatMessage;
+use crate::error::{HeliosError, Result};
+use crate::llm::LLMClient;
+use crate::rag::RAGSystem;
+use crate::tools::{Tool, ToolRegistry};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::OnceLock;
+
+/// The definition of a pipeline, as read from a pipeline file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineSpec {
+    /// The pipeline's name.
+    pub name: String,
+    /// What the pipeline does.
+    #[serde(default)]
+    pub description: String,
+    /// Values used when the caller does not provide an input of the same name.
+    #[serde(default)]
+    pub inputs: HashMap<String, Value>,
+    /// The steps, run in order.
+    pub steps: Vec<Step>,
+    /// A template for the final output. Defaults to the result of the last step.
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+/// One step of a pipeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Step {
+    /// The name the step's result is stored under. Defaults to `step_N`.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// What the step does.
+    #[serde(flatten)]
+    pub kind: StepKind,
+}
+
+/// The kinds of pipeline steps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepKind {
+    /// Sends a single prompt to the pipeline's LLM.
+    Prompt {
+        /// The user message template.
+        prompt: String,
+        /// An optional system prompt template.
+        #[serde(default)]
+        system: Option<String>,
+        /// The sampling temperature.
+        #[serde(default)]
+        temperature: Option<f32>,
+        /// The maximum number of tokens to generate.
+        #[serde(default)]
+        max_tokens: Option<u32>,
+    },
+    /// Sends a message to a registered agent, which may use its own tools.
+    Agent {
+        /// The name the agent was registered under.
+        agent: String,
+        /// The message template.
+        message: String,
+    },
+    /// Calls a tool.
+    Tool {
+        /// The tool name.
+        tool: String,
+        /// The arguments; string values are templates.
+        #[serde(default)]
+        args: Value,
+    },
+    /// Searches the pipeline's RAG system, producing the texts of the matches.
+    RagQuery {
+        /// The query template.
+        query: String,
+        /// The maximum number of matches.
+        #[serde(default = "default_rag_limit")]
+        limit: usize,
+    },
+    /// Runs one of two step lists depending on a condition.
+    Branch {
+        /// The condition to test.
+        condition: Condition,
+        /// The steps run when the condition holds.
+        #[serde(default)]
+        then: Vec<Step>,
+        /// The steps run otherwise.
+        #[serde(default, rename = "else")]
+        otherwise: Vec<Step>,
+    },
+    /// Runs a step once per item, producing the list of results.
+    Map {
+        /// A template naming the list. JSON arrays are iterated, other text by line.
+        items: String,
+        /// The step to run, with the item in `{{item}}` and its position in `{{index}}`.
+        step: Box<Step>,
+    },
+}
+
+fn default_rag_limit() -> usize {
+    5
+}
+
+/// A test on a rendered template, used by `branch` steps.
+///
+/// Every check that is set must pass. With no checks, the value must be non-empty
+/// and not `false`, `no` or `0`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Condition {
+    /// The template to test.
+    pub value: String,
+    /// Passes if the value contains this text (case-insensitive).
+    #[serde(default)]
+    pub contains: Option<String>,
+    /// Passes if the trimmed value equals this text (case-insensitive).
+    #[serde(default)]
+    pub equals: Option<String>,
+    /// Passes if the value matches this regular expression.
+    #[serde(default)]
+    pub matches: Option<String>,
+    /// Inverts the result.
+    #[serde(default)]
+    pub negate: bool,
+}
+
+impl Condition {
+    fn evaluate(&self, value: &str) -> Result<bool> {
+        let lower = value.to_lowercase();
+        let mut result = true;
+        if let Some(needle) = &self.contains {
+            result &= lower.contains(&needle.to_lowercase());
+        }
+        if let Some(expected) = &self.equals {
+            result &= lower.trim() == expected.to_lowercase().trim();
+        }
+        if let Some(pattern) = &self.matches {
+            let regex = regex::Regex::new(pattern).map_err(|e| {
+                HeliosError::ConfigError(format!("Invalid condition pattern '{}': {}", pattern, e))
+            })?;
+            result &= regex.is_match(value);
+        }
+        if self.contains.is_none() && self.equals.is_none() && self.matches.is_none() {
+            result = !matches!(lower.trim(), "" | "false" | "no" | "0");
+        }
+        Ok(result != self.negate)
+    }
+}
+
+/// The result of running a pipeline.
+#[derive(Debug, Clone)]
+pub struct PipelineOutput {
+    /// The rendered output template, or the result of the last step.
+    pub output: String,
+    /// The inputs and the result of every step, keyed by name.
+    pub variables: HashMap<String, Value>,
+}
+
+/// A runnable pipeline: a [`PipelineSpec`] plus the LLM, agents, tools and RAG
+/// system its steps use.
+pub struct Pipeline {
+    spec: PipelineSpec,
+    llm: Option<LLMClient>,
+    agents: HashMap<String, tokio::sync::Mutex<Agent>>,
+    tools: ToolRegistry,
+    rag: Option<RAGSystem>,
+}
+
+impl Pipeline {
+    /// Creates a pipeline from a definition.
+    pub fn new(spec: PipelineSpec) -> Self {
+        Self {
+            spec,
+            llm: None,
+            agents: HashMap::new(),
+            tools: ToolRegistry::new(),
+            rag: None,
+        }
+    }
+
+    /// Loads a pipeline from a YAML file, or a JSON file if the extension is `.json`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            HeliosError::ConfigError(format!(
+                "Failed to read pipeline '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        {
+            Ok(Self::new(serde_json::from_str(&content)?))
+        } else {
+            Self::from_yaml(&content)
+        }
+    }
+
+    /// Parses a pipeline from YAML.
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let spec = serde_norway::from_str(yaml)
+            .map_err(|e| HeliosError::ConfigError(format!("Invalid pipeline: {}", e)))?;
+        Ok(Self::new(spec))
+    }
+
+    /// Returns the pipeline's definition.
+    pub fn spec(&self) -> &PipelineSpec {
+        &self.spec
+    }
+
+    /// Sets the LLM used by `prompt` steps.
+    pub fn with_llm(mut self, client: LLMClient) -> Self {
+        self.llm = Some(client);
+        self
+    }
+
+    /// Registers an agent for `agent` steps under `name`.
+    pub fn with_agent(mut self, name: impl Into<String>, agent: Agent) -> Self {
+        self.agents
+            .insert(name.into(), tokio::sync::Mutex::new(agent));
+        self
+    }
+
+    /// Registers a tool for `tool` steps.
+    pub fn with_tool(mut self, tool: Box<dyn Tool>) -> Self {
+        self.tools.register(tool);
+        self
+    }
+
+    /// Sets the RAG system searched by `rag_query` steps.
+    pub fn with_rag(mut self, rag: RAGSystem) -> Self {
+        self.rag = Some(rag);
+        self
+    }
+
+    /// Returns the names of the tools the pipeline's steps call.
+    pub fn required_tools(&self) -> Vec<String> {
+        fn collect(steps: &[Step], names: &mut Vec<String>) {
+            for step in steps {
+                match &step.kind {
+                    StepKind::Tool { tool, .. } if !names.contains(tool) => {
+                        names.push(tool.clone())
+                    }
+                    StepKind::Branch {
+                        then, otherwise, ..
+                    } => {
+                        collect(then, names);
+                        collect(otherwise, names);
+                    }
+                    StepKind::Map { step, .. } => collect(std::slice::from_ref(step), names),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut names = Vec::new();
+        collect(&self.spec.steps, &mut names);
+        names
+    }
+
+    /// Runs the pipeline with the given inputs.
+    pub async fn run(&self, inputs: HashMap<String, Value>) -> Result<PipelineOutput> {
+        let mut variables = self.spec.inputs.clone();
+        variables.extend(inputs);
+
+        let last = self.run_steps(&self.spec.steps, &mut variables).await?;
+        let output = match &self.spec.output {
+            Some(template) => render(template, &variables)?,
+            None => last.as_ref().map(value_to_text).unwrap_or_default(),
+        };
+        Ok(PipelineOutput { output, variables })
+    }
+
+    /// Runs steps in order, returning the result of the last one.
+    fn run_steps<'a>(
+        &'a self,
+        steps: &'a [Step],
+        variables: &'a mut HashMap<String, Value>,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<Value>>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut last = None;
+            for (index, step) in steps.iter().enumerate() {
+                let result = self.run_step(step, variables).await?;
+                let id = step
+                    .id
+                    .clone()
+                    .unwrap_or_else(|| format!("step_{}", index + 1));
+                tracing::debug!("Pipeline '{}' finished step '{}'", self.spec.name, id);
+                variables.insert(id, result.clone());
+                last = Some(result);
+            }
+            Ok(last)
+        })
+    }
+
+    /// Runs one step and returns its result.
+    fn run_step<'a>(
+        &'a self,
+        step: &'a Step,
+        variables: &'a mut HashMap<String, Value>,
+    ) -> Pin<Box<dyn Future<Output = Result<Value>> + Send + 'a>> {
+        Box::pin(async move {
+            match &step.kind {
+                StepKind::Prompt {
+                    prompt,
+                    system,
+                    temperature,
+                    max_tokens,
+                } => {
+                    let llm = self.llm.as_ref().ok_or_else(|| {
+                        HeliosError::ConfigError(
+                            "Pipeline has a prompt step but no LLM; call with_llm".to_string(),
+                        )
+                    })?;
+                    let mut messages = Vec::new();
+                    if let Some(system) = system {
+                        messages.push(ChatMessage::system(render(system, variables)?));
+                    }
+                    messages.push(ChatMessage::user(render(prompt, variables)?));
+                    let reply = llm
+                        .chat(messages, None, *temperature, *max_tokens, None)
+                        .await?;
+                    Ok(Value::String(reply.content))
+                }
+                StepKind::Agent { agent, message } => {
+                    let agent = self.agents.get(agent).ok_or_else(|| {
+                        HeliosError::ConfigError(format!(
+                            "Pipeline step uses unknown agent '{}'",
+                            agent
+                        ))
+                    })?;
+                    let message = render(message, variables)?;
+                    let reply = agent.lock().await.chat(message).await?;
+                    Ok(Value::String(reply))
+                }
+                StepKind::Tool { tool, args } => {
+                    let args = render_value(args, variables)?;
+                    let result = self.tools.execute(tool, args).await?;
+                    if !result.success {
+                        return Err(HeliosError::ToolError(format!(
+                            "Tool '{}' failed: {}",
+                            tool, result.output
+                        )));
+                    }
+                    Ok(Value::String(result.output))
+                }
+                StepKind::RagQuery { query, limit } => {
+                    let rag = self.rag.as_ref().ok_or_else(|| {
+                        HeliosError::ConfigError(
+                            "Pipeline has a rag_query step but no RAG system; call with_rag"
+                                .to_string(),
+                        )
+                    })?;
+                    let results = rag.search(&render(query, variables)?, *limit).await?;
+                    Ok(Value::Array(
+                        results.into_iter().map(|r| Value::String(r.text)).collect(),
+                    ))
+                }
+                StepKind::Branch {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    let value = render(&condition.value, variables)?;
+                    let steps = if condition.evaluate(&value)? {
+                        then
+                    } else {
+                        otherwise
+                    };
+                    Ok(self
+                        .run_steps(steps, variables)
+                        .await?
+                        .unwrap_or(Value::Null))
+                }
+                StepKind::Map { items, step } => {
+                    let items = list_items(items, variables)?;
+                    let mut results = Vec::with_capacity(items.len());
+                    for (index, item) in items.into_iter().enumerate() {
+                        variables.insert("item".to_string(), item);
+                        variables.insert("index".to_string(), Value::from(index));
+                        results.push(self.run_step(step, variables).await?);
+                    }
+                    variables.remove("item");
+                    variables.remove("index");
+                    Ok(Value::Array(results))
+                }
+            }
+        })
+    }
+}
+
+/// Formats a value for insertion into text.
+///
+/// Strings are inserted as they are, lists of strings one per paragraph, and
+/// anything else as JSON.
+fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        Value::Array(items) if items.iter().all(Value::is_string) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        other => other.to_string(),
+    }
+}
+
+fn placeholder_regex() -> &'static regex::Regex {
+    static REGEX: OnceLock<regex::Regex> = OnceLock::new();
+    REGEX.get_or_init(|| regex::Regex::new(r"\{\{\s*([A-Za-z0-9_.\-]+)\s*\}\}").unwrap())
+}
+
+/// Looks up a variable by a dotted path such as `result.items.0`.
+fn lookup(path: &str, variables: &HashMap<String, Value>) -> Result<Value> {
+    let mut parts = path.split('.');
+    let name = parts.next().unwrap_or_default();
+    let mut value = variables
+        .get(name)
+        .cloned()
+        .ok_or_else(|| HeliosError::ConfigError(format!("Unknown pipeline variable '{}'", name)))?;
+    for part in parts {
+        // Text results from tools and models are often JSON; look inside them too.
+        if let Value::String(s) = &value {
+            if let Ok(parsed) = serde_json::from_str::<Value>(s) {
+                value = parsed;
+            }
+        }
+        value = step_into(&value, part).cloned().ok_or_else(|| {
+            HeliosError::ConfigError(format!(
+                "Pipeline variable '{}' has no field '{}'",
+                path, part
+            ))
+        })?;
+    }
+    Ok(value)
+}
+
+fn step_into<'a>(value: &'a Value, part: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(map) => map.get(part),
+        Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    }
+}
+
+/// Replaces `{{name}}` placeholders in a template.
+pub fn render(template: &str, variables: &HashMap<String, Value>) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut last = 0;
+    for captures in placeholder_regex().captures_iter(template) {
+        let whole = captures.get(0).expect("match");
+        output.push_str(&template[last..whole.start()]);
+        output.push_str(&value_to_text(&lookup(&captures[1], variables)?));
+        last = whole.end();
+    }
+    output.push_str(&template[last..]);
+    Ok(output)
+}
+
+/// Renders every string inside a JSON value.
+///
+/// A string that is exactly one placeholder is replaced by the variable's value
+/// itself, so numbers and objects keep their type.
+fn render_value(value: &Value, variables: &HashMap<String, Value>) -> Result<Value> {
+    Ok(match value {
+        Value::String(s) => {
+            let trimmed = s.trim();
+            match placeholder_regex().captures(trimmed) {
+                Some(captures) if captures.get(0).expect("match").as_str() == trimmed => {
+                    lookup(&captures[1], variables)?
+                }
+                _ => Value::String(render(s, variables)?),
+            }
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render_value(item, variables))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| Ok((key.clone(), render_value(value, variables)?)))
+                .collect::<Result<_>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// Resolves the items of a `map` step.
+fn list_items(template: &str, variables: &HashMap<String, Value>) -> Result<Vec<Value>> {
+    let value = render_value(&Value::String(template.to_string()), variables)?;
+    let text = match value {
+        Value::Array(items) => return Ok(items),
+        Value::String(text) => text,
+        other => return Ok(vec![other]),
+    };
+    if let Ok(Value::Array(items)) = serde_json::from_str(text.trim()) {
+        return Ok(items);
+    }
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| Value::String(line.to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LLMConfig;
+    use crate::mock::MockLLMProvider;
+    use crate::tools::EchoTool;
+
+    /// Tests prompt, tool, map and branch steps chained through templates.
+    #[tokio::test]
+    async fn test_pipeline_runs_steps() {
+        let yaml = r##"
+name: demo
+inputs:
+  topic: rust
+steps:
+  - id: ideas
+    type: prompt
+    prompt: "List ideas about {{topic}}"
+  - id: echoed
+    type: map
+    items: "{{ideas}}"
+    step:
+      type: tool
+      tool: echo
+      args:
+        message: "#{{index}} {{item}}"
+  - id: verdict
+    type: branch
+    condition:
+      value: "{{ideas}}"
+      contains: ownership
+    then:
+      - type: prompt
+        prompt: "Explain {{ideas.0}}"
+    else:
+      - type: prompt
+        prompt: "unreachable"
+output: "{{verdict}} / {{echoed}}"
+"##;
+        let llm = LLMClient::from_provider(
+            MockLLMProvider::new()
+                .with_response("[\"ownership\", \"lifetimes\"]")
+                .with_response("Ownership means one owner."),
+            LLMConfig::default(),
+        );
+        let pipeline = Pipeline::from_yaml(yaml)
+            .unwrap()
+            .with_llm(llm)
+            .with_tool(Box::new(EchoTool));
+        assert_eq!(pipeline.required_tools(), vec!["echo".to_string()]);
+
+        let result = pipeline.run(HashMap::new()).await.unwrap();
+        assert_eq!(
+            result.output,
+            "Ownership means one owner. / Echo: #0 ownership\n\nEcho: #1 lifetimes"
+        );
+        assert_eq!(result.variables["topic"], "rust");
+    }
+
+    /// Tests that unknown variables and missing resources are reported as errors.
+    #[tokio::test]
+    async fn test_pipeline_errors() {
+        let variables = HashMap::from([("a".to_string(), serde_json::json!({"b": [1, 2]}))]);
+        assert_eq!(render("{{ a.b.1 }}", &variables).unwrap(), "2");
+        assert!(render("{{missing}}", &variables).is_err());
+
+        let pipeline =
+            Pipeline::from_yaml("name: x\nsteps:\n  - type: prompt\n    prompt: hi\n").unwrap();
+        assert!(matches!(
+            pipeline.run(HashMap::new()).await,
+            Err(HeliosError::ConfigError(_))
+        ));
+    }
+}
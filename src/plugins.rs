@@ -0,0 +1,466 @@
+//! # Plugins Module
+//!
+//! Tools compiled to WebAssembly can be loaded at runtime, so third parties can
+//! ship tools without Helios being recompiled. Plugins run in a wasmtime sandbox
+//! without WASI: they have no access to files, the network, the environment or
+//! the clock, and each call gets a bounded amount of fuel and memory.
+//!
+//! ## Guest ABI (version 1)
+//!
+//! A plugin is a core WebAssembly module (`.wasm`, or `.wat` text) that exports:
+//!
+//! - `memory`: the module's linear memory.
+//! - `helios_alloc(len: i32) -> i32`: returns a pointer to `len` writable bytes.
+//! - `helios_manifest() -> i64`: returns the tool's manifest as UTF-8 JSON.
+//! - `helios_call(ptr: i32, len: i32) -> i64`: runs the tool with the JSON
+//!   arguments at `ptr`/`len` and returns a JSON result.
+//!
+//! Returned `i64`s pack a buffer as `(ptr << 32) | len`. The manifest looks like
+//!
+//! ```json
+//! {
+//!   "abi_version": 1,
+//!   "name": "word_count",
+//!   "description": "Counts the words in a text",
+//!   "parameters": {"text": {"type": "string", "description": "The text", "required": true}},
+//!   "permission": "read_only"
+//! }
+//! ```
+//!
+//! and results are `{"success": true, "output": "..."}`. A plugin may import
+//! `helios.log(ptr: i32, len: i32)` to write a message to the host's log.
+//!
+//! Every call runs in a fresh instance, so plugins keep no state between calls.
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{Tool, ToolParameter, ToolPermission, ToolResult};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use wasmtime::{Caller, Engine, Instance, Linker, Memory, Module, Store, StoreLimits};
+
+/// The guest ABI version this host implements.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Resource limits applied to every plugin call.
+#[derive(Debug, Clone, Copy)]
+pub struct PluginLimits {
+    /// Fuel per call, roughly the number of WebAssembly instructions executed.
+    pub fuel: u64,
+    /// The most linear memory a plugin may grow to, in bytes.
+    pub max_memory_bytes: usize,
+    /// The largest result a plugin may return, in bytes.
+    pub max_output_bytes: usize,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 1_000_000_000,
+            max_memory_bytes: 64 * 1024 * 1024,
+            max_output_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// Compiles and loads WebAssembly tool plugins.
+#[derive(Clone)]
+pub struct WasmPluginHost {
+    engine: Engine,
+    limits: PluginLimits,
+}
+
+impl WasmPluginHost {
+    /// Creates a host with the default limits.
+    pub fn new() -> Result<Self> {
+        Self::with_limits(PluginLimits::default())
+    }
+
+    /// Creates a host whose plugins run with `limits`.
+    pub fn with_limits(limits: PluginLimits) -> Result<Self> {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)
+            .map_err(|e| HeliosError::ToolError(format!("Failed to start plugin host: {}", e)))?;
+        Ok(Self { engine, limits })
+    }
+
+    /// Loads the plugin at `path` and reads its manifest.
+    pub fn load(&self, path: impl AsRef<Path>) -> Result<WasmTool> {
+        let path = path.as_ref();
+        let module = Module::from_file(&self.engine, path).map_err(|e| {
+            HeliosError::ToolError(format!(
+                "Failed to compile plugin '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let manifest = run_guest(&self.engine, &module, &self.limits, None).map_err(|e| {
+            HeliosError::ToolError(format!(
+                "Failed to read the manifest of plugin '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let manifest: PluginManifest = serde_json::from_slice(&manifest).map_err(|e| {
+            HeliosError::ToolError(format!(
+                "Invalid manifest in plugin '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        if manifest.abi_version != PLUGIN_ABI_VERSION {
+            return Err(HeliosError::ToolError(format!(
+                "Plugin '{}' targets ABI version {}, but this host implements version {}",
+                path.display(),
+                manifest.abi_version,
+                PLUGIN_ABI_VERSION
+            )));
+        }
+
+        Ok(WasmTool {
+            name: manifest.name,
+            description: manifest.description,
+            parameters: manifest
+                .parameters
+                .into_iter()
+                .map(|(name, param)| {
+                    (
+                        name,
+                        ToolParameter {
+                            param_type: param.param_type,
+                            description: param.description,
+                            required: Some(param.required),
+                        },
+                    )
+                })
+                .collect(),
+            permission: manifest.permission,
+            path: path.to_path_buf(),
+            engine: self.engine.clone(),
+            module,
+            limits: self.limits,
+        })
+    }
+
+    /// Loads every `.wasm` and `.wat` plugin in `dir`, in file name order.
+    pub fn load_dir(&self, dir: impl AsRef<Path>) -> Result<Vec<WasmTool>> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir).map_err(|e| {
+            HeliosError::ConfigError(format!(
+                "Failed to read plugin directory '{}': {}",
+                dir.display(),
+                e
+            ))
+        })?;
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && matches!(
+                        path.extension().and_then(|ext| ext.to_str()),
+                        Some("wasm" | "wat")
+                    )
+            })
+            .collect();
+        paths.sort();
+
+        let tools = paths
+            .iter()
+            .map(|path| self.load(path))
+            .collect::<Result<Vec<_>>>()?;
+        tracing::info!("Loaded {} plugin(s) from {}", tools.len(), dir.display());
+        Ok(tools)
+    }
+}
+
+/// Loads every plugin in `dir` with the default limits.
+pub fn load_plugins(dir: impl AsRef<Path>) -> Result<Vec<WasmTool>> {
+    WasmPluginHost::new()?.load_dir(dir)
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginManifest {
+    #[serde(default = "default_abi_version")]
+    abi_version: u32,
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    parameters: HashMap<String, ManifestParameter>,
+    #[serde(default)]
+    permission: ToolPermission,
+}
+
+fn default_abi_version() -> u32 {
+    PLUGIN_ABI_VERSION
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestParameter {
+    #[serde(rename = "type", default = "default_param_type")]
+    param_type: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    required: bool,
+}
+
+fn default_param_type() -> String {
+    "string".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginResult {
+    #[serde(default = "default_success")]
+    success: bool,
+    #[serde(default)]
+    output: String,
+}
+
+fn default_success() -> bool {
+    true
+}
+
+/// A tool implemented by a WebAssembly plugin.
+pub struct WasmTool {
+    name: String,
+    description: String,
+    parameters: HashMap<String, ToolParameter>,
+    permission: ToolPermission,
+    path: PathBuf,
+    engine: Engine,
+    module: Module,
+    limits: PluginLimits,
+}
+
+impl WasmTool {
+    /// Loads the plugin at `path` with the default limits.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        WasmPluginHost::new()?.load(path)
+    }
+
+    /// Returns the file the plugin was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl Tool for WasmTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        self.parameters.clone()
+    }
+
+    fn permission(&self) -> ToolPermission {
+        self.permission
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let input = serde_json::to_vec(&args)?;
+        let engine = self.engine.clone();
+        let module = self.module.clone();
+        let limits = self.limits;
+        let output =
+            tokio::task::spawn_blocking(move || run_guest(&engine, &module, &limits, Some(&input)))
+                .await
+                .map_err(|e| HeliosError::ToolError(format!("Plugin task failed: {}", e)))?;
+
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                return Ok(ToolResult::error(format!(
+                    "Plugin '{}' failed: {}",
+                    self.name, e
+                )))
+            }
+        };
+        match serde_json::from_slice::<PluginResult>(&output) {
+            Ok(result) if result.success => Ok(ToolResult::success(result.output)),
+            Ok(result) => Ok(ToolResult::error(result.output)),
+            Err(e) => Ok(ToolResult::error(format!(
+                "Plugin '{}' returned an invalid result: {}",
+                self.name, e
+            ))),
+        }
+    }
+}
+
+/// Instantiates `module` and calls `helios_call` with `input`, or `helios_manifest`
+/// without it, returning the bytes the guest hands back.
+fn run_guest(
+    engine: &Engine,
+    module: &Module,
+    limits: &PluginLimits,
+    input: Option<&[u8]>,
+) -> wasmtime::Result<Vec<u8>> {
+    let store_limits = wasmtime::StoreLimitsBuilder::new()
+        .memory_size(limits.max_memory_bytes)
+        .instances(1)
+        .build();
+    let mut store = Store::new(engine, store_limits);
+    store.limiter(|limits| limits);
+    store.set_fuel(limits.fuel)?;
+
+    let mut linker: Linker<StoreLimits> = Linker::new(engine);
+    linker.func_wrap(
+        "helios",
+        "log",
+        |mut caller: Caller<'_, StoreLimits>, ptr: i32, len: i32| {
+            if let Some(memory) = caller
+                .get_export("memory")
+                .and_then(|export| export.into_memory())
+            {
+                if let Ok(bytes) = read_bytes(&memory, &caller, ptr as u32, len as u32, 64 * 1024) {
+                    tracing::info!(target: "helios_plugin", "{}", String::from_utf8_lossy(&bytes));
+                }
+            }
+        },
+    )?;
+    let instance: Instance = linker.instantiate(&mut store, module)?;
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .ok_or_else(|| wasmtime::Error::msg("the plugin does not export 'memory'"))?;
+
+    let packed = match input {
+        None => instance
+            .get_typed_func::<(), i64>(&mut store, "helios_manifest")?
+            .call(&mut store, ())?,
+        Some(input) => {
+            let len = i32::try_from(input.len())
+                .map_err(|_| wasmtime::Error::msg("the arguments are too large"))?;
+            let ptr = instance
+                .get_typed_func::<i32, i32>(&mut store, "helios_alloc")?
+                .call(&mut store, len)?;
+            memory.write(&mut store, ptr as u32 as usize, input)?;
+            instance
+                .get_typed_func::<(i32, i32), i64>(&mut store, "helios_call")?
+                .call(&mut store, (ptr, len))?
+        }
+    };
+
+    let packed = packed as u64;
+    read_bytes(
+        &memory,
+        &store,
+        (packed >> 32) as u32,
+        packed as u32,
+        limits.max_output_bytes,
+    )
+}
+
+/// Copies `len` bytes at `ptr` out of a guest's memory.
+fn read_bytes(
+    memory: &Memory,
+    store: impl wasmtime::AsContext,
+    ptr: u32,
+    len: u32,
+    max_len: usize,
+) -> wasmtime::Result<Vec<u8>> {
+    let len = len as usize;
+    if len > max_len {
+        return Err(wasmtime::Error::msg(format!(
+            "the plugin returned {} bytes, more than the limit of {}",
+            len, max_len
+        )));
+    }
+    let mut buffer = vec![0; len];
+    memory.read(&store, ptr as usize, &mut buffer)?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A plugin that echoes its arguments, or loops forever on `{"spin":1}`.
+    const ECHO_PLUGIN: &str = r#"
+        (module
+          (import "helios" "log" (func $log (param i32 i32)))
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (data (i32.const 0) "{\"name\":\"echo\",\"description\":\"Echoes its input\",\"parameters\":{\"text\":{\"type\":\"string\",\"description\":\"Text\",\"required\":true}},\"permission\":\"read_only\"}")
+          (func (export "helios_alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "helios_manifest") (result i64)
+            (i64.const 150))
+          (func (export "helios_call") (param $ptr i32) (param $len i32) (result i64)
+            (call $log (local.get $ptr) (local.get $len))
+            ;; {"spin":1} starts with {"s
+            (if (i32.eq (i32.load8_u (i32.add (local.get $ptr) (i32.const 2))) (i32.const 115))
+              (then (loop $forever (br $forever))))
+            ;; Return the raw arguments, which are not a valid result object
+            ;; unless they look like one.
+            (i64.or
+              (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+              (i64.extend_i32_u (local.get $len)))))
+    "#;
+
+    fn write_plugin(dir: &Path) -> PathBuf {
+        let path = dir.join("echo.wat");
+        std::fs::write(&path, ECHO_PLUGIN).unwrap();
+        path
+    }
+
+    /// Tests loading a plugin directory and running a plugin tool.
+    #[tokio::test]
+    async fn test_wasm_plugin_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        write_plugin(dir.path());
+        std::fs::write(dir.path().join("README.md"), "not a plugin").unwrap();
+
+        let tools = load_plugins(dir.path()).unwrap();
+        assert_eq!(tools.len(), 1);
+        let tool = &tools[0];
+        assert_eq!(tool.name(), "echo");
+        assert_eq!(tool.permission(), ToolPermission::ReadOnly);
+        assert_eq!(tool.parameters()["text"].required, Some(true));
+
+        let result = tool
+            .execute(serde_json::json!({"output": "hi", "success": true}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, "hi");
+
+        let result = tool
+            .execute(serde_json::json!({"output": "nope", "success": false}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.output, "nope");
+
+        let result = tool.execute(serde_json::json!([1])).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("invalid result"));
+    }
+
+    /// Tests that a plugin that never returns runs out of fuel.
+    #[tokio::test]
+    async fn test_wasm_plugin_fuel_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let host = WasmPluginHost::with_limits(PluginLimits {
+            fuel: 1_000_000,
+            ..PluginLimits::default()
+        })
+        .unwrap();
+        let tool = host.load(write_plugin(dir.path())).unwrap();
+
+        let result = tool.execute(serde_json::json!({"spin": 1})).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.starts_with("Plugin 'echo' failed"));
+    }
+}
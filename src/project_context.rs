@@ -0,0 +1,446 @@
+//! # Project Context Module
+//!
+//! This module scans a workspace or repository and produces a compact overview of
+//! it (file tree, detected languages, build manifests) that can be injected into an
+//! agent's system prompt. It respects `.gitignore`/`.ignore` files and can optionally
+//! embed the project's text files into a `RAGSystem` so agents can retrieve code on demand.
+//!
+//! This is the foundation for code-assistant style agents that need to know what the
+//! project they are working in looks like before they start calling file tools.
+
+use crate::error::{HeliosError, Result};
+use crate::rag::RAGSystem;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Well-known build or package manifests used to describe a project.
+const KNOWN_MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.py",
+    "requirements.txt",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "CMakeLists.txt",
+    "Makefile",
+    "Gemfile",
+    "composer.json",
+];
+
+/// A single file discovered while scanning a project.
+#[derive(Debug, Clone)]
+pub struct ProjectFile {
+    /// Path of the file relative to the project root.
+    pub path: PathBuf,
+    /// Size of the file in bytes.
+    pub size: u64,
+}
+
+/// A scanned snapshot of a project that can be rendered into a prompt.
+#[derive(Debug, Clone)]
+pub struct ProjectContext {
+    root: PathBuf,
+    files: Vec<ProjectFile>,
+    manifests: Vec<String>,
+    truncated: bool,
+    max_tree_entries: usize,
+    embedded_files: usize,
+}
+
+impl ProjectContext {
+    /// Returns a new `ProjectContextBuilder` for scanning the given directory.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use helios_engine::ProjectContext;
+    /// # async fn example() -> helios_engine::Result<()> {
+    /// let context = ProjectContext::builder(".").max_files(500).build().await?;
+    /// println!("{}", context.overview());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder(root: impl Into<PathBuf>) -> ProjectContextBuilder {
+        ProjectContextBuilder::new(root)
+    }
+
+    /// Returns the root directory of the project.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the files discovered during the scan.
+    pub fn files(&self) -> &[ProjectFile] {
+        &self.files
+    }
+
+    /// Returns the build manifests found at the project root.
+    pub fn manifests(&self) -> &[String] {
+        &self.manifests
+    }
+
+    /// Returns true if the scan stopped early because the file limit was reached.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Returns the number of files that were embedded into the RAG system.
+    pub fn embedded_files(&self) -> usize {
+        self.embedded_files
+    }
+
+    /// Returns file counts per extension, most common first.
+    pub fn languages(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for file in &self.files {
+            if let Some(ext) = file.path.extension().and_then(|e| e.to_str()) {
+                *counts.entry(ext.to_lowercase()).or_insert(0) += 1;
+            }
+        }
+
+        let mut languages: Vec<(String, usize)> = counts.into_iter().collect();
+        languages.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        languages
+    }
+
+    /// Renders the discovered files as an indented tree, capped at the configured number of entries.
+    pub fn file_tree(&self) -> String {
+        // Build a nested directory map so directories are listed before their contents
+        #[derive(Default)]
+        struct Node {
+            children: BTreeMap<String, Node>,
+        }
+
+        let mut tree = Node::default();
+        for file in &self.files {
+            let mut node = &mut tree;
+            for component in file.path.components() {
+                let name = component.as_os_str().to_string_lossy().to_string();
+                node = node.children.entry(name).or_default();
+            }
+        }
+
+        fn render(node: &Node, depth: usize, lines: &mut Vec<String>) {
+            for (name, child) in &node.children {
+                let indent = "  ".repeat(depth);
+                if child.children.is_empty() {
+                    lines.push(format!("{}{}", indent, name));
+                } else {
+                    lines.push(format!("{}{}/", indent, name));
+                    render(child, depth + 1, lines);
+                }
+            }
+        }
+
+        let mut lines = Vec::new();
+        render(&tree, 0, &mut lines);
+
+        let total = lines.len();
+        if total > self.max_tree_entries {
+            lines.truncate(self.max_tree_entries);
+            lines.push(format!(
+                "... ({} more entries)",
+                total - self.max_tree_entries
+            ));
+        }
+
+        lines.join("\n")
+    }
+
+    /// Returns a compact, prompt-ready overview of the project.
+    pub fn overview(&self) -> String {
+        let name = self
+            .root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.root.display().to_string());
+
+        let mut overview = String::new();
+        overview.push_str(&format!("## Project: {}\n", name));
+        overview.push_str(&format!("Root: {}\n", self.root.display()));
+        overview.push_str(&format!(
+            "Files: {}{}\n",
+            self.files.len(),
+            if self.truncated { " (truncated)" } else { "" }
+        ));
+
+        let languages = self.languages();
+        if !languages.is_empty() {
+            let summary: Vec<String> = languages
+                .iter()
+                .take(8)
+                .map(|(ext, count)| format!("{} ({})", ext, count))
+                .collect();
+            overview.push_str(&format!("File types: {}\n", summary.join(", ")));
+        }
+
+        if !self.manifests.is_empty() {
+            overview.push_str(&format!("Manifests: {}\n", self.manifests.join(", ")));
+        }
+
+        if self.embedded_files > 0 {
+            overview.push_str(&format!(
+                "{} files are indexed for semantic search.\n",
+                self.embedded_files
+            ));
+        }
+
+        overview.push_str("\n### File tree\n");
+        overview.push_str(&self.file_tree());
+        overview
+    }
+}
+
+/// A builder for scanning a project directory into a `ProjectContext`.
+pub struct ProjectContextBuilder {
+    root: PathBuf,
+    max_depth: Option<usize>,
+    max_files: usize,
+    max_tree_entries: usize,
+    include_hidden: bool,
+    rag_system: Option<Arc<RAGSystem>>,
+    embed_extensions: Vec<String>,
+    max_embed_bytes: u64,
+}
+
+impl ProjectContextBuilder {
+    /// Creates a new builder for the given root directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            max_depth: None,
+            max_files: 2000,
+            max_tree_entries: 200,
+            include_hidden: false,
+            rag_system: None,
+            embed_extensions: Vec::new(),
+            max_embed_bytes: 64 * 1024,
+        }
+    }
+
+    /// Limits how deep the directory walk descends.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Sets the maximum number of files to collect.
+    pub fn max_files(mut self, max: usize) -> Self {
+        self.max_files = max;
+        self
+    }
+
+    /// Sets the maximum number of lines rendered in the file tree.
+    pub fn max_tree_entries(mut self, max: usize) -> Self {
+        self.max_tree_entries = max;
+        self
+    }
+
+    /// Includes hidden files and directories (dotfiles) in the scan.
+    pub fn include_hidden(mut self, include: bool) -> Self {
+        self.include_hidden = include;
+        self
+    }
+
+    /// Embeds the project's text files into the given RAG system while scanning.
+    pub fn embed_into(mut self, rag_system: Arc<RAGSystem>) -> Self {
+        self.rag_system = Some(rag_system);
+        self
+    }
+
+    /// Restricts embedding to files with the given extensions (e.g. `["rs", "md"]`).
+    pub fn embed_extensions(mut self, extensions: Vec<impl Into<String>>) -> Self {
+        self.embed_extensions = extensions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Skips embedding files larger than the given number of bytes.
+    pub fn max_embed_bytes(mut self, bytes: u64) -> Self {
+        self.max_embed_bytes = bytes;
+        self
+    }
+
+    /// Scans the project and, if configured, embeds its files.
+    pub async fn build(self) -> Result<ProjectContext> {
+        let root = self.root.canonicalize().map_err(|e| {
+            HeliosError::ConfigError(format!(
+                "Invalid project root '{}': {}",
+                self.root.display(),
+                e
+            ))
+        })?;
+
+        let mut walker = ignore::WalkBuilder::new(&root);
+        walker
+            .hidden(!self.include_hidden)
+            .git_ignore(true)
+            .git_exclude(true)
+            .ignore(true)
+            .parents(true)
+            // Honour .gitignore even when the directory is not inside a git checkout
+            .require_git(false)
+            .max_depth(self.max_depth)
+            .sort_by_file_path(|a, b| a.cmp(b));
+
+        let mut files = Vec::new();
+        let mut truncated = false;
+
+        for entry in walker.build().flatten() {
+            if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+                continue;
+            }
+            if files.len() >= self.max_files {
+                truncated = true;
+                break;
+            }
+
+            let relative = entry
+                .path()
+                .strip_prefix(&root)
+                .unwrap_or(entry.path())
+                .to_path_buf();
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            files.push(ProjectFile {
+                path: relative,
+                size,
+            });
+        }
+
+        let manifests = KNOWN_MANIFESTS
+            .iter()
+            .filter(|name| root.join(name).is_file())
+            .map(|name| name.to_string())
+            .collect();
+
+        let mut context = ProjectContext {
+            root,
+            files,
+            manifests,
+            truncated,
+            max_tree_entries: self.max_tree_entries,
+            embedded_files: 0,
+        };
+
+        if let Some(rag_system) = &self.rag_system {
+            context.embedded_files = self.embed_files(&context, rag_system).await?;
+        }
+
+        Ok(context)
+    }
+
+    /// Embeds eligible files into the RAG system and returns how many were added.
+    async fn embed_files(&self, context: &ProjectContext, rag_system: &RAGSystem) -> Result<usize> {
+        let mut embedded = 0;
+
+        for file in &context.files {
+            if file.size == 0 || file.size > self.max_embed_bytes {
+                continue;
+            }
+
+            if !self.embed_extensions.is_empty() {
+                let ext = file.path.extension().and_then(|e| e.to_str()).unwrap_or("");
+                if !self
+                    .embed_extensions
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(ext))
+                {
+                    continue;
+                }
+            }
+
+            // Non-UTF-8 (binary) files are skipped rather than failing the scan
+            let content = match std::fs::read_to_string(context.root.join(&file.path)) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+
+            let path = file.path.to_string_lossy().to_string();
+            let mut metadata = HashMap::new();
+            metadata.insert("path".to_string(), serde_json::json!(path));
+            metadata.insert("source".to_string(), serde_json::json!("project_context"));
+
+            rag_system
+                .add_document(&format!("File: {}\n\n{}", path, content), Some(metadata))
+                .await?;
+            embedded += 1;
+        }
+
+        Ok(embedded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    /// Tests that scanning respects .gitignore and detects manifests.
+    #[tokio::test]
+    async fn test_project_context_respects_gitignore() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        fs::create_dir_all(dir.path().join("target")).unwrap();
+        fs::write(dir.path().join("target/output.bin"), "binary").unwrap();
+        fs::write(dir.path().join("debug.log"), "log").unwrap();
+
+        let context = ProjectContext::builder(dir.path()).build().await.unwrap();
+        let paths: Vec<String> = context
+            .files()
+            .iter()
+            .map(|f| f.path.to_string_lossy().replace('\\', "/"))
+            .collect();
+
+        assert!(paths.contains(&"src/main.rs".to_string()));
+        assert!(paths.contains(&"Cargo.toml".to_string()));
+        assert!(!paths.iter().any(|p| p.starts_with("target")));
+        assert!(!paths.iter().any(|p| p.ends_with(".log")));
+        assert_eq!(context.manifests(), &["Cargo.toml".to_string()]);
+    }
+
+    /// Tests the rendered overview and tree truncation.
+    #[tokio::test]
+    async fn test_project_context_overview() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        for i in 0..5 {
+            fs::write(dir.path().join(format!("src/file{}.rs", i)), "// code").unwrap();
+        }
+
+        let context = ProjectContext::builder(dir.path())
+            .max_tree_entries(3)
+            .build()
+            .await
+            .unwrap();
+
+        let overview = context.overview();
+        assert!(overview.contains("Files: 5"));
+        assert!(overview.contains("rs (5)"));
+        assert!(overview.contains("src/"));
+        assert!(overview.contains("more entries"));
+    }
+
+    /// Tests that the file limit marks the context as truncated.
+    #[tokio::test]
+    async fn test_project_context_max_files() {
+        let dir = tempdir().unwrap();
+        for i in 0..4 {
+            fs::write(dir.path().join(format!("{}.txt", i)), "text").unwrap();
+        }
+
+        let context = ProjectContext::builder(dir.path())
+            .max_files(2)
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(context.files().len(), 2);
+        assert!(context.is_truncated());
+    }
+}
@@ -0,0 +1,686 @@
+//! # Prompt Module
+//!
+//! A small template language for long prompts, so they can live in their own files
+//! instead of being assembled with `format!`. The syntax is a subset of Handlebars:
+//!
+//! ```text
+//! {{! Comments are dropped. }}
+//! You are {{name}}, a {{role}}.
+//! {{> safety_rules}}
+//! {{#if tools}}
+//! You can use these tools:
+//! {{#each tools}}
+//! {{@index}}. {{this.name}}: {{description}}
+//! {{/each}}
+//! {{else}}
+//! Answer from your own knowledge.
+//! {{/if}}
+//! {{#unless verbose}}Keep answers short.{{/unless}}
+//! ```
+//!
+//! - `{{path}}` inserts a variable; dotted paths reach into objects and arrays
+//!   (`user.name`, `items.0`). Strings are inserted as they are, anything else as
+//!   JSON. An unknown variable is an error rather than an empty string.
+//! - `{{#if path}}`, `{{#unless path}}` and `{{#each path}}` take an optional
+//!   `{{else}}`. `null`, `false`, `0`, `""`, `[]` and `{}` count as false.
+//!   Inside `each`, `{{this}}` is the current item, `{{@index}}` its position, and
+//!   the item's fields can be used directly.
+//! - `{{> name}}` includes a partial registered with [`PromptTemplate::partial`].
+//!   Templates loaded with [`PromptTemplate::from_file`] also find partials in
+//!   files next to them, named `name` or `name` plus the template's extension.
+//!
+//! A block tag that is alone on its line takes the whole line with it, so
+//! templates can be laid out readably without leaving blank lines in the output.
+//!
+//! [`SystemPromptBuilder`] composes a system prompt from named sections instead,
+//! so a persona, tool guidance and safety rules can be kept and reused apart.
+
+use crate::error::{HeliosError, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// How deeply partials may include each other before rendering gives up.
+const MAX_PARTIAL_DEPTH: usize = 32;
+
+/// A prompt template with its partials.
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    source: String,
+    partials: HashMap<String, String>,
+    base_dir: Option<PathBuf>,
+    extension: Option<String>,
+}
+
+impl PromptTemplate {
+    /// Creates a template from its source text.
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            partials: HashMap::new(),
+            base_dir: None,
+            extension: None,
+        }
+    }
+
+    /// Loads a template from a file.
+    ///
+    /// Partials that are not registered explicitly are looked up in the same
+    /// directory.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            HeliosError::ConfigError(format!("Failed to read '{}': {}", path.display(), e))
+        })?;
+        let mut template = Self::new(source);
+        template.base_dir = path.parent().map(Path::to_path_buf);
+        template.extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().into_owned());
+        Ok(template)
+    }
+
+    /// Registers a partial that `{{> name}}` includes.
+    pub fn partial(mut self, name: impl Into<String>, source: impl Into<String>) -> Self {
+        self.partials.insert(name.into(), source.into());
+        self
+    }
+
+    /// Returns the template's source text.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Renders the template with the fields of `vars`, typically a `HashMap`, a
+    /// `serde_json::json!` object or a struct deriving `Serialize`.
+    pub fn render(&self, vars: &impl Serialize) -> Result<String> {
+        let root = serde_json::to_value(vars)?;
+        let nodes = parse(&self.source)?;
+        let mut output = String::with_capacity(self.source.len());
+        let mut scopes = vec![Scope {
+            value: root,
+            index: None,
+        }];
+        self.render_nodes(&nodes, &mut scopes, 0, &mut output)?;
+        Ok(output)
+    }
+
+    fn render_nodes(
+        &self,
+        nodes: &[Node],
+        scopes: &mut Vec<Scope>,
+        depth: usize,
+        output: &mut String,
+    ) -> Result<()> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => output.push_str(text),
+                Node::Var(path) => {
+                    let value = lookup(path, scopes).ok_or_else(|| {
+                        HeliosError::ConfigError(format!("Unknown template variable '{}'", path))
+                    })?;
+                    output.push_str(&value_to_text(&value));
+                }
+                Node::Partial(name) => {
+                    if depth >= MAX_PARTIAL_DEPTH {
+                        return Err(HeliosError::ConfigError(format!(
+                            "Partial '{}' is nested too deeply; do partials include each other?",
+                            name
+                        )));
+                    }
+                    let source = self.partial_source(name)?;
+                    self.render_nodes(&parse(&source)?, scopes, depth + 1, output)?;
+                }
+                Node::If {
+                    path,
+                    negate,
+                    then,
+                    otherwise,
+                } => {
+                    let truthy = lookup(path, scopes).is_some_and(|value| is_truthy(&value));
+                    let branch = if truthy != *negate { then } else { otherwise };
+                    self.render_nodes(branch, scopes, depth, output)?;
+                }
+                Node::Each {
+                    path,
+                    body,
+                    otherwise,
+                } => {
+                    let items = match lookup(path, scopes) {
+                        Some(Value::Array(items)) => items,
+                        Some(value) if is_truthy(&value) => vec![value],
+                        _ => Vec::new(),
+                    };
+                    if items.is_empty() {
+                        self.render_nodes(otherwise, scopes, depth, output)?;
+                    }
+                    for (index, item) in items.into_iter().enumerate() {
+                        scopes.push(Scope {
+                            value: item,
+                            index: Some(index),
+                        });
+                        let result = self.render_nodes(body, scopes, depth, output);
+                        scopes.pop();
+                        result?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the source of a partial, registered or on disk.
+    fn partial_source(&self, name: &str) -> Result<String> {
+        if let Some(source) = self.partials.get(name) {
+            return Ok(source.clone());
+        }
+        if let Some(dir) = &self.base_dir {
+            let mut candidates = vec![dir.join(name)];
+            if let Some(extension) = &self.extension {
+                candidates.push(dir.join(format!("{}.{}", name, extension)));
+            }
+            for candidate in candidates {
+                if candidate.is_file() {
+                    let source = std::fs::read_to_string(&candidate).map_err(|e| {
+                        HeliosError::ConfigError(format!(
+                            "Failed to read '{}': {}",
+                            candidate.display(),
+                            e
+                        ))
+                    })?;
+                    // The file's final newline belongs to the line that includes it.
+                    return Ok(source.strip_suffix('\n').unwrap_or(&source).to_string());
+                }
+            }
+        }
+        Err(HeliosError::ConfigError(format!(
+            "Unknown template partial '{}'",
+            name
+        )))
+    }
+}
+
+/// Renders a template string with the fields of `vars`.
+pub fn render(template: &str, vars: &impl Serialize) -> Result<String> {
+    PromptTemplate::new(template).render(vars)
+}
+
+/// A value that variables are resolved against: the root variables, or the
+/// current item of an `each` loop.
+struct Scope {
+    value: Value,
+    index: Option<usize>,
+}
+
+/// A parsed piece of a template.
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Var(String),
+    Partial(String),
+    If {
+        path: String,
+        negate: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Each {
+        path: String,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// A tag or a run of text, before blocks are matched up.
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Var(String),
+    Partial(String),
+    Comment,
+    Open { block: String, path: String },
+    Else,
+    Close(String),
+}
+
+impl Token {
+    fn from_tag(tag: &str) -> Result<Token> {
+        let invalid =
+            || HeliosError::ConfigError(format!("Invalid template tag '{{{{{}}}}}'", tag));
+        Ok(if tag.starts_with('!') {
+            Token::Comment
+        } else if let Some(name) = tag.strip_prefix('>') {
+            Token::Partial(name.trim().to_string())
+        } else if let Some(rest) = tag.strip_prefix('#') {
+            let (block, path) = rest.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            if !matches!(block, "if" | "unless" | "each") {
+                return Err(HeliosError::ConfigError(format!(
+                    "Unknown template block '#{}'",
+                    block
+                )));
+            }
+            Token::Open {
+                block: block.to_string(),
+                path: path.trim().to_string(),
+            }
+        } else if let Some(block) = tag.strip_prefix('/') {
+            Token::Close(block.trim().to_string())
+        } else if tag == "else" {
+            Token::Else
+        } else if tag.is_empty() || tag.contains(char::is_whitespace) {
+            return Err(invalid());
+        } else {
+            Token::Var(tag.to_string())
+        })
+    }
+
+    /// Whether the tag produces no text of its own, so a line holding only this
+    /// tag can be removed.
+    fn is_standalone_kind(&self) -> bool {
+        !matches!(self, Token::Text(_) | Token::Var(_))
+    }
+}
+
+/// Splits a template into tokens, removing the lines of standalone block tags.
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut rest = source;
+    // Whether everything since the start of the current line is whitespace that
+    // has already been emitted, i.e. the line began with a removed standalone tag.
+    let mut line_clean = true;
+
+    while let Some(start) = rest.find("{{") {
+        text.push_str(&rest[..start]);
+        let inner = &rest[start + 2..];
+        let end = inner
+            .find("}}")
+            .ok_or_else(|| HeliosError::ConfigError("Unclosed '{{' in template".to_string()))?;
+        let token = Token::from_tag(inner[..end].trim())?;
+        let mut after = &inner[end + 2..];
+
+        if token.is_standalone_kind() {
+            let indent = text.len() - text.rfind('\n').map_or(0, |i| i + 1);
+            let at_line_start = text.contains('\n') || line_clean;
+            let line_end = after.find('\n');
+            let trailing = &after[..line_end.unwrap_or(after.len())];
+            if at_line_start
+                && text[text.len() - indent..].trim().is_empty()
+                && trailing.trim().is_empty()
+            {
+                text.truncate(text.len() - indent);
+                after = match (line_end, &token) {
+                    // A partial keeps the newline, which ends its last line.
+                    (Some(i), Token::Partial(_)) => &after[i..],
+                    (Some(i), _) => &after[i + 1..],
+                    (None, _) => "",
+                };
+                line_clean = !matches!(token, Token::Partial(_));
+            } else {
+                line_clean = false;
+            }
+        } else {
+            line_clean = false;
+        }
+
+        if !text.is_empty() {
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+        }
+        tokens.push(token);
+        rest = after;
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    Ok(tokens)
+}
+
+/// Parses a template into nodes with matched blocks.
+fn parse(source: &str) -> Result<Vec<Node>> {
+    let mut tokens = tokenize(source)?.into_iter();
+    match parse_nodes(&mut tokens)? {
+        (nodes, None) => Ok(nodes),
+        (_, Some(Token::Else)) => Err(HeliosError::ConfigError(
+            "'{{else}}' outside of a block in template".to_string(),
+        )),
+        (_, Some(Token::Close(block))) => Err(HeliosError::ConfigError(format!(
+            "'{{{{/{}}}}}' without a matching open tag in template",
+            block
+        ))),
+        (_, Some(_)) => unreachable!("parse_nodes only stops at else, close or the end"),
+    }
+}
+
+/// Parses nodes up to the next `else` or close tag, which is returned.
+fn parse_nodes(tokens: &mut impl Iterator<Item = Token>) -> Result<(Vec<Node>, Option<Token>)> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text)),
+            Token::Var(path) => nodes.push(Node::Var(path)),
+            Token::Partial(name) => nodes.push(Node::Partial(name)),
+            Token::Comment => {}
+            Token::Open { block, path } => {
+                let (body, end) = parse_nodes(tokens)?;
+                let otherwise = match end {
+                    Some(Token::Else) => match parse_nodes(tokens)? {
+                        (otherwise, Some(Token::Close(closed))) if closed == block => otherwise,
+                        _ => return Err(unclosed(&block)),
+                    },
+                    Some(Token::Close(closed)) if closed == block => Vec::new(),
+                    _ => return Err(unclosed(&block)),
+                };
+                nodes.push(match block.as_str() {
+                    "each" => Node::Each {
+                        path,
+                        body,
+                        otherwise,
+                    },
+                    _ => Node::If {
+                        path,
+                        negate: block == "unless",
+                        then: body,
+                        otherwise,
+                    },
+                });
+            }
+            end @ (Token::Else | Token::Close(_)) => return Ok((nodes, Some(end))),
+        }
+    }
+    Ok((nodes, None))
+}
+
+fn unclosed(block: &str) -> HeliosError {
+    HeliosError::ConfigError(format!(
+        "'{{{{#{}}}}}' is not closed with '{{{{/{}}}}}' in template",
+        block, block
+    ))
+}
+
+/// Resolves a variable path against the scopes, innermost first.
+fn lookup(path: &str, scopes: &[Scope]) -> Option<Value> {
+    let innermost = scopes.last()?;
+    if path == "@index" {
+        return scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.index)
+            .map(Value::from);
+    }
+    if path == "this" {
+        return Some(innermost.value.clone());
+    }
+    let mut parts = path.split('.');
+    let first = parts.next()?;
+    let mut value = if first == "this" {
+        &innermost.value
+    } else {
+        scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.value.get(first))?
+    };
+    for part in parts {
+        value = match value {
+            Value::Object(map) => map.get(part)?,
+            Value::Array(items) => items.get(part.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(value.clone())
+}
+
+/// Formats a value for insertion into the prompt.
+fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+/// A system prompt composed from named sections.
+///
+/// Sections appear in the order they were added, separated by blank lines.
+/// Adding a section under a name that is already used replaces its text in
+/// place, so a shared base prompt can be adjusted section by section. Empty
+/// sections are left out.
+///
+/// An agent built with [`crate::AgentBuilder::system_prompt_builder`] fills the
+/// [`SystemPromptBuilder::TOOL_GUIDANCE`] section from the usage notes of its
+/// tools, unless that section was given explicitly.
+///
+/// ```rust
+/// use helios_engine::prompt::SystemPromptBuilder;
+///
+/// let base = SystemPromptBuilder::new()
+///     .section(SystemPromptBuilder::PERSONA, "You are a support assistant.")
+///     .section(SystemPromptBuilder::SAFETY, "Never share account passwords.");
+/// let prompt = base
+///     .clone()
+///     .section(SystemPromptBuilder::PERSONA, "You are a billing assistant.")
+///     .section_before(SystemPromptBuilder::SAFETY, "policy", "Refunds take 5 days.")
+///     .build();
+/// assert_eq!(
+///     prompt,
+///     "You are a billing assistant.\n\nRefunds take 5 days.\n\nNever share account passwords."
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemPromptBuilder {
+    sections: Vec<(String, String)>,
+}
+
+impl SystemPromptBuilder {
+    /// The section describing who the assistant is.
+    pub const PERSONA: &'static str = "persona";
+    /// The section explaining how to use the tools.
+    pub const TOOL_GUIDANCE: &'static str = "tool_guidance";
+    /// The section with rules the assistant must follow.
+    pub const SAFETY: &'static str = "safety";
+    /// The section with facts about the current situation, such as the date.
+    pub const CONTEXT: &'static str = "context";
+
+    /// Creates a builder without sections.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the text of a section, appending it if there is none by that name.
+    pub fn section(mut self, name: impl Into<String>, text: impl Into<String>) -> Self {
+        let name = name.into();
+        let text = text.into();
+        match self.sections.iter_mut().find(|(n, _)| *n == name) {
+            Some(section) => section.1 = text,
+            None => self.sections.push((name, text)),
+        }
+        self
+    }
+
+    /// Sets a section and places it right before `anchor`.
+    ///
+    /// A section of the same name is moved. Without an `anchor` section, the
+    /// section goes at the end.
+    pub fn section_before(
+        self,
+        anchor: &str,
+        name: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        self.insert_near(anchor, 0, name.into(), text.into())
+    }
+
+    /// Sets a section and places it right after `anchor`.
+    ///
+    /// A section of the same name is moved. Without an `anchor` section, the
+    /// section goes at the end.
+    pub fn section_after(
+        self,
+        anchor: &str,
+        name: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        self.insert_near(anchor, 1, name.into(), text.into())
+    }
+
+    /// Removes a section.
+    pub fn remove(mut self, name: &str) -> Self {
+        self.sections.retain(|(n, _)| n != name);
+        self
+    }
+
+    /// Returns the text of a section.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.sections
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, text)| text.as_str())
+    }
+
+    /// Returns the names of the sections, in order.
+    pub fn names(&self) -> Vec<&str> {
+        self.sections
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Joins the non-empty sections into the system prompt.
+    pub fn build(&self) -> String {
+        self.sections
+            .iter()
+            .map(|(_, text)| text.trim())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn insert_near(mut self, anchor: &str, offset: usize, name: String, text: String) -> Self {
+        self.sections.retain(|(n, _)| *n != name);
+        let index = self
+            .sections
+            .iter()
+            .position(|(n, _)| n == anchor)
+            .map_or(self.sections.len(), |i| i + offset);
+        self.sections.insert(index, (name, text));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Tests section ordering, replacement and removal.
+    #[test]
+    fn test_system_prompt_builder() {
+        let builder = SystemPromptBuilder::new()
+            .section(SystemPromptBuilder::PERSONA, "You are terse.")
+            .section(SystemPromptBuilder::SAFETY, "Stay polite.")
+            .section_after(SystemPromptBuilder::PERSONA, "style", "Use lists.")
+            .section_before("missing", "footer", "Bye.")
+            .section(SystemPromptBuilder::CONTEXT, "  ");
+        assert_eq!(
+            builder.names(),
+            vec!["persona", "style", "safety", "footer", "context"]
+        );
+        assert_eq!(
+            builder.build(),
+            "You are terse.\n\nUse lists.\n\nStay polite.\n\nBye."
+        );
+
+        let builder = builder
+            .section("style", "Use prose.")
+            .section_before(SystemPromptBuilder::PERSONA, "footer", "Hi.")
+            .remove(SystemPromptBuilder::SAFETY);
+        assert_eq!(builder.get("style"), Some("Use prose."));
+        assert_eq!(builder.build(), "Hi.\n\nYou are terse.\n\nUse prose.");
+    }
+
+    /// Tests variables, conditionals, loops and standalone block lines.
+    #[test]
+    fn test_render_blocks() {
+        let template = "\
+You are {{name}}.
+{{! internal note }}
+{{#if tools}}
+Tools:
+{{#each tools}}
+{{@index}}. {{this.name}}: {{description}}
+{{/each}}
+{{else}}
+No tools.
+{{/if}}
+{{#unless verbose}}Be brief.{{/unless}}";
+        let vars = json!({
+            "name": "Helios",
+            "verbose": false,
+            "tools": [
+                {"name": "calc", "description": "does math"},
+                {"name": "web", "description": "searches"}
+            ]
+        });
+        assert_eq!(
+            render(template, &vars).unwrap(),
+            "You are Helios.\nTools:\n0. calc: does math\n1. web: searches\nBe brief."
+        );
+
+        let vars = json!({"name": "Helios", "tools": [], "verbose": true});
+        assert_eq!(
+            render(template, &vars).unwrap(),
+            "You are Helios.\nNo tools.\n"
+        );
+    }
+
+    /// Tests that partials are included from the template and from disk.
+    #[test]
+    fn test_partials() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("rules.md"), "Never guess {{topic}}.\n").unwrap();
+        std::fs::write(
+            dir.path().join("system.md"),
+            "{{> intro}}\n  {{> rules}}\nDone.\n",
+        )
+        .unwrap();
+
+        let template = PromptTemplate::from_file(dir.path().join("system.md"))
+            .unwrap()
+            .partial("intro", "Hi, I help with {{topic}}.");
+        let mut vars = HashMap::new();
+        vars.insert("topic", "taxes");
+        assert_eq!(
+            template.render(&vars).unwrap(),
+            "Hi, I help with taxes.\nNever guess taxes.\nDone.\n"
+        );
+
+        let looping = PromptTemplate::new("{{> a}}").partial("a", "{{> a}}");
+        assert!(looping.render(&vars).is_err());
+    }
+
+    /// Tests that mistakes in a template are reported.
+    #[test]
+    fn test_template_errors() {
+        let vars = json!({"a": 1});
+        assert!(render("{{missing}}", &vars).is_err());
+        assert!(render("{{#if a}}open", &vars).is_err());
+        assert!(render("{{#if a}}x{{/each}}", &vars).is_err());
+        assert!(render("{{/if}}", &vars).is_err());
+        assert!(render("{{#with a}}{{/with}}", &vars).is_err());
+        assert!(render("{{a", &vars).is_err());
+        assert_eq!(render("{{#if missing}}x{{/if}}", &vars).unwrap(), "");
+    }
+}
@@ -1,18 +1,33 @@
 //! # RAG (Retrieval-Augmented Generation) Module
 //!
 //! This module provides a flexible RAG system with:
-//! - Multiple vector store backends (in-memory, Qdrant)
+//! - Multiple vector store backends (in-memory, SQLite, Qdrant, PostgreSQL/pgvector)
 //! - Embedding generation (OpenAI API, local models)
+//! - Document loaders for text, Markdown, HTML, PDF and DOCX files
 //! - Document chunking and preprocessing
-//! - Semantic search and retrieval
-//! - Reranking capabilities
+//! - Semantic search and retrieval, optionally fused with BM25 keyword search
+//! - Metadata filters, pushed down to Qdrant and pgvector
+//! - Reranking with an LLM or a hosted cross-encoder
 
+use crate::chunking::Chunker;
 use crate::error::{HeliosError, Result};
+use crate::footprint::{vector_entry_bytes, MemoryFootprint, MemoryStats};
 use async_trait::async_trait;
+use filter::MetadataFilter;
+use hybrid::{Bm25Index, HybridSearchConfig};
+use incremental::{IndexManifest, IndexedDocument, IngestReport, ManifestEntry, UpsertOutcome};
+use loaders::{DirectoryLoader, LoadedDocument};
 use reqwest::Client;
+use rerank::{RerankConfig, Reranker};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use uuid::Uuid;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub mod filter;
+pub mod hybrid;
+pub mod incremental;
+pub mod loaders;
+pub mod rerank;
 
 // ============================================================================
 // Core Types and Traits
@@ -44,6 +59,17 @@ pub struct SearchResult {
     pub metadata: Option<HashMap<String, serde_json::Value>>,
 }
 
+impl SearchResult {
+    /// Check whether the result's metadata satisfies `filter`
+    ///
+    /// Results without metadata match nothing.
+    pub fn matches(&self, filter: &MetadataFilter) -> bool {
+        self.metadata
+            .as_ref()
+            .is_some_and(|metadata| filter.matches(metadata))
+    }
+}
+
 // ============================================================================
 // Embedding Provider Trait
 // ============================================================================
@@ -80,6 +106,27 @@ pub trait VectorStore: Send + Sync {
     /// Search for similar documents
     async fn search(&self, query_embedding: Vec<f32>, limit: usize) -> Result<Vec<SearchResult>>;
 
+    /// Search for similar documents whose metadata matches `filter`
+    ///
+    /// The default implementation searches for `FILTER_OVERFETCH` times as many
+    /// documents and filters them, so it can return fewer than `limit` results when
+    /// the filter is selective. Stores that can filter natively should override it.
+    async fn search_filtered(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<SearchResult>> {
+        let results = self
+            .search(query_embedding, limit.saturating_mul(FILTER_OVERFETCH))
+            .await?;
+        Ok(results
+            .into_iter()
+            .filter(|result| result.matches(filter))
+            .take(limit)
+            .collect())
+    }
+
     /// Delete a document by ID
     async fn delete(&self, id: &str) -> Result<()>;
 
@@ -88,8 +135,42 @@ pub trait VectorStore: Send + Sync {
 
     /// Get document count
     async fn count(&self) -> Result<usize>;
+
+    /// Rank documents by keyword relevance to `query`
+    ///
+    /// Returns `None` if the store has no keyword search of its own, in which case
+    /// `RAGSystem` falls back to an index of the documents it added itself.
+    async fn keyword_search(
+        &self,
+        _query: &str,
+        _limit: usize,
+    ) -> Result<Option<Vec<SearchResult>>> {
+        Ok(None)
+    }
+
+    /// Get the document count and the approximate memory held in this process
+    ///
+    /// Stores backed by a database or server hold their documents elsewhere, so the
+    /// default reports zero bytes.
+    async fn memory_stats(&self) -> Result<MemoryStats> {
+        Ok(MemoryStats {
+            entries: self.count().await?,
+            bytes: 0,
+        })
+    }
+
+    /// Evict the least recently retrieved documents until at most `keep` remain
+    ///
+    /// Returns the IDs of the evicted documents. Only stores that hold documents in
+    /// memory evict anything; persistent stores return an empty list.
+    async fn evict_cold(&self, _keep: usize) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
 }
 
+/// How many candidates the default `VectorStore::search_filtered` fetches per result
+pub const FILTER_OVERFETCH: usize = 10;
+
 // ============================================================================
 // OpenAI Embedding Provider
 // ============================================================================
@@ -196,6 +277,148 @@ impl EmbeddingProvider for OpenAIEmbeddings {
     }
 }
 
+// ============================================================================
+// Local Embedding Provider
+// ============================================================================
+
+/// Embedding provider that runs a GGUF embedding model locally with llama.cpp.
+///
+/// Works with embedding models such as bge-small or all-MiniLM converted to GGUF,
+/// so a `RAGSystem` can run fully offline. The model is fetched from Hugging Face
+/// the same way as `LocalLLMProvider` models, and embeddings are L2-normalized.
+///
+/// ```rust,no_run
+/// # use helios_engine::{InMemoryVectorStore, LocalConfig, LocalEmbeddings, RAGSystem};
+/// # async fn example() -> helios_engine::Result<()> {
+/// let embeddings = LocalEmbeddings::new(LocalConfig {
+///     huggingface_repo: "CompendiumLabs/bge-small-en-v1.5-gguf".to_string(),
+///     model_file: "bge-small-en-v1.5-q8_0.gguf".to_string(),
+///     context_size: 512,
+///     ..LocalConfig::default()
+/// })
+/// .await?;
+///
+/// let rag = RAGSystem::new(Box::new(embeddings), Box::new(InMemoryVectorStore::new()));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "local")]
+pub struct LocalEmbeddings {
+    model: std::sync::Arc<llama_cpp_2::model::LlamaModel>,
+    backend: std::sync::Arc<llama_cpp_2::llama_backend::LlamaBackend>,
+    context_size: usize,
+    dimension: usize,
+}
+
+#[cfg(feature = "local")]
+impl LocalEmbeddings {
+    /// Loads a local embedding model, downloading it first if it is not cached.
+    ///
+    /// The generation settings are not used; text longer than `context_size` tokens
+    /// is truncated before embedding.
+    pub async fn new(config: crate::config::LocalConfig) -> Result<Self> {
+        use crate::llm::{
+            llama_backend, model_params, restore_output, suppress_output, LocalLLMProvider,
+        };
+        use llama_cpp_2::model::LlamaModel;
+
+        let model_path = LocalLLMProvider::download_model(&config).await?;
+
+        let (stdout_backup, stderr_backup) = suppress_output();
+        let loaded = llama_backend().and_then(|backend| {
+            let model_params = model_params(&config, &backend)?;
+            LlamaModel::load_from_file(&backend, &model_path, &model_params)
+                .map(|model| (backend, model))
+                .map_err(|e| {
+                    HeliosError::LLMError(format!("Failed to load embedding model: {:?}", e))
+                })
+        });
+        restore_output(stdout_backup, stderr_backup);
+        let (backend, model) = loaded?;
+
+        let dimension = model.n_embd() as usize;
+        Ok(Self {
+            model: std::sync::Arc::new(model),
+            backend,
+            context_size: config.context_size.max(1),
+            dimension,
+        })
+    }
+}
+
+#[cfg(feature = "local")]
+#[async_trait]
+impl EmbeddingProvider for LocalEmbeddings {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        use llama_cpp_2::{
+            context::params::LlamaContextParams, llama_batch::LlamaBatch, model::AddBos,
+        };
+
+        let model = std::sync::Arc::clone(&self.model);
+        let backend = std::sync::Arc::clone(&self.backend);
+        let context_size = self.context_size;
+        let text = text.to_string();
+
+        let (stdout_backup, stderr_backup) = crate::llm::suppress_output();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut tokens = model
+                .str_to_token(&text, AddBos::Always)
+                .map_err(|e| HeliosError::ToolError(format!("Tokenization failed: {:?}", e)))?;
+            if tokens.is_empty() {
+                return Err(HeliosError::ToolError(
+                    "Cannot embed empty text".to_string(),
+                ));
+            }
+            tokens.truncate(context_size);
+
+            // Embedding models attend over the whole input at once, so the batch
+            // must hold every token.
+            let n_tokens = tokens.len() as u32;
+            let ctx_params = LlamaContextParams::default()
+                .with_n_ctx(std::num::NonZeroU32::new(context_size as u32))
+                .with_n_batch(n_tokens)
+                .with_n_ubatch(n_tokens)
+                .with_embeddings(true);
+            let mut context = model.new_context(&backend, ctx_params).map_err(|e| {
+                HeliosError::ToolError(format!("Failed to create embedding context: {:?}", e))
+            })?;
+
+            let mut batch = LlamaBatch::new(tokens.len(), 1);
+            batch.add_sequence(&tokens, 0, false).map_err(|e| {
+                HeliosError::ToolError(format!("Failed to build embedding batch: {:?}", e))
+            })?;
+            context.decode(&mut batch).map_err(|e| {
+                HeliosError::ToolError(format!("Failed to run embedding model: {:?}", e))
+            })?;
+
+            let embedding = context.embeddings_seq_ith(0).map_err(|e| {
+                HeliosError::ToolError(format!("Failed to read embedding: {:?}", e))
+            })?;
+            Ok(normalize_embedding(embedding.to_vec()))
+        })
+        .await;
+        crate::llm::restore_output(stdout_backup, stderr_backup);
+
+        result.map_err(|e| HeliosError::ToolError(format!("Embedding task failed: {}", e)))?
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+}
+
+/// Scales an embedding to unit length so dot products equal cosine similarity.
+#[cfg(feature = "local")]
+fn normalize_embedding(mut embedding: Vec<f32>) -> Vec<f32> {
+    let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in &mut embedding {
+            *x /= norm;
+        }
+    }
+    embedding
+}
+
 // ============================================================================
 // In-Memory Vector Store
 // ============================================================================
@@ -204,14 +427,17 @@ impl EmbeddingProvider for OpenAIEmbeddings {
 pub struct InMemoryVectorStore {
     documents:
         std::sync::Arc<tokio::sync::RwLock<std::collections::HashMap<String, StoredDocument>>>,
+    /// Counts adds and retrievals; documents record the tick they were last used at
+    tick: std::sync::Arc<AtomicU64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 struct StoredDocument {
     id: String,
     embedding: Vec<f32>,
     text: String,
     metadata: HashMap<String, serde_json::Value>,
+    last_used: AtomicU64,
 }
 
 impl InMemoryVectorStore {
@@ -219,7 +445,63 @@ impl InMemoryVectorStore {
     pub fn new() -> Self {
         Self {
             documents: std::sync::Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+            tick: std::sync::Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Advance the usage counter and return the new tick
+    fn next_tick(&self) -> u64 {
+        self.tick.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+impl InMemoryVectorStore {
+    /// Rank the documents matching `filter` (or all of them) by similarity
+    async fn search_matching(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        filter: Option<&MetadataFilter>,
+    ) -> Result<Vec<SearchResult>> {
+        let docs = self.documents.read().await;
+
+        if docs.is_empty() {
+            return Ok(Vec::new());
         }
+
+        // Calculate similarities for all matching documents
+        let mut results: Vec<(String, f64)> = docs
+            .iter()
+            .filter(|(_, doc)| filter.map_or(true, |filter| filter.matches(&doc.metadata)))
+            .map(|(id, doc)| {
+                let similarity = cosine_similarity(&query_embedding, &doc.embedding);
+                (id.clone(), similarity)
+            })
+            .collect();
+
+        // Sort by similarity (descending)
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        // Take top results, marking them as recently used
+        let tick = self.next_tick();
+        let top_results: Vec<SearchResult> = results
+            .into_iter()
+            .take(limit)
+            .filter_map(|(id, score)| {
+                docs.get(&id).map(|doc| {
+                    doc.last_used.store(tick, Ordering::Relaxed);
+                    (doc, score)
+                })
+            })
+            .map(|(doc, score)| SearchResult {
+                id: doc.id.clone(),
+                score,
+                text: doc.text.clone(),
+                metadata: Some(doc.metadata.clone()),
+            })
+            .collect();
+
+        Ok(top_results)
     }
 }
 
@@ -270,6 +552,7 @@ impl VectorStore for InMemoryVectorStore {
                 embedding,
                 text: text.to_string(),
                 metadata,
+                last_used: AtomicU64::new(self.next_tick()),
             },
         );
 
@@ -277,39 +560,17 @@ impl VectorStore for InMemoryVectorStore {
     }
 
     async fn search(&self, query_embedding: Vec<f32>, limit: usize) -> Result<Vec<SearchResult>> {
-        let docs = self.documents.read().await;
-
-        if docs.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        // Calculate similarities for all documents
-        let mut results: Vec<(String, f64)> = docs
-            .iter()
-            .map(|(id, doc)| {
-                let similarity = cosine_similarity(&query_embedding, &doc.embedding);
-                (id.clone(), similarity)
-            })
-            .collect();
-
-        // Sort by similarity (descending)
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
-        // Take top results
-        let top_results: Vec<SearchResult> = results
-            .into_iter()
-            .take(limit)
-            .filter_map(|(id, score)| {
-                docs.get(&id).map(|doc| SearchResult {
-                    id: doc.id.clone(),
-                    score,
-                    text: doc.text.clone(),
-                    metadata: Some(doc.metadata.clone()),
-                })
-            })
-            .collect();
+        self.search_matching(query_embedding, limit, None).await
+    }
 
-        Ok(top_results)
+    async fn search_filtered(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_matching(query_embedding, limit, Some(filter))
+            .await
     }
 
     async fn delete(&self, id: &str) -> Result<()> {
@@ -328,6 +589,45 @@ impl VectorStore for InMemoryVectorStore {
         let docs = self.documents.read().await;
         Ok(docs.len())
     }
+
+    async fn keyword_search(&self, query: &str, limit: usize) -> Result<Option<Vec<SearchResult>>> {
+        let docs = self.documents.read().await;
+        let mut index = Bm25Index::new();
+        for doc in docs.values() {
+            index.add(doc.id.clone(), doc.text.clone(), Some(doc.metadata.clone()));
+        }
+        Ok(Some(index.search(query, limit)))
+    }
+
+    async fn memory_stats(&self) -> Result<MemoryStats> {
+        let docs = self.documents.read().await;
+        Ok(MemoryStats {
+            entries: docs.len(),
+            bytes: docs
+                .values()
+                .map(|doc| vector_entry_bytes(&doc.id, &doc.embedding, &doc.text, &doc.metadata))
+                .sum(),
+        })
+    }
+
+    async fn evict_cold(&self, keep: usize) -> Result<Vec<String>> {
+        let mut docs = self.documents.write().await;
+        if docs.len() <= keep {
+            return Ok(Vec::new());
+        }
+        let mut by_use: Vec<(u64, String)> = docs
+            .values()
+            .map(|doc| (doc.last_used.load(Ordering::Relaxed), doc.id.clone()))
+            .collect();
+        by_use.sort();
+        by_use.truncate(docs.len() - keep);
+
+        let evicted: Vec<String> = by_use.into_iter().map(|(_, id)| id).collect();
+        for id in &evicted {
+            docs.remove(id);
+        }
+        Ok(evicted)
+    }
 }
 
 // ============================================================================
@@ -354,6 +654,8 @@ struct QdrantSearchRequest {
     limit: usize,
     with_payload: bool,
     with_vector: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filter: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -377,6 +679,70 @@ impl QdrantVectorStore {
             client: Client::new(),
         }
     }
+
+    /// Search the collection, optionally with a Qdrant filter object
+    async fn search_points(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        filter: Option<serde_json::Value>,
+    ) -> Result<Vec<SearchResult>> {
+        let search_url = format!(
+            "{}/collections/{}/points/search",
+            self.qdrant_url, self.collection_name
+        );
+        let search_request = QdrantSearchRequest {
+            vector: query_embedding,
+            limit,
+            with_payload: true,
+            with_vector: false,
+            filter,
+        };
+
+        let response = self
+            .client
+            .post(&search_url)
+            .json(&search_request)
+            .send()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Search failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(HeliosError::ToolError(format!(
+                "Search request failed: {}",
+                error_text
+            )));
+        }
+
+        let search_response: QdrantSearchResponse = response.json().await.map_err(|e| {
+            HeliosError::ToolError(format!("Failed to parse search response: {}", e))
+        })?;
+
+        let results: Vec<SearchResult> = search_response
+            .result
+            .into_iter()
+            .filter_map(|r| {
+                r.payload.and_then(|p| {
+                    p.get("text").and_then(|t| t.as_str()).map(|text| {
+                        let mut metadata = p.clone();
+                        metadata.remove("text");
+                        SearchResult {
+                            id: r.id,
+                            score: r.score,
+                            text: text.to_string(),
+                            metadata: Some(metadata),
+                        }
+                    })
+                })
+            })
+            .collect();
+
+        Ok(results)
+    }
 }
 
 #[async_trait]
@@ -432,7 +798,7 @@ impl VectorStore for QdrantVectorStore {
         payload.insert("text".to_string(), serde_json::json!(text));
         payload.insert(
             "timestamp".to_string(),
-            serde_json::json!(chrono::Utc::now().to_rfc3339()),
+            serde_json::json!(crate::clock::now().to_rfc3339()),
         );
 
         let point = QdrantPoint {
@@ -472,60 +838,17 @@ impl VectorStore for QdrantVectorStore {
     }
 
     async fn search(&self, query_embedding: Vec<f32>, limit: usize) -> Result<Vec<SearchResult>> {
-        let search_url = format!(
-            "{}/collections/{}/points/search",
-            self.qdrant_url, self.collection_name
-        );
-        let search_request = QdrantSearchRequest {
-            vector: query_embedding,
-            limit,
-            with_payload: true,
-            with_vector: false,
-        };
+        self.search_points(query_embedding, limit, None).await
+    }
 
-        let response = self
-            .client
-            .post(&search_url)
-            .json(&search_request)
-            .send()
+    async fn search_filtered(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_points(query_embedding, limit, Some(filter.to_qdrant()))
             .await
-            .map_err(|e| HeliosError::ToolError(format!("Search failed: {}", e)))?;
-
-        if !response.status().is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(HeliosError::ToolError(format!(
-                "Search request failed: {}",
-                error_text
-            )));
-        }
-
-        let search_response: QdrantSearchResponse = response.json().await.map_err(|e| {
-            HeliosError::ToolError(format!("Failed to parse search response: {}", e))
-        })?;
-
-        let results: Vec<SearchResult> = search_response
-            .result
-            .into_iter()
-            .filter_map(|r| {
-                r.payload.and_then(|p| {
-                    p.get("text").and_then(|t| t.as_str()).map(|text| {
-                        let mut metadata = p.clone();
-                        metadata.remove("text");
-                        SearchResult {
-                            id: r.id,
-                            score: r.score,
-                            text: text.to_string(),
-                            metadata: Some(metadata),
-                        }
-                    })
-                })
-            })
-            .collect();
-
-        Ok(results)
     }
 
     async fn delete(&self, id: &str) -> Result<()> {
@@ -613,33 +936,722 @@ impl VectorStore for QdrantVectorStore {
 }
 
 // ============================================================================
-// RAG System
+// SQLite Vector Store
 // ============================================================================
 
-/// Main RAG system that combines embedding provider and vector store
-pub struct RAGSystem {
-    embedding_provider: Box<dyn EmbeddingProvider>,
-    vector_store: Box<dyn VectorStore>,
-    initialized: std::sync::Arc<tokio::sync::RwLock<bool>>,
+/// Persistent vector store in a single SQLite file
+///
+/// Embeddings are stored as little-endian `f32` blobs and searched by brute-force
+/// cosine similarity, which is fast enough for tens of thousands of documents and
+/// needs no external service. Use Qdrant or pgvector for larger collections.
+///
+/// ```rust,no_run
+/// # use helios_engine::{OpenAIEmbeddings, RAGSystem, SqliteVectorStore};
+/// # fn example() -> helios_engine::Result<()> {
+/// let store = SqliteVectorStore::open("rag.db")?;
+/// let embeddings = OpenAIEmbeddings::new("https://api.openai.com/v1/embeddings", "sk-...");
+/// let rag = RAGSystem::new(Box::new(embeddings), Box::new(store));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "sqlite")]
+pub struct SqliteVectorStore {
+    connection: std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>,
 }
 
-impl RAGSystem {
-    /// Create a new RAG system
-    pub fn new(
-        embedding_provider: Box<dyn EmbeddingProvider>,
-        vector_store: Box<dyn VectorStore>,
-    ) -> Self {
-        Self {
-            embedding_provider,
-            vector_store,
-            initialized: std::sync::Arc::new(tokio::sync::RwLock::new(false)),
-        }
+#[cfg(feature = "sqlite")]
+impl SqliteVectorStore {
+    /// Open (or create) a vector store in the SQLite database at `path`
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let connection = rusqlite::Connection::open(path).map_err(|e| {
+            HeliosError::ToolError(format!(
+                "Failed to open SQLite database '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::from_connection(connection)
     }
 
-    /// Ensure the system is initialized
-    async fn ensure_initialized(&self) -> Result<()> {
-        let is_initialized = *self.initialized.read().await;
-        if !is_initialized {
+    /// Create a vector store in a temporary in-memory SQLite database
+    pub fn open_in_memory() -> Result<Self> {
+        let connection = rusqlite::Connection::open_in_memory().map_err(|e| {
+            HeliosError::ToolError(format!("Failed to open SQLite database: {}", e))
+        })?;
+        Self::from_connection(connection)
+    }
+
+    /// Creates the documents table and wraps the connection.
+    fn from_connection(connection: rusqlite::Connection) -> Result<Self> {
+        connection
+            .execute_batch(
+                "PRAGMA journal_mode = WAL;
+                 CREATE TABLE IF NOT EXISTS documents (
+                     id TEXT PRIMARY KEY,
+                     embedding BLOB NOT NULL,
+                     text TEXT NOT NULL,
+                     metadata TEXT NOT NULL
+                 );",
+            )
+            .map_err(|e| HeliosError::ToolError(format!("Failed to create table: {}", e)))?;
+
+        Ok(Self {
+            connection: std::sync::Arc::new(std::sync::Mutex::new(connection)),
+        })
+    }
+
+    /// Runs `f` with the connection on the blocking thread pool.
+    async fn with_connection<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&rusqlite::Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let connection = std::sync::Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || {
+            let connection = connection.lock().unwrap_or_else(|e| e.into_inner());
+            f(&connection)
+        })
+        .await
+        .map_err(|e| HeliosError::ToolError(format!("SQLite task failed: {}", e)))?
+        .map_err(|e| HeliosError::ToolError(format!("SQLite error: {}", e)))
+    }
+
+    /// Rank the documents matching `filter` (or all of them) by similarity
+    async fn search_matching(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        filter: Option<MetadataFilter>,
+    ) -> Result<Vec<SearchResult>> {
+        self.with_connection(move |connection| {
+            // Score every embedding first, then load text and metadata for the winners only.
+            // Filtering needs the metadata up front.
+            let columns = if filter.is_some() {
+                "id, embedding, metadata"
+            } else {
+                "id, embedding, NULL"
+            };
+            let mut statement =
+                connection.prepare(&format!("SELECT {} FROM documents", columns))?;
+            let mut scored: Vec<(String, f64)> = statement
+                .query_map([], |row| {
+                    let id: String = row.get(0)?;
+                    let blob: Vec<u8> = row.get(1)?;
+                    let metadata: Option<String> = row.get(2)?;
+                    Ok((id, blob, metadata))
+                })?
+                .filter_map(|row| row.ok())
+                .filter(|(_, _, metadata)| match (&filter, metadata) {
+                    (Some(filter), Some(metadata)) => serde_json::from_str(metadata)
+                        .is_ok_and(|metadata| filter.matches(&metadata)),
+                    (Some(_), None) => false,
+                    (None, _) => true,
+                })
+                .map(|(id, blob, _)| {
+                    let score = cosine_similarity(&query_embedding, &blob_to_embedding(&blob));
+                    (id, score)
+                })
+                .collect();
+
+            scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+            scored.truncate(limit);
+
+            let mut statement =
+                connection.prepare("SELECT text, metadata FROM documents WHERE id = ?1")?;
+            let mut results = Vec::with_capacity(scored.len());
+            for (id, score) in scored {
+                let (text, metadata): (String, String) =
+                    statement.query_row([&id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                results.push(SearchResult {
+                    id,
+                    score,
+                    text,
+                    metadata: serde_json::from_str(&metadata).ok(),
+                });
+            }
+
+            Ok(results)
+        })
+        .await
+    }
+}
+
+/// Encodes an embedding as a little-endian `f32` blob
+#[cfg(feature = "sqlite")]
+fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// Decodes a little-endian `f32` blob into an embedding
+#[cfg(feature = "sqlite")]
+fn blob_to_embedding(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+#[cfg(feature = "sqlite")]
+#[async_trait]
+impl VectorStore for SqliteVectorStore {
+    async fn initialize(&self, _dimension: usize) -> Result<()> {
+        // The table is created when the store is opened
+        Ok(())
+    }
+
+    async fn add(
+        &self,
+        id: &str,
+        embedding: Vec<f32>,
+        text: &str,
+        metadata: HashMap<String, serde_json::Value>,
+    ) -> Result<()> {
+        let id = id.to_string();
+        let text = text.to_string();
+        let metadata = serde_json::to_string(&metadata)?;
+        let blob = embedding_to_blob(&embedding);
+
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT OR REPLACE INTO documents (id, embedding, text, metadata) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![id, blob, text, metadata],
+            )
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn search(&self, query_embedding: Vec<f32>, limit: usize) -> Result<Vec<SearchResult>> {
+        self.search_matching(query_embedding, limit, None).await
+    }
+
+    async fn search_filtered(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_matching(query_embedding, limit, Some(filter.clone()))
+            .await
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        let id = id.to_string();
+        self.with_connection(move |connection| {
+            connection.execute("DELETE FROM documents WHERE id = ?1", [id])
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.with_connection(|connection| connection.execute("DELETE FROM documents", []))
+            .await?;
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<usize> {
+        self.with_connection(|connection| {
+            connection.query_row("SELECT COUNT(*) FROM documents", [], |row| {
+                row.get::<_, i64>(0)
+            })
+        })
+        .await
+        .map(|count| count as usize)
+    }
+
+    async fn keyword_search(&self, query: &str, limit: usize) -> Result<Option<Vec<SearchResult>>> {
+        let query = query.to_string();
+        self.with_connection(move |connection| {
+            let mut statement = connection.prepare("SELECT id, text, metadata FROM documents")?;
+            let mut index = Bm25Index::new();
+            for row in statement.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })? {
+                let (id, text, metadata) = row?;
+                index.add(id, text, serde_json::from_str(&metadata).ok());
+            }
+            Ok(Some(index.search(&query, limit)))
+        })
+        .await
+    }
+}
+
+// ============================================================================
+// pgvector Vector Store
+// ============================================================================
+
+/// PostgreSQL vector store backed by the pgvector extension
+///
+/// Documents live in a single table with `id`, `embedding`, `text` and a JSONB
+/// `metadata` column. `initialize` creates the extension, the table and an HNSW
+/// cosine index if they do not exist yet. Scores are cosine similarities.
+///
+/// ```rust,no_run
+/// # use helios_engine::{OpenAIEmbeddings, PgVectorStore, RAGSystem};
+/// # async fn example() -> helios_engine::Result<()> {
+/// let store = PgVectorStore::connect("host=localhost user=helios dbname=app", "documents").await?;
+/// let embeddings = OpenAIEmbeddings::new("https://api.openai.com/v1/embeddings", "sk-...");
+/// let rag = RAGSystem::new(Box::new(embeddings), Box::new(store));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "pgvector")]
+pub struct PgVectorStore {
+    client: tokio_postgres::Client,
+    table: String,
+}
+
+#[cfg(feature = "pgvector")]
+impl PgVectorStore {
+    /// Connect to PostgreSQL and use `table` for documents
+    ///
+    /// `connection_string` is a libpq-style string or `postgres://` URL. The
+    /// connection is made without TLS; use [`PgVectorStore::from_client`] to pass
+    /// a client configured with a TLS connector.
+    pub async fn connect(connection_string: &str, table: impl Into<String>) -> Result<Self> {
+        let (client, connection) =
+            tokio_postgres::connect(connection_string, tokio_postgres::NoTls)
+                .await
+                .map_err(|e| {
+                    HeliosError::ToolError(format!("Failed to connect to PostgreSQL: {}", e))
+                })?;
+
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::error!("PostgreSQL connection error: {}", e);
+            }
+        });
+
+        Self::from_client(client, table)
+    }
+
+    /// Use an existing client and `table` for documents
+    ///
+    /// The table name may be schema-qualified (`rag.documents`) and may only
+    /// contain letters, digits and underscores.
+    pub fn from_client(client: tokio_postgres::Client, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        let valid = !table.is_empty()
+            && table.split('.').count() <= 2
+            && table.split('.').all(|part| {
+                part.chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                    && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+        if !valid {
+            return Err(HeliosError::ConfigError(format!(
+                "Invalid pgvector table name '{}'",
+                table
+            )));
+        }
+
+        Ok(Self { client, table })
+    }
+
+    /// Search for similar documents whose metadata contains every key/value in `filter`
+    ///
+    /// The filter is matched with JSONB containment (`metadata @> filter`), so
+    /// nested objects and arrays match if they are a subset of the stored value.
+    pub async fn search_with_filter(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        filter: HashMap<String, serde_json::Value>,
+    ) -> Result<Vec<SearchResult>> {
+        let query = format!(
+            "SELECT id, text, metadata, 1 - (embedding <=> $1::text::vector) AS score \
+             FROM {} WHERE metadata @> $2 \
+             ORDER BY embedding <=> $1::text::vector LIMIT $3",
+            self.table
+        );
+        let filter = serde_json::Value::Object(filter.into_iter().collect());
+
+        let rows = self
+            .client
+            .query(
+                &query,
+                &[
+                    &pgvector_literal(&query_embedding),
+                    &filter,
+                    &(limit as i64),
+                ],
+            )
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Search failed: {}", e)))?;
+
+        Ok(rows.into_iter().map(pg_row_to_result).collect())
+    }
+
+    /// Search for similar documents, evaluating `filter` in the query
+    async fn search_matching(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        filter: Option<&MetadataFilter>,
+    ) -> Result<Vec<SearchResult>> {
+        let mut params: Vec<filter::PgParam> = vec![
+            Box::new(pgvector_literal(&query_embedding)),
+            Box::new(limit as i64),
+        ];
+        let condition = match filter {
+            Some(filter) => filter.to_sql(&mut params),
+            None => "TRUE".to_string(),
+        };
+        let query = format!(
+            "SELECT id, text, metadata, 1 - (embedding <=> $1::text::vector) AS score \
+             FROM {} WHERE {} \
+             ORDER BY embedding <=> $1::text::vector LIMIT $2",
+            self.table, condition
+        );
+        let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = params
+            .iter()
+            .map(|param| param.as_ref() as &(dyn tokio_postgres::types::ToSql + Sync))
+            .collect();
+
+        let rows = self
+            .client
+            .query(&query, &params)
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Search failed: {}", e)))?;
+
+        Ok(rows.into_iter().map(pg_row_to_result).collect())
+    }
+}
+
+/// Converts a row with `id`, `text`, `metadata` and `score` columns to a search result
+#[cfg(feature = "pgvector")]
+fn pg_row_to_result(row: tokio_postgres::Row) -> SearchResult {
+    let metadata: serde_json::Value = row.get("metadata");
+    SearchResult {
+        id: row.get("id"),
+        score: row.get("score"),
+        text: row.get("text"),
+        metadata: serde_json::from_value(metadata).ok(),
+    }
+}
+
+/// Formats an embedding as a pgvector text literal, e.g. `[0.1,0.2]`
+#[cfg(feature = "pgvector")]
+fn pgvector_literal(embedding: &[f32]) -> String {
+    let values: Vec<String> = embedding.iter().map(|v| v.to_string()).collect();
+    format!("[{}]", values.join(","))
+}
+
+#[cfg(feature = "pgvector")]
+#[async_trait]
+impl VectorStore for PgVectorStore {
+    async fn initialize(&self, dimension: usize) -> Result<()> {
+        // Indexes are created in the table's schema, so their name is unqualified.
+        let table_name = self.table.rsplit('.').next().unwrap_or(&self.table);
+        let index = format!("{}_embedding_idx", table_name);
+        let statements = format!(
+            "CREATE EXTENSION IF NOT EXISTS vector;
+             CREATE TABLE IF NOT EXISTS {table} (
+                 id TEXT PRIMARY KEY,
+                 embedding vector({dimension}) NOT NULL,
+                 text TEXT NOT NULL,
+                 metadata JSONB NOT NULL DEFAULT '{{}}'
+             );
+             CREATE INDEX IF NOT EXISTS {index} ON {table}
+                 USING hnsw (embedding vector_cosine_ops);",
+            table = self.table,
+            dimension = dimension,
+            index = index,
+        );
+
+        self.client
+            .batch_execute(&statements)
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Failed to create table: {}", e)))
+    }
+
+    async fn add(
+        &self,
+        id: &str,
+        embedding: Vec<f32>,
+        text: &str,
+        metadata: HashMap<String, serde_json::Value>,
+    ) -> Result<()> {
+        let mut metadata = metadata;
+        metadata.insert(
+            "timestamp".to_string(),
+            serde_json::json!(crate::clock::now().to_rfc3339()),
+        );
+        let metadata = serde_json::Value::Object(metadata.into_iter().collect());
+
+        let query = format!(
+            "INSERT INTO {} (id, embedding, text, metadata) VALUES ($1, $2::text::vector, $3, $4) \
+             ON CONFLICT (id) DO UPDATE SET embedding = EXCLUDED.embedding, \
+             text = EXCLUDED.text, metadata = EXCLUDED.metadata",
+            self.table
+        );
+
+        self.client
+            .execute(
+                &query,
+                &[&id, &pgvector_literal(&embedding), &text, &metadata],
+            )
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Failed to upload document: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn search(&self, query_embedding: Vec<f32>, limit: usize) -> Result<Vec<SearchResult>> {
+        self.search_matching(query_embedding, limit, None).await
+    }
+
+    async fn search_filtered(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_matching(query_embedding, limit, Some(filter))
+            .await
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        self.client
+            .execute(&format!("DELETE FROM {} WHERE id = $1", self.table), &[&id])
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Delete failed: {}", e)))?;
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.client
+            .execute(&format!("DELETE FROM {}", self.table), &[])
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Clear failed: {}", e)))?;
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<usize> {
+        let row = self
+            .client
+            .query_one(&format!("SELECT COUNT(*) FROM {}", self.table), &[])
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Count failed: {}", e)))?;
+        let count: i64 = row.get(0);
+        Ok(count as usize)
+    }
+}
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// The `[rag]` section of a config file, used by the `helios-engine rag` commands
+///
+/// ```toml
+/// [rag]
+/// qdrant_url = "http://localhost:6333"
+/// collection = "docs"
+/// embedding_model = "text-embedding-3-small"
+/// chunk_size = 800
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RagConfig {
+    /// The Qdrant server to store documents in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qdrant_url: Option<String>,
+    /// The Qdrant collection to store documents in
+    #[serde(default = "default_collection")]
+    pub collection: String,
+    /// The SQLite database file to store documents in (requires the `sqlite` feature)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sqlite_path: Option<String>,
+    /// The embedding model
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+    /// The embeddings API base URL; defaults to the `[llm]` base URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_base_url: Option<String>,
+    /// The embeddings API key; defaults to the `[llm]` API key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_api_key: Option<String>,
+    /// The chunk size in characters; 0 stores whole files
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: usize,
+    /// The overlap between chunks in characters
+    #[serde(default = "default_chunk_overlap")]
+    pub chunk_overlap: usize,
+    /// The index manifest file; defaults to one named after the store
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<String>,
+}
+
+fn default_collection() -> String {
+    "helios_documents".to_string()
+}
+
+fn default_embedding_model() -> String {
+    "text-embedding-ada-002".to_string()
+}
+
+fn default_chunk_size() -> usize {
+    1000
+}
+
+fn default_chunk_overlap() -> usize {
+    100
+}
+
+impl Default for RagConfig {
+    fn default() -> Self {
+        Self {
+            qdrant_url: None,
+            collection: default_collection(),
+            sqlite_path: None,
+            embedding_model: default_embedding_model(),
+            embedding_base_url: None,
+            embedding_api_key: None,
+            chunk_size: default_chunk_size(),
+            chunk_overlap: default_chunk_overlap(),
+            manifest: None,
+        }
+    }
+}
+
+impl RagConfig {
+    /// Read the `[rag]` section of a config file, if it has one
+    pub fn from_config_file<P: AsRef<std::path::Path>>(path: P) -> Result<Option<Self>> {
+        #[derive(Deserialize)]
+        struct RagSection {
+            #[serde(default)]
+            rag: Option<RagConfig>,
+        }
+
+        let section: RagSection = crate::config::read_config_file(path.as_ref())?.try_into()?;
+        Ok(section.rag)
+    }
+
+    /// Create the embeddings client, taking unset API settings from `llm`
+    pub fn embeddings(&self, llm: &crate::config::LLMConfig) -> OpenAIEmbeddings {
+        let base_url = self.embedding_base_url.as_deref().unwrap_or(&llm.base_url);
+        OpenAIEmbeddings::with_model(
+            format!("{}/embeddings", base_url.trim_end_matches('/')),
+            self.embedding_api_key
+                .clone()
+                .unwrap_or_else(|| llm.api_key.clone()),
+            self.embedding_model.clone(),
+        )
+    }
+}
+
+// ============================================================================
+// RAG System
+// ============================================================================
+
+/// Main RAG system that combines embedding provider and vector store
+pub struct RAGSystem {
+    embedding_provider: Box<dyn EmbeddingProvider>,
+    vector_store: Box<dyn VectorStore>,
+    chunker: Option<Box<dyn Chunker>>,
+    chunk_ids: tokio::sync::RwLock<HashMap<String, Vec<String>>>,
+    hybrid: Option<HybridSearchConfig>,
+    keyword_index: tokio::sync::RwLock<Bm25Index>,
+    reranker: Option<(Box<dyn Reranker>, RerankConfig)>,
+    manifest: tokio::sync::Mutex<IndexManifest>,
+    manifest_path: Option<std::path::PathBuf>,
+    initialized: std::sync::Arc<tokio::sync::RwLock<bool>>,
+}
+
+impl RAGSystem {
+    /// Create a new RAG system
+    pub fn new(
+        embedding_provider: Box<dyn EmbeddingProvider>,
+        vector_store: Box<dyn VectorStore>,
+    ) -> Self {
+        Self {
+            embedding_provider,
+            vector_store,
+            chunker: None,
+            chunk_ids: tokio::sync::RwLock::new(HashMap::new()),
+            hybrid: None,
+            keyword_index: tokio::sync::RwLock::new(Bm25Index::new()),
+            reranker: None,
+            manifest: tokio::sync::Mutex::new(IndexManifest::default()),
+            manifest_path: None,
+            initialized: std::sync::Arc::new(tokio::sync::RwLock::new(false)),
+        }
+    }
+
+    /// Split documents with `chunker` before embedding them
+    ///
+    /// Each chunk is stored as its own entry whose metadata carries the
+    /// `document_id` it came from plus `chunk_index` and `chunk_count`.
+    pub fn with_chunker(mut self, chunker: impl Chunker + 'static) -> Self {
+        self.chunker = Some(Box::new(chunker));
+        self
+    }
+
+    /// Combine vector search with BM25 keyword search
+    ///
+    /// `search` then merges both rankings with reciprocal rank fusion, which helps
+    /// queries for exact identifiers that embeddings tend to blur. Stores without
+    /// their own keyword search are backed by an in-memory index of the documents
+    /// added through this system.
+    pub fn with_hybrid_search(mut self, config: HybridSearchConfig) -> Self {
+        self.hybrid = Some(config);
+        self
+    }
+
+    /// Rerank search results before returning them
+    ///
+    /// `search` fetches `config.top_k_in` candidates (or the requested limit, if
+    /// larger), has the reranker score them against the query and returns at most
+    /// `config.top_k_out` of them.
+    pub fn with_reranker(
+        mut self,
+        reranker: impl Reranker + 'static,
+        config: RerankConfig,
+    ) -> Self {
+        self.reranker = Some((Box::new(reranker), config));
+        self
+    }
+
+    /// Keep the content hashes used by incremental indexing in a JSON file
+    ///
+    /// The file is read now if it exists and rewritten whenever `upsert_document`,
+    /// `add_loaded_documents` or `ingest` change the index. Use one manifest per
+    /// vector store collection.
+    pub fn with_index_manifest(mut self, path: impl Into<std::path::PathBuf>) -> Result<Self> {
+        let path = path.into();
+        self.manifest = tokio::sync::Mutex::new(IndexManifest::load(&path)?);
+        self.manifest_path = Some(path);
+        Ok(self)
+    }
+
+    /// Embed one entry and write it to the vector store and keyword index
+    async fn store_entry(
+        &self,
+        id: &str,
+        text: &str,
+        metadata: HashMap<String, serde_json::Value>,
+    ) -> Result<()> {
+        let embedding = self.embedding_provider.embed(text).await?;
+        if self.hybrid.is_some() {
+            self.keyword_index
+                .write()
+                .await
+                .add(id, text, Some(metadata.clone()));
+        }
+        self.vector_store.add(id, embedding, text, metadata).await
+    }
+
+    /// Ensure the system is initialized
+    async fn ensure_initialized(&self) -> Result<()> {
+        let is_initialized = *self.initialized.read().await;
+        if !is_initialized {
             let mut init_guard = self.initialized.write().await;
             if !*init_guard {
                 let dimension = self.embedding_provider.dimension();
@@ -651,47 +1663,739 @@ impl RAGSystem {
     }
 
     /// Add a document to the RAG system
+    ///
+    /// With a chunker configured, a document that splits into several chunks is
+    /// stored as one entry per chunk. The returned ID identifies the whole document
+    /// and can be passed to `delete_document`.
     pub async fn add_document(
         &self,
         text: &str,
         metadata: Option<HashMap<String, serde_json::Value>>,
     ) -> Result<String> {
-        self.ensure_initialized().await?;
+        Ok(self.add_entries(text, metadata).await?.0)
+    }
 
-        let id = Uuid::new_v4().to_string();
-        let embedding = self.embedding_provider.embed(text).await?;
+    /// Add a document, returning its ID and the IDs of its vector store entries
+    async fn add_entries(
+        &self,
+        text: &str,
+        metadata: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<(String, Vec<String>)> {
+        self.ensure_initialized().await?;
 
+        let id = crate::clock::new_id().to_string();
         let mut meta = metadata.unwrap_or_default();
         meta.insert(
             "timestamp".to_string(),
-            serde_json::json!(chrono::Utc::now().to_rfc3339()),
+            serde_json::json!(crate::clock::now().to_rfc3339()),
+        );
+
+        let chunks = match &self.chunker {
+            Some(chunker) => chunker.chunk(text),
+            None => Vec::new(),
+        };
+        if chunks.len() <= 1 {
+            let text = chunks.first().map_or(text, String::as_str);
+            self.store_entry(&id, text, meta).await?;
+            return Ok((id.clone(), vec![id]));
+        }
+
+        let chunk_count = chunks.len();
+        let mut ids = Vec::with_capacity(chunk_count);
+        for (index, chunk) in chunks.iter().enumerate() {
+            let chunk_id = crate::clock::new_id().to_string();
+
+            let mut chunk_meta = meta.clone();
+            chunk_meta.insert("document_id".to_string(), serde_json::json!(id));
+            chunk_meta.insert("chunk_index".to_string(), serde_json::json!(index));
+            chunk_meta.insert("chunk_count".to_string(), serde_json::json!(chunk_count));
+
+            self.store_entry(&chunk_id, chunk, chunk_meta).await?;
+            ids.push(chunk_id);
+        }
+        self.chunk_ids.write().await.insert(id.clone(), ids.clone());
+
+        Ok((id, ids))
+    }
+
+    /// Add or replace the document stored under `key`, unless its content is unchanged
+    ///
+    /// The key identifies the document across runs, e.g. its file path. A hash of
+    /// the text and metadata is stored with it (and as `content_hash` in the
+    /// metadata); when the hash matches, nothing is embedded. When it differs, the
+    /// old entries are deleted and the document is added again.
+    pub async fn upsert_document(
+        &self,
+        key: &str,
+        text: &str,
+        metadata: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<UpsertOutcome> {
+        let outcome = self.upsert_entry(key, text, metadata).await?;
+        if !matches!(outcome, UpsertOutcome::Unchanged(_)) {
+            self.save_manifest().await?;
+        }
+        Ok(outcome)
+    }
+
+    /// `upsert_document` without saving the manifest
+    async fn upsert_entry(
+        &self,
+        key: &str,
+        text: &str,
+        metadata: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<UpsertOutcome> {
+        let mut metadata = metadata.unwrap_or_default();
+        let hash = incremental::content_hash(text, &metadata);
+
+        let mut manifest = self.manifest.lock().await;
+        let previous = manifest.documents.get(key).cloned();
+        if let Some(entry) = &previous {
+            if entry.content_hash == hash {
+                return Ok(UpsertOutcome::Unchanged(entry.document_id.clone()));
+            }
+            self.delete_entries(&entry.document_id, &entry.entry_ids)
+                .await?;
+        }
+
+        let source = metadata
+            .get("source")
+            .and_then(|source| source.as_str())
+            .map(str::to_string);
+        metadata.insert("content_hash".to_string(), serde_json::json!(hash));
+        let (id, entry_ids) = self.add_entries(text, Some(metadata)).await?;
+        manifest.documents.insert(
+            key.to_string(),
+            ManifestEntry {
+                document_id: id.clone(),
+                content_hash: hash,
+                entry_ids,
+                source,
+            },
         );
 
-        self.vector_store.add(&id, embedding, text, meta).await?;
+        Ok(match previous {
+            Some(_) => UpsertOutcome::Updated(id),
+            None => UpsertOutcome::Added(id),
+        })
+    }
+
+    /// Write the manifest to its file, if it has one
+    async fn save_manifest(&self) -> Result<()> {
+        match &self.manifest_path {
+            Some(path) => self.manifest.lock().await.save(path).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Add documents produced by a document loader, returning their IDs
+    ///
+    /// Documents with a `source` are upserted under their source (and page), so
+    /// loading the same unchanged files again embeds nothing.
+    pub async fn add_loaded_documents(
+        &self,
+        documents: Vec<LoadedDocument>,
+    ) -> Result<Vec<String>> {
+        let report = self.upsert_loaded(documents).await?;
+        self.save_manifest().await?;
+        Ok(report.ids())
+    }
+
+    /// Upsert loaded documents, returning what happened to each
+    async fn upsert_loaded(&self, documents: Vec<LoadedDocument>) -> Result<IngestReport> {
+        let mut report = IngestReport::default();
+        for document in documents {
+            match incremental::document_key(&document.metadata) {
+                Some(key) => report.record(
+                    self.upsert_entry(&key, &document.text, Some(document.metadata))
+                        .await?,
+                ),
+                None => report.added.push(
+                    self.add_document(&document.text, Some(document.metadata))
+                        .await?,
+                ),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Load a file or directory with the default loaders and bring the index up to date
+    ///
+    /// New files are added, changed files are re-embedded and unchanged files are
+    /// skipped. Documents previously ingested from under `path` whose files no
+    /// longer exist are deleted. Pair this with a chunker, since loaders return
+    /// whole files (or whole PDF pages), and with `with_index_manifest` to remember
+    /// what was indexed between runs.
+    pub async fn ingest(&self, path: impl AsRef<std::path::Path>) -> Result<IngestReport> {
+        let root = path.as_ref().to_path_buf();
+        let load_path = root.clone();
+        let documents = tokio::task::spawn_blocking(move || DirectoryLoader::new().load(load_path))
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Document loading failed: {}", e)))??;
+
+        let seen: std::collections::HashSet<String> = documents
+            .iter()
+            .filter_map(|document| incremental::document_key(&document.metadata))
+            .collect();
+        let mut report = self.upsert_loaded(documents).await?;
+
+        let mut manifest = self.manifest.lock().await;
+        let stale: Vec<String> = manifest
+            .documents
+            .iter()
+            .filter(|(key, entry)| {
+                !seen.contains(*key)
+                    && entry
+                        .source
+                        .as_ref()
+                        .is_some_and(|source| std::path::Path::new(source).starts_with(&root))
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in stale {
+            if let Some(entry) = manifest.documents.remove(&key) {
+                self.delete_entries(&entry.document_id, &entry.entry_ids)
+                    .await?;
+                report.removed.push(entry.document_id);
+            }
+        }
+        drop(manifest);
+
+        self.save_manifest().await?;
+        Ok(report)
+    }
 
-        Ok(id)
+    /// Delete a document's entries from the vector store and keyword index
+    async fn delete_entries(&self, document_id: &str, entry_ids: &[String]) -> Result<()> {
+        self.chunk_ids.write().await.remove(document_id);
+        for id in entry_ids {
+            self.keyword_index.write().await.remove(id);
+            self.vector_store.delete(id).await?;
+        }
+        Ok(())
     }
 
     /// Search for similar documents
+    ///
+    /// With hybrid search enabled, scores are fused rank scores rather than
+    /// cosine similarities. With a reranker, they are the reranker's scores.
     pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        self.search_inner(query, limit, None).await
+    }
+
+    /// Search for similar documents whose metadata matches `filter`
+    ///
+    /// Qdrant and pgvector apply the filter in the database. In hybrid mode the
+    /// keyword ranking is filtered after the keyword search.
+    pub async fn search_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_inner(query, limit, Some(filter)).await
+    }
+
+    /// Retrieve and optionally rerank results
+    async fn search_inner(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: Option<&MetadataFilter>,
+    ) -> Result<Vec<SearchResult>> {
         self.ensure_initialized().await?;
 
+        let Some((reranker, config)) = &self.reranker else {
+            return self.retrieve(query, limit, filter).await;
+        };
+        let candidates = self
+            .retrieve(query, config.top_k_in.max(limit), filter)
+            .await?;
+        reranker
+            .rerank(query, candidates, config.top_k_out.min(limit))
+            .await
+    }
+
+    /// Fetch the `limit` best candidates by vector or hybrid search
+    async fn retrieve(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: Option<&MetadataFilter>,
+    ) -> Result<Vec<SearchResult>> {
         let query_embedding = self.embedding_provider.embed(query).await?;
-        self.vector_store.search(query_embedding, limit).await
+        let vector_search = |limit| async move {
+            match filter {
+                Some(filter) => {
+                    self.vector_store
+                        .search_filtered(query_embedding, limit, filter)
+                        .await
+                }
+                None => self.vector_store.search(query_embedding, limit).await,
+            }
+        };
+        let Some(hybrid) = &self.hybrid else {
+            return vector_search(limit).await;
+        };
+
+        let candidates = limit.saturating_mul(hybrid.candidate_multiplier);
+        let vector = vector_search(candidates).await?;
+        let keyword_candidates = match filter {
+            Some(_) => candidates.saturating_mul(FILTER_OVERFETCH),
+            None => candidates,
+        };
+        let mut keyword = match self
+            .vector_store
+            .keyword_search(query, keyword_candidates)
+            .await?
+        {
+            Some(results) => results,
+            None => self
+                .keyword_index
+                .read()
+                .await
+                .search(query, keyword_candidates),
+        };
+        if let Some(filter) = filter {
+            keyword.retain(|result| result.matches(filter));
+            keyword.truncate(candidates);
+        }
+        Ok(hybrid.fuse(vector, keyword, limit))
     }
 
     /// Delete a document by ID
+    ///
+    /// Chunks of documents added through this system are deleted along with it,
+    /// as are chunks of documents recorded in the index manifest. Other chunks
+    /// stored by an earlier process can be deleted by their own IDs.
     pub async fn delete_document(&self, id: &str) -> Result<()> {
-        self.vector_store.delete(id).await
+        let mut manifest = self.manifest.lock().await;
+        let entry = manifest
+            .key_of(id)
+            .and_then(|key| manifest.documents.remove(&key));
+        if let Some(entry) = entry {
+            self.delete_entries(id, &entry.entry_ids).await?;
+            drop(manifest);
+            return self.save_manifest().await;
+        }
+        drop(manifest);
+
+        let ids = self
+            .chunk_ids
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| vec![id.to_string()]);
+        self.delete_entries(id, &ids).await
     }
 
     /// Clear all documents
     pub async fn clear(&self) -> Result<()> {
-        self.vector_store.clear().await
+        self.chunk_ids.write().await.clear();
+        self.keyword_index.write().await.clear();
+        self.manifest.lock().await.documents.clear();
+        self.vector_store.clear().await?;
+        self.save_manifest().await
     }
 
     /// Get document count
     pub async fn count(&self) -> Result<usize> {
         self.vector_store.count().await
     }
+
+    /// List the documents added under a key, sorted by key
+    ///
+    /// These are the documents `ingest` and `upsert_document` track, read from the
+    /// index manifest when one is configured. Documents added with `add_document`
+    /// have no key and are not listed.
+    pub async fn indexed_documents(&self) -> Vec<IndexedDocument> {
+        let manifest = self.manifest.lock().await;
+        let mut documents: Vec<IndexedDocument> = manifest
+            .documents
+            .iter()
+            .map(|(key, entry)| IndexedDocument {
+                key: key.clone(),
+                document_id: entry.document_id.clone(),
+                source: entry.source.clone(),
+                chunks: entry.entry_ids.len(),
+            })
+            .collect();
+        documents.sort_by(|a, b| a.key.cmp(&b.key));
+        documents
+    }
+
+    /// Get the number of stored entries and the approximate memory they hold
+    ///
+    /// Includes the vector store (zero bytes for remote stores), the keyword index
+    /// and the chunk and manifest bookkeeping
+    pub async fn memory_stats(&self) -> Result<MemoryStats> {
+        let store = self.vector_store.memory_stats().await?;
+        let keywords = self.keyword_index.read().await.memory_usage();
+        let chunks = self.chunk_ids.read().await.approx_bytes();
+        let manifest = self.manifest.lock().await.documents.approx_bytes();
+        Ok(MemoryStats {
+            entries: store.entries,
+            bytes: store.bytes + keywords.bytes + chunks + manifest,
+        })
+    }
+
+    /// Evict the least recently retrieved entries until at most `keep` remain
+    ///
+    /// A document that loses one chunk loses all of them and is dropped from the
+    /// index manifest, so the next ingest adds it again. Returns the number of
+    /// entries removed; persistent stores evict nothing
+    pub async fn evict_cold(&self, keep: usize) -> Result<usize> {
+        let evicted: HashSet<String> = self
+            .vector_store
+            .evict_cold(keep)
+            .await?
+            .into_iter()
+            .collect();
+        if evicted.is_empty() {
+            return Ok(0);
+        }
+        let touched = |ids: &[String]| ids.iter().any(|id| evicted.contains(id));
+
+        let mut documents: HashMap<String, Vec<String>> = HashMap::new();
+        self.manifest.lock().await.documents.retain(|_, entry| {
+            if touched(&entry.entry_ids) {
+                documents.insert(entry.document_id.clone(), entry.entry_ids.clone());
+                return false;
+            }
+            true
+        });
+        for (document_id, ids) in self.chunk_ids.read().await.iter() {
+            if touched(ids) {
+                documents.insert(document_id.clone(), ids.clone());
+            }
+        }
+
+        let mut removed = evicted.len();
+        {
+            let mut keyword_index = self.keyword_index.write().await;
+            for id in &evicted {
+                keyword_index.remove(id);
+            }
+        }
+        for (document_id, ids) in documents {
+            let rest: Vec<String> = ids.into_iter().filter(|id| !evicted.contains(id)).collect();
+            removed += rest.len();
+            self.delete_entries(&document_id, &rest).await?;
+        }
+        self.save_manifest().await?;
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunking::SentenceChunker;
+
+    /// Embeds text as its length and vowel count, which is enough to store and rank chunks.
+    struct CountingEmbeddings;
+
+    #[async_trait]
+    impl EmbeddingProvider for CountingEmbeddings {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            let vowels = text.chars().filter(|c| "aeiou".contains(*c)).count();
+            Ok(vec![text.len() as f32, vowels as f32 + 1.0])
+        }
+
+        fn dimension(&self) -> usize {
+            2
+        }
+    }
+
+    /// Counts calls to an inner embedding provider.
+    struct CallCounter(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    #[async_trait]
+    impl EmbeddingProvider for CallCounter {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            CountingEmbeddings.embed(text).await
+        }
+
+        fn dimension(&self) -> usize {
+            CountingEmbeddings.dimension()
+        }
+    }
+
+    /// Tests that ingesting again skips unchanged files, updates changed ones and removes deleted ones.
+    #[tokio::test]
+    async fn test_rag_system_incremental_ingest() {
+        let docs = tempfile::tempdir().unwrap();
+        let state = tempfile::tempdir().unwrap();
+        let manifest = state.path().join("index.json");
+        std::fs::write(docs.path().join("a.md"), "# A\n\nFirst version.").unwrap();
+        std::fs::write(docs.path().join("b.txt"), "Notes about b.").unwrap();
+
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let embeds = || calls.load(std::sync::atomic::Ordering::SeqCst);
+        let rag = RAGSystem::new(
+            Box::new(CallCounter(calls.clone())),
+            Box::new(InMemoryVectorStore::new()),
+        )
+        .with_index_manifest(&manifest)
+        .unwrap();
+
+        let report = rag.ingest(docs.path()).await.unwrap();
+        assert_eq!((report.added.len(), embeds()), (2, 2));
+
+        let report = rag.ingest(docs.path()).await.unwrap();
+        assert_eq!(report.unchanged.len(), 2);
+        assert_eq!(embeds(), 2);
+
+        std::fs::write(docs.path().join("a.md"), "# A\n\nSecond version.").unwrap();
+        std::fs::remove_file(docs.path().join("b.txt")).unwrap();
+        let report = rag.ingest(docs.path()).await.unwrap();
+        assert_eq!((report.updated.len(), report.removed.len()), (1, 1));
+        assert_eq!(embeds(), 3);
+        assert_eq!(rag.count().await.unwrap(), 1);
+        let results = rag.search("version", 5).await.unwrap();
+        assert!(results[0].text.contains("Second version."));
+        assert!(results[0].metadata.as_ref().unwrap()["content_hash"].is_string());
+
+        // A new system reading the same manifest knows the file is already indexed.
+        let before = embeds();
+        let rag = RAGSystem::new(
+            Box::new(CallCounter(calls.clone())),
+            Box::new(InMemoryVectorStore::new()),
+        )
+        .with_index_manifest(&manifest)
+        .unwrap();
+        let report = rag.ingest(docs.path()).await.unwrap();
+        assert_eq!(report.unchanged.len(), 1);
+        assert_eq!(embeds(), before);
+    }
+
+    /// Tests that a chunker splits documents and that deleting the document removes its chunks.
+    #[tokio::test]
+    async fn test_rag_system_chunks_documents() {
+        let rag = RAGSystem::new(
+            Box::new(CountingEmbeddings),
+            Box::new(InMemoryVectorStore::new()),
+        )
+        .with_chunker(SentenceChunker::new(20));
+
+        let id = rag
+            .add_document("First sentence here. Second one follows. Third.", None)
+            .await
+            .unwrap();
+        assert_eq!(rag.count().await.unwrap(), 3);
+
+        let results = rag.search("query", 3).await.unwrap();
+        let metadata = results[0].metadata.as_ref().unwrap();
+        assert_eq!(metadata["document_id"], serde_json::json!(id));
+        assert_eq!(metadata["chunk_count"], 3);
+
+        // Short documents are stored whole under the returned ID.
+        let short = rag.add_document("Tiny.", None).await.unwrap();
+        assert_eq!(rag.count().await.unwrap(), 4);
+
+        rag.delete_document(&id).await.unwrap();
+        assert_eq!(rag.count().await.unwrap(), 1);
+        rag.delete_document(&short).await.unwrap();
+        assert_eq!(rag.count().await.unwrap(), 0);
+    }
+
+    /// Tests that hybrid search surfaces an exact identifier the embeddings rank last.
+    #[tokio::test]
+    async fn test_rag_system_hybrid_search() {
+        let rag = RAGSystem::new(
+            Box::new(CountingEmbeddings),
+            Box::new(InMemoryVectorStore::new()),
+        );
+        rag.add_document("Bcdfg", None).await.unwrap();
+        rag.add_document("See E0308 for mismatched types.", None)
+            .await
+            .unwrap();
+
+        let results = rag.search("E0308", 1).await.unwrap();
+        assert_eq!(results[0].text, "Bcdfg");
+
+        let rag = rag.with_hybrid_search(HybridSearchConfig::default());
+        let results = rag.search("E0308", 1).await.unwrap();
+        assert_eq!(results[0].text, "See E0308 for mismatched types.");
+        assert!(results[0].score <= 1.0);
+    }
+
+    /// Tests that filtered searches only return documents with matching metadata.
+    #[tokio::test]
+    async fn test_rag_system_filtered_search() {
+        let rag = RAGSystem::new(
+            Box::new(CountingEmbeddings),
+            Box::new(InMemoryVectorStore::new()),
+        );
+        for (text, source, year) in [
+            ("Install with cargo.", "guide.md", 2023),
+            ("Install with cargo add.", "faq.md", 2024),
+            ("Configure the API key.", "guide.md", 2024),
+        ] {
+            let metadata = HashMap::from([
+                ("source".to_string(), serde_json::json!(source)),
+                ("year".to_string(), serde_json::json!(year)),
+            ]);
+            rag.add_document(text, Some(metadata)).await.unwrap();
+        }
+
+        let filter =
+            MetadataFilter::eq("source", "guide.md").and(MetadataFilter::gte("year", 2024));
+        let results = rag.search_filtered("Install", 5, &filter).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].text, "Configure the API key.");
+
+        let rag = rag.with_hybrid_search(HybridSearchConfig::default());
+        let results = rag
+            .search_filtered("cargo", 5, &MetadataFilter::eq("source", "faq.md"))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].text, "Install with cargo add.");
+    }
+
+    /// Tests that memory stats track the store and cold entries can be evicted.
+    #[tokio::test]
+    async fn test_rag_system_evict_cold() {
+        let rag = RAGSystem::new(
+            Box::new(CountingEmbeddings),
+            Box::new(InMemoryVectorStore::new()),
+        );
+        for text in ["Alpha notes.", "Beta notes.", "Gamma notes."] {
+            rag.add_document(text, None).await.unwrap();
+        }
+        let full = rag.memory_stats().await.unwrap();
+        assert_eq!(full.entries, 3);
+
+        let hot = rag.search("Beta notes.", 1).await.unwrap();
+        assert_eq!(rag.evict_cold(1).await.unwrap(), 2);
+
+        let pruned = rag.memory_stats().await.unwrap();
+        assert_eq!(pruned.entries, 1);
+        assert!(pruned.bytes < full.bytes);
+        let remaining = rag.search("notes", 5).await.unwrap();
+        assert_eq!(remaining[0].id, hot[0].id);
+        assert_eq!(rag.evict_cold(1).await.unwrap(), 0);
+    }
+
+    /// Reverses the candidates it is given and records how many there were.
+    struct ReversingReranker(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    #[async_trait]
+    impl Reranker for ReversingReranker {
+        async fn rerank(
+            &self,
+            _query: &str,
+            mut results: Vec<SearchResult>,
+            top_n: usize,
+        ) -> Result<Vec<SearchResult>> {
+            self.0
+                .store(results.len(), std::sync::atomic::Ordering::SeqCst);
+            results.reverse();
+            results.truncate(top_n);
+            Ok(results)
+        }
+    }
+
+    /// Tests that the reranker sees `top_k_in` candidates and `search` keeps `top_k_out`.
+    #[tokio::test]
+    async fn test_rag_system_reranks_results() {
+        let seen = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let rag = RAGSystem::new(
+            Box::new(CountingEmbeddings),
+            Box::new(InMemoryVectorStore::new()),
+        )
+        .with_reranker(
+            ReversingReranker(std::sync::Arc::clone(&seen)),
+            RerankConfig::new(4, 2),
+        );
+        for text in ["a", "bb", "ccc", "dddd", "eeeee", "ffffff"] {
+            rag.add_document(text, None).await.unwrap();
+        }
+
+        // The store only holds six documents.
+        let results = rag.search("query", 10).await.unwrap();
+        assert_eq!(seen.load(std::sync::atomic::Ordering::SeqCst), 6);
+        assert_eq!(results.len(), 2);
+
+        let results = rag.search("query", 1).await.unwrap();
+        assert_eq!(seen.load(std::sync::atomic::Ordering::SeqCst), 4);
+        assert_eq!(results.len(), 1);
+    }
+
+    /// Tests that the SQLite store ranks by similarity and survives reopening.
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_vector_store_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rag.db");
+
+        let store = SqliteVectorStore::open(&path).unwrap();
+        let mut metadata = HashMap::new();
+        metadata.insert("lang".to_string(), serde_json::json!("rust"));
+        store
+            .add("a", vec![1.0, 0.0], "about rust", metadata)
+            .await
+            .unwrap();
+        store
+            .add("b", vec![0.0, 1.0], "about python", HashMap::new())
+            .await
+            .unwrap();
+        store
+            .add("b", vec![0.6, 0.8], "about python, updated", HashMap::new())
+            .await
+            .unwrap();
+        drop(store);
+
+        let store = SqliteVectorStore::open(&path).unwrap();
+        assert_eq!(store.count().await.unwrap(), 2);
+
+        let results = store.search(vec![1.0, 0.1], 1).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "a");
+        assert_eq!(results[0].metadata.as_ref().unwrap()["lang"], "rust");
+
+        let results = store.search(vec![0.0, 1.0], 2).await.unwrap();
+        assert_eq!(results[0].text, "about python, updated");
+
+        let filter = MetadataFilter::eq("lang", "rust");
+        let results = store
+            .search_filtered(vec![0.0, 1.0], 2, &filter)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "a");
+
+        store.delete("a").await.unwrap();
+        assert_eq!(store.count().await.unwrap(), 1);
+        store.clear().await.unwrap();
+        assert_eq!(store.count().await.unwrap(), 0);
+    }
+
+    /// Tests reading the `[rag]` section of a config file.
+    #[test]
+    fn test_rag_config_from_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[llm]\nmodel_name = \"m\"\nbase_url = \"http://llm\"\napi_key = \"k\"\n\n\
+             [rag]\nsqlite_path = \"rag.db\"\nembedding_base_url = \"http://embed/\"\nchunk_size = 400\n",
+        )
+        .unwrap();
+        let rag = RagConfig::from_config_file(&path).unwrap().unwrap();
+        assert_eq!(rag.sqlite_path.as_deref(), Some("rag.db"));
+        assert_eq!(rag.collection, "helios_documents");
+        assert_eq!(rag.chunk_size, 400);
+        assert_eq!(rag.chunk_overlap, 100);
+        assert_eq!(rag.embedding_model, "text-embedding-ada-002");
+
+        std::fs::write(
+            &path,
+            "[llm]\nmodel_name = \"m\"\nbase_url = \"http://llm\"\n",
+        )
+        .unwrap();
+        assert!(RagConfig::from_config_file(&path).unwrap().is_none());
+    }
 }
@@ -0,0 +1,409 @@
+//! # Metadata Filters
+//!
+//! A [`MetadataFilter`] restricts a search to documents whose metadata matches,
+//! for example a single source file, a set of tags or a date range. Qdrant and
+//! pgvector evaluate filters in the database, so the nearest matching documents are
+//! found even when most of the collection is excluded. The in-memory and SQLite
+//! stores check every document, and other stores fall back to filtering a larger
+//! set of unfiltered results.
+//!
+//! Filters can also be written as JSON, which is how agents pass them to `RAGTool`:
+//!
+//! ```json
+//! {"source": "guide.pdf", "tags": {"any": ["api", "auth"]}, "timestamp": {"gte": "2024-01-01"}}
+//! ```
+
+use crate::error::{HeliosError, Result};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// A condition on document metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataFilter {
+    /// The field equals the value, or is an array containing it.
+    Equals {
+        /// The metadata key.
+        field: String,
+        /// The value to match.
+        value: Value,
+    },
+    /// The field equals, or is an array containing, at least one of the values.
+    AnyOf {
+        /// The metadata key.
+        field: String,
+        /// The accepted values.
+        values: Vec<Value>,
+    },
+    /// The field lies within the given bounds.
+    ///
+    /// Numbers compare numerically. Strings compare as RFC 3339 timestamps when both
+    /// sides parse as one, and as text otherwise, so ISO dates such as `2024-01-01`
+    /// work as bounds for timestamps.
+    Range {
+        /// The metadata key.
+        field: String,
+        /// Exclusive lower bound.
+        gt: Option<Value>,
+        /// Inclusive lower bound.
+        gte: Option<Value>,
+        /// Exclusive upper bound.
+        lt: Option<Value>,
+        /// Inclusive upper bound.
+        lte: Option<Value>,
+    },
+    /// Every filter matches.
+    All(Vec<MetadataFilter>),
+}
+
+impl MetadataFilter {
+    /// Matches documents whose `field` equals `value` or contains it as a tag.
+    pub fn eq(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::Equals {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Matches documents whose `field` equals or contains any of `values`.
+    pub fn any_of<V: Into<Value>>(
+        field: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        Self::AnyOf {
+            field: field.into(),
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Matches documents whose `field` is at least `min`.
+    pub fn gte(field: impl Into<String>, min: impl Into<Value>) -> Self {
+        Self::Range {
+            field: field.into(),
+            gt: None,
+            gte: Some(min.into()),
+            lt: None,
+            lte: None,
+        }
+    }
+
+    /// Matches documents whose `field` is at most `max`.
+    pub fn lte(field: impl Into<String>, max: impl Into<Value>) -> Self {
+        Self::Range {
+            field: field.into(),
+            gt: None,
+            gte: None,
+            lt: None,
+            lte: Some(max.into()),
+        }
+    }
+
+    /// Matches documents whose `field` lies between `min` and `max`, inclusive.
+    pub fn between(field: impl Into<String>, min: impl Into<Value>, max: impl Into<Value>) -> Self {
+        Self::Range {
+            field: field.into(),
+            gt: None,
+            gte: Some(min.into()),
+            lt: None,
+            lte: Some(max.into()),
+        }
+    }
+
+    /// Combines this filter with another; both must match.
+    pub fn and(self, other: MetadataFilter) -> Self {
+        match self {
+            Self::All(mut filters) => {
+                filters.push(other);
+                Self::All(filters)
+            }
+            filter => Self::All(vec![filter, other]),
+        }
+    }
+
+    /// Parses the JSON form of a filter.
+    ///
+    /// The JSON is an object mapping metadata keys to conditions, all of which must
+    /// match. A condition is a plain value (equality), `{"any": [...]}`, `{"eq": value}`,
+    /// or an object with any of `gt`, `gte`, `lt` and `lte` (a range).
+    pub fn from_json(json: &Value) -> Result<Self> {
+        let Value::Object(conditions) = json else {
+            return Err(HeliosError::ConfigError(format!(
+                "A metadata filter must be a JSON object, got {}",
+                json
+            )));
+        };
+
+        let mut filters = Vec::with_capacity(conditions.len());
+        for (field, condition) in conditions {
+            let filter = match condition {
+                Value::Object(ops) if ops.len() == 1 && ops.contains_key("eq") => {
+                    Self::eq(field.clone(), ops["eq"].clone())
+                }
+                Value::Object(ops) if ops.len() == 1 && ops.contains_key("any") => {
+                    let Value::Array(values) = &ops["any"] else {
+                        return Err(HeliosError::ConfigError(format!(
+                            "The 'any' condition on '{}' must be an array",
+                            field
+                        )));
+                    };
+                    Self::any_of(field.clone(), values.clone())
+                }
+                Value::Object(ops)
+                    if !ops.is_empty()
+                        && ops
+                            .keys()
+                            .all(|op| matches!(op.as_str(), "gt" | "gte" | "lt" | "lte")) =>
+                {
+                    Self::Range {
+                        field: field.clone(),
+                        gt: ops.get("gt").cloned(),
+                        gte: ops.get("gte").cloned(),
+                        lt: ops.get("lt").cloned(),
+                        lte: ops.get("lte").cloned(),
+                    }
+                }
+                value => Self::eq(field.clone(), value.clone()),
+            };
+            filters.push(filter);
+        }
+
+        Ok(match filters.len() {
+            1 => filters.remove(0),
+            _ => Self::All(filters),
+        })
+    }
+
+    /// Returns `true` if `metadata` satisfies the filter.
+    pub fn matches(&self, metadata: &HashMap<String, Value>) -> bool {
+        match self {
+            Self::Equals { field, value } => metadata
+                .get(field)
+                .is_some_and(|stored| value_matches(stored, value)),
+            Self::AnyOf { field, values } => metadata
+                .get(field)
+                .is_some_and(|stored| values.iter().any(|value| value_matches(stored, value))),
+            Self::Range {
+                field,
+                gt,
+                gte,
+                lt,
+                lte,
+            } => {
+                let Some(stored) = metadata.get(field) else {
+                    return false;
+                };
+                let check = |bound: &Option<Value>, accept: fn(Ordering) -> bool| {
+                    bound
+                        .as_ref()
+                        .map_or(true, |bound| compare(stored, bound).is_some_and(accept))
+                };
+                check(gt, Ordering::is_gt)
+                    && check(gte, Ordering::is_ge)
+                    && check(lt, Ordering::is_lt)
+                    && check(lte, Ordering::is_le)
+            }
+            Self::All(filters) => filters.iter().all(|filter| filter.matches(metadata)),
+        }
+    }
+
+    /// Converts the filter to a Qdrant filter object.
+    pub(crate) fn to_qdrant(&self) -> Value {
+        let mut conditions = Vec::new();
+        self.qdrant_conditions(&mut conditions);
+        serde_json::json!({ "must": conditions })
+    }
+
+    fn qdrant_conditions(&self, conditions: &mut Vec<Value>) {
+        match self {
+            Self::Equals { field, value } => conditions.push(serde_json::json!({
+                "key": field,
+                "match": { "value": value },
+            })),
+            Self::AnyOf { field, values } => conditions.push(serde_json::json!({
+                "key": field,
+                "match": { "any": values },
+            })),
+            Self::Range {
+                field,
+                gt,
+                gte,
+                lt,
+                lte,
+            } => {
+                let mut range = serde_json::Map::new();
+                for (op, bound) in [("gt", gt), ("gte", gte), ("lt", lt), ("lte", lte)] {
+                    if let Some(bound) = bound {
+                        range.insert(op.to_string(), bound.clone());
+                    }
+                }
+                conditions.push(serde_json::json!({ "key": field, "range": range }));
+            }
+            Self::All(filters) => {
+                for filter in filters {
+                    filter.qdrant_conditions(conditions);
+                }
+            }
+        }
+    }
+
+    /// Converts the filter to a SQL condition on a JSONB `metadata` column.
+    ///
+    /// Keys and values are passed as query parameters, appended to `params`.
+    #[cfg(feature = "pgvector")]
+    pub(crate) fn to_sql(&self, params: &mut Vec<PgParam>) -> String {
+        let mut push = |param: PgParam| {
+            params.push(param);
+            format!("${}", params.len())
+        };
+        match self {
+            Self::Equals { field, value } => {
+                let key = push(Box::new(field.clone()));
+                let value = push(Box::new(value.clone()));
+                format!("(metadata -> {}::text) @> {}::jsonb", key, value)
+            }
+            Self::AnyOf { field, values } => {
+                if values.is_empty() {
+                    return "FALSE".to_string();
+                }
+                let key = push(Box::new(field.clone()));
+                let conditions: Vec<String> = values
+                    .iter()
+                    .map(|value| {
+                        let value = push(Box::new(value.clone()));
+                        format!("(metadata -> {}::text) @> {}::jsonb", key, value)
+                    })
+                    .collect();
+                format!("({})", conditions.join(" OR "))
+            }
+            Self::Range {
+                field,
+                gt,
+                gte,
+                lt,
+                lte,
+            } => {
+                let key = push(Box::new(field.clone()));
+                let mut conditions = Vec::new();
+                for (op, bound) in [(">", gt), (">=", gte), ("<", lt), ("<=", lte)] {
+                    match bound {
+                        Some(Value::Number(n)) => {
+                            let bound = push(Box::new(n.as_f64().unwrap_or_default()));
+                            conditions.push(format!(
+                                "(CASE WHEN jsonb_typeof(metadata -> {key}::text) = 'number' \
+                                 THEN (metadata ->> {key}::text)::float8 END) {op} {bound}"
+                            ));
+                        }
+                        Some(Value::String(s)) => {
+                            let bound = push(Box::new(s.clone()));
+                            conditions
+                                .push(format!("(metadata ->> {key}::text) {op} {bound}::text"));
+                        }
+                        Some(_) => conditions.push("FALSE".to_string()),
+                        None => {}
+                    }
+                }
+                if conditions.is_empty() {
+                    format!("metadata ? {}::text", key)
+                } else {
+                    format!("({})", conditions.join(" AND "))
+                }
+            }
+            Self::All(filters) => {
+                if filters.is_empty() {
+                    return "TRUE".to_string();
+                }
+                let conditions: Vec<String> =
+                    filters.iter().map(|filter| filter.to_sql(params)).collect();
+                format!("({})", conditions.join(" AND "))
+            }
+        }
+    }
+}
+
+/// A boxed PostgreSQL query parameter.
+#[cfg(feature = "pgvector")]
+pub(crate) type PgParam = Box<dyn tokio_postgres::types::ToSql + Sync + Send>;
+
+/// Checks a stored value against an expected one, looking inside arrays.
+fn value_matches(stored: &Value, expected: &Value) -> bool {
+    match stored {
+        Value::Array(items) if !expected.is_array() => {
+            items.iter().any(|item| value_matches(item, expected))
+        }
+        Value::Number(a) => expected
+            .as_f64()
+            .zip(a.as_f64())
+            .is_some_and(|(b, a)| a == b),
+        stored => stored == expected,
+    }
+}
+
+/// Orders two metadata values, if they are comparable.
+fn compare(stored: &Value, bound: &Value) -> Option<Ordering> {
+    match (stored, bound) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => {
+            match (
+                chrono::DateTime::parse_from_rfc3339(a),
+                chrono::DateTime::parse_from_rfc3339(b),
+            ) {
+                (Ok(a), Ok(b)) => Some(a.cmp(&b)),
+                _ => Some(a.as_str().cmp(b.as_str())),
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn metadata(value: Value) -> HashMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    /// Tests equality, tag, numeric and date range matching from the JSON form.
+    #[test]
+    fn test_filter_matching() {
+        let doc = metadata(json!({
+            "source": "guide.pdf",
+            "tags": ["api", "auth"],
+            "page": 12,
+            "timestamp": "2024-03-05T10:00:00+00:00",
+        }));
+
+        let filter = MetadataFilter::from_json(&json!({
+            "source": "guide.pdf",
+            "tags": {"any": ["auth", "billing"]},
+            "page": {"gte": 10, "lt": 20},
+            "timestamp": {"gte": "2024-01-01", "lte": "2024-12-31"},
+        }))
+        .unwrap();
+        assert!(filter.matches(&doc));
+
+        assert!(MetadataFilter::eq("tags", "api").matches(&doc));
+        assert!(!MetadataFilter::eq("source", "other.pdf").matches(&doc));
+        assert!(!MetadataFilter::between("page", 1, 5).matches(&doc));
+        assert!(!MetadataFilter::gte("missing", 1).matches(&doc));
+        assert!(MetadataFilter::gte("timestamp", "2024-03-05T09:00:00Z")
+            .and(MetadataFilter::lte("page", 12.0))
+            .matches(&doc));
+        assert!(MetadataFilter::from_json(&json!("guide.pdf")).is_err());
+    }
+
+    /// Tests the conversion to Qdrant's filter format.
+    #[test]
+    fn test_qdrant_filter() {
+        let filter =
+            MetadataFilter::eq("source", "a.md").and(MetadataFilter::any_of("tags", ["x", "y"]));
+        assert_eq!(
+            filter.to_qdrant(),
+            json!({"must": [
+                {"key": "source", "match": {"value": "a.md"}},
+                {"key": "tags", "match": {"any": ["x", "y"]}},
+            ]})
+        );
+    }
+}
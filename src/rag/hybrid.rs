@@ -0,0 +1,383 @@
+//! # Hybrid Search
+//!
+//! Vector search finds passages that mean the same thing as the query, but it is
+//! weak at exact matches: an error code, a function name or a product SKU embeds
+//! much like any other token. Hybrid search runs a BM25 keyword search alongside the
+//! vector search and merges the two rankings with reciprocal rank fusion (RRF), so a
+//! document near the top of either list ends up near the top of the result.
+//!
+//! Enable it with `RAGSystem::with_hybrid_search`. Stores that can score keywords
+//! themselves do so through `VectorStore::keyword_search`; for the others the RAG
+//! system keeps a [`Bm25Index`] of the documents it adds.
+
+use super::SearchResult;
+use crate::footprint::{MemoryFootprint, MemoryStats};
+use std::collections::HashMap;
+
+/// Settings for combining keyword and vector rankings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HybridSearchConfig {
+    /// The weight of the vector ranking in the fused score.
+    pub vector_weight: f64,
+    /// The weight of the keyword ranking in the fused score.
+    pub keyword_weight: f64,
+    /// The RRF constant. Larger values flatten the difference between ranks.
+    pub rrf_k: f64,
+    /// How many candidates each search fetches, as a multiple of the requested limit.
+    pub candidate_multiplier: usize,
+}
+
+impl Default for HybridSearchConfig {
+    fn default() -> Self {
+        Self {
+            vector_weight: 1.0,
+            keyword_weight: 1.0,
+            rrf_k: 60.0,
+            candidate_multiplier: 4,
+        }
+    }
+}
+
+impl HybridSearchConfig {
+    /// Sets the relative weights of the vector and keyword rankings.
+    pub fn with_weights(mut self, vector_weight: f64, keyword_weight: f64) -> Self {
+        self.vector_weight = vector_weight.max(0.0);
+        self.keyword_weight = keyword_weight.max(0.0);
+        self
+    }
+
+    /// Sets the RRF constant.
+    pub fn with_rrf_k(mut self, rrf_k: f64) -> Self {
+        self.rrf_k = rrf_k.max(0.0);
+        self
+    }
+
+    /// Sets how many candidates each search fetches per requested result.
+    pub fn with_candidate_multiplier(mut self, multiplier: usize) -> Self {
+        self.candidate_multiplier = multiplier.max(1);
+        self
+    }
+
+    /// Merges vector and keyword results with weighted reciprocal rank fusion.
+    ///
+    /// Scores are scaled so that a document ranked first by both searches scores 1.0.
+    pub fn fuse(
+        &self,
+        vector: Vec<SearchResult>,
+        keyword: Vec<SearchResult>,
+        limit: usize,
+    ) -> Vec<SearchResult> {
+        let mut fused: HashMap<String, SearchResult> = HashMap::new();
+        let mut order = Vec::new();
+
+        for (results, weight) in [(vector, self.vector_weight), (keyword, self.keyword_weight)] {
+            for (rank, result) in results.into_iter().enumerate() {
+                let contribution = weight / (self.rrf_k + rank as f64 + 1.0);
+                match fused.get_mut(&result.id) {
+                    Some(existing) => existing.score += contribution,
+                    None => {
+                        order.push(result.id.clone());
+                        fused.insert(
+                            result.id.clone(),
+                            SearchResult {
+                                score: contribution,
+                                ..result
+                            },
+                        );
+                    }
+                }
+            }
+        }
+
+        let best = (self.vector_weight + self.keyword_weight) / (self.rrf_k + 1.0);
+        let mut results: Vec<SearchResult> = order
+            .into_iter()
+            .filter_map(|id| fused.remove(&id))
+            .map(|mut result| {
+                if best > 0.0 {
+                    result.score /= best;
+                }
+                result
+            })
+            .collect();
+        // A stable sort keeps vector order for ties.
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        results.truncate(limit);
+        results
+    }
+}
+
+/// A document held by a [`Bm25Index`].
+#[derive(Debug, Clone)]
+struct IndexedDocument {
+    text: String,
+    metadata: Option<HashMap<String, serde_json::Value>>,
+    term_freq: HashMap<String, u32>,
+    len: usize,
+}
+
+/// An in-memory BM25 keyword index.
+#[derive(Debug, Clone)]
+pub struct Bm25Index {
+    k1: f64,
+    b: f64,
+    documents: HashMap<String, IndexedDocument>,
+    doc_freq: HashMap<String, usize>,
+    total_len: usize,
+}
+
+impl Default for Bm25Index {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bm25Index {
+    /// Creates an empty index with the usual parameters (`k1 = 1.2`, `b = 0.75`).
+    pub fn new() -> Self {
+        Self::with_params(1.2, 0.75)
+    }
+
+    /// Creates an empty index with custom term-saturation (`k1`) and length-normalization (`b`) parameters.
+    pub fn with_params(k1: f64, b: f64) -> Self {
+        Self {
+            k1,
+            b,
+            documents: HashMap::new(),
+            doc_freq: HashMap::new(),
+            total_len: 0,
+        }
+    }
+
+    /// Adds a document, replacing any document with the same ID.
+    pub fn add(
+        &mut self,
+        id: impl Into<String>,
+        text: impl Into<String>,
+        metadata: Option<HashMap<String, serde_json::Value>>,
+    ) {
+        let id = id.into();
+        self.remove(&id);
+
+        let text = text.into();
+        let tokens = tokenize(&text);
+        let mut term_freq: HashMap<String, u32> = HashMap::new();
+        for token in &tokens {
+            *term_freq.entry(token.clone()).or_default() += 1;
+        }
+        for term in term_freq.keys() {
+            *self.doc_freq.entry(term.clone()).or_default() += 1;
+        }
+        self.total_len += tokens.len();
+        self.documents.insert(
+            id,
+            IndexedDocument {
+                text,
+                metadata,
+                term_freq,
+                len: tokens.len(),
+            },
+        );
+    }
+
+    /// Removes a document.
+    pub fn remove(&mut self, id: &str) {
+        let Some(document) = self.documents.remove(id) else {
+            return;
+        };
+        self.total_len -= document.len;
+        for term in document.term_freq.keys() {
+            if let Some(count) = self.doc_freq.get_mut(term) {
+                *count -= 1;
+                if *count == 0 {
+                    self.doc_freq.remove(term);
+                }
+            }
+        }
+    }
+
+    /// Removes every document.
+    pub fn clear(&mut self) {
+        self.documents.clear();
+        self.doc_freq.clear();
+        self.total_len = 0;
+    }
+
+    /// Returns the number of indexed documents and their approximate size.
+    pub fn memory_usage(&self) -> MemoryStats {
+        fn term_bytes<V>(terms: &HashMap<String, V>) -> usize {
+            terms
+                .keys()
+                .map(|term| term.approx_bytes() + std::mem::size_of::<V>())
+                .sum()
+        }
+        let documents: usize = self
+            .documents
+            .iter()
+            .map(|(id, document)| {
+                id.approx_bytes()
+                    + document.text.approx_bytes()
+                    + document.metadata.approx_bytes()
+                    + term_bytes(&document.term_freq)
+            })
+            .sum();
+        MemoryStats {
+            entries: self.documents.len(),
+            bytes: documents + term_bytes(&self.doc_freq),
+        }
+    }
+
+    /// Returns the number of indexed documents.
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Returns `true` if the index holds no documents.
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Returns the documents matching any query term, best BM25 score first.
+    ///
+    /// Documents sharing no term with the query are not returned.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        if self.documents.is_empty() {
+            return Vec::new();
+        }
+
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+
+        let n = self.documents.len() as f64;
+        let avg_len = (self.total_len as f64 / n).max(1.0);
+        let mut scored: Vec<(&String, f64)> = self
+            .documents
+            .iter()
+            .filter_map(|(id, document)| {
+                let mut score = 0.0;
+                for term in &terms {
+                    let Some(&tf) = document.term_freq.get(term) else {
+                        continue;
+                    };
+                    let df = self.doc_freq.get(term).copied().unwrap_or(0) as f64;
+                    let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+                    let tf = tf as f64;
+                    let norm = 1.0 - self.b + self.b * document.len as f64 / avg_len;
+                    score += idf * tf * (self.k1 + 1.0) / (tf + self.k1 * norm);
+                }
+                (score > 0.0).then_some((id, score))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(b.0))
+        });
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(id, score)| {
+                let document = &self.documents[id];
+                SearchResult {
+                    id: id.clone(),
+                    score,
+                    text: document.text.clone(),
+                    metadata: document.metadata.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Splits text into lowercase word tokens.
+///
+/// Identifiers are kept whole (`parse_config`, `E0308`) and snake_case identifiers
+/// are also indexed by their parts, so `config` matches `parse_config`.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in text
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+    {
+        let word = word.to_lowercase();
+        if word.contains('_') {
+            tokens.extend(
+                word.split('_')
+                    .filter(|part| !part.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        let trimmed = word.trim_matches('_');
+        if !trimmed.is_empty() {
+            tokens.push(trimmed.to_string());
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(id: &str) -> SearchResult {
+        SearchResult {
+            id: id.to_string(),
+            score: 0.0,
+            text: String::new(),
+            metadata: None,
+        }
+    }
+
+    /// Tests BM25 ranking, identifier tokenization and removal.
+    #[test]
+    fn test_bm25_index() {
+        let mut index = Bm25Index::new();
+        index.add("a", "Call parse_config to load settings.", None);
+        index.add(
+            "b",
+            "Settings are loaded at startup. Settings matter.",
+            None,
+        );
+        index.add("c", "Unrelated text about cats.", None);
+
+        let results = index.search("parse_config", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "a");
+
+        let results = index.search("settings", 10);
+        assert_eq!(results[0].id, "b");
+        assert_eq!(results.len(), 2);
+
+        assert_eq!(index.search("config", 10)[0].id, "a");
+
+        index.remove("a");
+        assert!(index.search("parse_config", 10).is_empty());
+        assert_eq!(index.len(), 2);
+    }
+
+    /// Tests that documents ranked well by both searches come first.
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let config = HybridSearchConfig::default();
+        let fused = config.fuse(
+            vec![result("a"), result("b"), result("c")],
+            vec![result("c"), result("d")],
+            3,
+        );
+        let ids: Vec<_> = fused.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "a", "b"]);
+        assert!(fused[0].score <= 1.0);
+
+        let keyword_only =
+            config
+                .with_weights(0.0, 1.0)
+                .fuse(vec![result("a")], vec![result("d")], 2);
+        assert_eq!(keyword_only[0].id, "d");
+    }
+}
@@ -0,0 +1,178 @@
+//! # Incremental Indexing
+//!
+//! Re-embedding a whole folder on every run is slow and costs API calls. The RAG
+//! system therefore records a content hash for every document it adds under a
+//! stable key (the source path, plus the page for PDFs). `RAGSystem::ingest` then
+//! skips documents whose hash is unchanged, replaces the ones that changed and
+//! deletes the ones whose files are gone.
+//!
+//! The record is kept in memory by default. `RAGSystem::with_index_manifest` saves
+//! it to a JSON file so it survives restarts, which is what makes a persistent
+//! store such as SQLite or Qdrant cheap to keep up to date.
+
+use crate::error::{HeliosError, Result};
+use crate::footprint::MemoryFootprint;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// What `RAGSystem::upsert_document` did with a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpsertOutcome {
+    /// The key was new and the document was added with this ID.
+    Added(String),
+    /// The content changed; the old entries were replaced by a document with this ID.
+    Updated(String),
+    /// The content was unchanged; the existing document has this ID.
+    Unchanged(String),
+}
+
+impl UpsertOutcome {
+    /// Returns the ID of the document now stored under the key.
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Added(id) | Self::Updated(id) | Self::Unchanged(id) => id,
+        }
+    }
+}
+
+/// The result of an incremental `RAGSystem::ingest`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IngestReport {
+    /// IDs of documents seen for the first time.
+    pub added: Vec<String>,
+    /// IDs of documents whose content changed and were re-embedded.
+    pub updated: Vec<String>,
+    /// IDs of documents that were skipped because nothing changed.
+    pub unchanged: Vec<String>,
+    /// IDs of documents deleted because their source no longer exists.
+    pub removed: Vec<String>,
+}
+
+impl IngestReport {
+    /// Returns the IDs of every document that is indexed after the ingest.
+    pub fn ids(&self) -> Vec<String> {
+        self.added
+            .iter()
+            .chain(&self.updated)
+            .chain(&self.unchanged)
+            .cloned()
+            .collect()
+    }
+
+    /// Records the outcome of one upsert.
+    pub(crate) fn record(&mut self, outcome: UpsertOutcome) {
+        match outcome {
+            UpsertOutcome::Added(id) => self.added.push(id),
+            UpsertOutcome::Updated(id) => self.updated.push(id),
+            UpsertOutcome::Unchanged(id) => self.unchanged.push(id),
+        }
+    }
+}
+
+/// A document the RAG system has indexed under a key; see
+/// `RAGSystem::indexed_documents`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedDocument {
+    /// The key the document is tracked under: its source path, plus the page for PDFs.
+    pub key: String,
+    /// The ID returned when the document was added.
+    pub document_id: String,
+    /// The file the document was loaded from.
+    pub source: Option<String>,
+    /// The number of entries stored for it: one per chunk.
+    pub chunks: usize,
+}
+
+/// A document recorded in the manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ManifestEntry {
+    /// The ID returned when the document was added.
+    pub document_id: String,
+    /// The hash of the document's text and metadata.
+    pub content_hash: String,
+    /// The IDs of the entries in the vector store: one per chunk.
+    pub entry_ids: Vec<String>,
+    /// The file the document was loaded from, used to find removed files.
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+impl MemoryFootprint for ManifestEntry {
+    fn approx_bytes(&self) -> usize {
+        self.document_id.approx_bytes()
+            + self.content_hash.approx_bytes()
+            + self.entry_ids.approx_bytes()
+            + self.source.approx_bytes()
+    }
+}
+
+/// Document keys and the hashes and entries stored for them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct IndexManifest {
+    #[serde(default)]
+    pub documents: HashMap<String, ManifestEntry>,
+}
+
+impl IndexManifest {
+    /// Loads a manifest, or returns an empty one if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| {
+                HeliosError::ConfigError(format!(
+                    "Invalid index manifest '{}': {}",
+                    path.display(),
+                    e
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the manifest to `path`, creating parent directories as needed.
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+        }
+        tokio::fs::write(path, serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+
+    /// Returns the key of the entry for `document_id`, if any.
+    pub fn key_of(&self, document_id: &str) -> Option<String> {
+        self.documents
+            .iter()
+            .find(|(_, entry)| entry.document_id == document_id)
+            .map(|(key, _)| key.clone())
+    }
+}
+
+/// Hashes a document's text together with its metadata.
+///
+/// Metadata keys are sorted first, so the hash doesn't depend on map order.
+pub(crate) fn content_hash(text: &str, metadata: &HashMap<String, Value>) -> String {
+    let sorted: BTreeMap<&String, &Value> = metadata.iter().collect();
+    let mut hasher = Sha256::new();
+    hasher.update(text.as_bytes());
+    hasher.update([0]);
+    hasher.update(
+        serde_json::to_string(&sorted)
+            .unwrap_or_default()
+            .as_bytes(),
+    );
+    format!("{:x}", hasher.finalize())
+}
+
+/// Builds the key a loaded document is tracked under: its source, plus its page.
+pub(crate) fn document_key(metadata: &HashMap<String, Value>) -> Option<String> {
+    let source = metadata.get("source")?.as_str()?;
+    Some(match metadata.get("page") {
+        Some(page) => format!("{}#page={}", source, page),
+        None => source.to_string(),
+    })
+}
@@ -0,0 +1,519 @@
+//! # Document Loaders
+//!
+//! Loaders turn files into text for the RAG system, along with metadata such as the
+//! source path, the document title and, for PDFs, the page number. Plain text,
+//! Markdown and HTML are always supported; PDF needs the `pdf` feature and DOCX the
+//! `docx` feature.
+//!
+//! [`DirectoryLoader`] picks a loader by file extension and walks directories, so a
+//! whole folder can be indexed with `RAGSystem::ingest`:
+//!
+//! ```rust,no_run
+//! use helios_engine::rag::loaders::DirectoryLoader;
+//!
+//! # fn example() -> helios_engine::Result<()> {
+//! for document in DirectoryLoader::new().load("./docs")? {
+//!     println!("{:?}: {} chars", document.metadata.get("source"), document.text.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{HeliosError, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Text extracted from a file, ready to be added to a `RAGSystem`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadedDocument {
+    /// The extracted text.
+    pub text: String,
+    /// Metadata such as `source`, `format`, `title` and `page`.
+    pub metadata: HashMap<String, Value>,
+}
+
+impl LoadedDocument {
+    /// Creates a document read from `path` in the given format.
+    fn new(text: String, path: &Path, format: &str) -> Self {
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            "source".to_string(),
+            Value::String(path.to_string_lossy().into_owned()),
+        );
+        metadata.insert("format".to_string(), Value::String(format.to_string()));
+        Self { text, metadata }
+    }
+
+    /// Adds a title to the metadata if one was found.
+    fn with_title(mut self, title: Option<String>) -> Self {
+        if let Some(title) = title.filter(|t| !t.trim().is_empty()) {
+            self.metadata
+                .insert("title".to_string(), Value::String(title.trim().to_string()));
+        }
+        self
+    }
+}
+
+/// Extracts text from one kind of file.
+pub trait DocumentLoader: Send + Sync {
+    /// The file extensions this loader handles, lowercase and without the dot.
+    fn extensions(&self) -> &[&str];
+
+    /// Reads `path` into one or more documents. Blank documents are left out.
+    fn load(&self, path: &Path) -> Result<Vec<LoadedDocument>>;
+}
+
+/// Reads a file into a string, naming the file in the error.
+fn read_to_string(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| HeliosError::ConfigError(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+/// Loads plain text files as they are.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextLoader;
+
+impl DocumentLoader for TextLoader {
+    fn extensions(&self) -> &[&str] {
+        &["txt", "text", "log", "csv", "rst"]
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<LoadedDocument>> {
+        let text = read_to_string(path)?;
+        Ok(non_blank(vec![LoadedDocument::new(text, path, "text")]))
+    }
+}
+
+/// Loads Markdown files, keeping the markup so `MarkdownChunker` can split on headers.
+///
+/// The title is taken from a `title:` key in YAML front matter or else from the
+/// first level-one header. Front matter is removed from the text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownLoader;
+
+impl DocumentLoader for MarkdownLoader {
+    fn extensions(&self) -> &[&str] {
+        &["md", "markdown", "mdx"]
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<LoadedDocument>> {
+        let content = read_to_string(path)?;
+        let (front_matter, body) = split_front_matter(&content);
+
+        let title = front_matter
+            .and_then(|fm| {
+                fm.lines().find_map(|line| {
+                    line.strip_prefix("title:")
+                        .map(|t| t.trim().trim_matches(['"', '\'']).to_string())
+                })
+            })
+            .or_else(|| {
+                body.lines()
+                    .find_map(|line| line.strip_prefix("# ").map(|t| t.trim().to_string()))
+            });
+
+        let document = LoadedDocument::new(body.trim().to_string(), path, "markdown");
+        Ok(non_blank(vec![document.with_title(title)]))
+    }
+}
+
+/// Splits YAML front matter delimited by `---` lines from the rest of a Markdown file.
+fn split_front_matter(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content);
+    };
+    match rest.find("\n---") {
+        Some(end) => {
+            let body = &rest[end + 4..];
+            let body = body.split_once('\n').map_or("", |(_, body)| body);
+            (Some(&rest[..end]), body)
+        }
+        None => (None, content),
+    }
+}
+
+/// Loads HTML files as readable text.
+///
+/// Scripts, styles and comments are removed, block elements become line breaks and
+/// common entities are decoded. The title comes from the `<title>` element, or the
+/// first `<h1>` when there is none.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlLoader;
+
+impl DocumentLoader for HtmlLoader {
+    fn extensions(&self) -> &[&str] {
+        &["html", "htm", "xhtml"]
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<LoadedDocument>> {
+        let html = read_to_string(path)?;
+        let title = element_text(&html, "title").or_else(|| element_text(&html, "h1"));
+        let document = LoadedDocument::new(html_to_text(&html), path, "html");
+        Ok(non_blank(vec![document.with_title(title)]))
+    }
+}
+
+/// Returns the text of the first `<tag>` element in `html`.
+fn element_text(html: &str, tag: &str) -> Option<String> {
+    let pattern = format!(r"(?is)<{tag}(?:\s[^>]*)?>(.*?)</{tag}\s*>");
+    let captures = regex::Regex::new(&pattern).ok()?.captures(html)?;
+    let text = html_to_text(captures.get(1)?.as_str());
+    (!text.is_empty()).then_some(text)
+}
+
+/// Converts HTML to plain text, keeping block structure as line breaks.
+pub(crate) fn html_to_text(html: &str) -> String {
+    let hidden = regex::Regex::new(
+        r"(?is)<!--.*?-->|<(script|style|noscript|template|svg|head)(?:\s[^>]*)?>.*?</(script|style|noscript|template|svg|head)\s*>",
+    )
+    .expect("valid regex");
+    let blocks = regex::Regex::new(
+        r"(?i)</?(p|div|br|li|ul|ol|tr|table|section|article|header|footer|nav|aside|main|blockquote|pre|h[1-6])(?:\s[^>]*)?/?>",
+    )
+    .expect("valid regex");
+    let tags = regex::Regex::new(r"(?s)<[^>]*>").expect("valid regex");
+
+    let text = hidden.replace_all(html, " ");
+    let text = blocks.replace_all(&text, "\n");
+    let text = tags.replace_all(&text, "");
+    let text = decode_entities(&text);
+
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Decodes the named entities common in HTML and XML plus numeric references.
+fn decode_entities(text: &str) -> String {
+    let entities = regex::Regex::new(r"&(#x[0-9a-fA-F]+|#[0-9]+|[a-zA-Z]+);").expect("valid regex");
+    entities
+        .replace_all(text, |caps: &regex::Captures| {
+            let entity = &caps[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            decoded.map_or_else(|| caps[0].to_string(), String::from)
+        })
+        .into_owned()
+}
+
+/// Loads PDF files, one document per page with a 1-based `page` in the metadata.
+#[cfg(feature = "pdf")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PdfLoader;
+
+#[cfg(feature = "pdf")]
+impl DocumentLoader for PdfLoader {
+    fn extensions(&self) -> &[&str] {
+        &["pdf"]
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<LoadedDocument>> {
+        let bytes = std::fs::read(path)?;
+        // pdf-extract panics on some malformed files instead of returning an error.
+        let pages =
+            std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(&bytes))
+                .map_err(|_| {
+                    HeliosError::ConfigError(format!("Failed to parse PDF {}", path.display()))
+                })?
+                .map_err(|e| {
+                    HeliosError::ConfigError(format!(
+                        "Failed to parse PDF {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+
+        let page_count = pages.len();
+        let documents = pages
+            .into_iter()
+            .enumerate()
+            .map(|(index, text)| {
+                let mut document = LoadedDocument::new(text.trim().to_string(), path, "pdf");
+                document
+                    .metadata
+                    .insert("page".to_string(), Value::from(index + 1));
+                document
+                    .metadata
+                    .insert("page_count".to_string(), Value::from(page_count));
+                document
+            })
+            .collect();
+        Ok(non_blank(documents))
+    }
+}
+
+/// Loads Word `.docx` files.
+///
+/// Paragraphs styled as headings are written as Markdown headers, so
+/// `MarkdownChunker` can split the document by section. The title comes from the
+/// document properties.
+#[cfg(feature = "docx")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DocxLoader;
+
+#[cfg(feature = "docx")]
+impl DocumentLoader for DocxLoader {
+    fn extensions(&self) -> &[&str] {
+        &["docx"]
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<LoadedDocument>> {
+        use std::io::Read;
+
+        let file = std::fs::File::open(path)?;
+        let mut archive = zip::ZipArchive::new(file).map_err(|e| {
+            HeliosError::ConfigError(format!("Failed to open DOCX {}: {}", path.display(), e))
+        })?;
+        let mut read_part = |name: &str| -> Option<String> {
+            let mut part = archive.by_name(name).ok()?;
+            let mut xml = String::new();
+            part.read_to_string(&mut xml).ok()?;
+            Some(xml)
+        };
+
+        let body = read_part("word/document.xml").ok_or_else(|| {
+            HeliosError::ConfigError(format!(
+                "{} is not a Word document: word/document.xml is missing",
+                path.display()
+            ))
+        })?;
+        let title = read_part("docProps/core.xml").and_then(|core| {
+            regex::Regex::new(r"(?s)<dc:title>(.*?)</dc:title>")
+                .ok()?
+                .captures(&core)
+                .map(|c| decode_entities(&c[1]))
+        });
+
+        let document = LoadedDocument::new(docx_to_text(&body), path, "docx");
+        Ok(non_blank(vec![document.with_title(title)]))
+    }
+}
+
+/// Extracts paragraphs from WordprocessingML, turning heading styles into Markdown headers.
+#[cfg(feature = "docx")]
+fn docx_to_text(xml: &str) -> String {
+    let paragraph = regex::Regex::new(r"(?s)<w:p[ >].*?</w:p>|<w:p/>").expect("valid regex");
+    let heading =
+        regex::Regex::new(r#"<w:pStyle w:val="(?:Heading|heading )(\d)""#).expect("valid regex");
+    let runs = regex::Regex::new(r"(?s)<w:t(?:\s[^>]*)?>(.*?)</w:t>|<w:tab/>|<w:br/>")
+        .expect("valid regex");
+
+    let mut paragraphs = Vec::new();
+    for p in paragraph.find_iter(xml) {
+        let mut text = String::new();
+        for run in runs.captures_iter(p.as_str()) {
+            match run.get(1) {
+                Some(t) => text.push_str(&decode_entities(t.as_str())),
+                None if &run[0] == "<w:tab/>" => text.push('\t'),
+                None => text.push('\n'),
+            }
+        }
+        if text.trim().is_empty() {
+            continue;
+        }
+        match heading
+            .captures(p.as_str())
+            .and_then(|c| c[1].parse::<usize>().ok())
+        {
+            Some(level) => {
+                paragraphs.push(format!("{} {}", "#".repeat(level.clamp(1, 6)), text.trim()))
+            }
+            None => paragraphs.push(text),
+        }
+    }
+    paragraphs.join("\n\n")
+}
+
+/// Drops documents with no text.
+fn non_blank(documents: Vec<LoadedDocument>) -> Vec<LoadedDocument> {
+    documents
+        .into_iter()
+        .filter(|document| !document.text.trim().is_empty())
+        .collect()
+}
+
+/// Loads files and directories with a loader chosen by file extension.
+///
+/// Directories are walked recursively by default. Hidden files and directories and
+/// files without a matching loader are skipped.
+pub struct DirectoryLoader {
+    loaders: Vec<Box<dyn DocumentLoader>>,
+    recursive: bool,
+}
+
+impl DirectoryLoader {
+    /// Creates a loader for every format enabled in this build.
+    pub fn new() -> Self {
+        let loaders: Vec<Box<dyn DocumentLoader>> = vec![
+            Box::new(TextLoader),
+            Box::new(MarkdownLoader),
+            Box::new(HtmlLoader),
+            #[cfg(feature = "pdf")]
+            Box::new(PdfLoader),
+            #[cfg(feature = "docx")]
+            Box::new(DocxLoader),
+        ];
+        Self {
+            loaders,
+            recursive: true,
+        }
+    }
+
+    /// Adds a loader. It takes precedence over earlier loaders for the same extensions.
+    pub fn with_loader(mut self, loader: impl DocumentLoader + 'static) -> Self {
+        self.loaders.insert(0, Box::new(loader));
+        self
+    }
+
+    /// Sets whether subdirectories are loaded too.
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Returns the loader for `path`, if its extension is supported.
+    fn loader_for(&self, path: &Path) -> Option<&dyn DocumentLoader> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        self.loaders
+            .iter()
+            .find(|loader| loader.extensions().contains(&extension.as_str()))
+            .map(|loader| loader.as_ref())
+    }
+
+    /// Loads a single file or every supported file under a directory.
+    ///
+    /// A file given directly must have a supported extension. Files in a directory
+    /// that fail to load are logged and skipped so one bad file does not stop a
+    /// large ingest.
+    pub fn load(&self, path: impl AsRef<Path>) -> Result<Vec<LoadedDocument>> {
+        let path = path.as_ref();
+        if path.is_file() {
+            let loader = self.loader_for(path).ok_or_else(|| {
+                HeliosError::ConfigError(format!(
+                    "No document loader for {}; supported extensions: {}",
+                    path.display(),
+                    self.supported_extensions().join(", ")
+                ))
+            })?;
+            return loader.load(path);
+        }
+        if !path.is_dir() {
+            return Err(HeliosError::ConfigError(format!(
+                "{} does not exist",
+                path.display()
+            )));
+        }
+
+        let mut walker = walkdir::WalkDir::new(path).sort_by_file_name();
+        if !self.recursive {
+            walker = walker.max_depth(1);
+        }
+        let mut documents = Vec::new();
+        let entries = walker.into_iter().filter_entry(|entry| {
+            entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+        });
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Some(loader) = self.loader_for(entry.path()) else {
+                continue;
+            };
+            match loader.load(entry.path()) {
+                Ok(loaded) => documents.extend(loaded),
+                Err(e) => tracing::warn!("Skipping {}: {}", entry.path().display(), e),
+            }
+        }
+        Ok(documents)
+    }
+
+    /// Lists the extensions the configured loaders handle.
+    pub fn supported_extensions(&self) -> Vec<&str> {
+        let mut extensions: Vec<&str> = self
+            .loaders
+            .iter()
+            .flat_map(|loader| loader.extensions().iter().copied())
+            .collect();
+        extensions.sort_unstable();
+        extensions.dedup();
+        extensions
+    }
+}
+
+impl Default for DirectoryLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests Markdown front matter, HTML cleanup and directory walking.
+    #[test]
+    fn test_directory_loader() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("guide.md"),
+            "---\ntitle: \"The Guide\"\n---\n# Intro\nHello.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("page.html"),
+            "<html><head><title>Page &amp; Co</title><style>p{}</style></head>\
+             <body><script>var x;</script><h1>Top</h1><p>First&nbsp;para</p><p>Second</p></body></html>",
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join(".hidden")).unwrap();
+        std::fs::write(dir.path().join(".hidden/secret.txt"), "skip me").unwrap();
+        std::fs::write(dir.path().join("image.png"), [0u8, 1, 2]).unwrap();
+        std::fs::write(dir.path().join("empty.txt"), "  \n").unwrap();
+
+        let documents = DirectoryLoader::new().load(dir.path()).unwrap();
+        assert_eq!(documents.len(), 2);
+
+        let markdown = &documents[0];
+        assert_eq!(markdown.text, "# Intro\nHello.");
+        assert_eq!(markdown.metadata["title"], "The Guide");
+        assert_eq!(markdown.metadata["format"], "markdown");
+
+        let html = &documents[1];
+        assert_eq!(html.text, "Top\nFirst para\nSecond");
+        assert_eq!(html.metadata["title"], "Page & Co");
+        assert!(html.metadata["source"]
+            .as_str()
+            .unwrap()
+            .ends_with("page.html"));
+
+        let error = DirectoryLoader::new()
+            .load(dir.path().join("image.png"))
+            .unwrap_err();
+        assert!(error.to_string().contains("No document loader"));
+    }
+
+    /// Tests that heading styles become Markdown headers and runs are joined.
+    #[cfg(feature = "docx")]
+    #[test]
+    fn test_docx_to_text() {
+        let xml = r#"<w:body><w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>Setup</w:t></w:r></w:p><w:p><w:r><w:t xml:space="preserve">Run </w:t></w:r><w:r><w:t>cargo &amp; go</w:t></w:r></w:p><w:p/></w:body>"#;
+        assert_eq!(docx_to_text(xml), "## Setup\n\nRun cargo & go");
+    }
+}
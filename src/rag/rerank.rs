@@ -0,0 +1,338 @@
+//! # Reranking
+//!
+//! Vector search compares the query and each document separately, through their
+//! embeddings. A reranker reads the query and each candidate together, which is
+//! slower but judges relevance much better. `RAGSystem::with_reranker` fetches a
+//! generous set of candidates with the fast search and lets the reranker pick the
+//! best few.
+//!
+//! Two rerankers are provided: [`LlmReranker`] asks a chat model to grade each
+//! candidate, and [`ApiReranker`] calls a hosted cross-encoder such as Cohere's or
+//! Jina's rerank endpoint.
+
+use super::SearchResult;
+use crate::chat::ChatMessage;
+use crate::error::{HeliosError, Result};
+use crate::llm::LLMClient;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+/// Reorders search results by their relevance to a query.
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// Returns the `top_n` most relevant of `results`, best first, with `score`
+    /// set to the reranker's relevance score.
+    async fn rerank(
+        &self,
+        query: &str,
+        results: Vec<SearchResult>,
+        top_n: usize,
+    ) -> Result<Vec<SearchResult>>;
+}
+
+/// How many candidates are reranked and how many are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RerankConfig {
+    /// The number of candidates fetched from the store and passed to the reranker.
+    pub top_k_in: usize,
+    /// The maximum number of results kept after reranking.
+    pub top_k_out: usize,
+}
+
+impl Default for RerankConfig {
+    fn default() -> Self {
+        Self {
+            top_k_in: 20,
+            top_k_out: 5,
+        }
+    }
+}
+
+impl RerankConfig {
+    /// Creates a config that reranks `top_k_in` candidates and keeps `top_k_out`.
+    pub fn new(top_k_in: usize, top_k_out: usize) -> Self {
+        Self {
+            top_k_in,
+            top_k_out,
+        }
+    }
+}
+
+/// Sorts results by score, best first, and keeps the first `top_n`.
+fn sort_and_truncate(mut results: Vec<SearchResult>, top_n: usize) -> Vec<SearchResult> {
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    results.truncate(top_n);
+    results
+}
+
+// ============================================================================
+// LLM Reranker
+// ============================================================================
+
+/// The default instructions given to the model by [`LlmReranker`].
+const LLM_RERANK_PROMPT: &str = "You rate how well passages answer a search query. \
+Score each passage from 0 (irrelevant) to 10 (answers the query directly). \
+Reply with only a JSON array of numbers, one score per passage, in the order given.";
+
+/// A reranker that asks a chat model to score every candidate in a single request.
+///
+/// Scores are divided by ten, so they fall between 0.0 and 1.0. Candidates the model
+/// fails to score keep a score of zero; a reply without any scores is an error.
+pub struct LlmReranker {
+    client: LLMClient,
+    prompt: String,
+    max_passage_chars: usize,
+}
+
+impl LlmReranker {
+    /// Creates a reranker that grades candidates with `client`.
+    pub fn new(client: LLMClient) -> Self {
+        Self {
+            client,
+            prompt: LLM_RERANK_PROMPT.to_string(),
+            max_passage_chars: 2000,
+        }
+    }
+
+    /// Replaces the grading instructions sent as the system prompt.
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    /// Truncates passages to this many characters to keep the request small.
+    pub fn with_max_passage_chars(mut self, max_chars: usize) -> Self {
+        self.max_passage_chars = max_chars;
+        self
+    }
+}
+
+/// Extracts the first JSON array of numbers from a model reply.
+fn parse_scores(reply: &str) -> Option<Vec<f64>> {
+    let start = reply.find('[')?;
+    let end = start + reply[start..].find(']')?;
+    let values: Vec<serde_json::Value> = serde_json::from_str(&reply[start..=end]).ok()?;
+    Some(
+        values
+            .iter()
+            .map(|value| match value {
+                serde_json::Value::String(s) => s.trim().parse().unwrap_or(0.0),
+                value => value.as_f64().unwrap_or(0.0),
+            })
+            .collect(),
+    )
+}
+
+#[async_trait]
+impl Reranker for LlmReranker {
+    async fn rerank(
+        &self,
+        query: &str,
+        mut results: Vec<SearchResult>,
+        top_n: usize,
+    ) -> Result<Vec<SearchResult>> {
+        if results.is_empty() {
+            return Ok(results);
+        }
+
+        let mut request = format!("Query: {}\n", query);
+        for (i, result) in results.iter().enumerate() {
+            let passage: String = result.text.chars().take(self.max_passage_chars).collect();
+            request.push_str(&format!("\nPassage {}:\n{}\n", i + 1, passage));
+        }
+
+        let reply = self
+            .client
+            .chat(
+                vec![
+                    ChatMessage::system(&self.prompt),
+                    ChatMessage::user(request),
+                ],
+                None,
+                Some(0.0),
+                None,
+                None,
+            )
+            .await?;
+        let scores = parse_scores(&reply.content).ok_or_else(|| {
+            HeliosError::LLMError(format!(
+                "Reranker reply did not contain scores: {}",
+                reply.content
+            ))
+        })?;
+
+        for (i, result) in results.iter_mut().enumerate() {
+            result.score = scores.get(i).copied().unwrap_or(0.0).clamp(0.0, 10.0) / 10.0;
+        }
+        Ok(sort_and_truncate(results, top_n))
+    }
+}
+
+// ============================================================================
+// API Reranker
+// ============================================================================
+
+/// A reranker backed by a hosted rerank endpoint.
+///
+/// Cohere and Jina share the same request shape: a query, a list of
+/// documents and `top_n`, answered with the index and relevance score of each
+/// kept document.
+pub struct ApiReranker {
+    api_url: String,
+    api_key: String,
+    model: String,
+    client: Client,
+}
+
+#[derive(Debug, Serialize)]
+struct RerankRequest<'a> {
+    model: &'a str,
+    query: &'a str,
+    documents: Vec<&'a str>,
+    top_n: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct RerankResponse {
+    results: Vec<RerankResponseItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RerankResponseItem {
+    index: usize,
+    relevance_score: f64,
+}
+
+impl ApiReranker {
+    /// Creates a reranker for any endpoint that speaks the Cohere rerank format.
+    pub fn new(
+        api_url: impl Into<String>,
+        api_key: impl Into<String>,
+        model: impl Into<String>,
+    ) -> Self {
+        Self {
+            api_url: api_url.into(),
+            api_key: api_key.into(),
+            model: model.into(),
+            client: Client::new(),
+        }
+    }
+
+    /// Creates a reranker for Cohere's `rerank-v3.5` model.
+    pub fn cohere(api_key: impl Into<String>) -> Self {
+        Self::new("https://api.cohere.com/v2/rerank", api_key, "rerank-v3.5")
+    }
+
+    /// Creates a reranker for Jina's multilingual reranker.
+    pub fn jina(api_key: impl Into<String>) -> Self {
+        Self::new(
+            "https://api.jina.ai/v1/rerank",
+            api_key,
+            "jina-reranker-v2-base-multilingual",
+        )
+    }
+
+    /// Uses a different model on the same endpoint.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+}
+
+#[async_trait]
+impl Reranker for ApiReranker {
+    async fn rerank(
+        &self,
+        query: &str,
+        results: Vec<SearchResult>,
+        top_n: usize,
+    ) -> Result<Vec<SearchResult>> {
+        if results.is_empty() || top_n == 0 {
+            return Ok(Vec::new());
+        }
+
+        let request = RerankRequest {
+            model: &self.model,
+            query,
+            documents: results.iter().map(|r| r.text.as_str()).collect(),
+            top_n: top_n.min(results.len()),
+        };
+
+        let response = self
+            .client
+            .post(&self.api_url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Rerank API error: {}", e)))?;
+
+        if !response.status().is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(HeliosError::ToolError(format!(
+                "Rerank API failed: {}",
+                error_text
+            )));
+        }
+
+        let rerank_response: RerankResponse = response.json().await.map_err(|e| {
+            HeliosError::ToolError(format!("Failed to parse rerank response: {}", e))
+        })?;
+
+        let mut slots: Vec<Option<SearchResult>> = results.into_iter().map(Some).collect();
+        let reranked = rerank_response
+            .results
+            .into_iter()
+            .filter_map(|item| {
+                let mut result = slots.get_mut(item.index)?.take()?;
+                result.score = item.relevance_score;
+                Some(result)
+            })
+            .collect();
+        Ok(sort_and_truncate(reranked, top_n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LLMConfig;
+    use crate::mock::MockLLMProvider;
+
+    fn result(id: &str) -> SearchResult {
+        SearchResult {
+            id: id.to_string(),
+            score: 0.9,
+            text: format!("text of {}", id),
+            metadata: None,
+        }
+    }
+
+    /// Tests that the LLM reranker orders results by the scores in the model's reply.
+    #[tokio::test]
+    async fn test_llm_reranker() {
+        let client = LLMClient::from_provider(
+            MockLLMProvider::new().with_response("Scores: [2, 9, \"5\"]"),
+            LLMConfig::default(),
+        );
+        let reranker = LlmReranker::new(client);
+
+        let reranked = reranker
+            .rerank("query", vec![result("a"), result("b"), result("c")], 2)
+            .await
+            .unwrap();
+        let ids: Vec<_> = reranked.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "c"]);
+        assert!((reranked[0].score - 0.9).abs() < 1e-9);
+
+        assert_eq!(parse_scores("no scores here"), None);
+    }
+}
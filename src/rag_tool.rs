@@ -3,6 +3,7 @@
 //! Provides a Tool implementation that wraps the RAG system for agent use.
 
 use crate::error::{HeliosError, Result};
+use crate::rag::filter::MetadataFilter;
 use crate::rag::{
     InMemoryVectorStore, OpenAIEmbeddings, QdrantVectorStore, RAGSystem, SearchResult,
 };
@@ -147,6 +148,17 @@ impl Tool for RAGTool {
                 required: Some(false),
             },
         );
+        params.insert(
+            "filter".to_string(),
+            ToolParameter {
+                param_type: "object".to_string(),
+                description: "Restrict search to documents whose metadata matches, e.g. \
+                              {\"source\": \"guide.pdf\", \"tags\": {\"any\": [\"api\"]}, \
+                              \"timestamp\": {\"gte\": \"2024-01-01\", \"lt\": \"2024-07-01\"}}"
+                    .to_string(),
+                required: Some(false),
+            },
+        );
         params
     }
 
@@ -186,7 +198,17 @@ impl Tool for RAGTool {
 
                 let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(5) as usize;
 
-                let results = self.rag_system.search(query, limit).await?;
+                let results = match args.get("filter").filter(|v| !v.is_null()) {
+                    Some(filter) => {
+                        let filter = MetadataFilter::from_json(filter).map_err(|e| {
+                            HeliosError::ToolError(format!("Invalid 'filter': {}", e))
+                        })?;
+                        self.rag_system
+                            .search_filtered(query, limit, &filter)
+                            .await?
+                    }
+                    None => self.rag_system.search(query, limit).await?,
+                };
                 Ok(ToolResult::success(self.format_results(&results)))
             }
             "delete" => {
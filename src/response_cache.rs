@@ -0,0 +1,449 @@
+//! # Response Cache Module
+//!
+//! This module provides an optional cache for LLM responses. Requests are keyed
+//! on a normalized form of the model, messages, tools and generation parameters,
+//! so re-sending an identical prompt is answered without calling the provider.
+//!
+//! Entries live in an in-memory LRU and, when a directory is configured, in a
+//! disk backend that survives restarts. Both honour a time-to-live and a size
+//! limit.
+
+use crate::chat::ChatMessage;
+use crate::error::Result;
+use crate::llm::LLMRequest;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Configuration for the response cache.
+///
+/// ```toml
+/// [llm.cache]
+/// enabled = true
+/// max_entries = 1000
+/// ttl_secs = 86400
+/// disk_path = ".helios/cache"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseCacheConfig {
+    /// Whether responses are cached at all.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The maximum number of responses kept in memory.
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+    /// How long a cached response stays valid, in seconds. `0` keeps entries forever.
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+    /// A directory in which responses are also stored on disk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_path: Option<PathBuf>,
+    /// The maximum total size of the disk cache in bytes; the oldest entries are removed first.
+    #[serde(default = "default_max_disk_bytes")]
+    pub max_disk_bytes: u64,
+    /// Only cache requests sent with a temperature of zero.
+    ///
+    /// Sampled responses differ between calls, so caching them changes behaviour.
+    #[serde(default = "default_deterministic_only")]
+    pub deterministic_only: bool,
+}
+
+/// Returns the default number of in-memory entries.
+fn default_max_entries() -> usize {
+    1000
+}
+
+/// Returns the default time-to-live in seconds.
+fn default_ttl_secs() -> u64 {
+    86_400
+}
+
+/// Returns the default disk cache size limit in bytes.
+fn default_max_disk_bytes() -> u64 {
+    100 * 1024 * 1024
+}
+
+/// Returns whether only deterministic requests are cached by default.
+fn default_deterministic_only() -> bool {
+    true
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: default_max_entries(),
+            ttl_secs: default_ttl_secs(),
+            disk_path: None,
+            max_disk_bytes: default_max_disk_bytes(),
+            deterministic_only: default_deterministic_only(),
+        }
+    }
+}
+
+impl ResponseCacheConfig {
+    /// Returns an enabled in-memory cache configuration with default limits.
+    pub fn in_memory() -> Self {
+        Self {
+            enabled: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns an enabled configuration that also persists responses under `path`.
+    pub fn on_disk(path: impl Into<PathBuf>) -> Self {
+        Self {
+            enabled: true,
+            disk_path: Some(path.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the time-to-live, or `None` if entries never expire.
+    pub fn ttl(&self) -> Option<Duration> {
+        (self.ttl_secs > 0).then(|| Duration::from_secs(self.ttl_secs))
+    }
+}
+
+/// Hit and miss counters for a response cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of lookups answered from the cache.
+    pub hits: u64,
+    /// The number of lookups that had to call the provider.
+    pub misses: u64,
+}
+
+/// A cached response as stored in memory and on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Seconds since the Unix epoch at which the response was stored.
+    created_at: u64,
+    /// The cached assistant message.
+    message: ChatMessage,
+}
+
+/// The in-memory LRU and counters shared by clones of a `ResponseCache`.
+#[derive(Debug, Default)]
+struct MemoryCache {
+    entries: HashMap<String, (CacheEntry, u64)>,
+    clock: u64,
+    stats: CacheStats,
+}
+
+/// A cache of LLM responses keyed on normalized requests.
+///
+/// Clones share the same entries.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    config: ResponseCacheConfig,
+    inner: Arc<Mutex<MemoryCache>>,
+}
+
+impl ResponseCache {
+    /// Creates a cache with the given configuration.
+    pub fn new(config: ResponseCacheConfig) -> Self {
+        Self {
+            config,
+            inner: Arc::new(Mutex::new(MemoryCache::default())),
+        }
+    }
+
+    /// Returns the cache configuration.
+    pub fn config(&self) -> &ResponseCacheConfig {
+        &self.config
+    }
+
+    /// Returns the cache key for a request, or `None` if the request should not be cached.
+    ///
+    /// The key is a SHA-256 digest of the model, the messages with surrounding
+    /// whitespace trimmed, the tool definitions and the generation parameters.
+    pub fn key_for(&self, request: &LLMRequest) -> Option<String> {
+        if !self.config.enabled {
+            return None;
+        }
+        if self.config.deterministic_only && request.temperature.unwrap_or(0.0) != 0.0 {
+            return None;
+        }
+
+        let messages: Vec<serde_json::Value> = request
+            .messages
+            .iter()
+            .map(|message| {
+                let mut message = message.clone();
+                message.content = message.content.trim().to_string();
+                serde_json::to_value(message).unwrap_or_default()
+            })
+            .collect();
+        // Converting to `Value` sorts object keys, so map ordering does not change the key.
+        let mut normalized = serde_json::json!({
+            "model": request.model,
+            "messages": messages,
+            "tools": request.tools,
+            "temperature": request.temperature,
+            "max_tokens": request.max_tokens,
+            "stop": request.stop,
+        });
+        // Only constrained requests carry a grammar, so existing keys stay valid.
+        if let Some(grammar) = &request.grammar {
+            normalized["grammar"] = grammar.as_gbnf().into();
+        }
+
+        let digest = Sha256::digest(normalized.to_string().as_bytes());
+        Some(format!("{:x}", digest))
+    }
+
+    /// Looks up a cached response, checking memory first and then disk.
+    pub fn get(&self, key: &str) -> Option<ChatMessage> {
+        let now = unix_now();
+        let mut inner = self.lock();
+        inner.clock += 1;
+        let clock = inner.clock;
+
+        let in_memory = match inner.entries.get_mut(key) {
+            Some((entry, _)) if self.is_expired(entry, now) => None,
+            Some((entry, last_used)) => {
+                *last_used = clock;
+                Some(entry.message.clone())
+            }
+            None => None,
+        };
+        if let Some(message) = in_memory {
+            inner.stats.hits += 1;
+            return Some(message);
+        }
+        inner.entries.remove(key);
+
+        match self
+            .read_disk(key)
+            .filter(|entry| !self.is_expired(entry, now))
+        {
+            Some(entry) => {
+                inner.stats.hits += 1;
+                let message = entry.message.clone();
+                self.insert_memory(&mut inner, key, entry);
+                Some(message)
+            }
+            None => {
+                inner.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Stores a response under `key`.
+    pub fn put(&self, key: &str, message: &ChatMessage) {
+        let entry = CacheEntry {
+            created_at: unix_now(),
+            message: message.clone(),
+        };
+        if let Err(e) = self.write_disk(key, &entry) {
+            tracing::warn!("Failed to write response cache entry: {}", e);
+        }
+        let mut inner = self.lock();
+        self.insert_memory(&mut inner, key, entry);
+    }
+
+    /// Removes every cached response from memory and disk.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+        if let Some(dir) = &self.config.disk_path {
+            for (path, _, _) in disk_entries(dir) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    /// Returns the number of responses held in memory.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns `true` if no responses are held in memory.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the hit and miss counters.
+    pub fn stats(&self) -> CacheStats {
+        self.lock().stats
+    }
+
+    fn is_expired(&self, entry: &CacheEntry, now: u64) -> bool {
+        self.config.ttl_secs > 0 && now.saturating_sub(entry.created_at) >= self.config.ttl_secs
+    }
+
+    fn insert_memory(&self, inner: &mut MemoryCache, key: &str, entry: CacheEntry) {
+        if self.config.max_entries == 0 {
+            return;
+        }
+        inner.clock += 1;
+        let clock = inner.clock;
+        inner.entries.insert(key.to_string(), (entry, clock));
+
+        while inner.entries.len() > self.config.max_entries {
+            let oldest = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => inner.entries.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+
+    fn read_disk(&self, key: &str) -> Option<CacheEntry> {
+        let dir = self.config.disk_path.as_ref()?;
+        let content = std::fs::read_to_string(dir.join(format!("{}.json", key))).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn write_disk(&self, key: &str, entry: &CacheEntry) -> Result<()> {
+        let Some(dir) = &self.config.disk_path else {
+            return Ok(());
+        };
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(
+            dir.join(format!("{}.json", key)),
+            serde_json::to_vec(entry)?,
+        )?;
+
+        // Trim the oldest files once the directory grows past its budget.
+        let mut files = disk_entries(dir);
+        let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+        if total > self.config.max_disk_bytes {
+            files.sort_by_key(|(_, _, modified)| *modified);
+            for (path, size, _) in files {
+                if total <= self.config.max_disk_bytes {
+                    break;
+                }
+                if std::fs::remove_file(&path).is_ok() {
+                    total = total.saturating_sub(size);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MemoryCache> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Lists the cache files in `dir` with their size and modification time.
+fn disk_entries(dir: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    read_dir
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((
+                entry.path(),
+                metadata.len(),
+                metadata.modified().unwrap_or(UNIX_EPOCH),
+            ))
+        })
+        .collect()
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(content: &str, temperature: f32) -> LLMRequest {
+        LLMRequest {
+            model: "mock".to_string(),
+            messages: vec![ChatMessage::user(content)],
+            temperature: Some(temperature),
+            max_tokens: Some(100),
+            max_completion_tokens: None,
+            tools: None,
+            tool_choice: None,
+            stream: None,
+            stop: None,
+            grammar: None,
+            logprobs: None,
+            top_logprobs: None,
+        }
+    }
+
+    /// Tests that keys are stable under whitespace and skip sampled requests.
+    #[test]
+    fn test_key_normalization() {
+        let cache = ResponseCache::new(ResponseCacheConfig::in_memory());
+        let key = cache.key_for(&request("hello", 0.0)).unwrap();
+        assert_eq!(cache.key_for(&request("  hello\n", 0.0)), Some(key.clone()));
+        assert_ne!(cache.key_for(&request("goodbye", 0.0)), Some(key.clone()));
+        assert!(cache.key_for(&request("hello", 0.7)).is_none());
+
+        let mut constrained = request("hello", 0.0);
+        constrained.grammar = Some(crate::grammar::Grammar::json());
+        assert_ne!(cache.key_for(&constrained), Some(key));
+
+        let disabled = ResponseCache::new(ResponseCacheConfig::default());
+        assert!(disabled.key_for(&request("hello", 0.0)).is_none());
+    }
+
+    /// Tests that the least recently used entry is evicted first.
+    #[test]
+    fn test_lru_eviction() {
+        let cache = ResponseCache::new(ResponseCacheConfig {
+            max_entries: 2,
+            ..ResponseCacheConfig::in_memory()
+        });
+        cache.put("a", &ChatMessage::assistant("A"));
+        cache.put("b", &ChatMessage::assistant("B"));
+        assert!(cache.get("a").is_some());
+        cache.put("c", &ChatMessage::assistant("C"));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").unwrap().content, "A");
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 1 });
+    }
+
+    /// Tests that entries persist on disk and that expired entries are ignored.
+    #[test]
+    fn test_disk_backend_and_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResponseCache::new(ResponseCacheConfig::on_disk(dir.path()));
+        cache.put("key", &ChatMessage::assistant("cached"));
+
+        let reopened = ResponseCache::new(ResponseCacheConfig::on_disk(dir.path()));
+        assert_eq!(reopened.get("key").unwrap().content, "cached");
+
+        let stale = CacheEntry {
+            created_at: unix_now() - 10,
+            message: ChatMessage::assistant("old"),
+        };
+        std::fs::write(
+            dir.path().join("stale.json"),
+            serde_json::to_vec(&stale).unwrap(),
+        )
+        .unwrap();
+        let short_ttl = ResponseCache::new(ResponseCacheConfig {
+            ttl_secs: 5,
+            ..ResponseCacheConfig::on_disk(dir.path())
+        });
+        assert!(short_ttl.get("stale").is_none());
+
+        short_ttl.clear();
+        assert!(reopened.read_disk("key").is_none());
+    }
+}
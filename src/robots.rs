@@ -0,0 +1,237 @@
+//! # Robots Module
+//!
+//! This module parses `robots.txt` files so the web scraper can honour the crawling
+//! rules a site publishes. It implements the parts of RFC 9309 that matter for a
+//! single-page fetcher: user-agent groups, `Allow`/`Disallow` rules with `*` and `$`
+//! patterns (the longest match wins, `Allow` wins ties) and the common
+//! `Crawl-delay` extension.
+
+use std::time::Duration;
+
+/// A single `Allow` or `Disallow` rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    pattern: String,
+    allow: bool,
+}
+
+/// The rules from one group of `User-agent` lines.
+#[derive(Debug, Clone, Default)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+/// A parsed `robots.txt` file.
+#[derive(Debug, Clone, Default)]
+pub struct RobotsTxt {
+    groups: Vec<Group>,
+}
+
+impl RobotsTxt {
+    /// Parses the contents of a `robots.txt` file.
+    ///
+    /// Unknown fields and malformed lines are ignored, as the standard requires.
+    pub fn parse(content: &str) -> Self {
+        let mut groups: Vec<Group> = Vec::new();
+        let mut current: Option<Group> = None;
+        let mut in_agent_lines = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let field = field.trim().to_ascii_lowercase();
+            let value = value.trim();
+
+            match field.as_str() {
+                "user-agent" => {
+                    // Consecutive user-agent lines share one group.
+                    if !in_agent_lines {
+                        groups.extend(current.take());
+                        current = Some(Group::default());
+                    }
+                    if let Some(group) = current.as_mut() {
+                        group.agents.push(value.to_ascii_lowercase());
+                    }
+                    in_agent_lines = true;
+                }
+                "allow" | "disallow" => {
+                    in_agent_lines = false;
+                    // An empty `Disallow` allows everything, so it adds no rule.
+                    if let (Some(group), false) = (current.as_mut(), value.is_empty()) {
+                        group.rules.push(Rule {
+                            pattern: value.to_string(),
+                            allow: field == "allow",
+                        });
+                    }
+                }
+                "crawl-delay" => {
+                    in_agent_lines = false;
+                    if let (Some(group), Ok(secs)) = (current.as_mut(), value.parse::<f64>()) {
+                        if secs.is_finite() && secs >= 0.0 {
+                            group.crawl_delay = Some(Duration::from_secs_f64(secs));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        groups.extend(current);
+
+        Self { groups }
+    }
+
+    /// A `robots.txt` that allows every path, used when a site has none.
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// A `robots.txt` that forbids every path, used when a site's file cannot be read.
+    pub fn disallow_all() -> Self {
+        Self {
+            groups: vec![Group {
+                agents: vec!["*".to_string()],
+                rules: vec![Rule {
+                    pattern: "/".to_string(),
+                    allow: false,
+                }],
+                crawl_delay: None,
+            }],
+        }
+    }
+
+    /// Returns whether `user_agent` may fetch `path`.
+    ///
+    /// `path` is the URL path including any query string, e.g. `/search?q=rust`.
+    pub fn is_allowed(&self, user_agent: &str, path: &str) -> bool {
+        let Some(group) = self.group_for(user_agent) else {
+            return true;
+        };
+
+        let mut best: Option<&Rule> = None;
+        for rule in &group.rules {
+            if !pattern_matches(&rule.pattern, path) {
+                continue;
+            }
+            best = match best {
+                Some(b)
+                    if b.pattern.len() > rule.pattern.len()
+                        || (b.pattern.len() == rule.pattern.len() && b.allow) =>
+                {
+                    Some(b)
+                }
+                _ => Some(rule),
+            };
+        }
+
+        best.map_or(true, |rule| rule.allow)
+    }
+
+    /// Returns the `Crawl-delay` the site asks `user_agent` to observe, if any.
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<Duration> {
+        self.group_for(user_agent)
+            .and_then(|group| group.crawl_delay)
+    }
+
+    /// Finds the group that applies to `user_agent`, falling back to `*`.
+    fn group_for(&self, user_agent: &str) -> Option<&Group> {
+        // Groups name the product token, e.g. "Helios-WebScraper" for
+        // "Helios-WebScraper/1.0 (+https://example.com)".
+        let product = user_agent
+            .split(['/', ' '])
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        self.groups
+            .iter()
+            .find(|group| {
+                group
+                    .agents
+                    .iter()
+                    .any(|agent| agent != "*" && !product.is_empty() && *agent == product)
+            })
+            .or_else(|| {
+                self.groups
+                    .iter()
+                    .find(|group| group.agents.iter().any(|agent| agent == "*"))
+            })
+    }
+}
+
+/// Matches a robots.txt path pattern, supporting `*` wildcards and a trailing `$`.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        let is_last = i == parts.len() - 1;
+        if is_last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    !anchored || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS: &str = "\
+# Example robots.txt
+User-agent: *
+Disallow: /private/
+Allow: /private/public-page
+Disallow: /*.pdf$
+Crawl-delay: 2
+
+User-agent: Helios-WebScraper
+User-agent: OtherBot
+Disallow: /no-helios
+";
+
+    /// Tests group selection, longest-match precedence and wildcard rules.
+    #[test]
+    fn test_robots_rules() {
+        let robots = RobotsTxt::parse(ROBOTS);
+        let generic = "SomeBot/2.0";
+
+        assert!(robots.is_allowed(generic, "/"));
+        assert!(!robots.is_allowed(generic, "/private/secret"));
+        assert!(robots.is_allowed(generic, "/private/public-page"));
+        assert!(!robots.is_allowed(generic, "/docs/report.pdf"));
+        assert!(robots.is_allowed(generic, "/docs/report.pdf?download=1"));
+        assert_eq!(robots.crawl_delay(generic), Some(Duration::from_secs(2)));
+
+        // The named group replaces the `*` group entirely.
+        let helios = "Helios-WebScraper/1.0";
+        assert!(robots.is_allowed(helios, "/private/secret"));
+        assert!(!robots.is_allowed(helios, "/no-helios/page"));
+        assert_eq!(robots.crawl_delay(helios), None);
+    }
+
+    /// Tests the allow-all and disallow-all fallbacks.
+    #[test]
+    fn test_robots_fallbacks() {
+        assert!(RobotsTxt::allow_all().is_allowed("bot", "/anything"));
+        assert!(!RobotsTxt::disallow_all().is_allowed("bot", "/anything"));
+        assert!(RobotsTxt::parse("User-agent: *\nDisallow:\n").is_allowed("bot", "/x"));
+    }
+}
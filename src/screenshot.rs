@@ -0,0 +1,395 @@
+//! # Screenshot Module
+//!
+//! `ScreenshotTool` saves a PNG of the screen or of the focused window, so a desktop
+//! assistant can pass what the user sees to a vision model or attach it to a report.
+//!
+//! Capturing uses the platform's own tools instead of linking against display
+//! libraries: `screencapture` on macOS, PowerShell with .NET on Windows, and the
+//! first of `gnome-screenshot`, `spectacle`, `grim`, `scrot`, `maim` or ImageMagick's
+//! `import` found on other systems (only the first three work under Wayland). Any
+//! other program can be set with [`ScreenshotTool::command`].
+//!
+//! Requires the `screenshot` feature.
+
+use crate::error::{HeliosError, Result};
+use crate::footprint::format_bytes;
+use crate::tools::{Tool, ToolParameter, ToolResult};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+/// A screenshot program for Linux and the BSDs.
+struct Capturer {
+    program: &'static str,
+    /// Arguments that capture every screen; `{path}` is replaced by the output file.
+    screen: &'static [&'static str],
+    /// Arguments that capture the focused window, if the program can.
+    window: Option<&'static [&'static str]>,
+    /// Whether the program works in a Wayland session.
+    wayland: bool,
+}
+
+/// Screenshot programs in order of preference.
+#[rustfmt::skip]
+const CAPTURERS: &[Capturer] = &[
+    Capturer { program: "gnome-screenshot", screen: &["-f", "{path}"], window: Some(&["-w", "-f", "{path}"]), wayland: true },
+    Capturer { program: "spectacle", screen: &["-b", "-n", "-f", "-o", "{path}"], window: Some(&["-b", "-n", "-a", "-o", "{path}"]), wayland: true },
+    Capturer { program: "grim", screen: &["{path}"], window: None, wayland: true },
+    Capturer { program: "scrot", screen: &["{path}"], window: Some(&["-u", "{path}"]), wayland: false },
+    Capturer { program: "maim", screen: &["{path}"], window: None, wayland: false },
+    Capturer { program: "import", screen: &["-window", "root", "{path}"], window: None, wayland: false },
+];
+
+/// Prints the position and size of the frontmost window as `x, y, width, height`.
+const MACOS_FRONT_WINDOW: &str = "tell application \"System Events\" to tell \
+    (first application process whose frontmost is true) to get {position, size} of front window";
+
+/// Captures the virtual screen or the foreground window to `$env:HELIOS_SCREENSHOT_PATH`.
+const WINDOWS_CAPTURE: &str = r#"
+Add-Type -AssemblyName System.Windows.Forms, System.Drawing
+Add-Type @"
+using System;
+using System.Runtime.InteropServices;
+public static class HeliosWindow {
+    public struct Rect { public int Left, Top, Right, Bottom; }
+    [DllImport("user32.dll")] public static extern IntPtr GetForegroundWindow();
+    [DllImport("user32.dll")] public static extern bool GetWindowRect(IntPtr window, out Rect rect);
+}
+"@
+if ($env:HELIOS_SCREENSHOT_TARGET -eq 'window') {
+    $rect = New-Object HeliosWindow+Rect
+    [void][HeliosWindow]::GetWindowRect([HeliosWindow]::GetForegroundWindow(), [ref]$rect)
+    $bounds = [System.Drawing.Rectangle]::FromLTRB($rect.Left, $rect.Top, $rect.Right, $rect.Bottom)
+} else {
+    $bounds = [System.Windows.Forms.SystemInformation]::VirtualScreen
+}
+$bitmap = New-Object System.Drawing.Bitmap $bounds.Width, $bounds.Height
+$graphics = [System.Drawing.Graphics]::FromImage($bitmap)
+$graphics.CopyFromScreen($bounds.Location, [System.Drawing.Point]::Empty, $bounds.Size)
+$bitmap.Save($env:HELIOS_SCREENSHOT_PATH, [System.Drawing.Imaging.ImageFormat]::Png)
+"#;
+
+/// A tool that captures the screen or the focused window to a PNG file.
+///
+/// ```rust,no_run
+/// use helios_engine::ScreenshotTool;
+///
+/// let screenshots = ScreenshotTool::new().output_dir("./captures");
+///
+/// // Use a program this module does not know, such as `flameshot`.
+/// let custom = ScreenshotTool::new().command("flameshot", ["full", "--path", "{path}"]);
+/// ```
+pub struct ScreenshotTool {
+    output_dir: PathBuf,
+    command: Option<(String, Vec<String>)>,
+    timeout: Duration,
+}
+
+impl ScreenshotTool {
+    /// Creates a tool that saves screenshots to the temporary directory.
+    pub fn new() -> Self {
+        Self {
+            output_dir: std::env::temp_dir(),
+            command: None,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Sets the directory screenshots are saved to when a call gives no `path`.
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = dir.into();
+        self
+    }
+
+    /// Captures with `program` instead of the platform default.
+    ///
+    /// In `args`, `{path}` is replaced by the output file and `{target}` by `screen`
+    /// or `window`.
+    pub fn command<I, S>(mut self, program: impl Into<String>, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.command = Some((program.into(), args.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Limits how long a capture may take (30 seconds by default).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the file to write, refusing to replace an existing one.
+    fn output_path(&self, path: Option<&str>) -> Result<PathBuf> {
+        let name = format!(
+            "screenshot-{}.png",
+            chrono::Local::now().format("%Y%m%d-%H%M%S-%3f")
+        );
+        let mut path = match path {
+            Some(path) if Path::new(path).is_dir() => Path::new(path).join(name),
+            Some(path) => PathBuf::from(path),
+            None => self.output_dir.join(name),
+        };
+        if path.extension().is_none() {
+            path.set_extension("png");
+        }
+        if path.exists() {
+            return Err(HeliosError::ToolError(format!(
+                "'{}' already exists",
+                path.display()
+            )));
+        }
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(path)
+    }
+
+    /// Builds the command that captures `target` into `path`.
+    async fn capture_command(&self, target: &str, path: &Path) -> Result<tokio::process::Command> {
+        let path_text = path.to_string_lossy();
+        let fill = |arg: &str| {
+            arg.replace("{path}", &path_text)
+                .replace("{target}", target)
+        };
+
+        if let Some((program, args)) = &self.command {
+            let mut command = tokio::process::Command::new(program);
+            command.args(args.iter().map(|arg| fill(arg)));
+            return Ok(command);
+        }
+
+        if cfg!(target_os = "macos") {
+            let mut command = tokio::process::Command::new("screencapture");
+            command.arg("-x");
+            if target == "window" {
+                command.arg(format!("-R{}", self.macos_front_window().await?));
+            }
+            command.arg(path);
+            return Ok(command);
+        }
+
+        if cfg!(windows) {
+            let mut command = tokio::process::Command::new("powershell");
+            command
+                .args(["-NoProfile", "-NonInteractive", "-Command", WINDOWS_CAPTURE])
+                .env("HELIOS_SCREENSHOT_PATH", path)
+                .env("HELIOS_SCREENSHOT_TARGET", target);
+            return Ok(command);
+        }
+
+        let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+        let usable: Vec<&Capturer> = CAPTURERS
+            .iter()
+            .filter(|c| c.wayland || !wayland)
+            .filter(|c| target == "screen" || c.window.is_some())
+            .collect();
+        let capturer = usable.iter().find(|c| on_path(c.program)).ok_or_else(|| {
+            let names: Vec<&str> = usable.iter().map(|c| c.program).collect();
+            HeliosError::ToolError(format!(
+                "No screenshot program found for the {}; install one of: {}",
+                target,
+                names.join(", ")
+            ))
+        })?;
+        let args = match target {
+            "window" => capturer.window.unwrap_or(capturer.screen),
+            _ => capturer.screen,
+        };
+        let mut command = tokio::process::Command::new(capturer.program);
+        command.args(args.iter().map(|arg| fill(arg)));
+        Ok(command)
+    }
+
+    /// Returns the frontmost window's bounds as `x,y,width,height`.
+    async fn macos_front_window(&self) -> Result<String> {
+        let output = tokio::process::Command::new("osascript")
+            .args(["-e", MACOS_FRONT_WINDOW])
+            .output()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Failed to run osascript: {}", e)))?;
+        let bounds = String::from_utf8_lossy(&output.stdout).replace(' ', "");
+        let bounds = bounds.trim();
+        if !output.status.success() || bounds.split(',').count() != 4 {
+            return Err(HeliosError::ToolError(format!(
+                "Could not find the front window: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(bounds.to_string())
+    }
+
+    async fn capture(&self, target: &str, path: Option<&str>) -> Result<String> {
+        let path = self.output_path(path)?;
+        let mut command = self.capture_command(target, &path).await?;
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let child = command.spawn().map_err(|e| {
+            HeliosError::ToolError(format!("Failed to start the screenshot program: {}", e))
+        })?;
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| {
+                HeliosError::ToolError(format!(
+                    "The screenshot program did not finish within {} seconds",
+                    self.timeout.as_secs()
+                ))
+            })??;
+
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if !output.status.success() || size == 0 {
+            let _ = std::fs::remove_file(&path);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(HeliosError::ToolError(format!(
+                "Taking the screenshot failed ({}): {}",
+                output.status,
+                stderr.trim()
+            )));
+        }
+        Ok(format!(
+            "Saved a screenshot of the {} to {} ({})",
+            target,
+            path.display(),
+            format_bytes(size as usize)
+        ))
+    }
+}
+
+impl Default for ScreenshotTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns whether `program` is an executable file in a `PATH` directory.
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+#[async_trait]
+impl Tool for ScreenshotTool {
+    fn name(&self) -> &str {
+        "screenshot"
+    }
+
+    fn description(&self) -> &str {
+        "Take a screenshot of the whole screen or the focused window and save it as a PNG file."
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        let mut params = HashMap::new();
+        params.insert(
+            "target".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "'screen' (default) or 'window' for the focused window".to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "path".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "File or directory to save to (default: a new file in the \
+                              screenshot directory)"
+                    .to_string(),
+                required: Some(false),
+            },
+        );
+        params
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let target = args
+            .get("target")
+            .and_then(Value::as_str)
+            .unwrap_or("screen");
+        if target != "screen" && target != "window" {
+            return Ok(ToolResult::error(format!(
+                "Unknown target '{}'; use 'screen' or 'window'",
+                target
+            )));
+        }
+        Ok(
+            match self
+                .capture(target, args.get("path").and_then(Value::as_str))
+                .await
+            {
+                Ok(output) => ToolResult::success(output),
+                Err(e) => ToolResult::error(e.to_string()),
+            },
+        )
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Tests capturing with a custom command and where screenshots are saved.
+    #[tokio::test]
+    async fn test_screenshot_tool_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = ScreenshotTool::new()
+            .output_dir(dir.path().join("shots"))
+            .command(
+                "sh",
+                [
+                    "-c",
+                    "printf '%s' \"$1\" > \"$2\"",
+                    "sh",
+                    "{target}",
+                    "{path}",
+                ],
+            );
+
+        let result = tool.execute(json!({})).await.unwrap();
+        assert!(result.success, "{}", result.output);
+        assert!(result
+            .output
+            .starts_with("Saved a screenshot of the screen to "));
+        let saved: Vec<_> = std::fs::read_dir(dir.path().join("shots"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].extension().unwrap(), "png");
+        assert_eq!(std::fs::read_to_string(&saved[0]).unwrap(), "screen");
+
+        let path = dir.path().join("window");
+        let result = tool
+            .execute(json!({"target": "window", "path": path}))
+            .await
+            .unwrap();
+        assert!(result.success, "{}", result.output);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("window.png")).unwrap(),
+            "window"
+        );
+        let result = tool
+            .execute(json!({"target": "window", "path": path}))
+            .await
+            .unwrap();
+        assert!(result.output.contains("already exists"));
+
+        let failing = ScreenshotTool::new()
+            .output_dir(dir.path())
+            .command("sh", ["-c", "echo 'cannot open display' >&2; exit 1"]);
+        let result = failing.execute(json!({"target": "screen"})).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("cannot open display"));
+
+        let result = tool.execute(json!({"target": "desk"})).await.unwrap();
+        assert!(!result.success);
+    }
+}
@@ -0,0 +1,305 @@
+//! # Semantic Cache Module
+//!
+//! This module provides a cache of agent answers keyed on the meaning of the
+//! question rather than its exact text. Questions are embedded with any
+//! [`EmbeddingProvider`] and kept in any RAG [`VectorStore`], so "What's the
+//! capital of France?" can be answered from an earlier "what is the capital of
+//! france". A hit either becomes the reply directly, skipping the model, or is
+//! handed to the model as a previous answer it may reuse.
+//!
+//! Entries are tagged in their metadata, so a store can also hold other data,
+//! but a [`RAGSystem`](crate::RAGSystem) searching the same store would see them.
+//! Give the cache its own store, collection or table.
+
+use crate::error::Result;
+use crate::rag::filter::MetadataFilter;
+use crate::rag::{EmbeddingProvider, VectorStore};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The `kind` metadata value that marks cache entries in a vector store.
+const ENTRY_KIND: &str = "semantic_cache";
+
+/// What an agent does with a cached answer to a similar question.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SemanticCacheMode {
+    /// Reply with the cached answer without calling the model.
+    #[default]
+    Answer,
+    /// Call the model as usual, showing it the cached answer to reuse or correct.
+    Prime,
+}
+
+/// An earlier answer to a question similar to the one being asked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedAnswer {
+    /// The question that was answered.
+    pub query: String,
+    /// The answer given to it.
+    pub answer: String,
+    /// How similar the questions are, from 0.0 to 1.0.
+    pub similarity: f64,
+}
+
+/// A cache of answers looked up by question similarity.
+///
+/// ```rust,no_run
+/// # use helios_engine::{InMemoryVectorStore, OpenAIEmbeddings, SemanticCache, SemanticCacheMode};
+/// # use std::time::Duration;
+/// let cache = SemanticCache::new(
+///     Box::new(OpenAIEmbeddings::new("https://api.openai.com/v1/embeddings", "sk-...")),
+///     Box::new(InMemoryVectorStore::new()),
+/// )
+/// .with_threshold(0.9)
+/// .with_mode(SemanticCacheMode::Prime)
+/// .with_ttl(Duration::from_secs(24 * 60 * 60));
+/// ```
+pub struct SemanticCache {
+    embedding_provider: Box<dyn EmbeddingProvider>,
+    vector_store: Box<dyn VectorStore>,
+    threshold: f64,
+    mode: SemanticCacheMode,
+    ttl: Option<Duration>,
+    namespace: String,
+    initialized: tokio::sync::OnceCell<()>,
+}
+
+impl SemanticCache {
+    /// Creates a cache that embeds questions with `embedding_provider` and keeps
+    /// them in `vector_store`.
+    ///
+    /// Questions must be at least 0.95 similar to hit, answers are returned
+    /// directly and never expire, until changed with the `with_` methods.
+    pub fn new(
+        embedding_provider: Box<dyn EmbeddingProvider>,
+        vector_store: Box<dyn VectorStore>,
+    ) -> Self {
+        Self {
+            embedding_provider,
+            vector_store,
+            threshold: 0.95,
+            mode: SemanticCacheMode::Answer,
+            ttl: None,
+            namespace: "default".to_string(),
+            initialized: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    /// Sets the similarity, from 0.0 to 1.0, a question needs to hit.
+    ///
+    /// Lower thresholds hit more often but risk answering a different question.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets what an agent does with a hit.
+    pub fn with_mode(mut self, mode: SemanticCacheMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Ignores and removes answers older than `ttl`.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Keeps entries apart from those of caches with other namespaces in the same store.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Returns what an agent does with a hit.
+    pub fn mode(&self) -> SemanticCacheMode {
+        self.mode
+    }
+
+    /// Returns the similarity a question needs to hit.
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// Initializes the vector store on first use.
+    async fn ensure_initialized(&self) -> Result<()> {
+        self.initialized
+            .get_or_try_init(|| async {
+                self.vector_store
+                    .initialize(self.embedding_provider.dimension())
+                    .await
+            })
+            .await
+            .map(|_| ())
+    }
+
+    /// Returns the filter matching this cache's entries.
+    fn filter(&self) -> MetadataFilter {
+        MetadataFilter::eq("kind", ENTRY_KIND)
+            .and(MetadataFilter::eq("namespace", self.namespace.as_str()))
+    }
+
+    /// Returns the cached answer to the question most similar to `query`, if it
+    /// is similar enough and has not expired.
+    pub async fn lookup(&self, query: &str) -> Result<Option<CachedAnswer>> {
+        self.ensure_initialized().await?;
+        let embedding = self.embedding_provider.embed(query).await?;
+        let Some(best) = self
+            .vector_store
+            .search_filtered(embedding, 1, &self.filter())
+            .await?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+        if best.score < self.threshold {
+            return Ok(None);
+        }
+
+        let metadata = best.metadata.unwrap_or_default();
+        if let Some(ttl) = self.ttl {
+            let cached_at = metadata
+                .get("cached_at")
+                .and_then(|value| value.as_str())
+                .and_then(|value| chrono::DateTime::parse_from_rfc3339(value).ok());
+            let expired = cached_at.map_or(true, |cached_at| {
+                crate::clock::now().signed_duration_since(cached_at)
+                    > chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX)
+            });
+            if expired {
+                self.vector_store.delete(&best.id).await?;
+                return Ok(None);
+            }
+        }
+
+        Ok(metadata
+            .get("answer")
+            .and_then(|answer| answer.as_str())
+            .map(|answer| CachedAnswer {
+                query: best.text,
+                answer: answer.to_string(),
+                similarity: best.score,
+            }))
+    }
+
+    /// Caches `answer` as the answer to `query`.
+    pub async fn store(&self, query: &str, answer: &str) -> Result<()> {
+        self.ensure_initialized().await?;
+        let embedding = self.embedding_provider.embed(query).await?;
+        let metadata = HashMap::from([
+            ("kind".to_string(), ENTRY_KIND.into()),
+            ("namespace".to_string(), self.namespace.clone().into()),
+            ("answer".to_string(), answer.into()),
+            (
+                "cached_at".to_string(),
+                crate::clock::now().to_rfc3339().into(),
+            ),
+        ]);
+        self.vector_store
+            .add(
+                &crate::clock::new_id().to_string(),
+                embedding,
+                query,
+                metadata,
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::clock::{FixedClock, SequentialIds};
+    use crate::rag::InMemoryVectorStore;
+    use async_trait::async_trait;
+    use std::sync::Arc;
+
+    /// Embeds text as counts of its lowercased words hashed into a few buckets.
+    pub(crate) struct WordEmbeddings;
+
+    #[async_trait]
+    impl EmbeddingProvider for WordEmbeddings {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            let mut embedding = vec![0.0; 16];
+            for word in text
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+            {
+                let bucket = word
+                    .to_lowercase()
+                    .bytes()
+                    .fold(7usize, |hash, byte| hash * 31 + byte as usize);
+                embedding[bucket % 16] += 1.0;
+            }
+            Ok(embedding)
+        }
+
+        fn dimension(&self) -> usize {
+            16
+        }
+    }
+
+    /// Tests hits, misses, namespaces and expiry.
+    #[tokio::test]
+    async fn test_semantic_cache() {
+        let clock = Arc::new(FixedClock::new(chrono::Utc::now()));
+        let ids = Arc::new(SequentialIds::default());
+        crate::clock::scope(clock.clone(), ids, async {
+            let cache = SemanticCache::new(
+                Box::new(WordEmbeddings),
+                Box::new(InMemoryVectorStore::new()),
+            )
+            .with_ttl(Duration::from_secs(60));
+            assert_eq!(
+                cache
+                    .lookup("What is the capital of France?")
+                    .await
+                    .unwrap(),
+                None
+            );
+
+            cache
+                .store("What is the capital of France?", "Paris")
+                .await
+                .unwrap();
+            let hit = cache
+                .lookup("what is the capital of france")
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(hit.answer, "Paris");
+            assert_eq!(hit.query, "What is the capital of France?");
+            assert!(hit.similarity > 0.99);
+            assert_eq!(
+                cache.lookup("How tall is Mount Everest?").await.unwrap(),
+                None
+            );
+
+            clock.advance(chrono::Duration::seconds(61));
+            assert_eq!(
+                cache
+                    .lookup("What is the capital of France?")
+                    .await
+                    .unwrap(),
+                None
+            );
+        })
+        .await;
+
+        let store = InMemoryVectorStore::new();
+        store
+            .add(
+                "doc",
+                vec![1.0; 16],
+                "What is the capital of France?",
+                HashMap::new(),
+            )
+            .await
+            .unwrap();
+        let cache = SemanticCache::new(Box::new(WordEmbeddings), Box::new(store))
+            .with_threshold(0.0)
+            .with_namespace("support");
+        assert_eq!(cache.lookup("anything").await.unwrap(), None);
+    }
+}
@@ -22,12 +22,83 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! ### Authentication
+//!
+//! With [`ServerState::with_api_keys`], every endpoint except the `/health`,
+//! `/healthz` and `/readyz` probes requires an `Authorization: Bearer <key>` header
+//! carrying one of the keys, as OpenAI clients send it.
+//!
+//! ### Probes and metrics
+//!
+//! `GET /healthz` answers while the process is up. `GET /readyz` answers `503`
+//! while the server is aborted or its provider is unreachable; see
+//! [`LLMClient::check_ready`]. `GET /metrics` exports the counters of
+//! [`ServerMetrics`](crate::serve::metrics::ServerMetrics) for Prometheus.
+//!
+//! ### Responses API
+//!
+//! `POST /v1/responses` serves clients of OpenAI's newer Responses API, streamed
+//! or not. The tools an agent runs are reported as `function_call` and
+//! `function_call_output` output items; see [`responses`](crate::serve::responses).
+//!
+//! ### Embeddings
+//!
+//! With [`ServerState::with_embeddings`], `POST /v1/embeddings` embeds text with
+//! any [`EmbeddingProvider`], in floats or, as OpenAI's SDKs ask by default,
+//! base64. A request may carry up to 2048 inputs, which are embedded a few at a
+//! time.
+//!
+//! ### Sessions
+//!
+//! `POST /v1/sessions` starts a conversation the server keeps, so clients send
+//! only each new message to `POST /v1/sessions/{id}/messages`. Idle sessions are
+//! evicted, and [`ServerState::with_sessions`] can persist them; see
+//! [`sessions`](crate::serve::sessions).
+//!
+//! ### Moderation
+//!
+//! Moderators added with [`ServerState::with_moderator`] check the last user
+//! message of each request and the final reply. A blocked request or reply is
+//! answered with an empty message and `finish_reason: "content_filter"`, as is one
+//! blocked by the served agent's own moderators. When the server has moderators,
+//! streamed replies are held back and sent as a single chunk once moderated.
+//!
+//! ### Slack
+//!
+//! With [`ServerState::with_slack`], `POST /slack/events` answers Slack's Events API:
+//! point the Slack app's event subscription there and subscribe to `app_mention`
+//! and `message.im`. Requests are checked against the app's signing secret rather
+//! than the server's API keys. Each thread is its own conversation with the agent,
+//! and replies are posted in the thread.
+//!
+//! ### Reloading the configuration
+//!
+//! [`ServerState::watch_config`] watches a config file and swaps in a client built
+//! from the new settings whenever it changes, so API keys can be rotated and
+//! models switched without restarting. Requests already running finish on the old
+//! client and streams stay open.
+//!
+//! ```no_run
+//! use helios_engine::{Config, serve::{self, ServerState}};
+//!
+//! # async fn example() -> helios_engine::Result<()> {
+//! let state = ServerState::from_config(&Config::from_file("config.toml")?).await?;
+//! let _watcher = state.watch_config("config.toml")?;
+//! serve::start_server_with_state(state, "127.0.0.1:8000", None).await?;
+//! # Ok(())
+//! # }
+//! ```
 
+use crate::abort::AbortHandle;
 use crate::agent::Agent;
 use crate::chat::{ChatMessage, Role};
 use crate::config::Config;
 use crate::error::{HeliosError, Result};
 use crate::llm::{LLMClient, LLMProviderType};
+use crate::messaging::{SlackEvents, SlackRequest};
+use crate::moderation::{self, ModerationTarget, Moderator};
+use crate::rag::EmbeddingProvider;
 use axum::{
     extract::State,
     http::StatusCode,
@@ -35,19 +106,27 @@ use axum::{
         sse::{Event, Sse},
         IntoResponse,
     },
-    routing::{delete, get, patch, post, put},
+    routing::{delete, get, patch, post, put, MethodRouter},
     Json, Router,
 };
 use futures::stream::Stream;
 use serde::{Deserialize, Serialize};
 use std::convert::Infallible;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use tokio_stream::wrappers::ReceiverStream;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
-use tracing::{error, info};
-use uuid::Uuid;
+use tracing::{error, info, warn};
+
+pub mod metrics;
+pub mod responses;
+pub mod sessions;
+
+use metrics::ServerMetrics;
+use sessions::ServerSessions;
 
 /// OpenAI-compatible chat completion request.
 #[derive(Debug, Deserialize)]
@@ -121,7 +200,7 @@ pub struct OpenAIMessageResponse {
 }
 
 /// Usage statistics for a completion.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 pub struct Usage {
     /// The number of prompt tokens.
     pub prompt_tokens: u32,
@@ -131,6 +210,49 @@ pub struct Usage {
     pub total_tokens: u32,
 }
 
+impl From<crate::llm::Usage> for Usage {
+    fn from(usage: crate::llm::Usage) -> Self {
+        Self {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        }
+    }
+}
+
+impl From<crate::usage::TokenUsage> for Usage {
+    fn from(usage: crate::usage::TokenUsage) -> Self {
+        let clamp = |n: u64| u32::try_from(n).unwrap_or(u32::MAX);
+        Self {
+            prompt_tokens: clamp(usage.prompt_tokens),
+            completion_tokens: clamp(usage.completion_tokens),
+            total_tokens: clamp(usage.total_tokens),
+        }
+    }
+}
+
+/// OpenAI-compatible embeddings request.
+#[derive(Debug, Deserialize)]
+pub struct EmbeddingsRequest {
+    /// The model to report in the response; the server's provider picks the model.
+    pub model: String,
+    /// The text to embed, or a list of texts.
+    pub input: EmbeddingsInput,
+    /// `"float"` for arrays of numbers, or `"base64"` for little-endian `f32` bytes.
+    #[serde(default)]
+    pub encoding_format: Option<String>,
+}
+
+/// The `input` of an [`EmbeddingsRequest`].
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum EmbeddingsInput {
+    /// A single text.
+    Text(String),
+    /// Several texts, embedded separately.
+    Texts(Vec<String>),
+}
+
 /// Model information for the models endpoint.
 #[derive(Debug, Serialize)]
 pub struct ModelInfo {
@@ -205,31 +327,260 @@ impl Default for CustomEndpointsConfig {
 #[derive(Clone)]
 pub struct ServerState {
     /// The LLM client for direct LLM calls.
-    pub llm_client: Option<Arc<LLMClient>>,
+    ///
+    /// The client is replaced when the configuration is reloaded; take it with
+    /// [`ServerState::current_client`] for the duration of one request.
+    pub llm_client: Option<Arc<RwLock<Arc<LLMClient>>>>,
     /// The agent (if serving an agent).
     pub agent: Option<Arc<RwLock<Agent>>>,
     /// The model name being served.
     pub model_name: String,
+    /// Stops in-flight completions and rejects new ones while tripped.
+    pub abort: AbortHandle,
+    /// The keys clients must present as bearer tokens; empty means the API is open.
+    pub api_keys: Arc<Vec<String>>,
+    /// Check the last user message of each request and the final reply.
+    pub moderators: Arc<Vec<Arc<dyn Moderator>>>,
+    /// Answers Slack's Events API at `/slack/events`, if set.
+    pub slack: Option<Arc<SlackEvents>>,
+    /// Answers `/v1/embeddings`, if set.
+    pub embeddings: Option<Arc<dyn EmbeddingProvider>>,
+    /// The request, token and tool call counts exported at `/metrics`.
+    pub metrics: Arc<ServerMetrics>,
+    /// The conversations kept for clients of `/v1/sessions`.
+    pub sessions: ServerSessions,
 }
 
 impl ServerState {
     /// Creates a new server state with an LLM client.
     pub fn with_llm_client(llm_client: LLMClient, model_name: String) -> Self {
         Self {
-            llm_client: Some(Arc::new(llm_client)),
+            llm_client: Some(Arc::new(RwLock::new(Arc::new(llm_client)))),
             agent: None,
             model_name,
+            abort: AbortHandle::new(),
+            api_keys: Arc::new(Vec::new()),
+            moderators: Arc::new(Vec::new()),
+            slack: None,
+            embeddings: None,
+            metrics: Arc::new(ServerMetrics::new()),
+            sessions: ServerSessions::new(),
         }
     }
 
+    /// Creates a server state with an LLM client built from `config`.
+    ///
+    /// A `[local]` model is served when one is configured, otherwise `[llm]`.
+    pub async fn from_config(config: &Config) -> Result<Self> {
+        let llm_client = LLMClient::new(provider_type(config)).await?;
+        Ok(Self::with_llm_client(llm_client, served_model_name(config)))
+    }
+
     /// Creates a new server state with an agent.
     pub fn with_agent(agent: Agent, model_name: String) -> Self {
+        // Taken up front so an abort does not have to wait for the agent's lock.
+        let abort = agent.abort_handle();
+        let audit = agent.tool_audit().clone();
         Self {
             llm_client: None,
             agent: Some(Arc::new(RwLock::new(agent))),
             model_name,
+            abort,
+            api_keys: Arc::new(Vec::new()),
+            moderators: Arc::new(Vec::new()),
+            slack: None,
+            embeddings: None,
+            metrics: Arc::new(ServerMetrics::new().with_tool_audit(audit)),
+            sessions: ServerSessions::new(),
         }
     }
+
+    /// Requires clients to authenticate with one of `keys`.
+    pub fn with_api_keys(mut self, keys: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.api_keys = Arc::new(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Adds a moderator for chat completion requests and replies.
+    pub fn with_moderator(mut self, moderator: impl Moderator + 'static) -> Self {
+        let mut moderators = self.moderators.to_vec();
+        moderators.push(Arc::new(moderator));
+        self.moderators = Arc::new(moderators);
+        self
+    }
+
+    /// Answers Slack events at `/slack/events`, replying in each message's thread.
+    pub fn with_slack(mut self, events: SlackEvents) -> Self {
+        self.slack = Some(Arc::new(events));
+        self
+    }
+
+    /// Answers `/v1/embeddings` with `provider`.
+    ///
+    /// The provider is kept when the configuration is reloaded.
+    pub fn with_embeddings(mut self, provider: impl EmbeddingProvider + 'static) -> Self {
+        self.embeddings = Some(Arc::new(provider));
+        self
+    }
+
+    /// Keeps the conversations of `/v1/sessions` in `sessions`, to change how long
+    /// idle sessions are kept or to persist them.
+    pub fn with_sessions(mut self, sessions: ServerSessions) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
+    /// Returns the LLM client requests are currently served with, if any.
+    pub async fn current_client(&self) -> Option<Arc<LLMClient>> {
+        match &self.llm_client {
+            Some(client) => Some(client.read().await.clone()),
+            None => None,
+        }
+    }
+
+    /// Applies a new configuration to the running server.
+    ///
+    /// A new LLM client is built from `config` and swapped in; requests already in
+    /// flight finish on the old one. A served agent gets the new client once its
+    /// current turn ends and keeps its conversation and tools. The advertised model
+    /// name does not change.
+    pub async fn reload(&self, config: &Config) -> Result<()> {
+        let llm_client = LLMClient::new(provider_type(config)).await?;
+        if let Some(current) = &self.llm_client {
+            let mut current = current.write().await;
+            let usage = current.usage_tracker().clone();
+            *current = Arc::new(llm_client.with_usage_tracker(usage));
+        } else if let Some(agent) = &self.agent {
+            agent.write().await.set_llm_client(llm_client);
+        }
+        Ok(())
+    }
+
+    /// Reloads the configuration from `path` whenever the file changes.
+    ///
+    /// A file that fails to load or produces a client that cannot be built is
+    /// logged and the current settings are kept. Watching stops when the returned
+    /// [`ConfigWatcher`] is dropped.
+    pub fn watch_config(&self, path: impl Into<PathBuf>) -> Result<ConfigWatcher> {
+        self.watch_config_with(path, |_| {})
+    }
+
+    /// Like [`ServerState::watch_config`], calling `prepare` on every reloaded
+    /// configuration before it is applied, for overrides such as a forced mode.
+    pub fn watch_config_with<F>(
+        &self,
+        path: impl Into<PathBuf>,
+        prepare: F,
+    ) -> Result<ConfigWatcher>
+    where
+        F: Fn(&mut Config) + Send + 'static,
+    {
+        use notify::Watcher;
+
+        let path = path.into();
+        let watch_error = |e: notify::Error| {
+            HeliosError::ConfigError(format!("Failed to watch {}: {}", path.display(), e))
+        };
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| HeliosError::ConfigError(format!("{} is not a file", path.display())))?
+            .to_os_string();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        // Editors often save by writing a new file and renaming it over the old
+        // one, so the directory is watched rather than the file itself.
+        let (sender, mut changes) = tokio::sync::mpsc::unbounded_channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else { return };
+                let relevant = matches!(
+                    event.kind,
+                    notify::EventKind::Create(_) | notify::EventKind::Modify(_)
+                ) && event
+                    .paths
+                    .iter()
+                    .any(|changed| changed.file_name() == Some(file_name.as_os_str()));
+                if relevant {
+                    let _ = sender.send(());
+                }
+            })
+            .map_err(watch_error)?;
+        watcher
+            .watch(&dir, notify::RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+
+        let state = self.clone();
+        let task = tokio::spawn(async move {
+            while changes.recv().await.is_some() {
+                // A save usually arrives as several events; wait for them to settle.
+                tokio::time::sleep(CONFIG_RELOAD_DEBOUNCE).await;
+                while changes.try_recv().is_ok() {}
+                let reloaded = Config::from_file(&path).map(|mut config| {
+                    prepare(&mut config);
+                    config
+                });
+                let result = match reloaded {
+                    Ok(config) => state.reload(&config).await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(()) => info!("Reloaded configuration from {}", path.display()),
+                    Err(e) => warn!(
+                        "Keeping the current configuration; reloading {} failed: {}",
+                        path.display(),
+                        e
+                    ),
+                }
+            }
+        });
+
+        Ok(ConfigWatcher {
+            _watcher: watcher,
+            task,
+        })
+    }
+}
+
+/// How long to wait after a config file changes before reloading it.
+const CONFIG_RELOAD_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// The most inputs one embeddings request may carry, as with OpenAI's API.
+const MAX_EMBEDDING_INPUTS: usize = 2048;
+
+/// How many inputs of an embeddings request are sent to the provider at once.
+const EMBEDDING_CONCURRENCY: usize = 8;
+
+/// Keeps a config file watched; see [`ServerState::watch_config`].
+pub struct ConfigWatcher {
+    _watcher: notify::RecommendedWatcher,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Returns the provider a server built from `config` talks to.
+fn provider_type(config: &Config) -> LLMProviderType {
+    #[cfg(feature = "local")]
+    if let Some(local_config) = config.local.clone() {
+        return LLMProviderType::Local(local_config);
+    }
+    LLMProviderType::Remote(config.llm.clone())
+}
+
+/// Returns the model name a server built from `config` advertises.
+fn served_model_name(config: &Config) -> String {
+    #[cfg(feature = "local")]
+    if config.local.is_some() {
+        return "local-model".to_string();
+    }
+    config.llm.model_name.clone()
 }
 
 /// Starts the HTTP server with the given configuration.
@@ -243,36 +594,12 @@ impl ServerState {
 ///
 /// A `Result` that resolves when the server shuts down.
 pub async fn start_server(config: Config, address: &str) -> Result<()> {
-    #[cfg(feature = "local")]
-    let provider_type = if let Some(local_config) = config.local.clone() {
-        LLMProviderType::Local(local_config)
-    } else {
-        LLMProviderType::Remote(config.llm.clone())
-    };
-
-    #[cfg(not(feature = "local"))]
-    let provider_type = LLMProviderType::Remote(config.llm.clone());
-
-    let llm_client = LLMClient::new(provider_type).await?;
-
-    #[cfg(feature = "local")]
-    let model_name = config
-        .local
-        .as_ref()
-        .map(|_| "local-model".to_string())
-        .unwrap_or_else(|| config.llm.model_name.clone());
-
-    #[cfg(not(feature = "local"))]
-    let model_name = config.llm.model_name.clone();
-
-    let state = ServerState::with_llm_client(llm_client, model_name);
+    let state = ServerState::from_config(&config).await?;
 
     let app = create_router(state);
 
     info!("🚀 Starting Helios Engine server on http://{}", address);
-    info!("📡 OpenAI-compatible API endpoints:");
-    info!("   POST /v1/chat/completions");
-    info!("   GET  /v1/models");
+    log_builtin_endpoints();
 
     let listener = tokio::net::TcpListener::bind(address)
         .await
@@ -309,9 +636,7 @@ pub async fn start_server_with_agent(
         "🚀 Starting Helios Engine server with agent on http://{}",
         address
     );
-    info!("📡 OpenAI-compatible API endpoints:");
-    info!("   POST /v1/chat/completions");
-    info!("   GET  /v1/models");
+    log_builtin_endpoints();
 
     let listener = tokio::net::TcpListener::bind(address)
         .await
@@ -340,36 +665,31 @@ pub async fn start_server_with_custom_endpoints(
     address: &str,
     custom_endpoints: Option<CustomEndpointsConfig>,
 ) -> Result<()> {
-    #[cfg(feature = "local")]
-    let provider_type = if let Some(local_config) = config.local.clone() {
-        LLMProviderType::Local(local_config)
-    } else {
-        LLMProviderType::Remote(config.llm.clone())
-    };
-
-    #[cfg(not(feature = "local"))]
-    let provider_type = LLMProviderType::Remote(config.llm.clone());
-
-    let llm_client = LLMClient::new(provider_type).await?;
-
-    #[cfg(feature = "local")]
-    let model_name = config
-        .local
-        .as_ref()
-        .map(|_| "local-model".to_string())
-        .unwrap_or_else(|| config.llm.model_name.clone());
-
-    #[cfg(not(feature = "local"))]
-    let model_name = config.llm.model_name.clone();
-
-    let state = ServerState::with_llm_client(llm_client, model_name);
+    let state = ServerState::from_config(&config).await?;
+    start_server_with_state(state, address, custom_endpoints).await
+}
 
+/// Starts the HTTP server with a prepared state, such as one whose configuration
+/// is being watched.
+///
+/// # Arguments
+///
+/// * `state` - The client or agent to serve.
+/// * `address` - The address to bind to (e.g., "127.0.0.1:8000").
+/// * `custom_endpoints` - Optional custom endpoints configuration.
+///
+/// # Returns
+///
+/// A `Result` that resolves when the server shuts down.
+pub async fn start_server_with_state(
+    state: ServerState,
+    address: &str,
+    custom_endpoints: Option<CustomEndpointsConfig>,
+) -> Result<()> {
     let app = create_router_with_custom_endpoints(state, custom_endpoints.clone());
 
     info!("🚀 Starting Helios Engine server on http://{}", address);
-    info!("📡 OpenAI-compatible API endpoints:");
-    info!("   POST /v1/chat/completions");
-    info!("   GET  /v1/models");
+    log_builtin_endpoints();
 
     if let Some(config) = &custom_endpoints {
         info!("📡 Custom endpoints:");
@@ -415,9 +735,7 @@ pub async fn start_server_with_agent_and_custom_endpoints(
         "🚀 Starting Helios Engine server with agent on http://{}",
         address
     );
-    info!("📡 OpenAI-compatible API endpoints:");
-    info!("   POST /v1/chat/completions");
-    info!("   GET  /v1/models");
+    log_builtin_endpoints();
 
     if let Some(config) = &custom_endpoints {
         info!("📡 Custom endpoints:");
@@ -526,9 +844,7 @@ impl ServerBuilder {
             "🚀 Starting Helios Engine server with agent on http://{}",
             self.address
         );
-        info!("📡 OpenAI-compatible API endpoints:");
-        info!("   POST /v1/chat/completions");
-        info!("   GET  /v1/models");
+        log_builtin_endpoints();
 
         let listener = tokio::net::TcpListener::bind(&self.address)
             .await
@@ -571,13 +887,52 @@ pub fn load_custom_endpoints_config(path: &str) -> Result<CustomEndpointsConfig>
 
 /// Creates the router with all endpoints.
 fn create_router(state: ServerState) -> Router {
-    Router::new()
-        .route("/v1/chat/completions", post(chat_completions))
-        .route("/v1/models", get(list_models))
-        .route("/health", get(health_check))
-        .layer(CorsLayer::permissive())
-        .layer(TraceLayer::new_for_http())
-        .with_state(state)
+    finish_router(base_router(), state)
+}
+
+/// The built-in endpoints every server has, with the methods they answer to.
+fn builtin_routes() -> Vec<(&'static str, &'static str, MethodRouter<ServerState>)> {
+    vec![
+        ("POST", "/v1/chat/completions", post(chat_completions)),
+        ("POST", "/v1/responses", post(responses::create_response)),
+        ("POST", "/v1/embeddings", post(embeddings)),
+        ("POST", "/v1/sessions", post(sessions::create_session)),
+        (
+            "GET, DELETE",
+            "/v1/sessions/{id}",
+            get(sessions::get_session).delete(sessions::delete_session),
+        ),
+        (
+            "POST",
+            "/v1/sessions/{id}/messages",
+            post(sessions::post_message),
+        ),
+        ("GET", "/v1/models", get(list_models)),
+        ("GET", "/health", get(health_check)),
+        ("GET", "/healthz", get(health_check)),
+        ("GET", "/readyz", get(readiness_check)),
+        ("GET", "/metrics", get(export_metrics)),
+        ("POST", "/admin/abort", post(admin_abort)),
+        ("POST", "/admin/resume", post(admin_resume)),
+        ("POST", "/slack/events", post(slack_events)),
+    ]
+}
+
+/// Logs the built-in endpoints served by `base_router`.
+fn log_builtin_endpoints() {
+    info!("📡 Built-in endpoints:");
+    for (methods, path, _) in builtin_routes() {
+        info!("   {:<11} {}", methods, path);
+    }
+}
+
+/// Creates a router with the built-in endpoints every server has.
+fn base_router() -> Router<ServerState> {
+    builtin_routes()
+        .into_iter()
+        .fold(Router::new(), |router, (_, path, route)| {
+            router.route(path, route)
+        })
 }
 
 /// Creates the router with custom endpoints.
@@ -585,10 +940,7 @@ fn create_router_with_custom_endpoints(
     state: ServerState,
     custom_endpoints: Option<CustomEndpointsConfig>,
 ) -> Router {
-    let mut router = Router::new()
-        .route("/v1/chat/completions", post(chat_completions))
-        .route("/v1/models", get(list_models))
-        .route("/health", get(health_check));
+    let mut router = base_router();
 
     // Add custom endpoints if provided
     if let Some(config) = custom_endpoints {
@@ -612,10 +964,7 @@ fn create_router_with_custom_endpoints(
         }
     }
 
-    router
-        .layer(CorsLayer::permissive())
-        .layer(TraceLayer::new_for_http())
-        .with_state(state)
+    finish_router(router, state)
 }
 
 /// Creates the router with new-style custom endpoints.
@@ -625,10 +974,7 @@ fn create_router_with_new_endpoints(
 ) -> Router {
     use crate::endpoint_builder::HttpMethod;
 
-    let mut router = Router::new()
-        .route("/v1/chat/completions", post(chat_completions))
-        .route("/v1/models", get(list_models))
-        .route("/health", get(health_check));
+    let mut router = base_router();
 
     // Add new-style custom endpoints
     for endpoint in endpoints {
@@ -666,12 +1012,74 @@ fn create_router_with_new_endpoints(
         }
     }
 
+    finish_router(router, state)
+}
+
+/// Applies the layers shared by every router: authentication, metrics, CORS and
+/// tracing.
+fn finish_router(router: Router<ServerState>, state: ServerState) -> Router {
     router
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            require_api_key,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            record_metrics,
+        ))
         .layer(CorsLayer::permissive())
         .layer(TraceLayer::new_for_http())
         .with_state(state)
 }
 
+/// Rejects requests without a valid bearer token when the server has API keys.
+async fn require_api_key(
+    State(state): State<ServerState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    // Slack signs its requests instead; `SlackEvents` checks the signature. The
+    // probes stay open so an orchestrator can call them without a key.
+    let path = request.uri().path();
+    if state.api_keys.is_empty()
+        || matches!(path, "/health" | "/healthz" | "/readyz" | "/slack/events")
+    {
+        return next.run(request).await;
+    }
+    let token = request
+        .headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    let authorized =
+        token.is_some_and(|token| state.api_keys.iter().any(|key| keys_match(key, token)));
+    if authorized {
+        return next.run(request).await;
+    }
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(serde_json::json!({
+            "error": {
+                "message": "Invalid or missing API key",
+                "type": "invalid_request_error",
+                "code": "invalid_api_key"
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// Compares two keys without stopping at the first differing byte.
+pub(crate) fn keys_match(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
 /// Health check endpoint.
 async fn health_check() -> Json<serde_json::Value> {
     Json(serde_json::json!({
@@ -680,6 +1088,100 @@ async fn health_check() -> Json<serde_json::Value> {
     }))
 }
 
+/// Reports whether the server can answer completions: it has not been stopped with
+/// `/admin/abort` and the served provider is reachable.
+///
+/// Answers `503 Service Unavailable` when it cannot, so a load balancer takes the
+/// server out of rotation until it recovers.
+async fn readiness_check(
+    State(state): State<ServerState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let result = if state.abort.is_aborted() {
+        Err(HeliosError::Aborted(
+            "the server was stopped with /admin/abort".to_string(),
+        ))
+    } else if let Some(agent) = &state.agent {
+        // An agent busy with a request is serving, and probing would wait for it.
+        match agent.try_read() {
+            Ok(agent) => agent.check_ready().await,
+            Err(_) => Ok(()),
+        }
+    } else if let Some(llm_client) = state.current_client().await {
+        llm_client.check_ready().await
+    } else {
+        Err(HeliosError::ConfigError(
+            "No agent or LLM client to serve".to_string(),
+        ))
+    };
+
+    match result {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "status": "ready", "model": state.model_name })),
+        ),
+        Err(e) => {
+            warn!("Readiness check failed: {}", e);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({ "status": "unavailable", "error": e.to_string() })),
+            )
+        }
+    }
+}
+
+/// Exports the server's metrics in the Prometheus text format.
+async fn export_metrics(State(state): State<ServerState>) -> impl IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        state.metrics.render(),
+    )
+}
+
+/// Counts each request and records its latency by matched route.
+///
+/// Requests that match no route are counted under `unmatched`, so probing for
+/// paths cannot add labels without bound.
+async fn record_metrics(
+    State(state): State<ServerState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let route = request
+        .extensions()
+        .get::<axum::extract::MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+    let method = request.method().to_string();
+    let started = std::time::Instant::now();
+    let response = next.run(request).await;
+    state.metrics.record_request(
+        &route,
+        &method,
+        response.status().as_u16(),
+        started.elapsed(),
+    );
+    response
+}
+
+/// Trips the emergency stop, cancelling in-flight completions.
+///
+/// New completion requests are rejected with `503 Service Unavailable` until
+/// `/admin/resume` is called.
+async fn admin_abort(State(state): State<ServerState>) -> Json<serde_json::Value> {
+    warn!("Abort requested; stopping in-flight completions");
+    state.abort.abort();
+    Json(serde_json::json!({ "aborted": true }))
+}
+
+/// Clears the emergency stop so completions are served again.
+async fn admin_resume(State(state): State<ServerState>) -> Json<serde_json::Value> {
+    info!("Resuming after abort");
+    state.abort.reset();
+    Json(serde_json::json!({ "aborted": false }))
+}
+
 /// Lists available models.
 async fn list_models(State(state): State<ServerState>) -> Json<ModelsResponse> {
     Json(ModelsResponse {
@@ -687,7 +1189,7 @@ async fn list_models(State(state): State<ServerState>) -> Json<ModelsResponse> {
         data: vec![ModelInfo {
             id: state.model_name.clone(),
             object: "model".to_string(),
-            created: chrono::Utc::now().timestamp() as u64,
+            created: crate::clock::now().timestamp() as u64,
             owned_by: "helios-engine".to_string(),
         }],
     })
@@ -724,6 +1226,7 @@ async fn chat_completions(
                 name: msg.name,       // Optional name for tool messages
                 tool_calls: None,     // Not used in conversion (OpenAI format differs)
                 tool_call_id: None,   // Not used in conversion (OpenAI format differs)
+                reasoning: None,
             })
         })
         .collect();
@@ -733,6 +1236,10 @@ async fn chat_completions(
         StatusCode::BAD_REQUEST
     })?;
 
+    if state.abort.is_aborted() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
     let stream = request.stream.unwrap_or(false);
 
     if stream {
@@ -749,63 +1256,38 @@ async fn chat_completions(
     }
 
     // Handle non-streaming response
-    let completion_id = format!("chatcmpl-{}", Uuid::new_v4());
-    let created = chrono::Utc::now().timestamp() as u64;
-
-    // Clone messages for token estimation and LLM client usage
-    let messages_clone = messages.clone();
-
-    let response_content = if let Some(agent) = &state.agent {
-        // Use agent for response with full conversation history
-        let mut agent = agent.write().await;
-
-        match agent
-            .chat_with_history(
-                messages.clone(),
-                request.temperature,
-                request.max_tokens,
-                request.stop.clone(),
-            )
-            .await
-        {
-            Ok(content) => content,
-            Err(e) => {
-                error!("Agent error: {}", e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
+    let completion_id = format!("chatcmpl-{}", crate::clock::new_id());
+    let created = crate::clock::now().timestamp() as u64;
+
+    let mut usage = Usage::default();
+    let result = complete(
+        &state,
+        messages,
+        request.temperature,
+        request.max_tokens,
+        request.stop.clone(),
+        &mut usage,
+    )
+    .await;
+    state.metrics.record_usage(&usage);
+
+    let (response_content, finish_reason) = match result {
+        Ok(content) => (content, "stop"),
+        Err(HeliosError::Blocked(reason)) => {
+            info!("Completion blocked: {}", reason);
+            (String::new(), "content_filter")
         }
-    } else if let Some(llm_client) = &state.llm_client {
-        // Use LLM client directly
-        match llm_client
-            .chat(
-                messages_clone,
-                None,
-                request.temperature,
-                request.max_tokens,
-                request.stop.clone(),
-            )
-            .await
-        {
-            Ok(msg) => msg.content,
-            Err(e) => {
-                error!("LLM error: {}", e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
+        Err(HeliosError::Aborted(_)) => return Err(StatusCode::SERVICE_UNAVAILABLE),
+        Err(HeliosError::BudgetExceeded(reason)) => {
+            warn!("Agent budget exceeded: {}", reason);
+            return Err(StatusCode::TOO_MANY_REQUESTS);
+        }
+        Err(e) => {
+            error!("Completion error: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
-    } else {
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
     };
 
-    // Estimate token usage (simplified - in production, use actual tokenizer)
-    let prompt_tokens = estimate_tokens(
-        &messages
-            .iter()
-            .map(|m| m.content.as_str())
-            .collect::<Vec<_>>()
-            .join(" "),
-    );
-    let completion_tokens = estimate_tokens(&response_content);
-
     let response = ChatCompletionResponse {
         id: completion_id,
         object: "chat.completion".to_string(),
@@ -817,18 +1299,179 @@ async fn chat_completions(
                 role: "assistant".to_string(),
                 content: response_content,
             },
-            finish_reason: "stop".to_string(),
+            finish_reason: finish_reason.to_string(),
         }],
-        usage: Usage {
-            prompt_tokens,
-            completion_tokens,
-            total_tokens: prompt_tokens + completion_tokens,
-        },
+        usage,
     };
 
     Ok(Json(response).into_response())
 }
 
+/// Runs a non-streaming completion with the served agent or LLM client.
+///
+/// The last user message and the reply pass through the server's moderators, and
+/// the tokens used are written to `usage`.
+async fn complete(
+    state: &ServerState,
+    mut messages: Vec<ChatMessage>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    stop: Option<Vec<String>>,
+    usage: &mut Usage,
+) -> Result<String> {
+    moderation::moderate_last_input(&state.moderators, &mut messages).await?;
+
+    let content = if let Some(agent) = &state.agent {
+        // Use agent for response with full conversation history
+        let mut agent = agent.write().await;
+        // The write lock serializes requests, so the usage delta belongs to this request
+        let usage_before = agent.usage();
+        let result = agent
+            .chat_with_history(messages, temperature, max_tokens, stop)
+            .await;
+        *usage = Usage::from(agent.usage().since(&usage_before));
+        result?
+    } else if let Some(llm_client) = state.current_client().await {
+        // Use LLM client directly
+        let (msg, call_usage) = state
+            .abort
+            .run(llm_client.chat_with_usage(messages, None, temperature, max_tokens, stop))
+            .await??;
+        *usage = Usage::from(call_usage);
+        msg.content
+    } else {
+        return Err(HeliosError::ConfigError(
+            "No agent or LLM client to serve".to_string(),
+        ));
+    };
+
+    moderation::moderate(&state.moderators, &content, ModerationTarget::Output).await
+}
+
+/// Handles embeddings requests with the server's embedding provider.
+///
+/// Answers `404 Not Found` when the server has no provider, and `400 Bad Request`
+/// for no inputs or more than `MAX_EMBEDDING_INPUTS`.
+async fn embeddings(
+    State(state): State<ServerState>,
+    Json(request): Json<EmbeddingsRequest>,
+) -> std::result::Result<Json<serde_json::Value>, StatusCode> {
+    let Some(provider) = state.embeddings.clone() else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let base64 = match request.encoding_format.as_deref() {
+        None | Some("float") => false,
+        Some("base64") => true,
+        Some(other) => {
+            error!("Unsupported embedding encoding format: {}", other);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+    let texts = match request.input {
+        EmbeddingsInput::Text(text) => vec![text],
+        EmbeddingsInput::Texts(texts) => texts,
+    };
+    if texts.is_empty() || texts.len() > MAX_EMBEDDING_INPUTS {
+        error!("Embeddings request has {} inputs", texts.len());
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    use futures::{StreamExt, TryStreamExt};
+    let vectors: Vec<Vec<f32>> = futures::stream::iter(texts.clone())
+        .map(|text| {
+            let provider = Arc::clone(&provider);
+            async move { provider.embed(&text).await }
+        })
+        .buffered(EMBEDDING_CONCURRENCY)
+        .try_collect()
+        .await
+        .map_err(|e| {
+            error!("Embedding error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let data: Vec<serde_json::Value> = vectors
+        .into_iter()
+        .enumerate()
+        .map(|(index, vector)| {
+            let embedding = if base64 {
+                use base64::Engine;
+                let bytes: Vec<u8> = vector.iter().flat_map(|x| x.to_le_bytes()).collect();
+                serde_json::json!(base64::engine::general_purpose::STANDARD.encode(bytes))
+            } else {
+                serde_json::json!(vector)
+            };
+            serde_json::json!({
+                "object": "embedding",
+                "index": index,
+                "embedding": embedding
+            })
+        })
+        .collect();
+    let tokens: u32 = texts.iter().map(|text| estimate_tokens(text)).sum();
+    Ok(Json(serde_json::json!({
+        "object": "list",
+        "data": data,
+        "model": request.model,
+        "usage": {
+            "prompt_tokens": tokens,
+            "total_tokens": tokens
+        }
+    })))
+}
+
+/// Handles Slack's Events API.
+///
+/// Slack expects an answer within three seconds, so the event is acknowledged
+/// at once and the reply is posted to the thread when the completion finishes.
+async fn slack_events(
+    State(state): State<ServerState>,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> axum::response::Response {
+    let Some(slack) = state.slack.clone() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let (channel, thread_ts, text) = match slack.parse(&headers, &body) {
+        SlackRequest::Challenge(challenge) => {
+            return Json(serde_json::json!({ "challenge": challenge })).into_response()
+        }
+        SlackRequest::Unauthorized => return StatusCode::UNAUTHORIZED.into_response(),
+        SlackRequest::Ignore => return StatusCode::OK.into_response(),
+        SlackRequest::Message {
+            channel,
+            thread_ts,
+            text,
+        } => (channel, thread_ts, text),
+    };
+
+    tokio::spawn(async move {
+        let messages = slack.begin_turn(&channel, &thread_ts, &text);
+        let mut usage = Usage::default();
+        let reply = match complete(&state, messages, None, None, None, &mut usage).await {
+            Ok(reply) => {
+                slack.finish_turn(&channel, &thread_ts, &reply);
+                reply
+            }
+            Err(HeliosError::Blocked(reason)) => {
+                info!("Slack message blocked: {}", reason);
+                "Sorry, I can't help with that.".to_string()
+            }
+            Err(e) => {
+                error!("Slack completion error: {}", e);
+                "Sorry, something went wrong while answering.".to_string()
+            }
+        };
+        if let Err(e) = slack
+            .slack()
+            .post_message(&channel, &reply, Some(&thread_ts))
+            .await
+        {
+            error!("Could not post Slack reply: {}", e);
+        }
+    });
+    StatusCode::OK.into_response()
+}
+
 /// Streams a chat completion response.
 fn stream_chat_completion(
     state: ServerState,
@@ -839,11 +1482,11 @@ fn stream_chat_completion(
     stop: Option<Vec<String>>,
 ) -> Sse<impl Stream<Item = std::result::Result<Event, Infallible>>> {
     let (tx, rx) = tokio::sync::mpsc::channel(100);
-    let completion_id = format!("chatcmpl-{}", Uuid::new_v4());
-    let created = chrono::Utc::now().timestamp() as u64;
+    let completion_id = format!("chatcmpl-{}", crate::clock::new_id());
+    let created = crate::clock::now().timestamp() as u64;
 
     tokio::spawn(async move {
-        let on_chunk = |chunk: &str| {
+        let send_chunk = |chunk: &str| {
             let event = Event::default()
                 .json_data(serde_json::json!({
                     "id": completion_id,
@@ -861,40 +1504,78 @@ fn stream_chat_completion(
                 .unwrap();
             let _ = tx.try_send(Ok(event));
         };
+        // A reply the server moderates is only sent once it is complete.
+        let buffered = !state.moderators.is_empty();
+        let on_chunk = |chunk: &str| {
+            if !buffered {
+                send_chunk(chunk);
+            }
+        };
 
-        if let Some(agent) = &state.agent {
-            // Use agent for true streaming response with full conversation history
-            let mut agent = agent.write().await;
+        let mut messages = messages;
+        let mut usage = None;
+
+        let result: Result<()> = async {
+            moderation::moderate_last_input(&state.moderators, &mut messages).await?;
+
+            let content = if let Some(agent) = &state.agent {
+                // Use agent for true streaming response with full conversation history
+                let mut agent = agent.write().await;
+                let usage_before = agent.usage();
+                let result = agent
+                    .chat_stream_with_history(
+                        messages,
+                        temperature,
+                        max_tokens,
+                        stop.clone(),
+                        on_chunk,
+                    )
+                    .await;
+                usage = Some(Usage::from(agent.usage().since(&usage_before)));
+                result?.content
+            } else if let Some(llm_client) = state.current_client().await {
+                // Use LLM client streaming
+                let (msg, call_usage) = state
+                    .abort
+                    .run(llm_client.chat_stream_with_usage(
+                        messages,
+                        None,
+                        temperature,
+                        max_tokens,
+                        stop.clone(),
+                        on_chunk,
+                    ))
+                    .await??;
+                usage = Some(Usage::from(call_usage));
+                msg.content
+            } else {
+                return Err(HeliosError::ConfigError(
+                    "No agent or LLM client to serve".to_string(),
+                ));
+            };
 
-            match agent
-                .chat_stream_with_history(messages, temperature, max_tokens, stop.clone(), on_chunk)
-                .await
-            {
-                Ok(_) => {
-                    // Streaming completed successfully
-                    // The on_chunk callback has already been called for each token
-                }
-                Err(e) => {
-                    error!("Agent streaming error: {}", e);
-                }
+            if buffered {
+                let content =
+                    moderation::moderate(&state.moderators, &content, ModerationTarget::Output)
+                        .await?;
+                send_chunk(&content);
             }
-        } else if let Some(llm_client) = &state.llm_client {
-            // Use LLM client streaming
-            match llm_client
-                .chat_stream(
-                    messages,
-                    None,
-                    temperature,
-                    max_tokens,
-                    stop.clone(),
-                    on_chunk,
-                )
-                .await
-            {
-                Ok(_) => {}
-                Err(e) => {
-                    error!("LLM streaming error: {}", e);
-                }
+            Ok(())
+        }
+        .await;
+        if let Some(usage) = &usage {
+            state.metrics.record_usage(usage);
+        }
+
+        let finish_reason = match result {
+            Ok(()) => "stop",
+            Err(HeliosError::Blocked(reason)) => {
+                info!("Streamed completion blocked: {}", reason);
+                "content_filter"
+            }
+            Err(e) => {
+                error!("Streaming error: {}", e);
+                "stop"
             }
         };
 
@@ -908,8 +1589,9 @@ fn stream_chat_completion(
                 "choices": [{
                     "index": 0,
                     "delta": {},
-                    "finish_reason": "stop"
-                }]
+                    "finish_reason": finish_reason
+                }],
+                "usage": usage
             }))
             .unwrap();
         let _ = tx.send(Ok(final_event)).await;
@@ -921,6 +1603,601 @@ fn stream_chat_completion(
 /// Estimates the number of tokens in a text (simplified approximation).
 /// In production, use an actual tokenizer.
 pub fn estimate_tokens(text: &str) -> u32 {
-    // Rough approximation: ~4 characters per token
-    (text.len() as f32 / 4.0).ceil() as u32
+    crate::usage::estimate_tokens(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::ServiceExt;
+
+    /// Tests that every logged built-in endpoint is routed.
+    #[tokio::test]
+    async fn test_builtin_routes() {
+        let client = LLMClient::from_provider(
+            crate::mock::MockLLMProvider::new(),
+            crate::config::LLMConfig::default(),
+        );
+        let app = create_router(ServerState::with_llm_client(
+            client,
+            "test-model".to_string(),
+        ));
+        let paths: Vec<&str> = builtin_routes().iter().map(|(_, path, _)| *path).collect();
+        for path in ["/admin/abort", "/slack/events", "/health"] {
+            assert!(paths.contains(&path), "{} is missing", path);
+        }
+        for path in paths {
+            // No built-in endpoint takes PATCH, so a routed path answers 405
+            let request = axum::http::Request::builder()
+                .method("PATCH")
+                .uri(path.replace("{id}", "abc"))
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(
+                response.status(),
+                StatusCode::METHOD_NOT_ALLOWED,
+                "{}",
+                path
+            );
+        }
+    }
+
+    /// Tests that API keys are required on every endpoint but the health check.
+    #[tokio::test]
+    async fn test_api_key_authentication() {
+        let client = LLMClient::from_provider(
+            crate::mock::MockLLMProvider::new(),
+            crate::config::LLMConfig::default(),
+        );
+        let state = ServerState::with_llm_client(client, "test-model".to_string())
+            .with_api_keys(["secret"]);
+        let app = create_router(state);
+        let request = |path: &str, key: Option<&str>| {
+            let mut builder = axum::http::Request::builder().uri(path);
+            if let Some(key) = key {
+                builder = builder.header("Authorization", format!("Bearer {}", key));
+            }
+            builder.body(axum::body::Body::empty()).unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(request("/v1/models", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app
+            .clone()
+            .oneshot(request("/v1/models", Some("wrong")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app
+            .clone()
+            .oneshot(request("/v1/models", Some("secret")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(request("/health", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    /// Tests that server moderators filter requests and replies.
+    #[tokio::test]
+    async fn test_chat_completion_moderation() {
+        use crate::moderation::{KeywordModerator, ModerationAction};
+
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_response("Reach me at 555-1234")
+            .with_response("Try a bomb")
+            .with_response("Call 555-0000");
+        let client = LLMClient::from_provider(mock.clone(), crate::config::LLMConfig::default());
+        let moderator = KeywordModerator::new()
+            .words(["bomb"], ModerationAction::Block)
+            .unwrap()
+            .pattern(r"\d{3}-\d{4}", ModerationAction::Redact("[phone]".into()))
+            .unwrap();
+        let app = create_router(
+            ServerState::with_llm_client(client, "test-model".to_string())
+                .with_moderator(moderator),
+        );
+        let complete = |content: &str, stream: bool| {
+            let body = serde_json::json!({
+                "model": "test-model",
+                "messages": [{"role": "user", "content": content}],
+                "stream": stream
+            });
+            let request = axum::http::Request::builder()
+                .method("POST")
+                .uri("/v1/chat/completions")
+                .header("Content-Type", "application/json")
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
+        };
+
+        let body: serde_json::Value =
+            serde_json::from_str(&complete("build a bomb", false).await).unwrap();
+        assert_eq!(body["choices"][0]["finish_reason"], "content_filter");
+        assert_eq!(body["choices"][0]["message"]["content"], "");
+        assert!(mock.requests().is_empty());
+
+        let body: serde_json::Value =
+            serde_json::from_str(&complete("I am 555-9876", false).await).unwrap();
+        assert_eq!(body["choices"][0]["finish_reason"], "stop");
+        assert_eq!(
+            body["choices"][0]["message"]["content"],
+            "Reach me at [phone]"
+        );
+        assert_eq!(mock.requests()[0].messages[0].content, "I am [phone]");
+
+        let body: serde_json::Value =
+            serde_json::from_str(&complete("any ideas?", false).await).unwrap();
+        assert_eq!(body["choices"][0]["finish_reason"], "content_filter");
+
+        let stream = complete("who do I call?", true).await;
+        assert!(stream.contains(r#""content":"Call [phone]""#));
+        assert!(!stream.contains("555-0000"));
+        assert!(stream.contains(r#""finish_reason":"stop""#));
+    }
+
+    /// Tests the Responses API with an agent, streamed and not, including tool-call items.
+    #[tokio::test]
+    async fn test_responses_api() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_tool_call("calculator", serde_json::json!({"expression": "6 * 7"}))
+            .with_response("It is 42.")
+            .with_response("Still 42.")
+            .with_tool_call("calculator", serde_json::json!({"expression": "1 + 1"}))
+            .with_response("And 2.");
+        let agent = Agent::builder("calc")
+            .llm_provider(mock.clone())
+            .tool(Box::new(crate::tools::CalculatorTool))
+            .build()
+            .await
+            .unwrap();
+        let app = create_router(ServerState::with_agent(agent, "calc".to_string()));
+        let respond = |body: serde_json::Value| {
+            let request = axum::http::Request::builder()
+                .method("POST")
+                .uri("/v1/responses")
+                .header("Content-Type", "application/json")
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
+        };
+
+        let body: serde_json::Value = serde_json::from_str(
+            &respond(serde_json::json!({
+                "model": "calc",
+                "instructions": "Use the calculator.",
+                "input": "What is 6 * 7?"
+            }))
+            .await,
+        )
+        .unwrap();
+        assert_eq!(body["object"], "response");
+        assert_eq!(body["status"], "completed");
+        assert_eq!(body["output_text"], "It is 42.");
+        let output = body["output"].as_array().unwrap();
+        let types: Vec<&str> = output
+            .iter()
+            .map(|item| item["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(types, ["function_call", "function_call_output", "message"]);
+        assert_eq!(output[0]["name"], "calculator");
+        assert_eq!(output[1]["call_id"], output[0]["call_id"]);
+        assert_eq!(output[1]["output"], "42");
+        assert_eq!(output[2]["content"][0]["text"], "It is 42.");
+        assert_eq!(
+            mock.requests()[0].messages[0].content,
+            "Use the calculator."
+        );
+
+        // Earlier tool calls can be sent back as input items.
+        respond(serde_json::json!({
+            "model": "calc",
+            "input": [
+                {"role": "user", "content": [{"type": "input_text", "text": "What is 6 * 7?"}]},
+                {"type": "function_call", "call_id": "call_9", "name": "calculator", "arguments": "{}"},
+                {"type": "function_call_output", "call_id": "call_9", "output": "42"},
+                {"type": "message", "role": "assistant", "content": [{"type": "output_text", "text": "It is 42."}]},
+                {"role": "user", "content": "And now?"}
+            ]
+        }))
+        .await;
+        let messages = &mock.requests()[2].messages;
+        assert_eq!(messages[1].tool_calls.as_ref().unwrap()[0].id, "call_9");
+        assert_eq!(messages[2].tool_call_id.as_deref(), Some("call_9"));
+        assert_eq!(messages[4].content, "And now?");
+
+        let stream = respond(serde_json::json!({
+            "model": "calc",
+            "input": "What is 1 + 1?",
+            "stream": true
+        }))
+        .await;
+        let events: Vec<&str> = stream
+            .lines()
+            .filter_map(|line| line.strip_prefix("event: "))
+            .collect();
+        assert_eq!(events[..2], ["response.created", "response.in_progress"]);
+        assert_eq!(events.last(), Some(&"response.completed"));
+        assert!(events.contains(&"response.function_call_arguments.done"));
+        assert!(events.contains(&"response.output_text.delta"));
+        assert!(stream.contains(r#""output_text":"And 2.""#));
+    }
+
+    /// Tests the health and readiness probes and the metrics they feed.
+    #[tokio::test]
+    async fn test_probes_and_metrics() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_tool_call("calculator", serde_json::json!({"expression": "6 * 7"}))
+            .with_response("It is 42.");
+        let agent = Agent::builder("calc")
+            .llm_provider(mock)
+            .tool(Box::new(crate::tools::CalculatorTool))
+            .build()
+            .await
+            .unwrap();
+        let app = create_router(
+            ServerState::with_agent(agent, "calc".to_string()).with_api_keys(["secret"]),
+        );
+        let send = |method: &str, uri: &str, body: Option<serde_json::Value>| {
+            let mut request = axum::http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", "Bearer secret");
+            if body.is_some() {
+                request = request.header("Content-Type", "application/json");
+            }
+            let request = request
+                .body(axum::body::Body::from(
+                    body.map(|b| b.to_string()).unwrap_or_default(),
+                ))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (status, String::from_utf8(body.to_vec()).unwrap())
+            }
+        };
+        let unauthenticated = |uri: &str| {
+            let request = axum::http::Request::builder()
+                .uri(uri)
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let app = app.clone();
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+
+        // The probes need no key; the metrics do.
+        assert_eq!(unauthenticated("/healthz").await, StatusCode::OK);
+        assert_eq!(unauthenticated("/readyz").await, StatusCode::OK);
+        assert_eq!(unauthenticated("/metrics").await, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = send(
+            "POST",
+            "/v1/chat/completions",
+            Some(serde_json::json!({
+                "model": "calc",
+                "messages": [{"role": "user", "content": "What is 6 * 7?"}]
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(send("GET", "/nowhere", None).await.0, StatusCode::NOT_FOUND);
+
+        send("POST", "/admin/abort", None).await;
+        let (status, body) = send("GET", "/readyz", None).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("/admin/abort"));
+        send("POST", "/admin/resume", None).await;
+        assert_eq!(send("GET", "/readyz", None).await.0, StatusCode::OK);
+
+        let (status, text) = send("GET", "/metrics", None).await;
+        assert_eq!(status, StatusCode::OK);
+        for line in [
+            "helios_http_requests_total{route=\"/v1/chat/completions\",method=\"POST\",status=\"200\"} 1",
+            "helios_http_requests_total{route=\"/readyz\",method=\"GET\",status=\"200\"} 2",
+            "helios_http_requests_total{route=\"/readyz\",method=\"GET\",status=\"503\"} 1",
+            "helios_http_requests_total{route=\"/metrics\",method=\"GET\",status=\"401\"} 1",
+            "helios_http_requests_total{route=\"unmatched\",method=\"GET\",status=\"404\"} 1",
+            "helios_http_request_duration_seconds_count{route=\"/v1/chat/completions\",method=\"POST\"} 1",
+            "helios_tool_calls_total{tool=\"calculator\"} 1",
+            "helios_tool_call_failures_total{tool=\"calculator\"} 0",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {}", line);
+        }
+        assert!(text.contains("helios_tokens_total{type=\"prompt\"}"));
+    }
+
+    /// Tests a conversation kept by the server across messages.
+    #[tokio::test]
+    async fn test_sessions_api() {
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_response("Hello, Ada.")
+            .with_response("Your name is Ada.");
+        let client = LLMClient::from_provider(mock.clone(), crate::config::LLMConfig::default());
+        let app = create_router(ServerState::with_llm_client(
+            client,
+            "test-model".to_string(),
+        ));
+        let send = |method: &str, uri: &str, body: Option<serde_json::Value>| {
+            let mut request = axum::http::Request::builder().method(method).uri(uri);
+            if body.is_some() {
+                request = request.header("Content-Type", "application/json");
+            }
+            let request = request
+                .body(axum::body::Body::from(
+                    body.map(|b| b.to_string()).unwrap_or_default(),
+                ))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (status, serde_json::from_slice(&body).unwrap_or_default())
+            }
+        };
+
+        let (status, session): (_, serde_json::Value) = send(
+            "POST",
+            "/v1/sessions",
+            Some(serde_json::json!({"system_prompt": "Be brief."})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(session["object"], "session");
+        let id = session["id"].as_str().unwrap();
+        let messages_uri = format!("/v1/sessions/{}/messages", id);
+
+        let (status, reply) = send(
+            "POST",
+            &messages_uri,
+            Some(serde_json::json!({"content": "I am Ada."})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(reply["choices"][0]["message"]["content"], "Hello, Ada.");
+        let (_, reply) = send(
+            "POST",
+            &messages_uri,
+            Some(serde_json::json!({"content": "What is my name?"})),
+        )
+        .await;
+        assert_eq!(
+            reply["choices"][0]["message"]["content"],
+            "Your name is Ada."
+        );
+
+        // The second request carried the whole conversation.
+        let contents: Vec<String> = mock.requests()[1]
+            .messages
+            .iter()
+            .map(|m| m.content.clone())
+            .collect();
+        assert_eq!(
+            contents,
+            ["Be brief.", "I am Ada.", "Hello, Ada.", "What is my name?"]
+        );
+
+        // A failed turn is not added to the conversation.
+        let (status, _) = send(
+            "POST",
+            &messages_uri,
+            Some(serde_json::json!({"content": "Still there?"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+        let (status, session) = send("GET", &format!("/v1/sessions/{}", id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(session["system_prompt"], "Be brief.");
+        assert_eq!(session["messages"].as_array().unwrap().len(), 4);
+        assert_eq!(session["messages"][3]["role"], "assistant");
+
+        let (status, deleted) = send("DELETE", &format!("/v1/sessions/{}", id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(deleted["deleted"], true);
+        let (status, _) = send(
+            "POST",
+            &messages_uri,
+            Some(serde_json::json!({"content": "Hello?"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // The body is optional.
+        let (status, session) = send("POST", "/v1/sessions", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(session["system_prompt"].is_null());
+    }
+
+    /// Tests the embeddings endpoint in float and base64 encodings.
+    #[tokio::test]
+    async fn test_embeddings_endpoint() {
+        use crate::semantic_cache::tests::WordEmbeddings;
+        use base64::Engine;
+
+        let client = LLMClient::from_provider(
+            crate::mock::MockLLMProvider::new(),
+            crate::config::LLMConfig::default(),
+        );
+        let state = ServerState::with_llm_client(client, "test-model".to_string());
+        let embed = |app: Router, body: serde_json::Value| async move {
+            let request = axum::http::Request::builder()
+                .method("POST")
+                .uri("/v1/embeddings")
+                .header("Content-Type", "application/json")
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (status, serde_json::from_slice(&body).unwrap_or_default())
+        };
+
+        let (status, _): (_, serde_json::Value) = embed(
+            create_router(state.clone()),
+            serde_json::json!({"model": "e", "input": "hi"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let app = create_router(state.with_embeddings(WordEmbeddings));
+        let expected = WordEmbeddings.embed("hello world").await.unwrap();
+        let (status, body) = embed(
+            app.clone(),
+            serde_json::json!({"model": "words", "input": ["hello world", "bye"]}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["model"], "words");
+        assert_eq!(body["data"].as_array().unwrap().len(), 2);
+        assert_eq!(body["data"][1]["index"], 1);
+        let floats: Vec<f32> =
+            serde_json::from_value(body["data"][0]["embedding"].clone()).unwrap();
+        assert_eq!(floats, expected);
+
+        let (_, body) = embed(
+            app.clone(),
+            serde_json::json!({"model": "words", "input": "hello world", "encoding_format": "base64"}),
+        )
+        .await;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(body["data"][0]["embedding"].as_str().unwrap())
+            .unwrap();
+        let decoded: Vec<f32> = bytes
+            .chunks(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        assert_eq!(decoded, expected);
+
+        let (status, _) = embed(
+            app.clone(),
+            serde_json::json!({"model": "words", "input": "hi", "encoding_format": "int8"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let too_many = vec!["hi"; MAX_EMBEDDING_INPUTS + 1];
+        for input in [serde_json::json!([]), serde_json::json!(too_many)] {
+            let (status, _) = embed(
+                app.clone(),
+                serde_json::json!({"model": "words", "input": input}),
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    /// Tests that Slack mentions become threaded conversations with the agent.
+    #[tokio::test]
+    async fn test_slack_events() {
+        use crate::messaging::tests::mock_slack;
+        use crate::messaging::SlackTool;
+
+        let (slack_url, posted) = mock_slack().await;
+        let mock = crate::mock::MockLLMProvider::new()
+            .with_response("The disk is full.")
+            .with_response("Deleted old logs.");
+        let agent = Agent::builder("oncall")
+            .llm_provider(mock.clone())
+            .build()
+            .await
+            .unwrap();
+        let events =
+            SlackEvents::with_tool("secret", SlackTool::new("xoxb").with_base_url(slack_url));
+        let app = create_router(
+            ServerState::with_agent(agent, "oncall".to_string())
+                .with_api_keys(["server-key"])
+                .with_slack(events),
+        );
+        let send = |body: String, secret: &str| {
+            let mut request = axum::http::Request::builder()
+                .method("POST")
+                .uri("/slack/events")
+                .body(axum::body::Body::from(body.clone()))
+                .unwrap();
+            request
+                .headers_mut()
+                .extend(crate::messaging::tests::slack_headers(secret, &body));
+            app.clone().oneshot(request)
+        };
+        let wait_for_posts = |count: usize| {
+            let posted = posted.clone();
+            async move {
+                for _ in 0..200 {
+                    if posted.lock().unwrap().len() >= count {
+                        return;
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                panic!("no reply was posted");
+            }
+        };
+
+        let challenge = r#"{"type":"url_verification","challenge":"xyz"}"#.to_string();
+        let response = send(challenge.clone(), "secret").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body.as_ref(), br#"{"challenge":"xyz"}"#);
+        let response = send(challenge, "forged").await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let mention = r#"{"type":"event_callback","event_id":"Ev1","event":{"type":"app_mention","channel":"C1","ts":"9.0","text":"<@UBOT> why are writes failing?"}}"#;
+        let response = send(mention.to_string(), "secret").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        wait_for_posts(1).await;
+
+        let reply = r#"{"type":"event_callback","event_id":"Ev2","event":{"type":"app_mention","channel":"C1","ts":"9.5","thread_ts":"9.0","text":"<@UBOT> fix it"}}"#;
+        send(reply.to_string(), "secret").await.unwrap();
+        wait_for_posts(2).await;
+
+        let posted = posted.lock().unwrap();
+        assert_eq!(posted[0]["text"], "The disk is full.");
+        assert_eq!(posted[0]["thread_ts"], "9.0");
+        assert_eq!(posted[1]["text"], "Deleted old logs.");
+        assert_eq!(posted[1]["thread_ts"], "9.0");
+        let requests = mock.requests();
+        let history: Vec<&str> = requests[1]
+            .messages
+            .iter()
+            .filter(|m| m.role != Role::System)
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(
+            history,
+            ["why are writes failing?", "The disk is full.", "fix it"]
+        );
+    }
 }
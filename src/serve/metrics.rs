@@ -0,0 +1,243 @@
+//! Prometheus metrics for the server, exported at `GET /metrics`.
+//!
+//! Every request is counted by route, method and status, and its latency is
+//! recorded in a histogram; for streamed replies that is the time until the
+//! stream starts. The tokens used by completions are totalled, and when an agent
+//! is served, its tool calls are counted from its [`ToolAudit`].
+
+use super::Usage;
+use crate::audit::ToolAudit;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The upper bounds, in seconds, of the request latency histogram's buckets.
+const LATENCY_BUCKETS: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+
+/// The latencies of the requests to one route.
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// The number of requests in each bucket, not cumulative.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+/// The counters behind a [`ServerMetrics`].
+#[derive(Debug, Default)]
+struct MetricsState {
+    /// Requests by route, method and status.
+    requests: BTreeMap<(String, String, u16), u64>,
+    /// Latencies by route and method.
+    latencies: BTreeMap<(String, String), Histogram>,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+/// The server's request, token and tool call metrics.
+#[derive(Debug, Default)]
+pub struct ServerMetrics {
+    state: Mutex<MetricsState>,
+    tool_audit: Option<ToolAudit>,
+}
+
+impl ServerMetrics {
+    /// Creates empty metrics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the tool calls recorded in `audit`.
+    pub fn with_tool_audit(mut self, audit: ToolAudit) -> Self {
+        self.tool_audit = Some(audit);
+        self
+    }
+
+    /// Locks the counters, recovering them if a writer panicked.
+    fn lock(&self) -> std::sync::MutexGuard<'_, MetricsState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Records a request to `route` answered with `status` after `latency`.
+    pub fn record_request(&self, route: &str, method: &str, status: u16, latency: Duration) {
+        let mut state = self.lock();
+        *state
+            .requests
+            .entry((route.to_string(), method.to_string(), status))
+            .or_default() += 1;
+        state
+            .latencies
+            .entry((route.to_string(), method.to_string()))
+            .or_default()
+            .observe(latency.as_secs_f64());
+    }
+
+    /// Adds the tokens a completion used.
+    pub fn record_usage(&self, usage: &Usage) {
+        let mut state = self.lock();
+        state.prompt_tokens += u64::from(usage.prompt_tokens);
+        state.completion_tokens += u64::from(usage.completion_tokens);
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        {
+            let state = self.lock();
+
+            out.push_str("# HELP helios_http_requests_total HTTP requests answered.\n");
+            out.push_str("# TYPE helios_http_requests_total counter\n");
+            for ((route, method, status), count) in &state.requests {
+                let _ = writeln!(
+                    out,
+                    "helios_http_requests_total{{route=\"{}\",method=\"{}\",status=\"{}\"}} {}",
+                    escape(route),
+                    escape(method),
+                    status,
+                    count
+                );
+            }
+
+            out.push_str(
+                "# HELP helios_http_request_duration_seconds Time taken to answer HTTP requests.\n",
+            );
+            out.push_str("# TYPE helios_http_request_duration_seconds histogram\n");
+            for ((route, method), histogram) in &state.latencies {
+                let labels = format!("route=\"{}\",method=\"{}\"", escape(route), escape(method));
+                let mut cumulative = 0;
+                for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                    cumulative += count;
+                    let _ = writeln!(
+                        out,
+                        "helios_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                        labels, bound, cumulative
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "helios_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                    labels, histogram.count
+                );
+                let _ = writeln!(
+                    out,
+                    "helios_http_request_duration_seconds_sum{{{}}} {}",
+                    labels, histogram.sum
+                );
+                let _ = writeln!(
+                    out,
+                    "helios_http_request_duration_seconds_count{{{}}} {}",
+                    labels, histogram.count
+                );
+            }
+
+            out.push_str("# HELP helios_tokens_total Tokens used by completions.\n");
+            out.push_str("# TYPE helios_tokens_total counter\n");
+            let _ = writeln!(
+                out,
+                "helios_tokens_total{{type=\"prompt\"}} {}",
+                state.prompt_tokens
+            );
+            let _ = writeln!(
+                out,
+                "helios_tokens_total{{type=\"completion\"}} {}",
+                state.completion_tokens
+            );
+        }
+
+        if let Some(audit) = &self.tool_audit {
+            let stats = audit.stats();
+            out.push_str("# HELP helios_tool_calls_total Tool calls made by the agent.\n");
+            out.push_str("# TYPE helios_tool_calls_total counter\n");
+            for (tool, stats) in &stats {
+                let _ = writeln!(
+                    out,
+                    "helios_tool_calls_total{{tool=\"{}\"}} {}",
+                    escape(tool),
+                    stats.calls
+                );
+            }
+            out.push_str("# HELP helios_tool_call_failures_total Tool calls that failed.\n");
+            out.push_str("# TYPE helios_tool_call_failures_total counter\n");
+            for (tool, stats) in &stats {
+                let _ = writeln!(
+                    out,
+                    "helios_tool_call_failures_total{{tool=\"{}\"}} {}",
+                    escape(tool),
+                    stats.failures
+                );
+            }
+        }
+
+        out
+    }
+}
+
+/// Escapes a label value for the text exposition format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that latency buckets are rendered cumulatively and labels escaped.
+    #[test]
+    fn test_render_metrics() {
+        let metrics = ServerMetrics::new();
+        let route = "/v1/\"chat\"";
+        metrics.record_request(route, "POST", 200, Duration::from_millis(3));
+        metrics.record_request(route, "POST", 500, Duration::from_millis(200));
+        metrics.record_request(route, "POST", 200, Duration::from_secs(600));
+        metrics.record_usage(&Usage {
+            prompt_tokens: 12,
+            completion_tokens: 5,
+            total_tokens: 17,
+        });
+
+        let text = metrics.render();
+        let labels = "route=\"/v1/\\\"chat\\\"\",method=\"POST\"";
+        for line in [
+            format!("helios_http_requests_total{{{},status=\"200\"}} 2", labels),
+            format!("helios_http_requests_total{{{},status=\"500\"}} 1", labels),
+            format!(
+                "helios_http_request_duration_seconds_bucket{{{},le=\"0.005\"}} 1",
+                labels
+            ),
+            format!(
+                "helios_http_request_duration_seconds_bucket{{{},le=\"0.25\"}} 2",
+                labels
+            ),
+            format!(
+                "helios_http_request_duration_seconds_bucket{{{},le=\"120\"}} 2",
+                labels
+            ),
+            format!(
+                "helios_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 3",
+                labels
+            ),
+            format!("helios_http_request_duration_seconds_count{{{}}} 3", labels),
+            "helios_tokens_total{type=\"prompt\"} 12".to_string(),
+            "helios_tokens_total{type=\"completion\"} 5".to_string(),
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {}", line);
+        }
+        assert!(!text.contains("helios_tool_calls_total"));
+    }
+}
@@ -0,0 +1,535 @@
+//! The OpenAI Responses API, served at `POST /v1/responses`.
+//!
+//! The request's `input` and `instructions` become the conversation passed to the
+//! served agent or LLM client. The tools an agent runs during the turn are
+//! reported as `function_call` and `function_call_output` items ahead of the
+//! reply's `message` item, so clients can show what the agent did. Tool calls in
+//! the input are accepted as history, but the request's own `tools` are not
+//! offered to the model: the agent's tools are.
+//!
+//! With `"stream": true` the response is sent as the API's server-sent events,
+//! from `response.created` to `response.completed`.
+
+use super::{ServerState, Usage};
+use crate::chat::{ChatMessage, FunctionCall, Role, ToolCall};
+use crate::error::{HeliosError, Result};
+use crate::llm::StreamEvent;
+use crate::moderation::{self, ModerationTarget};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{
+        sse::{Event, Sse},
+        IntoResponse,
+    },
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tracing::{error, info, warn};
+
+/// A request to create a response.
+#[derive(Debug, Deserialize)]
+pub struct ResponsesRequest {
+    /// The model to use.
+    pub model: String,
+    /// The conversation: a single user message, or a list of input items.
+    pub input: ResponseInput,
+    /// A system message placed before the input.
+    #[serde(default)]
+    pub instructions: Option<String>,
+    /// The temperature to use.
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// The maximum number of tokens to generate.
+    #[serde(default)]
+    pub max_output_tokens: Option<u32>,
+    /// Whether to stream the response as server-sent events.
+    #[serde(default)]
+    pub stream: Option<bool>,
+}
+
+/// The `input` of a [`ResponsesRequest`].
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ResponseInput {
+    /// A single user message.
+    Text(String),
+    /// Messages, function calls and function call outputs, oldest first.
+    Items(Vec<Value>),
+}
+
+impl ResponseInput {
+    /// Converts the input into chat messages.
+    fn into_messages(self) -> Result<Vec<ChatMessage>> {
+        let items = match self {
+            ResponseInput::Text(text) => return Ok(vec![ChatMessage::user(text)]),
+            ResponseInput::Items(items) => items,
+        };
+        let mut messages: Vec<ChatMessage> = Vec::new();
+        for item in items {
+            let field = |name: &str| item.get(name).and_then(Value::as_str).unwrap_or_default();
+            match item
+                .get("type")
+                .and_then(Value::as_str)
+                .unwrap_or("message")
+            {
+                "message" => messages.push(input_message(&item)?),
+                "function_call" => {
+                    let call = ToolCall {
+                        id: field("call_id").to_string(),
+                        call_type: "function".to_string(),
+                        function: FunctionCall {
+                            name: field("name").to_string(),
+                            arguments: field("arguments").to_string(),
+                        },
+                    };
+                    // Calls made in one step share the assistant message that made them.
+                    match messages.last_mut() {
+                        Some(ChatMessage {
+                            role: Role::Assistant,
+                            tool_calls: Some(calls),
+                            ..
+                        }) => calls.push(call),
+                        _ => {
+                            let mut message = ChatMessage::assistant("");
+                            message.tool_calls = Some(vec![call]);
+                            messages.push(message);
+                        }
+                    }
+                }
+                "function_call_output" => {
+                    let output = match item.get("output") {
+                        Some(Value::String(output)) => output.clone(),
+                        Some(output) => output.to_string(),
+                        None => String::new(),
+                    };
+                    messages.push(ChatMessage::tool(output, field("call_id")));
+                }
+                // Reasoning is the model's own, and is not sent back to it.
+                "reasoning" => {}
+                other => {
+                    return Err(HeliosError::ConfigError(format!(
+                        "Unsupported input item type: {}",
+                        other
+                    )))
+                }
+            }
+        }
+        Ok(messages)
+    }
+}
+
+/// Converts a `message` input item, whose content is a string or a list of text parts.
+fn input_message(item: &Value) -> Result<ChatMessage> {
+    let role = match item.get("role").and_then(Value::as_str).unwrap_or_default() {
+        "system" | "developer" => Role::System,
+        "user" => Role::User,
+        "assistant" => Role::Assistant,
+        other => return Err(HeliosError::ConfigError(format!("Invalid role: {}", other))),
+    };
+    let content = match item.get("content") {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(parts)) => {
+            let mut texts = Vec::new();
+            for part in parts {
+                match part.get("type").and_then(Value::as_str) {
+                    Some("input_text" | "output_text" | "text") => {
+                        texts.push(part.get("text").and_then(Value::as_str).unwrap_or_default())
+                    }
+                    other => {
+                        return Err(HeliosError::ConfigError(format!(
+                            "Unsupported content part type: {}",
+                            other.unwrap_or("none")
+                        )))
+                    }
+                }
+            }
+            texts.join("\n")
+        }
+        _ => String::new(),
+    };
+    let mut message = ChatMessage::user(content);
+    message.role = role;
+    Ok(message)
+}
+
+/// Handles `POST /v1/responses`.
+pub(super) async fn create_response(
+    State(state): State<ServerState>,
+    Json(request): Json<ResponsesRequest>,
+) -> std::result::Result<axum::response::Response, StatusCode> {
+    let mut messages = request.input.into_messages().map_err(|e| {
+        error!("Failed to convert response input: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    if let Some(instructions) = request.instructions {
+        messages.insert(0, ChatMessage::system(instructions));
+    }
+    if state.abort.is_aborted() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    let temperature = request.temperature;
+    let max_tokens = request.max_output_tokens;
+    if request.stream.unwrap_or(false) {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let mut writer = ResponseWriter::new(request.model, Some(sender));
+        // A reply the server moderates is only sent once it is complete.
+        writer.buffered = !state.moderators.is_empty();
+        tokio::spawn(async move {
+            writer.start();
+            let mut usage = Usage::default();
+            let result = run(
+                &state,
+                messages,
+                temperature,
+                max_tokens,
+                |event| writer.event(event),
+                &mut usage,
+            )
+            .await;
+            state.metrics.record_usage(&usage);
+            if let Err(e) = writer.finish(result, usage) {
+                error!("Streamed response failed: {}", e);
+            }
+        });
+        return Ok(Sse::new(UnboundedReceiverStream::new(receiver))
+            .keep_alive(axum::response::sse::KeepAlive::default())
+            .into_response());
+    }
+
+    let mut writer = ResponseWriter::new(request.model, None);
+    let mut usage = Usage::default();
+    let result = run(
+        &state,
+        messages,
+        temperature,
+        max_tokens,
+        |event| writer.event(event),
+        &mut usage,
+    )
+    .await;
+    state.metrics.record_usage(&usage);
+    match writer.finish(result, usage) {
+        Ok(response) => Ok(Json(response).into_response()),
+        Err(HeliosError::Aborted(_)) => Err(StatusCode::SERVICE_UNAVAILABLE),
+        Err(HeliosError::BudgetExceeded(reason)) => {
+            warn!("Agent budget exceeded: {}", reason);
+            Err(StatusCode::TOO_MANY_REQUESTS)
+        }
+        Err(e) => {
+            error!("Response error: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Runs one turn with the served agent or LLM client, reporting its text and
+/// tool calls to `on_event`.
+///
+/// The last user message and the reply pass through the server's moderators, and
+/// the tokens used are written to `usage`.
+async fn run<F>(
+    state: &ServerState,
+    mut messages: Vec<ChatMessage>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    on_event: F,
+    usage: &mut Usage,
+) -> Result<String>
+where
+    F: FnMut(StreamEvent) + Send,
+{
+    moderation::moderate_last_input(&state.moderators, &mut messages).await?;
+
+    let content = if let Some(agent) = &state.agent {
+        let mut agent = agent.write().await;
+        let usage_before = agent.usage();
+        let result = agent
+            .chat_stream_with_history_events(messages, temperature, max_tokens, None, on_event)
+            .await;
+        *usage = Usage::from(agent.usage().since(&usage_before));
+        result?.content
+    } else if let Some(llm_client) = state.current_client().await {
+        let (message, call_usage) = state
+            .abort
+            .run(llm_client.chat_stream_events_with_usage(
+                messages,
+                None,
+                temperature,
+                max_tokens,
+                None,
+                on_event,
+            ))
+            .await??;
+        *usage = Usage::from(call_usage);
+        message.content
+    } else {
+        return Err(HeliosError::ConfigError(
+            "No agent or LLM client to serve".to_string(),
+        ));
+    };
+
+    moderation::moderate(&state.moderators, &content, ModerationTarget::Output).await
+}
+
+/// Builds a response's output items from the events of a turn, sending the
+/// streaming events for them when streaming.
+struct ResponseWriter {
+    id: String,
+    created_at: i64,
+    model: String,
+    output: Vec<Value>,
+    /// The ID and text of the message item being written, if any.
+    message: Option<(String, String)>,
+    sequence_number: u64,
+    sender: Option<UnboundedSender<std::result::Result<Event, Infallible>>>,
+    /// Whether text is written only once the reply is final, rather than as it streams.
+    buffered: bool,
+}
+
+impl ResponseWriter {
+    /// Creates a writer, streaming to `sender` if given.
+    ///
+    /// Without a sender the reply is written in full when the turn finishes.
+    fn new(
+        model: String,
+        sender: Option<UnboundedSender<std::result::Result<Event, Infallible>>>,
+    ) -> Self {
+        Self {
+            id: format!("resp_{}", crate::clock::new_id().simple()),
+            created_at: crate::clock::now().timestamp(),
+            model,
+            output: Vec::new(),
+            message: None,
+            sequence_number: 0,
+            buffered: sender.is_none(),
+            sender,
+        }
+    }
+
+    /// Sends a streaming event of type `kind`, if streaming.
+    fn emit(&mut self, kind: &str, mut data: Value) {
+        let Some(sender) = &self.sender else { return };
+        data["type"] = json!(kind);
+        data["sequence_number"] = json!(self.sequence_number);
+        self.sequence_number += 1;
+        if let Ok(event) = Event::default().event(kind).json_data(data) {
+            let _ = sender.send(Ok(event));
+        }
+    }
+
+    /// Returns the response object with the given status and output so far.
+    fn response(&self, status: &str, usage: Option<&Usage>) -> Value {
+        let output_text: String = self
+            .output
+            .iter()
+            .filter(|item| item["type"] == "message")
+            .filter_map(|item| item["content"][0]["text"].as_str())
+            .collect();
+        json!({
+            "id": self.id,
+            "object": "response",
+            "created_at": self.created_at,
+            "status": status,
+            "model": self.model,
+            "output": self.output,
+            "output_text": output_text,
+            "usage": usage.map(|usage| json!({
+                "input_tokens": usage.prompt_tokens,
+                "output_tokens": usage.completion_tokens,
+                "total_tokens": usage.total_tokens,
+            })),
+        })
+    }
+
+    /// Sends the events that open the response.
+    fn start(&mut self) {
+        let response = self.response("in_progress", None);
+        self.emit("response.created", json!({ "response": response }));
+        self.emit("response.in_progress", json!({ "response": response }));
+    }
+
+    /// Records one event of the turn.
+    fn event(&mut self, event: StreamEvent) {
+        match event {
+            StreamEvent::Content(text) if !self.buffered => self.text(&text),
+            StreamEvent::ToolStarted {
+                id,
+                name,
+                arguments,
+            } => {
+                self.close_message();
+                let item_id = format!("fc_{}", crate::clock::new_id().simple());
+                let item = json!({
+                    "type": "function_call",
+                    "id": item_id,
+                    "call_id": id,
+                    "name": name,
+                    "arguments": arguments,
+                    "status": "completed",
+                });
+                self.add_item(item, |writer, output_index| {
+                    writer.emit(
+                        "response.function_call_arguments.done",
+                        json!({
+                            "item_id": item_id,
+                            "output_index": output_index,
+                            "arguments": arguments,
+                        }),
+                    );
+                });
+            }
+            StreamEvent::ToolFinished { id, output, .. } => {
+                let item = json!({
+                    "type": "function_call_output",
+                    "id": format!("fco_{}", crate::clock::new_id().simple()),
+                    "call_id": id,
+                    "output": output,
+                    "status": "completed",
+                });
+                self.add_item(item, |_, _| {});
+            }
+            _ => {}
+        }
+    }
+
+    /// Adds a finished item to the output, sending `between` after it is added.
+    fn add_item(&mut self, item: Value, between: impl FnOnce(&mut Self, usize)) {
+        let output_index = self.output.len();
+        self.emit(
+            "response.output_item.added",
+            json!({ "output_index": output_index, "item": item }),
+        );
+        between(self, output_index);
+        self.emit(
+            "response.output_item.done",
+            json!({ "output_index": output_index, "item": item }),
+        );
+        self.output.push(item);
+    }
+
+    /// Adds text to the message item, opening one if needed.
+    fn text(&mut self, delta: &str) {
+        let output_index = self.output.len();
+        if self.message.is_none() {
+            let item_id = format!("msg_{}", crate::clock::new_id().simple());
+            self.emit(
+                "response.output_item.added",
+                json!({
+                    "output_index": output_index,
+                    "item": {
+                        "type": "message",
+                        "id": item_id,
+                        "status": "in_progress",
+                        "role": "assistant",
+                        "content": [],
+                    },
+                }),
+            );
+            self.emit(
+                "response.content_part.added",
+                json!({
+                    "item_id": item_id,
+                    "output_index": output_index,
+                    "content_index": 0,
+                    "part": { "type": "output_text", "text": "", "annotations": [] },
+                }),
+            );
+            self.message = Some((item_id, String::new()));
+        }
+        let item_id = match &mut self.message {
+            Some((item_id, text)) => {
+                text.push_str(delta);
+                item_id.clone()
+            }
+            None => return,
+        };
+        self.emit(
+            "response.output_text.delta",
+            json!({
+                "item_id": item_id,
+                "output_index": output_index,
+                "content_index": 0,
+                "delta": delta,
+            }),
+        );
+    }
+
+    /// Finishes the message item being written, if any.
+    fn close_message(&mut self) {
+        let Some((item_id, text)) = self.message.take() else {
+            return;
+        };
+        let output_index = self.output.len();
+        let part = json!({ "type": "output_text", "text": text, "annotations": [] });
+        self.emit(
+            "response.output_text.done",
+            json!({
+                "item_id": item_id,
+                "output_index": output_index,
+                "content_index": 0,
+                "text": text,
+            }),
+        );
+        self.emit(
+            "response.content_part.done",
+            json!({
+                "item_id": item_id,
+                "output_index": output_index,
+                "content_index": 0,
+                "part": part,
+            }),
+        );
+        let item = json!({
+            "type": "message",
+            "id": item_id,
+            "status": "completed",
+            "role": "assistant",
+            "content": [part],
+        });
+        self.emit(
+            "response.output_item.done",
+            json!({ "output_index": output_index, "item": item }),
+        );
+        self.output.push(item);
+    }
+
+    /// Finishes the response with the result of the turn and returns it.
+    ///
+    /// A reply blocked by moderation ends the response as `incomplete`, with
+    /// `content_filter` as the reason. Other errors are returned, after a
+    /// `response.failed` event when streaming.
+    fn finish(&mut self, result: Result<String>, usage: Usage) -> Result<Value> {
+        match result {
+            Ok(content) => {
+                if self.buffered && !content.is_empty() {
+                    self.text(&content);
+                }
+                self.close_message();
+                let response = self.response("completed", Some(&usage));
+                self.emit("response.completed", json!({ "response": response }));
+                Ok(response)
+            }
+            Err(HeliosError::Blocked(reason)) => {
+                info!("Response blocked: {}", reason);
+                self.close_message();
+                let mut response = self.response("incomplete", Some(&usage));
+                response["incomplete_details"] = json!({ "reason": "content_filter" });
+                self.emit("response.incomplete", json!({ "response": response }));
+                Ok(response)
+            }
+            Err(e) => {
+                self.close_message();
+                let mut response = self.response("failed", Some(&usage));
+                response["error"] = json!({ "code": "server_error", "message": e.to_string() });
+                self.emit("response.failed", json!({ "response": response }));
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,411 @@
+//! Conversations kept by the server, served at `/v1/sessions`.
+//!
+//! A client creates a session with `POST /v1/sessions` and then sends each user
+//! message alone to `POST /v1/sessions/{id}/messages`; the server adds it and the
+//! reply to the session's [`ChatSession`], so the history is not resent with
+//! every request. `GET /v1/sessions/{id}` returns the conversation and
+//! `DELETE /v1/sessions/{id}` ends it.
+//!
+//! Sessions left idle longer than [`ServerSessions::idle_timeout`] are evicted.
+//! With [`ServerSessions::persist_to`], every session is also saved to a
+//! [`SessionStore`] after each reply; an evicted or restarted session is loaded
+//! again from there when it is next used, and only deleting it removes the file.
+
+use super::{ChatCompletionResponse, CompletionChoice, OpenAIMessageResponse, ServerState, Usage};
+use crate::chat::{ChatMessage, ChatSession, Role, SessionStore};
+use crate::error::{HeliosError, Result};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// A session held in memory.
+struct SessionEntry {
+    session: Arc<tokio::sync::Mutex<ChatSession>>,
+    last_used: DateTime<Utc>,
+}
+
+/// The sessions a server keeps, shared by its clones.
+#[derive(Clone)]
+pub struct ServerSessions {
+    entries: Arc<Mutex<HashMap<String, SessionEntry>>>,
+    store: Option<SessionStore>,
+    idle_timeout: Duration,
+    max_sessions: usize,
+}
+
+impl Default for ServerSessions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServerSessions {
+    /// Creates in-memory sessions that are evicted after 30 idle minutes, keeping
+    /// at most 10,000.
+    pub fn new() -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            store: None,
+            idle_timeout: Duration::from_secs(30 * 60),
+            max_sessions: 10_000,
+        }
+    }
+
+    /// Saves sessions to `store`, so they outlive eviction and restarts.
+    pub fn persist_to(mut self, store: SessionStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Sets how long a session may go unused before it is evicted.
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Sets how many sessions are kept in memory; the least recently used one is
+    /// evicted to make room for a new one.
+    pub fn max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions.max(1);
+        self
+    }
+
+    /// Returns the store sessions are saved to, if any.
+    pub fn store(&self) -> Option<&SessionStore> {
+        self.store.as_ref()
+    }
+
+    /// Locks the entries, recovering them if a writer panicked.
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, SessionEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Starts a session with `session` as its history and returns its ID.
+    pub fn create(&self, session: ChatSession) -> Result<String> {
+        let id = format!("sess_{}", crate::clock::new_id().simple());
+        self.save(&id, &session)?;
+        self.insert(&id, session);
+        Ok(id)
+    }
+
+    /// Returns the session with the ID `id`, loading it from the store when it is
+    /// not in memory.
+    pub fn get(&self, id: &str) -> Option<Arc<tokio::sync::Mutex<ChatSession>>> {
+        self.evict_idle();
+        if let Some(entry) = self.lock().get_mut(id) {
+            entry.last_used = crate::clock::now();
+            return Some(entry.session.clone());
+        }
+        let store = self.store.as_ref().filter(|store| store.contains(id))?;
+        match store.load(id) {
+            Ok(session) => Some(self.insert(id, session)),
+            Err(e) => {
+                warn!("Failed to load session '{}': {}", id, e);
+                None
+            }
+        }
+    }
+
+    /// Saves `session` under `id` to the store, if there is one.
+    pub fn save(&self, id: &str, session: &ChatSession) -> Result<()> {
+        match &self.store {
+            Some(store) => store.save(id, session),
+            None => Ok(()),
+        }
+    }
+
+    /// Ends the session with the ID `id`, deleting it from the store too.
+    ///
+    /// Returns `false` if there was no such session.
+    pub fn remove(&self, id: &str) -> Result<bool> {
+        let mut removed = self.lock().remove(id).is_some();
+        if let Some(store) = self.store.as_ref().filter(|store| store.contains(id)) {
+            store.delete(id)?;
+            removed = true;
+        }
+        Ok(removed)
+    }
+
+    /// Evicts the sessions idle for longer than the idle timeout and returns how
+    /// many were evicted. Sessions answering a message are kept.
+    pub fn evict_idle(&self) -> usize {
+        let Ok(timeout) = chrono::Duration::from_std(self.idle_timeout) else {
+            return 0;
+        };
+        let cutoff = crate::clock::now() - timeout;
+        let mut entries = self.lock();
+        let before = entries.len();
+        entries.retain(|_, entry| entry.last_used > cutoff || entry.session.try_lock().is_err());
+        before - entries.len()
+    }
+
+    /// Returns the number of sessions in memory.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if no sessions are in memory.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds a session to memory, evicting the least recently used one when full.
+    fn insert(&self, id: &str, session: ChatSession) -> Arc<tokio::sync::Mutex<ChatSession>> {
+        let session = Arc::new(tokio::sync::Mutex::new(session));
+        let mut entries = self.lock();
+        if entries.len() >= self.max_sessions && !entries.contains_key(id) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            id.to_string(),
+            SessionEntry {
+                session: session.clone(),
+                last_used: crate::clock::now(),
+            },
+        );
+        session
+    }
+}
+
+/// A request to start a session.
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateSessionRequest {
+    /// The system prompt of the conversation.
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Metadata kept with the conversation.
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+/// A request to add a user message to a session and get the reply.
+#[derive(Debug, Deserialize)]
+pub struct SessionMessageRequest {
+    /// The user message.
+    pub content: String,
+    /// The temperature to use.
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// The maximum number of tokens to generate.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Stop sequences.
+    #[serde(default)]
+    pub stop: Option<Vec<String>>,
+}
+
+/// A session and its conversation.
+#[derive(Debug, Serialize)]
+pub struct SessionObject {
+    /// The session ID.
+    pub id: String,
+    /// The object type, `session`.
+    pub object: String,
+    /// The system prompt of the conversation.
+    pub system_prompt: Option<String>,
+    /// The user and assistant messages so far.
+    pub messages: Vec<OpenAIMessageResponse>,
+    /// Metadata kept with the conversation.
+    pub metadata: HashMap<String, String>,
+}
+
+impl SessionObject {
+    fn new(id: String, session: &ChatSession) -> Self {
+        let messages = session
+            .messages
+            .iter()
+            .filter_map(|message| {
+                let role = match message.role {
+                    Role::User => "user",
+                    Role::Assistant => "assistant",
+                    _ => return None,
+                };
+                Some(OpenAIMessageResponse {
+                    role: role.to_string(),
+                    content: message.content.clone(),
+                })
+            })
+            .collect();
+        Self {
+            id,
+            object: "session".to_string(),
+            system_prompt: session.system_prompt.clone(),
+            messages,
+            metadata: session.metadata.clone(),
+        }
+    }
+}
+
+/// Starts a session. The request body is optional.
+pub(super) async fn create_session(
+    State(state): State<ServerState>,
+    request: Option<Json<CreateSessionRequest>>,
+) -> std::result::Result<Json<SessionObject>, StatusCode> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let mut session = ChatSession::new();
+    session.system_prompt = request.system_prompt;
+    session.metadata = request.metadata;
+    let id = state.sessions.create(session.clone()).map_err(|e| {
+        error!("Failed to create session: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!("Started session {}", id);
+    Ok(Json(SessionObject::new(id, &session)))
+}
+
+/// Returns a session's conversation.
+pub(super) async fn get_session(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+) -> std::result::Result<Json<SessionObject>, StatusCode> {
+    let session = state.sessions.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    let session = session.lock().await;
+    Ok(Json(SessionObject::new(id, &session)))
+}
+
+/// Ends a session.
+pub(super) async fn delete_session(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+) -> std::result::Result<Json<serde_json::Value>, StatusCode> {
+    match state.sessions.remove(&id) {
+        Ok(true) => Ok(Json(serde_json::json!({
+            "id": id,
+            "object": "session.deleted",
+            "deleted": true
+        }))),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to delete session {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Adds a user message to a session and answers it with the served agent or LLM
+/// client, in the shape of a chat completion.
+///
+/// Messages to one session are answered one at a time. A message that is blocked
+/// or fails is not added to the conversation.
+pub(super) async fn post_message(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+    Json(request): Json<SessionMessageRequest>,
+) -> std::result::Result<Json<ChatCompletionResponse>, StatusCode> {
+    let session = state.sessions.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    if state.abort.is_aborted() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    let mut session = session.lock().await;
+    let mut messages = session.get_messages();
+    messages.push(ChatMessage::user(request.content.clone()));
+
+    let mut usage = Usage::default();
+    let result = super::complete(
+        &state,
+        messages,
+        request.temperature,
+        request.max_tokens,
+        request.stop,
+        &mut usage,
+    )
+    .await;
+    state.metrics.record_usage(&usage);
+
+    let (content, finish_reason) = match result {
+        Ok(content) => {
+            session.add_user_message(request.content);
+            session.add_assistant_message(content.clone());
+            if let Err(e) = state.sessions.save(&id, &session) {
+                error!("Failed to save session {}: {}", id, e);
+            }
+            (content, "stop")
+        }
+        Err(HeliosError::Blocked(reason)) => {
+            info!("Session message blocked: {}", reason);
+            (String::new(), "content_filter")
+        }
+        Err(HeliosError::Aborted(_)) => return Err(StatusCode::SERVICE_UNAVAILABLE),
+        Err(HeliosError::BudgetExceeded(reason)) => {
+            warn!("Agent budget exceeded: {}", reason);
+            return Err(StatusCode::TOO_MANY_REQUESTS);
+        }
+        Err(e) => {
+            error!("Session message error: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    Ok(Json(ChatCompletionResponse {
+        id: format!("chatcmpl-{}", crate::clock::new_id()),
+        object: "chat.completion".to_string(),
+        created: crate::clock::now().timestamp() as u64,
+        model: state.model_name.clone(),
+        choices: vec![CompletionChoice {
+            index: 0,
+            message: OpenAIMessageResponse {
+                role: "assistant".to_string(),
+                content,
+            },
+            finish_reason: finish_reason.to_string(),
+        }],
+        usage,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{FixedClock, SequentialIds};
+
+    /// Tests idle eviction, and reloading evicted sessions from the store.
+    #[tokio::test]
+    async fn test_server_sessions_eviction() {
+        let clock = Arc::new(FixedClock::new(Utc::now()));
+        let dir = tempfile::tempdir().unwrap();
+        crate::clock::scope(clock.clone(), Arc::new(SequentialIds::new()), async {
+            let sessions = ServerSessions::new().idle_timeout(Duration::from_secs(60));
+            let id = sessions.create(ChatSession::new()).unwrap();
+            clock.advance(chrono::Duration::seconds(30));
+            assert!(sessions.get(&id).is_some());
+            clock.advance(chrono::Duration::seconds(61));
+            assert_eq!(sessions.evict_idle(), 1);
+            assert!(sessions.get(&id).is_none());
+
+            let persisted = ServerSessions::new()
+                .idle_timeout(Duration::from_secs(60))
+                .max_sessions(1)
+                .persist_to(SessionStore::new(dir.path()));
+            let first = persisted
+                .create(ChatSession::new().with_system_prompt("first"))
+                .unwrap();
+            clock.advance(chrono::Duration::seconds(1));
+            let second = persisted.create(ChatSession::new()).unwrap();
+            assert_eq!(persisted.len(), 1);
+            let reloaded = persisted.get(&first).unwrap();
+            assert_eq!(
+                reloaded.lock().await.system_prompt.as_deref(),
+                Some("first")
+            );
+
+            assert!(persisted.remove(&second).unwrap());
+            assert!(!persisted.remove(&second).unwrap());
+            assert!(persisted.get(&second).is_none());
+        })
+        .await;
+    }
+}
@@ -0,0 +1,726 @@
+//! # Shell Policy Module
+//!
+//! This module defines `ShellPolicy`, which decides what `ShellCommandTool` may run.
+//! Instead of searching the command text for dangerous substrings, the command is
+//! split into its pipeline and list segments and the program of every segment is
+//! checked against an allowlist or a denylist. Command substitution and program
+//! names built from variables or wildcards, which could hide a program from that
+//! check, are always refused.
+//!
+//! A policy also controls the shell used (`sh`, `cmd` or PowerShell), which
+//! environment variables reach the command, its working directory, its timeout and
+//! how much output is returned.
+
+use crate::error::{HeliosError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// The shell a command is run with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Shell {
+    /// POSIX `sh -c`.
+    Sh,
+    /// Windows `cmd /C`.
+    Cmd,
+    /// Windows PowerShell, `powershell -NoProfile -NonInteractive -Command`.
+    PowerShell,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        if cfg!(windows) {
+            Shell::Cmd
+        } else {
+            Shell::Sh
+        }
+    }
+}
+
+impl Shell {
+    /// Returns the program and leading arguments used to run a command string.
+    pub fn invocation(&self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            Shell::Sh => ("sh", &["-c"]),
+            Shell::Cmd => ("cmd", &["/C"]),
+            Shell::PowerShell => ("powershell", &["-NoProfile", "-NonInteractive", "-Command"]),
+        }
+    }
+}
+
+/// Rules for what `ShellCommandTool` may run and how.
+///
+/// The default policy blocks a denylist of destructive and privilege-escalating
+/// programs and allows pipes and redirection. `ShellPolicy::read_only()` allows only
+/// a fixed set of inspection commands.
+///
+/// ```rust
+/// use helios_engine::ShellPolicy;
+///
+/// let policy = ShellPolicy::default();
+/// assert!(policy.check("ls -la | grep toml > files.txt").is_ok());
+/// assert!(policy.check("cat notes.txt && rm -rf /").is_err());
+///
+/// let strict = ShellPolicy::allow_only(["git", "cargo"]);
+/// assert!(strict.check("git status").is_ok());
+/// assert!(strict.check("curl example.com").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShellPolicy {
+    /// Programs that may be run. When empty, any program not denied may run.
+    #[serde(default)]
+    pub allowed_programs: Vec<String>,
+    /// Programs that may never run, even if allowed.
+    #[serde(default = "default_denied_programs")]
+    pub denied_programs: Vec<String>,
+    /// Whether `>` and `<` redirections are allowed.
+    #[serde(default = "default_true")]
+    pub allow_redirection: bool,
+    /// Environment variables passed through to the command; all others are cleared.
+    #[serde(default = "default_env_allowlist")]
+    pub env_allowlist: Vec<String>,
+    /// The directory commands run in. Defaults to the current directory.
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    /// The most bytes of stdout, and separately of stderr, returned to the model.
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: usize,
+    /// The timeout used when the call does not set one.
+    #[serde(default = "default_timeout_secs")]
+    pub default_timeout_secs: u64,
+    /// The longest timeout a call may ask for.
+    #[serde(default = "default_max_timeout_secs")]
+    pub max_timeout_secs: u64,
+    /// The shell commands are run with.
+    #[serde(default)]
+    pub shell: Shell,
+}
+
+/// Returns `true`.
+fn default_true() -> bool {
+    true
+}
+
+/// Returns the default list of denied programs.
+fn default_denied_programs() -> Vec<String> {
+    [
+        // Deleting and overwriting data
+        "rm",
+        "rmdir",
+        "shred",
+        "wipe",
+        "dd",
+        "mkfs",
+        "fdisk",
+        "parted",
+        "truncate",
+        "del",
+        "erase",
+        "rd",
+        "format",
+        "diskpart",
+        "remove-item",
+        // Privilege escalation and account changes
+        "sudo",
+        "su",
+        "doas",
+        "pkexec",
+        "runas",
+        "passwd",
+        "chpasswd",
+        "useradd",
+        "usermod",
+        "userdel",
+        "chown",
+        "chmod",
+        "icacls",
+        "takeown",
+        // System state
+        "shutdown",
+        "reboot",
+        "halt",
+        "poweroff",
+        "init",
+        "systemctl",
+        "kill",
+        "killall",
+        "pkill",
+        "taskkill",
+        "stop-process",
+        // Nested shells and interpreters that would bypass the policy
+        "sh",
+        "bash",
+        "zsh",
+        "dash",
+        "fish",
+        "ksh",
+        "csh",
+        "cmd",
+        "powershell",
+        "pwsh",
+        "python",
+        "python2",
+        "python3",
+        "py",
+        "perl",
+        "ruby",
+        "node",
+        "php",
+        "awk",
+        "gawk",
+        "mawk",
+        "nawk",
+        "eval",
+        "exec",
+        "source",
+    ]
+    .iter()
+    .map(|program| program.to_string())
+    .collect()
+}
+
+/// Returns the default environment variable allowlist.
+fn default_env_allowlist() -> Vec<String> {
+    [
+        "PATH",
+        "HOME",
+        "USER",
+        "LANG",
+        "LC_ALL",
+        "TERM",
+        "TMPDIR",
+        "TZ",
+        "SYSTEMROOT",
+        "WINDIR",
+        "COMSPEC",
+        "PATHEXT",
+        "TEMP",
+        "TMP",
+        "USERPROFILE",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
+}
+
+/// Returns the default output limit of 64 KB per stream.
+fn default_max_output_bytes() -> usize {
+    64 * 1024
+}
+
+/// Returns the default command timeout of 30 seconds.
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+/// Returns the default maximum command timeout of 60 seconds.
+fn default_max_timeout_secs() -> u64 {
+    60
+}
+
+/// Programs that run another program given as one of their arguments.
+const WRAPPER_PROGRAMS: &[&str] = &[
+    "env", "xargs", "nice", "nohup", "timeout", "time", "watch", "find", "start", "call",
+    "command", "builtin", "setsid", "stdbuf", "busybox", "ionice", "chroot", "flock", "nsenter",
+    "unshare", "chrt", "taskset", "sudo", "doas", "pkexec", "runas",
+];
+
+/// `find` actions that change files or run other programs.
+///
+/// These are refused together with redirection, and `-delete` is refused
+/// whenever `rm` is.
+const FIND_WRITE_ACTIONS: &[&str] = &[
+    "-delete", "-exec", "-execdir", "-ok", "-okdir", "-fprint", "-fprint0", "-fprintf", "-fls",
+];
+
+/// Shell keywords that can precede the program of a segment.
+const SHELL_KEYWORDS: &[&str] = &[
+    "!", "{", "}", "if", "then", "else", "elif", "fi", "do", "done", "while", "until", "esac",
+];
+
+/// Options whose value is a program that is run, as `(program, option)`.
+const PROGRAM_OPTIONS: &[(&str, &str)] = &[("rg", "--pre")];
+
+/// Options that make a program write to a file, as `(program, short, long)`.
+///
+/// These are refused together with redirection.
+const FILE_WRITING_OPTIONS: &[(&str, char, Option<&str>)] =
+    &[("sort", 'o', Some("--output")), ("tree", 'o', None)];
+
+impl Default for ShellPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_programs: Vec::new(),
+            denied_programs: default_denied_programs(),
+            allow_redirection: true,
+            env_allowlist: default_env_allowlist(),
+            working_dir: None,
+            max_output_bytes: default_max_output_bytes(),
+            default_timeout_secs: default_timeout_secs(),
+            max_timeout_secs: default_max_timeout_secs(),
+            shell: Shell::default(),
+        }
+    }
+}
+
+impl ShellPolicy {
+    /// Creates a policy that only allows the given programs.
+    pub fn allow_only<I, S>(programs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            allowed_programs: programs.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a policy for inspecting files and the system without changing them.
+    ///
+    /// Only common read-only commands are allowed, and redirection is refused along
+    /// with options that write files, such as `sort -o`.
+    pub fn read_only() -> Self {
+        Self {
+            allow_redirection: false,
+            ..Self::allow_only([
+                "ls",
+                "cat",
+                "head",
+                "tail",
+                "grep",
+                "rg",
+                "wc",
+                "sort",
+                "uniq",
+                "cut",
+                "diff",
+                "file",
+                "stat",
+                "du",
+                "df",
+                "pwd",
+                "echo",
+                "date",
+                "uname",
+                "whoami",
+                "which",
+                "tree",
+                "dir",
+                "type",
+                "findstr",
+                "where",
+                "get-childitem",
+                "get-content",
+            ])
+        }
+    }
+
+    /// Sets the directory commands run in.
+    pub fn working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the shell commands are run with.
+    pub fn shell(mut self, shell: Shell) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// Sets the most bytes of each output stream returned to the model.
+    pub fn max_output_bytes(mut self, bytes: usize) -> Self {
+        self.max_output_bytes = bytes;
+        self
+    }
+
+    /// Adds an environment variable to the allowlist.
+    pub fn allow_env(mut self, name: impl Into<String>) -> Self {
+        self.env_allowlist.push(name.into());
+        self
+    }
+
+    /// Checks a command against the policy.
+    ///
+    /// Returns a `ToolError` starting with "Command blocked" if it may not run.
+    pub fn check(&self, command: &str) -> Result<()> {
+        let parsed = parse_command(command);
+
+        if parsed.substitution {
+            return Err(blocked("command substitution is not allowed"));
+        }
+        if parsed.redirection && !self.allow_redirection {
+            return Err(blocked("redirection is not allowed"));
+        }
+        if parsed.segments.iter().all(|segment| segment.is_empty()) {
+            return Err(HeliosError::ToolError("Command is empty".to_string()));
+        }
+
+        for segment in &parsed.segments {
+            let Some(position) = program_position(segment) else {
+                continue;
+            };
+            let word = &segment[position];
+            if word.contains(['$', '{', '}', '*', '?', '[']) {
+                return Err(blocked(&format!(
+                    "'{}' is not a literal program name",
+                    word
+                )));
+            }
+            let program = program_name(word);
+            let arguments = &segment[position + 1..];
+            self.check_program(&program)?;
+
+            for (name, option) in PROGRAM_OPTIONS {
+                if program == *name {
+                    if let Some(value) = option_value(arguments, option) {
+                        self.check_program(&program_name(&value))?;
+                    }
+                }
+            }
+            if !self.allow_redirection {
+                for (name, short, long) in FILE_WRITING_OPTIONS {
+                    if program == *name && has_option(arguments, *short, *long) {
+                        return Err(blocked(&format!(
+                            "'{}' would write a file, and redirection is not allowed",
+                            program
+                        )));
+                    }
+                }
+            }
+
+            if program == "find" {
+                if self.is_denied("rm") && arguments.iter().any(|a| a == "-delete") {
+                    return Err(blocked("'find -delete' is not allowed"));
+                }
+                if !self.allow_redirection {
+                    if let Some(action) = arguments
+                        .iter()
+                        .find(|a| FIND_WRITE_ACTIONS.contains(&a.as_str()))
+                    {
+                        return Err(blocked(&format!(
+                            "'find {}' would change files, and redirection is not allowed",
+                            action
+                        )));
+                    }
+                }
+            }
+
+            // Wrappers run their arguments as programs, so those must pass too
+            if WRAPPER_PROGRAMS.contains(&program.as_str()) {
+                for argument in arguments {
+                    let name = program_name(argument);
+                    if self.is_denied(&name) {
+                        return Err(blocked(&format!("'{}' is not allowed", name)));
+                    }
+                    // A quoted command line, such as the one `env -S` splits, is
+                    // checked as a command of its own
+                    let nested = argument
+                        .strip_prefix("--split-string=")
+                        .or_else(|| argument.strip_prefix("-S").filter(|_| program == "env"))
+                        .unwrap_or(argument);
+                    if nested.contains(char::is_whitespace) && !nested.trim().is_empty() {
+                        self.check(nested)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the allowlisted environment variables from the current process.
+    pub fn environment(&self) -> Vec<(String, String)> {
+        std::env::vars()
+            .filter(|(name, _)| {
+                self.env_allowlist
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(name))
+            })
+            .collect()
+    }
+
+    fn check_program(&self, program: &str) -> Result<()> {
+        if self.is_denied(program) {
+            return Err(blocked(&format!("'{}' is not allowed", program)));
+        }
+        if !self.allowed_programs.is_empty()
+            && !self
+                .allowed_programs
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(program))
+        {
+            return Err(blocked(&format!("'{}' is not in the allowlist", program)));
+        }
+        Ok(())
+    }
+
+    fn is_denied(&self, program: &str) -> bool {
+        self.denied_programs
+            .iter()
+            .any(|denied| denied.eq_ignore_ascii_case(program))
+    }
+}
+
+/// Builds the error returned for a refused command.
+fn blocked(reason: &str) -> HeliosError {
+    HeliosError::ToolError(format!("Command blocked by shell policy: {}", reason))
+}
+
+/// A command split into the segments separated by pipes and list operators.
+#[derive(Debug, Default)]
+struct ParsedCommand {
+    segments: Vec<Vec<String>>,
+    redirection: bool,
+    substitution: bool,
+}
+
+/// Splits a command into segments of words, honouring quotes and escapes.
+fn parse_command(command: &str) -> ParsedCommand {
+    let mut parsed = ParsedCommand::default();
+    let mut segment = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut single = false;
+    let mut double = false;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if single {
+            if c == '\'' {
+                single = false;
+            } else {
+                word.push(c);
+            }
+            continue;
+        }
+        match c {
+            '\\' if !cfg!(windows) => {
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                    in_word = true;
+                }
+            }
+            '"' => {
+                double = !double;
+                in_word = true;
+            }
+            '`' => parsed.substitution = true,
+            '$' if matches!(chars.peek(), Some('(')) => parsed.substitution = true,
+            '\'' if !double => {
+                single = true;
+                in_word = true;
+            }
+            _ if double => word.push(c),
+            '<' if matches!(chars.peek(), Some('(')) => parsed.substitution = true,
+            '>' | '<' => {
+                parsed.redirection = true;
+                finish_word(&mut word, &mut in_word, &mut segment);
+                // `2>&1` duplicates a descriptor rather than starting a new command
+                if matches!(chars.peek(), Some('&')) {
+                    chars.next();
+                    while matches!(chars.peek(), Some(c) if c.is_ascii_digit() || *c == '-') {
+                        chars.next();
+                    }
+                }
+            }
+            '|' | '&' | ';' | '\n' | '(' | ')' => {
+                finish_word(&mut word, &mut in_word, &mut segment);
+                parsed.segments.push(std::mem::take(&mut segment));
+            }
+            c if c.is_whitespace() => finish_word(&mut word, &mut in_word, &mut segment),
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    finish_word(&mut word, &mut in_word, &mut segment);
+    parsed.segments.push(segment);
+    parsed
+}
+
+fn finish_word(word: &mut String, in_word: &mut bool, segment: &mut Vec<String>) {
+    if *in_word || !word.is_empty() {
+        segment.push(std::mem::take(word));
+    }
+    *in_word = false;
+}
+
+/// Returns the index of the word a segment runs as its program, skipping leading
+/// `NAME=value` assignments and shell keywords such as `then` or `{`.
+fn program_position(segment: &[String]) -> Option<usize> {
+    segment
+        .iter()
+        .position(|word| !is_assignment(word) && !SHELL_KEYWORDS.contains(&word.as_str()))
+}
+
+/// Returns the value given to a long option as `--name value` or `--name=value`.
+fn option_value(arguments: &[String], option: &str) -> Option<String> {
+    let mut iter = arguments.iter();
+    while let Some(argument) = iter.next() {
+        if argument == "--" {
+            break;
+        }
+        if argument == option {
+            return iter.next().cloned();
+        }
+        if let Some(value) = argument
+            .strip_prefix(option)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Returns `true` if the arguments set an option, including in a cluster of short
+/// options such as `-uo` or as an abbreviated long option such as `--out=file`.
+fn has_option(arguments: &[String], short: char, long: Option<&str>) -> bool {
+    for argument in arguments {
+        if argument == "--" {
+            break;
+        }
+        if let Some(name) = argument.strip_prefix("--") {
+            let name = name.split('=').next().unwrap_or(name);
+            if let Some(long) = long.and_then(|long| long.strip_prefix("--")) {
+                if !name.is_empty() && long.starts_with(name) {
+                    return true;
+                }
+            }
+        } else if let Some(cluster) = argument.strip_prefix('-') {
+            if cluster.contains(short) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn is_assignment(word: &str) -> bool {
+    match word.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+/// Reduces a program path such as `/bin/rm` or `C:\Tools\rm.exe` to `rm`.
+fn program_name(word: &str) -> String {
+    let name = word.rsplit(['/', '\\']).next().unwrap_or(word);
+    let name = name.to_lowercase();
+    for extension in [".exe", ".cmd", ".bat", ".com", ".ps1"] {
+        if let Some(stem) = name.strip_suffix(extension) {
+            return stem.to_string();
+        }
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that pipes, redirection and quoted operators are allowed by default.
+    #[test]
+    fn test_default_policy_allows_pipelines() {
+        let policy = ShellPolicy::default();
+        assert!(policy.check("ls -la | grep rs > out.txt").is_ok());
+        assert!(policy.check("echo 'a; rm -rf /' && cargo build").is_ok());
+        assert!(policy
+            .check("RUST_LOG=debug cargo test 2>&1 | tail")
+            .is_ok());
+    }
+
+    /// Tests that denied programs are caught anywhere in the command.
+    #[test]
+    fn test_default_policy_blocks_denied_programs() {
+        let policy = ShellPolicy::default();
+        for command in [
+            "rm -rf /",
+            "ls; /bin/rm -rf /",
+            "cat x || sudo reboot",
+            "find . -name '*.log' -exec rm {} +",
+            "ls | xargs rm",
+            "bash -c 'rm -rf /'",
+            "echo $(rm -rf /)",
+            "echo `whoami`",
+            "FOO=1 RM.EXE file",
+            "(rm -rf /)",
+            "{ rm -rf /; }",
+            "if true; then rm -rf /; fi",
+            "while true; do ! rm -rf /; done",
+            "X=rm; $X -rf /",
+            "r${X} -rf /",
+            "/bin/r? -rf /",
+            "python3 -c 'import shutil'",
+            "command rm -rf /",
+            "env -S 'rm -rf /'",
+            "env '-Srm -rf /'",
+            "env --split-string='rm -rf /'",
+            "setsid rm -rf /tmp/x",
+            "stdbuf -o0 rm -rf x",
+            "busybox rm -rf x",
+            "find . -delete",
+            "awk '{system(\"rm -rf x\")}' file",
+            "watch 'rm -rf x'",
+            "perl -e 'unlink glob q(*)'",
+        ] {
+            let error = policy.check(command).unwrap_err().to_string();
+            assert!(error.contains("Command blocked"), "{}", command);
+        }
+    }
+
+    /// Tests that grouping and keywords do not hide allowed programs.
+    #[test]
+    fn test_groups_and_keywords() {
+        let policy = ShellPolicy::allow_only(["ls", "cat", "true"]);
+        assert!(policy.check("(ls) && { cat a; }").is_ok());
+        assert!(policy.check("if true; then ls; else cat a; fi").is_ok());
+        assert!(policy.check("(ls; curl example.com)").is_err());
+    }
+
+    /// Tests that the read-only profile refuses options that write or run programs.
+    #[test]
+    fn test_read_only_refuses_writing_options() {
+        let no_writes = ShellPolicy {
+            allow_redirection: false,
+            ..ShellPolicy::default()
+        };
+        assert!(no_writes.check("find . -name '*.rs'").is_ok());
+        assert!(no_writes
+            .check("find . -name '*.rs' -exec cat {} ;")
+            .is_err());
+        assert!(no_writes.check("find . -fprint list.txt").is_err());
+
+        let read_only = ShellPolicy::read_only();
+        assert!(read_only.check("rg -n TODO src").is_ok());
+        assert!(read_only.check("sort -u names.txt").is_ok());
+        assert!(read_only.check("rg --pre cat TODO .").is_ok());
+        for command in [
+            "rg --pre rm x .",
+            "rg --pre=rm x .",
+            "sort -o names.txt names.txt",
+            "sort -uonames.txt names.txt",
+            "sort --out=names.txt names.txt",
+            "tree -o tree.txt",
+        ] {
+            let error = read_only.check(command).unwrap_err().to_string();
+            assert!(error.contains("Command blocked"), "{}", command);
+        }
+    }
+
+    /// Tests the allowlist and read-only profiles.
+    #[test]
+    fn test_allowlist_and_read_only_profile() {
+        let policy = ShellPolicy::allow_only(["git"]);
+        assert!(policy.check("git log --oneline | git status").is_ok());
+        assert!(policy.check("git log | less").is_err());
+
+        let read_only = ShellPolicy::read_only();
+        assert!(read_only.check("cat Cargo.toml | grep name").is_ok());
+        assert!(read_only.check("echo hi > file.txt").is_err());
+        assert!(read_only.check("curl https://example.com").is_err());
+    }
+}
@@ -5,6 +5,7 @@
 
 use crate::error::{HeliosError, Result};
 use crate::tools::{Tool, ToolParameter, ToolResult};
+use crate::truncation::OutputLimit;
 use async_trait::async_trait;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -46,6 +47,8 @@ pub struct ToolBuilder {
     description: String,
     parameters: HashMap<String, ToolParameter>,
     parameter_order: Vec<String>,
+    usage_notes: Option<String>,
+    output_limit: Option<OutputLimit>,
     function: Option<ToolFunction>,
 }
 
@@ -61,6 +64,8 @@ impl ToolBuilder {
             description: String::new(),
             parameters: HashMap::new(),
             parameter_order: Vec::new(),
+            usage_notes: None,
+            output_limit: None,
             function: None,
         }
     }
@@ -208,6 +213,18 @@ impl ToolBuilder {
         self
     }
 
+    /// Sets notes on when and how to use the tool, see [`Tool::usage_notes`].
+    pub fn usage_notes(mut self, notes: impl Into<String>) -> Self {
+        self.usage_notes = Some(notes.into());
+        self
+    }
+
+    /// Limits the output the tool returns to the model, see [`Tool::output_limit`].
+    pub fn output_limit(mut self, limit: OutputLimit) -> Self {
+        self.output_limit = Some(limit);
+        self
+    }
+
     /// Adds a parameter to the tool.
     ///
     /// # Arguments
@@ -568,6 +585,8 @@ impl ToolBuilder {
             name: self.name,
             description: self.description,
             parameters: self.parameters,
+            usage_notes: self.usage_notes,
+            output_limit: self.output_limit,
             function: self.function.unwrap(),
         })
     }
@@ -586,6 +605,8 @@ impl ToolBuilder {
             name: self.name,
             description: self.description,
             parameters: self.parameters,
+            usage_notes: self.usage_notes,
+            output_limit: self.output_limit,
             function: self.function.unwrap(),
         }))
     }
@@ -596,6 +617,8 @@ struct CustomTool {
     name: String,
     description: String,
     parameters: std::collections::HashMap<String, ToolParameter>,
+    usage_notes: Option<String>,
+    output_limit: Option<OutputLimit>,
     function: ToolFunction,
 }
 
@@ -613,6 +636,14 @@ impl Tool for CustomTool {
         self.parameters.clone()
     }
 
+    fn usage_notes(&self) -> Option<&str> {
+        self.usage_notes.as_deref()
+    }
+
+    fn output_limit(&self) -> Option<OutputLimit> {
+        self.output_limit
+    }
+
     async fn execute(&self, args: Value) -> Result<ToolResult> {
         (self.function)(args).await
     }
@@ -122,7 +122,6 @@ macro_rules! quick_tool {
 
 #[cfg(test)]
 mod tests {
-    use crate::quick_tool;
     use serde_json::json;
 
     #[test]
@@ -0,0 +1,504 @@
+//! # Tool Protocol Module
+//!
+//! How an agent tells the model about its tools and reads back the calls it makes.
+//! The default, [`ToolProtocol::Native`], uses the provider's function-calling API.
+//! [`ToolProtocol::React`] is for models that never emit `tool_calls`, which is
+//! common with local GGUF models: the tools are described in the system prompt,
+//! the model writes `Action:` / `Action Input:` lines, and the agent turns those
+//! into ordinary tool calls.
+//!
+//! The chat history keeps its usual form under either protocol, with assistant
+//! tool calls and tool-role results. Only the request sent to the model is
+//! rewritten into the textual format, so a saved session can be resumed with
+//! either protocol.
+
+use crate::chat::{ChatMessage, FunctionCall, Role, ToolCall};
+use crate::grammar::Grammar;
+use crate::tools::ToolDefinition;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The stop sequence that keeps a ReAct model from inventing tool results.
+const OBSERVATION_STOP: &str = "\nObservation:";
+
+/// The markers a ReAct reply is made of.
+const MARKERS: [&str; 3] = ["Thought:", "Action:", "Final Answer:"];
+
+/// How tools are offered to the model and how its tool calls are read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolProtocol {
+    /// The provider's function-calling API (`tools` in the request, `tool_calls`
+    /// in the reply).
+    #[default]
+    Native,
+    /// A text protocol in the system prompt, for models without function calling.
+    ///
+    /// The model answers with `Thought:`, `Action:` and `Action Input:` lines to call
+    /// a tool, gets the result back as `Observation:`, and ends with `Final Answer:`.
+    React,
+}
+
+/// What to send to the model for one step of the tool loop.
+pub(crate) struct ToolRequest {
+    pub messages: Vec<ChatMessage>,
+    pub tools: Option<Vec<ToolDefinition>>,
+    pub stop: Option<Vec<String>>,
+    /// Keeps local models to the protocol; remote providers ignore it.
+    pub grammar: Option<Grammar>,
+}
+
+impl ToolProtocol {
+    /// Builds the request for `messages` with the agent's tools.
+    pub(crate) fn request(
+        self,
+        messages: Vec<ChatMessage>,
+        tools: &[ToolDefinition],
+        stop: Option<Vec<String>>,
+    ) -> ToolRequest {
+        if tools.is_empty() {
+            return ToolRequest {
+                messages,
+                tools: None,
+                stop,
+                grammar: None,
+            };
+        }
+        match self {
+            ToolProtocol::Native => ToolRequest {
+                messages,
+                tools: Some(tools.to_vec()),
+                stop,
+                grammar: None,
+            },
+            ToolProtocol::React => {
+                let mut stop = stop.unwrap_or_default();
+                stop.push(OBSERVATION_STOP.to_string());
+                ToolRequest {
+                    messages: react_messages(messages, tools),
+                    tools: None,
+                    stop: Some(stop),
+                    grammar: Some(Grammar::react(tools)),
+                }
+            }
+        }
+    }
+
+    /// Reads the tool call out of a reply, if the protocol puts it in the text.
+    ///
+    /// Under [`ToolProtocol::React`] an `Action:` becomes a tool call whose content is
+    /// the model's thought, and a `Final Answer:` becomes the reply's whole content.
+    pub(crate) fn parse(self, mut response: ChatMessage, tools: &[ToolDefinition]) -> ChatMessage {
+        if self == ToolProtocol::Native || tools.is_empty() || response.tool_calls.is_some() {
+            return response;
+        }
+        match parse_react(&response.content, tools) {
+            ReactReply::Action {
+                thought,
+                name,
+                arguments,
+            } => {
+                response.content = thought;
+                response.tool_calls = Some(vec![ToolCall {
+                    id: format!("call_{}", uuid::Uuid::new_v4().simple()),
+                    call_type: "function".to_string(),
+                    function: FunctionCall { name, arguments },
+                }]);
+            }
+            ReactReply::Answer(answer) => response.content = answer,
+            ReactReply::Text => {}
+        }
+        response
+    }
+}
+
+/// A reply in the ReAct format.
+#[derive(Debug, PartialEq)]
+enum ReactReply {
+    /// A tool call.
+    Action {
+        thought: String,
+        name: String,
+        arguments: String,
+    },
+    /// The text after `Final Answer:`.
+    Answer(String),
+    /// No markers: the model answered directly.
+    Text,
+}
+
+/// Returns the byte offset of `marker` at the start of a line in `text`.
+fn line_marker(text: &str, marker: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start().len();
+        if line.trim_start().starts_with(marker) {
+            return Some(offset + indent);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn parse_react(text: &str, tools: &[ToolDefinition]) -> ReactReply {
+    // Anything after an invented observation is not the model's to say.
+    let text = match line_marker(text, "Observation:") {
+        Some(end) => &text[..end],
+        None => text,
+    };
+    let action = line_marker(text, "Action:");
+    let answer = line_marker(text, "Final Answer:");
+
+    match (action, answer) {
+        (Some(action), answer) if answer.map_or(true, |answer| action < answer) => {
+            let rest = &text[action + "Action:".len()..];
+            let (name_line, after_name) = rest.split_once('\n').unwrap_or((rest, ""));
+            let name = name_line
+                .trim()
+                .trim_matches(|c| c == '`' || c == '"' || c == '\'')
+                .to_string();
+            let input = match line_marker(after_name, "Action Input:") {
+                Some(start) => after_name[start + "Action Input:".len()..].trim(),
+                None => "",
+            };
+            let thought = text[..action].trim();
+            let thought = thought.strip_prefix("Thought:").unwrap_or(thought).trim();
+            ReactReply::Action {
+                arguments: action_arguments(input, &name, tools),
+                thought: thought.to_string(),
+                name,
+            }
+        }
+        (_, Some(answer)) => {
+            ReactReply::Answer(text[answer + "Final Answer:".len()..].trim().to_string())
+        }
+        _ => ReactReply::Text,
+    }
+}
+
+/// Turns an `Action Input:` into JSON arguments.
+///
+/// A JSON object is used as is. Anything else is given to the tool's only
+/// parameter when it has exactly one, since small models often write the bare value.
+fn action_arguments(input: &str, name: &str, tools: &[ToolDefinition]) -> String {
+    let input = input
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+    let parsed = serde_json::from_str::<Value>(input).ok();
+    if let Some(Value::Object(arguments)) = &parsed {
+        return Value::Object(arguments.clone()).to_string();
+    }
+    let only_parameter = tools
+        .iter()
+        .find(|tool| tool.function.name == name)
+        .map(|tool| &tool.function.parameters.properties)
+        .filter(|properties| properties.len() == 1)
+        .and_then(|properties| properties.keys().next());
+    match only_parameter {
+        Some(parameter) => {
+            let value = match parsed {
+                Some(Value::String(text)) => Value::String(text),
+                Some(value) => value,
+                None => Value::String(input.to_string()),
+            };
+            serde_json::json!({ parameter: value }).to_string()
+        }
+        None => input.to_string(),
+    }
+}
+
+/// Describes the tools and the reply format for the system prompt.
+fn react_instructions(tools: &[ToolDefinition]) -> String {
+    let mut tools: Vec<&ToolDefinition> = tools.iter().collect();
+    tools.sort_by(|a, b| a.function.name.cmp(&b.function.name));
+
+    let mut out = String::from("You can use these tools:\n");
+    for tool in &tools {
+        out.push_str(&format!(
+            "\n{}: {}\n",
+            tool.function.name, tool.function.description
+        ));
+        let required = tool
+            .function
+            .parameters
+            .required
+            .clone()
+            .unwrap_or_default();
+        let mut parameters: Vec<_> = tool.function.parameters.properties.iter().collect();
+        parameters.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, parameter) in parameters {
+            out.push_str(&format!(
+                "  - {} ({}{}): {}\n",
+                name,
+                parameter.param_type,
+                if required.contains(name) {
+                    ", required"
+                } else {
+                    ""
+                },
+                parameter.description
+            ));
+        }
+    }
+    let names: Vec<&str> = tools.iter().map(|t| t.function.name.as_str()).collect();
+    out.push_str(&format!(
+        "\nTo use a tool, reply in exactly this format and stop:\n\n\
+         Thought: what you need to do next\n\
+         Action: the tool to use, one of [{}]\n\
+         Action Input: the arguments as a JSON object\n\n\
+         The result comes back as \"Observation: ...\". Use as many tools as you need, \
+         one at a time. When you can answer, reply:\n\n\
+         Thought: I can answer now\n\
+         Final Answer: your answer to the user",
+        names.join(", ")
+    ));
+    out
+}
+
+/// Rewrites the history into the ReAct format and adds the instructions.
+fn react_messages(messages: Vec<ChatMessage>, tools: &[ToolDefinition]) -> Vec<ChatMessage> {
+    let instructions = react_instructions(tools);
+    let mut out: Vec<ChatMessage> = Vec::with_capacity(messages.len() + 1);
+    let mut instructed = false;
+
+    for message in messages {
+        match message.role {
+            Role::System if !instructed => {
+                instructed = true;
+                out.push(ChatMessage::system(format!(
+                    "{}\n\n{}",
+                    message.content, instructions
+                )));
+            }
+            Role::Assistant if message.tool_calls.is_some() => {
+                let mut text = String::new();
+                let thought = message.content.trim();
+                if !thought.is_empty() {
+                    if !thought.starts_with("Thought:") {
+                        text.push_str("Thought: ");
+                    }
+                    text.push_str(thought);
+                    text.push('\n');
+                }
+                for call in message.tool_calls.iter().flatten() {
+                    text.push_str(&format!(
+                        "Action: {}\nAction Input: {}\n",
+                        call.function.name, call.function.arguments
+                    ));
+                }
+                out.push(ChatMessage::assistant(text.trim_end()));
+            }
+            Role::Tool => {
+                let observation = format!("Observation: {}", message.content);
+                // Results of several calls in a row go back as one message.
+                match out.last_mut() {
+                    Some(last)
+                        if last.role == Role::User && last.content.starts_with("Observation:") =>
+                    {
+                        last.content.push('\n');
+                        last.content.push_str(&observation);
+                    }
+                    _ => out.push(ChatMessage::user(observation)),
+                }
+            }
+            _ => out.push(message),
+        }
+    }
+    if !instructed {
+        out.insert(0, ChatMessage::system(instructions));
+    }
+    out
+}
+
+/// Decides which streamed text of a ReAct reply the user should see.
+///
+/// Thoughts and actions are held back. The text after `Final Answer:` is passed
+/// through as it arrives, and so is a reply that does not use the format at all.
+#[derive(Debug, Default)]
+pub(crate) struct ReactStream {
+    text: String,
+    state: StreamState,
+}
+
+#[derive(Debug, Default, PartialEq)]
+enum StreamState {
+    #[default]
+    Undecided,
+    /// Showing the final answer, which starts at `from`; `shown` bytes are out.
+    Answer { from: usize, shown: usize },
+    /// A reply without markers.
+    Plain,
+    /// A tool call.
+    Hidden,
+}
+
+impl ReactStream {
+    /// Takes the next streamed chunk and returns the text to show now.
+    pub(crate) fn push(&mut self, chunk: &str) -> Option<String> {
+        self.text.push_str(chunk);
+        if self.state == StreamState::Undecided {
+            if let Some(answer) = line_marker(&self.text, "Final Answer:") {
+                self.state = StreamState::Answer {
+                    from: answer + "Final Answer:".len(),
+                    shown: 0,
+                };
+            } else if line_marker(&self.text, "Action:").is_some() {
+                self.state = StreamState::Hidden;
+            } else {
+                let start = self.text.trim_start();
+                let may_be_marker = MARKERS
+                    .iter()
+                    .any(|marker| marker.starts_with(start) || start.starts_with(marker));
+                if !start.is_empty() && !may_be_marker {
+                    self.state = StreamState::Plain;
+                    return Some(self.text.clone());
+                }
+            }
+        }
+
+        match &mut self.state {
+            StreamState::Plain => Some(chunk.to_string()),
+            StreamState::Answer { from, shown } => {
+                let mut pending = &self.text[*from + *shown..];
+                if *shown == 0 {
+                    let trimmed = pending.trim_start();
+                    *from += pending.len() - trimmed.len();
+                    pending = trimmed;
+                }
+                *shown += pending.len();
+                (!pending.is_empty()).then(|| pending.to_string())
+            }
+            StreamState::Undecided | StreamState::Hidden => None,
+        }
+    }
+
+    /// Returns the text still to show once the reply is complete and parsed.
+    ///
+    /// A reply that never left the undecided state, such as a lone `Thought:`, is
+    /// shown whole.
+    pub(crate) fn finish(&self, response: &ChatMessage) -> Option<String> {
+        match self.state {
+            StreamState::Undecided if response.tool_calls.is_none() => {
+                Some(response.content.clone()).filter(|content| !content.is_empty())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{CalculatorTool, EchoTool, Tool};
+
+    fn tools() -> Vec<ToolDefinition> {
+        vec![CalculatorTool.to_definition(), EchoTool.to_definition()]
+    }
+
+    /// Tests reading actions, final answers and plain replies.
+    #[test]
+    fn test_parse_react() {
+        let tools = tools();
+        let reply = "Thought: I need to add.\nAction: calculator\nAction Input: {\"expression\": \"2 + 2\"}\nObservation: 5";
+        assert_eq!(
+            parse_react(reply, &tools),
+            ReactReply::Action {
+                thought: "I need to add.".to_string(),
+                name: "calculator".to_string(),
+                arguments: "{\"expression\":\"2 + 2\"}".to_string(),
+            }
+        );
+
+        // A bare value goes to the tool's only parameter.
+        let reply = "Action: `echo`\nAction Input: \"hello\"";
+        assert_eq!(
+            parse_react(reply, &tools),
+            ReactReply::Action {
+                thought: String::new(),
+                name: "echo".to_string(),
+                arguments: "{\"message\":\"hello\"}".to_string(),
+            }
+        );
+
+        let reply = "Thought: I know this.\nFinal Answer:  4\n";
+        assert_eq!(
+            parse_react(reply, &tools),
+            ReactReply::Answer("4".to_string())
+        );
+        assert_eq!(parse_react("Just 4.", &tools), ReactReply::Text);
+    }
+
+    /// Tests rewriting a history with tool calls into the text format.
+    #[test]
+    fn test_react_request() {
+        let tools = tools();
+        let mut call = ChatMessage::assistant("I need to add.");
+        call.tool_calls = Some(vec![ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "calculator".to_string(),
+                arguments: "{\"expression\":\"2 + 2\"}".to_string(),
+            },
+        }]);
+        let messages = vec![
+            ChatMessage::system("Be brief."),
+            ChatMessage::user("2 + 2?"),
+            call,
+            ChatMessage::tool("4", "call_1"),
+        ];
+
+        let request = ToolProtocol::React.request(messages.clone(), &tools, None);
+        assert!(request.tools.is_none());
+        assert_eq!(request.stop, Some(vec![OBSERVATION_STOP.to_string()]));
+        assert_eq!(request.grammar, Some(Grammar::react(&tools)));
+        let sent = &request.messages;
+        assert!(sent[0]
+            .content
+            .starts_with("Be brief.\n\nYou can use these tools:"));
+        assert!(sent[0]
+            .content
+            .contains("  - message (string, required): The message to echo back"));
+        assert!(sent[0].content.contains("one of [calculator, echo]"));
+        assert_eq!(
+            sent[2].content,
+            "Thought: I need to add.\nAction: calculator\nAction Input: {\"expression\":\"2 + 2\"}"
+        );
+        assert!(sent[2].tool_calls.is_none());
+        assert_eq!(sent[3].role, Role::User);
+        assert_eq!(sent[3].content, "Observation: 4");
+
+        let request = ToolProtocol::Native.request(messages, &tools, None);
+        assert_eq!(request.tools.map(|t| t.len()), Some(2));
+        assert_eq!(request.messages[3].role, Role::Tool);
+    }
+
+    /// Tests which streamed text is shown.
+    #[test]
+    fn test_react_stream() {
+        let shown = |chunks: &[&str]| {
+            let mut stream = ReactStream::default();
+            let text: String = chunks.iter().filter_map(|c| stream.push(c)).collect();
+            (text, stream)
+        };
+
+        let (text, stream) = shown(&["Thou", "ght: add\nFinal Ans", "wer: It ", "is 4."]);
+        assert_eq!(text, "It is 4.");
+        assert_eq!(stream.finish(&ChatMessage::assistant("It is 4.")), None);
+
+        let (text, _) = shown(&["Thought: add\n", "Action: calculator\n", "Action Input: {}"]);
+        assert_eq!(text, "");
+
+        let (text, _) = shown(&["It", " is 4."]);
+        assert_eq!(text, "It is 4.");
+
+        let (text, stream) = shown(&["Thought: I am not sure."]);
+        assert_eq!(text, "");
+        assert_eq!(
+            stream.finish(&ChatMessage::assistant("Thought: I am not sure.")),
+            Some("Thought: I am not sure.".to_string())
+        );
+    }
+}
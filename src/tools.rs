@@ -5,15 +5,20 @@
 //! for managing a collection of tools.
 //! It also includes several built-in tools for common tasks.
 
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use crate::code_interpreter::CodeInterpreterTool;
 use crate::error::{HeliosError, Result};
+use crate::path_utils::{self, PathJail};
+use crate::robots::RobotsTxt;
+use crate::shell_policy::ShellPolicy;
+use crate::truncation::{OutputLimit, OutputSummarizer};
 use async_trait::async_trait;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::io::{BufReader, BufWriter, Read, Write};
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
-use uuid::Uuid;
+use std::sync::Arc;
 
 /// A parameter for a tool.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +94,44 @@ impl ToolResult {
     }
 }
 
+/// Returns the string argument `name`, or a `ToolError` if it is missing.
+pub(crate) fn required_str<'a>(args: &'a Value, name: &str) -> Result<&'a str> {
+    args.get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| HeliosError::ToolError(format!("Missing '{}' parameter", name)))
+}
+
+/// Returns the string at a JSON pointer, or `""` if there is none.
+#[cfg(any(feature = "kubernetes", feature = "docker", feature = "github"))]
+pub(crate) fn str_at<'a>(value: &'a Value, pointer: &str) -> &'a str {
+    value.pointer(pointer).and_then(Value::as_str).unwrap_or("")
+}
+
+/// Lays out rows under a header as a plain-text table with aligned columns.
+#[cfg(any(feature = "kubernetes", feature = "docker"))]
+pub(crate) fn table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        padded.join("   ").trim_end().to_string()
+    };
+    let mut out = line(header.to_vec());
+    for row in rows {
+        out.push('\n');
+        out.push_str(&line(row.iter().map(String::as_str).collect()));
+    }
+    out
+}
+
 /// A trait for tools that can be used by agents.
 #[async_trait]
 pub trait Tool: Send + Sync {
@@ -101,6 +144,40 @@ pub trait Tool: Send + Sync {
     /// Executes the tool with the given arguments.
     async fn execute(&self, args: Value) -> Result<ToolResult>;
 
+    /// How much the tool can change the system it runs on.
+    ///
+    /// Calls to `Destructive` tools are passed to the registry's approval handler,
+    /// if one is set, before they run.
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Standard
+    }
+
+    /// The permission needed for one call with `args`.
+    ///
+    /// Defaults to [`Tool::permission`]. Tools whose actions differ in risk, such as
+    /// one that can both read and send email, override this so only the risky
+    /// actions need approval.
+    fn call_permission(&self, _args: &Value) -> ToolPermission {
+        self.permission()
+    }
+
+    /// Notes on when and how to use the tool, beyond its description.
+    ///
+    /// Agents whose system prompt is composed with a
+    /// [`crate::prompt::SystemPromptBuilder`] gather these into its tool guidance
+    /// section.
+    fn usage_notes(&self) -> Option<&str> {
+        None
+    }
+
+    /// The most output the tool returns to the model, if it has its own limit.
+    ///
+    /// A limit set for the tool on the registry takes precedence, and the
+    /// registry's default limit applies to tools without either.
+    fn output_limit(&self) -> Option<OutputLimit> {
+        None
+    }
+
     /// Converts the tool to a `ToolDefinition`.
     fn to_definition(&self) -> ToolDefinition {
         let required: Vec<String> = self
@@ -129,9 +206,88 @@ pub trait Tool: Send + Sync {
     }
 }
 
+/// How much a tool can change the system it runs on.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolPermission {
+    /// The tool only reads data.
+    ReadOnly,
+    /// The tool may have side effects, but none that are hard to undo.
+    #[default]
+    Standard,
+    /// The tool can modify or delete data, or run arbitrary commands.
+    Destructive,
+}
+
+/// A tool call waiting for approval.
+#[derive(Debug, Clone)]
+pub struct ToolApprovalRequest {
+    /// The name of the tool to be run.
+    pub tool_name: String,
+    /// The arguments the tool will be called with.
+    pub arguments: Value,
+    /// The tool's permission level.
+    pub permission: ToolPermission,
+}
+
+/// Decides whether a destructive tool call may run.
+///
+/// Implement the trait for asynchronous approval, e.g. asking a user over a web
+/// socket. Any `Fn(&ToolApprovalRequest) -> bool` closure is also a handler.
+#[async_trait]
+pub trait ToolApprovalHandler: Send + Sync {
+    /// Returns `true` to run the tool, or `false` to refuse the call.
+    async fn approve(&self, request: &ToolApprovalRequest) -> bool;
+}
+
+#[async_trait]
+impl<F> ToolApprovalHandler for F
+where
+    F: Fn(&ToolApprovalRequest) -> bool + Send + Sync,
+{
+    async fn approve(&self, request: &ToolApprovalRequest) -> bool {
+        self(request)
+    }
+}
+
+/// An approval handler that asks on the terminal with an "Allow? (y/n)" prompt.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsoleApprovalHandler;
+
+#[async_trait]
+impl ToolApprovalHandler for ConsoleApprovalHandler {
+    async fn approve(&self, request: &ToolApprovalRequest) -> bool {
+        let tool_name = request.tool_name.clone();
+        let arguments = request.arguments.to_string();
+        tokio::task::spawn_blocking(move || {
+            print!(
+                "\n⚠️  The agent wants to run '{}' with {}\nAllow? (y/n): ",
+                tool_name, arguments
+            );
+            let _ = std::io::stdout().flush();
+            let mut answer = String::new();
+            if std::io::stdin().read_line(&mut answer).is_err() {
+                return false;
+            }
+            matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+        })
+        .await
+        .unwrap_or(false)
+    }
+}
+
 /// A registry for managing a collection of tools.
 pub struct ToolRegistry {
     tools: HashMap<String, Box<dyn Tool>>,
+    breaker_config: Option<CircuitBreakerConfig>,
+    breakers: HashMap<String, CircuitBreaker>,
+    permissions: HashMap<String, ToolPermission>,
+    approval_handler: Option<Arc<dyn ToolApprovalHandler>>,
+    output_limits: HashMap<String, OutputLimit>,
+    default_output_limit: Option<OutputLimit>,
+    output_summarizer: Option<Arc<dyn OutputSummarizer>>,
 }
 
 impl ToolRegistry {
@@ -139,28 +295,170 @@ impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            breaker_config: None,
+            breakers: HashMap::new(),
+            permissions: HashMap::new(),
+            approval_handler: None,
+            output_limits: HashMap::new(),
+            default_output_limit: None,
+            output_summarizer: None,
         }
     }
 
+    /// Creates a registry with a tool for each allowed operation of an OpenAPI document.
+    ///
+    /// See [`OpenApiSpec`](crate::openapi::OpenApiSpec) for loading the document,
+    /// choosing the operations and setting authentication headers.
+    pub fn from_openapi(spec: &crate::openapi::OpenApiSpec) -> Result<Self> {
+        let mut registry = Self::new();
+        for tool in spec.tools()? {
+            registry.register(tool);
+        }
+        Ok(registry)
+    }
+
     /// Registers a tool with the registry.
     pub fn register(&mut self, tool: Box<dyn Tool>) {
         let name = tool.name().to_string();
+        if let Some(config) = &self.breaker_config {
+            self.breakers.insert(
+                name.clone(),
+                CircuitBreaker::new(name.clone(), config.clone()),
+            );
+        }
         self.tools.insert(name, tool);
     }
 
+    /// Guards every tool with its own circuit breaker.
+    ///
+    /// After `failure_threshold` consecutive errors from a tool, further calls to it
+    /// fail immediately with `HeliosError::CircuitOpen` until the cooldown elapses.
+    pub fn set_circuit_breaker(&mut self, config: CircuitBreakerConfig) {
+        self.breakers = self
+            .tools
+            .keys()
+            .map(|name| {
+                (
+                    name.clone(),
+                    CircuitBreaker::new(name.clone(), config.clone()),
+                )
+            })
+            .collect();
+        self.breaker_config = Some(config);
+    }
+
+    /// Returns the circuit breaker guarding a tool, if circuit breaking is enabled.
+    pub fn circuit_breaker(&self, name: &str) -> Option<&CircuitBreaker> {
+        self.breakers.get(name)
+    }
+
+    /// Overrides the permission level of a tool.
+    pub fn set_permission(&mut self, name: impl Into<String>, permission: ToolPermission) {
+        self.permissions.insert(name.into(), permission);
+    }
+
+    /// Returns the permission level of a tool, taking overrides into account.
+    pub fn permission(&self, name: &str) -> Option<ToolPermission> {
+        self.permissions
+            .get(name)
+            .copied()
+            .or_else(|| self.tools.get(name).map(|tool| tool.permission()))
+    }
+
+    /// Sets the handler consulted before a destructive tool is executed.
+    ///
+    /// Without a handler, destructive tools run without confirmation.
+    pub fn set_approval_handler(&mut self, handler: impl ToolApprovalHandler + 'static) {
+        self.approval_handler = Some(Arc::new(handler));
+    }
+
+    /// Sets an approval handler that has already been boxed.
+    pub(crate) fn set_boxed_approval_handler(&mut self, handler: Box<dyn ToolApprovalHandler>) {
+        self.approval_handler = Some(Arc::from(handler));
+    }
+
+    /// Limits the output of the tool called `name`, overriding its own and the default limit.
+    pub fn set_output_limit(&mut self, name: impl Into<String>, limit: OutputLimit) {
+        self.output_limits.insert(name.into(), limit);
+    }
+
+    /// Limits the output of tools without a limit of their own.
+    pub fn set_default_output_limit(&mut self, limit: OutputLimit) {
+        self.default_output_limit = Some(limit);
+    }
+
+    /// Returns the output limit applied to a tool, if any.
+    pub fn output_limit(&self, name: &str) -> Option<OutputLimit> {
+        self.output_limits
+            .get(name)
+            .copied()
+            .or_else(|| self.tools.get(name).and_then(|tool| tool.output_limit()))
+            .or(self.default_output_limit)
+    }
+
+    /// Sets the summarizer used by [`TruncationStrategy::Summarize`](crate::TruncationStrategy::Summarize) limits.
+    pub fn set_output_summarizer(&mut self, summarizer: impl OutputSummarizer + 'static) {
+        self.output_summarizer = Some(Arc::new(summarizer));
+    }
+
+    /// Sets an output summarizer that has already been boxed.
+    pub(crate) fn set_boxed_output_summarizer(&mut self, summarizer: Box<dyn OutputSummarizer>) {
+        self.output_summarizer = Some(Arc::from(summarizer));
+    }
+
     /// Gets a tool from the registry by name.
     pub fn get(&self, name: &str) -> Option<&dyn Tool> {
         self.tools.get(name).map(|b| &**b)
     }
 
     /// Executes a tool in the registry by name.
+    ///
+    /// Output over the tool's [`OutputLimit`] is shortened before it is returned.
     pub async fn execute(&self, name: &str, args: Value) -> Result<ToolResult> {
         let tool = self
             .tools
             .get(name)
             .ok_or_else(|| HeliosError::ToolError(format!("Tool '{}' not found", name)))?;
 
-        tool.execute(args).await
+        let permission = self
+            .permissions
+            .get(name)
+            .copied()
+            .unwrap_or_else(|| tool.call_permission(&args));
+        if permission == ToolPermission::Destructive {
+            if let Some(handler) = &self.approval_handler {
+                let request = ToolApprovalRequest {
+                    tool_name: name.to_string(),
+                    arguments: args.clone(),
+                    permission,
+                };
+                if !handler.approve(&request).await {
+                    return Ok(ToolResult::error(format!(
+                        "Running tool '{}' was not approved",
+                        name
+                    )));
+                }
+            }
+        }
+
+        let mut result = match self.breakers.get(name) {
+            Some(breaker) => {
+                breaker.check()?;
+                let result = tool.execute(args).await;
+                match &result {
+                    Ok(output) if output.success => breaker.record_success(),
+                    _ => breaker.record_failure(),
+                }
+                result
+            }
+            None => tool.execute(args).await,
+        }?;
+        if let Some(limit) = self.output_limit(name) {
+            result.output = limit
+                .apply(name, &result.output, self.output_summarizer.as_deref())
+                .await;
+        }
+        Ok(result)
     }
 
     /// Gets the definitions of all tools in the registry.
@@ -175,6 +473,17 @@ impl ToolRegistry {
     pub fn list_tools(&self) -> Vec<String> {
         self.tools.keys().cloned().collect()
     }
+
+    /// Returns the usage notes of the tools that have them, sorted by tool name.
+    pub fn usage_notes(&self) -> Vec<(String, String)> {
+        let mut notes: Vec<(String, String)> = self
+            .tools
+            .iter()
+            .filter_map(|(name, tool)| Some((name.clone(), tool.usage_notes()?.to_string())))
+            .collect();
+        notes.sort();
+        notes
+    }
 }
 
 impl Default for ToolRegistry {
@@ -183,6 +492,154 @@ impl Default for ToolRegistry {
     }
 }
 
+/// The names of the built-in tools, as accepted by [`builtin_tool`].
+pub const BUILTIN_TOOLS: &[&str] = &[
+    "calculator",
+    "echo",
+    "file_search",
+    "file_read",
+    "file_write",
+    "file_edit",
+    "file_io",
+    "file_list",
+    "memory_db",
+    "web_scraper",
+    "json_parser",
+    "timestamp",
+    "shell_command",
+    "http_request",
+    "system_info",
+    "text_processor",
+    "code_interpreter",
+];
+
+/// Creates the built-in tool called `name`, if there is one.
+///
+/// `shell_policy` restricts `shell_command`; without one it gets the default policy.
+pub fn builtin_tool(name: &str, shell_policy: Option<&ShellPolicy>) -> Option<Box<dyn Tool>> {
+    let tool: Box<dyn Tool> = match name {
+        "calculator" => Box::new(CalculatorTool),
+        "echo" => Box::new(EchoTool),
+        "file_search" => Box::new(FileSearchTool),
+        "file_read" => Box::new(FileReadTool),
+        "file_write" => Box::new(FileWriteTool),
+        "file_edit" => Box::new(FileEditTool),
+        "file_io" => Box::new(FileIOTool),
+        "file_list" => Box::new(FileListTool),
+        "memory_db" => Box::new(MemoryDBTool::new()),
+        "web_scraper" => Box::new(WebScraperTool::new()),
+        "json_parser" => Box::new(JsonParserTool),
+        "timestamp" => Box::new(TimestampTool),
+        "shell_command" => Box::new(ShellCommandTool::with_policy(
+            shell_policy.cloned().unwrap_or_default(),
+        )),
+        "http_request" => Box::new(HttpRequestTool),
+        "system_info" => Box::new(SystemInfoTool),
+        "text_processor" => Box::new(TextProcessorTool),
+        "code_interpreter" => Box::new(CodeInterpreterTool::new()),
+        _ => return None,
+    };
+    Some(tool)
+}
+
+/// The arguments of the built-in file tools that hold paths.
+const PATH_ARGUMENTS: &[&str] = &["path", "src_path", "dst_path"];
+
+/// Wraps a file tool so every path it is given must stay inside a root directory.
+///
+/// Path arguments (`path`, `src_path` and `dst_path`) are resolved with a
+/// `PathJail` and replaced by the absolute path that was checked, so relative
+/// paths are taken relative to the root and `..` or symlinks cannot escape it.
+/// When the tool's optional `path` argument is omitted, the root is used.
+///
+/// ```rust,no_run
+/// use helios_engine::{FileReadTool, FileWriteTool, PathJail, SandboxedTool, ToolRegistry};
+///
+/// # fn example() -> helios_engine::Result<()> {
+/// let jail = PathJail::new("./workspace")?;
+/// let mut registry = ToolRegistry::new();
+/// registry.register(Box::new(SandboxedTool::new(Box::new(FileReadTool), jail.clone())));
+/// registry.register(Box::new(SandboxedTool::new(Box::new(FileWriteTool), jail)));
+/// # Ok(())
+/// # }
+/// ```
+pub struct SandboxedTool {
+    inner: Box<dyn Tool>,
+    jail: PathJail,
+}
+
+impl SandboxedTool {
+    /// Confines `inner` to the jail's root directory.
+    pub fn new(inner: Box<dyn Tool>, jail: PathJail) -> Self {
+        Self { inner, jail }
+    }
+
+    /// Returns the jail the tool is confined to.
+    pub fn jail(&self) -> &PathJail {
+        &self.jail
+    }
+}
+
+#[async_trait]
+impl Tool for SandboxedTool {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    fn parameters(&self) -> HashMap<String, ToolParameter> {
+        self.inner.parameters()
+    }
+
+    fn permission(&self) -> ToolPermission {
+        self.inner.permission()
+    }
+
+    fn call_permission(&self, args: &Value) -> ToolPermission {
+        self.inner.call_permission(args)
+    }
+
+    fn usage_notes(&self) -> Option<&str> {
+        self.inner.usage_notes()
+    }
+
+    fn output_limit(&self) -> Option<OutputLimit> {
+        self.inner.output_limit()
+    }
+
+    async fn execute(&self, mut args: Value) -> Result<ToolResult> {
+        if let Some(args) = args.as_object_mut() {
+            for key in PATH_ARGUMENTS {
+                if let Some(path) = args.get(*key).and_then(|v| v.as_str()) {
+                    let resolved = self.jail.resolve(path)?;
+                    args.insert(
+                        key.to_string(),
+                        Value::String(resolved.to_string_lossy().into_owned()),
+                    );
+                }
+            }
+
+            let optional_path = self
+                .inner
+                .parameters()
+                .get("path")
+                .map(|param| !param.required.unwrap_or(false))
+                .unwrap_or(false);
+            if optional_path && !args.contains_key("path") {
+                args.insert(
+                    "path".to_string(),
+                    Value::String(self.jail.root().to_string_lossy().into_owned()),
+                );
+            }
+        }
+
+        self.inner.execute(args).await
+    }
+}
+
 // Example built-in tools
 
 /// A tool for performing basic arithmetic operations.
@@ -198,6 +655,10 @@ impl Tool for CalculatorTool {
         "Perform basic arithmetic operations. Supports +, -, *, / operations."
     }
 
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::ReadOnly
+    }
+
     fn parameters(&self) -> HashMap<String, ToolParameter> {
         let mut params = HashMap::new();
         params.insert(
@@ -271,6 +732,10 @@ impl Tool for EchoTool {
         "Echo back the provided message."
     }
 
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::ReadOnly
+    }
+
     fn parameters(&self) -> HashMap<String, ToolParameter> {
         let mut params = HashMap::new();
         params.insert(
@@ -304,7 +769,11 @@ impl Tool for FileSearchTool {
     }
 
     fn description(&self) -> &str {
-        "Search for files by name pattern or search for content within files. Can search recursively in directories."
+        "Search for files by name pattern or search for content (plain text or regex) within files. Searches directories recursively, skipping files excluded by .gitignore/.ignore and binary files."
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::ReadOnly
     }
 
     fn parameters(&self) -> HashMap<String, ToolParameter> {
@@ -343,13 +812,67 @@ impl Tool for FileSearchTool {
                 required: Some(false),
             },
         );
+        params.insert(
+            "regex".to_string(),
+            ToolParameter {
+                param_type: "boolean".to_string(),
+                description: "Treat 'content' as a regular expression (default: false)".to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "context_lines".to_string(),
+            ToolParameter {
+                param_type: "number".to_string(),
+                description: "Lines of context to show around each content match (default: 0)"
+                    .to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "max_file_size".to_string(),
+            ToolParameter {
+                param_type: "number".to_string(),
+                description: "Maximum bytes of each file to search for content (default: 1 MB)"
+                    .to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "respect_ignore".to_string(),
+            ToolParameter {
+                param_type: "boolean".to_string(),
+                description: "Skip files excluded by .gitignore and .ignore (default: true)"
+                    .to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "time_budget_ms".to_string(),
+            ToolParameter {
+                param_type: "number".to_string(),
+                description: "Stop searching after this many milliseconds and return what was found (default: 10000)".to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "case_insensitive".to_string(),
+            ToolParameter {
+                param_type: "boolean".to_string(),
+                description: "Match file names ignoring case (default: true on Windows and macOS, false elsewhere)".to_string(),
+                required: Some(false),
+            },
+        );
         params
     }
 
     async fn execute(&self, args: Value) -> Result<ToolResult> {
-        use walkdir::WalkDir;
-
-        let base_path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+        let base_path =
+            path_utils::resolve_path(args.get("path").and_then(|v| v.as_str()).unwrap_or("."))?;
+        let case_insensitive = args
+            .get("case_insensitive")
+            .and_then(|v| v.as_bool())
+            .unwrap_or_else(path_utils::default_case_insensitive);
 
         let pattern = args.get("pattern").and_then(|v| v.as_str());
         let content_search = args.get("content").and_then(|v| v.as_str());
@@ -357,6 +880,23 @@ impl Tool for FileSearchTool {
             .get("max_results")
             .and_then(|v| v.as_u64())
             .unwrap_or(50) as usize;
+        let use_regex = args.get("regex").and_then(|v| v.as_bool()).unwrap_or(false);
+        let context_lines = args
+            .get("context_lines")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as usize;
+        let max_file_size = args
+            .get("max_file_size")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(FILE_SEARCH_MAX_FILE_SIZE);
+        let respect_ignore = args
+            .get("respect_ignore")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let time_budget_ms = args
+            .get("time_budget_ms")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(FILE_SEARCH_TIME_BUDGET_MS);
 
         if pattern.is_none() && content_search.is_none() {
             return Err(HeliosError::ToolError(
@@ -364,12 +904,13 @@ impl Tool for FileSearchTool {
             ));
         }
 
-        let mut results = Vec::new();
-
         // Precompile filename pattern to avoid compiling per file
         let compiled_re = if let Some(pat) = pattern {
             let re_pattern = pat.replace(".", r"\.").replace("*", ".*").replace("?", ".");
-            match regex::Regex::new(&format!("^{}$", re_pattern)) {
+            match regex::RegexBuilder::new(&format!("^{}$", re_pattern))
+                .case_insensitive(case_insensitive)
+                .build()
+            {
                 Ok(re) => Some(re),
                 Err(e) => {
                     tracing::warn!(
@@ -384,96 +925,250 @@ impl Tool for FileSearchTool {
             None
         };
 
-        for entry in WalkDir::new(base_path)
-            .max_depth(10)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if results.len() >= max_results {
-                break;
+        // Content queries are matched as regexes; plain text is escaped first
+        let content_re = match content_search {
+            Some(term) => {
+                let source = if use_regex {
+                    term.to_string()
+                } else {
+                    regex::escape(term)
+                };
+                Some(regex::Regex::new(&source).map_err(|e| {
+                    HeliosError::ToolError(format!("Invalid content regex '{}': {}", term, e))
+                })?)
             }
+            None => None,
+        };
 
-            let path = entry.path();
+        let query = Arc::new(FileSearchQuery {
+            name_re: compiled_re,
+            name_substring: pattern.map(|pat| pat.to_string()),
+            case_insensitive,
+            content_re,
+            context_lines,
+            max_file_size,
+        });
 
-            // Skip hidden files and common ignore directories
-            if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                if file_name.starts_with('.')
-                    || file_name == "target"
-                    || file_name == "node_modules"
-                    || file_name == "__pycache__"
-                {
-                    continue;
+        let mut walker = ignore::WalkBuilder::new(&base_path);
+        walker
+            .hidden(false)
+            .git_ignore(respect_ignore)
+            .git_global(respect_ignore)
+            .git_exclude(respect_ignore)
+            .ignore(respect_ignore)
+            .parents(respect_ignore)
+            // Honour .gitignore even when the directory is not inside a git checkout
+            .require_git(false)
+            .max_depth(Some(10))
+            .follow_links(false)
+            // Prune hidden entries and common build/dependency directories
+            .filter_entry(|entry| {
+                if entry.depth() == 0 {
+                    return true;
                 }
-            }
+                let skipped_dir = entry
+                    .file_name()
+                    .to_str()
+                    .map(|name| FILE_SEARCH_SKIPPED_DIRS.contains(&name))
+                    .unwrap_or(false);
+                !skipped_dir && !path_utils::is_hidden(entry.path(), entry.metadata().ok().as_ref())
+            });
 
-            // Pattern matching for file names
-            if let Some(pat) = pattern {
-                if path.is_file() {
-                    if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                        let is_match = if let Some(re) = &compiled_re {
-                            re.is_match(file_name)
-                        } else {
-                            file_name.contains(pat)
-                        };
-                        if is_match {
-                            results.push(format!("📄 {}", path.display()));
-                        }
+        // Search files on a work-stealing pool of threads, off the async runtime
+        let deadline = std::time::Instant::now() + std::time::Duration::from_millis(time_budget_ms);
+        let (matches, timed_out) = tokio::task::spawn_blocking(move || {
+            let found = std::sync::Mutex::new(Vec::<(std::path::PathBuf, Vec<String>)>::new());
+            let result_count = std::sync::atomic::AtomicUsize::new(0);
+            let timed_out = std::sync::atomic::AtomicBool::new(false);
+
+            walker.build_parallel().run(|| {
+                let query = Arc::clone(&query);
+                let (found, result_count, timed_out) = (&found, &result_count, &timed_out);
+                Box::new(move |entry| {
+                    use ignore::WalkState;
+                    use std::sync::atomic::Ordering;
+
+                    if result_count.load(Ordering::Relaxed) >= max_results {
+                        return WalkState::Quit;
+                    }
+                    if std::time::Instant::now() >= deadline {
+                        timed_out.store(true, Ordering::Relaxed);
+                        return WalkState::Quit;
                     }
-                }
-            }
 
-            // Content search within files
-            if let Some(search_term) = content_search {
-                if path.is_file() {
-                    if let Ok(content) = std::fs::read_to_string(path) {
-                        if content.contains(search_term) {
-                            // Find line numbers where content appears
-                            let matching_lines: Vec<(usize, &str)> = content
-                                .lines()
-                                .enumerate()
-                                .filter(|(_, line)| line.contains(search_term))
-                                .take(3) // Show up to 3 matching lines per file
-                                .collect();
-
-                            if !matching_lines.is_empty() {
-                                results.push(format!(
-                                    "📄 {} (found in {} lines)",
-                                    path.display(),
-                                    matching_lines.len()
-                                ));
-                                for (line_num, line) in matching_lines {
-                                    results.push(format!(
-                                        "  Line {}: {}",
-                                        line_num + 1,
-                                        line.trim()
-                                    ));
-                                }
-                            }
-                        }
+                    let entry = match entry {
+                        Ok(entry) => entry,
+                        Err(_) => return WalkState::Continue,
+                    };
+                    if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+                        return WalkState::Continue;
                     }
-                }
-            }
-        }
 
-        if results.is_empty() {
-            Ok(ToolResult::success(
-                "No files found matching the criteria.".to_string(),
-            ))
+                    let lines = query.search(entry.path());
+                    if !lines.is_empty() {
+                        result_count.fetch_add(lines.len(), Ordering::Relaxed);
+                        found
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .push((entry.into_path(), lines));
+                    }
+                    WalkState::Continue
+                })
+            });
+
+            let mut found = found.into_inner().unwrap_or_else(|e| e.into_inner());
+            found.sort_by(|a, b| a.0.cmp(&b.0));
+            (found, timed_out.into_inner())
+        })
+        .await
+        .map_err(|e| HeliosError::ToolError(format!("File search failed: {}", e)))?;
+
+        let mut results: Vec<String> = matches.into_iter().flat_map(|(_, lines)| lines).collect();
+        results.truncate(max_results);
+
+        let mut output = if results.is_empty() {
+            "No files found matching the criteria.".to_string()
         } else {
-            let output = format!(
+            format!(
                 "Found {} result(s):\n\n{}",
                 results.len(),
                 results.join("\n")
-            );
-            Ok(ToolResult::success(output))
+            )
+        };
+        if timed_out {
+            output.push_str(&format!(
+                "\n\n⏱ Search stopped after the {} ms time budget; results may be incomplete.",
+                time_budget_ms
+            ));
         }
+        Ok(ToolResult::success(output))
     }
 }
 
-// (removed) glob_match helper – logic moved to precompiled regex in FileSearchTool::execute
+/// Directories `FileSearchTool` never descends into.
+const FILE_SEARCH_SKIPPED_DIRS: &[&str] = &["target", "node_modules", "__pycache__"];
+
+/// The number of bytes of each file `FileSearchTool` reads for content search by default.
+const FILE_SEARCH_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// How long `FileSearchTool` searches before returning partial results, by default.
+const FILE_SEARCH_TIME_BUDGET_MS: u64 = 10_000;
+
+/// The number of leading bytes inspected when deciding whether a file is binary.
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+/// Searches the first `max_bytes` of a file for lines matching `re`.
+///
+/// Binary files (those with a NUL byte near the start) and unreadable files are
+/// skipped. Returns the formatted result lines for the file, showing up to three
+/// matches, each surrounded by `context_lines` lines of context.
+fn search_file_content(
+    path: &std::path::Path,
+    re: &regex::Regex,
+    context_lines: usize,
+    max_bytes: u64,
+) -> Option<Vec<String>> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut bytes = Vec::new();
+    file.take(max_bytes).read_to_end(&mut bytes).ok()?;
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return None;
+    }
+
+    let content = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = content.lines().collect();
+    let matching: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| re.is_match(line))
+        .map(|(index, _)| index)
+        .take(3) // Show up to 3 matching lines per file
+        .collect();
+    if matching.is_empty() {
+        return None;
+    }
+
+    let mut output = vec![format!(
+        "📄 {} (found in {} lines)",
+        path_utils::display_path(path),
+        matching.len()
+    )];
+    // The next line index not yet printed, so overlapping context is shown once
+    let mut next_unprinted = 0;
+    for &index in &matching {
+        let from = index.saturating_sub(context_lines).max(next_unprinted);
+        let to = (index + context_lines).min(lines.len() - 1);
+        for (offset, line) in lines[from..=to].iter().enumerate() {
+            let line_index = from + offset;
+            let marker = if line_index == index { ':' } else { '-' };
+            output.push(format!(
+                "  Line {}{} {}",
+                line_index + 1,
+                marker,
+                line.trim()
+            ));
+        }
+        next_unprinted = to + 1;
+    }
+    Some(output)
+}
+
+/// The compiled criteria of a `FileSearchTool` call, shared by the search threads.
+struct FileSearchQuery {
+    name_re: Option<regex::Regex>,
+    name_substring: Option<String>,
+    case_insensitive: bool,
+    content_re: Option<regex::Regex>,
+    context_lines: usize,
+    max_file_size: u64,
+}
+
+impl FileSearchQuery {
+    /// Returns the result lines for a file: a name match, content matches, or both.
+    fn search(&self, path: &std::path::Path) -> Vec<String> {
+        let mut results = Vec::new();
+
+        // Pattern matching for file names; substring matching if the glob did not compile
+        if let Some(pat) = &self.name_substring {
+            if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
+                let is_match = if let Some(re) = &self.name_re {
+                    re.is_match(file_name)
+                } else if self.case_insensitive {
+                    file_name.to_lowercase().contains(&pat.to_lowercase())
+                } else {
+                    file_name.contains(pat.as_str())
+                };
+                if is_match {
+                    results.push(format!("📄 {}", path_utils::display_path(path)));
+                }
+            }
+        }
+
+        // Content search within files
+        if let Some(re) = &self.content_re {
+            if let Some(matches) =
+                search_file_content(path, re, self.context_lines, self.max_file_size)
+            {
+                results.extend(matches);
+            }
+        }
+
+        results
+    }
+}
+
+/// The largest file `FileReadTool` returns whole unless `max_bytes` says otherwise.
+const FILE_READ_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// The number of bytes read at a time when scanning a file backwards.
+const FILE_READ_CHUNK_SIZE: u64 = 64 * 1024;
 
 /// A tool for reading the contents of a file.
+///
+/// Files are never loaded whole unless they fit under the size limit; line ranges
+/// and head views are streamed, tail views are read backwards from the end, and
+/// byte ranges seek straight to the requested offset, so multi-gigabyte logs can
+/// be inspected without exhausting memory.
 pub struct FileReadTool;
 
 #[async_trait]
@@ -483,7 +1178,11 @@ impl Tool for FileReadTool {
     }
 
     fn description(&self) -> &str {
-        "Read the contents of a file. Returns the full file content or specific lines."
+        "Read the contents of a file. Returns the full file content, specific lines, the first or last lines, or a byte range. Use head, tail or bytes mode for large files."
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::ReadOnly
     }
 
     fn parameters(&self) -> HashMap<String, ToolParameter> {
@@ -512,6 +1211,47 @@ impl Tool for FileReadTool {
                 required: Some(false),
             },
         );
+        params.insert(
+            "mode".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "Read mode: 'full' (default), 'head', 'tail' or 'bytes'".to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "lines".to_string(),
+            ToolParameter {
+                param_type: "number".to_string(),
+                description: "Number of lines for 'head' and 'tail' modes (default: 50)"
+                    .to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "offset".to_string(),
+            ToolParameter {
+                param_type: "number".to_string(),
+                description: "Byte offset to start from in 'bytes' mode (default: 0)".to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "length".to_string(),
+            ToolParameter {
+                param_type: "number".to_string(),
+                description: "Number of bytes to read in 'bytes' mode (default: 65536)".to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "max_bytes".to_string(),
+            ToolParameter {
+                param_type: "number".to_string(),
+                description: "Maximum number of bytes to return (default: 10 MB)".to_string(),
+                required: Some(false),
+            },
+        );
         params
     }
 
@@ -521,9 +1261,18 @@ impl Tool for FileReadTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| HeliosError::ToolError("Missing 'path' parameter".to_string()))?;
 
-        let content = std::fs::read_to_string(file_path)
+        let path = path_utils::resolve_path(file_path)?;
+        let file = std::fs::File::open(&path)
             .map_err(|e| HeliosError::ToolError(format!("Failed to read file: {}", e)))?;
+        let file_size = file
+            .metadata()
+            .map_err(|e| HeliosError::ToolError(format!("Failed to read file: {}", e)))?
+            .len();
 
+        let max_bytes = args
+            .get("max_bytes")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(FILE_READ_MAX_BYTES);
         let start_line = args
             .get("start_line")
             .and_then(|v| v.as_u64())
@@ -532,36 +1281,194 @@ impl Tool for FileReadTool {
             .get("end_line")
             .and_then(|v| v.as_u64())
             .map(|n| n as usize);
+        let line_count = args.get("lines").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
+        let io_error =
+            |e: std::io::Error| HeliosError::ToolError(format!("Failed to read file: {}", e));
+
+        let mode = args.get("mode").and_then(|v| v.as_str()).unwrap_or(
+            if start_line.is_some() || end_line.is_some() {
+                "lines"
+            } else {
+                "full"
+            },
+        );
 
-        let output = if let (Some(start), Some(end)) = (start_line, end_line) {
-            let lines: Vec<&str> = content.lines().collect();
-            let start_idx = start.saturating_sub(1);
-            let end_idx = end.min(lines.len());
-
-            if start_idx >= lines.len() {
-                return Err(HeliosError::ToolError(format!(
-                    "Start line {} is beyond file length ({})",
+        let output = match mode {
+            "full" => {
+                if file_size > max_bytes {
+                    return Err(HeliosError::ToolError(format!(
+                        "File {} is {} bytes, larger than the {} byte limit. Use mode 'head', 'tail' or 'bytes', or a line range, to read part of it.",
+                        file_path, file_size, max_bytes
+                    )));
+                }
+                let mut bytes = Vec::with_capacity(file_size as usize);
+                BufReader::new(file)
+                    .read_to_end(&mut bytes)
+                    .map_err(io_error)?;
+                format!(
+                    "File: {}:\n\n{}",
+                    file_path,
+                    String::from_utf8_lossy(&bytes)
+                )
+            }
+            "lines" | "head" => {
+                let (start, end) = if mode == "head" {
+                    (1, line_count)
+                } else {
+                    (
+                        start_line.unwrap_or(1).max(1),
+                        end_line.unwrap_or(usize::MAX),
+                    )
+                };
+                let (lines, total) = read_line_range(BufReader::new(file), start, end, max_bytes)
+                    .map_err(io_error)?;
+                if mode == "lines" && start > total {
+                    return Err(HeliosError::ToolError(format!(
+                        "Start line {} is beyond file length ({})",
+                        start, total
+                    )));
+                }
+                format!(
+                    "File: {} (lines {}-{}):\n\n{}",
+                    file_path,
                     start,
-                    lines.len()
-                )));
+                    start + lines.len().saturating_sub(1),
+                    lines.join("\n")
+                )
+            }
+            "tail" => {
+                let lines = read_tail(file, file_size, line_count, max_bytes).map_err(io_error)?;
+                format!(
+                    "File: {} (last {} lines):\n\n{}",
+                    file_path,
+                    lines.len(),
+                    lines.join("\n")
+                )
+            }
+            "bytes" => {
+                let offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0);
+                let length = args
+                    .get("length")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(FILE_READ_CHUNK_SIZE)
+                    .min(max_bytes);
+                if offset > file_size {
+                    return Err(HeliosError::ToolError(format!(
+                        "Offset {} is beyond file size ({} bytes)",
+                        offset, file_size
+                    )));
+                }
+                let bytes = read_byte_range(file, offset, length).map_err(io_error)?;
+                format!(
+                    "File: {} (bytes {}-{} of {}):\n\n{}",
+                    file_path,
+                    offset,
+                    offset + bytes.len() as u64,
+                    file_size,
+                    String::from_utf8_lossy(&bytes)
+                )
+            }
+            other => {
+                return Err(HeliosError::ToolError(format!(
+                    "Unknown mode '{}'. Use 'full', 'head', 'tail' or 'bytes'.",
+                    other
+                )))
             }
-
-            let selected_lines = &lines[start_idx..end_idx];
-            format!(
-                "File: {} (lines {}-{}):\n\n{}",
-                file_path,
-                start,
-                end_idx,
-                selected_lines.join("\n")
-            )
-        } else {
-            format!("File: {}:\n\n{}", file_path, content)
         };
 
         Ok(ToolResult::success(output))
     }
 }
 
+/// Streams lines `start..=end` (1-indexed) from `reader`, stopping after `max_bytes` of output.
+///
+/// Returns the selected lines and the number of lines read, which is the file's
+/// line count when the range runs past its end.
+fn read_line_range<R: std::io::BufRead>(
+    mut reader: R,
+    start: usize,
+    end: usize,
+    max_bytes: u64,
+) -> std::io::Result<(Vec<String>, usize)> {
+    let mut lines = Vec::new();
+    let mut line_number = 0;
+    let mut returned_bytes = 0u64;
+    let mut buf = Vec::new();
+
+    while line_number < end {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        line_number += 1;
+        if line_number < start {
+            continue;
+        }
+        returned_bytes += buf.len() as u64;
+        if returned_bytes > max_bytes {
+            break;
+        }
+        lines.push(decode_line(&buf));
+    }
+
+    Ok((lines, line_number))
+}
+
+/// Reads the last `count` lines of a file by scanning backwards from the end.
+fn read_tail(
+    mut file: std::fs::File,
+    file_size: u64,
+    count: usize,
+    max_bytes: u64,
+) -> std::io::Result<Vec<String>> {
+    use std::io::{Seek, SeekFrom};
+
+    let mut pos = file_size;
+    let mut buf: Vec<u8> = Vec::new();
+    while pos > 0 && (buf.len() as u64) < max_bytes {
+        let chunk_start = pos.saturating_sub(FILE_READ_CHUNK_SIZE);
+        let mut chunk = vec![0u8; (pos - chunk_start) as usize];
+        file.seek(SeekFrom::Start(chunk_start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+        pos = chunk_start;
+
+        let body = buf.strip_suffix(b"\n").unwrap_or(&buf);
+        if body.iter().filter(|&&b| b == b'\n').count() >= count {
+            break;
+        }
+    }
+
+    // A partial first line is dropped unless the scan reached the start of the file.
+    let mut lines: Vec<String> = buf.split(|&b| b == b'\n').map(decode_line).collect();
+    if buf.ends_with(b"\n") {
+        lines.pop();
+    }
+    if pos > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(count);
+    Ok(lines.split_off(skip))
+}
+
+/// Reads up to `length` bytes starting at `offset`.
+fn read_byte_range(mut file: std::fs::File, offset: u64, length: u64) -> std::io::Result<Vec<u8>> {
+    use std::io::{Seek, SeekFrom};
+
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.take(length).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Decodes a line read as raw bytes, dropping the line ending.
+fn decode_line(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
 /// A tool for writing content to a file.
 pub struct FileWriteTool;
 
@@ -575,6 +1482,10 @@ impl Tool for FileWriteTool {
         "Write content to a file. Creates new file or overwrites existing file."
     }
 
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Destructive
+    }
+
     fn parameters(&self) -> HashMap<String, ToolParameter> {
         let mut params = HashMap::new();
         params.insert(
@@ -608,13 +1519,14 @@ impl Tool for FileWriteTool {
             .ok_or_else(|| HeliosError::ToolError("Missing 'content' parameter".to_string()))?;
 
         // Create parent directories if they don't exist
-        if let Some(parent) = std::path::Path::new(file_path).parent() {
+        let path = path_utils::resolve_path(file_path)?;
+        if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent).map_err(|e| {
                 HeliosError::ToolError(format!("Failed to create directories: {}", e))
             })?;
         }
 
-        std::fs::write(file_path, content)
+        std::fs::write(&path, content)
             .map_err(|e| HeliosError::ToolError(format!("Failed to write file: {}", e)))?;
 
         Ok(ToolResult::success(format!(
@@ -638,6 +1550,10 @@ impl Tool for FileEditTool {
         "Edit a file by replacing specific text or lines. Use this to make targeted changes to existing files."
     }
 
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Destructive
+    }
+
     fn parameters(&self) -> HashMap<String, ToolParameter> {
         let mut params = HashMap::new();
         params.insert(
@@ -689,7 +1605,8 @@ impl Tool for FileEditTool {
             ));
         }
 
-        let path = Path::new(file_path);
+        let resolved = path_utils::resolve_path(file_path)?;
+        let path = resolved.as_path();
         let parent = path
             .parent()
             .ok_or_else(|| HeliosError::ToolError(format!("Invalid target path: {}", file_path)))?;
@@ -699,11 +1616,12 @@ impl Tool for FileEditTool {
 
         // Build a temp file path in the same directory for atomic rename
         let pid = std::process::id();
-        let nanos = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| HeliosError::ToolError(format!("Clock error: {}", e)))?
-            .as_nanos();
-        let tmp_name = format!("{}.tmp.{}.{}", file_name.to_string_lossy(), pid, nanos);
+        let tmp_name = format!(
+            "{}.tmp.{}.{}",
+            file_name.to_string_lossy(),
+            pid,
+            crate::clock::new_id().simple()
+        );
         let tmp_path = parent.join(tmp_name);
 
         // Open files
@@ -748,8 +1666,8 @@ impl Tool for FileEditTool {
             }
         }
 
-        // Atomic replace
-        std::fs::rename(&tmp_path, path).map_err(|e| {
+        // Atomic replace; falls back to copying if the temp file landed on another volume
+        path_utils::rename_or_copy(&tmp_path, path).map_err(|e| {
             let _ = std::fs::remove_file(&tmp_path);
             HeliosError::ToolError(format!("Failed to replace original file: {}", e))
         })?;
@@ -1015,12 +1933,12 @@ impl QdrantRAGTool {
         let embedding = self.generate_embedding(text).await?;
 
         // Create point with metadata
-        let point_id = Uuid::new_v4().to_string();
+        let point_id = crate::clock::new_id().to_string();
         let mut payload = metadata;
         payload.insert("text".to_string(), serde_json::json!(text));
         payload.insert(
             "timestamp".to_string(),
-            serde_json::json!(chrono::Utc::now().to_rfc3339()),
+            serde_json::json!(crate::clock::now().to_rfc3339()),
         );
 
         let point = QdrantPoint {
@@ -1483,8 +2401,149 @@ impl Tool for MemoryDBTool {
     }
 }
 
-/// A tool for scraping web content from URLs.
-pub struct WebScraperTool;
+/// The user agent `WebScraperTool` sends by default.
+const WEB_SCRAPER_USER_AGENT: &str = "Helios-WebScraper/1.0";
+
+/// The default minimum time between two requests to the same host.
+const WEB_SCRAPER_MIN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// The default cap on how much of a response body is read (2 MB).
+const WEB_SCRAPER_MAX_CONTENT_BYTES: usize = 2 * 1024 * 1024;
+
+/// The most of a `robots.txt` file that is parsed (500 KB, as RFC 9309 allows).
+const WEB_SCRAPER_ROBOTS_MAX_BYTES: usize = 500 * 1024;
+
+/// A tool for fetching web pages politely.
+///
+/// Requests to the same host are spaced at least `min_request_interval` apart (or
+/// the site's `Crawl-delay`, if longer), `robots.txt` is checked before each fetch,
+/// and response bodies are cut off after `max_content_bytes`.
+///
+/// ```rust
+/// use helios_engine::WebScraperTool;
+/// use std::time::Duration;
+///
+/// let scraper = WebScraperTool::new()
+///     .user_agent("MyResearchBot/0.1 (+https://example.com/bot)")
+///     .min_request_interval(Duration::from_secs(5))
+///     .max_content_bytes(512 * 1024);
+/// ```
+pub struct WebScraperTool {
+    user_agent: String,
+    min_request_interval: std::time::Duration,
+    max_content_bytes: usize,
+    respect_robots_txt: bool,
+    next_request: std::sync::Mutex<HashMap<String, tokio::time::Instant>>,
+    robots: tokio::sync::Mutex<HashMap<String, Arc<RobotsTxt>>>,
+}
+
+impl WebScraperTool {
+    /// Creates a scraper with the default user agent, a one second per-host
+    /// interval, a 2 MB content cap and `robots.txt` checking enabled.
+    pub fn new() -> Self {
+        Self {
+            user_agent: WEB_SCRAPER_USER_AGENT.to_string(),
+            min_request_interval: WEB_SCRAPER_MIN_INTERVAL,
+            max_content_bytes: WEB_SCRAPER_MAX_CONTENT_BYTES,
+            respect_robots_txt: true,
+            next_request: std::sync::Mutex::new(HashMap::new()),
+            robots: tokio::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the `User-Agent` header, which is also the name matched in `robots.txt`.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Sets the minimum time between two requests to the same host.
+    pub fn min_request_interval(mut self, interval: std::time::Duration) -> Self {
+        self.min_request_interval = interval;
+        self
+    }
+
+    /// Sets how many bytes of a response body are read before it is truncated.
+    pub fn max_content_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_content_bytes = max_bytes;
+        self
+    }
+
+    /// Sets whether `robots.txt` is fetched and obeyed (on by default).
+    pub fn respect_robots_txt(mut self, respect: bool) -> Self {
+        self.respect_robots_txt = respect;
+        self
+    }
+
+    /// Waits until the host may be contacted again and reserves the next slot.
+    async fn throttle(&self, host: &str, crawl_delay: Option<std::time::Duration>) {
+        let interval = crawl_delay.map_or(self.min_request_interval, |delay| {
+            delay.max(self.min_request_interval)
+        });
+
+        let start = {
+            let mut next_request = self.next_request.lock().unwrap_or_else(|e| e.into_inner());
+            let now = tokio::time::Instant::now();
+            let start = next_request.get(host).map_or(now, |next| (*next).max(now));
+            next_request.insert(host.to_string(), start + interval);
+            start
+        };
+
+        tokio::time::sleep_until(start).await;
+    }
+
+    /// Returns the cached `robots.txt` for the URL's origin, fetching it if needed.
+    async fn robots_for(&self, client: &reqwest::Client, url: &reqwest::Url) -> Arc<RobotsTxt> {
+        let origin = url.origin().ascii_serialization();
+        let mut cache = self.robots.lock().await;
+        if let Some(robots) = cache.get(&origin) {
+            return Arc::clone(robots);
+        }
+
+        let robots = match client.get(format!("{}/robots.txt", origin)).send().await {
+            Ok(response) if response.status().is_success() => {
+                let body = read_body_limited(response, WEB_SCRAPER_ROBOTS_MAX_BYTES)
+                    .await
+                    .map(|(body, _)| body)
+                    .unwrap_or_default();
+                RobotsTxt::parse(&String::from_utf8_lossy(&body))
+            }
+            // A missing robots.txt means the site sets no rules.
+            Ok(response) if response.status().is_client_error() => RobotsTxt::allow_all(),
+            // Server errors and unreachable sites are treated as "keep out".
+            _ => RobotsTxt::disallow_all(),
+        };
+
+        let robots = Arc::new(robots);
+        cache.insert(origin, Arc::clone(&robots));
+        robots
+    }
+}
+
+impl Default for WebScraperTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads up to `limit` bytes of a response body, reporting whether it was cut off.
+async fn read_body_limited(response: reqwest::Response, limit: usize) -> Result<(Vec<u8>, bool)> {
+    let mut stream = response.bytes_stream();
+    let mut body = Vec::new();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk
+            .map_err(|e| HeliosError::ToolError(format!("Failed to read response body: {}", e)))?;
+        let remaining = limit - body.len();
+        if chunk.len() > remaining {
+            body.extend_from_slice(&chunk[..remaining]);
+            return Ok((body, true));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok((body, false))
+}
 
 #[async_trait]
 impl Tool for WebScraperTool {
@@ -1493,7 +2552,7 @@ impl Tool for WebScraperTool {
     }
 
     fn description(&self) -> &str {
-        "Fetch and extract content from web URLs. Supports HTML text extraction and basic web scraping."
+        "Fetch and extract content from web URLs. Supports HTML text extraction and basic web scraping. Honours robots.txt and rate limits per site."
     }
 
     fn parameters(&self) -> HashMap<String, ToolParameter> {
@@ -1542,14 +2601,43 @@ impl Tool for WebScraperTool {
             .and_then(|v| v.as_u64())
             .unwrap_or(30);
 
+        let parsed_url = reqwest::Url::parse(url)
+            .map_err(|e| HeliosError::ToolError(format!("Invalid URL '{}': {}", url, e)))?;
+        if !matches!(parsed_url.scheme(), "http" | "https") {
+            return Err(HeliosError::ToolError(format!(
+                "Unsupported URL scheme '{}'; only http and https are allowed",
+                parsed_url.scheme()
+            )));
+        }
+        let host = parsed_url.host_str().unwrap_or_default().to_string();
+
         let client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(timeout_seconds))
-            .user_agent("Helios-WebScraper/1.0")
+            .user_agent(self.user_agent.as_str())
             .build()
             .map_err(|e| HeliosError::ToolError(format!("Failed to create HTTP client: {}", e)))?;
 
+        let mut crawl_delay = None;
+        if self.respect_robots_txt {
+            let robots = self.robots_for(&client, &parsed_url).await;
+            let mut path = parsed_url.path().to_string();
+            if let Some(query) = parsed_url.query() {
+                path.push('?');
+                path.push_str(query);
+            }
+            if !robots.is_allowed(&self.user_agent, &path) {
+                return Ok(ToolResult::error(format!(
+                    "Fetching {} is disallowed by the site's robots.txt",
+                    url
+                )));
+            }
+            crawl_delay = robots.crawl_delay(&self.user_agent);
+        }
+
+        self.throttle(&host, crawl_delay).await;
+
         let response = client
-            .get(url)
+            .get(parsed_url)
             .send()
             .await
             .map_err(|e| HeliosError::ToolError(format!("HTTP request failed: {}", e)))?;
@@ -1561,23 +2649,28 @@ impl Tool for WebScraperTool {
             )));
         }
 
-        let headers = response.headers().clone();
-        let content_type = headers
+        let content_type = response
+            .headers()
             .get("content-type")
             .and_then(|ct| ct.to_str().ok())
-            .unwrap_or("");
+            .unwrap_or("")
+            .to_string();
 
-        let body = response
-            .text()
-            .await
-            .map_err(|e| HeliosError::ToolError(format!("Failed to read response body: {}", e)))?;
+        let (body, truncated) = read_body_limited(response, self.max_content_bytes).await?;
+        let body = String::from_utf8_lossy(&body).into_owned();
 
-        let result = if extract_text && content_type.contains("text/html") {
+        let mut result = if extract_text && content_type.contains("text/html") {
             // Simple HTML text extraction
             extract_text_from_html(&body)
         } else {
             body
         };
+        if truncated {
+            result.push_str(&format!(
+                "\n\n[Content truncated at {} bytes]",
+                self.max_content_bytes
+            ));
+        }
 
         Ok(ToolResult::success(format!(
             "Content fetched from: {}\nContent-Type: {}\n\n{}",
@@ -1623,6 +2716,10 @@ impl Tool for JsonParserTool {
         "Parse, validate, format, and manipulate JSON data. Supports operations: parse, stringify, get_value, set_value, validate"
     }
 
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::ReadOnly
+    }
+
     fn parameters(&self) -> HashMap<String, ToolParameter> {
         let mut params = HashMap::new();
         params.insert(
@@ -1895,6 +2992,10 @@ impl Tool for TimestampTool {
         "Work with timestamps and date/time operations. Supports current time, formatting, parsing, and time arithmetic."
     }
 
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::ReadOnly
+    }
+
     fn parameters(&self) -> HashMap<String, ToolParameter> {
         let mut params = HashMap::new();
         params.insert(
@@ -1966,7 +3067,7 @@ impl Tool for TimestampTool {
 
         match operation {
             "now" => {
-                let now = chrono::Utc::now();
+                let now = crate::clock::now();
                 let timestamp = now.timestamp();
                 let rfc3339 = now.to_rfc3339();
 
@@ -2058,7 +3159,7 @@ impl Tool for TimestampTool {
                 )))
             }
             "add" | "subtract" => {
-                let default_timestamp = chrono::Utc::now().to_rfc3339();
+                let default_timestamp = crate::clock::now().to_rfc3339();
                 let timestamp_str = args
                     .get("timestamp")
                     .and_then(|v| v.as_str())
@@ -2193,6 +3294,10 @@ impl Tool for FileIOTool {
         "Basic file operations: read, write, append, delete, copy, move. Unified interface for common file I/O tasks. Delete operation is safe by default (only empty directories)."
     }
 
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Destructive
+    }
+
     fn parameters(&self) -> HashMap<String, ToolParameter> {
         let mut params = HashMap::new();
         params.insert(
@@ -2365,7 +3470,7 @@ impl Tool for FileIOTool {
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| HeliosError::ToolError("Missing 'dst_path' parameter for copy operation".to_string()))?;
 
-                std::fs::copy(src_path, dst_path)
+                std::fs::copy(path_utils::resolve_path(src_path)?, path_utils::resolve_path(dst_path)?)
                     .map_err(|e| HeliosError::ToolError(format!("Failed to copy file: {}", e)))?;
 
                 Ok(ToolResult::success(format!(
@@ -2384,7 +3489,7 @@ impl Tool for FileIOTool {
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| HeliosError::ToolError("Missing 'dst_path' parameter for move operation".to_string()))?;
 
-                std::fs::rename(src_path, dst_path)
+                path_utils::rename_or_copy(&path_utils::resolve_path(src_path)?, &path_utils::resolve_path(dst_path)?)
                     .map_err(|e| HeliosError::ToolError(format!("Failed to move file: {}", e)))?;
 
                 Ok(ToolResult::success(format!(
@@ -2438,7 +3543,32 @@ impl Tool for FileIOTool {
 }
 
 /// A tool for executing shell commands safely.
-pub struct ShellCommandTool;
+pub struct ShellCommandTool {
+    policy: ShellPolicy,
+}
+
+impl ShellCommandTool {
+    /// Creates a shell tool with the default policy.
+    pub fn new() -> Self {
+        Self::with_policy(ShellPolicy::default())
+    }
+
+    /// Creates a shell tool that enforces `policy`.
+    pub fn with_policy(policy: ShellPolicy) -> Self {
+        Self { policy }
+    }
+
+    /// Returns the policy the tool enforces.
+    pub fn policy(&self) -> &ShellPolicy {
+        &self.policy
+    }
+}
+
+impl Default for ShellCommandTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 #[async_trait]
 impl Tool for ShellCommandTool {
@@ -2447,7 +3577,11 @@ impl Tool for ShellCommandTool {
     }
 
     fn description(&self) -> &str {
-        "Execute shell commands with safety restrictions. Limited to basic commands, no destructive operations allowed."
+        "Execute shell commands under a safety policy. Pipes and redirection are supported; destructive and privilege-escalating programs are blocked."
+    }
+
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::Destructive
     }
 
     fn parameters(&self) -> HashMap<String, ToolParameter> {
@@ -2464,7 +3598,10 @@ impl Tool for ShellCommandTool {
             "timeout_seconds".to_string(),
             ToolParameter {
                 param_type: "number".to_string(),
-                description: "Command timeout in seconds (default: 30, max: 60)".to_string(),
+                description: format!(
+                    "Command timeout in seconds (default: {}, max: {})",
+                    self.policy.default_timeout_secs, self.policy.max_timeout_secs
+                ),
                 required: Some(false),
             },
         );
@@ -2480,76 +3617,66 @@ impl Tool for ShellCommandTool {
         let timeout_seconds = args
             .get("timeout_seconds")
             .and_then(|v| v.as_u64())
-            .unwrap_or(30)
-            .min(60); // Max 60 seconds
-
-        // Safety check - block dangerous commands
-        let dangerous_patterns = [
-            "rm ",
-            "rmdir",
-            "del ",
-            "format",
-            "fdisk",
-            "mkfs",
-            "dd ",
-            "shred",
-            "wipe",
-            "sudo",
-            "su ",
-            "chmod 777",
-            "chown root",
-            "passwd",
-            "usermod",
-            "userdel",
-            ">",
-            ">>",
-            "|",
-            ";",
-            "&&",
-            "||",
-            "`",
-            "$(",
-        ];
-
-        for pattern in &dangerous_patterns {
-            if command.contains(pattern) {
-                return Err(HeliosError::ToolError(format!(
-                    "Command blocked for safety: contains '{}'",
-                    pattern
-                )));
-            }
+            .unwrap_or(self.policy.default_timeout_secs)
+            .min(self.policy.max_timeout_secs);
+
+        self.policy.check(command)?;
+
+        let (shell, shell_args) = self.policy.shell.invocation();
+        let mut process = tokio::process::Command::new(shell);
+        process
+            .args(shell_args)
+            .arg(command)
+            .env_clear()
+            .envs(self.policy.environment())
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(dir) = &self.policy.working_dir {
+            process.current_dir(dir);
         }
 
-        // Execute command with timeout
-        let output = tokio::time::timeout(
-            std::time::Duration::from_secs(timeout_seconds),
-            tokio::process::Command::new("sh")
-                .arg("-c")
-                .arg(command)
-                .output(),
-        )
-        .await
-        .map_err(|_| {
-            HeliosError::ToolError(format!(
-                "Command timed out after {} seconds",
-                timeout_seconds
-            ))
-        })?
-        .map_err(|e| HeliosError::ToolError(format!("Failed to execute command: {}", e)))?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut child = process
+            .spawn()
+            .map_err(|e| HeliosError::ToolError(format!("Failed to execute command: {}", e)))?;
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let limit = self.policy.max_output_bytes as u64;
+
+        let (status, stdout, stderr) =
+            tokio::time::timeout(std::time::Duration::from_secs(timeout_seconds), async {
+                tokio::try_join!(
+                    child.wait(),
+                    read_limited(stdout, limit),
+                    read_limited(stderr, limit)
+                )
+            })
+            .await
+            .map_err(|_| {
+                HeliosError::ToolError(format!(
+                    "Command timed out after {} seconds",
+                    timeout_seconds
+                ))
+            })?
+            .map_err(|e| HeliosError::ToolError(format!("Failed to execute command: {}", e)))?;
 
-        let exit_code = output.status.code().unwrap_or(-1);
+        let exit_code = status.code().unwrap_or(-1);
 
         let mut result = format!("Command: {}\nExit code: {}\n", command, exit_code);
 
-        if !stdout.is_empty() {
-            result.push_str(&format!("Stdout:\n{}\n", stdout));
-        }
-
-        if !stderr.is_empty() {
-            result.push_str(&format!("Stderr:\n{}\n", stderr));
+        for (label, (bytes, dropped)) in [("Stdout", stdout), ("Stderr", stderr)] {
+            if bytes.is_empty() {
+                continue;
+            }
+            result.push_str(&format!(
+                "{}:\n{}\n",
+                label,
+                String::from_utf8_lossy(&bytes)
+            ));
+            if dropped > 0 {
+                result.push_str(&format!("[{} more bytes truncated]\n", dropped));
+            }
         }
 
         if exit_code == 0 {
@@ -2560,6 +3687,27 @@ impl Tool for ShellCommandTool {
     }
 }
 
+/// Reads at most `limit` bytes from a child's output stream, discarding the rest.
+///
+/// Returns the bytes kept and the number of bytes discarded.
+pub(crate) async fn read_limited<R>(
+    stream: Option<R>,
+    limit: u64,
+) -> std::io::Result<(Vec<u8>, u64)>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let mut kept = Vec::new();
+    let mut dropped = 0;
+    if let Some(mut stream) = stream {
+        (&mut stream).take(limit).read_to_end(&mut kept).await?;
+        dropped = tokio::io::copy(&mut stream, &mut tokio::io::sink()).await?;
+    }
+    Ok((kept, dropped))
+}
+
 /// A tool for making HTTP requests.
 pub struct HttpRequestTool;
 
@@ -2723,6 +3871,10 @@ impl Tool for FileListTool {
         "List directory contents with detailed information including file sizes, types, and modification times."
     }
 
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::ReadOnly
+    }
+
     fn parameters(&self) -> HashMap<String, ToolParameter> {
         let mut params = HashMap::new();
         params.insert(
@@ -2762,6 +3914,7 @@ impl Tool for FileListTool {
 
     async fn execute(&self, args: Value) -> Result<ToolResult> {
         let base_path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+        let resolved = path_utils::resolve_path(base_path)?;
         let show_hidden = args
             .get("show_hidden")
             .and_then(|v| v.as_bool())
@@ -2775,7 +3928,7 @@ impl Tool for FileListTool {
         let mut results = Vec::new();
 
         if recursive {
-            for entry in walkdir::WalkDir::new(base_path)
+            for entry in walkdir::WalkDir::new(&resolved)
                 .max_depth(max_depth)
                 .into_iter()
                 .filter_map(|e| e.ok())
@@ -2785,7 +3938,7 @@ impl Tool for FileListTool {
                 }
             }
         } else {
-            let entries = std::fs::read_dir(base_path)
+            let entries = std::fs::read_dir(&resolved)
                 .map_err(|e| HeliosError::ToolError(format!("Failed to read directory: {}", e)))?;
 
             for entry in entries.filter_map(|e| e.ok()) {
@@ -2812,15 +3965,14 @@ impl Tool for FileListTool {
 /// Format a walkdir directory entry with metadata.
 fn format_walkdir_entry(entry: &walkdir::DirEntry, show_hidden: bool) -> Option<String> {
     let path = entry.path();
-    let file_name = path.file_name()?.to_str()?;
+    path.file_name()?.to_str()?;
+    let metadata = entry.metadata().ok()?;
 
     // Skip hidden files if not requested
-    if !show_hidden && file_name.starts_with('.') {
+    if !show_hidden && path_utils::is_hidden(path, Some(&metadata)) {
         return None;
     }
 
-    let metadata = entry.metadata().ok()?;
-
     let file_type = if metadata.is_dir() { "DIR" } else { "FILE" };
     let size = metadata.len();
     let modified = metadata.modified().ok()?;
@@ -2832,22 +3984,21 @@ fn format_walkdir_entry(entry: &walkdir::DirEntry, show_hidden: bool) -> Option<
         file_type,
         size,
         modified_str,
-        path.display()
+        path_utils::display_path(path)
     ))
 }
 
 /// Format a directory entry with metadata.
 fn format_entry(entry: &std::fs::DirEntry, show_hidden: bool) -> Option<String> {
     let path = entry.path();
-    let file_name = path.file_name()?.to_str()?;
+    path.file_name()?.to_str()?;
+    let metadata = entry.metadata().ok()?;
 
     // Skip hidden files if not requested
-    if !show_hidden && file_name.starts_with('.') {
+    if !show_hidden && path_utils::is_hidden(&path, Some(&metadata)) {
         return None;
     }
 
-    let metadata = entry.metadata().ok()?;
-
     let file_type = if metadata.is_dir() { "DIR" } else { "FILE" };
     let size = metadata.len();
     let modified = metadata.modified().ok()?;
@@ -2859,11 +4010,20 @@ fn format_entry(entry: &std::fs::DirEntry, show_hidden: bool) -> Option<String>
         file_type,
         size,
         modified_str,
-        path.display()
+        path_utils::display_path(&path)
     ))
 }
 
+/// The default number of processes reported by `SystemInfoTool`.
+const SYSTEM_INFO_PROCESS_LIMIT: usize = 10;
+
+/// How long `SystemInfoTool` waits for `nvidia-smi` before giving up on GPU info.
+const SYSTEM_INFO_GPU_TIMEOUT_SECS: u64 = 5;
+
 /// A tool for retrieving system information.
+///
+/// Results are formatted as text by default; pass `"output_format": "json"` to get
+/// machine-readable metrics keyed by category.
 pub struct SystemInfoTool;
 
 #[async_trait]
@@ -2876,15 +4036,46 @@ impl Tool for SystemInfoTool {
         "Retrieve system information including OS, CPU, memory, disk usage, and network interfaces."
     }
 
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::ReadOnly
+    }
+
     fn parameters(&self) -> HashMap<String, ToolParameter> {
         let mut params = HashMap::new();
         params.insert(
-            "category".to_string(),
+            "category".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description:
+                    "Info category: 'all', 'os', 'cpu', 'memory', 'disk', 'network', 'processes', 'gpu' (default: all)"
+                        .to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "output_format".to_string(),
             ToolParameter {
                 param_type: "string".to_string(),
-                description:
-                    "Info category: 'all', 'os', 'cpu', 'memory', 'disk', 'network' (default: all)"
-                        .to_string(),
+                description: "Output format: 'text' or 'json' (default: text)".to_string(),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "process_limit".to_string(),
+            ToolParameter {
+                param_type: "number".to_string(),
+                description: format!(
+                    "Maximum number of processes to report (default: {})",
+                    SYSTEM_INFO_PROCESS_LIMIT
+                ),
+                required: Some(false),
+            },
+        );
+        params.insert(
+            "sort_by".to_string(),
+            ToolParameter {
+                param_type: "string".to_string(),
+                description: "Order processes by 'cpu' or 'memory' (default: cpu)".to_string(),
                 required: Some(false),
             },
         );
@@ -2896,33 +4087,91 @@ impl Tool for SystemInfoTool {
             .get("category")
             .and_then(|v| v.as_str())
             .unwrap_or("all");
+        let output_format = args
+            .get("output_format")
+            .and_then(|v| v.as_str())
+            .unwrap_or("text");
+        let process_limit = args
+            .get("process_limit")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(SYSTEM_INFO_PROCESS_LIMIT);
+        let sort_by = args
+            .get("sort_by")
+            .and_then(|v| v.as_str())
+            .unwrap_or("cpu");
+
+        const CATEGORIES: &[&str] = &["os", "cpu", "memory", "disk", "network", "processes", "gpu"];
+        let categories: Vec<&str> = match category {
+            "all" => CATEGORIES.to_vec(),
+            c if CATEGORIES.contains(&c) => vec![c],
+            _ => {
+                return Err(HeliosError::ToolError(format!(
+                    "Unknown category '{}'. Use: all, {}",
+                    category,
+                    CATEGORIES.join(", ")
+                )))
+            }
+        };
+        if output_format != "text" && output_format != "json" {
+            return Err(HeliosError::ToolError(format!(
+                "Unknown output_format '{}'. Use: text, json",
+                output_format
+            )));
+        }
+        if sort_by != "cpu" && sort_by != "memory" {
+            return Err(HeliosError::ToolError(format!(
+                "Unknown sort_by '{}'. Use: cpu, memory",
+                sort_by
+            )));
+        }
 
         let mut system = sysinfo::System::new_all();
         system.refresh_all();
+        if categories.contains(&"processes") {
+            // CPU usage is measured between two refreshes, so take a second sample.
+            tokio::time::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL).await;
+            system.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+        }
 
         let disks = sysinfo::Disks::new_with_refreshed_list();
         let networks = sysinfo::Networks::new_with_refreshed_list();
+        let gpus = if categories.contains(&"gpu") {
+            query_gpus().await
+        } else {
+            None
+        };
+        let processes = top_processes(&system, process_limit, sort_by);
+
+        if output_format == "json" {
+            let mut report = serde_json::Map::new();
+            for category in &categories {
+                let value = match *category {
+                    "os" => os_info_json(),
+                    "cpu" => cpu_info_json(&system),
+                    "memory" => memory_info_json(&system),
+                    "disk" => disk_info_json(&disks),
+                    "network" => network_info_json(&networks),
+                    "processes" => Value::Array(processes.clone()),
+                    _ => gpus.clone().map_or(Value::Null, Value::Array),
+                };
+                report.insert(category.to_string(), value);
+            }
+            return Ok(ToolResult::success(serde_json::to_string_pretty(
+                &Value::Object(report),
+            )?));
+        }
 
         let mut output = String::new();
-
-        match category {
-            "all" => {
-                output.push_str(&get_os_info(&system));
-                output.push_str(&get_cpu_info(&system));
-                output.push_str(&get_memory_info(&system));
-                output.push_str(&get_disk_info(&disks));
-                output.push_str(&get_network_info(&networks));
-            }
-            "os" => output.push_str(&get_os_info(&system)),
-            "cpu" => output.push_str(&get_cpu_info(&system)),
-            "memory" => output.push_str(&get_memory_info(&system)),
-            "disk" => output.push_str(&get_disk_info(&disks)),
-            "network" => output.push_str(&get_network_info(&networks)),
-            _ => {
-                return Err(HeliosError::ToolError(format!(
-                    "Unknown category '{}'. Use: all, os, cpu, memory, disk, network",
-                    category
-                )))
+        for category in &categories {
+            match *category {
+                "os" => output.push_str(&get_os_info(&system)),
+                "cpu" => output.push_str(&get_cpu_info(&system)),
+                "memory" => output.push_str(&get_memory_info(&system)),
+                "disk" => output.push_str(&get_disk_info(&disks)),
+                "network" => output.push_str(&get_network_info(&networks)),
+                "processes" => output.push_str(&get_process_info(&processes)),
+                _ => output.push_str(&get_gpu_info(gpus.as_deref())),
             }
         }
 
@@ -3050,6 +4299,193 @@ fn get_network_info(networks: &sysinfo::Networks) -> String {
     info
 }
 
+/// Get the processes using the most CPU or memory, as JSON objects.
+fn top_processes(system: &sysinfo::System, limit: usize, sort_by: &str) -> Vec<Value> {
+    let mut processes: Vec<&sysinfo::Process> = system.processes().values().collect();
+    if sort_by == "memory" {
+        processes.sort_by_key(|p| std::cmp::Reverse(p.memory()));
+    } else {
+        processes.sort_by(|a, b| b.cpu_usage().total_cmp(&a.cpu_usage()));
+    }
+
+    processes
+        .into_iter()
+        .take(limit)
+        .map(|p| {
+            serde_json::json!({
+                "pid": p.pid().as_u32(),
+                "name": p.name().to_string_lossy(),
+                "cpu_usage_percent": p.cpu_usage(),
+                "memory_bytes": p.memory(),
+                "status": p.status().to_string(),
+                "run_time_secs": p.run_time(),
+            })
+        })
+        .collect()
+}
+
+/// Get per-process information.
+fn get_process_info(processes: &[Value]) -> String {
+    let mut info = String::from("=== Processes ===\n");
+
+    for process in processes {
+        info.push_str(&format!(
+            "PID {}: {} - {:.1}% CPU, {} MB memory, {}\n",
+            process["pid"],
+            process["name"].as_str().unwrap_or(""),
+            process["cpu_usage_percent"].as_f64().unwrap_or(0.0),
+            process["memory_bytes"].as_u64().unwrap_or(0) / 1024 / 1024,
+            process["status"].as_str().unwrap_or("")
+        ));
+    }
+
+    info.push('\n');
+    info
+}
+
+/// Query NVIDIA GPUs through `nvidia-smi`.
+///
+/// Returns `None` when the command is missing, fails or does not answer in time.
+async fn query_gpus() -> Option<Vec<Value>> {
+    let output = tokio::time::timeout(
+        std::time::Duration::from_secs(SYSTEM_INFO_GPU_TIMEOUT_SECS),
+        tokio::process::Command::new("nvidia-smi")
+            .args([
+                "--query-gpu=index,name,utilization.gpu,memory.used,memory.total,temperature.gpu",
+                "--format=csv,noheader,nounits",
+            ])
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let number = |field: Option<&str>| {
+        field
+            .and_then(|f| f.trim().parse::<f64>().ok())
+            .map_or(Value::Null, |n| serde_json::json!(n))
+    };
+
+    let gpus = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            serde_json::json!({
+                "index": number(fields.first().copied()),
+                "name": fields.get(1).map(|f| f.trim()).unwrap_or(""),
+                "utilization_percent": number(fields.get(2).copied()),
+                "memory_used_mb": number(fields.get(3).copied()),
+                "memory_total_mb": number(fields.get(4).copied()),
+                "temperature_celsius": number(fields.get(5).copied()),
+            })
+        })
+        .collect();
+
+    Some(gpus)
+}
+
+/// Get GPU information.
+fn get_gpu_info(gpus: Option<&[Value]>) -> String {
+    let mut info = String::from("=== GPU Information ===\n");
+
+    match gpus {
+        None => info.push_str("GPU information unavailable (nvidia-smi not found)\n"),
+        Some([]) => info.push_str("No GPUs found\n"),
+        Some(gpus) => {
+            for gpu in gpus {
+                info.push_str(&format!(
+                    "GPU {}: {}\n",
+                    gpu["index"],
+                    gpu["name"].as_str().unwrap_or("")
+                ));
+                info.push_str(&format!("Utilization: {}%\n", gpu["utilization_percent"]));
+                info.push_str(&format!(
+                    "Memory: {} / {} MB\n",
+                    gpu["memory_used_mb"], gpu["memory_total_mb"]
+                ));
+                info.push_str(&format!("Temperature: {} °C\n", gpu["temperature_celsius"]));
+            }
+        }
+    }
+
+    info.push('\n');
+    info
+}
+
+/// Get operating system information as JSON.
+fn os_info_json() -> Value {
+    serde_json::json!({
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "family": std::env::consts::FAMILY,
+        "name": sysinfo::System::name(),
+        "os_version": sysinfo::System::os_version(),
+        "kernel_version": sysinfo::System::kernel_version(),
+        "hostname": hostname::get().ok().and_then(|h| h.to_str().map(str::to_string)),
+        "uptime_secs": sysinfo::System::uptime(),
+    })
+}
+
+/// Get CPU information as JSON.
+fn cpu_info_json(system: &sysinfo::System) -> Value {
+    serde_json::json!({
+        "physical_cores": sysinfo::System::physical_core_count(),
+        "logical_cores": system.cpus().len(),
+        "brand": system.cpus().first().map(|cpu| cpu.brand()),
+        "global_usage_percent": system.global_cpu_usage(),
+        "usage_percent": system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect::<Vec<_>>(),
+    })
+}
+
+/// Get memory information as JSON.
+fn memory_info_json(system: &sysinfo::System) -> Value {
+    serde_json::json!({
+        "total_bytes": system.total_memory(),
+        "used_bytes": system.used_memory(),
+        "available_bytes": system.available_memory(),
+        "total_swap_bytes": system.total_swap(),
+        "used_swap_bytes": system.used_swap(),
+    })
+}
+
+/// Get disk information as JSON.
+fn disk_info_json(disks: &sysinfo::Disks) -> Value {
+    disks
+        .list()
+        .iter()
+        .map(|disk| {
+            serde_json::json!({
+                "mount_point": disk.mount_point().to_string_lossy(),
+                "file_system": disk.file_system().to_string_lossy(),
+                "total_bytes": disk.total_space(),
+                "available_bytes": disk.available_space(),
+                "removable": disk.is_removable(),
+            })
+        })
+        .collect()
+}
+
+/// Get network information as JSON.
+fn network_info_json(networks: &sysinfo::Networks) -> Value {
+    networks
+        .list()
+        .iter()
+        .map(|(name, data)| {
+            serde_json::json!({
+                "interface": name,
+                "received_bytes": data.total_received(),
+                "transmitted_bytes": data.total_transmitted(),
+            })
+        })
+        .collect()
+}
+
 /// A tool for text processing and manipulation operations.
 pub struct TextProcessorTool;
 
@@ -3063,6 +4499,10 @@ impl Tool for TextProcessorTool {
         "Process and manipulate text with operations like search, replace, split, join, count, and format."
     }
 
+    fn permission(&self) -> ToolPermission {
+        ToolPermission::ReadOnly
+    }
+
     fn parameters(&self) -> HashMap<String, ToolParameter> {
         let mut params = HashMap::new();
         params.insert(
@@ -3328,10 +4768,10 @@ mod tests {
         assert!(result.success);
         let out = result.output;
         // Should find .rs files
-        assert!(out.contains(&file_rs.to_string_lossy().to_string()));
-        assert!(out.contains(&file_sub_rs.to_string_lossy().to_string()));
+        assert!(out.contains(&path_utils::display_path(&file_rs)));
+        assert!(out.contains(&path_utils::display_path(&file_sub_rs)));
         // Should not include .txt
-        assert!(!out.contains(&file_txt.to_string_lossy().to_string()));
+        assert!(!out.contains(&path_utils::display_path(&file_txt)));
 
         // Cleanup
         let _ = std::fs::remove_dir_all(&test_dir);
@@ -3363,12 +4803,175 @@ mod tests {
         let result = tool.execute(args).await.unwrap();
         assert!(result.success);
         let out = result.output;
-        assert!(out.contains(&special.to_string_lossy().to_string()));
+        assert!(out.contains(&path_utils::display_path(&special)));
 
         // Cleanup
         let _ = std::fs::remove_dir_all(&test_dir);
     }
 
+    /// Tests the file read tool's line range, head, tail and byte range modes.
+    #[tokio::test]
+    async fn test_file_read_tool_partial_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let content: String = (1..=200_000).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, &content).unwrap();
+        let path = path.to_string_lossy().to_string();
+        let tool = FileReadTool;
+
+        let result = tool
+            .execute(json!({"path": path, "start_line": 3, "end_line": 4}))
+            .await
+            .unwrap();
+        assert!(result.output.ends_with("line 3\nline 4"));
+
+        let result = tool
+            .execute(json!({"path": path, "mode": "head", "lines": 2}))
+            .await
+            .unwrap();
+        assert!(result.output.ends_with("\n\nline 1\nline 2"));
+
+        let result = tool
+            .execute(json!({"path": path, "mode": "tail", "lines": 3}))
+            .await
+            .unwrap();
+        assert!(result
+            .output
+            .ends_with("\n\nline 199998\nline 199999\nline 200000"));
+
+        let result = tool
+            .execute(json!({"path": path, "mode": "bytes", "offset": 7, "length": 6}))
+            .await
+            .unwrap();
+        assert!(result.output.ends_with("line 2"));
+
+        // Whole-file reads are refused above the size limit.
+        assert!(tool
+            .execute(json!({"path": path, "max_bytes": 1024}))
+            .await
+            .is_err());
+        assert!(tool
+            .execute(json!({"path": path, "start_line": 300_000}))
+            .await
+            .is_err());
+    }
+
+    /// Tests that content search respects ignore files, skips binaries and shows context.
+    #[tokio::test]
+    async fn test_file_search_tool_ignore_binary_and_context() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "build/\n").unwrap();
+        std::fs::create_dir_all(dir.path().join("build")).unwrap();
+        std::fs::write(
+            dir.path().join("build").join("out.txt"),
+            "TODO: generated\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("blob.bin"), b"TODO\0\x01\x02").unwrap();
+        std::fs::write(
+            dir.path().join("main.rs"),
+            "fn main() {\n    // TODO: fix 42\n}\n",
+        )
+        .unwrap();
+
+        let tool = FileSearchTool;
+        let result = tool
+            .execute(json!({
+                "path": dir.path().to_string_lossy(),
+                "content": r"TODO: \w+ \d+",
+                "regex": true,
+                "context_lines": 1
+            }))
+            .await
+            .unwrap();
+        let out = result.output;
+        assert!(out.contains("main.rs (found in 1 lines)"));
+        assert!(out.contains("Line 1- fn main() {"));
+        assert!(out.contains("Line 2: // TODO: fix 42"));
+        assert!(out.contains("Line 3- }"));
+        assert!(!out.contains("out.txt"));
+        assert!(!out.contains("blob.bin"));
+
+        let result = tool
+            .execute(json!({
+                "path": dir.path().to_string_lossy(),
+                "content": "TODO",
+                "respect_ignore": false
+            }))
+            .await
+            .unwrap();
+        assert!(result.output.contains("out.txt"));
+        assert!(!result.output.contains("blob.bin"));
+
+        assert!(tool
+            .execute(json!({"path": dir.path().to_string_lossy(), "content": "(", "regex": true}))
+            .await
+            .is_err());
+    }
+
+    /// Tests that parallel content search returns every match in path order and
+    /// honours its time budget.
+    #[tokio::test]
+    async fn test_file_search_tool_parallel_and_time_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..40 {
+            let sub = dir.path().join(format!("dir{}", i % 4));
+            std::fs::create_dir_all(&sub).unwrap();
+            std::fs::write(sub.join(format!("file{:02}.txt", i)), "needle\n").unwrap();
+        }
+
+        let tool = FileSearchTool;
+        let result = tool
+            .execute(json!({
+                "path": dir.path().to_string_lossy(),
+                "content": "needle",
+                "max_results": 1000
+            }))
+            .await
+            .unwrap();
+        let files: Vec<&str> = result
+            .output
+            .lines()
+            .filter(|line| line.starts_with("📄"))
+            .collect();
+        assert_eq!(files.len(), 40);
+        let mut sorted = files.clone();
+        sorted.sort();
+        assert_eq!(files, sorted);
+
+        let result = tool
+            .execute(json!({
+                "path": dir.path().to_string_lossy(),
+                "content": "needle",
+                "time_budget_ms": 0
+            }))
+            .await
+            .unwrap();
+        assert!(result.output.contains("time budget"));
+    }
+
+    /// Tests that the file search tool honours the `case_insensitive` option.
+    #[tokio::test]
+    async fn test_file_search_tool_case_insensitive() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("README.MD"), "docs\n").unwrap();
+
+        let tool = FileSearchTool;
+        let search = |case_insensitive: bool| {
+            json!({
+                "path": dir.path().to_string_lossy(),
+                "pattern": "*.md",
+                "case_insensitive": case_insensitive
+            })
+        };
+
+        let result = tool.execute(search(true)).await.unwrap();
+        assert!(result.output.contains("README.MD"));
+
+        let result = tool.execute(search(false)).await.unwrap();
+        assert!(result.output.contains("No files found"));
+    }
+
     /// Tests the creation of an error `ToolResult`.
     #[test]
     fn test_tool_result_error() {
@@ -3492,6 +5095,173 @@ mod tests {
         assert!(result.is_err());
     }
 
+    /// Tests that a repeatedly failing tool is short-circuited by its circuit breaker.
+    #[tokio::test]
+    async fn test_tool_registry_circuit_breaker() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(CalculatorTool));
+        registry.set_circuit_breaker(CircuitBreakerConfig::new(
+            2,
+            std::time::Duration::from_secs(60),
+        ));
+
+        for _ in 0..2 {
+            let result = registry.execute("calculator", json!({})).await;
+            assert!(matches!(result, Err(HeliosError::ToolError(_))));
+        }
+
+        let result = registry
+            .execute("calculator", json!({"expression": "1 + 1"}))
+            .await;
+        assert!(matches!(result, Err(HeliosError::CircuitOpen(_))));
+
+        registry.circuit_breaker("calculator").unwrap().reset();
+        let result = registry
+            .execute("calculator", json!({"expression": "1 + 1"}))
+            .await
+            .unwrap();
+        assert_eq!(result.output, "2");
+    }
+
+    /// Tests that unsuccessful tool results count as circuit breaker failures.
+    #[tokio::test]
+    async fn test_tool_registry_circuit_breaker_counts_failed_results() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MemoryDBTool::new()));
+        registry.set_circuit_breaker(CircuitBreakerConfig::new(
+            2,
+            std::time::Duration::from_secs(60),
+        ));
+
+        for _ in 0..2 {
+            let result = registry
+                .execute("memory_db", json!({"operation": "get", "key": "missing"}))
+                .await
+                .unwrap();
+            assert!(!result.success);
+        }
+
+        let result = registry
+            .execute("memory_db", json!({"operation": "list"}))
+            .await;
+        assert!(matches!(result, Err(HeliosError::CircuitOpen(_))));
+    }
+
+    /// Tests that sandboxed file tools cannot reach outside their root.
+    #[tokio::test]
+    async fn test_sandboxed_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        let jail = PathJail::new(dir.path()).unwrap();
+        let write = SandboxedTool::new(Box::new(FileWriteTool), jail.clone());
+        let io = SandboxedTool::new(Box::new(FileIOTool), jail.clone());
+        let list = SandboxedTool::new(Box::new(FileListTool), jail);
+
+        assert_eq!(write.name(), "file_write");
+        let result = write
+            .execute(json!({"path": "notes/a.txt", "content": "hi"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(dir.path().join("notes").join("a.txt").exists());
+
+        assert!(write
+            .execute(json!({"path": "../escape.txt", "content": "no"}))
+            .await
+            .is_err());
+        assert!(io
+            .execute(json!({
+                "operation": "move",
+                "src_path": "notes/a.txt",
+                "dst_path": "../../a.txt"
+            }))
+            .await
+            .is_err());
+
+        // Without a path, listing starts at the root rather than the working directory.
+        let result = list.execute(json!({})).await.unwrap();
+        assert!(result.output.contains("notes"));
+    }
+
+    /// Tests that destructive tools are only run when the approval handler allows it.
+    #[tokio::test]
+    async fn test_tool_registry_approval() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(FileWriteTool));
+        registry.register(Box::new(CalculatorTool));
+        registry.set_approval_handler(|request: &ToolApprovalRequest| {
+            request.arguments["content"] == "allowed"
+        });
+
+        assert_eq!(
+            registry.permission("file_write"),
+            Some(ToolPermission::Destructive)
+        );
+        assert_eq!(
+            registry.permission("calculator"),
+            Some(ToolPermission::ReadOnly)
+        );
+
+        let args = json!({"path": path.to_string_lossy(), "content": "denied"});
+        let result = registry.execute("file_write", args).await.unwrap();
+        assert!(!result.success);
+        assert!(!path.exists());
+
+        let args = json!({"path": path.to_string_lossy(), "content": "allowed"});
+        assert!(registry.execute("file_write", args).await.unwrap().success);
+
+        // Overrides take precedence over the tool's own level.
+        registry.set_permission("calculator", ToolPermission::Destructive);
+        let result = registry
+            .execute("calculator", json!({"expression": "1 + 1"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+    }
+
+    /// Tests that output over a tool's limit is shortened, and which limit applies.
+    #[tokio::test]
+    async fn test_tool_registry_output_limit() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(EchoTool));
+        registry.register(Box::new(CalculatorTool));
+        let message = json!({"message": "a".repeat(50)});
+
+        let result = registry.execute("echo", message.clone()).await.unwrap();
+        assert_eq!(result.output.len(), 56);
+
+        registry.set_default_output_limit(OutputLimit::head(10));
+        let result = registry.execute("echo", message.clone()).await.unwrap();
+        assert!(result.success);
+        assert!(result
+            .output
+            .starts_with("Echo: aaaa\n[... output truncated: showing the first 10 of 56"));
+        let result = registry
+            .execute("calculator", json!({"expression": "5 * 6"}))
+            .await
+            .unwrap();
+        assert_eq!(result.output, "30");
+
+        // A tool's own limit takes precedence over the default.
+        registry.register(
+            crate::ToolBuilder::new("digits")
+                .output_limit(OutputLimit::head_tail(40))
+                .sync_function(|_| Ok(ToolResult::success("0123456789".repeat(3))))
+                .build(),
+        );
+        let result = registry.execute("digits", json!({})).await.unwrap();
+        assert_eq!(result.output.len(), 30);
+
+        registry.set_output_limit("echo", OutputLimit::head_tail(4));
+        assert_eq!(
+            registry.output_limit("echo"),
+            Some(OutputLimit::head_tail(4))
+        );
+        let result = registry.execute("echo", message).await.unwrap();
+        assert_eq!(result.output, "Ec\n[... 52 characters omitted ...]\naa");
+    }
+
     /// Tests getting the definitions of all tools in the `ToolRegistry`.
     #[test]
     fn test_tool_registry_get_definitions() {
@@ -3739,7 +5509,7 @@ mod tests {
     /// Tests the WebScraperTool.
     #[tokio::test]
     async fn test_web_scraper_tool() {
-        let tool = WebScraperTool;
+        let tool = WebScraperTool::new();
         assert_eq!(tool.name(), "web_scraper");
 
         // Test with missing URL parameter
@@ -3751,6 +5521,53 @@ mod tests {
         // without mocking, but we can test parameter validation
     }
 
+    /// Tests robots.txt checking, per-host throttling and the content size cap.
+    #[tokio::test]
+    async fn test_web_scraper_tool_politeness() {
+        use axum::routing::get;
+
+        let app = axum::Router::new()
+            .route(
+                "/robots.txt",
+                get(|| async { "User-agent: *\nDisallow: /private\n" }),
+            )
+            .route("/page", get(|| async { "x".repeat(10_000) }))
+            .route("/private/data", get(|| async { "secret" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let tool = WebScraperTool::new()
+            .min_request_interval(std::time::Duration::from_millis(200))
+            .max_content_bytes(100);
+
+        let result = tool
+            .execute(json!({ "url": format!("http://{}/private/data", addr) }))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("robots.txt"));
+
+        let started = std::time::Instant::now();
+        for _ in 0..2 {
+            let result = tool
+                .execute(json!({ "url": format!("http://{}/page", addr) }))
+                .await
+                .unwrap();
+            assert!(result.success);
+            assert!(result.output.contains("[Content truncated at 100 bytes]"));
+            assert!(!result.output.contains(&"x".repeat(101)));
+        }
+        assert!(started.elapsed() >= std::time::Duration::from_millis(200));
+
+        let tool = WebScraperTool::new().respect_robots_txt(false);
+        let result = tool
+            .execute(json!({ "url": format!("http://{}/private/data", addr) }))
+            .await
+            .unwrap();
+        assert!(result.output.contains("secret"));
+    }
+
     /// Tests the JsonParserTool parse operation.
     #[tokio::test]
     async fn test_json_parser_tool_parse() {
@@ -3990,7 +5807,7 @@ mod tests {
     /// Tests the ShellCommandTool with a safe command.
     #[tokio::test]
     async fn test_shell_command_tool_safe() {
-        let tool = ShellCommandTool;
+        let tool = ShellCommandTool::new();
         assert_eq!(tool.name(), "shell_command");
 
         // Test with a safe command
@@ -4005,7 +5822,7 @@ mod tests {
     /// Tests the ShellCommandTool with a blocked dangerous command.
     #[tokio::test]
     async fn test_shell_command_tool_blocked() {
-        let tool = ShellCommandTool;
+        let tool = ShellCommandTool::new();
 
         let args = json!({
             "command": "rm -rf /"
@@ -4015,6 +5832,32 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("Command blocked"));
     }
 
+    /// Tests that pipes run and that the policy's working directory and output limit apply.
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_command_tool_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("marker.txt"), "x").unwrap();
+        let tool = ShellCommandTool::with_policy(
+            ShellPolicy::default()
+                .working_dir(dir.path())
+                .max_output_bytes(5),
+        );
+
+        let result = tool
+            .execute(json!({"command": "ls | grep marker"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result.output.contains("marke\n[6 more bytes truncated]"));
+
+        let strict = ShellCommandTool::with_policy(ShellPolicy::read_only());
+        assert!(strict
+            .execute(json!({"command": "echo hi > out.txt"}))
+            .await
+            .is_err());
+    }
+
     /// Tests the HttpRequestTool with missing method.
     #[tokio::test]
     async fn test_http_request_tool_missing_method() {
@@ -4059,6 +5902,35 @@ mod tests {
         assert!(result.output.contains("OS:"));
     }
 
+    /// Tests the SystemInfoTool JSON output with process stats.
+    #[tokio::test]
+    async fn test_system_info_tool_json() {
+        let tool = SystemInfoTool;
+
+        let args = json!({
+            "category": "processes",
+            "output_format": "json",
+            "process_limit": 3,
+            "sort_by": "memory"
+        });
+        let result = tool.execute(args).await.unwrap();
+        assert!(result.success);
+        let report: Value = serde_json::from_str(&result.output).unwrap();
+        let processes = report["processes"].as_array().unwrap();
+        assert!(!processes.is_empty() && processes.len() <= 3);
+        assert!(processes[0]["pid"].is_u64());
+        assert!(processes[0]["memory_bytes"].is_u64());
+
+        let args = json!({ "category": "memory", "output_format": "json" });
+        let result = tool.execute(args).await.unwrap();
+        let report: Value = serde_json::from_str(&result.output).unwrap();
+        assert!(report["memory"]["total_bytes"].as_u64().unwrap() > 0);
+        assert!(report.get("os").is_none());
+
+        let args = json!({ "output_format": "xml" });
+        assert!(tool.execute(args).await.is_err());
+    }
+
     /// Tests the TextProcessorTool search operation.
     #[tokio::test]
     async fn test_text_processor_tool_search() {
@@ -0,0 +1,189 @@
+//! # Toolset Module
+//!
+//! This module groups tools into named toolsets, so an agent can be given
+//! everything it needs for one kind of work at once. The built-in toolsets
+//! bundle built-in tools; custom ones can hold any tools.
+//!
+//! Toolsets and single built-in tools can also be enabled from the config file:
+//!
+//! ```toml
+//! tools = ["filesystem", "web", "calculator"]
+//!
+//! [llm]
+//! model_name = "gpt-4o"
+//! ```
+
+use crate::error::{HeliosError, Result};
+use crate::tools::{builtin_tool, Tool, BUILTIN_TOOLS};
+
+/// The built-in toolsets and the built-in tools in each.
+pub const BUILTIN_TOOLSETS: &[(&str, &[&str])] = &[
+    (
+        "filesystem",
+        &[
+            "file_search",
+            "file_read",
+            "file_write",
+            "file_edit",
+            "file_list",
+        ],
+    ),
+    ("web", &["web_scraper", "http_request"]),
+    (
+        "data",
+        &["calculator", "json_parser", "text_processor", "timestamp"],
+    ),
+    ("system", &["shell_command", "system_info"]),
+];
+
+/// A named group of tools.
+///
+/// ```rust,no_run
+/// # use helios_engine::{Agent, Config, Toolset};
+/// # async fn example() -> helios_engine::Result<()> {
+/// let agent = Agent::builder("researcher")
+///     .config(Config::new_default())
+///     .toolset(Toolset::web())
+///     .toolset(Toolset::data())
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Toolset {
+    name: String,
+    tools: Vec<Box<dyn Tool>>,
+}
+
+impl Toolset {
+    /// Creates an empty toolset.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            tools: Vec::new(),
+        }
+    }
+
+    /// Adds a tool to the toolset.
+    pub fn tool(mut self, tool: Box<dyn Tool>) -> Self {
+        self.tools.push(tool);
+        self
+    }
+
+    /// Returns the built-in toolset called `name`, if there is one.
+    pub fn builtin(name: &str) -> Option<Self> {
+        let (name, tools) = BUILTIN_TOOLSETS
+            .iter()
+            .find(|(toolset, _)| *toolset == name)?;
+        Some(Self {
+            name: name.to_string(),
+            tools: tools
+                .iter()
+                .filter_map(|tool| builtin_tool(tool, None))
+                .collect(),
+        })
+    }
+
+    /// Tools for finding, reading, writing and editing files.
+    pub fn filesystem() -> Self {
+        Self::builtin("filesystem").unwrap_or_else(|| Self::new("filesystem"))
+    }
+
+    /// Tools for fetching web pages and calling HTTP APIs.
+    pub fn web() -> Self {
+        Self::builtin("web").unwrap_or_else(|| Self::new("web"))
+    }
+
+    /// Tools for arithmetic, JSON, text and dates.
+    pub fn data() -> Self {
+        Self::builtin("data").unwrap_or_else(|| Self::new("data"))
+    }
+
+    /// Tools for running shell commands and inspecting the machine.
+    ///
+    /// `shell_command` gets the default shell policy; see
+    /// [`ShellCommandTool::with_policy`](crate::ShellCommandTool::with_policy) to
+    /// restrict it further.
+    pub fn system() -> Self {
+        Self::builtin("system").unwrap_or_else(|| Self::new("system"))
+    }
+
+    /// Returns the name of the toolset.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the names of the tools in the toolset.
+    pub fn tool_names(&self) -> Vec<&str> {
+        self.tools.iter().map(|tool| tool.name()).collect()
+    }
+
+    /// Returns the tools in the toolset.
+    pub fn into_tools(self) -> Vec<Box<dyn Tool>> {
+        self.tools
+    }
+}
+
+/// Expands toolset names into the built-in tools they hold.
+///
+/// Each name is a built-in toolset or a built-in tool. The tools are returned
+/// in order, without duplicates; an unknown name is a configuration error.
+pub fn resolve_tool_names(names: &[String]) -> Result<Vec<String>> {
+    let mut resolved: Vec<String> = Vec::new();
+    for name in names {
+        let tools: Vec<&str> = match BUILTIN_TOOLSETS.iter().find(|(toolset, _)| toolset == name) {
+            Some((_, tools)) => tools.to_vec(),
+            None if BUILTIN_TOOLS.contains(&name.as_str()) => vec![name.as_str()],
+            None => {
+                let toolsets: Vec<&str> = BUILTIN_TOOLSETS.iter().map(|(name, _)| *name).collect();
+                return Err(HeliosError::ConfigError(format!(
+                    "Unknown tool or toolset '{}'. Toolsets: {}. Built-in tools: {}",
+                    name,
+                    toolsets.join(", "),
+                    BUILTIN_TOOLS.join(", ")
+                )));
+            }
+        };
+        for tool in tools {
+            if !resolved.iter().any(|known| known == tool) {
+                resolved.push(tool.to_string());
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the built-in toolsets and resolving names from the config.
+    #[test]
+    fn test_toolsets() {
+        for (name, tools) in BUILTIN_TOOLSETS {
+            assert!(tools.iter().all(|tool| BUILTIN_TOOLS.contains(tool)));
+            assert_eq!(Toolset::builtin(name).unwrap().tool_names(), *tools);
+        }
+        assert_eq!(Toolset::web().name(), "web");
+        assert!(Toolset::filesystem().tool_names().contains(&"file_read"));
+        assert!(Toolset::builtin("kitchen").is_none());
+
+        let names =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+        assert_eq!(
+            resolve_tool_names(&names(&["web", "calculator", "data"])).unwrap(),
+            names(&[
+                "web_scraper",
+                "http_request",
+                "calculator",
+                "json_parser",
+                "text_processor",
+                "timestamp"
+            ])
+        );
+        let error = resolve_tool_names(&names(&["kitchen"])).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Toolsets: filesystem, web, data, system"));
+    }
+}
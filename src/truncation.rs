@@ -0,0 +1,261 @@
+//! # Truncation Module
+//!
+//! This module limits how much of a tool's output reaches the conversation. A
+//! `file_read` of a large file or an `http_request` for a big page can return
+//! more text than the model's context holds; an [`OutputLimit`] set on the
+//! [`ToolRegistry`](crate::ToolRegistry) cuts such output down before it is
+//! added to the session, keeping its start, its start and end, or an LLM
+//! summary of it.
+
+use crate::chat::ChatMessage;
+use crate::error::Result;
+use crate::llm::LLMClient;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// The most characters of an output passed to a summarizer; longer outputs are
+/// cut to their start and end first.
+const MAX_SUMMARY_INPUT_CHARS: usize = 50_000;
+
+/// How output over the limit is shortened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationStrategy {
+    /// Keep the start of the output.
+    Head,
+    /// Keep the start and the end of the output, where errors and totals tend to be.
+    #[default]
+    HeadTail,
+    /// Replace the output with a summary from the registry's summarizer, falling
+    /// back to `HeadTail` without one or when summarizing fails.
+    Summarize,
+}
+
+/// The most output a tool may return, and how longer output is shortened.
+///
+/// ```rust,no_run
+/// # use helios_engine::{OutputLimit, ToolRegistry};
+/// let mut registry = ToolRegistry::new();
+/// registry.set_default_output_limit(OutputLimit::head_tail(8_000));
+/// registry.set_output_limit("file_read", OutputLimit::head(20_000));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputLimit {
+    /// The most characters kept, not counting the note saying what was cut.
+    pub max_chars: usize,
+    /// How longer output is shortened.
+    #[serde(default)]
+    pub strategy: TruncationStrategy,
+}
+
+impl OutputLimit {
+    /// Keeps the first `max_chars` characters.
+    pub fn head(max_chars: usize) -> Self {
+        Self {
+            max_chars,
+            strategy: TruncationStrategy::Head,
+        }
+    }
+
+    /// Keeps the first and last `max_chars / 2` characters.
+    pub fn head_tail(max_chars: usize) -> Self {
+        Self {
+            max_chars,
+            strategy: TruncationStrategy::HeadTail,
+        }
+    }
+
+    /// Summarizes output longer than `max_chars` characters.
+    pub fn summarize(max_chars: usize) -> Self {
+        Self {
+            max_chars,
+            strategy: TruncationStrategy::Summarize,
+        }
+    }
+
+    /// Returns whether `output` is over the limit.
+    pub fn is_exceeded_by(&self, output: &str) -> bool {
+        output.chars().nth(self.max_chars).is_some()
+    }
+
+    /// Shortens `output` to the limit without a summarizer.
+    ///
+    /// Output within the limit is returned unchanged. `Summarize` limits keep
+    /// the start and end, like `HeadTail`.
+    pub fn truncate(&self, output: &str) -> String {
+        if !self.is_exceeded_by(output) {
+            return output.to_string();
+        }
+        let total = output.chars().count();
+        match self.strategy {
+            TruncationStrategy::Head => format!(
+                "{}\n[... output truncated: showing the first {} of {} characters]",
+                take_head(output, self.max_chars),
+                self.max_chars,
+                total
+            ),
+            TruncationStrategy::HeadTail | TruncationStrategy::Summarize => {
+                let head = self.max_chars - self.max_chars / 2;
+                let tail = self.max_chars / 2;
+                format!(
+                    "{}\n[... {} characters omitted ...]\n{}",
+                    take_head(output, head),
+                    total - head - tail,
+                    take_tail(output, tail)
+                )
+            }
+        }
+    }
+
+    /// Shortens `output` of the tool called `tool` to the limit, summarizing it
+    /// with `summarizer` for `Summarize` limits.
+    pub async fn apply(
+        &self,
+        tool: &str,
+        output: &str,
+        summarizer: Option<&dyn OutputSummarizer>,
+    ) -> String {
+        if !self.is_exceeded_by(output) {
+            return output.to_string();
+        }
+        if let (TruncationStrategy::Summarize, Some(summarizer)) = (self.strategy, summarizer) {
+            let input = OutputLimit::head_tail(MAX_SUMMARY_INPUT_CHARS).truncate(output);
+            match summarizer.summarize(tool, &input, self.max_chars).await {
+                Ok(summary) => {
+                    return format!(
+                        "[Summary of {} characters of output]\n{}",
+                        output.chars().count(),
+                        take_head(summary.trim(), self.max_chars)
+                    )
+                }
+                Err(e) => tracing::warn!("Failed to summarize the output of '{}': {}", tool, e),
+            }
+        }
+        self.truncate(output)
+    }
+}
+
+/// Shortens `text` to its first `max` characters, ending it with `marker` if
+/// anything was cut.
+pub fn truncate_chars(text: &str, max: usize, marker: &str) -> String {
+    let head = take_head(text, max);
+    if head.len() == text.len() {
+        text.to_string()
+    } else {
+        format!("{}{}", head, marker)
+    }
+}
+
+/// Shortens `text` to its last `max` characters, starting it with `marker` if
+/// anything was cut.
+pub fn truncate_chars_tail(text: &str, max: usize, marker: &str) -> String {
+    let tail = take_tail(text, max);
+    if tail.len() == text.len() {
+        text.to_string()
+    } else {
+        format!("{}{}", marker, tail)
+    }
+}
+
+/// Returns the first `chars` characters of `text`.
+fn take_head(text: &str, chars: usize) -> &str {
+    match text.char_indices().nth(chars) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// Returns the last `chars` characters of `text`.
+fn take_tail(text: &str, chars: usize) -> &str {
+    if chars == 0 {
+        return "";
+    }
+    match text.char_indices().rev().nth(chars - 1) {
+        Some((start, _)) => &text[start..],
+        None => text,
+    }
+}
+
+/// Summarizes tool output that is over its [`OutputLimit`].
+///
+/// [`LLMClient`] is a summarizer, so a small, cheap model can condense the
+/// output of the agent's tools.
+#[async_trait]
+pub trait OutputSummarizer: Send + Sync {
+    /// Summarizes `output` of the tool called `tool` in about `max_chars` characters.
+    async fn summarize(&self, tool: &str, output: &str, max_chars: usize) -> Result<String>;
+}
+
+#[async_trait]
+impl OutputSummarizer for LLMClient {
+    async fn summarize(&self, tool: &str, output: &str, max_chars: usize) -> Result<String> {
+        let messages = vec![
+            ChatMessage::system(format!(
+                "Summarize the output of the '{}' tool in at most {} characters. Keep the facts, \
+                 names, numbers and errors another assistant needs to act on it, and leave out \
+                 the rest. Reply with the summary only.",
+                tool, max_chars
+            )),
+            ChatMessage::user(output),
+        ];
+        Ok(self.chat(messages, None, None, None, None).await?.content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LLMConfig;
+    use crate::mock::MockLLMProvider;
+
+    /// Tests that text is cut on character boundaries and marked only when cut.
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("héllo", 5, "..."), "héllo");
+        assert_eq!(truncate_chars("héllo wörld", 7, "..."), "héllo w...");
+        assert_eq!(truncate_chars("wörld", 2, "…"), "wö…");
+        assert_eq!(truncate_chars_tail("héllo", 5, "..."), "héllo");
+        assert_eq!(truncate_chars_tail("héllo wörld", 5, "..."), "...wörld");
+        assert_eq!(truncate_chars_tail("wörld", 0, "…"), "…");
+    }
+
+    /// Tests the head and head-and-tail strategies, including multi-byte text.
+    #[test]
+    fn test_output_limit_truncate() {
+        let output = "héllo wörld, and goodbye";
+        assert_eq!(OutputLimit::head(100).truncate(output), output);
+        assert_eq!(
+            OutputLimit::head(5).truncate(output),
+            "héllo\n[... output truncated: showing the first 5 of 24 characters]"
+        );
+        assert_eq!(
+            OutputLimit::head_tail(10).truncate(output),
+            "héllo\n[... 14 characters omitted ...]\nodbye"
+        );
+        assert!(!OutputLimit::head(24).is_exceeded_by(output));
+        assert!(OutputLimit::head(23).is_exceeded_by(output));
+    }
+
+    /// Tests summarizing with an LLM and falling back when it fails.
+    #[tokio::test]
+    async fn test_output_limit_summarize() {
+        let output = "line\n".repeat(100);
+        let mock = MockLLMProvider::new().with_response("  100 identical lines.  ");
+        let client = LLMClient::from_provider(mock.clone(), LLMConfig::default());
+        let limit = OutputLimit::summarize(40);
+
+        let summary = limit.apply("file_read", &output, Some(&client)).await;
+        assert_eq!(
+            summary,
+            "[Summary of 500 characters of output]\n100 identical lines."
+        );
+        let request = &mock.requests()[0];
+        assert!(request.messages[0].content.contains("'file_read' tool"));
+        assert_eq!(request.messages[1].content, output);
+
+        // The mock has no responses left, so the output is cut instead.
+        let fallback = limit.apply("file_read", &output, Some(&client)).await;
+        assert_eq!(fallback, limit.truncate(&output));
+        assert_eq!(limit.apply("file_read", "short", None).await, "short");
+    }
+}
@@ -0,0 +1,743 @@
+//! # TUI Module
+//!
+//! A full-screen chat interface for `helios-engine chat --tui`, built with ratatui.
+//! The conversation streams token by token into the main pane, with Markdown
+//! replies rendered as styled text. The side panes list the tools the agent runs,
+//! log activity as it happens (requests, provider warnings such as retries and
+//! fallbacks, aborts and errors), and track token usage, throughput and cost.
+//!
+//! Several conversations can be open at once: Ctrl+N starts a new one and Tab
+//! switches between them. Ctrl+Y copies the last reply to the clipboard through
+//! the terminal (OSC 52), which also works over SSH.
+
+use helios_engine::llm::{split_reasoning, StreamEvent};
+use helios_engine::{AbortHandle, Agent, ChatMessage, ChatSession, HeliosError, Role, TokenUsage};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use ratatui::Frame;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex;
+
+/// The number of lines kept in the activity pane, and of calls in the tools pane.
+const ACTIVITY_LIMIT: usize = 200;
+
+/// The number of lines of a tool result shown in the conversation.
+const TOOL_OUTPUT_LINES: usize = 6;
+
+/// Something the TUI reacts to.
+pub enum AppEvent {
+    /// A key press.
+    Key(KeyEvent),
+    /// The terminal was resized.
+    Resize,
+    /// Text or tool activity of the reply being streamed.
+    Stream(StreamEvent),
+    /// The reply finished, failed or was aborted, after the given time. On success
+    /// this carries the conversation including the reply and any tool calls.
+    Finished(helios_engine::Result<ChatSession>, Duration),
+    /// A log line, shown in the activity pane.
+    Log(String),
+}
+
+/// A `tracing` writer that forwards log lines to the activity pane.
+///
+/// Printing logs to the terminal would draw over the interface, so the CLI
+/// installs this writer instead of stdout when running the TUI.
+#[derive(Clone)]
+pub struct ActivityWriter {
+    sender: UnboundedSender<AppEvent>,
+}
+
+impl std::io::Write for ActivityWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let _ = self.sender.send(AppEvent::Log(line.trim().to_string()));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Creates the event channel of the TUI together with a log writer feeding it.
+pub fn channel() -> (
+    ActivityWriter,
+    UnboundedSender<AppEvent>,
+    UnboundedReceiver<AppEvent>,
+) {
+    let (sender, receiver) = unbounded_channel();
+    (
+        ActivityWriter {
+            sender: sender.clone(),
+        },
+        sender,
+        receiver,
+    )
+}
+
+/// A tool call made while answering, as shown in the tools pane.
+struct ToolActivity {
+    id: String,
+    name: String,
+    arguments: String,
+    /// Whether the call succeeded and its output, once it finished.
+    result: Option<(bool, String)>,
+}
+
+/// The state of the chat screen.
+struct App {
+    /// The open conversations; `current` is the one on screen.
+    sessions: Vec<ChatSession>,
+    current: usize,
+    model: String,
+    input: String,
+    /// The reply being streamed, without the model's reasoning.
+    streaming: Option<String>,
+    /// Whether the reply being streamed has started reasoning.
+    thinking: bool,
+    tools: VecDeque<ToolActivity>,
+    activity: VecDeque<String>,
+    /// How many lines the conversation is scrolled up from the bottom.
+    scroll_back: u16,
+    usage: TokenUsage,
+    cost: f64,
+    last_reply: Option<(u64, Duration)>,
+    quit: bool,
+}
+
+impl App {
+    fn log(&mut self, line: impl Into<String>) {
+        if self.activity.len() == ACTIVITY_LIMIT {
+            self.activity.pop_front();
+        }
+        self.activity.push_back(line.into());
+    }
+
+    fn session(&self) -> &ChatSession {
+        &self.sessions[self.current]
+    }
+
+    fn session_mut(&mut self) -> &mut ChatSession {
+        &mut self.sessions[self.current]
+    }
+
+    /// Aborts the reply being streamed, or quits when there is none.
+    fn abort_or_quit(&mut self, abort: &AbortHandle) {
+        if self.streaming.is_some() {
+            abort.abort();
+        } else {
+            self.quit = true;
+        }
+    }
+
+    /// Opens an empty conversation with the same system prompt and shows it.
+    fn new_session(&mut self) {
+        let mut session = self.session().clone();
+        session.clear();
+        session.metadata.clear();
+        self.sessions.push(session);
+        self.show_session(self.sessions.len() - 1);
+    }
+
+    /// Shows the conversation at `index`, wrapping around at either end.
+    fn show_session(&mut self, index: usize) {
+        self.current = index % self.sessions.len();
+        self.scroll_back = 0;
+        self.log(format!(
+            "Session {} of {}",
+            self.current + 1,
+            self.sessions.len()
+        ));
+    }
+
+    /// Copies the last reply of the current conversation to the clipboard.
+    fn copy_last_reply(&mut self) {
+        let reply = self
+            .session()
+            .messages
+            .iter()
+            .rev()
+            .find(|message| message.role == Role::Assistant && !message.content.is_empty())
+            .map(|message| split_reasoning(&message.content).1);
+        match reply {
+            Some(reply) => match copy_to_clipboard(&reply) {
+                Ok(()) => self.log(format!(
+                    "✓ Copied {} characters to the clipboard",
+                    reply.chars().count()
+                )),
+                Err(e) => self.log(format!("✗ Could not copy: {}", e)),
+            },
+            None => self.log("Nothing to copy yet"),
+        }
+    }
+
+    /// Renders the conversation, including the reply in progress, as styled lines.
+    fn conversation_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        let label = |text: &str, color: Color| {
+            Line::from(Span::styled(
+                text.to_string(),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ))
+        };
+        let dim = Style::default().fg(Color::DarkGray);
+
+        for message in &self.session().messages {
+            match message.role {
+                Role::User => {
+                    lines.push(label("You", Color::Cyan));
+                    lines.extend(
+                        message
+                            .content
+                            .lines()
+                            .map(|line| Line::from(line.to_string())),
+                    );
+                }
+                Role::Assistant => {
+                    lines.push(label("Assistant", Color::Green));
+                    if !message.content.is_empty() {
+                        lines.extend(markdown_lines(&split_reasoning(&message.content).1));
+                    }
+                    for call in message.tool_calls.iter().flatten() {
+                        lines.push(Line::from(Span::styled(
+                            format!("🔧 {}({})", call.function.name, call.function.arguments),
+                            dim,
+                        )));
+                    }
+                }
+                Role::Tool => {
+                    lines.push(label("Tool", Color::Yellow));
+                    let total = message.content.lines().count();
+                    lines.extend(
+                        message
+                            .content
+                            .lines()
+                            .take(TOOL_OUTPUT_LINES)
+                            .map(|line| Line::from(line.to_string())),
+                    );
+                    if total > TOOL_OUTPUT_LINES {
+                        lines.push(Line::from(Span::styled(
+                            format!("… {} more lines", total - TOOL_OUTPUT_LINES),
+                            dim,
+                        )));
+                    }
+                }
+                Role::System => continue,
+            }
+            lines.push(Line::default());
+        }
+        if let Some(reply) = &self.streaming {
+            lines.push(label("Assistant", Color::Green));
+            lines.extend(markdown_lines(&format!("{}▌", reply)));
+        }
+        lines
+    }
+}
+
+/// Runs the chat interface until the user quits.
+///
+/// The agent answers every message; its system prompt, tools and price table
+/// apply to all the conversations opened in the interface.
+pub async fn run(
+    agent: Agent,
+    model: String,
+    sender: UnboundedSender<AppEvent>,
+    mut events: UnboundedReceiver<AppEvent>,
+) -> helios_engine::Result<()> {
+    let abort = agent.abort_handle();
+    let session = agent.chat_session().clone();
+    let usage = agent.usage();
+    let cost = agent.cost();
+    let agent = Arc::new(Mutex::new(agent));
+    let stop_input = Arc::new(AtomicBool::new(false));
+    spawn_input_thread(sender.clone(), Arc::clone(&stop_input));
+
+    let mut app = App {
+        sessions: vec![session],
+        current: 0,
+        model,
+        input: String::new(),
+        streaming: None,
+        thinking: false,
+        tools: VecDeque::new(),
+        activity: VecDeque::new(),
+        scroll_back: 0,
+        usage,
+        cost,
+        last_reply: None,
+        quit: false,
+    };
+    app.log("Enter sends · Esc aborts or quits · Ctrl+L clears · PgUp/PgDn scroll");
+    app.log("Ctrl+N new session · Tab switches sessions · Ctrl+Y copies the last reply");
+
+    let mut terminal = ratatui::try_init()?;
+    let result = async {
+        while !app.quit {
+            terminal.draw(|frame| draw(frame, &app))?;
+            let Some(event) = events.recv().await else {
+                break;
+            };
+            handle_event(&mut app, event, &agent, &abort, &sender);
+        }
+        Ok::<_, HeliosError>(())
+    }
+    .await;
+
+    stop_input.store(true, Ordering::SeqCst);
+    abort.abort();
+    ratatui::restore();
+    result
+}
+
+/// Reads terminal events on a dedicated thread, since crossterm's reads block.
+fn spawn_input_thread(sender: UnboundedSender<AppEvent>, stop: Arc<AtomicBool>) {
+    std::thread::spawn(move || {
+        while !stop.load(Ordering::SeqCst) && !sender.is_closed() {
+            if !event::poll(Duration::from_millis(100)).unwrap_or(false) {
+                continue;
+            }
+            let event = match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => AppEvent::Key(key),
+                Ok(Event::Resize(..)) => AppEvent::Resize,
+                _ => continue,
+            };
+            if sender.send(event).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn handle_event(
+    app: &mut App,
+    event: AppEvent,
+    agent: &Arc<Mutex<Agent>>,
+    abort: &AbortHandle,
+    sender: &UnboundedSender<AppEvent>,
+) {
+    match event {
+        AppEvent::Key(key) => handle_key(app, key, agent, abort, sender),
+        AppEvent::Resize => {}
+        AppEvent::Stream(StreamEvent::Content(chunk)) => {
+            if let Some(reply) = app.streaming.as_mut() {
+                reply.push_str(&chunk);
+            }
+        }
+        AppEvent::Stream(StreamEvent::Reasoning(_)) => {
+            if !app.thinking {
+                app.thinking = true;
+                app.log("💭 Thinking");
+            }
+        }
+        AppEvent::Stream(StreamEvent::ToolCallDelta { .. }) => {}
+        AppEvent::Stream(StreamEvent::ToolStarted {
+            id,
+            name,
+            arguments,
+        }) => {
+            app.log(format!("🔧 Running {}", name));
+            if app.tools.len() == ACTIVITY_LIMIT {
+                app.tools.pop_front();
+            }
+            app.tools.push_back(ToolActivity {
+                id,
+                name,
+                arguments,
+                result: None,
+            });
+        }
+        AppEvent::Stream(StreamEvent::ToolFinished {
+            id,
+            success,
+            output,
+            ..
+        }) => {
+            if let Some(call) = app.tools.iter_mut().rev().find(|call| call.id == id) {
+                call.result = Some((success, output));
+            }
+        }
+        AppEvent::Finished(result, elapsed) => {
+            app.streaming = None;
+            match result {
+                Ok(session) => {
+                    *app.session_mut() = session;
+                    // The reply task releases the agent before reporting back.
+                    if let Ok(agent) = agent.try_lock() {
+                        let usage = agent.usage();
+                        let tokens = usage.completion_tokens - app.usage.completion_tokens;
+                        app.log(format!(
+                            "✓ {} tokens in {:.1}s",
+                            tokens,
+                            elapsed.as_secs_f64()
+                        ));
+                        app.last_reply = Some((tokens, elapsed));
+                        app.usage = usage;
+                        app.cost = agent.cost();
+                    }
+                }
+                Err(HeliosError::Aborted(_)) => {
+                    app.log("⏹ Response aborted");
+                    abort.reset();
+                    app.session_mut().messages.pop();
+                }
+                Err(e) => {
+                    app.log(format!("✗ {}", e));
+                    app.session_mut().messages.pop();
+                }
+            }
+        }
+        AppEvent::Log(line) => app.log(line),
+    }
+}
+
+fn handle_key(
+    app: &mut App,
+    key: KeyEvent,
+    agent: &Arc<Mutex<Agent>>,
+    abort: &AbortHandle,
+    sender: &UnboundedSender<AppEvent>,
+) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let idle = app.streaming.is_none();
+    match key.code {
+        KeyCode::Esc => app.abort_or_quit(abort),
+        KeyCode::Char('c') if ctrl => app.abort_or_quit(abort),
+        KeyCode::Char('d') if ctrl => app.quit = true,
+        KeyCode::Char('l') if ctrl && idle => {
+            app.session_mut().clear();
+            app.scroll_back = 0;
+            app.log("✓ Chat history cleared");
+        }
+        KeyCode::Char('n') if ctrl && idle => app.new_session(),
+        KeyCode::Char('y') if ctrl => app.copy_last_reply(),
+        KeyCode::Char(_) if ctrl => {}
+        KeyCode::Char(c) => app.input.push(c),
+        KeyCode::Backspace => {
+            app.input.pop();
+        }
+        KeyCode::Tab if idle && app.sessions.len() > 1 => app.show_session(app.current + 1),
+        KeyCode::BackTab if idle && app.sessions.len() > 1 => {
+            app.show_session(app.current + app.sessions.len() - 1)
+        }
+        KeyCode::Up => app.scroll_back = app.scroll_back.saturating_add(1),
+        KeyCode::Down => app.scroll_back = app.scroll_back.saturating_sub(1),
+        KeyCode::PageUp => app.scroll_back = app.scroll_back.saturating_add(10),
+        KeyCode::PageDown => app.scroll_back = app.scroll_back.saturating_sub(10),
+        KeyCode::End => app.scroll_back = 0,
+        KeyCode::Enter => {
+            let input = app.input.trim().to_string();
+            if input.is_empty() || !idle {
+                return;
+            }
+            app.input.clear();
+            app.scroll_back = 0;
+            let history = app.session().clone();
+            app.session_mut().add_user_message(&input);
+            app.streaming = Some(String::new());
+            app.thinking = false;
+            app.log(format!("→ Request to {}", app.model));
+            send(agent, sender, history, input);
+        }
+        _ => {}
+    }
+}
+
+/// Answers `input` in the background, continuing the conversation `history` and
+/// forwarding the streamed text and tool activity as events.
+fn send(
+    agent: &Arc<Mutex<Agent>>,
+    sender: &UnboundedSender<AppEvent>,
+    history: ChatSession,
+    input: String,
+) {
+    let agent = Arc::clone(agent);
+    let sender = sender.clone();
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let mut agent = agent.lock().await;
+        *agent.chat_session_mut() = history;
+        let events = sender.clone();
+        let result = agent
+            .chat_stream(input, move |event| {
+                let _ = events.send(AppEvent::Stream(event));
+            })
+            .await
+            .map(|_| agent.chat_session().clone());
+        drop(agent);
+        let _ = sender.send(AppEvent::Finished(result, started.elapsed()));
+    });
+}
+
+/// Writes `text` to the system clipboard through the terminal's OSC 52 sequence.
+fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+    use base64::Engine;
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    let mut stdout = std::io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", encoded)?;
+    stdout.flush()
+}
+
+/// Renders Markdown as styled lines: headings, lists, quotes, rules, fenced code
+/// blocks, and inline code, bold and italic text.
+fn markdown_lines(content: &str) -> Vec<Line<'static>> {
+    let code = Style::default().fg(Color::Magenta);
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            lines.push(Line::from(Span::styled(format!("  {}", line), code)));
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            lines.push(Line::from(Span::styled(
+                trimmed[level..].trim().to_string(),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            )));
+        } else if ["---", "***", "___"].contains(&trimmed.trim_end()) {
+            lines.push(Line::from(Span::styled(
+                "─".repeat(40),
+                Style::default().fg(Color::DarkGray),
+            )));
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            let style = Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::ITALIC);
+            let mut spans = vec![Span::styled("▎ ", style)];
+            spans.extend(inline_spans(quote.trim_start(), style));
+            lines.push(Line::from(spans));
+        } else if let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|bullet| trimmed.strip_prefix(bullet))
+        {
+            let mut spans = vec![Span::raw(format!("{}• ", indent))];
+            spans.extend(inline_spans(item, Style::default()));
+            lines.push(Line::from(spans));
+        } else {
+            lines.push(Line::from(inline_spans(line, Style::default())));
+        }
+    }
+    lines
+}
+
+/// Splits a line into spans, styling `code`, **bold** and *italic* text.
+///
+/// A marker only counts when it is closed on the same line and the text inside
+/// does not start with a space, so expressions like `2 * 3 * 4` stay as they are.
+fn inline_spans(text: &str, base: Style) -> Vec<Span<'static>> {
+    let markers = [
+        ("`", Style::default().fg(Color::Magenta)),
+        ("**", base.add_modifier(Modifier::BOLD)),
+        ("*", base.add_modifier(Modifier::ITALIC)),
+    ];
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    'scan: while let Some(c) = rest.chars().next() {
+        for (marker, style) in markers {
+            let Some(after) = rest.strip_prefix(marker) else {
+                continue;
+            };
+            if after.starts_with(char::is_whitespace) {
+                continue;
+            }
+            if let Some(end) = after.find(marker).filter(|&end| end > 0) {
+                if !plain.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut plain), base));
+                }
+                spans.push(Span::styled(after[..end].to_string(), style));
+                rest = &after[end + marker.len()..];
+                continue 'scan;
+            }
+        }
+        plain.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    if !plain.is_empty() || spans.is_empty() {
+        spans.push(Span::styled(plain, base));
+    }
+    spans
+}
+
+fn draw(frame: &mut Frame, app: &App) {
+    let [main, side] = Layout::horizontal([Constraint::Percentage(70), Constraint::Percentage(30)])
+        .areas(frame.area());
+    let [conversation, input] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).areas(main);
+    let [tools, activity, stats] = Layout::vertical([
+        Constraint::Percentage(40),
+        Constraint::Min(3),
+        Constraint::Length(9),
+    ])
+    .areas(side);
+
+    // Scroll so the newest text stays in view unless the user scrolled up.
+    let lines = app.conversation_lines();
+    let inner_width = conversation.width.saturating_sub(2).max(1) as usize;
+    let inner_height = conversation.height.saturating_sub(2) as usize;
+    let total: usize = lines
+        .iter()
+        .map(|line| (line.width().max(1) + inner_width - 1) / inner_width)
+        .sum();
+    let bottom = total.saturating_sub(inner_height);
+    let top = bottom.saturating_sub(app.scroll_back as usize);
+    let session = if app.sessions.len() > 1 {
+        format!(" {}/{}", app.current + 1, app.sessions.len())
+    } else {
+        String::new()
+    };
+    let title = if app.scroll_back > 0 {
+        format!(" Conversation{} (scrolled, End to follow) ", session)
+    } else {
+        format!(" Conversation{} ", session)
+    };
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .wrap(Wrap { trim: false })
+            .scroll((top.min(u16::MAX as usize) as u16, 0)),
+        conversation,
+    );
+
+    let prompt = if app.streaming.is_some() {
+        " Generating… (Esc to abort) "
+    } else {
+        " Message "
+    };
+    frame.render_widget(
+        Paragraph::new(app.input.as_str())
+            .block(Block::default().borders(Borders::ALL).title(prompt)),
+        input,
+    );
+    if app.streaming.is_none() {
+        let cursor_x = input.x + 1 + app.input.chars().count().min(u16::MAX as usize) as u16;
+        frame.set_cursor_position((cursor_x.min(input.right().saturating_sub(2)), input.y + 1));
+    }
+
+    // Each call takes two lines: the call itself and the first line of its result.
+    let tools_height = tools.height.saturating_sub(2) as usize;
+    let dim = Style::default().fg(Color::DarkGray);
+    let tool_lines: Vec<Line> = app
+        .tools
+        .iter()
+        .skip(app.tools.len().saturating_sub(tools_height / 2))
+        .flat_map(|call| {
+            let (icon, output) = match &call.result {
+                None => ("⏳", "running…".to_string()),
+                Some((success, output)) => (
+                    if *success { "✓" } else { "✗" },
+                    output.lines().next().unwrap_or("").to_string(),
+                ),
+            };
+            [
+                Line::from(format!("{} {} {}", icon, call.name, call.arguments)),
+                Line::from(Span::styled(format!("  {}", output), dim)),
+            ]
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(tool_lines).block(Block::default().borders(Borders::ALL).title(" Tools ")),
+        tools,
+    );
+
+    let activity_height = activity.height.saturating_sub(2) as usize;
+    let activity_lines: Vec<Line> = app
+        .activity
+        .iter()
+        .skip(app.activity.len().saturating_sub(activity_height))
+        .map(|line| Line::from(line.as_str()))
+        .collect();
+    frame.render_widget(
+        Paragraph::new(activity_lines)
+            .block(Block::default().borders(Borders::ALL).title(" Activity "))
+            .wrap(Wrap { trim: true }),
+        activity,
+    );
+
+    let throughput = match app.last_reply {
+        Some((tokens, elapsed)) if elapsed.as_secs_f64() > 0.0 => {
+            format!("{:.1} tok/s", tokens as f64 / elapsed.as_secs_f64())
+        }
+        _ => "–".to_string(),
+    };
+    let stats_lines = vec![
+        Line::from(format!("Model:      {}", app.model)),
+        Line::from(format!("Messages:   {}", app.session().messages.len())),
+        Line::from(format!("Requests:   {}", app.usage.requests)),
+        Line::from(format!("Prompt:     {} tokens", app.usage.prompt_tokens)),
+        Line::from(format!(
+            "Completion: {} tokens",
+            app.usage.completion_tokens
+        )),
+        Line::from(format!("Last reply: {}", throughput)),
+        Line::from(format!("Cost:       ${:.4}", app.cost)),
+    ];
+    frame.render_widget(
+        Paragraph::new(stats_lines).block(Block::default().borders(Borders::ALL).title(" Stats ")),
+        stats,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    /// Tests that Markdown block syntax is turned into styled lines.
+    #[test]
+    fn test_markdown_lines() {
+        let lines = markdown_lines("## Steps\n- first\n  * nested\n```\nlet *x* = 1;\n```\n> note");
+        let rendered: Vec<String> = lines.iter().map(text).collect();
+        assert_eq!(
+            rendered,
+            ["Steps", "• first", "  • nested", "  let *x* = 1;", "▎ note"]
+        );
+        assert!(lines[0].spans[0]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+    }
+
+    /// Tests inline code, bold and italic spans, and that lone asterisks are left alone.
+    #[test]
+    fn test_inline_spans() {
+        let spans = inline_spans("use `cargo` **now**, *please*", Style::default());
+        let parts: Vec<&str> = spans.iter().map(|span| span.content.as_ref()).collect();
+        assert_eq!(parts, ["use ", "cargo", " ", "now", ", ", "please"]);
+        assert!(spans[3].style.add_modifier.contains(Modifier::BOLD));
+        assert!(spans[5].style.add_modifier.contains(Modifier::ITALIC));
+
+        let spans = inline_spans("2 * 3 * 4", Style::default());
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].content, "2 * 3 * 4");
+    }
+}
@@ -0,0 +1,328 @@
+//! # Usage Module
+//!
+//! This module tracks token usage reported by LLM providers and turns it into a
+//! dollar cost. Every `LLMClient` records the usage of each call in a shared
+//! `UsageTracker`, which agents aggregate per session. A `PriceTable` maps model
+//! names to per-million-token prices.
+
+use crate::error::{HeliosError, Result};
+use crate::llm::Usage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Aggregated token usage over one or more LLM calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    /// The number of prompt (input) tokens.
+    pub prompt_tokens: u64,
+    /// The number of completion (output) tokens.
+    pub completion_tokens: u64,
+    /// The total number of tokens.
+    pub total_tokens: u64,
+    /// The number of LLM calls that contributed to this usage.
+    pub requests: u64,
+}
+
+impl TokenUsage {
+    /// Adds the usage reported for a single call.
+    pub fn record(&mut self, usage: &Usage) {
+        self.prompt_tokens += u64::from(usage.prompt_tokens);
+        self.completion_tokens += u64::from(usage.completion_tokens);
+        self.total_tokens += u64::from(usage.total_tokens);
+        self.requests += 1;
+    }
+
+    /// Returns the usage accumulated since `earlier` was captured.
+    pub fn since(&self, earlier: &TokenUsage) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self.prompt_tokens.saturating_sub(earlier.prompt_tokens),
+            completion_tokens: self
+                .completion_tokens
+                .saturating_sub(earlier.completion_tokens),
+            total_tokens: self.total_tokens.saturating_sub(earlier.total_tokens),
+            requests: self.requests.saturating_sub(earlier.requests),
+        }
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        self.requests += other.requests;
+    }
+}
+
+/// The price of a model, in dollars per million tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    /// The price per million prompt tokens.
+    pub input_per_million: f64,
+    /// The price per million completion tokens.
+    pub output_per_million: f64,
+}
+
+impl ModelPrice {
+    /// Computes the cost of the given usage at this price.
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.prompt_tokens as f64 * self.input_per_million
+            + usage.completion_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// A table of model prices used to compute the dollar cost of token usage.
+///
+/// Prices can be set in code or loaded from a TOML file of the form:
+///
+/// ```toml
+/// [models."gpt-4o-mini"]
+/// input_per_million = 0.15
+/// output_per_million = 0.6
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriceTable {
+    /// Prices keyed by model name.
+    #[serde(default)]
+    pub models: HashMap<String, ModelPrice>,
+}
+
+impl PriceTable {
+    /// Creates an empty price table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the price of a model, in dollars per million prompt and completion tokens.
+    pub fn with_price(
+        mut self,
+        model: impl Into<String>,
+        input_per_million: f64,
+        output_per_million: f64,
+    ) -> Self {
+        self.models.insert(
+            model.into(),
+            ModelPrice {
+                input_per_million,
+                output_per_million,
+            },
+        );
+        self
+    }
+
+    /// Loads a price table from a TOML file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            HeliosError::ConfigError(format!(
+                "Failed to read price table '{}': {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Returns the price of a model, if known.
+    pub fn price(&self, model: &str) -> Option<&ModelPrice> {
+        self.models.get(model)
+    }
+
+    /// Computes the cost of `usage` for `model`. Unknown models cost nothing.
+    pub fn cost(&self, model: &str, usage: &TokenUsage) -> f64 {
+        self.price(model).map(|p| p.cost(usage)).unwrap_or(0.0)
+    }
+
+    /// Computes the total cost of usage broken down by model.
+    pub fn total_cost(&self, by_model: &HashMap<String, TokenUsage>) -> f64 {
+        by_model
+            .iter()
+            .map(|(model, usage)| self.cost(model, usage))
+            .sum()
+    }
+}
+
+/// Limits on how many tokens and dollars an agent may spend in one session.
+///
+/// The cost limit is computed with the agent's `PriceTable`, so it only counts
+/// models that have a price.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Budget {
+    /// The most the session may cost, in dollars.
+    pub max_cost: Option<f64>,
+    /// The most tokens the session may use, prompt and completion combined.
+    pub max_tokens: Option<u64>,
+}
+
+impl Budget {
+    /// Returns whether any limit is set.
+    pub fn is_limited(&self) -> bool {
+        self.max_cost.is_some() || self.max_tokens.is_some()
+    }
+
+    /// Fails with `HeliosError::BudgetExceeded` if `usage` or `cost` has reached a limit.
+    pub fn check(&self, usage: &TokenUsage, cost: f64) -> Result<()> {
+        if let Some(max_tokens) = self.max_tokens {
+            if usage.total_tokens >= max_tokens {
+                return Err(HeliosError::BudgetExceeded(format!(
+                    "used {} of {} tokens",
+                    usage.total_tokens, max_tokens
+                )));
+            }
+        }
+        if let Some(max_cost) = self.max_cost {
+            if cost >= max_cost {
+                return Err(HeliosError::BudgetExceeded(format!(
+                    "spent ${:.4} of ${:.4}",
+                    cost, max_cost
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A shared, thread-safe record of token usage broken down by model.
+///
+/// Cloning a tracker yields a handle to the same underlying counters, so a tracker
+/// can be handed to other components that need to observe a client's usage.
+#[derive(Debug, Clone, Default)]
+pub struct UsageTracker {
+    inner: Arc<Mutex<HashMap<String, TokenUsage>>>,
+}
+
+impl UsageTracker {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the usage of a single call to `model`.
+    pub fn record(&self, model: &str, usage: &Usage) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.entry(model.to_string()).or_default().record(usage);
+    }
+
+    /// Returns the usage summed over all models.
+    pub fn total(&self) -> TokenUsage {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut total = TokenUsage::default();
+        for usage in inner.values() {
+            total += *usage;
+        }
+        total
+    }
+
+    /// Returns a snapshot of the usage per model.
+    pub fn by_model(&self) -> HashMap<String, TokenUsage> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Computes the total dollar cost of the recorded usage.
+    pub fn cost(&self, prices: &PriceTable) -> f64 {
+        prices.total_cost(&self.by_model())
+    }
+
+    /// Clears all recorded usage.
+    pub fn reset(&self) {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+/// Estimates the number of tokens in a text, for providers that do not report usage.
+///
+/// Uses the common approximation of roughly four characters per token.
+pub fn estimate_tokens(text: &str) -> u32 {
+    (text.len() as f32 / 4.0).ceil() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt: u32, completion: u32) -> Usage {
+        Usage {
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            total_tokens: prompt + completion,
+        }
+    }
+
+    /// Tests that the tracker aggregates usage per model and in total.
+    #[test]
+    fn test_tracker_aggregates_by_model() {
+        let tracker = UsageTracker::new();
+        tracker.record("a", &usage(10, 5));
+        tracker.record("a", &usage(20, 10));
+        tracker.clone().record("b", &usage(1, 1));
+
+        let by_model = tracker.by_model();
+        assert_eq!(by_model["a"].prompt_tokens, 30);
+        assert_eq!(by_model["a"].requests, 2);
+        assert_eq!(tracker.total().total_tokens, 47);
+        assert_eq!(tracker.total().requests, 3);
+
+        tracker.reset();
+        assert_eq!(tracker.total(), TokenUsage::default());
+    }
+
+    /// Tests that a budget fails once either limit is reached.
+    #[test]
+    fn test_budget_check() {
+        let mut used = TokenUsage::default();
+        used.record(&usage(600, 400));
+
+        assert!(!Budget::default().is_limited());
+        assert!(Budget::default().check(&used, 100.0).is_ok());
+
+        let tokens = Budget {
+            max_tokens: Some(1_000),
+            ..Budget::default()
+        };
+        let err = tokens.check(&used, 0.0).unwrap_err();
+        assert_eq!(err.to_string(), "Budget exceeded: used 1000 of 1000 tokens");
+        assert!(tokens.check(&TokenUsage::default(), 0.0).is_ok());
+
+        let cost = Budget {
+            max_cost: Some(0.5),
+            ..Budget::default()
+        };
+        assert!(cost.check(&used, 0.49).is_ok());
+        assert!(matches!(
+            cost.check(&used, 0.5),
+            Err(HeliosError::BudgetExceeded(_))
+        ));
+    }
+
+    /// Tests cost computation from a price table.
+    #[test]
+    fn test_price_table_cost() {
+        let prices = PriceTable::new().with_price("gpt", 2.0, 8.0);
+        let tracker = UsageTracker::new();
+        tracker.record("gpt", &usage(1_000_000, 500_000));
+        tracker.record("unknown", &usage(1_000, 1_000));
+
+        assert!((tracker.cost(&prices) - 6.0).abs() < 1e-9);
+
+        let parsed: PriceTable =
+            toml::from_str("[models.gpt]\ninput_per_million = 1.0\noutput_per_million = 3.0\n")
+                .unwrap();
+        assert_eq!(parsed.price("gpt").unwrap().output_per_million, 3.0);
+    }
+
+    /// Tests the difference between two usage snapshots.
+    #[test]
+    fn test_token_usage_since() {
+        let mut before = TokenUsage::default();
+        before.record(&usage(3, 2));
+        let mut after = before;
+        after.record(&usage(7, 1));
+
+        let delta = after.since(&before);
+        assert_eq!(delta.prompt_tokens, 7);
+        assert_eq!(delta.completion_tokens, 1);
+        assert_eq!(delta.requests, 1);
+    }
+}
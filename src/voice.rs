@@ -0,0 +1,750 @@
+//! # Voice Module
+//!
+//! This module adds speech to Helios: `SpeechToText` turns recorded audio into a
+//! prompt and `TextToSpeech` reads replies aloud. Each has an implementation for
+//! OpenAI-compatible audio endpoints and one that runs locally (whisper.cpp for
+//! transcription, any command-line synthesizer such as Piper or espeak-ng for speech).
+//!
+//! The CLI's `chat --voice` mode reads a `[voice]` section from the config file,
+//! records from the microphone and plays audio through external commands
+//! (`arecord` and `ffplay` by default):
+//!
+//! ```toml
+//! [voice]
+//! record_seconds = 5
+//!
+//! [voice.stt]
+//! provider = "whisper_cpp"
+//! model_path = "models/ggml-base.en.bin"
+//!
+//! [voice.tts]
+//! provider = "openai"
+//! voice = "nova"
+//! ```
+
+use crate::config::LLMConfig;
+use crate::error::{HeliosError, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Converts recorded speech into text.
+#[async_trait]
+pub trait SpeechToText: Send + Sync {
+    /// Transcribes the audio file at `audio`.
+    async fn transcribe(&self, audio: &Path) -> Result<String>;
+}
+
+/// Synthesized speech and the format it is encoded in.
+#[derive(Debug, Clone)]
+pub struct SpeechAudio {
+    /// The encoded audio.
+    pub data: Vec<u8>,
+    /// The file extension of the encoding, such as `mp3` or `wav`.
+    pub format: String,
+}
+
+/// Converts text into speech.
+#[async_trait]
+pub trait TextToSpeech: Send + Sync {
+    /// Synthesizes `text` as audio.
+    async fn synthesize(&self, text: &str) -> Result<SpeechAudio>;
+}
+
+/// Reads the error body of a failed audio API response.
+async fn api_error(response: reqwest::Response, what: &str) -> HeliosError {
+    let status = response.status();
+    let error_text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+    HeliosError::ToolError(format!("{} failed ({}): {}", what, status, error_text))
+}
+
+// ============================================================================
+// OpenAI-compatible Audio APIs
+// ============================================================================
+
+/// Transcription through an OpenAI-compatible `/audio/transcriptions` endpoint.
+///
+/// Works with OpenAI's Whisper and with local servers that expose the same API,
+/// such as whisper.cpp's server or faster-whisper-server.
+pub struct OpenAISpeechToText {
+    base_url: String,
+    api_key: String,
+    model: String,
+    language: Option<String>,
+    client: Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+}
+
+impl OpenAISpeechToText {
+    /// Creates a transcriber for the API at `base_url` (for example `https://api.openai.com/v1`).
+    pub fn new(base_url: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            api_key: api_key.into(),
+            model: "whisper-1".to_string(),
+            language: None,
+            client: Client::new(),
+        }
+    }
+
+    /// Sets the transcription model. Defaults to `whisper-1`.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Sets the spoken language as an ISO-639-1 code, which improves accuracy and latency.
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+}
+
+#[async_trait]
+impl SpeechToText for OpenAISpeechToText {
+    async fn transcribe(&self, audio: &Path) -> Result<String> {
+        let data = tokio::fs::read(audio).await?;
+        let file_name = audio
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "audio.wav".to_string());
+
+        let mut form = reqwest::multipart::Form::new()
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(data).file_name(file_name),
+            )
+            .text("model", self.model.clone())
+            .text("response_format", "json");
+        if let Some(language) = &self.language {
+            form = form.text("language", language.clone());
+        }
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/audio/transcriptions",
+                self.base_url.trim_end_matches('/')
+            ))
+            .bearer_auth(&self.api_key)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Transcription API error: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(api_error(response, "Transcription").await);
+        }
+
+        let transcription: TranscriptionResponse = response.json().await.map_err(|e| {
+            HeliosError::ToolError(format!("Failed to parse transcription response: {}", e))
+        })?;
+        Ok(transcription.text.trim().to_string())
+    }
+}
+
+/// Speech synthesis through an OpenAI-compatible `/audio/speech` endpoint.
+pub struct OpenAITextToSpeech {
+    base_url: String,
+    api_key: String,
+    model: String,
+    voice: String,
+    format: String,
+    client: Client,
+}
+
+#[derive(Debug, Serialize)]
+struct SpeechRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+    voice: &'a str,
+    response_format: &'a str,
+}
+
+impl OpenAITextToSpeech {
+    /// Creates a synthesizer for the API at `base_url` (for example `https://api.openai.com/v1`).
+    pub fn new(base_url: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            api_key: api_key.into(),
+            model: "tts-1".to_string(),
+            voice: "alloy".to_string(),
+            format: "mp3".to_string(),
+            client: Client::new(),
+        }
+    }
+
+    /// Sets the speech model. Defaults to `tts-1`.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Sets the voice. Defaults to `alloy`.
+    pub fn with_voice(mut self, voice: impl Into<String>) -> Self {
+        self.voice = voice.into();
+        self
+    }
+
+    /// Sets the audio format, such as `mp3`, `wav` or `opus`. Defaults to `mp3`.
+    pub fn with_format(mut self, format: impl Into<String>) -> Self {
+        self.format = format.into();
+        self
+    }
+}
+
+#[async_trait]
+impl TextToSpeech for OpenAITextToSpeech {
+    async fn synthesize(&self, text: &str) -> Result<SpeechAudio> {
+        let request = SpeechRequest {
+            model: &self.model,
+            input: text,
+            voice: &self.voice,
+            response_format: &self.format,
+        };
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/audio/speech",
+                self.base_url.trim_end_matches('/')
+            ))
+            .bearer_auth(&self.api_key)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Speech API error: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(api_error(response, "Speech synthesis").await);
+        }
+
+        let data = response
+            .bytes()
+            .await
+            .map_err(|e| HeliosError::ToolError(format!("Failed to read speech audio: {}", e)))?;
+        Ok(SpeechAudio {
+            data: data.to_vec(),
+            format: self.format.clone(),
+        })
+    }
+}
+
+// ============================================================================
+// Local Engines
+// ============================================================================
+
+/// Offline transcription with the whisper.cpp command-line program.
+///
+/// whisper.cpp expects 16 kHz mono WAV input, which is what the CLI records by default.
+pub struct WhisperCppSpeechToText {
+    binary: String,
+    model_path: String,
+    language: Option<String>,
+    threads: Option<usize>,
+}
+
+impl WhisperCppSpeechToText {
+    /// Creates a transcriber that uses the ggml model at `model_path`.
+    pub fn new(model_path: impl Into<String>) -> Self {
+        Self {
+            binary: "whisper-cli".to_string(),
+            model_path: model_path.into(),
+            language: None,
+            threads: None,
+        }
+    }
+
+    /// Sets the whisper.cpp executable. Defaults to `whisper-cli` on the `PATH`.
+    pub fn with_binary(mut self, binary: impl Into<String>) -> Self {
+        self.binary = binary.into();
+        self
+    }
+
+    /// Sets the spoken language, such as `en`. whisper.cpp detects it otherwise.
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Sets the number of threads whisper.cpp uses.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+}
+
+#[async_trait]
+impl SpeechToText for WhisperCppSpeechToText {
+    async fn transcribe(&self, audio: &Path) -> Result<String> {
+        let mut command = Command::new(&self.binary);
+        command
+            .arg("-m")
+            .arg(&self.model_path)
+            .arg("-f")
+            .arg(audio)
+            .args(["-nt", "-np"])
+            .stdin(Stdio::null());
+        if let Some(language) = &self.language {
+            command.arg("-l").arg(language);
+        }
+        if let Some(threads) = self.threads {
+            command.arg("-t").arg(threads.to_string());
+        }
+
+        let output = command.output().await.map_err(|e| {
+            HeliosError::ToolError(format!("Failed to run '{}': {}", self.binary, e))
+        })?;
+        if !output.status.success() {
+            return Err(HeliosError::ToolError(format!(
+                "'{}' failed: {}",
+                self.binary,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+}
+
+/// Replaces `{name}` placeholders in command arguments.
+fn substitute(args: &[String], replacements: &[(&str, &str)]) -> Vec<String> {
+    args.iter()
+        .map(|arg| {
+            replacements.iter().fold(arg.clone(), |arg, (name, value)| {
+                arg.replace(&format!("{{{}}}", name), value)
+            })
+        })
+        .collect()
+}
+
+/// Runs a command given as program and arguments, optionally feeding `stdin`.
+async fn run_command(command: &[String], stdin: Option<&str>) -> Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| HeliosError::ConfigError("Voice command is empty".to_string()))?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| HeliosError::ToolError(format!("Failed to run '{}': {}", program, e)))?;
+    if let (Some(text), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(text.as_bytes()).await?;
+    }
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(HeliosError::ToolError(format!(
+            "'{}' failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Offline speech synthesis with any command-line synthesizer.
+///
+/// The text is written to the command's standard input and `{output}` in the
+/// arguments is replaced with the file the command should write. For example,
+/// Piper: `["piper", "--model", "en_US-lessac-medium.onnx", "--output_file", "{output}"]`,
+/// or espeak-ng: `["espeak-ng", "--stdin", "-w", "{output}"]`.
+pub struct CommandTextToSpeech {
+    command: Vec<String>,
+    format: String,
+}
+
+impl CommandTextToSpeech {
+    /// Creates a synthesizer that runs `command` and produces audio in `format` (such as `wav`).
+    pub fn new(command: Vec<String>, format: impl Into<String>) -> Self {
+        Self {
+            command,
+            format: format.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl TextToSpeech for CommandTextToSpeech {
+    async fn synthesize(&self, text: &str) -> Result<SpeechAudio> {
+        let output = tempfile::Builder::new()
+            .suffix(&format!(".{}", self.format))
+            .tempfile()?
+            .into_temp_path();
+        let path = output.to_string_lossy().into_owned();
+
+        run_command(&substitute(&self.command, &[("output", &path)]), Some(text)).await?;
+        Ok(SpeechAudio {
+            data: tokio::fs::read(&output).await?,
+            format: self.format.clone(),
+        })
+    }
+}
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// The speech-to-text engine used by the CLI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "provider")]
+pub enum SttConfig {
+    /// An OpenAI-compatible transcription API.
+    #[serde(rename = "openai")]
+    OpenAI {
+        /// The API base URL. Defaults to the `[llm]` base URL.
+        #[serde(default)]
+        base_url: Option<String>,
+        /// The API key. Defaults to the `[llm]` API key.
+        #[serde(default)]
+        api_key: Option<String>,
+        /// The transcription model.
+        #[serde(default = "default_stt_model")]
+        model: String,
+        /// The spoken language as an ISO-639-1 code.
+        #[serde(default)]
+        language: Option<String>,
+    },
+    /// The whisper.cpp command-line program.
+    #[serde(rename = "whisper_cpp")]
+    WhisperCpp {
+        /// The whisper.cpp executable.
+        #[serde(default = "default_whisper_binary")]
+        binary: String,
+        /// The path to the ggml model file.
+        model_path: String,
+        /// The spoken language, such as `en`.
+        #[serde(default)]
+        language: Option<String>,
+        /// The number of threads to use.
+        #[serde(default)]
+        threads: Option<usize>,
+    },
+}
+
+impl Default for SttConfig {
+    fn default() -> Self {
+        SttConfig::OpenAI {
+            base_url: None,
+            api_key: None,
+            model: default_stt_model(),
+            language: None,
+        }
+    }
+}
+
+/// The text-to-speech engine used by the CLI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "provider")]
+pub enum TtsConfig {
+    /// An OpenAI-compatible speech API.
+    #[serde(rename = "openai")]
+    OpenAI {
+        /// The API base URL. Defaults to the `[llm]` base URL.
+        #[serde(default)]
+        base_url: Option<String>,
+        /// The API key. Defaults to the `[llm]` API key.
+        #[serde(default)]
+        api_key: Option<String>,
+        /// The speech model.
+        #[serde(default = "default_tts_model")]
+        model: String,
+        /// The voice.
+        #[serde(default = "default_tts_voice")]
+        voice: String,
+        /// The audio format.
+        #[serde(default = "default_tts_format")]
+        format: String,
+    },
+    /// A command-line synthesizer; see [`CommandTextToSpeech`].
+    #[serde(rename = "command")]
+    Command {
+        /// The program and its arguments, with `{output}` for the output file.
+        command: Vec<String>,
+        /// The audio format the command writes.
+        #[serde(default = "default_command_tts_format")]
+        format: String,
+    },
+}
+
+fn default_stt_model() -> String {
+    "whisper-1".to_string()
+}
+
+fn default_whisper_binary() -> String {
+    "whisper-cli".to_string()
+}
+
+fn default_tts_model() -> String {
+    "tts-1".to_string()
+}
+
+fn default_tts_voice() -> String {
+    "alloy".to_string()
+}
+
+fn default_tts_format() -> String {
+    "mp3".to_string()
+}
+
+fn default_command_tts_format() -> String {
+    "wav".to_string()
+}
+
+fn default_record_seconds() -> u32 {
+    5
+}
+
+fn default_record_command() -> Vec<String> {
+    [
+        "arecord",
+        "-q",
+        "-f",
+        "S16_LE",
+        "-r",
+        "16000",
+        "-c",
+        "1",
+        "-d",
+        "{seconds}",
+        "{output}",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+fn default_play_command() -> Vec<String> {
+    [
+        "ffplay",
+        "-nodisp",
+        "-autoexit",
+        "-loglevel",
+        "quiet",
+        "{input}",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// The `[voice]` section of the config file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceConfig {
+    /// How many seconds each recording lasts.
+    #[serde(default = "default_record_seconds")]
+    pub record_seconds: u32,
+    /// The recorder command, with `{output}` for the WAV file and `{seconds}` for the duration.
+    #[serde(default = "default_record_command")]
+    pub record_command: Vec<String>,
+    /// The player command, with `{input}` for the audio file.
+    #[serde(default = "default_play_command")]
+    pub play_command: Vec<String>,
+    /// The speech-to-text engine.
+    #[serde(default)]
+    pub stt: SttConfig,
+    /// The text-to-speech engine. Replies are only printed when unset.
+    #[serde(default)]
+    pub tts: Option<TtsConfig>,
+}
+
+impl Default for VoiceConfig {
+    fn default() -> Self {
+        Self {
+            record_seconds: default_record_seconds(),
+            record_command: default_record_command(),
+            play_command: default_play_command(),
+            stt: SttConfig::default(),
+            tts: None,
+        }
+    }
+}
+
+impl VoiceConfig {
+    /// Reads the `[voice]` section of a config file, if it has one.
+    pub fn from_config_file<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+        #[derive(Deserialize)]
+        struct VoiceSection {
+            #[serde(default)]
+            voice: Option<VoiceConfig>,
+        }
+
+        let section: VoiceSection = crate::config::read_config_file(path.as_ref())?.try_into()?;
+        Ok(section.voice)
+    }
+
+    /// Builds the configured speech-to-text engine. API settings default to those of `llm`.
+    pub fn speech_to_text(&self, llm: &LLMConfig) -> Box<dyn SpeechToText> {
+        match &self.stt {
+            SttConfig::OpenAI {
+                base_url,
+                api_key,
+                model,
+                language,
+            } => {
+                let mut stt = OpenAISpeechToText::new(
+                    base_url.as_deref().unwrap_or(&llm.base_url),
+                    api_key.as_deref().unwrap_or(&llm.api_key),
+                )
+                .with_model(model);
+                if let Some(language) = language {
+                    stt = stt.with_language(language);
+                }
+                Box::new(stt)
+            }
+            SttConfig::WhisperCpp {
+                binary,
+                model_path,
+                language,
+                threads,
+            } => {
+                let mut stt = WhisperCppSpeechToText::new(model_path).with_binary(binary);
+                if let Some(language) = language {
+                    stt = stt.with_language(language);
+                }
+                if let Some(threads) = threads {
+                    stt = stt.with_threads(*threads);
+                }
+                Box::new(stt)
+            }
+        }
+    }
+
+    /// Builds the configured text-to-speech engine, if any. API settings default to those of `llm`.
+    pub fn text_to_speech(&self, llm: &LLMConfig) -> Option<Box<dyn TextToSpeech>> {
+        let tts: Box<dyn TextToSpeech> = match self.tts.as_ref()? {
+            TtsConfig::OpenAI {
+                base_url,
+                api_key,
+                model,
+                voice,
+                format,
+            } => Box::new(
+                OpenAITextToSpeech::new(
+                    base_url.as_deref().unwrap_or(&llm.base_url),
+                    api_key.as_deref().unwrap_or(&llm.api_key),
+                )
+                .with_model(model)
+                .with_voice(voice)
+                .with_format(format),
+            ),
+            TtsConfig::Command { command, format } => {
+                Box::new(CommandTextToSpeech::new(command.clone(), format))
+            }
+        };
+        Some(tts)
+    }
+
+    /// Records from the microphone into the WAV file at `output`.
+    pub async fn record(&self, output: &Path) -> Result<()> {
+        let seconds = self.record_seconds.to_string();
+        let output = output.to_string_lossy();
+        let command = substitute(
+            &self.record_command,
+            &[("output", &output), ("seconds", &seconds)],
+        );
+        run_command(&command, None).await
+    }
+
+    /// Plays synthesized speech and waits for it to finish.
+    pub async fn play(&self, audio: &SpeechAudio) -> Result<()> {
+        let file = tempfile::Builder::new()
+            .suffix(&format!(".{}", audio.format))
+            .tempfile()?
+            .into_temp_path();
+        tokio::fs::write(&file, &audio.data).await?;
+        let input = file.to_string_lossy().into_owned();
+        run_command(&substitute(&self.play_command, &[("input", &input)]), None).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests parsing a `[voice]` section and falling back to the LLM settings.
+    #[test]
+    fn test_voice_config_from_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            r#"
+[llm]
+model_name = "gpt-4o-mini"
+base_url = "http://localhost:8080/v1"
+api_key = "key"
+temperature = 0.7
+max_tokens = 100
+
+[voice]
+record_seconds = 8
+
+[voice.stt]
+provider = "whisper_cpp"
+model_path = "ggml-base.en.bin"
+language = "en"
+
+[voice.tts]
+provider = "command"
+command = ["espeak-ng", "--stdin", "-w", "{output}"]
+"#,
+        )
+        .unwrap();
+
+        let voice = VoiceConfig::from_config_file(&path).unwrap().unwrap();
+        assert_eq!(voice.record_seconds, 8);
+        assert_eq!(voice.record_command[0], "arecord");
+        assert!(matches!(
+            voice.stt,
+            SttConfig::WhisperCpp { ref binary, .. } if binary == "whisper-cli"
+        ));
+        assert!(matches!(
+            voice.tts,
+            Some(TtsConfig::Command { ref format, .. }) if format == "wav"
+        ));
+
+        std::fs::write(&path, "[llm]\nmodel_name = \"m\"\nbase_url = \"u\"\napi_key = \"k\"\ntemperature = 0.7\nmax_tokens = 1\n").unwrap();
+        assert!(VoiceConfig::from_config_file(&path).unwrap().is_none());
+    }
+
+    /// Tests that the command synthesizer passes text on stdin and reads back the output file.
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_text_to_speech() {
+        let tts = CommandTextToSpeech::new(
+            vec![
+                "sh".into(),
+                "-c".into(),
+                "cat > \"$0\"".into(),
+                "{output}".into(),
+            ],
+            "wav",
+        );
+        let audio = tts.synthesize("hello").await.unwrap();
+        assert_eq!(audio.data, b"hello");
+        assert_eq!(audio.format, "wav");
+    }
+}
@@ -25,9 +25,16 @@ async fn test_agent_with_calculator_tool() {
             api_key: std::env::var("TEST_API_KEY").unwrap_or_else(|_| "test-key".to_string()),
             temperature: 0.7,
             max_tokens: 2048,
+            ..Default::default()
         },
+        profiles: Default::default(),
+        tools: Vec::new(),
         #[cfg(feature = "local")]
         local: None,
+        #[cfg(feature = "local")]
+        local_models: Default::default(),
+        #[cfg(feature = "local")]
+        model_pool: Default::default(),
     };
 
     // Create an agent with the calculator tool.
@@ -60,9 +67,16 @@ async fn test_agent_with_echo_tool() {
             api_key: std::env::var("TEST_API_KEY").unwrap_or_else(|_| "test-key".to_string()),
             temperature: 0.7,
             max_tokens: 2048,
+            ..Default::default()
         },
+        profiles: Default::default(),
+        tools: Vec::new(),
         #[cfg(feature = "local")]
         local: None,
+        #[cfg(feature = "local")]
+        local_models: Default::default(),
+        #[cfg(feature = "local")]
+        model_pool: Default::default(),
     };
 
     let agent = Agent::builder("echo_test_agent")
@@ -121,14 +135,20 @@ fn test_config_serialization() {
             api_key: "test-key".to_string(),
             temperature: 0.7,
             max_tokens: 2048,
+            ..Default::default()
         },
+        profiles: Default::default(),
+        tools: Vec::new(),
         local: Some(LocalConfig {
             huggingface_repo: "test/repo".to_string(),
             model_file: "model.gguf".to_string(),
             context_size: 2048,
             temperature: 0.7,
             max_tokens: 2048,
+            ..LocalConfig::default()
         }),
+        local_models: Default::default(),
+        model_pool: Default::default(),
     };
 
     // Test serialization to a TOML string.
@@ -176,7 +196,10 @@ fn test_config_serialization() {
             api_key: "test-key".to_string(),
             temperature: 0.7,
             max_tokens: 2048,
+            ..Default::default()
         },
+        profiles: Default::default(),
+        tools: Vec::new(),
     };
 
     // Test serialization to a TOML string.
@@ -370,9 +393,16 @@ async fn test_agent_with_react_mode() {
             api_key: std::env::var("TEST_API_KEY").unwrap_or_else(|_| "test-key".to_string()),
             temperature: 0.7,
             max_tokens: 2048,
+            ..Default::default()
         },
+        profiles: Default::default(),
+        tools: Vec::new(),
         #[cfg(feature = "local")]
         local: None,
+        #[cfg(feature = "local")]
+        local_models: Default::default(),
+        #[cfg(feature = "local")]
+        model_pool: Default::default(),
     };
 
     // Create an agent with ReAct mode enabled.
@@ -408,9 +438,16 @@ async fn test_agent_builder_pattern() {
             api_key: std::env::var("TEST_API_KEY").unwrap_or_else(|_| "test-key".to_string()),
             temperature: 0.7,
             max_tokens: 2048,
+            ..Default::default()
         },
+        profiles: Default::default(),
+        tools: Vec::new(),
         #[cfg(feature = "local")]
         local: None,
+        #[cfg(feature = "local")]
+        local_models: Default::default(),
+        #[cfg(feature = "local")]
+        model_pool: Default::default(),
     };
 
     // Build an agent with all options.
@@ -492,9 +529,16 @@ async fn test_server_state_with_llm_client() {
             api_key: "test-key".to_string(),
             temperature: 0.7,
             max_tokens: 2048,
+            ..Default::default()
         },
+        profiles: Default::default(),
+        tools: Vec::new(),
         #[cfg(feature = "local")]
         local: None,
+        #[cfg(feature = "local")]
+        local_models: Default::default(),
+        #[cfg(feature = "local")]
+        model_pool: Default::default(),
     };
 
     // This will fail without proper credentials, but we can test the structure
@@ -524,9 +568,16 @@ async fn test_server_state_with_agent() {
             api_key: "test-key".to_string(),
             temperature: 0.7,
             max_tokens: 2048,
+            ..Default::default()
         },
+        profiles: Default::default(),
+        tools: Vec::new(),
         #[cfg(feature = "local")]
         local: None,
+        #[cfg(feature = "local")]
+        local_models: Default::default(),
+        #[cfg(feature = "local")]
+        model_pool: Default::default(),
     };
 
     // Create a simple agent for testing
@@ -544,6 +595,50 @@ async fn test_server_state_with_agent() {
     assert_eq!(state.model_name, "test-model");
 }
 
+/// Tests that a watched config file is reloaded into the running server state.
+#[tokio::test]
+async fn test_server_state_reloads_watched_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    let write_config = |model: &str, key: &str| {
+        std::fs::write(
+            &path,
+            format!(
+                "[llm]\nmodel_name = \"{}\"\nbase_url = \"http://localhost:1/v1\"\napi_key = \"{}\"\n",
+                model, key
+            ),
+        )
+        .unwrap();
+    };
+    write_config("gpt-4o", "old-key");
+
+    let state = serve::ServerState::from_config(&Config::from_file(&path).unwrap())
+        .await
+        .unwrap();
+    let _watcher = state.watch_config(&path).unwrap();
+    write_config("gpt-4o-mini", "new-key");
+
+    let mut model = String::new();
+    for _ in 0..50 {
+        model = state.current_client().await.unwrap().model_name();
+        if model == "gpt-4o-mini" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(model, "gpt-4o-mini");
+    // The advertised model name is the one the server started with.
+    assert_eq!(state.model_name, "gpt-4o");
+
+    // A broken file leaves the current client in place.
+    std::fs::write(&path, "[llm\n").unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(600)).await;
+    assert_eq!(
+        state.current_client().await.unwrap().model_name(),
+        "gpt-4o-mini"
+    );
+}
+
 /// Tests the conversion of OpenAI messages to ChatMessage format.
 #[test]
 fn test_openai_message_conversion() {
@@ -605,6 +700,7 @@ fn test_openai_message_conversion() {
                 name: msg.name,
                 tool_calls: None,
                 tool_call_id: None,
+                reasoning: None,
             })
         })
         .collect();
@@ -781,6 +877,7 @@ fn test_invalid_role_conversion() {
                 name: msg.name,
                 tool_calls: None,
                 tool_call_id: None,
+                reasoning: None,
             })
         })
         .collect();
@@ -946,3 +1043,35 @@ status_code = 404
     assert_eq!(config.endpoints[1].status_code, 201);
     assert_eq!(config.endpoints[2].status_code, 404);
 }
+
+/// Tests a full tool-calling turn against a scripted provider, without an API key.
+#[tokio::test]
+async fn test_agent_tool_loop_with_mock_provider() {
+    use helios_engine::{MockLLMProvider, Role};
+
+    let mock = MockLLMProvider::new()
+        .with_tool_call("calculator", json!({"expression": "6 * 7"}))
+        .with_response("The answer is 42.");
+
+    let mut agent = Agent::builder("mock_agent")
+        .llm_provider(mock.clone())
+        .tool(Box::new(CalculatorTool))
+        .build()
+        .await
+        .expect("Failed to create agent");
+
+    let answer = agent.chat("What is 6 * 7?").await.unwrap();
+    assert_eq!(answer, "The answer is 42.");
+    assert_eq!(mock.remaining(), 0);
+
+    // The second request carries the calculator's result back to the model.
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 2);
+    let tool_message = requests[1]
+        .messages
+        .iter()
+        .find(|m| m.role == Role::Tool)
+        .expect("tool result should be sent to the model");
+    assert_eq!(tool_message.content, "42");
+    assert!(requests[0].tools.is_some());
+}
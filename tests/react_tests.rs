@@ -15,9 +15,16 @@ fn create_test_config() -> Config {
             api_key: std::env::var("TEST_API_KEY").unwrap_or_else(|_| "test-key".to_string()),
             temperature: 0.7,
             max_tokens: 2048,
+            ..Default::default()
         },
+        profiles: Default::default(),
+        tools: Vec::new(),
         #[cfg(feature = "local")]
         local: None,
+        #[cfg(feature = "local")]
+        local_models: Default::default(),
+        #[cfg(feature = "local")]
+        model_pool: Default::default(),
     }
 }
 